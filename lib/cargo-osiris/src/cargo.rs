@@ -4,7 +4,7 @@
 //! subcommands programmatically, parsing the output into machine-readable
 //! types.
 
-use crate::{md, misc};
+use crate::{md, misc, op};
 use std::collections::{BTreeMap, BTreeSet};
 
 /// Error definitions for all possible errors of the Cargo metadata extraction.
@@ -13,9 +13,10 @@ pub enum Error {
     /// Execution of `cargo` could not commence
     Exec(std::io::Error),
     /// `cargo` exited without success
-    Cargo(std::process::ExitStatus),
-    /// Unicode decoding error
-    Unicode(std::str::Utf8Error),
+    Cargo(op::ToolExit),
+    /// Unicode decoding error, carrying an excerpt of the data around the
+    /// invalid sequence, as well as the offset of the excerpt
+    Unicode(std::str::Utf8Error, usize, Vec<u8>),
     /// JSON decoding error
     Json,
     /// No package specified, nor does the Cargo workspace have a root
@@ -28,6 +29,14 @@ pub enum Error {
     Data,
     /// Osiris Metadata parsing errors
     MdOsiris(md::OsirisError),
+    /// Build succeeded, but produced no artifacts of the requested crate
+    /// type for the given package (or the root package, if `None`)
+    NoArtifacts {
+        crate_type: String,
+        package: Option<String>,
+    },
+    /// Compilation configuration key is not a valid identifier
+    InvalidCfg(String),
 }
 
 /// Cargo arguments shared across different Cargo sub-commands. They select
@@ -35,10 +44,17 @@ pub enum Error {
 /// the package.
 #[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Arguments {
+    /// Base directory that relative manifest and target directory paths
+    /// are resolved against, and that Cargo is run in. Defaults to the
+    /// working directory of the process. A relative base directory is
+    /// itself resolved against the working directory of the process.
+    pub base_dir: Option<std::path::PathBuf>,
     pub default_features: Option<bool>,
     pub features: Vec<String>,
     pub frozen: Option<bool>,
+    pub locked: Option<bool>,
     pub manifest_path: Option<std::path::PathBuf>,
+    pub offline: Option<bool>,
     pub package: Option<String>,
     pub profile: Option<String>,
     pub target_dir: Option<std::path::PathBuf>,
@@ -103,11 +119,55 @@ pub struct BuildArtifact {
     pub path: String,
 }
 
+/// Diagnostic reported by the compiler during a build query.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct BuildMessage {
+    /// Level of the diagnostic as reported by `rustc` (e.g., `warning` or
+    /// `error`).
+    pub level: String,
+    /// Package ID of the origin of this diagnostic.
+    pub package_id: Option<String>,
+    /// Diagnostic rendered as human-readable text, as `rustc` would print
+    /// it.
+    pub rendered: String,
+}
+
 /// Output of a `cargo build` run with only relevant pieces retained.
 #[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Build {
     /// List of artifacts produced by the build.
     pub artifacts: Vec<BuildArtifact>,
+    /// List of compiler diagnostics in the order they were reported.
+    pub messages: Vec<BuildMessage>,
+}
+
+/// Single compilation unit of a Cargo unit graph.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Unit {
+    /// Package ID of the origin of this unit.
+    pub package_id: String,
+    /// Name of the compiled target.
+    pub target_name: String,
+    /// Crate types produced by this unit.
+    pub crate_types: Vec<String>,
+    /// Target platform of this unit, or `None` for the host.
+    pub platform: Option<String>,
+    /// Compile mode of this unit (e.g., `build` or `run-custom-build`).
+    pub mode: String,
+    /// Indices of the units this unit depends on.
+    pub dependencies: Vec<usize>,
+    /// File names of the outputs this unit is intended to produce.
+    pub outputs: Vec<String>,
+}
+
+/// Unit graph of a `cargo build` run, enumerating all compilation units
+/// rather than only the final artifacts.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct UnitGraph {
+    /// List of all units of the build.
+    pub units: Vec<Unit>,
+    /// Indices of the units requested by the build.
+    pub roots: Vec<usize>,
 }
 
 // Intermediate state after cargo-build returned, but the blob was not yet
@@ -129,7 +189,24 @@ pub struct BuildQuery<'ctx> {
     pub crate_type: Option<String>,
     /// Environment variables to set for the build.
     pub envs: Vec<(std::ffi::OsString, std::ffi::OsString)>,
-    /// The target platform to compile for.
+    /// Additional arguments passed verbatim to `rustc`
+    pub rustc_args: Vec<String>,
+    /// The target platform to compile for. If `None`, the target is taken
+    /// from `CARGO_BUILD_TARGET`, if set. Otherwise, Cargo picks its
+    /// configured default target, or the host.
+    pub target: Option<String>,
+}
+
+/// Parameters to a `cargo build --unit-graph` query. To be filled in by the
+/// query requester.
+///
+/// Unit graphs are an unstable feature of Cargo and require a nightly
+/// toolchain. The build is not performed, but only planned.
+#[derive(Clone, Debug)]
+pub struct UnitGraphQuery<'ctx> {
+    /// Package, workspace, and configuration arguments for Cargo.
+    pub cargo_arguments: &'ctx Arguments,
+    /// The target platform to plan for, or `None` for the host.
     pub target: Option<String>,
 }
 
@@ -147,18 +224,40 @@ impl core::fmt::Display for Error {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
         match self {
             Error::Exec(e) => fmt.write_fmt(core::format_args!("Execution of `cargo` could not commence (io-error: {})", e)),
-            Error::Cargo(e) => fmt.write_fmt(core::format_args!("`cargo` failed unexpectedly (exit-code: {})", e)),
-            Error::Unicode(e) => fmt.write_fmt(core::format_args!("`cargo` returned invalid Unicode data (utf8-error: {})", e)),
+            Error::Cargo(e) => fmt.write_fmt(core::format_args!("`cargo` failed unexpectedly: {}", e)),
+            Error::Unicode(e, offset, excerpt) => fmt.write_fmt(core::format_args!("`cargo` returned invalid Unicode data at byte offset {} (utf8-error: {}, data at offset {}: {})", e.valid_up_to(), e, offset, hexdump(excerpt))),
             Error::Json => fmt.write_fmt(core::format_args!("`cargo` returned invalid JSON data")),
             Error::NoPackage => fmt.write_fmt(core::format_args!("No package specified, nor does the Cargo workspace have a root package")),
             Error::UnknownPackage(v) => fmt.write_fmt(core::format_args!("Cannot resolve requested package name: {}", v)),
             Error::AmbiguousPackage(v) => fmt.write_fmt(core::format_args!("Ambiguous package name: {}", v)),
             Error::Data => fmt.write_fmt(core::format_args!("Cannot decode Cargo metadata")),
             Error::MdOsiris(e) => fmt.write_fmt(core::format_args!("Cannot parse Osiris metadata: {}", e)),
+            Error::NoArtifacts { crate_type, package } => fmt.write_fmt(core::format_args!("Build of package `{}` produced no artifacts of crate-type `{}`", package.as_deref().unwrap_or("<root>"), crate_type)),
+            Error::InvalidCfg(v) => fmt.write_fmt(core::format_args!("Invalid compilation configuration key: {}", v)),
         }
     }
 }
 
+// Format bytes as space separated hex pairs.
+fn hexdump(data: &[u8]) -> String {
+    data.iter()
+        .map(|v| format!("{:02x}", v))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Decode Cargo output as UTF-8. On failure, an excerpt of up to 8 bytes on
+// either side of the invalid sequence is retained for diagnostics.
+fn decode_utf8(data: &[u8]) -> Result<&str, Error> {
+    std::str::from_utf8(data).map_err(|e| {
+        let start = e.valid_up_to().saturating_sub(8);
+        let end = data.len().min(
+            e.valid_up_to() + e.error_len().unwrap_or(0) + 8,
+        );
+        Error::Unicode(e, start, data[start..end].to_vec())
+    })
+}
+
 impl core::convert::From<md::OsirisError> for Error {
     fn from(v: md::OsirisError) -> Self {
         Error::MdOsiris(v)
@@ -171,14 +270,117 @@ impl Arguments {
         self.frozen.unwrap_or(false)
     }
 
+    /// Yield whether `Cargo.lock` must remain unchanged. This is implied by
+    /// frozen operation.
+    pub fn locked(&self) -> bool {
+        self.frozen() || self.locked.unwrap_or(false)
+    }
+
+    /// Yield whether network access is prohibited. This is implied by
+    /// frozen operation.
+    pub fn offline(&self) -> bool {
+        self.frozen() || self.offline.unwrap_or(false)
+    }
+
+    /// Yield the Cargo flags selecting the lock-file and network behavior.
+    /// `--frozen` is used if both `--locked` and `--offline` are implied,
+    /// so each behavior is requested exactly once.
+    pub fn lock_flags(&self) -> Vec<&'static str> {
+        match (self.locked(), self.offline()) {
+            (true, true) => vec!["--frozen"],
+            (true, false) => vec!["--locked"],
+            (false, true) => vec!["--offline"],
+            (false, false) => vec![],
+        }
+    }
+
+    // Yield the base directory as absolute path, if any. Cargo is run in
+    // the base directory, so resolved paths must not be relative to it
+    // again.
+    fn base_dir(&self) -> Option<std::path::PathBuf> {
+        self.base_dir.as_deref().map(|v| match v.is_absolute() {
+            true => v.to_path_buf(),
+            false => std::env::current_dir()
+                .map(|cwd| cwd.join(v))
+                .unwrap_or_else(|_| v.to_path_buf()),
+        })
+    }
+
+    // Resolve a path against the base directory, if any. Absolute paths
+    // are retained.
+    fn resolve(&self, path: &std::path::Path) -> std::path::PathBuf {
+        match self.base_dir() {
+            Some(v) => v.join(path),
+            None => path.to_path_buf(),
+        }
+    }
+
     /// Yield the path to the manifest, returning the default if none was
-    /// specified.
-    pub fn manifest_path(&self) -> &std::path::Path {
-        self.manifest_path.as_deref().unwrap_or(
-            std::path::Path::new("./Cargo.toml"),
+    /// specified. Relative paths are resolved against the base directory.
+    pub fn manifest_path(&self) -> std::path::PathBuf {
+        self.resolve(
+            self.manifest_path.as_deref().unwrap_or(
+                std::path::Path::new("./Cargo.toml"),
+            ),
         )
     }
 
+    /// Yield the path to the target directory, if one was specified.
+    /// Relative paths are resolved against the base directory.
+    pub fn target_dir(&self) -> Option<std::path::PathBuf> {
+        self.target_dir.as_deref().map(|v| self.resolve(v))
+    }
+
+    // Run Cargo in the base directory, if any, so its configuration is
+    // discovered relative to the same directory as the paths.
+    fn apply_base_dir(&self, cmd: &mut std::process::Command) {
+        if let Some(v) = self.base_dir() {
+            cmd.current_dir(v);
+        }
+    }
+
+    // Append the package, workspace, and configuration arguments shared by
+    // all build-like invocations of Cargo, and run it in the base
+    // directory. The target platform is left to the caller.
+    fn apply_build(&self, cmd: &mut std::process::Command) {
+        // Append the selected features.
+        for v in &self.features {
+            cmd.arg("--features");
+            cmd.arg(v);
+        }
+
+        // Lock dependencies and prohibit network access, if requested.
+        cmd.args(self.lock_flags());
+
+        // Append path to the manifest.
+        cmd.arg("--manifest-path");
+        cmd.arg(self.manifest_path());
+        self.apply_base_dir(cmd);
+
+        // Append default-feature selector, if set.
+        if self.no_default_features() {
+            cmd.arg("--no-default-features");
+        }
+
+        // Select requested package.
+        if let Some(ref package) = self.package {
+            cmd.arg("--package");
+            cmd.arg(package);
+        }
+
+        // Select requested profile.
+        if let Some(ref profile) = self.profile {
+            cmd.arg("--profile");
+            cmd.arg(profile);
+        }
+
+        // Select requested target directory.
+        if let Some(ref target_dir) = self.target_dir() {
+            cmd.arg("--target-dir");
+            cmd.arg(target_dir);
+        }
+    }
+
     /// Yield whether default features should be disabled with this
     /// configuration.
     pub fn no_default_features(&self) -> bool {
@@ -187,15 +389,30 @@ impl Arguments {
 }
 
 impl MetadataBlob {
+    // Parse the metadata blob from the output of Cargo. Some environments
+    // inject warnings or progress lines into the output, so any lines before
+    // the first line starting a JSON object are skipped. Only a single JSON
+    // value is read, and any trailing data is ignored.
     fn from_str(data: &str) -> Result<Self, Error> {
+        let mut rem = data;
+        while !rem.trim_start().starts_with('{') {
+            rem = rem.split_once('\n').ok_or(Error::Json)?.1;
+        }
+
+        let json = serde_json::Deserializer::from_str(rem)
+            .into_iter::<serde_json::Value>()
+            .next()
+            .ok_or(Error::Json)?
+            .map_err(|_| Error::Json)?;
+
         Ok(Self {
-            json: serde_json::from_str(data).map_err(|_| Error::Json)?,
+            json: json,
         })
     }
 
     fn from_bytes(data: &[u8]) -> Result<Self, Error> {
         Self::from_str(
-            std::str::from_utf8(data).map_err(|v| Error::Unicode(v))?,
+            decode_utf8(data)?,
         )
     }
 
@@ -440,7 +657,7 @@ impl MetadataBlob {
                             }
                         }
                         if let Some(serde_json::Value::String(manifest_str)) = android.get("manifest-file") {
-                            manifest_file = Some(manifest_str);
+                            manifest_file = Some(package_path.as_path().join(manifest_str));
                         }
                         if let Some(serde_json::Value::Array(dirs)) = android.get("resource-dirs") {
                             for dir in dirs.iter() {
@@ -467,9 +684,7 @@ impl MetadataBlob {
                     android_sets.push(MetadataAndroid {
                         java_dirs: java_dirs,
                         kotlin_dirs: kotlin_dirs,
-                        manifest_file: manifest_file.map(
-                            |v| std::path::Path::new(v).into(),
-                        ),
+                        manifest_file: manifest_file,
                         resource_dirs: res_dirs,
                     });
                 }
@@ -504,7 +719,6 @@ impl<'ctx> MetadataQuery<'ctx> {
         cmd.args([
             "metadata",
             "--format-version=1",
-            "--offline",
             "--quiet",
         ]);
 
@@ -514,14 +728,17 @@ impl<'ctx> MetadataQuery<'ctx> {
             cmd.arg(v);
         }
 
-        // Freeze dependencies, if requested.
-        if self.cargo_arguments.frozen() {
-            cmd.arg("--frozen");
+        // Lock dependencies, if requested. Metadata queries never access
+        // the network, regardless of the arguments.
+        cmd.args(self.cargo_arguments.lock_flags());
+        if !self.cargo_arguments.offline() {
+            cmd.arg("--offline");
         }
 
         // Append path to the manifest.
         cmd.arg("--manifest-path");
         cmd.arg(self.cargo_arguments.manifest_path());
+        self.cargo_arguments.apply_base_dir(&mut cmd);
 
         // Append default-feature selector, if set.
         if self.cargo_arguments.no_default_features() {
@@ -531,7 +748,7 @@ impl<'ctx> MetadataQuery<'ctx> {
         // Run cargo and verify it exited successfully.
         let output = cmd.output().map_err(|v| Error::Exec(v))?;
         if !output.status.success() {
-            return Err(Error::Cargo(output.status));
+            return Err(Error::Cargo((&output).into()));
         }
 
         // Decode output as JSON value.
@@ -559,13 +776,53 @@ impl BuildBlob {
 
     fn from_bytes(data: &[u8]) -> Result<Self, Error> {
         Self::from_str(
-            std::str::from_utf8(data).map_err(|v| Error::Unicode(v))?,
+            decode_utf8(data)?,
         )
     }
 
+    // Collect all compiler diagnostics of the blob. Reports without a
+    // level or rendered text are skipped.
+    fn messages(&self) -> Vec<BuildMessage> {
+        let mut messages = Vec::new();
+
+        for report in &self.json {
+            match report.get("reason") {
+                Some(serde_json::Value::String(v)) if v == "compiler-message" => {},
+                _ => continue,
+            }
+
+            let Some(serde_json::Value::Object(message)) = report.get("message") else {
+                continue;
+            };
+            let (
+                Some(serde_json::Value::String(level)),
+                Some(serde_json::Value::String(rendered)),
+            ) = (message.get("level"), message.get("rendered")) else {
+                continue;
+            };
+            let o_package_id = match report.get("package_id") {
+                Some(serde_json::Value::String(v)) => Some(v.clone()),
+                _ => None,
+            };
+
+            messages.push(BuildMessage {
+                level: level.clone(),
+                package_id: o_package_id,
+                rendered: rendered.clone(),
+            });
+        }
+
+        messages
+    }
+
     // Parse all desired fields in the `Build` blob and expose them as a
     // new `Build` object.
-    fn parse(&self) -> Result<Build, Error> {
+    //
+    // If a crate-type is given, artifacts of that crate-type only retain
+    // the files named after the conventions of the given target (or the
+    // host, if `None`). Otherwise, files are selected heuristically.
+    // Diagnostics are not parsed, but collected via `Self::messages()`.
+    fn parse(&self, crate_type: Option<&str>, target: Option<&str>) -> Result<Build, Error> {
         let mut success = false;
         let mut artifacts = Vec::new();
 
@@ -597,9 +854,26 @@ impl BuildBlob {
                 "compiler-artifact" => {
                     // See whether this artifact report is of interest. Any
                     // auxiliary targets like examples and tests are ignored.
+                    // Reports of the requested crate-type are always of
+                    // interest, and we know which files they produce.
                     let mut collect = false;
-                    if let Some(serde_json::Value::Object(target)) = report.get("target") {
-                        if let Some(serde_json::Value::Array(kinds)) = target.get("kind") {
+                    let mut o_expected = None;
+                    if let Some(serde_json::Value::Object(report_target)) = report.get("target") {
+                        let crate_types = match report_target.get("crate_types") {
+                            Some(serde_json::Value::Array(v)) => v.as_slice(),
+                            _ => &[],
+                        };
+                        if let (
+                            Some(v),
+                            Some(serde_json::Value::String(name)),
+                        ) = (crate_type, report_target.get("name")) {
+                            if crate_types.iter().any(|t| t.as_str() == Some(v)) {
+                                collect = true;
+                                o_expected = Some(unit_outputs(name, &[v.to_string()], target, "build"));
+                            }
+                        }
+
+                        if let Some(serde_json::Value::Array(kinds)) = report_target.get("kind") {
                             for kind in kinds.iter() {
                                 if let serde_json::Value::String(kind_str) = kind {
                                     match kind_str.as_str() {
@@ -637,6 +911,10 @@ impl BuildBlob {
                     //      the filename extensions of all supported platforms
                     //      of Cargo. We do our best and blacklist artifacts we
                     //      know we are not interested in.
+                    //
+                    // None of this guessing is needed for reports of the
+                    // requested crate-type, since we can derive the exact
+                    // file names.
                     if let Some(package_id) = o_package_id {
                         let o_executable = match report.get("executable") {
                             Some(serde_json::Value::String(v)) => Some(v),
@@ -646,13 +924,19 @@ impl BuildBlob {
                         if let Some(serde_json::Value::Array(filenames)) = report.get("filenames") {
                             for filename in filenames.iter() {
                                 if let serde_json::Value::String(filename_str) = filename {
-                                    let save = match filename_str.rsplit_once('.') {
-                                        Some((_, "a")) => false,
-                                        Some((_, "d")) => false,
-                                        Some((_, "lib")) => false,
-                                        Some((_, "rlib")) => false,
-                                        Some((_, "rmeta")) => false,
-                                        _ => true,
+                                    let save = match o_expected {
+                                        Some(ref expected) => {
+                                            let file_name = std::path::Path::new(filename_str).file_name();
+                                            expected.iter().any(|v| file_name == Some(std::ffi::OsStr::new(v)))
+                                        },
+                                        None => match filename_str.rsplit_once('.') {
+                                            Some((_, "a")) => false,
+                                            Some((_, "d")) => false,
+                                            Some((_, "lib")) => false,
+                                            Some((_, "rlib")) => false,
+                                            Some((_, "rmeta")) => false,
+                                            _ => true,
+                                        },
                                     };
 
                                     if save {
@@ -685,21 +969,132 @@ impl BuildBlob {
         Ok(
             Build {
                 artifacts: artifacts,
+                messages: Vec::new(),
             }
         )
     }
 }
 
 impl<'ctx> BuildQuery<'ctx> {
+    /// ## Create a build query
+    ///
+    /// Create a query for the given Cargo arguments. No configuration or
+    /// environment is added, and neither target nor crate-type are
+    /// selected. Use the `with_*()` methods to adjust the query.
+    pub fn new(
+        cargo_arguments: &'ctx Arguments,
+    ) -> Self {
+        Self {
+            cargo_arguments: cargo_arguments,
+            cfgs: Vec::new(),
+            crate_type: None,
+            envs: Vec::new(),
+            rustc_args: Vec::new(),
+            target: None,
+        }
+    }
+
+    /// Select the target platform to compile for, or `None` to use the
+    /// default target. See `Self::target` for details.
+    pub fn with_target(mut self, target: Option<&str>) -> Self {
+        self.target = target.map(|v| v.into());
+        self
+    }
+
+    /// Select the crate type to build.
+    pub fn with_crate_type(mut self, crate_type: &str) -> Self {
+        self.crate_type = Some(crate_type.into());
+        self
+    }
+
+    /// Add an environment variable to set for the build.
+    pub fn with_env(
+        mut self,
+        key: impl Into<std::ffi::OsString>,
+        value: impl Into<std::ffi::OsString>,
+    ) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Add a compilation configuration, either as `--cfg KEY` or as
+    /// `--cfg KEY="VALUE"`. The value is escaped as string literal when
+    /// the query is run, so it must not be quoted by the caller.
+    pub fn with_cfg(mut self, key: &str, value: Option<&str>) -> Self {
+        self.cfgs.push((key.into(), value.map(|v| v.into())));
+        self
+    }
+
+    /// Add an argument to pass verbatim to `rustc`.
+    pub fn with_rustc_arg(mut self, arg: &str) -> Self {
+        self.rustc_args.push(arg.into());
+        self
+    }
+
+    // Verify that a build of a selected crate-type produced at least one
+    // artifact. Otherwise, the package likely does not declare that
+    // crate-type, and anything assembled from the build would be broken.
+    fn verify_artifacts(&self, build: Build) -> Result<Build, Error> {
+        match self.crate_type {
+            Some(ref v) if build.artifacts.is_empty() => Err(Error::NoArtifacts {
+                crate_type: v.clone(),
+                package: self.cargo_arguments.package.clone(),
+            }),
+            _ => Ok(build),
+        }
+    }
+
+    // Assemble the `--cfg` arguments for `rustc`. Keys must be ASCII
+    // identifiers, and values are passed as escaped string literals, so
+    // neither can inject other arguments or alter the meaning of the flag.
+    fn cfg_args(&self) -> Result<Vec<String>, Error> {
+        let mut args = Vec::new();
+
+        for (key, o_value) in &self.cfgs {
+            let mut chars = key.chars();
+            let valid = key != "_"
+                && chars.next().is_some_and(|v| v.is_ascii_alphabetic() || v == '_')
+                && chars.all(|v| v.is_ascii_alphanumeric() || v == '_');
+            if !valid {
+                return Err(Error::InvalidCfg(key.clone()));
+            }
+
+            // The debug representation of a string is a valid Rust string
+            // literal, including quotes and escapes.
+            args.push("--cfg".to_string());
+            match o_value {
+                Some(value) => args.push(format!("{}={:?}", key, value)),
+                None => args.push(key.clone()),
+            }
+        }
+
+        Ok(args)
+    }
+
+    // Resolve the target to build for. An explicit target takes precedence
+    // over `CARGO_BUILD_TARGET`, which is looked up via `lookup`.
+    fn target_from_lookup(
+        &self,
+        lookup: impl Fn(&str) -> Option<std::ffi::OsString>,
+    ) -> Option<std::ffi::OsString> {
+        self.target.as_ref()
+            .map(|v| v.into())
+            .or_else(|| lookup("CARGO_BUILD_TARGET"))
+            .filter(|v| !v.is_empty())
+    }
+
     /// Request a full build operation from Cargo. This will invoke
     /// `cargo build` and parse all the cargo output into a `Build` object.
-    pub fn run(&self) -> Result<Build, Error> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<Build, Error> {
+        // Verify compilation configurations before anything is spawned.
+        let cfg_args = self.cfg_args()?;
+
         // Build the cargo-build invocation.
         let mut cmd = std::process::Command::new(cargo_command());
         cmd.args([
             "rustc",
             "--lib",
-            "--message-format=json-render-diagnostics",
+            "--message-format=json",
         ]);
 
         // Append all desired environment variables.
@@ -713,86 +1108,278 @@ impl<'ctx> BuildQuery<'ctx> {
             cmd.arg(v);
         }
 
-        // Append the selected features.
-        for v in &self.cargo_arguments.features {
-            cmd.arg("--features");
-            cmd.arg(v);
-        }
+        // Append package, workspace, and configuration arguments.
+        self.cargo_arguments.apply_build(&mut cmd);
 
-        // Freeze dependencies, if requested.
-        if self.cargo_arguments.frozen() {
-            cmd.arg("--frozen");
+        // Build for requested target.
+        let o_target = self.target_from_lookup(|v| std::env::var_os(v));
+        if let Some(ref target) = o_target {
+            cmd.arg("--target");
+            cmd.arg(target);
         }
 
-        // Append path to the manifest.
-        cmd.arg("--manifest-path");
-        cmd.arg(self.cargo_arguments.manifest_path());
+        // Separate Cargo options from rustc options
+        cmd.arg("--");
 
-        // Append default-feature selector, if set.
-        if self.cargo_arguments.no_default_features() {
-            cmd.arg("--no-default-features");
+        // Append compilation environments as verified above.
+        cmd.args(&cfg_args);
+
+        // Append all remaining `rustc` arguments verbatim.
+        cmd.args(&self.rustc_args);
+
+        // Always forward Cargo status to the parent error stream, so
+        // the user can inspect it.
+        cmd.stderr(std::process::Stdio::inherit());
+
+        // Run cargo via the runner and decode output as JSON stream.
+        let output = runner.run(&mut cmd).map_err(|v| Error::Exec(v))?;
+        let r_blob = BuildBlob::from_bytes(&output.stdout);
+
+        // Cargo does not render diagnostics it reports as JSON, so forward
+        // them to the runner, which treats them like standard error. This
+        // is done before checking the exit status, since diagnostics
+        // explain failed builds.
+        let messages = r_blob.as_ref().map_or_else(|_| Vec::new(), |v| v.messages());
+        for message in &messages {
+            runner.diagnose(&message.rendered);
         }
 
-        // Select requested package.
-        if let Some(ref package) = self.cargo_arguments.package {
-            cmd.arg("--package");
-            cmd.arg(package);
+        // Verify cargo exited successfully. If standard error was captured,
+        // it lacks the diagnostics reported as JSON, so the errors among
+        // them are prepended to its excerpt.
+        if !output.status.success() {
+            let mut exit: op::ToolExit = (&output).into();
+            if let Some(ref mut excerpt) = exit.excerpt {
+                let errors: String = messages.iter()
+                    .filter(|v| v.level == "error")
+                    .map(|v| v.rendered.as_str())
+                    .collect();
+                excerpt.insert_str(0, &errors);
+            }
+            return Err(Error::Cargo(exit));
         }
+        let blob = r_blob?;
+
+        // Parse data into a `Build` object and verify it is not empty.
+        // Artifacts are only filtered by crate-type if the file naming
+        // conventions of the target are known. Custom targets might use
+        // any conventions.
+        let o_target_str = o_target.as_ref().and_then(|v| v.to_str());
+        let known = match o_target {
+            None => true,
+            Some(_) => o_target_str.is_some_and(|v| naming(Some(v)).is_some()),
+        };
+        let mut build = blob.parse(
+            self.crate_type.as_deref().filter(|_| known),
+            o_target_str,
+        )?;
+        build.messages = messages;
+        self.verify_artifacts(build)
+    }
+}
 
-        // Select requested profile.
-        if let Some(ref profile) = self.cargo_arguments.profile {
-            cmd.arg("--profile");
-            cmd.arg(profile);
+// Return the file naming conventions of the given platform as flags for
+// Windows, Apple, and MSVC targets. The platform is `None` for the host.
+// Yields `None` for platforms with unknown conventions.
+fn naming(platform: Option<&str>) -> Option<(bool, bool, bool)> {
+    match platform {
+        None => Some((cfg!(windows), cfg!(target_vendor = "apple"), cfg!(target_env = "msvc"))),
+        Some(v) if v.contains("-windows") => Some((true, false, v.ends_with("-msvc"))),
+        Some(v) if v.contains("-apple-") => Some((false, true, false)),
+        Some(v) if v.contains("-linux") => Some((false, false, false)),
+        Some(_) => None,
+    }
+}
+
+// Return the file names a unit produces for its crate types on the given
+// platform, following the naming conventions of `rustc`. Only build units
+// produce outputs. The platform is `None` for the host. Platforms with
+// unknown conventions are assumed to follow the ELF conventions.
+fn unit_outputs(
+    name: &str,
+    crate_types: &[String],
+    platform: Option<&str>,
+    mode: &str,
+) -> Vec<String> {
+    if mode != "build" {
+        return Vec::new();
+    }
+
+    let (windows, apple, msvc) = naming(platform).unwrap_or((false, false, false));
+    let dylib = |v: &str| match (windows, apple) {
+        (true, _) => format!("{}.dll", v),
+        (false, true) => format!("lib{}.dylib", v),
+        (false, false) => format!("lib{}.so", v),
+    };
+
+    // Library names have dashes replaced, binaries retain them.
+    let lib = name.replace('-', "_");
+
+    crate_types.iter().filter_map(|v| match v.as_str() {
+        "bin" if windows => Some(format!("{}.exe", name)),
+        "bin" => Some(name.to_string()),
+        "lib" | "rlib" => Some(format!("lib{}.rlib", lib)),
+        "staticlib" if msvc => Some(format!("{}.lib", lib)),
+        "staticlib" => Some(format!("lib{}.a", lib)),
+        "cdylib" | "dylib" => Some(dylib(&lib)),
+        // Procedural macros are always built for the host.
+        "proc-macro" => Some(match (cfg!(windows), cfg!(target_vendor = "apple")) {
+            (true, _) => format!("{}.dll", lib),
+            (false, true) => format!("lib{}.dylib", lib),
+            (false, false) => format!("lib{}.so", lib),
+        }),
+        _ => None,
+    }).collect()
+}
+
+impl UnitGraph {
+    fn from_str(data: &str) -> Result<Self, Error> {
+        let json: serde_json::Value = serde_json::from_str(data).map_err(|_| Error::Json)?;
+        let index = |v: &serde_json::Value| v.as_u64().and_then(|v| usize::try_from(v).ok());
+        let strings = |v: Option<&serde_json::Value>| -> Result<Vec<String>, Error> {
+            v.and_then(|v| v.as_array())
+                .ok_or(Error::Data)?
+                .iter()
+                .map(|v| v.as_str().map(|v| v.to_string()).ok_or(Error::Data))
+                .collect()
+        };
+
+        // Only version 1 of the unit graph format is known.
+        if json.get("version").and_then(|v| v.as_u64()) != Some(1) {
+            return Err(Error::Data);
         }
 
-        // Build for requested target.
-        if let Some(ref target) = self.target {
-            cmd.arg("--target");
-            cmd.arg(target);
+        let mut units = Vec::new();
+        for unit in json.get("units").and_then(|v| v.as_array()).ok_or(Error::Data)? {
+            let package_id = unit.get("pkg_id").and_then(|v| v.as_str()).ok_or(Error::Data)?;
+            let target = unit.get("target").ok_or(Error::Data)?;
+            let target_name = target.get("name").and_then(|v| v.as_str()).ok_or(Error::Data)?;
+            let crate_types = strings(target.get("crate_types"))?;
+            let platform = unit.get("platform").and_then(|v| v.as_str());
+            let mode = unit.get("mode").and_then(|v| v.as_str()).ok_or(Error::Data)?;
+
+            let mut dependencies = Vec::new();
+            if let Some(serde_json::Value::Array(deps)) = unit.get("dependencies") {
+                for dep in deps.iter() {
+                    dependencies.push(dep.get("index").and_then(index).ok_or(Error::Data)?);
+                }
+            }
+
+            units.push(Unit {
+                package_id: package_id.into(),
+                target_name: target_name.into(),
+                outputs: unit_outputs(target_name, &crate_types, platform, mode),
+                crate_types: crate_types,
+                platform: platform.map(|v| v.into()),
+                mode: mode.into(),
+                dependencies: dependencies,
+            });
         }
 
-        // Select requested target directory.
-        if let Some(ref target_dir) = self.cargo_arguments.target_dir {
-            cmd.arg("--target-dir");
-            cmd.arg(target_dir);
+        let mut roots = Vec::new();
+        if let Some(serde_json::Value::Array(v)) = json.get("roots") {
+            for root in v.iter() {
+                roots.push(index(root).ok_or(Error::Data)?);
+            }
         }
 
-        // Separate Cargo options from rustc options
-        cmd.arg("--");
+        // Reject dangling references, so users can index without checks.
+        if roots.iter().chain(units.iter().flat_map(|v| v.dependencies.iter())).any(|v| *v >= units.len()) {
+            return Err(Error::Data);
+        }
 
-        // Append compilation environments. This must either be `--cfg KEY` or
-        // `--cfg KEY="VALUE"`. The key is limited to valid rust identifiers
-        // and the value must be a valid rust (raw) string literal. We do not
-        // verify this, and we leave it to the caller to decide how to provide
-        // the data.
-        //
-        // XXX: Ideally, we would verify the input enough to ensure it cannot
-        //      produce bogus unintended results. This is not critical, but
-        //      would certainly be nice.
-        for (key, o_value) in &self.cfgs {
-            cmd.arg("--cfg");
-            if let Some(value) = o_value {
-                cmd.arg(format!("{}={}", key, value));
-            } else {
-                cmd.arg(key);
-            }
+        Ok(Self {
+            units: units,
+            roots: roots,
+        })
+    }
+
+    /// ## Outputs by package
+    ///
+    /// Return the intended output file names of all units, grouped by the
+    /// package ID of their origin.
+    pub fn outputs(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut map = BTreeMap::<&str, Vec<&str>>::new();
+        for unit in &self.units {
+            map.entry(&unit.package_id)
+                .or_default()
+                .extend(unit.outputs.iter().map(|v| v.as_str()));
         }
+        map
+    }
+}
 
-        // Always forward diagnostics to the parent error stream, so
-        // the user can inspect them.
-        cmd.stderr(std::process::Stdio::inherit());
+impl<'ctx> UnitGraphQuery<'ctx> {
+    /// ## Create a unit-graph query
+    ///
+    /// Create a query for the given Cargo arguments, planning for the
+    /// host.
+    pub fn new(
+        cargo_arguments: &'ctx Arguments,
+    ) -> Self {
+        Self {
+            cargo_arguments: cargo_arguments,
+            target: None,
+        }
+    }
 
-        // Run cargo and verify it exited successfully.
-        let output = cmd.output().map_err(|v| Error::Exec(v))?;
+    /// Select the target platform to plan for, or `None` for the host.
+    pub fn with_target(mut self, target: Option<&str>) -> Self {
+        self.target = target.map(|v| v.into());
+        self
+    }
+
+    /// Request the unit graph of a build from Cargo. This will invoke
+    /// `cargo build --unit-graph` and parse its output into a `UnitGraph`
+    /// object.
+    ///
+    /// Since this requires a nightly toolchain, `None` is returned if Cargo
+    /// fails, and callers should fall back to the artifacts reported by a
+    /// `BuildQuery`. Any genuine failure will surface there as well. Standard
+    /// error of Cargo is handled by the runner like for any other tool, so
+    /// the reason of a failure is not hidden.
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<Option<UnitGraph>, Error> {
+        let mut cmd = std::process::Command::new(cargo_command());
+        cmd.args([
+            "build",
+            "--unit-graph",
+            "-Z",
+            "unstable-options",
+        ]);
+
+        // Append package, workspace, and configuration arguments.
+        self.cargo_arguments.apply_build(&mut cmd);
+
+        // Plan for requested target.
+        if let Some(ref target) = self.target {
+            cmd.arg("--target");
+            cmd.arg(target);
+        }
+
+        // Run cargo via the runner. Stable toolchains reject the unstable
+        // flags, which is an expected outcome and not treated as error.
+        let output = runner.run(&mut cmd).map_err(|v| Error::Exec(v))?;
         if !output.status.success() {
-            return Err(Error::Cargo(output.status));
+            return Ok(None);
         }
 
-        // Decode output as JSON stream.
-        let blob = BuildBlob::from_bytes(&output.stdout)?;
+        let data = decode_utf8(&output.stdout)?;
+        UnitGraph::from_str(data).map(Some)
+    }
+}
 
-        // Parse data into a `Build` object.
-        blob.parse()
+// Create metadata of the package `foobar` with the given Osiris metadata in
+// its JSON representation, building into `/target`.
+#[cfg(test)]
+impl Metadata {
+    pub(crate) fn from_osiris_json(json: &serde_json::Value) -> Result<Self, md::OsirisError> {
+        Ok(Self {
+            android_sets: Vec::new(),
+            osiris: Some(md::osiris_from_json(json)?),
+            package_id: "foobar (...)".into(),
+            package_name: "foobar".into(),
+            target_directory: "/target".into(),
+        })
     }
 }
 
@@ -938,6 +1525,396 @@ mod tests {
         );
     }
 
+    // Verify that invalid UTF-8 is reported with its offset and the bytes
+    // surrounding it.
+    #[test]
+    fn unicode_error() {
+        let mut data = b"{\"packages\": [".to_vec();
+        data.extend_from_slice(&[0xff, 0xfe]);
+        data.extend_from_slice(b"]}");
+
+        let e = MetadataBlob::from_bytes(&data).unwrap_err();
+        assert!(matches!(e, Error::Unicode(_, 6, _)));
+        assert_eq!(
+            e.to_string(),
+            "`cargo` returned invalid Unicode data at byte offset 14 (utf8-error: invalid utf-8 sequence of 1 bytes from index 14, data at offset 6: 61 67 65 73 22 3a 20 5b ff fe 5d 7d)",
+        );
+    }
+
+    // Verify that relative manifest and target paths are resolved against
+    // the base directory, while absolute paths are retained.
+    #[test]
+    fn base_dir() {
+        let mut args = Arguments {
+            target_dir: Some("target".into()),
+            ..Default::default()
+        };
+        assert_eq!(args.manifest_path(), std::path::Path::new("./Cargo.toml"));
+        assert_eq!(args.target_dir().unwrap(), std::path::Path::new("target"));
+
+        args.base_dir = Some("/srv/build".into());
+        assert_eq!(args.manifest_path(), std::path::Path::new("/srv/build/Cargo.toml"));
+        assert_eq!(args.target_dir().unwrap(), std::path::Path::new("/srv/build/target"));
+
+        args.manifest_path = Some("app/Cargo.toml".into());
+        args.target_dir = Some("/tmp/target".into());
+        assert_eq!(args.manifest_path(), std::path::Path::new("/srv/build/app/Cargo.toml"));
+        assert_eq!(args.target_dir().unwrap(), std::path::Path::new("/tmp/target"));
+
+        // Relative base directories are resolved only once, since Cargo
+        // is run in the base directory.
+        args.base_dir = Some("srv".into());
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(args.manifest_path(), cwd.join("srv/app/Cargo.toml"));
+        assert_eq!(args.base_dir(), Some(cwd.join("srv")));
+    }
+
+    // Verify that lock-file and network flags imply each other as in Cargo,
+    // and are passed without duplicates.
+    #[test]
+    fn lock_flags() {
+        let args = |frozen, locked, offline| Arguments {
+            frozen: frozen,
+            locked: locked,
+            offline: offline,
+            ..Default::default()
+        };
+
+        let v = args(None, None, None);
+        assert!(!v.frozen() && !v.locked() && !v.offline());
+        assert!(v.lock_flags().is_empty());
+
+        let v = args(None, Some(true), None);
+        assert!(!v.frozen() && v.locked() && !v.offline());
+        assert_eq!(v.lock_flags(), ["--locked"]);
+
+        let v = args(None, None, Some(true));
+        assert!(!v.frozen() && !v.locked() && v.offline());
+        assert_eq!(v.lock_flags(), ["--offline"]);
+
+        let v = args(None, Some(true), Some(true));
+        assert!(!v.frozen() && v.locked() && v.offline());
+        assert_eq!(v.lock_flags(), ["--frozen"]);
+
+        // Frozen operation implies the others, even if disabled explicitly.
+        for (locked, offline) in [(None, None), (Some(false), Some(false)), (Some(true), None)] {
+            let v = args(Some(true), locked, offline);
+            assert!(v.frozen() && v.locked() && v.offline());
+            assert_eq!(v.lock_flags(), ["--frozen"]);
+        }
+
+        let v = args(Some(false), Some(false), Some(false));
+        assert!(v.lock_flags().is_empty());
+    }
+
+    // Verify that `CARGO_BUILD_TARGET` is used as fallback if no target is
+    // given explicitly, but never overrides an explicit target.
+    #[test]
+    fn build_target_env() {
+        let resolve = |target: Option<&str>, env: Option<&str>| BuildQuery {
+            cargo_arguments: &Default::default(),
+            cfgs: Vec::new(),
+            crate_type: None,
+            envs: Vec::new(),
+            rustc_args: Vec::new(),
+            target: target.map(|v| v.into()),
+        }.target_from_lookup(|v| {
+            assert_eq!(v, "CARGO_BUILD_TARGET");
+            env.map(|v| v.into())
+        });
+
+        assert_eq!(resolve(None, None), None);
+        assert_eq!(resolve(None, Some("")), None);
+        assert_eq!(resolve(None, Some("aarch64-linux-android")).unwrap(), "aarch64-linux-android");
+        assert_eq!(resolve(Some("x86_64-linux-android"), None).unwrap(), "x86_64-linux-android");
+        assert_eq!(resolve(Some("x86_64-linux-android"), Some("aarch64-linux-android")).unwrap(), "x86_64-linux-android");
+    }
+
+    // Verify that a build without artifacts of interest is rejected if a
+    // crate-type was requested, naming the crate-type and package.
+    #[test]
+    fn build_no_artifacts() {
+        let blob = BuildBlob::from_str(r#"
+            {"reason": "compiler-artifact", "package_id": "foo 0.1.0 (path+file:///foo)", "target": {"kind": ["lib"]}, "filenames": ["/target/libfoo.rlib", "/target/libfoo.rmeta"], "executable": null}
+            {"reason": "build-finished", "success": true}
+        "#).unwrap();
+        let build = blob.parse(None, None).unwrap();
+        assert!(build.artifacts.is_empty());
+
+        let arguments = Arguments {
+            package: Some("foo".into()),
+            ..Default::default()
+        };
+        let query = BuildQuery::new(&arguments).with_crate_type("cdylib");
+        let e = query.verify_artifacts(build.clone()).unwrap_err();
+        assert!(matches!(
+            e,
+            Error::NoArtifacts { ref crate_type, package: Some(ref package) }
+                if crate_type == "cdylib" && package == "foo",
+        ));
+        assert_eq!(e.to_string(), "Build of package `foo` produced no artifacts of crate-type `cdylib`");
+
+        // Without a selected crate-type, empty builds are fine.
+        let query = BuildQuery::new(&arguments);
+        assert!(query.verify_artifacts(build).unwrap().artifacts.is_empty());
+
+        let blob = BuildBlob::from_str(r#"
+            {"reason": "compiler-artifact", "package_id": "foo 0.1.0 (path+file:///foo)", "target": {"kind": ["cdylib"]}, "filenames": ["/target/libfoo.so"], "executable": null}
+            {"reason": "build-finished", "success": true}
+        "#).unwrap();
+        let query = BuildQuery::new(&arguments).with_crate_type("cdylib");
+        let build = query.verify_artifacts(blob.parse(None, None).unwrap()).unwrap();
+        assert_eq!(build.artifacts.len(), 1);
+    }
+
+    // Verify that reports of the requested crate-type only retain the files
+    // named after the conventions of the target, while other reports are
+    // still selected heuristically.
+    #[test]
+    fn build_crate_type() {
+        let blob = BuildBlob::from_str(r#"
+            {"reason": "compiler-artifact", "package_id": "bar (...)", "target": {"kind": ["dylib"], "crate_types": ["dylib"], "name": "bar"}, "filenames": ["/target/bar.dll", "/target/bar.pdb"], "executable": null}
+            {"reason": "compiler-artifact", "package_id": "foo (...)", "target": {"kind": ["cdylib", "rlib"], "crate_types": ["cdylib", "rlib"], "name": "foo"}, "filenames": ["/target/foo.dll", "/target/foo.dll.exp", "/target/foo.dll.lib", "/target/foo.pdb", "/target/libfoo.rlib"], "executable": null}
+            {"reason": "build-finished", "success": true}
+        "#).unwrap();
+        let paths = |build: Build| -> Vec<String> {
+            build.artifacts.into_iter().map(|v| v.path).collect()
+        };
+
+        assert_eq!(
+            paths(blob.parse(Some("cdylib"), Some("x86_64-pc-windows-msvc")).unwrap()),
+            vec!["/target/bar.dll", "/target/bar.pdb", "/target/foo.dll"],
+        );
+        assert_eq!(
+            paths(blob.parse(Some("cdylib"), Some("x86_64-unknown-linux-gnu")).unwrap()),
+            vec!["/target/bar.dll", "/target/bar.pdb"],
+        );
+        assert_eq!(
+            paths(blob.parse(None, None).unwrap()),
+            vec!["/target/bar.dll", "/target/bar.pdb", "/target/foo.dll", "/target/foo.dll.exp", "/target/foo.pdb"],
+        );
+    }
+
+    // Verify that compiler diagnostics are collected in order with their
+    // level and rendered text, and incomplete reports are skipped.
+    #[test]
+    fn build_messages() {
+        let blob = BuildBlob::from_str(r#"
+            {"reason": "compiler-message", "package_id": "foo 0.1.0 (path+file:///foo)", "message": {"level": "warning", "message": "unused variable: `x`", "rendered": "warning: unused variable: `x`\n"}}
+            {"reason": "compiler-message", "message": {"level": "error", "rendered": "error: foobar\n"}}
+            {"reason": "compiler-message", "package_id": "foo 0.1.0 (path+file:///foo)", "message": {"level": "warning"}}
+            {"reason": "compiler-message"}
+            {"reason": "build-finished", "success": true}
+        "#).unwrap();
+        assert_eq!(
+            blob.messages(),
+            vec![
+                BuildMessage {
+                    level: "warning".into(),
+                    package_id: Some("foo 0.1.0 (path+file:///foo)".into()),
+                    rendered: "warning: unused variable: `x`\n".into(),
+                },
+                BuildMessage {
+                    level: "error".into(),
+                    package_id: None,
+                    rendered: "error: foobar\n".into(),
+                },
+            ],
+        );
+    }
+
+    // Verify that diagnostics of a build are forwarded to the runner, even
+    // if the build fails, and are retained in the build result. Errors are
+    // also reported with captured standard error of failed builds.
+    #[test]
+    fn build_diagnose() {
+        let stdout = |_: &[String]| String::from(concat!(
+            r#"{"reason": "compiler-message", "message": {"level": "warning", "rendered": "warning: foo\n"}}"#, "\n",
+            r#"{"reason": "build-finished", "success": true}"#, "\n",
+        ));
+        let stdout_error = |_: &[String]| String::from(concat!(
+            r#"{"reason": "compiler-message", "message": {"level": "warning", "rendered": "warning: foo\n"}}"#, "\n",
+            r#"{"reason": "compiler-message", "message": {"level": "error", "rendered": "error: bar\n"}}"#, "\n",
+            r#"{"reason": "build-finished", "success": false}"#, "\n",
+        ));
+
+        let arguments = Arguments::default();
+        let runner = op::FakeRunner::new(stdout);
+        let build = BuildQuery::new(&arguments).run(&runner).unwrap();
+        assert_eq!(build.messages.len(), 1);
+        assert_eq!(*runner.diagnostics.borrow(), ["warning: foo\n"]);
+
+        let runner = op::FakeRunner::new(stdout).with_fail("cargo");
+        assert!(matches!(
+            BuildQuery::new(&arguments).run(&runner),
+            Err(Error::Cargo(_)),
+        ));
+        assert_eq!(*runner.diagnostics.borrow(), ["warning: foo\n"]);
+
+        let runner = op::FakeRunner::new(stdout_error).with_fail("cargo");
+        assert!(matches!(
+            BuildQuery::new(&arguments).run(&runner),
+            Err(Error::Cargo(ref v)) if v.excerpt.as_deref() == Some("error: bar\ncargo failed"),
+        ));
+        assert_eq!(*runner.diagnostics.borrow(), ["warning: foo\n", "error: bar\n"]);
+    }
+
+    // Verify that cfg keys are validated, and values are passed as escaped
+    // string literals.
+    #[test]
+    fn build_cfg_args() {
+        let arguments = Arguments::default();
+
+        let query = BuildQuery::new(&arguments)
+            .with_cfg("foo", None)
+            .with_cfg("_bar2", Some("v"))
+            .with_cfg("baz", Some("a \"b\" \\ c\n"));
+        assert_eq!(
+            query.cfg_args().unwrap(),
+            vec![
+                "--cfg", "foo",
+                "--cfg", "_bar2=\"v\"",
+                "--cfg", "baz=\"a \\\"b\\\" \\\\ c\\n\"",
+            ],
+        );
+
+        for key in ["", "_", "1foo", "foo=bar", "foo bar", "foo::bar", "--cfg"] {
+            let query = BuildQuery::new(&arguments).with_cfg(key, Some("v"));
+            assert!(matches!(query.cfg_args(), Err(Error::InvalidCfg(v)) if v == key));
+        }
+    }
+
+    // Verify that unit graphs are parsed with their dependencies, and
+    // intended outputs are derived per platform.
+    #[test]
+    fn unit_graph() {
+        let graph = UnitGraph::from_str(
+            r#"{
+                "version": 1,
+                "units": [
+                    {
+                        "pkg_id": "foo-bar 0.1.0 (path+file:///foo)",
+                        "target": { "kind": ["lib", "cdylib"], "crate_types": ["lib", "cdylib"], "name": "foo-bar" },
+                        "platform": "aarch64-linux-android",
+                        "mode": "build",
+                        "dependencies": [ { "index": 1, "extern_crate_name": "baz" } ]
+                    },
+                    {
+                        "pkg_id": "baz 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                        "target": { "kind": ["lib"], "crate_types": ["lib"], "name": "baz" },
+                        "platform": "aarch64-linux-android",
+                        "mode": "build",
+                        "dependencies": []
+                    },
+                    {
+                        "pkg_id": "foo-bar 0.1.0 (path+file:///foo)",
+                        "target": { "kind": ["bin"], "crate_types": ["bin"], "name": "foo-bar" },
+                        "platform": "x86_64-pc-windows-msvc",
+                        "mode": "build",
+                        "dependencies": []
+                    },
+                    {
+                        "pkg_id": "baz 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                        "target": { "kind": ["lib"], "crate_types": ["lib"], "name": "baz" },
+                        "platform": null,
+                        "mode": "check",
+                        "dependencies": []
+                    }
+                ],
+                "roots": [0]
+            }"#,
+        ).unwrap();
+
+        assert_eq!(graph.units.len(), 4);
+        assert_eq!(graph.roots, [0]);
+        assert_eq!(graph.units[0].dependencies, [1]);
+        assert_eq!(graph.units[0].platform.as_deref(), Some("aarch64-linux-android"));
+        assert_eq!(graph.units[0].outputs, ["libfoo_bar.rlib", "libfoo_bar.so"]);
+        assert_eq!(graph.units[2].outputs, ["foo-bar.exe"]);
+        assert!(graph.units[3].outputs.is_empty());
+        assert_eq!(
+            graph.outputs(),
+            BTreeMap::from([
+                ("baz 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)", vec!["libbaz.rlib"]),
+                ("foo-bar 0.1.0 (path+file:///foo)", vec!["libfoo_bar.rlib", "libfoo_bar.so", "foo-bar.exe"]),
+            ]),
+        );
+
+        // Unknown versions and dangling references are rejected.
+        assert!(matches!(UnitGraph::from_str(r#"{"version": 2, "units": []}"#), Err(Error::Data)));
+        assert!(matches!(UnitGraph::from_str(r#"{"version": 1, "units": [], "roots": [0]}"#), Err(Error::Data)));
+    }
+
+    // Verify that unit-graph queries pass the same arguments as builds and
+    // go through the runner, and that failures of Cargo yield no graph.
+    #[test]
+    fn unit_graph_query() {
+        let stdout = |_: &[String]| String::from(r#"{"version": 1, "units": [], "roots": []}"#);
+        let arguments = Arguments {
+            manifest_path: Some("/foo/Cargo.toml".into()),
+            package: Some("foobar".into()),
+            target_dir: Some("/target".into()),
+            ..Default::default()
+        };
+
+        let runner = op::FakeRunner::new(stdout);
+        let graph = UnitGraphQuery::new(&arguments)
+            .with_target(Some("aarch64-linux-android"))
+            .run(&runner)
+            .unwrap()
+            .unwrap();
+        assert!(graph.units.is_empty());
+        assert_eq!(
+            runner.calls.take(),
+            [[
+                "cargo",
+                "build", "--unit-graph", "-Z", "unstable-options",
+                "--manifest-path", "/foo/Cargo.toml",
+                "--package", "foobar",
+                "--target-dir", "/target",
+                "--target", "aarch64-linux-android",
+            ]],
+        );
+
+        let runner = op::FakeRunner::new(stdout).with_fail("cargo");
+        assert!(UnitGraphQuery::new(&arguments).run(&runner).unwrap().is_none());
+    }
+
+    // Verify that the builder yields the same query as the struct literal.
+    #[test]
+    fn build_query_builder() {
+        let arguments = Arguments::default();
+        let literal = BuildQuery {
+            cargo_arguments: &arguments,
+            cfgs: vec![("foo".into(), None), ("bar".into(), Some("v".into()))],
+            crate_type: Some("cdylib".into()),
+            envs: vec![("KEY".into(), "value".into())],
+            rustc_args: vec!["-Clink-arg=-lc++_shared".into()],
+            target: Some("aarch64-linux-android".into()),
+        };
+        let built = BuildQuery::new(&arguments)
+            .with_target(Some("aarch64-linux-android"))
+            .with_crate_type("cdylib")
+            .with_env("KEY", "value")
+            .with_cfg("foo", None)
+            .with_cfg("bar", Some("v"))
+            .with_rustc_arg("-Clink-arg=-lc++_shared");
+
+        assert_eq!(built, literal);
+
+        assert_eq!(
+            BuildQuery::new(&arguments).with_target(None),
+            BuildQuery {
+                cargo_arguments: &arguments,
+                cfgs: Vec::new(),
+                crate_type: None,
+                envs: Vec::new(),
+                rustc_args: Vec::new(),
+                target: None,
+            },
+        );
+    }
+
     // Create `Metadata` from a set of predefined JSON blobs and verify it is
     // parsed as expected.
     #[test]
@@ -958,6 +1935,22 @@ mod tests {
             ),
         );
 
+        // Non-JSON lines are no valid metadata either.
+        assert!(
+            matches!(
+                MetadataBlob::from_str("warning: foo\n").unwrap_err(),
+                Error::Json,
+            ),
+        );
+
+        // Leading lines and trailing data around the blob are ignored.
+        assert_eq!(
+            MetadataBlob::from_str(
+                "warning: {noise}\n  Blocking waiting for file lock\n\n{\"version\": 1}\nwarning: trailing\n{}",
+            ).unwrap().json,
+            serde_json::json!({"version": 1}),
+        );
+
         // Empty sets lack mandatory metadata fields and must be rejected.
         assert!(
             matches!(
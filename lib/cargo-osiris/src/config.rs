@@ -26,8 +26,46 @@ pub enum Error {
     DuplicateArchive(String),
     /// Duplicate platform IDs
     DuplicatePlatform(String),
+    /// Specified key requires a version number
+    InvalidVersion(&'static str, String),
+    /// Additional `Info.plist` key is invalid or reserved
+    InvalidPlistKey(String),
+    /// Native library of the Android main activity is not a valid name
+    InvalidLibrary(String),
+    /// Class of the Android main activity is not a valid name
+    InvalidActivity(String),
+    /// Entitlement key is invalid or reserved
+    InvalidEntitlementKey(String),
+    /// Target configuration for an ABI that is not built
+    InvalidTarget(String),
 }
 
+// Entitlement key of the macOS app sandbox, which is enabled by default.
+const ENTITLEMENT_SANDBOX: &str = "com.apple.security.app-sandbox";
+
+// Entitlement keys of macOS bundles that are generated from the
+// configuration and cannot be supplied explicitly.
+const ENTITLEMENT_RESERVED_KEYS: &[&str] = &[
+    "com.apple.application-identifier",
+    "com.apple.developer.team-identifier",
+];
+
+// Keys of the macOS `Info.plist` that are generated from the configuration
+// and cannot be supplied as additional entries.
+const PLIST_RESERVED_KEYS: &[&str] = &[
+    "CFBundleDisplayName",
+    "CFBundleExecutable",
+    "CFBundleIdentifier",
+    "CFBundleName",
+    "CFBundlePackageType",
+    "CFBundleShortVersionString",
+    "CFBundleSignature",
+    "CFBundleSupportedPlatforms",
+    "CFBundleVersion",
+    "LSApplicationCategoryType",
+    "LSMinimumSystemVersion",
+];
+
 /// Metadata on a particular icon instance.
 pub struct ConfigIcon {
     pub path: String,
@@ -39,6 +77,8 @@ pub struct ConfigIcon {
 pub struct ConfigArchiveMacosPkg {
     pub app_id: Option<String>,
     pub codesign_identity: Option<String>,
+    pub entitlements: BTreeMap<String, md::OsirisPlistValue>,
+    pub notarize: bool,
     pub pkgsign_identity: Option<String>,
     pub provision_file: Option<std::path::PathBuf>,
     pub team_id: Option<String>,
@@ -70,9 +110,61 @@ pub struct ConfigPlatformAndroid {
     pub target_sdk: u32,
 
     pub abis: Vec<String>,
+    /// File extensions of APK entries to store uncompressed.
+    pub uncompressed_extensions: Vec<String>,
+
+    /// Merge the generated launcher activity into a user-provided manifest,
+    /// rather than using it verbatim.
+    pub manifest_merge: bool,
+
+    /// Native library loaded by a generated main activity, or `None` if no
+    /// main activity is generated. The library must export the entry-point
+    /// described by `platform::android::ENV_MAIN_SYMBOL`.
+    pub main_activity: Option<String>,
+    /// Class of the launcher activity, either fully qualified or relative to
+    /// the namespace if prefixed with a dot.
+    pub main_activity_class: String,
+
+    /// Permissions requested by the generated manifest.
+    pub permissions: Vec<String>,
+
+    /// Keystore to sign release builds with, or `None` if only debug
+    /// builds can be signed.
+    pub keystore: Option<ConfigPlatformAndroidKeystore>,
 
     pub version_code: u32,
     pub version_name: String,
+
+    /// Per-ABI build configuration, indexed by ABI. ABIs without entry use
+    /// the default configuration.
+    pub targets: BTreeMap<String, ConfigPlatformAndroidTarget>,
+}
+
+/// Release keystore of the Android platform. Pass phrases are not part of
+/// the configuration, but read from the named environment variables when
+/// signing.
+pub struct ConfigPlatformAndroidKeystore {
+    pub path: std::path::PathBuf,
+    pub key_alias: Option<String>,
+    /// Environment variable carrying the pass phrase of the keystore.
+    pub phrase_env: String,
+    /// Environment variable carrying the pass phrase of the key, or `None`
+    /// if it matches the pass phrase of the keystore.
+    pub key_phrase_env: Option<String>,
+}
+
+/// Android specific build configuration of a single ABI.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConfigPlatformAndroidTarget {
+    /// Additional arguments passed to `rustc`.
+    pub rustc_args: Vec<String>,
+    /// Additional environment variables of the Cargo build. These take
+    /// precedence over the computed linker configuration.
+    pub envs: BTreeMap<String, String>,
+    /// Additional `--cfg` flags as key and optional value.
+    pub cfgs: Vec<(String, Option<String>)>,
+    /// Bundle `libc++_shared.so` of the NDK with the native libraries.
+    pub libcxx_shared: bool,
 }
 
 /// MacOS specific configuration for a platform integration.
@@ -86,12 +178,26 @@ pub struct ConfigPlatformMacos {
     pub version_name: String,
 
     pub category: String,
+
+    pub plist_extra: BTreeMap<String, md::OsirisPlistValue>,
+}
+
+/// iOS specific configuration for a platform integration.
+pub struct ConfigPlatformIos {
+    pub bundle_id: String,
+
+    pub abis: Vec<String>,
+    pub min_os: String,
+
+    pub version_code: u32,
+    pub version_name: String,
 }
 
 /// Union for platform specific configuration that is part of a platform
 /// integration configuration.
 pub enum ConfigPlatformConfiguration {
     Android(ConfigPlatformAndroid),
+    Ios(ConfigPlatformIos),
     Macos(ConfigPlatformMacos),
 }
 
@@ -126,6 +232,8 @@ pub struct Config {
 
     /// Icon information
     pub icons: Vec<ConfigIcon>,
+    /// Whether to generate placeholder icons if no icon is provided
+    pub placeholder_icon: bool,
 
     /// Archive configurations
     pub archives: BTreeMap<String, ConfigArchive>,
@@ -143,6 +251,12 @@ impl core::fmt::Display for Error {
             Self::MissingKey(key) => fmt.write_fmt(core::format_args!("Missing mandatory configuration for: {}", key)),
             Self::DuplicateArchive(id) => fmt.write_fmt(core::format_args!("Duplicate archive configuration for ID: {}", id)),
             Self::DuplicatePlatform(id) => fmt.write_fmt(core::format_args!("Duplicate platform configuration for ID: {}", id)),
+            Self::InvalidVersion(key, v) => fmt.write_fmt(core::format_args!("Invalid version number for {}: {}", key, v)),
+            Self::InvalidPlistKey(key) => fmt.write_fmt(core::format_args!("Invalid or reserved Info.plist key: {}", key)),
+            Self::InvalidLibrary(name) => fmt.write_fmt(core::format_args!("Invalid native library name for the Android main activity: {}", name)),
+            Self::InvalidActivity(name) => fmt.write_fmt(core::format_args!("Invalid class name for the Android main activity: {}", name)),
+            Self::InvalidEntitlementKey(key) => fmt.write_fmt(core::format_args!("Invalid or reserved entitlement key: {}", key)),
+            Self::InvalidTarget(abi) => fmt.write_fmt(core::format_args!("Target configuration for unselected ABI: {}", abi)),
         }
     }
 }
@@ -159,6 +273,10 @@ impl Config {
                     ConfigArchiveMacosPkg {
                         app_id: None,
                         codesign_identity: None,
+                        entitlements: BTreeMap::from([
+                            (ENTITLEMENT_SANDBOX.to_string(), md::OsirisPlistValue::Bool(true)),
+                        ]),
+                        notarize: false,
                         pkgsign_identity: None,
                         provision_file: None,
                         team_id: None,
@@ -170,7 +288,7 @@ impl Config {
         self.platform_defaults.insert(
             "android".to_string(),
             ConfigPlatform {
-                path_platform: (&self.path_application).join("platform/android"),
+                path_platform: self.path_application.join("platform/android"),
 
                 id: "android".to_string(),
                 id_symbol: "android".to_string(),
@@ -186,9 +304,44 @@ impl Config {
 
                         abis: ["armeabi-v7a", "arm64-v8a", "x86", "x86_64"]
                             .iter().map(|v| v.to_string()).collect(),
+                        uncompressed_extensions: vec!["so".to_string()],
+
+                        manifest_merge: false,
+
+                        main_activity: None,
+                        main_activity_class: ".MainActivity".to_string(),
+
+                        permissions: Vec::new(),
+
+                        keystore: None,
 
                         version_code: 1,
                         version_name: "0.1.0".to_string(),
+
+                        targets: BTreeMap::new(),
+                    },
+                ),
+            },
+        );
+
+        self.platform_defaults.insert(
+            "ios".to_string(),
+            ConfigPlatform {
+                path_platform: self.path_application.join("platform/ios"),
+
+                id: "ios".to_string(),
+                id_symbol: "ios".to_string(),
+
+                configuration: ConfigPlatformConfiguration::Ios(
+                    ConfigPlatformIos {
+                        bundle_id: "com.example.unknown".to_string(),
+
+                        abis: ["arm64", "x86_64"]
+                            .iter().map(|v| v.to_string()).collect(),
+                        min_os: "14.0".to_string(),
+
+                        version_code: 1,
+                        version_name: "1.0.0".to_string(),
                     },
                 ),
             },
@@ -197,7 +350,7 @@ impl Config {
         self.platform_defaults.insert(
             "macos".to_string(),
             ConfigPlatform {
-                path_platform: (&self.path_application).join("platform/macos"),
+                path_platform: self.path_application.join("platform/macos"),
 
                 id: "macos".to_string(),
                 id_symbol: "macos".to_string(),
@@ -214,6 +367,8 @@ impl Config {
                         version_name: "1.0.0".to_string(),
 
                         category: "public.app-category.utilities".to_string(),
+
+                        plist_extra: BTreeMap::new(),
                     },
                 ),
             },
@@ -237,17 +392,37 @@ impl Config {
             Some(md::OsirisArchiveConfiguration::MacosPkg(data_macos)) => {
                 let v_app_id = data_macos.app_id.clone();
                 let v_codesign_identity = data_macos.codesign_identity.clone();
+                let v_notarize = data_macos.notarize.unwrap_or(false);
                 let v_pkgsign_identity = data_macos.pkgsign_identity.clone();
                 let v_provision_file = data_macos.provision_file.as_ref().map(
                     |v| self.path_application.join(v),
                 );
                 let v_team_id = data_macos.team_id.clone();
 
+                // Entitlement keys use reverse-DNS notation. Keys derived
+                // from other configuration cannot be overridden. The app
+                // sandbox is enabled unless explicitly configured.
+                for k in data_macos.entitlements.keys() {
+                    let valid = k.split('.').count() >= 2
+                        && k.split('.').all(|v| {
+                            !v.is_empty() && v.chars().all(|v| v.is_ascii_alphanumeric() || v == '-' || v == '_')
+                        })
+                        && !ENTITLEMENT_RESERVED_KEYS.contains(&k.as_str());
+                    if !valid {
+                        return Err(Error::InvalidEntitlementKey(k.clone()));
+                    }
+                }
+                let mut v_entitlements = data_macos.entitlements.clone();
+                v_entitlements.entry(ENTITLEMENT_SANDBOX.to_string())
+                    .or_insert(md::OsirisPlistValue::Bool(true));
+
                 Ok(
                     ConfigArchiveConfiguration::MacosPkg(
                         ConfigArchiveMacosPkg {
                             app_id: v_app_id,
                             codesign_identity: v_codesign_identity,
+                            entitlements: v_entitlements,
+                            notarize: v_notarize,
                             pkgsign_identity: v_pkgsign_identity,
                             provision_file: v_provision_file,
                             team_id: v_team_id,
@@ -370,6 +545,73 @@ impl Config {
                         .iter().map(|v| v.to_string()).collect()
                 };
 
+                // Native libraries are stored uncompressed by default, so
+                // Android can map them directly from the APK rather than
+                // extracting them on installation. All other entries
+                // compress well and are thus compressed.
+                let v_uncompressed_extensions = data_android.uncompressed_extensions.clone()
+                    .unwrap_or_else(|| vec!["so".to_string()]);
+
+                // A manifest provided via the package metadata is used
+                // verbatim by default, since it is under full control of the
+                // user. Merging the generated launcher activity is opt-in.
+                let v_manifest_merge = data_android.manifest_merge.unwrap_or(false);
+
+                // A main activity that loads the native library is only
+                // generated on request. The library defaults to the
+                // symbolized application ID, which matches the library name
+                // of the package unless the ID was changed. Library names
+                // are embedded in Java sources, so only identifiers are
+                // allowed.
+                let v_main_activity = match data_android.main_activity {
+                    Some(true) => {
+                        let v = data_android.main_activity_library.clone()
+                            .unwrap_or_else(|| self.id_symbol.clone());
+                        if v.is_empty() || !v.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                            return Err(Error::InvalidLibrary(v));
+                        }
+                        Some(v)
+                    },
+                    _ => None,
+                };
+
+                // The launcher activity defaults to the `MainActivity` class
+                // in the namespace, which is also the class of a generated
+                // main activity. Hence, a generated main activity cannot use
+                // any other class. Class names are embedded in the manifest
+                // and must be valid Java names.
+                let v_main_activity_class = data_android.main_activity_class.clone()
+                    .unwrap_or_else(|| ".MainActivity".to_string());
+                let valid = v_main_activity_class
+                    .strip_prefix('.')
+                    .unwrap_or(&v_main_activity_class)
+                    .split('.')
+                    .all(|v| {
+                        v.chars().next().is_some_and(|c| !c.is_ascii_digit())
+                            && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+                    });
+                let generated = v_main_activity_class == ".MainActivity"
+                    || v_main_activity_class == format!("{}.MainActivity", v_namespace);
+                if !valid || (v_main_activity.is_some() && !generated) {
+                    return Err(Error::InvalidActivity(v_main_activity_class));
+                }
+
+                // A release keystore is optional, since debug builds are
+                // signed with the debug keystore. Pass phrases must never
+                // end up in the configuration, so only the names of the
+                // environment variables carrying them are configured.
+                let v_keystore = match data_android.keystore {
+                    None => None,
+                    Some(ref v) => Some(ConfigPlatformAndroidKeystore {
+                        path: self.path_application.join(v),
+                        key_alias: data_android.keystore_key_alias.clone(),
+                        phrase_env: data_android.keystore_phrase_env.clone().ok_or(
+                            Error::MissingKey(".platforms.[].android.keystore-phrase-env"),
+                        )?,
+                        key_phrase_env: data_android.key_phrase_env.clone(),
+                    }),
+                };
+
                 // The version-code is a simple positive integer increased for
                 // every new build. It allows the app stores to identify the
                 // builds and decide which one is the most recent. The code has
@@ -382,6 +624,42 @@ impl Config {
                 let v_version_name = data_android.version_name.as_deref()
                     .unwrap_or("0.1.0");
 
+                // Targets allow customizing the build of individual ABIs.
+                // Configuration for ABIs that are not built is most likely a
+                // typo, so it is rejected. Cfgs use the `key` or `key=value`
+                // syntax of `rustc`, with the value optionally in quotes.
+                let mut v_targets = BTreeMap::new();
+                for (abi, target) in &data_android.targets {
+                    if !v_abis.contains(abi) {
+                        return Err(Error::InvalidTarget(abi.clone()));
+                    }
+
+                    let v_cfgs = target.cfgs.iter().flatten()
+                        .map(|v| match v.split_once('=') {
+                            Some((k, v)) => (
+                                k.to_string(),
+                                Some(
+                                    v.strip_prefix('"')
+                                        .and_then(|v| v.strip_suffix('"'))
+                                        .unwrap_or(v)
+                                        .to_string(),
+                                ),
+                            ),
+                            None => (v.to_string(), None),
+                        })
+                        .collect();
+
+                    v_targets.insert(
+                        abi.clone(),
+                        ConfigPlatformAndroidTarget {
+                            rustc_args: target.rustc_args.clone().unwrap_or_default(),
+                            envs: target.envs.clone(),
+                            cfgs: v_cfgs,
+                            libcxx_shared: target.libcxx_shared.unwrap_or(false),
+                        },
+                    );
+                }
+
                 Ok(
                     ConfigPlatformConfiguration::Android(
                         ConfigPlatformAndroid {
@@ -393,9 +671,63 @@ impl Config {
                             target_sdk: v_target_sdk,
 
                             abis: v_abis,
+                            uncompressed_extensions: v_uncompressed_extensions,
+
+                            manifest_merge: v_manifest_merge,
+
+                            main_activity: v_main_activity,
+                            main_activity_class: v_main_activity_class,
+
+                            permissions: data_android.permissions.clone().unwrap_or_default(),
+
+                            keystore: v_keystore,
 
                             version_code: v_version_code,
                             version_name: v_version_name.to_string(),
+
+                            targets: v_targets,
+                        }
+                    )
+                )
+            },
+            Some(md::OsirisPlatformConfiguration::Ios(data_ios)) => {
+                // The Bundle-ID follows the same rules as on macOS.
+                let v_bundle_id = data_ios.bundle_id.clone()
+                    .unwrap_or_else(|| {
+                        self.id_symbol.clone()
+                    });
+
+                // Let the user select the iOS ABIs to build for. If it is
+                // not specified, we provide the default set with all ABIs.
+                let v_abis = if let Some(v) = data_ios.abis.as_ref() {
+                    v.clone()
+                } else {
+                    ["arm64", "x86_64"]
+                        .iter().map(|v| v.to_string()).collect()
+                };
+
+                // Rust supports the arm64 simulator starting with iOS 14, so
+                // use it as default minimum OS version.
+                let v_min_os = data_ios.min_os.clone().unwrap_or("14.0".to_string());
+                if v_min_os.parse::<misc::Version>().is_err() {
+                    return Err(Error::InvalidVersion(".platforms.[].ios.min-os", v_min_os));
+                }
+
+                // Version-code and -name follow the same rules as on macOS.
+                let v_version_code = data_ios.version_code.unwrap_or(1);
+                let v_version_name = data_ios.version_name.clone()
+                    .unwrap_or_else(|| format!("{}.0.0", v_version_code));
+
+                Ok(
+                    ConfigPlatformConfiguration::Ios(
+                        ConfigPlatformIos {
+                            bundle_id: v_bundle_id,
+
+                            abis: v_abis,
+                            min_os: v_min_os,
+
+                            version_code: v_version_code,
+                            version_name: v_version_name,
                         }
                     )
                 )
@@ -426,6 +758,9 @@ impl Config {
                 //
                 //   https://developer.apple.com/documentation/packagedescription/supportedplatform/macosversion
                 let v_min_os = data_macos.min_os.clone().unwrap_or("10.13".to_string());
+                if v_min_os.parse::<misc::Version>().is_err() {
+                    return Err(Error::InvalidVersion(".platforms.[].macos.min-os", v_min_os));
+                }
 
                 // The version-code is a simple positive integer increased for
                 // every new build. It allows the app stores to identify the
@@ -446,6 +781,19 @@ impl Config {
                     .unwrap_or("public.app-category.utilities")
                     .to_string();
 
+                // Additional `Info.plist` entries are passed through, but
+                // must not override the keys generated by us. Key names
+                // are restricted to the characters Apple uses for its keys.
+                for k in data_macos.plist_extra.keys() {
+                    let valid = !k.is_empty()
+                        && k.chars().all(|v| v.is_ascii_alphanumeric() || v == '.' || v == '-' || v == '_')
+                        && !PLIST_RESERVED_KEYS.contains(&k.as_str());
+                    if !valid {
+                        return Err(Error::InvalidPlistKey(k.clone()));
+                    }
+                }
+                let v_plist_extra = data_macos.plist_extra.clone();
+
                 Ok(
                     ConfigPlatformConfiguration::Macos(
                         ConfigPlatformMacos {
@@ -458,6 +806,8 @@ impl Config {
                             version_name: v_version_name,
 
                             category: v_category,
+
+                            plist_extra: v_plist_extra,
                         }
                     )
                 )
@@ -497,15 +847,13 @@ impl Config {
         // Remember the absolute path to the directory of the configuration.
         // Other relative paths in the configuration are relative to it.
         let v_path_application = misc::absdir(
-            &cargo_arguments.manifest_path.as_deref()
-                .unwrap_or(std::path::Path::new("./Cargo.toml")),
+            &cargo_arguments.manifest_path(),
         );
 
         // Remember the absolute path to the Cargo target directory that will
         // be used by this invocation.
-        let v_path_target = cargo_arguments.target_dir.as_deref()
-            .unwrap_or(std::path::Path::new(&cargo_metadata.target_directory))
-            .to_path_buf();
+        let v_path_target = cargo_arguments.target_dir()
+            .unwrap_or_else(|| cargo_metadata.target_directory.clone().into());
 
         // Use the package-name as application name. Derive its ID from
         // it by masking unsupported characters.
@@ -513,8 +861,10 @@ impl Config {
         let mut v_id = v_name.clone();
         let mut v_id_symbol = lib::str::symbolize(&v_id);
 
-        // Use empty icon-information as default.
+        // Use empty icon-information as default. Placeholders must be
+        // requested explicitly, so they are never shipped by accident.
         let mut v_icons = Vec::new();
+        let mut v_placeholder_icon = false;
 
         let mut config = match cargo_metadata.osiris {
             None => {
@@ -529,6 +879,7 @@ impl Config {
                     name: v_name,
 
                     icons: v_icons,
+                    placeholder_icon: v_placeholder_icon,
 
                     archives: BTreeMap::new(),
                     archive_defaults: BTreeMap::new(),
@@ -550,6 +901,8 @@ impl Config {
                         v_name = v.into();
                     }
 
+                    v_placeholder_icon = mdosi_application.placeholder_icon.unwrap_or(false);
+
                     for icon in &mdosi_application.icons {
                         let Some(ref v_path) = icon.path else { continue };
                         let v_scale = icon.scale.unwrap_or(1);
@@ -574,6 +927,7 @@ impl Config {
                     name: v_name,
 
                     icons: v_icons,
+                    placeholder_icon: v_placeholder_icon,
 
                     archives: BTreeMap::new(),
                     archive_defaults: BTreeMap::new(),
@@ -661,6 +1015,7 @@ mod tests {
                     name: None,
 
                     icons: Vec::new(),
+                    placeholder_icon: None,
                 }),
                 archives: Vec::new(),
                 platforms: Vec::new(),
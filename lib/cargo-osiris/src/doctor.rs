@@ -0,0 +1,206 @@
+//! # Environment Diagnostics
+//!
+//! This module probes the host environment for all tools required by the
+//! build system, and reports their availability together with remediation
+//! hints. Probing is strictly read-only. Nothing is built, installed, or
+//! executed. Missing components can be installed via `install()`, but only
+//! on explicit request of the user.
+
+use crate::{config, op, platform};
+
+/// Result of a single diagnostic probe.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Check {
+    /// Name of the probed component
+    pub name: String,
+    /// Whether the component is available
+    pub ok: bool,
+    /// Location of the component, or a remediation hint if unavailable
+    pub detail: String,
+}
+
+/// Environment of diagnostic probes. All environment variables are resolved
+/// via `env`, which allows probing a faked environment.
+pub struct Probe<'ctx> {
+    /// Lookup of environment variables
+    pub env: &'ctx dyn Fn(&str) -> Option<std::ffi::OsString>,
+}
+
+impl Check {
+    /// Create a successful check with the location of the component.
+    pub fn pass(name: &str, detail: &dyn core::fmt::Display) -> Self {
+        Self {
+            name: name.into(),
+            ok: true,
+            detail: detail.to_string(),
+        }
+    }
+
+    /// Create a failed check with a remediation hint.
+    pub fn fail(name: &str, hint: &dyn core::fmt::Display) -> Self {
+        Self {
+            name: name.into(),
+            ok: false,
+            detail: hint.to_string(),
+        }
+    }
+}
+
+impl<'ctx> Probe<'ctx> {
+    /// Look up the environment variable with the given name.
+    pub fn var(&self, name: &str) -> Option<std::ffi::OsString> {
+        (self.env)(name)
+    }
+
+    /// Find an executable with the given name in the directories listed in
+    /// `PATH`. The platform-specific executable suffix is appended to the
+    /// name.
+    pub fn find_tool(&self, name: &str) -> Option<std::path::PathBuf> {
+        let path = self.var("PATH")?;
+        let file = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+
+        std::env::split_paths(&path)
+            .map(|v| v.join(&file))
+            .find(|v| v.is_file())
+    }
+}
+
+impl core::fmt::Display for Check {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self.ok {
+            true => fmt.write_fmt(core::format_args!("\u{2713} {}: {}", self.name, self.detail)),
+            false => fmt.write_fmt(core::format_args!("\u{2717} {}: {}", self.name, self.detail)),
+        }
+    }
+}
+
+/// Probe for Cargo, either via `CARGO` or via `PATH`.
+pub fn cargo(probe: &Probe) -> Check {
+    match probe.var("CARGO") {
+        Some(v) => {
+            let path = std::path::PathBuf::from(v);
+            if path.is_file() {
+                Check::pass("cargo", &path.display())
+            } else {
+                Check::fail(
+                    "cargo",
+                    &core::format_args!("`CARGO` does not point to a file: {}", path.display()),
+                )
+            }
+        },
+        None => match probe.find_tool("cargo") {
+            Some(v) => Check::pass("cargo", &v.display()),
+            None => Check::fail("cargo", &"Cargo is not available, install a Rust toolchain via: rustup"),
+        },
+    }
+}
+
+/// Probe for all tools required by the given platform configurations. Cargo
+/// is always probed. Android tools are probed if any Android platform is
+/// configured. Xcode tools are probed if `macos` is set.
+pub fn run(
+    probe: &Probe,
+    platforms: &[&config::ConfigPlatform],
+    macos: bool,
+) -> Vec<Check> {
+    let mut acc = vec![cargo(probe)];
+
+    let android = platforms.iter()
+        .filter_map(|v| v.android())
+        .collect::<Vec<_>>();
+
+    if !android.is_empty() {
+        platform::android::doctor(probe, &android, &mut acc);
+    }
+
+    if macos {
+        platform::macos::doctor(probe, &mut acc);
+    }
+
+    acc
+}
+
+/// Install missing components required by the given platform
+/// configurations, as far as the platform tooling supports it. Currently,
+/// only components of the Android SDK can be installed. Yields the names of
+/// all installed components.
+pub fn install(
+    probe: &Probe,
+    platforms: &[&config::ConfigPlatform],
+    runner: &dyn op::ToolRunner,
+) -> Result<Vec<String>, op::BuildError> {
+    let android = platforms.iter()
+        .filter_map(|v| v.android())
+        .collect::<Vec<_>>();
+
+    match android.is_empty() {
+        true => Ok(Vec::new()),
+        false => platform::android::install_missing(probe, &android, runner),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(path: &std::path::Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"").unwrap();
+    }
+
+    // Verify the probes against a faked environment, both with and without
+    // the required tools.
+    #[test]
+    fn doctor_probe() {
+        let dir = op::TestDir::new("doctor");
+        let bin = dir.join("bin");
+        let sdk = dir.join("sdk");
+
+        let metadata = crate::cargo::Metadata {
+            target_directory: dir.to_str().unwrap().into(),
+            ..crate::cargo::Metadata::from_osiris_json(&serde_json::json!({})).unwrap()
+        };
+        let config = config::Config::from_cargo(&Default::default(), &metadata).unwrap();
+        let android = config.platform("android").unwrap();
+        let min_sdk = android.android().unwrap().min_sdk;
+
+        // An empty environment fails all probes with hints.
+        let env = |_: &str| None;
+        let r = run(&Probe { env: &env }, &[android], true);
+        assert!(r.iter().all(|v| !v.ok));
+        assert_eq!(
+            r.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(),
+            ["cargo", "android-sdk", "jdk", "kdk", "xcrun", "codesign"],
+        );
+        assert!(r[1].detail.contains("ANDROID_HOME"));
+
+        // Populate a fake SDK and tools, but omit the build-tools.
+        for v in ["cargo", "javac", "kotlinc", "xcrun", "codesign"] {
+            touch(&bin.join(format!("{}{}", v, std::env::consts::EXE_SUFFIX)));
+        }
+        touch(&sdk.join("licenses/android-sdk-license"));
+        touch(&sdk.join("ndk/26.1.0/source.properties"));
+        touch(&sdk.join(format!("platforms/android-{}/android.jar", min_sdk)));
+
+        let env = |v: &str| match v {
+            "PATH" => Some(bin.clone().into()),
+            "ANDROID_HOME" => Some(sdk.clone().into()),
+            _ => None,
+        };
+        let r = run(&Probe { env: &env }, &[android], false);
+        assert_eq!(
+            r.iter().map(|v| (v.name.as_str(), v.ok)).collect::<Vec<_>>(),
+            [
+                ("cargo", true),
+                ("android-sdk", true),
+                ("android-ndk", true),
+                ("android-build-tools", false),
+                ("android-platform", true),
+                ("jdk", true),
+                ("kdk", true),
+            ],
+        );
+        assert!(r[2].detail.ends_with("26.1.0"));
+        assert!(r[3].detail.contains("sdkmanager \"build-tools;"));
+    }
+}
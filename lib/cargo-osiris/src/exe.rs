@@ -7,27 +7,39 @@
 //! operations exposed by the library. This module does not implement any of
 //! the operations, but merely uses the APIs from the library.
 
-use crate::{cargo, config, lib, op, this};
+use crate::{cargo, config, doctor, lib, op, this};
+
+// Trailing lines of standard error retained per failing tool, if matrix
+// cells are built in parallel.
+const MATRIX_STDERR_LINES: usize = 20;
 
 /// Application entry-point of cargo-osiris.
 ///
 /// This is the entry-point to the build-system command-line tool of Osiris. It
 /// is used to interact with the Osiris Build System. It can be invoked as a
 /// standalone tool or via `cargo osiris ...`.
-pub fn cargo_osiris() -> std::process::ExitCode {
+///
+/// If a cancellation token is provided, running operations stop and tear
+/// down their child processes once it is cancelled. Setting it (e.g., on
+/// interrupts) is left to the caller.
+pub fn cargo_osiris(cancel: Option<&op::CancelToken>) -> std::process::ExitCode {
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
     enum Cmd {
         Root,
         Archive,
         Build,
+        Doctor,
+        Matrix,
     }
 
-    struct Cli {
+    struct Cli<'ctx> {
+        cancel: Option<&'ctx op::CancelToken>,
     }
 
-    impl Cli {
-        fn new() -> Self {
+    impl<'ctx> Cli<'ctx> {
+        fn new(cancel: Option<&'ctx op::CancelToken>) -> Self {
             Self {
+                cancel: cancel,
             }
         }
 
@@ -131,10 +143,14 @@ pub fn cargo_osiris() -> std::process::ExitCode {
                 config: &config,
                 platform: &platform,
                 verbose: verbose,
+                cancel: self.cancel,
             };
 
             match op.run() {
-                Ok(()) => {
+                Ok(v) => {
+                    for artifact in &v.artifacts {
+                        println!("{}", artifact.path.display());
+                    }
                     Ok(())
                 },
                 Err(e) => {
@@ -147,6 +163,7 @@ pub fn cargo_osiris() -> std::process::ExitCode {
         fn op_build(
             &self,
             v_platform: &Option<String>,
+            emit_manifest_only: bool,
             verbose: bool,
             cargo_arguments: &cargo::Arguments,
         ) -> Result<(), u8> {
@@ -158,10 +175,29 @@ pub fn cargo_osiris() -> std::process::ExitCode {
                 config: &config,
                 platform: &platform,
                 verbose: verbose,
+                cancel: self.cancel,
+                runner: None,
+                variant: None,
             };
 
+            if emit_manifest_only {
+                return match build.emit_manifest() {
+                    Ok(v) => {
+                        println!("{}", v.display());
+                        Ok(())
+                    },
+                    Err(e) => {
+                        eprintln!("Cannot emit platform manifest: {}", e);
+                        Err(1)
+                    },
+                };
+            }
+
             match build.build() {
-                Ok(()) => {
+                Ok(v) => {
+                    for artifact in &v.artifacts {
+                        println!("{}", artifact.path.display());
+                    }
                     Ok(())
                 },
                 Err(e) => {
@@ -171,6 +207,180 @@ pub fn cargo_osiris() -> std::process::ExitCode {
             }
         }
 
+        // Build a single cell of a build matrix.
+        fn matrix_cell(
+            &self,
+            cell: &op::MatrixCell,
+            verbose: bool,
+            stderr: op::StderrMode,
+            cargo_arguments: &cargo::Arguments,
+        ) -> Result<(), String> {
+            let runner = op::ProcessRunner { cancel: self.cancel, stderr: stderr };
+
+            let Some(ref id) = cell.platform else {
+                // Without a platform, build the library for the target.
+                return cargo::BuildQuery::new(cargo_arguments)
+                    .with_target(cell.target.as_deref())
+                    .run(&runner)
+                    .map(|_| ()).map_err(|e| e.to_string());
+            };
+
+            // Platform integrations select their targets themselves.
+            if cell.target.is_some() {
+                return Err("A target cannot be combined with a platform".into());
+            }
+
+            let metadata = cargo::MetadataQuery {
+                cargo_arguments: cargo_arguments,
+                target: None,
+            }.run().map_err(|e| format!("Cannot query cargo metadata: {}", e))?;
+            let config = config::Config::from_cargo(cargo_arguments, &metadata)
+                .map_err(|e| format!("Cannot build configuration: {}", e))?;
+            let platform = config.platform(id)
+                .ok_or_else(|| format!("No platform integration with ID {}", id))?;
+
+            // Cells of the same platform are built in parallel, so each set
+            // of features needs its own build directory.
+            let variant = cell.variant();
+
+            op::Build {
+                cargo_arguments: cargo_arguments,
+                cargo_metadata: &metadata,
+                config: &config,
+                platform: platform,
+                verbose: verbose,
+                cancel: self.cancel,
+                runner: Some(&runner),
+                variant: variant.as_deref(),
+            }.build().map(|_| ()).map_err(|e| e.to_string())
+        }
+
+        fn op_matrix(
+            &self,
+            v_cells: &[&str],
+            v_jobs: Option<&str>,
+            keep_going: bool,
+            verbose: bool,
+            cargo_arguments: &cargo::Arguments,
+        ) -> Result<(), u8> {
+            let mut cells = Vec::new();
+            let mut variants = std::collections::BTreeSet::new();
+            for v in v_cells {
+                let cell = match v.parse::<op::MatrixCell>() {
+                    Ok(v) => v,
+                    Err(()) => {
+                        eprintln!("Invalid matrix cell (expected `<features>:<target>:<platform>`): {}", v);
+                        return Err(2);
+                    },
+                };
+
+                // Platform cells with equal features share their build
+                // directory, and would contend for its lock.
+                if cell.platform.is_some() && !variants.insert((cell.platform.clone(), cell.variant())) {
+                    eprintln!("Duplicate matrix cell: {}", v);
+                    return Err(2);
+                }
+
+                cells.push(cell);
+            }
+
+            if cells.is_empty() {
+                eprintln!("No matrix cells specified");
+                return Err(2);
+            }
+
+            let jobs = match v_jobs.map(|v| v.parse::<usize>()) {
+                None => 1,
+                Some(Ok(v)) if v > 0 => v,
+                Some(_) => {
+                    eprintln!("Invalid number of jobs (expected a positive integer): {}", v_jobs.unwrap_or_default());
+                    return Err(2);
+                },
+            };
+
+            let matrix = op::Matrix {
+                cargo_arguments: cargo_arguments,
+                cells: &cells,
+                jobs: jobs,
+                keep_going: keep_going,
+                cancel: self.cancel,
+            };
+
+            // Standard error of cells built in parallel would interleave, so
+            // it is captured instead, and reported with failing tools.
+            let stderr = match jobs {
+                1 => op::StderrMode::Inherit,
+                _ => op::StderrMode::Capture(MATRIX_STDERR_LINES),
+            };
+
+            let outcomes = matrix.run(
+                |cell, arguments| self.matrix_cell(cell, verbose, stderr, arguments),
+            );
+
+            print!("{}", matrix.grid(&outcomes));
+            for (cell, outcome) in cells.iter().zip(outcomes.iter()) {
+                if let op::MatrixOutcome::Fail(e) = outcome {
+                    eprintln!("Cannot build {:?}: {}", cell, e);
+                }
+            }
+
+            match outcomes.iter().all(|v| *v == op::MatrixOutcome::Pass) {
+                true => Ok(()),
+                false => Err(1),
+            }
+        }
+
+        fn op_doctor(
+            &self,
+            install_missing: bool,
+            cargo_arguments: &cargo::Arguments,
+        ) -> Result<(), u8> {
+            let (_, config) = self.config(cargo_arguments)?;
+
+            // Probe the tools of all configured platforms. If none are
+            // configured, probe for the default platforms instead.
+            let mut platforms = config.platforms.values().collect::<Vec<_>>();
+            if platforms.is_empty() {
+                platforms = config.platform_defaults.values().collect();
+            }
+
+            // Xcode tools are only available on macOS, so skip them on other
+            // hosts, since macOS and iOS platforms cannot be built there.
+            let macos = cfg!(target_os = "macos") && platforms.iter().any(
+                |v| core::matches!(
+                    v.configuration,
+                    config::ConfigPlatformConfiguration::Ios(_)
+                        | config::ConfigPlatformConfiguration::Macos(_),
+                ),
+            );
+
+            let env = |v: &str| std::env::var_os(v);
+            let probe = doctor::Probe { env: &env };
+
+            // Install missing components only on explicit request, since
+            // this requires the user to accept their licenses.
+            if install_missing {
+                match doctor::install(&probe, &platforms, &op::ProcessRunner::default()) {
+                    Ok(v) => v.iter().for_each(|v| println!("Installed: {}", v)),
+                    Err(e) => {
+                        eprintln!("Cannot install missing components: {}", e);
+                        return Err(1);
+                    },
+                }
+            }
+
+            let checks = doctor::run(&probe, &platforms, macos);
+
+            for v in checks.iter() {
+                println!("{}", v);
+            }
+
+            match checks.iter().all(|v| v.ok) {
+                true => Ok(()),
+                false => Err(1),
+            }
+        }
+
         fn run(&self) -> Result<(), u8> {
             use crate::lib::args::{Flag, Value};
 
@@ -179,45 +389,67 @@ pub fn cargo_osiris() -> std::process::ExitCode {
             let args = std::env::args_os().skip(1).collect::<Vec<std::ffi::OsString>>();
 
             let v_archive: core::cell::RefCell<Option<String>> = Default::default();
+            let v_cells: core::cell::RefCell<Vec<&str>> = Default::default();
             let v_help = lib::args::Help::new();
+            let v_keep_going: core::cell::RefCell<Option<bool>> = Default::default();
             let v_display: core::cell::RefCell<Option<String>> = Default::default();
+            let v_emit_manifest_only: core::cell::RefCell<Option<bool>> = Default::default();
+            let v_install_missing: core::cell::RefCell<Option<bool>> = Default::default();
+            let v_jobs: core::cell::RefCell<Option<String>> = Default::default();
             let v_platform: core::cell::RefCell<Option<String>> = Default::default();
             let v_verbose: core::cell::RefCell<Option<bool>> = Default::default();
 
+            let v_base_dir: core::cell::RefCell<Option<&std::ffi::OsStr>> = Default::default();
             let v_default_features: core::cell::RefCell<Option<bool>> = Default::default();
             let v_features: core::cell::RefCell<Vec<&str>> = Default::default();
             let v_frozen: core::cell::RefCell<Option<bool>> = Default::default();
-            let v_manifest_path: core::cell::RefCell<Option<std::ffi::OsString>> = Default::default();
+            let v_locked: core::cell::RefCell<Option<bool>> = Default::default();
+            let v_manifest_path: core::cell::RefCell<Option<&std::ffi::OsStr>> = Default::default();
+            let v_offline: core::cell::RefCell<Option<bool>> = Default::default();
             let v_package: core::cell::RefCell<Option<String>> = Default::default();
             let v_profile: core::cell::RefCell<Option<String>> = Default::default();
-            let v_target_dir: core::cell::RefCell<Option<std::ffi::OsString>> = Default::default();
-
-            let flags_build = lib::args::FlagList::with([
-                Flag::with_name("help", Value::Set(&v_help), Some("Show usage information")),
-                Flag::with_name("platform", Value::Parse(&v_platform), Some("ID of the target platform")),
-                Flag::with_name("verbose", Value::Parse(&v_verbose), Some("Be more verbose")),
+            let v_target_dir: core::cell::RefCell<Option<&std::ffi::OsStr>> = Default::default();
 
+            // Flags selecting the package, workspace, and configuration for
+            // Cargo, shared by all commands that build.
+            let flags_cargo = || [
+                Flag::with_name("base-dir", Value::Parse(&v_base_dir), Some("Directory to resolve paths against and run Cargo in")),
                 Flag::with_name("default-features", Value::Toggle(&v_default_features), Some("Enable/Disable default package features")),
                 Flag::with_name("features", Value::Parse(&v_features), Some("Enable specified package features")),
-                Flag::with_name("frozen", Value::Parse(&v_frozen), Some("Use `Cargo.lock` without checking for updates")),
+                Flag::with_name("frozen", Value::Parse(&v_frozen), Some("Require `Cargo.lock` to be up-to-date and run offline")),
+                Flag::with_name("locked", Value::Parse(&v_locked), Some("Require `Cargo.lock` to be up-to-date")),
                 Flag::with_name("manifest-path", Value::Parse(&v_manifest_path), Some("Path to `Cargo.toml`")),
+                Flag::with_name("offline", Value::Parse(&v_offline), Some("Run without accessing the network")),
                 Flag::with_name("package", Value::Parse(&v_package), Some("Workspace package to build")),
                 Flag::with_name("profile", Value::Parse(&v_profile), Some("Name of the build profile")),
                 Flag::with_name("target-dir", Value::Parse(&v_target_dir), Some("Path to the target directory")),
-            ]);
-            let flags_archive = lib::args::FlagList::with([
+            ];
+            let flags_build: lib::args::FlagListBox<_> = [
+                Flag::with_name("emit-manifest-only", Value::Toggle(&v_emit_manifest_only), Some("Only generate the platform manifest")),
+                Flag::with_name("help", Value::Set(&v_help), Some("Show usage information")),
+                Flag::with_name("platform", Value::Parse(&v_platform), Some("ID of the target platform")),
+                Flag::with_name("verbose", Value::Parse(&v_verbose), Some("Be more verbose")),
+            ].into_iter().chain(flags_cargo()).collect();
+            let flags_archive: lib::args::FlagListBox<_> = [
                 Flag::with_name("archive", Value::Parse(&v_archive), Some("ID of the target archive")),
                 Flag::with_name("help", Value::Set(&v_help), Some("Show usage information")),
                 Flag::with_name("platform", Value::Parse(&v_platform), Some("ID of the target platform")),
                 Flag::with_name("verbose", Value::Parse(&v_verbose), Some("Be more verbose")),
+            ].into_iter().chain(flags_cargo()).collect();
+            let flags_matrix: lib::args::FlagListBox<_> = [
+                Flag::with_name("cell", Value::Parse(&v_cells), Some("Matrix cell as `<features>:<target>:<platform>`")),
+                Flag::with_name("help", Value::Set(&v_help), Some("Show usage information")),
+                Flag::with_name("jobs", Value::Parse(&v_jobs), Some("Number of cells to build in parallel, capturing tool diagnostics")),
+                Flag::with_name("keep-going", Value::Toggle(&v_keep_going), Some("Continue with further cells after a failure")),
+                Flag::with_name("verbose", Value::Parse(&v_verbose), Some("Be more verbose")),
+            ].into_iter().chain(flags_cargo()).collect();
+            let flags_doctor = lib::args::FlagList::with([
+                Flag::with_name("help", Value::Set(&v_help), Some("Show usage information")),
+                Flag::with_name("install-missing", Value::Parse(&v_install_missing), Some("Install missing SDK components via `sdkmanager`")),
 
-                Flag::with_name("default-features", Value::Toggle(&v_default_features), Some("Enable/Disable default package features")),
-                Flag::with_name("features", Value::Parse(&v_features), Some("Enable specified package features")),
-                Flag::with_name("frozen", Value::Parse(&v_frozen), Some("Use `Cargo.lock` without checking for updates")),
+                Flag::with_name("base-dir", Value::Parse(&v_base_dir), Some("Directory to resolve paths against and run Cargo in")),
                 Flag::with_name("manifest-path", Value::Parse(&v_manifest_path), Some("Path to `Cargo.toml`")),
-                Flag::with_name("package", Value::Parse(&v_package), Some("Workspace package to build")),
-                Flag::with_name("profile", Value::Parse(&v_profile), Some("Name of the build profile")),
-                Flag::with_name("target-dir", Value::Parse(&v_target_dir), Some("Path to the target directory")),
+                Flag::with_name("package", Value::Parse(&v_package), Some("Workspace package to probe for")),
             ]);
             let flags_root = lib::args::FlagList::with([
                 Flag::with_name("display", Value::Parse(&v_display), Some("Select display mode")),
@@ -233,6 +465,14 @@ pub fn cargo_osiris() -> std::process::ExitCode {
                     Cmd::Build, "build", Default::default(), &flags_build, None,
                     Some("Build artifacts for the specified platform"),
                 ),
+                lib::args::Command::with_name(
+                    Cmd::Doctor, "doctor", Default::default(), &flags_doctor, None,
+                    Some("Check the environment for required tools"),
+                ),
+                lib::args::Command::with_name(
+                    Cmd::Matrix, "matrix", Default::default(), &flags_matrix, None,
+                    Some("Build a matrix of features, targets, and platforms"),
+                ),
             ]);
 
             let root = lib::args::Command::with_name(
@@ -281,6 +521,13 @@ pub fn cargo_osiris() -> std::process::ExitCode {
                 return Ok(());
             }
 
+            // Relative paths given on the command-line are resolved against
+            // the base directory, which defaults to the working directory.
+            let base_dir = match *v_base_dir.borrow() {
+                Some(v) => this.workdir().join(v),
+                None => this.workdir().to_path_buf(),
+            };
+
             match cmd {
                 Cmd::Root => {
                     // If a non-selectable command was chosen, print usage
@@ -294,37 +541,67 @@ pub fn cargo_osiris() -> std::process::ExitCode {
                     &*v_platform.borrow(),
                     v_verbose.borrow().unwrap_or(false),
                     &cargo::Arguments {
+                        base_dir: Some(base_dir.clone()),
                         default_features: *v_default_features.borrow(),
                         features: v_features.borrow().iter().map(|v| (*v).into()).collect(),
                         frozen: *v_frozen.borrow(),
-                        manifest_path: v_manifest_path.borrow().as_ref()
-                            .map(|v| this.workdir().join(v)),
+                        locked: *v_locked.borrow(),
+                        manifest_path: v_manifest_path.borrow().map(|v| v.into()),
+                        offline: *v_offline.borrow(),
                         package: v_package.borrow().clone(),
                         profile: v_profile.borrow().clone(),
-                        target_dir: v_target_dir.borrow().as_ref()
-                            .map(|v| this.workdir().join(v)),
+                        target_dir: v_target_dir.borrow().map(|v| v.into()),
                     },
                 ),
                 Cmd::Build => self.op_build(
                     &*v_platform.borrow(),
+                    v_emit_manifest_only.borrow().unwrap_or(false),
                     v_verbose.borrow().unwrap_or(false),
                     &cargo::Arguments {
+                        base_dir: Some(base_dir.clone()),
                         default_features: *v_default_features.borrow(),
                         features: v_features.borrow().iter().map(|v| (*v).into()).collect(),
                         frozen: *v_frozen.borrow(),
-                        manifest_path: v_manifest_path.borrow().as_ref()
-                            .map(|v| this.workdir().join(v)),
+                        locked: *v_locked.borrow(),
+                        manifest_path: v_manifest_path.borrow().map(|v| v.into()),
+                        offline: *v_offline.borrow(),
                         package: v_package.borrow().clone(),
                         profile: v_profile.borrow().clone(),
-                        target_dir: v_target_dir.borrow().as_ref()
-                            .map(|v| this.workdir().join(v)),
+                        target_dir: v_target_dir.borrow().map(|v| v.into()),
+                    },
+                ),
+                Cmd::Matrix => self.op_matrix(
+                    &v_cells.borrow(),
+                    v_jobs.borrow().as_deref(),
+                    v_keep_going.borrow().unwrap_or(false),
+                    v_verbose.borrow().unwrap_or(false),
+                    &cargo::Arguments {
+                        base_dir: Some(base_dir.clone()),
+                        default_features: *v_default_features.borrow(),
+                        features: v_features.borrow().iter().map(|v| (*v).into()).collect(),
+                        frozen: *v_frozen.borrow(),
+                        locked: *v_locked.borrow(),
+                        manifest_path: v_manifest_path.borrow().map(|v| v.into()),
+                        offline: *v_offline.borrow(),
+                        package: v_package.borrow().clone(),
+                        profile: v_profile.borrow().clone(),
+                        target_dir: v_target_dir.borrow().map(|v| v.into()),
+                    },
+                ),
+                Cmd::Doctor => self.op_doctor(
+                    v_install_missing.borrow().unwrap_or(false),
+                    &cargo::Arguments {
+                        base_dir: Some(base_dir.clone()),
+                        manifest_path: v_manifest_path.borrow().map(|v| v.into()),
+                        package: v_package.borrow().clone(),
+                        ..Default::default()
                     },
                 ),
             }
         }
     }
 
-    match Cli::new().run() {
+    match Cli::new(cancel).run() {
         Ok(()) => 0.into(),
         Err(v) => v.into(),
     }
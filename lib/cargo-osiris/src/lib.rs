@@ -20,13 +20,16 @@
 mod cargo;
 mod con;
 mod config;
+mod doctor;
 mod exe;
 mod md;
 mod misc;
 mod op;
 mod platform;
+mod png;
 mod this;
 
 use osi_lib as lib;
 
 pub use exe::cargo_osiris;
+pub use op::CancelToken;
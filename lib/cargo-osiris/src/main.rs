@@ -4,10 +4,59 @@
 //! meant as sub-command of `cargo` and to be invoked as `cargo osiris ...`.
 //!
 //! This executable simply calls into `cargo_osiris::cargo_osiris()` of the
-//! accompanying library.
+//! accompanying library. Interrupts (e.g., Ctrl-C) are handled here rather
+//! than in the library, and cancel the running operation, so it can tear
+//! down its child processes.
 
 use cargo_osiris;
 
+// Cancellation token of all operations of the executable. It is set on
+// interrupts.
+static CANCEL: cargo_osiris::CancelToken = cargo_osiris::CancelToken::new();
+
+// Install a handler for interrupts that sets `CANCEL` instead of terminating
+// the process. Failures are ignored, since this merely results in the
+// default behavior of terminating the process.
+#[cfg(unix)]
+fn install_interrupt_handler() {
+    extern "C" fn handler(_: core::ffi::c_int) {
+        CANCEL.cancel();
+    }
+
+    extern "C" {
+        fn signal(
+            signum: core::ffi::c_int,
+            handler: extern "C" fn(core::ffi::c_int),
+        ) -> *mut core::ffi::c_void;
+    }
+
+    // SAFETY: The handler is async-signal-safe, since it only performs an
+    //         atomic store.
+    unsafe {
+        signal(2 /* SIGINT */, handler);
+    }
+}
+
+#[cfg(windows)]
+fn install_interrupt_handler() {
+    use windows_sys::Win32;
+
+    unsafe extern "system" fn handler(_: u32) -> Win32::Foundation::BOOL {
+        CANCEL.cancel();
+        1
+    }
+
+    // SAFETY: The handler only performs an atomic store.
+    unsafe {
+        Win32::System::Console::SetConsoleCtrlHandler(Some(handler), 1);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn install_interrupt_handler() {
+}
+
 fn main() -> std::process::ExitCode {
-    cargo_osiris::cargo_osiris()
+    install_interrupt_handler();
+    cargo_osiris::cargo_osiris(Some(&CANCEL))
 }
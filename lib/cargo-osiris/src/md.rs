@@ -52,6 +52,8 @@ pub struct OsirisApplication {
     /// Information on the application icon, allowing for multiple alternatives
     /// that can each provide different attributes (e.g., dimensions).
     pub icons: Vec<OsirisApplicationIcon>,
+    /// Whether to generate placeholder icons if no icon is provided
+    pub placeholder_icon: Option<bool>,
 }
 
 /// Archive configuration for macOS PKGs
@@ -59,6 +61,9 @@ pub struct OsirisApplication {
 pub struct OsirisArchiveMacosPkg {
     pub app_id: Option<String>,
     pub codesign_identity: Option<String>,
+    /// Entitlements of the bundle, indexed by key
+    pub entitlements: std::collections::BTreeMap<String, OsirisPlistValue>,
+    pub notarize: Option<bool>,
     pub pkgsign_identity: Option<String>,
     pub provision_file: Option<String>,
     pub team_id: Option<String>,
@@ -93,9 +98,35 @@ pub struct OsirisPlatformAndroid {
     pub target_sdk: Option<u32>,
 
     pub abis: Option<Vec<String>>,
+    pub uncompressed_extensions: Option<Vec<String>>,
+
+    pub manifest_merge: Option<bool>,
+
+    pub main_activity: Option<bool>,
+    pub main_activity_library: Option<String>,
+    pub main_activity_class: Option<String>,
+
+    pub permissions: Option<Vec<String>>,
+
+    pub keystore: Option<String>,
+    pub keystore_key_alias: Option<String>,
+    pub keystore_phrase_env: Option<String>,
+    pub key_phrase_env: Option<String>,
 
     pub version_code: Option<u32>,
     pub version_name: Option<String>,
+
+    /// Per-ABI build configuration, indexed by ABI
+    pub targets: std::collections::BTreeMap<String, OsirisPlatformAndroidTarget>,
+}
+
+/// Build configuration of a single ABI of the Android platform
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct OsirisPlatformAndroidTarget {
+    pub rustc_args: Option<Vec<String>>,
+    pub envs: std::collections::BTreeMap<String, String>,
+    pub cfgs: Option<Vec<String>>,
+    pub libcxx_shared: Option<bool>,
 }
 
 /// Metadata about the application and framework for the macOS platform
@@ -110,6 +141,36 @@ pub struct OsirisPlatformMacos {
     pub version_name: Option<String>,
 
     pub category: Option<String>,
+
+    /// Additional entries of the bundle `Info.plist`, indexed by key
+    pub plist_extra: std::collections::BTreeMap<String, OsirisPlistValue>,
+}
+
+/// Metadata about the application and framework for the iOS platform
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct OsirisPlatformIos {
+    pub bundle_id: Option<String>,
+
+    pub abis: Option<Vec<String>>,
+    pub min_os: Option<String>,
+
+    pub version_code: Option<u32>,
+    pub version_name: Option<String>,
+}
+
+/// Value of an additional `Info.plist` entry
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum OsirisPlistValue {
+    /// Boolean value (`<true/>` or `<false/>`)
+    Bool(bool),
+    /// Integer value
+    Integer(i64),
+    /// String value
+    String(String),
+    /// Array of values
+    Array(Vec<OsirisPlistValue>),
+    /// Dictionary of values, indexed by key
+    Dict(std::collections::BTreeMap<String, OsirisPlistValue>),
 }
 
 /// Metadata specific to a platform, indexed by the name of the platform
@@ -117,6 +178,8 @@ pub struct OsirisPlatformMacos {
 pub enum OsirisPlatformConfiguration {
     /// Android platform table
     Android(OsirisPlatformAndroid),
+    /// iOS platform table
+    Ios(OsirisPlatformIos),
     /// Macos platform table
     Macos(OsirisPlatformMacos),
 }
@@ -232,6 +295,24 @@ pub fn str_from_json<'json>(
     ).map(|v| Some(v))
 }
 
+/// Parse a sub-key of a JSON object as a boolean value
+pub fn bool_from_json(
+    json: &serde_json::Value,
+    key: &str,
+    path: &str,
+) -> Result<Option<bool>, FormatError> {
+    let Some(entry) = entry_from_json(json, key, path)? else {
+        return Ok(None);
+    };
+
+    entry.as_bool().ok_or_else(
+        || FormatError::TypeInvalid {
+            key: format!("{}.{}", path, key),
+            needs: "boolean".into(),
+        },
+    ).map(|v| Some(v))
+}
+
 /// Parse a sub-key of a JSON object as a number value
 pub fn num_from_json<'json>(
     json: &'json serde_json::Value,
@@ -276,19 +357,73 @@ fn osiris_archive_macos_pkg_from_json(
 ) -> Result<OsirisArchiveMacosPkg, OsirisError> {
     let v_app_id = str_from_json(json, "app-id", "osiris.archives.[].macos-pkg")?;
     let v_codesign_identity = str_from_json(json, "codesign-identity", "osiris.archives.[].macos-pkg")?;
+    let v_notarize = bool_from_json(json, "notarize", "osiris.archives.[].macos-pkg")?;
     let v_pkgsign_identity = str_from_json(json, "pkgsign-identity", "osiris.archives.[].macos-pkg")?;
     let v_provision_file = str_from_json(json, "provision-file", "osiris.archives.[].macos-pkg")?;
     let v_team_id = str_from_json(json, "team-id", "osiris.archives.[].macos-pkg")?;
 
+    let mut v_entitlements = std::collections::BTreeMap::new();
+    if let Some(json_entitlements) = entry_from_json(json, "entitlements", "osiris.archives.[].macos-pkg")? {
+        let serde_json::Value::Object(ref map) = *json_entitlements else {
+            return Err(FormatError::TypeInvalid {
+                key: "osiris.archives.[].macos-pkg.entitlements".into(),
+                needs: "object".into(),
+            }.into());
+        };
+        for (k, v) in map {
+            v_entitlements.insert(
+                k.clone(),
+                plist_value_from_json(v, &format!("osiris.archives.[].macos-pkg.entitlements.{}", k))?,
+            );
+        }
+    }
+
     Ok(OsirisArchiveMacosPkg {
         app_id: v_app_id.map(|v| v.into()),
         codesign_identity: v_codesign_identity.map(|v| v.into()),
+        entitlements: v_entitlements,
+        notarize: v_notarize,
         pkgsign_identity: v_pkgsign_identity.map(|v| v.into()),
         provision_file: v_provision_file.map(|v| v.into()),
         team_id: v_team_id.map(|v| v.into()),
     })
 }
 
+fn osiris_android_target_from_json(
+    json: &serde_json::Value,
+    path: &str,
+) -> Result<OsirisPlatformAndroidTarget, OsirisError> {
+    let v_rustc_args = array_str_from_json(json, "rustc-args", path)?;
+    let v_cfgs = array_str_from_json(json, "cfgs", path)?;
+    let v_libcxx_shared = bool_from_json(json, "libcxx-shared", path)?;
+
+    let mut v_envs = std::collections::BTreeMap::new();
+    if let Some(json_envs) = entry_from_json(json, "envs", path)? {
+        let serde_json::Value::Object(ref map) = *json_envs else {
+            return Err(FormatError::TypeInvalid {
+                key: format!("{}.envs", path),
+                needs: "object".into(),
+            }.into());
+        };
+        for (k, v) in map {
+            let Some(v_str) = v.as_str() else {
+                return Err(FormatError::TypeInvalid {
+                    key: format!("{}.envs.{}", path, k),
+                    needs: "string".into(),
+                }.into());
+            };
+            v_envs.insert(k.clone(), v_str.to_string());
+        }
+    }
+
+    Ok(OsirisPlatformAndroidTarget {
+        rustc_args: v_rustc_args.map(|v| v.iter().map(|v| v.to_string()).collect()),
+        envs: v_envs,
+        cfgs: v_cfgs.map(|v| v.iter().map(|v| v.to_string()).collect()),
+        libcxx_shared: v_libcxx_shared,
+    })
+}
+
 fn osiris_android_from_json(
     json: &serde_json::Value,
 ) -> Result<OsirisPlatformAndroid, OsirisError> {
@@ -298,9 +433,35 @@ fn osiris_android_from_json(
     let v_min_sdk = u32_from_json(json, "min-sdk", "osiris.platforms.[].android")?;
     let v_target_sdk = u32_from_json(json, "target-sdk", "osiris.platforms.[].android")?;
     let v_abis = array_str_from_json(json, "abis", "osiris.platforms.[].android")?;
+    let v_uncompressed_extensions = array_str_from_json(json, "uncompressed-extensions", "osiris.platforms.[].android")?;
+    let v_manifest_merge = bool_from_json(json, "manifest-merge", "osiris.platforms.[].android")?;
+    let v_main_activity = bool_from_json(json, "main-activity", "osiris.platforms.[].android")?;
+    let v_main_activity_library = str_from_json(json, "main-activity-library", "osiris.platforms.[].android")?;
+    let v_main_activity_class = str_from_json(json, "main-activity-class", "osiris.platforms.[].android")?;
+    let v_permissions = array_str_from_json(json, "permissions", "osiris.platforms.[].android")?;
+    let v_keystore = str_from_json(json, "keystore", "osiris.platforms.[].android")?;
+    let v_keystore_key_alias = str_from_json(json, "keystore-key-alias", "osiris.platforms.[].android")?;
+    let v_keystore_phrase_env = str_from_json(json, "keystore-phrase-env", "osiris.platforms.[].android")?;
+    let v_key_phrase_env = str_from_json(json, "key-phrase-env", "osiris.platforms.[].android")?;
     let v_version_code = u32_from_json(json, "version-code", "osiris.platforms.[].android")?;
     let v_version_name = str_from_json(json, "version-name", "osiris.platforms.[].android")?;
 
+    let mut v_targets = std::collections::BTreeMap::new();
+    if let Some(json_targets) = entry_from_json(json, "targets", "osiris.platforms.[].android")? {
+        let serde_json::Value::Object(ref map) = *json_targets else {
+            return Err(FormatError::TypeInvalid {
+                key: "osiris.platforms.[].android.targets".into(),
+                needs: "object".into(),
+            }.into());
+        };
+        for (k, v) in map {
+            v_targets.insert(
+                k.clone(),
+                osiris_android_target_from_json(v, &format!("osiris.platforms.[].android.targets.{}", k))?,
+            );
+        }
+    }
+
     Ok(OsirisPlatformAndroid {
         application_id: v_application_id.map(|v| v.into()),
         namespace: v_namespace.map(|v| v.into()),
@@ -310,6 +471,74 @@ fn osiris_android_from_json(
         target_sdk: v_target_sdk,
 
         abis: v_abis.map(|v| v.iter().map(|v| v.to_string()).collect()),
+        uncompressed_extensions: v_uncompressed_extensions.map(|v| v.iter().map(|v| v.to_string()).collect()),
+
+        manifest_merge: v_manifest_merge,
+
+        main_activity: v_main_activity,
+        main_activity_library: v_main_activity_library.map(|v| v.into()),
+        main_activity_class: v_main_activity_class.map(|v| v.into()),
+
+        permissions: v_permissions.map(|v| v.iter().map(|v| v.to_string()).collect()),
+
+        keystore: v_keystore.map(|v| v.into()),
+        keystore_key_alias: v_keystore_key_alias.map(|v| v.into()),
+        keystore_phrase_env: v_keystore_phrase_env.map(|v| v.into()),
+        key_phrase_env: v_key_phrase_env.map(|v| v.into()),
+
+        version_code: v_version_code,
+        version_name: v_version_name.map(|v| v.into()),
+
+        targets: v_targets,
+    })
+}
+
+// Parse a JSON value as `Info.plist` value. Only booleans, integers,
+// strings, as well as arrays and dictionaries (JSON objects) thereof are
+// supported.
+fn plist_value_from_json(
+    json: &serde_json::Value,
+    path: &str,
+) -> Result<OsirisPlistValue, FormatError> {
+    match json {
+        serde_json::Value::Bool(v) => Ok(OsirisPlistValue::Bool(*v)),
+        serde_json::Value::Number(v) if v.is_i64() => Ok(OsirisPlistValue::Integer(v.as_i64().unwrap())),
+        serde_json::Value::String(v) => Ok(OsirisPlistValue::String(v.clone())),
+        serde_json::Value::Array(list) => {
+            let mut acc = Vec::new();
+            for v in list {
+                acc.push(plist_value_from_json(v, &format!("{}.[]", path))?);
+            }
+            Ok(OsirisPlistValue::Array(acc))
+        },
+        serde_json::Value::Object(map) => {
+            let mut acc = std::collections::BTreeMap::new();
+            for (k, v) in map {
+                acc.insert(k.clone(), plist_value_from_json(v, &format!("{}.{}", path, k))?);
+            }
+            Ok(OsirisPlistValue::Dict(acc))
+        },
+        _ => Err(FormatError::TypeInvalid {
+            key: path.into(),
+            needs: "boolean, integer, string, array, or object".into(),
+        }),
+    }
+}
+
+fn osiris_ios_from_json(
+    json: &serde_json::Value,
+) -> Result<OsirisPlatformIos, OsirisError> {
+    let v_bundle_id = str_from_json(json, "bundle-id", "osiris.platforms.[].ios")?;
+    let v_abis = array_str_from_json(json, "abis", "osiris.platforms.[].ios")?;
+    let v_min_os = str_from_json(json, "min-os", "osiris.platforms.[].ios")?;
+    let v_version_code = u32_from_json(json, "version-code", "osiris.platforms.[].ios")?;
+    let v_version_name = str_from_json(json, "version-name", "osiris.platforms.[].ios")?;
+
+    Ok(OsirisPlatformIos {
+        bundle_id: v_bundle_id.map(|v| v.into()),
+
+        abis: v_abis.map(|v| v.iter().map(|v| v.to_string()).collect()),
+        min_os: v_min_os.map(|v| v.into()),
 
         version_code: v_version_code,
         version_name: v_version_name.map(|v| v.into()),
@@ -326,6 +555,22 @@ fn osiris_macos_from_json(
     let v_version_name = str_from_json(json, "version-name", "osiris.platforms.[].macos")?;
     let v_category = str_from_json(json, "category", "osiris.platforms.[].macos")?;
 
+    let mut v_plist_extra = std::collections::BTreeMap::new();
+    if let Some(json_plist) = entry_from_json(json, "plist-extra", "osiris.platforms.[].macos")? {
+        let serde_json::Value::Object(ref map) = *json_plist else {
+            return Err(FormatError::TypeInvalid {
+                key: "osiris.platforms.[].macos.plist-extra".into(),
+                needs: "object".into(),
+            }.into());
+        };
+        for (k, v) in map {
+            v_plist_extra.insert(
+                k.clone(),
+                plist_value_from_json(v, &format!("osiris.platforms.[].macos.plist-extra.{}", k))?,
+            );
+        }
+    }
+
     Ok(OsirisPlatformMacos {
         bundle_id: v_bundle_id.map(|v| v.into()),
 
@@ -336,6 +581,8 @@ fn osiris_macos_from_json(
         version_name: v_version_name.map(|v| v.into()),
 
         category: v_category.map(|v| v.into()),
+
+        plist_extra: v_plist_extra,
     })
 }
 
@@ -362,12 +609,14 @@ pub fn osiris_from_json(
     if let Some(json_application) = entry_from_json(json, "application", "osiris")? {
         let v_id = str_from_json(json_application, "id", "osiris.application")?;
         let v_name = str_from_json(json_application, "name", "osiris.application")?;
+        let v_placeholder_icon = bool_from_json(json_application, "placeholder-icon", "osiris.application")?;
 
         let mut osi_application = OsirisApplication {
             id: v_id.map(|v| v.into()),
             name: v_name.map(|v| v.into()),
 
             icons: Vec::new(),
+            placeholder_icon: v_placeholder_icon,
         };
 
         if let Some(json_icons) = array_from_json(json_application, "icons", "osiris.application")? {
@@ -422,20 +671,26 @@ pub fn osiris_from_json(
             let v_path = str_from_json(json_platform, "path", "osiris.platforms.[]")?;
 
             let v_android = entry_from_json(json_platform, "android", "osiris.platforms.[]")?;
+            let v_ios = entry_from_json(json_platform, "ios", "osiris.platforms.[]")?;
             let v_macos = entry_from_json(json_platform, "macos", "osiris.platforms.[]")?;
-            let v_configuration = match (v_android, v_macos) {
-                (None, None) => Ok(None),
-                (Some(v), None) => {
+            let v_configuration = match (v_android, v_ios, v_macos) {
+                (None, None, None) => Ok(None),
+                (Some(v), None, None) => {
                     osiris_android_from_json(v).map(
                         |v| Some(OsirisPlatformConfiguration::Android(v)),
                     )
                 },
-                (None, Some(v)) => {
+                (None, Some(v), None) => {
+                    osiris_ios_from_json(v).map(
+                        |v| Some(OsirisPlatformConfiguration::Ios(v)),
+                    )
+                },
+                (None, None, Some(v)) => {
                     osiris_macos_from_json(v).map(
                         |v| Some(OsirisPlatformConfiguration::Macos(v)),
                     )
                 },
-                _ => Err(FormatError::KeyExclusive { key: "osiris.platforms.[].{android,macos}".into() }.into()),
+                _ => Err(FormatError::KeyExclusive { key: "osiris.platforms.[].{android,ios,macos}".into() }.into()),
             }?;
 
             let osi_platform = OsirisPlatform {
@@ -3,6 +3,102 @@
 //! A collection of small utilities that extend the Rust standard library with
 //! features required by this crate.
 
+/// ## Version Number
+///
+/// A version number given as dot-separated list of non-negative integers
+/// (e.g., `34.0.1`). The first three segments are called major, minor, and
+/// patch version, but any number of segments is supported, including fewer
+/// than three.
+///
+/// Versions order numerically segment by segment, and a version orders
+/// after all its prefixes (i.e., `9 < 10 < 10.0 < 10.0.1`).
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Version {
+    segments: Vec<u32>,
+}
+
+/// Errors raised when parsing a version number.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VersionError {
+    /// Version number has no segments
+    Empty,
+    /// Segment is not a non-negative integer
+    SegmentInvalid(String),
+}
+
+impl Version {
+    /// Create a version from its segments. Returns `None` if no segments
+    /// are given.
+    pub fn with(segments: Vec<u32>) -> Option<Self> {
+        match segments.is_empty() {
+            true => None,
+            false => Some(Self { segments: segments }),
+        }
+    }
+
+    /// Return all segments of the version.
+    pub fn segments(&self) -> &[u32] {
+        &self.segments
+    }
+
+    /// Return the major version.
+    pub fn major(&self) -> u32 {
+        self.segments[0]
+    }
+
+    /// Return the minor version, or 0 if not specified.
+    pub fn minor(&self) -> u32 {
+        self.segments.get(1).copied().unwrap_or(0)
+    }
+
+    /// Return the patch version, or 0 if not specified.
+    pub fn patch(&self) -> u32 {
+        self.segments.get(2).copied().unwrap_or(0)
+    }
+}
+
+impl core::str::FromStr for Version {
+    type Err = VersionError;
+
+    fn from_str(v: &str) -> Result<Self, Self::Err> {
+        if v.is_empty() {
+            return Err(VersionError::Empty);
+        }
+
+        // Only accept plain decimal digits, since `u32::from_str()` would
+        // also accept a leading sign.
+        let segments = v.split('.')
+            .map(|v| match v.bytes().all(|v| v.is_ascii_digit()) {
+                true => v.parse::<u32>().ok(),
+                false => None,
+            }.ok_or_else(|| VersionError::SegmentInvalid(v.into())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { segments: segments })
+    }
+}
+
+impl core::fmt::Display for Version {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        for (i, v) in self.segments.iter().enumerate() {
+            if i > 0 {
+                fmt.write_str(".")?;
+            }
+            fmt.write_fmt(core::format_args!("{}", v))?;
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Display for VersionError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::Empty => fmt.write_str("Version number is empty"),
+            Self::SegmentInvalid(v) => fmt.write_fmt(core::format_args!("Invalid version segment: '{}'", v)),
+        }
+    }
+}
+
 /// ## Return the absolute directory of a file path
 ///
 /// This takes a path to a file and returns the absolute path to the directory
@@ -58,33 +154,6 @@ pub fn escape_single_quote(input: &std::ffi::OsStr) -> std::ffi::OsString {
     unsafe { std::ffi::OsString::from_encoded_bytes_unchecked(to) }
 }
 
-/// ## Escape XML PCDATA
-///
-/// Create a new string that has the same content as the input but all special
-/// characters encoded suitably for XML PCDATA.
-pub fn escape_xml_pcdata(input: &str) -> String {
-    let n_alloc = input.chars().fold(
-        0,
-        |acc, v| match v {
-            '&' => acc + 5,
-            '<' => acc + 4,
-            _ => acc + 1,
-        }
-    );
-
-    let mut v = String::with_capacity(n_alloc);
-
-    for c in input.chars() {
-        match c {
-            '&' => v.push_str("&amp;"),
-            '<' => v.push_str("&lt;"),
-            _ => v.push(c),
-        }
-    }
-
-    v
-}
-
 /// Reduce a line of text to a fixed width, highlighting a selected range. Use
 /// it to reduce overlong lines when displaying on a limited device.
 ///
@@ -151,6 +220,26 @@ pub fn ellipse(
     )
 }
 
+/// ## Calculate CRC-32
+///
+/// Calculate the CRC-32 (ISO-HDLC) of the data, as used by PNG chunks and
+/// ZIP archives.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &v in data {
+        crc ^= v as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                0 => crc >> 1,
+                _ => (crc >> 1) ^ 0xedb88320,
+            };
+        }
+    }
+
+    !crc
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -196,6 +285,42 @@ mod tests {
         cwd.pop();
     }
 
+    // Verify parsing, formatting, and numeric ordering of versions.
+    #[test]
+    fn test_version() {
+        let v: Version = "34.0.1".parse().unwrap();
+        assert_eq!(v.segments(), [34, 0, 1]);
+        assert_eq!((v.major(), v.minor(), v.patch()), (34, 0, 1));
+        assert_eq!(v.to_string(), "34.0.1");
+
+        let v: Version = "10.13".parse().unwrap();
+        assert_eq!((v.major(), v.minor(), v.patch()), (10, 13, 0));
+        assert_eq!("1.2.3.4".parse::<Version>().unwrap().segments(), [1, 2, 3, 4]);
+        assert_eq!("007".parse::<Version>().unwrap().to_string(), "7");
+
+        let v = ["9", "10", "10.0", "10.0.1", "10.1", "11"].map(|v| v.parse::<Version>().unwrap());
+        assert!(v.windows(2).all(|v| v[0] < v[1]));
+        assert!("9".parse::<Version>().unwrap() < "10".parse().unwrap());
+
+        assert_eq!("".parse::<Version>(), Err(VersionError::Empty));
+        for (v, segment) in [
+            ("1.", ""),
+            (".1", ""),
+            ("1..2", ""),
+            ("1.x", "x"),
+            ("+1", "+1"),
+            ("1.-2", "-2"),
+            ("11-rc1", "11-rc1"),
+            ("1. 2", " 2"),
+            ("4294967296", "4294967296"),
+        ] {
+            assert_eq!(v.parse::<Version>(), Err(VersionError::SegmentInvalid(segment.into())));
+        }
+
+        assert_eq!(Version::with(Vec::new()), None);
+        assert_eq!(Version::with(vec![1, 2]), Some("1.2".parse().unwrap()));
+    }
+
     // Verify that the single-quote escapes are properly handled.
     #[test]
     fn test_single_quote() {
@@ -217,13 +342,11 @@ mod tests {
         );
     }
 
-    // Verify that the XML-PCDATA escapes are properly handled.
+    // Verify checksums against known values.
     #[test]
-    fn test_xml_pcdata() {
-        assert_eq!(escape_xml_pcdata(""), "");
-        assert_eq!(escape_xml_pcdata("foobar"), "foobar");
-        assert_eq!(escape_xml_pcdata("foo & bar"), "foo &amp; bar");
-        assert_eq!(escape_xml_pcdata("<foobar>"), "&lt;foobar>");
-        assert_eq!(escape_xml_pcdata("<&>"), "&lt;&amp;>");
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
     }
 }
@@ -8,6 +8,7 @@ use crate::{cargo, config, lib, platform};
 
 /// Error definitions shared across most implemented operations, describing
 /// errors when accessing or modifying data store on the file system.
+#[derive(Debug)]
 pub enum ErrorFileSystem {
     /// Cannot traverse the specified directory
     DirectoryTraversal { path: std::ffi::OsString, io: std::io::Error },
@@ -15,10 +16,16 @@ pub enum ErrorFileSystem {
     DirectoryCreation { path: std::ffi::OsString, io: std::io::Error },
     /// Cannot remove the specified build artifact directory
     DirectoryRemoval { path: std::ffi::OsString, io: std::io::Error },
+    /// Reading the file at the specified path failed with the given error
+    FileRead { path: std::path::PathBuf, io: std::io::Error },
     /// Updating the file at the specified path failed with the given error
     FileUpdate { path: std::path::PathBuf, io: std::io::Error },
     /// Copying a file failed with the given error
     FileCopy { from: std::path::PathBuf, to: std::path::PathBuf, io: std::io::Error },
+    /// Acquiring the lock file failed with the given error
+    LockAcquisition { path: std::path::PathBuf, io: std::io::Error },
+    /// Lock file is held by another operation in progress
+    LockHeld { path: std::path::PathBuf },
 }
 
 /// Error definitions shared across most implemented operations, describing
@@ -38,6 +45,10 @@ pub enum ArchiveError {
     FileSystem(ErrorFileSystem),
     /// Process execution errors
     Process(ErrorProcess),
+    /// Operation was cancelled via its cancellation token
+    Cancelled,
+    /// macOS platform specific errors
+    MacosPlatform(platform::macos::ErrorNotary),
 }
 
 /// ## Build Errors
@@ -54,13 +65,121 @@ pub enum BuildError {
     /// Execution of the given tool could not commence.
     Exec(String, std::io::Error),
     /// Given tool failed executing.
-    Exit(String, std::process::ExitStatus),
+    Exit(String, ToolExit),
     /// Cargo specific errors.
     Cargo(cargo::Error),
     /// Android platform specific errors.
     AndroidPlatform(platform::android::BuildError),
+    /// iOS platform specific errors.
+    IosPlatform(platform::ios::ErrorBuild),
     /// macOS platform specific errors.
     MacosPlatform(platform::macos::ErrorBuild),
+    /// Operation was cancelled via its cancellation token.
+    Cancelled,
+    /// Worker of a parallel build panicked in the given context.
+    Panicked(String),
+}
+
+/// ## Cancellation Token
+///
+/// A flag that can be set from any thread (or a signal handler) to request
+/// cancellation of a running operation. Operations check it between their
+/// phases, and child processes run via `Self::output()` are killed once it
+/// is set. Cancellation cannot be revoked.
+#[derive(Debug, Default)]
+pub struct CancelToken {
+    flag: std::sync::atomic::AtomicBool,
+}
+
+/// Kind of a final artifact produced by an operation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArtifactKind {
+    /// Signed Android application package
+    AndroidApk,
+    /// iOS application bundle directory
+    IosBundle,
+    /// macOS application bundle directory
+    MacosBundle,
+    /// macOS installer package
+    MacosPkg,
+}
+
+/// Final artifact produced by an operation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Artifact {
+    pub kind: ArtifactKind,
+    pub path: std::path::PathBuf,
+}
+
+/// ## Operation Output
+///
+/// Result of a successful build or archive operation, enumerating the final
+/// artifacts it produced. Intermediate files are not listed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Output {
+    pub artifacts: Vec<Artifact>,
+}
+
+/// ## Standard Error Mode
+///
+/// Selects how runners treat the standard error stream of external tools.
+/// By default, the stream is left as configured by the tool invocation,
+/// which usually inherits it, so diagnostics are shown on the terminal as
+/// they are produced. Non-interactive callers can capture the stream
+/// instead, in which case only its last lines are retained and reported
+/// with errors of failing tools.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum StderrMode {
+    /// Leave standard error as configured by the tool invocation.
+    #[default]
+    Inherit,
+    /// Capture standard error and retain the given number of trailing
+    /// lines.
+    Capture(usize),
+}
+
+/// ## Tool Exit
+///
+/// Exit condition of a failed external tool, together with an excerpt of
+/// its standard error, if it was captured.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ToolExit {
+    pub status: std::process::ExitStatus,
+    pub excerpt: Option<String>,
+}
+
+/// ## Tool Runner
+///
+/// Abstraction over the execution of external tools by platform pipelines.
+/// `ProcessRunner` spawns the tools as child processes. Other
+/// implementations can record invocations and return canned results, so
+/// pipelines can be tested without any SDK installed.
+pub trait ToolRunner {
+    /// Run the command to completion and capture its standard output,
+    /// similar to `std::process::Command::output()`. Standard error is only
+    /// captured if the runner is configured to do so.
+    fn run(
+        &self,
+        cmd: &mut std::process::Command,
+    ) -> Result<std::process::Output, std::io::Error>;
+
+    /// Forward a diagnostic that a tool reported in structured form rather
+    /// than on standard error, like the compiler messages of Cargo. It is
+    /// treated like standard error of the tool. The default implementation
+    /// discards it.
+    fn diagnose(&self, _rendered: &str) {
+    }
+}
+
+/// ## Process Runner
+///
+/// Tool runner that spawns tools as child processes. If a cancellation
+/// token is given, running tools are killed once it is set. Standard error
+/// is handled as selected by `stderr`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessRunner<'ctx> {
+    pub cancel: Option<&'ctx CancelToken>,
+    pub stderr: StderrMode,
 }
 
 /// Collection of parameters for an archive operation
@@ -71,6 +190,7 @@ pub struct Archive<'ctx> {
     pub config: &'ctx config::Config,
     pub platform: &'ctx config::ConfigPlatform,
     pub verbose: bool,
+    pub cancel: Option<&'ctx CancelToken>,
 }
 
 /// Collection of parameters for a build operation
@@ -80,6 +200,297 @@ pub struct Build<'ctx> {
     pub config: &'ctx config::Config,
     pub platform: &'ctx config::ConfigPlatform,
     pub verbose: bool,
+    pub cancel: Option<&'ctx CancelToken>,
+    /// Runner for external tools, or `None` to spawn processes with
+    /// inherited standard error
+    pub runner: Option<&'ctx dyn ToolRunner>,
+    /// Build variant, or `None` for the default build. Each variant uses
+    /// its own build directory, so variants of a platform can be built in
+    /// parallel.
+    pub variant: Option<&'ctx str>,
+}
+
+/// ## Build Matrix Cell
+///
+/// A single combination of a build matrix. The features are enabled on top
+/// of the features of the base arguments. If a platform is given, the
+/// platform integration is built, otherwise the library is built for the
+/// given target (or the host, if none is given).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MatrixCell {
+    pub features: Vec<String>,
+    pub target: Option<String>,
+    pub platform: Option<String>,
+}
+
+/// Outcome of a single cell of a build matrix
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MatrixOutcome {
+    /// Build of the cell succeeded
+    Pass,
+    /// Build of the cell failed with the given message
+    Fail(String),
+    /// Cell was not built, due to an earlier failure or cancellation
+    Skipped,
+}
+
+/// Collection of parameters for a matrix operation
+pub struct Matrix<'ctx> {
+    pub cargo_arguments: &'ctx cargo::Arguments,
+    pub cells: &'ctx [MatrixCell],
+    /// Number of cells to build in parallel (0 is treated as 1)
+    pub jobs: usize,
+    pub keep_going: bool,
+    pub cancel: Option<&'ctx CancelToken>,
+}
+
+/// ## Directory Lock
+///
+/// Advisory lock on a build directory, acquired via `lock_dir()`. The lock is
+/// released when this object is dropped.
+pub struct Lock {
+    #[allow(unused)]
+    file: std::fs::File,
+    path: std::path::PathBuf,
+    pidfile: bool,
+}
+
+impl CancelToken {
+    /// ## Create new token
+    ///
+    /// Create a new cancellation token that is not cancelled. This is a
+    /// `const fn`, so tokens can be placed in statics (e.g., for access
+    /// from signal handlers).
+    pub const fn new() -> Self {
+        Self {
+            flag: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    /// ## Request cancellation
+    ///
+    /// Mark the token as cancelled. This is async-signal-safe.
+    pub fn cancel(&self) {
+        self.flag.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// ## Check for cancellation
+    ///
+    /// Return `true` if cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// ## Run command with cancellation
+    ///
+    /// Run the command to completion and capture its standard output,
+    /// similar to `std::process::Command::output()`. Standard error is left
+    /// as configured by the caller (inherited by default) and is only
+    /// captured if the caller configured it as pipe.
+    ///
+    /// If the token is cancelled while the command runs, the child process
+    /// is killed and an error of kind `std::io::ErrorKind::Interrupted` is
+    /// returned.
+    pub fn output(
+        &self,
+        cmd: &mut std::process::Command,
+    ) -> Result<std::process::Output, std::io::Error> {
+        cmd.stdout(std::process::Stdio::piped());
+
+        let mut child = cmd.spawn()?;
+
+        // Drain the output in a separate thread, so the child never blocks
+        // on a full pipe while we poll for its exit.
+        let mut stdout = child.stdout.take().expect("Standard output must be piped");
+        let reader = std::thread::spawn(move || {
+            let mut acc = Vec::new();
+            std::io::Read::read_to_end(&mut stdout, &mut acc).map(|_| acc)
+        });
+        let reader_err = child.stderr.take().map(|mut stderr| {
+            std::thread::spawn(move || {
+                let mut acc = Vec::new();
+                std::io::Read::read_to_end(&mut stderr, &mut acc).map(|_| acc)
+            })
+        });
+
+        let status = loop {
+            if let Some(v) = child.try_wait()? {
+                break v;
+            }
+
+            if self.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                let _ = reader.join();
+                let _ = reader_err.map(|v| v.join());
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Interrupted,
+                    "Operation was cancelled",
+                ));
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        };
+
+        let stdout = reader.join().expect("Output reader must not panic")?;
+        let stderr = match reader_err {
+            Some(v) => v.join().expect("Error reader must not panic")?,
+            None => Vec::new(),
+        };
+
+        Ok(std::process::Output {
+            status: status,
+            stdout: stdout,
+            stderr: stderr,
+        })
+    }
+}
+
+// Yield the last `lines` lines of the given data. A trailing line
+// terminator does not start another line.
+fn tail_lines(data: &[u8], lines: usize) -> &[u8] {
+    if lines == 0 {
+        return &[];
+    }
+
+    let body = data.strip_suffix(b"\n").unwrap_or(data);
+    let start = body.iter()
+        .enumerate()
+        .rev()
+        .filter(|v| *v.1 == b'\n')
+        .nth(lines - 1)
+        .map_or(0, |v| v.0 + 1);
+
+    &data[start..]
+}
+
+impl<'ctx> ToolRunner for ProcessRunner<'ctx> {
+    fn run(
+        &self,
+        cmd: &mut std::process::Command,
+    ) -> Result<std::process::Output, std::io::Error> {
+        // Capturing overrides any stream selected by the tool invocation.
+        if let StderrMode::Capture(_) = self.stderr {
+            cmd.stderr(std::process::Stdio::piped());
+        }
+
+        let mut output = match self.cancel {
+            Some(v) => v.output(cmd),
+            None => cmd.output(),
+        }?;
+
+        if let StderrMode::Capture(lines) = self.stderr {
+            output.stderr = tail_lines(&output.stderr, lines).to_vec();
+        }
+
+        Ok(output)
+    }
+
+    fn diagnose(&self, rendered: &str) {
+        // Diagnostics are shown along with inherited standard error of the
+        // tool, but dropped with captured standard error, which is meant
+        // for non-interactive callers.
+        if let StderrMode::Inherit = self.stderr {
+            eprint!("{}", rendered);
+        }
+    }
+}
+
+impl From<&std::process::Output> for ToolExit {
+    fn from(v: &std::process::Output) -> Self {
+        let excerpt = String::from_utf8_lossy(&v.stderr);
+
+        Self {
+            status: v.status,
+            excerpt: lib::str::some(excerpt.trim_end()).map(|v| v.into()),
+        }
+    }
+}
+
+impl core::fmt::Display for ToolExit {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self.excerpt {
+            None => fmt.write_fmt(core::format_args!("{}", self.status)),
+            Some(ref v) => fmt.write_fmt(core::format_args!("{}, with standard error ending in:\n{}", self.status, v)),
+        }
+    }
+}
+
+// Length limit of paths on Windows, unless extended-length paths are used.
+// This is `MAX_PATH` minus the space reserved for 8.3 file names, since
+// directory creation uses this lower limit.
+const WINDOWS_MAX_PATH: usize = 248;
+
+// Turn an absolute Windows path into an extended-length path with the
+// `\\?\` prefix, if it exceeds `WINDOWS_MAX_PATH`. Extended-length paths
+// are passed to the file system verbatim, so forward slashes are turned into
+// backslashes, and `.` and `..` components are resolved here. Returns `None`
+// if the path is short enough, already prefixed, or not absolute.
+//
+// This operates on strings rather than `std::path::Path`, since the latter
+// only understands Windows prefixes when compiled for Windows.
+fn windows_long_path(path: &str) -> Option<String> {
+    if path.len() < WINDOWS_MAX_PATH
+        || path.starts_with("\\\\?\\")
+        || path.starts_with("//?/")
+    {
+        return None;
+    }
+
+    let path = path.replace('/', "\\");
+    let (prefix, rest) = if let Some(unc) = path.strip_prefix("\\\\") {
+        ("\\\\?\\UNC\\", unc)
+    } else if path.as_bytes().get(1) == Some(&b':')
+        && path.as_bytes().get(2) == Some(&b'\\')
+        && path.as_bytes()[0].is_ascii_alphabetic()
+    {
+        ("\\\\?\\", path.as_str())
+    } else {
+        return None;
+    };
+
+    // Resolve the components, but never pop the root, which is the drive
+    // for drive paths and the server and share for UNC paths.
+    let root = if prefix.ends_with("UNC\\") { 2 } else { 1 };
+    let mut components: Vec<&str> = Vec::new();
+    for v in rest.split('\\') {
+        match v {
+            "" | "." if components.len() >= root => {},
+            ".." if components.len() >= root => {
+                if components.len() > root {
+                    components.pop();
+                }
+            },
+            v => components.push(v),
+        }
+    }
+
+    Some(format!("{}{}", prefix, components.join("\\")))
+}
+
+// Yield the path to use for file system operations on `path`. On Windows,
+// this applies the extended-length prefix to paths exceeding the length
+// limit. Relative paths are made absolute first, since the prefix is only
+// valid on absolute paths. On all other platforms, this is an identity
+// function.
+fn long_path(path: &std::path::Path) -> std::borrow::Cow<'_, std::path::Path> {
+    #[cfg(windows)]
+    {
+        let abs = if path.is_absolute() {
+            Some(path.to_path_buf())
+        } else {
+            std::env::current_dir().ok().map(|v| v.join(path))
+        };
+
+        if let Some(v) = abs.as_ref()
+            .and_then(|v| v.to_str())
+            .and_then(windows_long_path)
+        {
+            return std::borrow::Cow::Owned(v.into());
+        }
+    }
+
+    std::borrow::Cow::Borrowed(path)
 }
 
 /// ## Enumerate Directory Recursively
@@ -146,7 +557,7 @@ pub fn lsrdir(path: &std::path::Path) -> Result<Vec<std::path::PathBuf>, ErrorFi
 /// This is a wrapper around `std::fs::create_dir_all()` that properly
 /// converts failures into the local error domain.
 pub fn mkdir(path: &std::path::Path) -> Result<(), ErrorFileSystem> {
-    std::fs::create_dir_all(path).map_err(
+    std::fs::create_dir_all(long_path(path)).map_err(
         |io| ErrorFileSystem::DirectoryCreation { path: path.into(), io },
     )?;
 
@@ -162,8 +573,10 @@ pub fn mkdir(path: &std::path::Path) -> Result<(), ErrorFileSystem> {
 /// system. Note that this is only checked once, and thus may still fail
 /// when another removal runs in parallel.
 pub fn rmdir(path: &std::path::Path) -> Result<(), ErrorFileSystem> {
-    if path.exists() {
-        std::fs::remove_dir_all(path).map_err(
+    let fs_path = long_path(path);
+
+    if fs_path.exists() {
+        std::fs::remove_dir_all(&fs_path).map_err(
             |io| ErrorFileSystem::DirectoryRemoval { path: path.into(), io },
         )?;
     }
@@ -175,17 +588,58 @@ pub fn rmdir(path: &std::path::Path) -> Result<(), ErrorFileSystem> {
 ///
 /// This is a wrapper around `std::fs::copy()` that converts errors into
 /// the local error domain.
+///
+/// Artifacts are always placed into bundles as copies rather than links.
+/// Bundles are code-signed after assembly, which rewrites the placed
+/// binaries (and thus would rewrite the link targets in the build tree),
+/// and links do not survive archiving of bundles.
 pub fn copy_file(
     src: &std::path::Path,
     dst: &std::path::Path,
 ) -> Result<(), ErrorFileSystem> {
-    std::fs::copy(src, dst).map_err(
+    std::fs::copy(long_path(src), long_path(dst)).map_err(
         |io| ErrorFileSystem::FileCopy { from: src.into(), to: dst.into(), io },
     )?;
 
     Ok(())
 }
 
+/// ## Copy a file while hashing it
+///
+/// Copy the file at `src` to `dst` like `copy_file()`, but feed all copied
+/// bytes into a hash engine of the given backend and return the final hash.
+/// This avoids reading the file a second time when its content hash is
+/// needed (e.g., as cache key). Permissions of `src` are copied as well.
+pub fn copy_file_hashed<Backend: Default + lib::hash::Engine>(
+    src: &std::path::Path,
+    dst: &std::path::Path,
+) -> Result<Backend::Hash, ErrorFileSystem> {
+    let err = |io| ErrorFileSystem::FileCopy { from: src.into(), to: dst.into(), io };
+
+    let mut f_src = std::fs::File::open(long_path(src)).map_err(err)?;
+    let permissions = f_src.metadata().map_err(err)?.permissions();
+    let mut f_dst = std::fs::File::create(long_path(dst)).map_err(err)?;
+
+    let mut engine = Backend::default();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        let n = match <std::fs::File as std::io::Read>::read(&mut f_src, &mut buffer) {
+            Ok(0) => break,
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(err(e)),
+        };
+
+        engine.push(&buffer[..n]);
+        <std::fs::File as std::io::Write>::write_all(&mut f_dst, &buffer[..n]).map_err(err)?;
+    }
+
+    f_dst.set_permissions(permissions).map_err(err)?;
+
+    Ok(engine.finalize())
+}
+
 /// ## Update a file if required
 ///
 /// This writes the given content to the specified file, but only if the file
@@ -205,14 +659,15 @@ pub fn update_file(
     // If the desired content is an empty file, we have to know whether the
     // file existed before we open it. Otherwise, we might create it when
     // opening it, and then cannot tell if we actually did.
-    let maybe_new = content.is_empty() && !path.is_file();
+    let fs_path = long_path(path);
+    let maybe_new = content.is_empty() && !fs_path.is_file();
 
     // Open the file read+write and create it if it does not exist, yet.
     let mut f = std::fs::OpenOptions::new()
         .read(true)
         .write(true)
         .create(true)
-        .open(path)
+        .open(&fs_path)
         .map_err(
             |io| ErrorFileSystem::FileUpdate { path: path.into(), io },
         )?;
@@ -255,6 +710,274 @@ pub fn update_file(
     Ok(new)
 }
 
+// Try to acquire an exclusive OS file lock on the given file without
+// blocking. Returns `Ok(false)` if the lock is held elsewhere.
+#[cfg(unix)]
+fn lock_file_os(file: &std::fs::File) -> Result<bool, std::io::Error> {
+    const LOCK_EX: i32 = 0x2;
+    const LOCK_NB: i32 = 0x4;
+
+    extern "C" {
+        fn flock(fd: i32, operation: i32) -> i32;
+    }
+
+    let fd = std::os::fd::AsRawFd::as_raw_fd(file);
+
+    // SAFETY: The file-descriptor is valid for the lifetime of `file`.
+    if unsafe { flock(fd, LOCK_EX | LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+
+    let e = std::io::Error::last_os_error();
+    if e.kind() == std::io::ErrorKind::WouldBlock {
+        Ok(false)
+    } else {
+        Err(e)
+    }
+}
+
+// Try to acquire an exclusive OS file lock on the given file without
+// blocking. Returns `Ok(false)` if the lock is held elsewhere.
+#[cfg(windows)]
+fn lock_file_os(file: &std::fs::File) -> Result<bool, std::io::Error> {
+    use windows_sys::Win32;
+
+    let handle = std::os::windows::io::AsRawHandle::as_raw_handle(file) as isize;
+
+    // SAFETY: The handle is valid for the lifetime of `file`, and the
+    //         overlapped structure is valid for the duration of the call.
+    let r = unsafe {
+        let mut overlapped: Win32::System::IO::OVERLAPPED = core::mem::zeroed();
+        Win32::Storage::FileSystem::LockFileEx(
+            handle,
+            Win32::Storage::FileSystem::LOCKFILE_EXCLUSIVE_LOCK
+                | Win32::Storage::FileSystem::LOCKFILE_FAIL_IMMEDIATELY,
+            0,
+            u32::MAX,
+            u32::MAX,
+            &mut overlapped,
+        )
+    };
+    if r != 0 {
+        return Ok(true);
+    }
+
+    let e = std::io::Error::last_os_error();
+    if e.raw_os_error() == Some(Win32::Foundation::ERROR_LOCK_VIOLATION as i32) {
+        Ok(false)
+    } else {
+        Err(e)
+    }
+}
+
+// Check whether the process with the given PID is known to be gone. Without
+// a way to query processes, PID files are never considered stale.
+fn pid_is_stale(pid: u32) -> bool {
+    let proc = std::path::Path::new("/proc");
+    proc.is_dir() && !proc.join(pid.to_string()).exists()
+}
+
+// Acquire a PID-file as lock. This is used where no OS file locks are
+// available. The PID of the owner is stored in the file, and stale PID-files
+// of processes that no longer exist are replaced.
+fn lock_file_pid(path: &std::path::Path) -> Result<Option<std::fs::File>, std::io::Error> {
+    loop {
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut f) => {
+                std::io::Write::write_all(&mut f, std::process::id().to_string().as_bytes())?;
+                return Ok(Some(f));
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let pid = std::fs::read_to_string(path)?.trim().parse::<u32>().ok();
+                match pid {
+                    Some(v) if pid_is_stale(v) => std::fs::remove_file(path)?,
+                    _ => return Ok(None),
+                }
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// ## Lock Build Directory
+///
+/// Acquire an advisory lock on the specified directory, to prevent parallel
+/// operations from modifying the same intermediate files. The lock is
+/// represented by a lock file in the directory, and released when the
+/// returned object is dropped.
+///
+/// OS file locks (`flock(2)` or `LockFileEx()`) are used where available, so
+/// locks are released even if the owning process crashes. Otherwise, a
+/// PID-file is used, which is considered stale if its process is known to
+/// be gone.
+///
+/// Fails with `ErrorFileSystem::LockHeld` if the lock is held by another
+/// operation.
+pub fn lock_dir(dir: &std::path::Path) -> Result<Lock, ErrorFileSystem> {
+    let path = dir.join("osiris.lock");
+    let err = |io| ErrorFileSystem::LockAcquisition { path: path.clone(), io };
+
+    #[cfg(any(unix, windows))]
+    {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(err)?;
+
+        if !lock_file_os(&file).map_err(err)? {
+            return Err(ErrorFileSystem::LockHeld { path });
+        }
+
+        // Record the owner for diagnostics. The lock is tied to the file,
+        // so the content has no meaning to the lock itself.
+        file.set_len(0).map_err(err)?;
+        std::io::Write::write_all(&mut &file, std::process::id().to_string().as_bytes())
+            .map_err(err)?;
+
+        Ok(Lock { file: file, path: path, pidfile: false })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        match lock_file_pid(&path).map_err(err)? {
+            Some(file) => Ok(Lock { file: file, path: path, pidfile: true }),
+            None => Err(ErrorFileSystem::LockHeld { path }),
+        }
+    }
+}
+
+// Run each job of a batch on its own thread named after its context, and
+// wait for all of them to finish. Results are returned in the order of the
+// jobs. Jobs that cannot be spawned or that panic yield an error.
+fn parallel_batch<T, F>(batch: Vec<(String, F)>) -> Vec<Result<T, BuildError>>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    std::thread::scope(|scope| {
+        let mut handles = Vec::with_capacity(batch.len());
+
+        for (ctx, job) in batch {
+            let handle = std::thread::Builder::new()
+                .name(format!("osiris-build-{}", ctx))
+                .spawn_scoped(scope, job)
+                .map_err(|v| BuildError::Exec(format!("thread osiris-build-{}", ctx), v));
+            handles.push((ctx, handle));
+        }
+
+        // Join all threads before reporting any error, so no job outlives
+        // this call in an unknown state.
+        handles.into_iter().map(|(ctx, handle)| {
+            handle?.join().map_err(|_| BuildError::Panicked(ctx))
+        }).collect()
+    })
+}
+
+/// ## Run Jobs in Parallel
+///
+/// Run each job on its own thread, with at most `jobs` threads at a time,
+/// and wait for all of them to finish. Jobs are started in batches of
+/// `jobs`, and a value of 0 is treated as 1. Each job is given a context
+/// (e.g., the ABI it builds for), which names its thread
+/// `osiris-build-<context>`, so diagnostics of the panic handler can be
+/// attributed.
+///
+/// Results are returned in the order of the jobs. If any job fails, the
+/// error of the first failed job is returned instead. Errors are converted
+/// into `BuildError` on the calling thread, since it is not `Send`. A job
+/// that panics fails with `BuildError::Panicked` carrying its context,
+/// rather than tearing down the caller.
+pub fn parallel<T, E, F>(jobs: usize, tasks: Vec<(String, F)>) -> Result<Vec<T>, BuildError>
+where
+    T: Send,
+    E: Send + Into<BuildError>,
+    F: FnOnce() -> Result<T, E> + Send,
+{
+    let mut results = Vec::with_capacity(tasks.len());
+    let mut tasks = tasks.into_iter();
+
+    loop {
+        let batch: Vec<_> = tasks.by_ref().take(jobs.max(1)).collect();
+        if batch.is_empty() {
+            break;
+        }
+
+        results.extend(
+            parallel_batch(batch).into_iter()
+                .map(|v| v.and_then(|v| v.map_err(|v| v.into()))),
+        );
+    }
+
+    results.into_iter().collect()
+}
+
+// Escape all characters that cannot be embedded verbatim in XML. If `attr` is
+// set, this escapes for quoted attribute values, otherwise for character
+// data. The input is borrowed unmodified if nothing needs escaping.
+fn xml_escape(input: &str, attr: bool) -> std::borrow::Cow<'_, str> {
+    let escape = |c: char| -> Option<&'static str> {
+        match (c, attr) {
+            ('&', _) => Some("&amp;"),
+            ('<', _) => Some("&lt;"),
+            ('"', true) => Some("&quot;"),
+            ('\'', true) => Some("&apos;"),
+            // Literal whitespace in attributes is normalized by XML parsers,
+            // so use character references to retain it.
+            ('\t', true) => Some("&#9;"),
+            ('\n', true) => Some("&#10;"),
+            ('\r', true) => Some("&#13;"),
+            _ => None,
+        }
+    };
+
+    if !input.chars().any(|c| escape(c).is_some()) {
+        return std::borrow::Cow::Borrowed(input);
+    }
+
+    let mut v = String::with_capacity(input.len() + 16);
+
+    for c in input.chars() {
+        match escape(c) {
+            Some(e) => v.push_str(e),
+            None => v.push(c),
+        }
+    }
+
+    std::borrow::Cow::Owned(v)
+}
+
+/// ## Escape XML Attribute Values
+///
+/// Escape the input so it can be embedded in a quoted XML attribute value,
+/// regardless of whether single or double quotes are used. The input is
+/// returned unmodified if it contains no special characters.
+pub fn xml_escape_attr(input: &str) -> std::borrow::Cow<'_, str> {
+    xml_escape(input, true)
+}
+
+/// ## Escape XML Character Data
+///
+/// Escape the input so it can be embedded as character data in XML elements
+/// (e.g., `<string>...</string>` in property lists). The input is returned
+/// unmodified if it contains no special characters.
+pub fn xml_escape_text(input: &str) -> std::borrow::Cow<'_, str> {
+    xml_escape(input, false)
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // OS file locks are released with the file. The lock file is
+        // retained, since other operations might already have it opened.
+        // PID-files, on the other hand, must be removed to release them.
+        if self.pidfile {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
 impl<'ctx> Archive<'ctx> {
     fn path_for(
         config: &config::Config,
@@ -270,19 +993,19 @@ impl<'ctx> Archive<'ctx> {
     }
 
     /// Package the build artifacts of a platform integration into an archive
-    /// suitable for distribution.
+    /// suitable for distribution. Returns the final artifacts of the archive.
     pub fn run(
         &self,
-    ) -> Result<(), ArchiveError> {
+    ) -> Result<Output, ArchiveError> {
         // Create an artifact directory for the archive operation.
         let path_archive = Self::path_for(self.config, self.archive);
         mkdir(path_archive.as_path())?;
 
         // Provide the platform directory to the operation.
-        let path_platform = Build::path_for(self.config, self.platform);
+        let path_platform = Build::path_for(self.config, self.platform, None);
 
         // Invoke the platform-dependent handler
-        match self.archive.configuration {
+        let r = match self.archive.configuration {
             config::ConfigArchiveConfiguration::MacosPkg(ref v) => {
                 platform::macos::archive_pkg(
                     self,
@@ -291,19 +1014,47 @@ impl<'ctx> Archive<'ctx> {
                     &path_platform,
                 )
             },
+        };
+
+        // Any failure after cancellation is likely caused by it, so report
+        // the cancellation instead.
+        match r {
+            Err(_) if self.is_cancelled() => Err(ArchiveError::Cancelled),
+            v => v,
+        }
+    }
+
+    /// ## Check for cancellation
+    ///
+    /// Return `ArchiveError::Cancelled` if the cancellation token of this
+    /// operation was set. Meant to be called between phases.
+    pub fn check_cancel(&self) -> Result<(), ArchiveError> {
+        match self.is_cancelled() {
+            true => Err(ArchiveError::Cancelled),
+            false => Ok(()),
         }
     }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_some_and(|v| v.is_cancelled())
+    }
 }
 
 impl<'ctx> Build<'ctx> {
     fn path_for(
         config: &config::Config,
         platform: &config::ConfigPlatform,
+        variant: Option<&str>,
     ) -> std::path::PathBuf {
         let mut path_build = std::path::PathBuf::new();
 
         path_build.push(&config.path_target);
-        path_build.push("osiris/platform");
+        if let Some(v) = variant {
+            path_build.push("osiris/variant");
+            path_build.push(v);
+        } else {
+            path_build.push("osiris/platform");
+        }
         path_build.push(&platform.id_symbol);
 
         path_build
@@ -318,17 +1069,24 @@ impl<'ctx> Build<'ctx> {
     /// The target directory of the current crate is used to store any build
     /// artifacts. Hence, you likely want to call this through `cargo <external>`
     /// to ensure cargo integration is hooked up as expected.
+    ///
+    /// Returns the final artifacts of the build.
     pub fn build(
         &self,
-    ) -> Result<(), BuildError> {
+    ) -> Result<Output, BuildError> {
         // Create a build directory for all output artifacts of the build
         // process. Re-use the existing directory, if possible, to speed up
         // builds.
-        let path_build = Self::path_for(self.config, self.platform);
+        let path_build = Self::path_for(self.config, self.platform, self.variant);
         mkdir(&path_build)?;
 
+        // Prevent parallel builds from corrupting each others intermediate
+        // files. The lock is held until the build finished.
+        let _lock = lock_dir(&path_build)?;
+        self.check_cancel()?;
+
         // Invoke the platform-dependent handler
-        match self.platform.configuration {
+        let r = match self.platform.configuration {
             config::ConfigPlatformConfiguration::Android(ref v) => {
                 platform::android::build(
                     self,
@@ -336,6 +1094,13 @@ impl<'ctx> Build<'ctx> {
                     &path_build,
                 )
             },
+            config::ConfigPlatformConfiguration::Ios(ref v) => {
+                platform::ios::build(
+                    self,
+                    v,
+                    &path_build,
+                )
+            },
             config::ConfigPlatformConfiguration::Macos(ref v) => {
                 platform::macos::build(
                     self,
@@ -343,7 +1108,298 @@ impl<'ctx> Build<'ctx> {
                     &path_build,
                 )
             },
+        };
+
+        // Any failure after cancellation is likely caused by it (e.g., a
+        // killed child process), so report the cancellation instead.
+        match r {
+            Err(_) if self.is_cancelled() => Err(BuildError::Cancelled),
+            v => v,
+        }
+    }
+
+    /// ## Check for cancellation
+    ///
+    /// Return `BuildError::Cancelled` if the cancellation token of this
+    /// operation was set. Meant to be called between phases.
+    pub fn check_cancel(&self) -> Result<(), BuildError> {
+        match self.is_cancelled() {
+            true => Err(BuildError::Cancelled),
+            false => Ok(()),
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_some_and(|v| v.is_cancelled())
+    }
+
+    /// ## Emit platform manifest
+    ///
+    /// Generate the manifest of the platform integration of the specified
+    /// platform (e.g., `AndroidManifest.xml` or `Info.plist`) and write it
+    /// to the build directory, but skip all other build steps. No compilers
+    /// or SDK tools are invoked. This allows inspecting the generated
+    /// manifest without a full build.
+    ///
+    /// Returns the path to the generated manifest file.
+    pub fn emit_manifest(
+        &self,
+    ) -> Result<std::path::PathBuf, BuildError> {
+        let path_build = Self::path_for(self.config, self.platform, self.variant);
+        mkdir(&path_build)?;
+
+        let _lock = lock_dir(&path_build)?;
+
+        match self.platform.configuration {
+            config::ConfigPlatformConfiguration::Android(ref v) => {
+                platform::android::emit_manifest(
+                    self,
+                    v,
+                    &path_build,
+                )
+            },
+            config::ConfigPlatformConfiguration::Ios(ref v) => {
+                platform::ios::emit_manifest(
+                    self,
+                    v,
+                    &path_build,
+                )
+            },
+            config::ConfigPlatformConfiguration::Macos(ref v) => {
+                platform::macos::emit_manifest(
+                    self,
+                    v,
+                    &path_build,
+                )
+            },
+        }
+    }
+}
+
+impl Output {
+    /// ## Find artifact
+    ///
+    /// Return the path of the first artifact of the given kind, if any.
+    pub fn path(&self, kind: ArtifactKind) -> Option<&std::path::Path> {
+        self.artifacts.iter()
+            .find(|v| v.kind == kind)
+            .map(|v| v.path.as_path())
+    }
+}
+
+// Run tools of a build via the configured runner, or spawn them as child
+// processes subject to the cancellation token of the build.
+impl<'ctx> ToolRunner for Build<'ctx> {
+    fn run(
+        &self,
+        cmd: &mut std::process::Command,
+    ) -> Result<std::process::Output, std::io::Error> {
+        match self.runner {
+            Some(v) => v.run(cmd),
+            None => ProcessRunner { cancel: self.cancel, stderr: StderrMode::Inherit }.run(cmd),
+        }
+    }
+
+    fn diagnose(&self, rendered: &str) {
+        match self.runner {
+            Some(v) => v.diagnose(rendered),
+            None => ProcessRunner { cancel: self.cancel, stderr: StderrMode::Inherit }.diagnose(rendered),
+        }
+    }
+}
+
+// Run tools of an archive operation as child processes subject to the
+// cancellation token of the operation.
+impl<'ctx> ToolRunner for Archive<'ctx> {
+    fn run(
+        &self,
+        cmd: &mut std::process::Command,
+    ) -> Result<std::process::Output, std::io::Error> {
+        ProcessRunner { cancel: self.cancel, stderr: StderrMode::Inherit }.run(cmd)
+    }
+
+    fn diagnose(&self, rendered: &str) {
+        ProcessRunner { cancel: self.cancel, stderr: StderrMode::Inherit }.diagnose(rendered)
+    }
+}
+
+impl MatrixCell {
+    /// ## Derive Cargo arguments
+    ///
+    /// Return the Cargo arguments to build this cell with. These are the
+    /// given base arguments with the features of this cell appended.
+    pub fn arguments(&self, base: &cargo::Arguments) -> cargo::Arguments {
+        let mut v = base.clone();
+        v.features.extend(self.features.iter().cloned());
+        v
+    }
+
+    /// ## Derive build variant
+    ///
+    /// Return the build variant of this cell, naming its build directory.
+    /// It is derived from the set of features of the cell, regardless of
+    /// their order. All characters but ASCII alphanumerics, `-`, and `_`
+    /// are escaped as `%xx`, so distinct sets yield distinct variants.
+    /// Cells without features use the default build, and yield `None`.
+    pub fn variant(&self) -> Option<String> {
+        let mut features = self.features.iter()
+            .map(|v| {
+                v.bytes().map(|b| match b {
+                    b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' | b'-' | b'_' => {
+                        (b as char).to_string()
+                    },
+                    _ => format!("%{:02x}", b),
+                }).collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        features.sort();
+        features.dedup();
+
+        (!features.is_empty()).then(|| features.join(","))
+    }
+
+    // Label of the grid row of this cell.
+    fn row(&self) -> String {
+        match self.features.is_empty() {
+            true => "(default)".into(),
+            false => self.features.join(","),
+        }
+    }
+
+    // Label of the grid column of this cell.
+    fn column(&self) -> String {
+        match (&self.platform, &self.target) {
+            (Some(p), Some(t)) => format!("{}/{}", p, t),
+            (Some(p), None) => p.clone(),
+            (None, Some(t)) => t.clone(),
+            (None, None) => "(host)".into(),
+        }
+    }
+}
+
+// Parse a matrix cell from `<features>:<target>:<platform>`, where features
+// are comma-separated. Each part can be empty, and trailing parts can be
+// omitted.
+impl core::str::FromStr for MatrixCell {
+    type Err = ();
+
+    fn from_str(v: &str) -> Result<Self, Self::Err> {
+        let mut parts = v.split(':');
+        let features = parts.next().unwrap_or("");
+        let target = parts.next().unwrap_or("");
+        let platform = parts.next().unwrap_or("");
+
+        if parts.next().is_some() {
+            return Err(());
+        }
+
+        Ok(Self {
+            features: features.split(',')
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string())
+                .collect(),
+            target: (!target.is_empty()).then(|| target.to_string()),
+            platform: (!platform.is_empty()).then(|| platform.to_string()),
+        })
+    }
+}
+
+impl<'ctx> Matrix<'ctx> {
+    /// ## Run all cells
+    ///
+    /// Invoke `build` for each cell, passing the Cargo arguments derived for
+    /// the cell. Return the outcome of each cell in order. Cells are built
+    /// in batches of `jobs` cells in parallel, like `parallel()` does, and
+    /// a panicking build fails its cell. Unless `keep_going` is set, all
+    /// batches after the first failure are skipped. Once the cancellation
+    /// token is set, all remaining batches are skipped.
+    pub fn run<F>(&self, build: F) -> Vec<MatrixOutcome>
+    where
+        F: Fn(&MatrixCell, &cargo::Arguments) -> Result<(), String> + Sync,
+    {
+        let mut outcomes = Vec::with_capacity(self.cells.len());
+        let mut failed = false;
+
+        for batch in self.cells.chunks(self.jobs.max(1)) {
+            if (failed && !self.keep_going) || self.cancel.is_some_and(|v| v.is_cancelled()) {
+                outcomes.extend(batch.iter().map(|_| MatrixOutcome::Skipped));
+                continue;
+            }
+
+            let build = &build;
+            let tasks = batch.iter().map(|cell| {
+                let arguments = cell.arguments(self.cargo_arguments);
+                (cell.column(), move || build(cell, &arguments))
+            }).collect();
+
+            for r in parallel_batch(tasks) {
+                outcomes.push(match r {
+                    Ok(Ok(())) => MatrixOutcome::Pass,
+                    Ok(Err(e)) => MatrixOutcome::Fail(e),
+                    Err(e) => MatrixOutcome::Fail(e.to_string()),
+                });
+            }
+
+            failed |= outcomes.iter().any(|v| matches!(v, MatrixOutcome::Fail(_)));
         }
+
+        outcomes
+    }
+
+    /// ## Render outcome grid
+    ///
+    /// Render the outcomes of all cells as a grid with one row per feature
+    /// set and one column per platform and target. Combinations that are
+    /// not part of the matrix are shown as `-`.
+    pub fn grid(&self, outcomes: &[MatrixOutcome]) -> String {
+        let mut rows: Vec<String> = Vec::new();
+        let mut columns: Vec<String> = Vec::new();
+
+        for cell in self.cells {
+            let (row, column) = (cell.row(), cell.column());
+            if !rows.contains(&row) {
+                rows.push(row);
+            }
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+
+        let mut table = vec![
+            core::iter::once("features".to_string())
+                .chain(columns.iter().cloned())
+                .collect::<Vec<_>>(),
+        ];
+        for row in rows.iter() {
+            let mut line = vec![row.clone()];
+            for column in columns.iter() {
+                let outcome = self.cells.iter().zip(outcomes.iter()).find(
+                    |(cell, _)| cell.row() == *row && cell.column() == *column,
+                ).map(|v| v.1);
+                line.push(match outcome {
+                    Some(MatrixOutcome::Pass) => "pass".into(),
+                    Some(MatrixOutcome::Fail(_)) => "FAIL".into(),
+                    Some(MatrixOutcome::Skipped) => "skip".into(),
+                    None => "-".into(),
+                });
+            }
+            table.push(line);
+        }
+
+        let widths = (0..=columns.len()).map(
+            |i| table.iter().map(|v| v[i].chars().count()).max().unwrap_or(0),
+        ).collect::<Vec<_>>();
+
+        let mut acc = String::new();
+        for line in table.iter() {
+            let cells = line.iter().zip(widths.iter()).map(
+                |(v, w)| format!("{:<w$}", v, w = w),
+            ).collect::<Vec<_>>();
+            acc += cells.join(" | ").trim_end();
+            acc += "\n";
+        }
+
+        acc
     }
 }
 
@@ -381,6 +1437,12 @@ impl From<ErrorFileSystem> for ArchiveError {
     }
 }
 
+impl From<platform::macos::ErrorNotary> for ArchiveError {
+    fn from(v: platform::macos::ErrorNotary) -> Self {
+        Self::MacosPlatform(v)
+    }
+}
+
 impl From<ErrorProcess> for ArchiveError {
     fn from(v: ErrorProcess) -> Self {
         Self::Process(v)
@@ -417,6 +1479,12 @@ impl From<platform::android::BuildError> for BuildError {
     }
 }
 
+impl From<platform::ios::ErrorBuild> for BuildError {
+    fn from(v: platform::ios::ErrorBuild) -> Self {
+        Self::IosPlatform(v)
+    }
+}
+
 impl From<platform::macos::ErrorBuild> for BuildError {
     fn from(v: platform::macos::ErrorBuild) -> Self {
         Self::MacosPlatform(v)
@@ -429,8 +1497,11 @@ impl core::fmt::Display for ErrorFileSystem {
             ErrorFileSystem::DirectoryTraversal { path, io } => fmt.write_fmt(core::format_args!("Cannot traverse directory ({}): {}", path.to_string_lossy(), io)),
             ErrorFileSystem::DirectoryCreation { path, io } => fmt.write_fmt(core::format_args!("Cannot create directory ({}): {}", path.to_string_lossy(), io)),
             ErrorFileSystem::DirectoryRemoval { path, io } => fmt.write_fmt(core::format_args!("Cannot remove directory ({}): {}", path.to_string_lossy(), io)),
+            ErrorFileSystem::FileRead { path, io } => fmt.write_fmt(core::format_args!("Cannot read file ({}): {}", path.to_string_lossy(), io)),
             ErrorFileSystem::FileUpdate { path, io } => fmt.write_fmt(core::format_args!("Cannot update file ({}): {}", path.to_string_lossy(), io)),
             ErrorFileSystem::FileCopy { from, to, io } => fmt.write_fmt(core::format_args!("Cannot copy file ({} -> {}): {}", from.to_string_lossy(), to.to_string_lossy(), io)),
+            ErrorFileSystem::LockAcquisition { path, io } => fmt.write_fmt(core::format_args!("Cannot acquire lock file ({}): {}", path.to_string_lossy(), io)),
+            ErrorFileSystem::LockHeld { path } => fmt.write_fmt(core::format_args!("Another build is in progress, lock file is held: {}", path.to_string_lossy())),
         }
     }
 }
@@ -450,6 +1521,8 @@ impl core::fmt::Display for ArchiveError {
             ArchiveError::Uncaught(e) => fmt.write_fmt(core::format_args!("Uncaught failure: {}", e)),
             ArchiveError::FileSystem(e) => fmt.write_fmt(core::format_args!("File system failure: {}", e)),
             ArchiveError::Process(e) => fmt.write_fmt(core::format_args!("Process failure: {}", e)),
+            ArchiveError::Cancelled => fmt.write_fmt(core::format_args!("Operation was cancelled")),
+            ArchiveError::MacosPlatform(e) => fmt.write_fmt(core::format_args!("macOS archive failed: {}", e)),
         }
     }
 }
@@ -464,7 +1537,565 @@ impl core::fmt::Display for BuildError {
             BuildError::Exit(tool, e) => fmt.write_fmt(core::format_args!("Execution of {} failed: {}", tool, e)),
             BuildError::Cargo(e) => fmt.write_fmt(core::format_args!("Cargo execution failed: {}", e)),
             BuildError::AndroidPlatform(e) => fmt.write_fmt(core::format_args!("Android build failed: {}", e)),
+            BuildError::IosPlatform(e) => fmt.write_fmt(core::format_args!("iOS build failed: {}", e)),
             BuildError::MacosPlatform(e) => fmt.write_fmt(core::format_args!("macOS build failed: {}", e)),
+            BuildError::Cancelled => fmt.write_fmt(core::format_args!("Operation was cancelled")),
+            BuildError::Panicked(ctx) => fmt.write_fmt(core::format_args!("Build worker for {} panicked", ctx)),
         }
     }
 }
+
+// Tool runner that records all invocations and reports canned results,
+// so pipelines can be tested without spawning any tools. Invocations are
+// recorded as file name of the program followed by its arguments, and the
+// standard output of each invocation is provided by `stdout`. Invocations
+// of the tool selected by `fail` exit with code 1 and report a failure on
+// standard error, and diagnostics are recorded as well.
+#[cfg(test)]
+pub(crate) struct FakeRunner<Stdout> {
+    pub(crate) calls: core::cell::RefCell<Vec<Vec<String>>>,
+    pub(crate) diagnostics: core::cell::RefCell<Vec<String>>,
+    pub(crate) fail: Option<&'static str>,
+    pub(crate) stdout: Stdout,
+}
+
+#[cfg(test)]
+impl<Stdout: Fn(&[String]) -> String> FakeRunner<Stdout> {
+    pub(crate) fn new(stdout: Stdout) -> Self {
+        Self {
+            calls: Default::default(),
+            diagnostics: Default::default(),
+            fail: None,
+            stdout: stdout,
+        }
+    }
+
+    pub(crate) fn with_fail(mut self, tool: &'static str) -> Self {
+        self.fail = Some(tool);
+        self
+    }
+
+    // Yield the file names of all invoked programs.
+    pub(crate) fn tools(&self) -> Vec<String> {
+        self.calls.borrow().iter().map(|v| v[0].clone()).collect()
+    }
+}
+
+#[cfg(test)]
+impl<Stdout: Fn(&[String]) -> String> ToolRunner for FakeRunner<Stdout> {
+    fn run(
+        &self,
+        cmd: &mut std::process::Command,
+    ) -> Result<std::process::Output, std::io::Error> {
+        #[cfg(unix)]
+        use std::os::unix::process::ExitStatusExt;
+        #[cfg(windows)]
+        use std::os::windows::process::ExitStatusExt;
+
+        // Unix encodes the exit code in the upper byte of the wait status,
+        // while Windows reports the exit code verbatim.
+        let code_fail = if cfg!(unix) { 1 << 8 } else { 1 };
+
+        let name = std::path::Path::new(cmd.get_program())
+            .file_name()
+            .map_or_else(String::new, |v| v.to_string_lossy().into_owned());
+        let call: Vec<String> = [name.clone()].into_iter()
+            .chain(cmd.get_args().map(|v| v.to_string_lossy().into_owned()))
+            .collect();
+        let stdout = (self.stdout)(&call);
+        let fail = self.fail == Some(name.as_str());
+        self.calls.borrow_mut().push(call);
+
+        Ok(std::process::Output {
+            status: std::process::ExitStatus::from_raw(if fail { code_fail } else { 0 }),
+            stdout: stdout.into_bytes(),
+            stderr: match fail {
+                true => format!("{} failed\n", name).into_bytes(),
+                false => Vec::new(),
+            },
+        })
+    }
+
+    fn diagnose(&self, rendered: &str) {
+        self.diagnostics.borrow_mut().push(rendered.into());
+    }
+}
+
+// Temporary directory named after the test and the current process, so
+// concurrent test runs cannot collide. Stale content of previous runs is
+// cleared on creation, and the directory is removed when dropped.
+#[cfg(test)]
+pub(crate) struct TestDir(pub(crate) std::path::PathBuf);
+
+#[cfg(test)]
+impl TestDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(
+            format!("osiris-test-{}-{}", name, std::process::id()),
+        );
+        rmdir(&path).unwrap();
+        mkdir(&path).unwrap();
+        Self(path)
+    }
+}
+
+#[cfg(test)]
+impl core::ops::Deref for TestDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = rmdir(&self.0);
+    }
+}
+
+// Run `f` with a build operation of the platform `id`, configured from
+// the given metadata and arguments. Tool invocations go to `runner`, and
+// configuration failures are returned to the caller.
+#[cfg(test)]
+pub(crate) fn with_build<R>(
+    arguments: &cargo::Arguments,
+    metadata: &cargo::Metadata,
+    id: &str,
+    runner: Option<&dyn ToolRunner>,
+    f: impl FnOnce(Build<'_>) -> R,
+) -> Result<R, config::Error> {
+    let config = config::Config::from_cargo(arguments, metadata)?;
+
+    Ok(f(Build {
+        cargo_arguments: arguments,
+        cargo_metadata: metadata,
+        config: &config,
+        platform: &config.platforms[id],
+        verbose: false,
+        cancel: None,
+        runner: runner,
+        variant: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify that a held directory lock rejects further locks, and is
+    // released when dropped.
+    #[test]
+    fn lock_held() {
+        let dir = TestDir::new("lock");
+
+        let lock = lock_dir(&dir).unwrap();
+        assert!(matches!(lock_dir(&dir), Err(ErrorFileSystem::LockHeld { .. })));
+        drop(lock);
+
+        let lock = lock_dir(&dir).unwrap();
+        drop(lock);
+    }
+
+    // Verify that hashed copies yield the same hash as hashing the file
+    // content independently, and report missing sources.
+    #[test]
+    fn copy_hashed() {
+        use lib::hash::sha256;
+
+        let dir = TestDir::new("copy-hashed");
+        let src = dir.join("src");
+        let dst = dir.join("dst");
+
+        // Span multiple read buffers with a non-repeating pattern.
+        let content: Vec<u8> = (0..200_000u32).map(|v| (v % 251) as u8).collect();
+        std::fs::write(&src, &content).unwrap();
+        std::fs::write(&dst, "stale content").unwrap();
+
+        let hash = copy_file_hashed::<sha256::Engine>(&src, &dst).unwrap();
+        assert_eq!(std::fs::read(&dst).unwrap(), content);
+        assert_eq!(hash.as_bytes(), lib::hash::hash::<sha256::Engine>(&content).as_bytes());
+
+        std::fs::write(&src, "").unwrap();
+        let hash = copy_file_hashed::<sha256::Engine>(&src, &dst).unwrap();
+        assert_eq!(std::fs::read(&dst).unwrap(), b"");
+        assert_eq!(hash.as_bytes(), lib::hash::hash::<sha256::Engine>(b"").as_bytes());
+
+        assert!(matches!(
+            copy_file_hashed::<sha256::Engine>(&dir.join("missing"), &dst),
+            Err(ErrorFileSystem::FileCopy { .. }),
+        ));
+    }
+
+    // Verify that PID-files of gone processes are considered stale, while
+    // the PID-file of a running process is respected.
+    #[test]
+    fn lock_pidfile() {
+        let dir = TestDir::new("pidfile");
+        let path = dir.join("osiris.lock");
+
+        let file = lock_file_pid(&path).unwrap();
+        assert!(file.is_some());
+        assert!(lock_file_pid(&path).unwrap().is_none());
+
+        if std::path::Path::new("/proc").is_dir() {
+            std::fs::write(&path, format!("{}", u32::MAX)).unwrap();
+            assert!(lock_file_pid(&path).unwrap().is_some());
+        }
+    }
+
+    // Verify that over-long Windows paths get the extended-length prefix
+    // applied, with separators and dot-components normalized.
+    #[test]
+    fn windows_long_paths() {
+        let long = "a".repeat(WINDOWS_MAX_PATH);
+
+        // Short, relative, or already prefixed paths are left untouched.
+        assert_eq!(windows_long_path("C:\\foo\\bar"), None);
+        assert_eq!(windows_long_path(&format!("foo\\{}", long)), None);
+        assert_eq!(windows_long_path(&format!("\\\\?\\C:\\{}", long)), None);
+
+        assert_eq!(
+            windows_long_path(&format!("C:\\foo/./bar\\..//{}", long)),
+            Some(format!("\\\\?\\C:\\foo\\{}", long)),
+        );
+        assert_eq!(
+            windows_long_path(&format!("C:/../{}", long)),
+            Some(format!("\\\\?\\C:\\{}", long)),
+        );
+        assert_eq!(
+            windows_long_path(&format!("\\\\server\\share\\..\\{}", long)),
+            Some(format!("\\\\?\\UNC\\server\\share\\{}", long)),
+        );
+    }
+
+    // Create metadata of an Android application building into `dir`.
+    fn android_metadata(dir: &std::path::Path) -> cargo::Metadata {
+        cargo::Metadata {
+            target_directory: dir.to_str().unwrap().into(),
+            ..cargo::Metadata::from_osiris_json(&serde_json::json!({
+                "platforms": [
+                    {
+                        "id": "android",
+                        "android": {
+                            "namespace": "com.example",
+                            "min-sdk": 31,
+                        },
+                    },
+                ],
+            })).unwrap()
+        }
+    }
+
+    // Verify that emitting the manifest only writes the manifest file, but
+    // does not run any other build step (which would require the SDK and
+    // produce further build directories).
+    #[test]
+    fn emit_manifest_only() {
+        let dir = TestDir::new("manifest");
+        let metadata = android_metadata(&dir);
+
+        with_build(&Default::default(), &metadata, "android", None, |build| {
+            let path = build.emit_manifest().unwrap_or_else(|e| panic!("{}", e));
+            assert_eq!(path.file_name().unwrap(), "AndroidManifest.xml");
+            assert!(std::fs::read_to_string(&path).unwrap().contains("<manifest"));
+
+            // Only the artifact directory must have been created, besides
+            // the lock file of the build directory.
+            let path_build = Build::path_for(build.config, build.platform, None);
+            let mut entries = std::fs::read_dir(&path_build).unwrap()
+                .map(|v| v.unwrap().file_name())
+                .filter(|v| v != "osiris.lock")
+                .collect::<Vec<_>>();
+            entries.sort();
+            assert_eq!(entries, ["artifacts"]);
+        }).unwrap();
+    }
+
+    // Verify that a cancelled token stops the build pipeline after the
+    // build directory was prepared, and reports the cancellation.
+    #[test]
+    fn build_cancelled() {
+        let dir = TestDir::new("cancel");
+        let metadata = android_metadata(&dir);
+        let cancel = CancelToken::new();
+
+        with_build(&Default::default(), &metadata, "android", None, |build| {
+            let build = Build { cancel: Some(&cancel), ..build };
+
+            assert!(build.check_cancel().is_ok());
+            cancel.cancel();
+            assert!(matches!(build.check_cancel(), Err(BuildError::Cancelled)));
+            assert!(matches!(build.build(), Err(BuildError::Cancelled)));
+
+            // The build directory was prepared, but no platform phase ran.
+            let path_build = Build::path_for(build.config, build.platform, None);
+            assert!(path_build.is_dir());
+            assert!(!path_build.join("artifacts").exists());
+        }).unwrap();
+    }
+
+    // Verify that cancelling a token kills running child processes.
+    #[cfg(unix)]
+    #[test]
+    fn cancel_output() {
+        let cancel = std::sync::Arc::new(CancelToken::new());
+
+        let v = cancel.output(std::process::Command::new("echo").arg("foo")).unwrap();
+        assert!(v.status.success());
+        assert_eq!(v.stdout, b"foo\n");
+
+        let thread_cancel = cancel.clone();
+        let thread = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            thread_cancel.cancel();
+        });
+
+        let start = std::time::Instant::now();
+        let e = cancel.output(std::process::Command::new("sleep").arg("30")).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::Interrupted);
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+
+        thread.join().unwrap();
+    }
+
+    // Verify that captured standard error of failing tools is reduced to its
+    // last lines and reported with the exit condition, with and without
+    // cancellation token.
+    #[cfg(unix)]
+    #[test]
+    fn stderr_excerpt() {
+        assert_eq!(tail_lines(b"a\nb\nc\n", 2), b"b\nc\n");
+        assert_eq!(tail_lines(b"a\nb\nc", 2), b"b\nc");
+        assert_eq!(tail_lines(b"a\nb\n", 5), b"a\nb\n");
+        assert_eq!(tail_lines(b"a\nb\n", 0), b"");
+
+        let cancel = CancelToken::new();
+        for o_cancel in [None, Some(&cancel)] {
+            let mut cmd = std::process::Command::new("sh");
+            cmd.args(["-c", "echo out; printf 'one\\ntwo\\nthree\\n' >&2; exit 3"]);
+            cmd.stderr(std::process::Stdio::inherit());
+
+            let runner = ProcessRunner { cancel: o_cancel, stderr: StderrMode::Capture(2) };
+            let output = runner.run(&mut cmd).unwrap();
+            assert!(!output.status.success());
+            assert_eq!(output.stdout, b"out\n");
+            assert_eq!(output.stderr, b"two\nthree\n");
+
+            let e = BuildError::Exit("javac".into(), (&output).into());
+            assert_eq!(
+                e.to_string(),
+                "Execution of javac failed: exit status: 3, with standard error ending in:\ntwo\nthree",
+            );
+        }
+
+        // Without capturing, no excerpt is reported.
+        let runner = ProcessRunner { cancel: Some(&cancel), stderr: StderrMode::Inherit };
+        let output = runner.run(std::process::Command::new("false").stderr(std::process::Stdio::null())).unwrap();
+        let exit = ToolExit::from(&output);
+        assert!(exit.excerpt.is_none());
+        assert_eq!(exit.to_string(), "exit status: 1");
+    }
+
+    // Verify escaping of XML character data.
+    #[test]
+    fn xml_text() {
+        assert_eq!(xml_escape_text(""), "");
+        assert_eq!(xml_escape_text("foobar"), "foobar");
+        assert_eq!(xml_escape_text("foo & bar"), "foo &amp; bar");
+        assert_eq!(xml_escape_text("<foobar>"), "&lt;foobar>");
+        assert_eq!(xml_escape_text("<&>"), "&lt;&amp;>");
+        assert_eq!(xml_escape_text("\"'\t\n"), "\"'\t\n");
+
+        assert!(matches!(xml_escape_text("foobar"), std::borrow::Cow::Borrowed(_)));
+    }
+
+    // Verify escaping of XML attribute values.
+    #[test]
+    fn xml_attr() {
+        assert_eq!(xml_escape_attr(""), "");
+        assert_eq!(xml_escape_attr("foobar"), "foobar");
+        assert_eq!(xml_escape_attr("foo & bar"), "foo &amp; bar");
+        assert_eq!(xml_escape_attr("<foobar>"), "&lt;foobar>");
+        assert_eq!(xml_escape_attr("\"foo\""), "&quot;foo&quot;");
+        assert_eq!(xml_escape_attr("'foo'"), "&apos;foo&apos;");
+        assert_eq!(xml_escape_attr("a\tb\nc\rd"), "a&#9;b&#10;c&#13;d");
+
+        assert!(matches!(xml_escape_attr("foobar"), std::borrow::Cow::Borrowed(_)));
+    }
+
+    // Verify a 2x2 matrix runs all cells with the derived arguments, and
+    // renders the outcomes as grid, skipping cells after failures unless
+    // asked to keep going.
+    #[test]
+    fn matrix_grid() {
+        let cells = [
+            "::android".parse::<MatrixCell>().unwrap(),
+            ":x86_64-unknown-linux-gnu".parse::<MatrixCell>().unwrap(),
+            "foo,bar::android".parse::<MatrixCell>().unwrap(),
+            "foo,bar:x86_64-unknown-linux-gnu:".parse::<MatrixCell>().unwrap(),
+        ];
+        assert_eq!(
+            cells[2],
+            MatrixCell {
+                features: vec!["foo".into(), "bar".into()],
+                target: None,
+                platform: Some("android".into()),
+            },
+        );
+        assert!("a:b:c:d".parse::<MatrixCell>().is_err());
+
+        let arguments = cargo::Arguments {
+            features: vec!["base".into()],
+            ..Default::default()
+        };
+        let mut matrix = Matrix {
+            cargo_arguments: &arguments,
+            cells: &cells,
+            jobs: 1,
+            keep_going: true,
+            cancel: None,
+        };
+
+        // Stub build that fails for the `foo` feature on the host.
+        let seen = std::sync::Mutex::new(Vec::new());
+        let build = |cell: &MatrixCell, args: &cargo::Arguments| {
+            seen.lock().unwrap().push(args.features.join(","));
+            match (cell.target.is_some(), args.features.iter().any(|v| v == "foo")) {
+                (true, true) => Err("stub failure".to_string()),
+                _ => Ok(()),
+            }
+        };
+
+        let outcomes = matrix.run(build);
+        assert_eq!(outcomes[3], MatrixOutcome::Fail("stub failure".into()));
+        assert_eq!(
+            matrix.grid(&outcomes),
+            concat!(
+                "features  | android | x86_64-unknown-linux-gnu\n",
+                "(default) | pass    | pass\n",
+                "foo,bar   | pass    | FAIL\n",
+            ),
+        );
+
+        // Without `keep_going`, cells after a failure are skipped.
+        let cells = [cells[3].clone(), cells[0].clone()];
+        matrix.cells = &cells;
+        matrix.keep_going = false;
+        let outcomes = matrix.run(build);
+        assert_eq!(
+            matrix.grid(&outcomes),
+            concat!(
+                "features  | x86_64-unknown-linux-gnu | android\n",
+                "foo,bar   | FAIL                     | -\n",
+                "(default) | -                        | skip\n",
+            ),
+        );
+        assert_eq!(
+            *seen.lock().unwrap(),
+            ["base", "base", "base,foo,bar", "base,foo,bar", "base,foo,bar"],
+        );
+
+        // Cells of the same batch are built even after a failure, and
+        // panics fail their cell.
+        matrix.jobs = 2;
+        let outcomes = matrix.run(|cell, args| {
+            match cell.target.is_some() {
+                true => build(cell, args),
+                false => panic!("stub panic"),
+            }
+        });
+        assert_eq!(
+            outcomes,
+            [
+                MatrixOutcome::Fail("stub failure".into()),
+                MatrixOutcome::Fail("Build worker for android panicked".into()),
+            ],
+        );
+    }
+
+    // Verify that cells of the same platform use separate build directories
+    // per set of features, so parallel builds of them do not contend for
+    // the build lock.
+    #[test]
+    fn matrix_variants() {
+        let dir = TestDir::new("matrix");
+        let metadata = android_metadata(&dir);
+
+        let variant = |v: &str| v.parse::<MatrixCell>().unwrap().variant();
+        assert_eq!(variant("::android"), None);
+        assert_eq!(variant("foo,bar::android").as_deref(), Some("bar,foo"));
+        assert_eq!(variant("bar,foo,bar::android").as_deref(), Some("bar,foo"));
+        assert_eq!(variant("dep/foo::android").as_deref(), Some("dep%2ffoo"));
+
+        let cells = [
+            "::android".parse::<MatrixCell>().unwrap(),
+            "foo::android".parse::<MatrixCell>().unwrap(),
+        ];
+        let arguments = Default::default();
+        let matrix = Matrix {
+            cargo_arguments: &arguments,
+            cells: &cells,
+            jobs: 2,
+            keep_going: false,
+            cancel: None,
+        };
+
+        // Both cells hold the lock of their build directory at the same
+        // time, which fails if they share the directory.
+        let barrier = std::sync::Barrier::new(2);
+        let outcomes = matrix.run(|cell, args| {
+            with_build(args, &metadata, "android", None, |build| {
+                let variant = cell.variant();
+                let build = Build { variant: variant.as_deref(), ..build };
+                let path_build = Build::path_for(build.config, build.platform, build.variant);
+                mkdir(&path_build).unwrap();
+
+                let lock = lock_dir(&path_build);
+                barrier.wait();
+                lock.map(|_| ()).map_err(|e| e.to_string())
+            }).unwrap()
+        });
+        assert_eq!(outcomes, [MatrixOutcome::Pass, MatrixOutcome::Pass]);
+        assert!(dir.join("osiris/platform/android").is_dir());
+        assert!(dir.join("osiris/variant/foo/android").is_dir());
+    }
+
+    // Verify that parallel jobs run on named threads, never exceed the
+    // number of jobs, and that a panicking job surfaces as
+    // `BuildError::Panicked` with its context.
+    #[test]
+    fn parallel_panic() {
+        let job = |abi: &str, panic: bool| -> (String, Box<dyn FnOnce() -> Result<String, cargo::Error> + Send>) {
+            (abi.into(), Box::new(move || {
+                if panic {
+                    panic!("stub panic");
+                }
+                Ok(std::thread::current().name().unwrap().into())
+            }))
+        };
+
+        let v = parallel(2, vec![job("x86_64", false), job("arm64-v8a", false)]).ok().unwrap();
+        assert_eq!(v, ["osiris-build-x86_64", "osiris-build-arm64-v8a"]);
+
+        let r = parallel(2, vec![job("x86_64", false), job("arm64-v8a", true)]);
+        assert!(matches!(r, Err(BuildError::Panicked(v)) if v == "arm64-v8a"));
+
+        // Count the jobs running at the same time.
+        let running = std::sync::atomic::AtomicUsize::new(0);
+        let peak = std::sync::atomic::AtomicUsize::new(0);
+        let tasks = (0..5).map(|i| {
+            let job = || -> Result<(), cargo::Error> {
+                let n = running.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                peak.fetch_max(n, std::sync::atomic::Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                running.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            };
+            (i.to_string(), job)
+        }).collect();
+        assert_eq!(parallel(2, tasks).ok().unwrap().len(), 5);
+        assert!(peak.load(std::sync::atomic::Ordering::SeqCst) <= 2);
+    }
+}
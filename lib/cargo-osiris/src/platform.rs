@@ -5,4 +5,5 @@
 //! dependent toolchains.
 
 pub mod android;
+pub mod ios;
 pub mod macos;
@@ -4,7 +4,7 @@
 //! supports direct builds via the Android SDK, or following the official
 //! Gradle build system.
 
-use crate::{cargo, config, lib, op};
+use crate::{cargo, config, doctor, lib, op};
 use std::collections::BTreeMap;
 
 mod apk;
@@ -13,7 +13,22 @@ mod flatres;
 mod java;
 mod keystore;
 mod kotlin;
+mod manifest;
 mod sdk;
+mod sdkmanager;
+mod zip;
+
+/// Environment variable carrying the JNI symbol of the entry-point called by
+/// a generated main activity. It is set for all Cargo builds of the native
+/// library if a main activity is generated. The library must export an
+/// `extern "system" fn(*mut JNIEnv, jobject)` under this name, for example
+/// via `#[export_name = env!("OSIRIS_ANDROID_MAIN_SYMBOL")]`.
+pub const ENV_MAIN_SYMBOL: &str = "OSIRIS_ANDROID_MAIN_SYMBOL";
+
+// Class and method of the JNI entry-point of a generated main activity,
+// with the class relative to the namespace.
+const MAIN_CLASS: &str = "MainActivity";
+const MAIN_METHOD: &str = "osirisMain";
 
 /// ## Android Platform Build Errors
 ///
@@ -28,8 +43,9 @@ pub enum BuildError {
     UnsupportedHost,
     /// Unsupported target ABI for the Android platform.
     UnsupportedAbi(String),
-    /// No Android SDK available, `ANDROID_HOME` is not set.
-    NoAndroidHome,
+    /// No Android SDK available, `ANDROID_HOME` is not set. Carries the
+    /// API-level of the platform required by the build.
+    NoAndroidHome(u32),
     /// No Android SDK available at the selected location.
     NoSdk(std::path::PathBuf),
     /// Invalid Android SDK at the selected location.
@@ -42,12 +58,16 @@ pub enum BuildError {
     NoKdk(std::path::PathBuf),
     /// Invalid Android Kotlin SDK at the selected location.
     InvalidKdk(std::path::PathBuf),
-    /// No NDK available in the selected Android SDK.
-    NoNdk,
+    /// No NDK available in the selected Android SDK. Carries the requested
+    /// version, if any.
+    NoNdk(Option<std::ffi::OsString>),
     /// Invalid NDK with the selected version in the Android SDK.
     InvalidNdk(std::ffi::OsString),
-    /// No Build Tools available in the selected Android SDK.
-    NoBuildTools,
+    /// No `libc++_shared.so` in the NDK at the given location.
+    NoLibcxxShared(std::path::PathBuf),
+    /// No Build Tools available in the selected Android SDK. Carries the
+    /// requested version, if any.
+    NoBuildTools(Option<std::ffi::OsString>),
     /// Invalid Build Tools with the selected version in the Android SDK.
     InvalidBuildTools(std::ffi::OsString),
     /// No platform for the selected API-level available in the selected
@@ -59,23 +79,66 @@ pub enum BuildError {
     /// Execution of the Android resource compiler could not commence.
     FlatresExec(std::io::Error),
     /// Android resource compiler failed executing.
-    FlatresExit(std::process::ExitStatus),
+    FlatresExit(op::ToolExit),
     /// Execution of the Java compiler could not commence.
     JavacExec(std::io::Error),
     /// Java compiler failed executing.
-    JavacExit(std::process::ExitStatus),
+    JavacExit(op::ToolExit),
     /// Execution of the Kotlin compiler could not commence.
     KotlincExec(std::io::Error),
     /// Kotlin compiler failed executing.
-    KotlincExit(std::process::ExitStatus),
+    KotlincExit(op::ToolExit),
     /// Execution of the DEX compiler could not commence.
     DexExec(std::io::Error),
     /// DEX compiler failed executing.
-    DexExit(std::process::ExitStatus),
-    /// Execution of the Android APK linker could not commence.
-    AaptExec(std::io::Error),
-    /// Android APK linker failed executing.
-    AaptExit(std::process::ExitStatus),
+    DexExit(op::ToolExit),
+    /// No `sdkmanager` available at the given path.
+    NoSdkmanager(std::path::PathBuf),
+    /// No installable version of the given SDK package is available.
+    NoSdkPackage(String),
+    /// Execution of the Android SDK manager could not commence.
+    SdkmanagerExec(std::io::Error),
+    /// Android SDK manager failed executing.
+    SdkmanagerExit(op::ToolExit),
+    /// User-provided Android manifest could not be read.
+    ManifestRead(std::path::PathBuf, std::io::Error),
+    /// User-provided Android manifest lacks a `<manifest>` root element.
+    ManifestInvalid(std::path::PathBuf),
+    /// User-provided Android manifest is not well-formed, or uses XML
+    /// features not supported by the build system. The line of the
+    /// offending markup is provided.
+    ManifestMalformed(std::path::PathBuf, usize),
+    /// Multiple packages provide an Android manifest.
+    ManifestAmbiguous(std::path::PathBuf, std::path::PathBuf),
+    /// Two artifacts of the same ABI would be placed at the same file name
+    /// in the APK. The package IDs of both origins are provided.
+    ArtifactCollision {
+        abi: String,
+        file_name: std::ffi::OsString,
+        first: String,
+        second: String,
+    },
+    /// Two resource files would be compiled to the same output file name.
+    /// The paths of both resource files are provided.
+    ResourceCollision {
+        file_name: std::ffi::OsString,
+        first: std::path::PathBuf,
+        second: std::path::PathBuf,
+    },
+    /// Intermediate APK is malformed or uses unsupported ZIP features.
+    ApkInvalid(std::path::PathBuf),
+    /// APK exceeds the size limits of ZIP archives.
+    ApkOverflow,
+    /// APK entry with the given name is provided multiple times.
+    ApkDuplicate(String),
+    /// File name of an artifact is not valid UTF-8, as required for APK
+    /// entries.
+    ApkEntryName(std::ffi::OsString),
+    /// Release builds of the given profile require a keystore.
+    NoKeystore(String),
+    /// Pass phrase of the keystore is missing in the given environment
+    /// variable.
+    NoKeystorePhrase(String),
 }
 
 struct Build<'ctx> {
@@ -85,7 +148,6 @@ struct Build<'ctx> {
     pub op: &'ctx op::Build<'ctx>,
 
     // Build directories
-    pub apk_dir: std::path::PathBuf,
     pub artifact_dir: std::path::PathBuf,
     pub class_dir: std::path::PathBuf,
     pub dex_dir: std::path::PathBuf,
@@ -95,11 +157,36 @@ struct Build<'ctx> {
     // Artifact files
     pub apk_aligned_file: std::path::PathBuf,
     pub apk_base_file: std::path::PathBuf,
-    pub apk_linked_file: std::path::PathBuf,
     pub apk_signed_file: std::path::PathBuf,
     pub classes_dex_file: std::path::PathBuf,
     pub debug_keystore_file: std::path::PathBuf,
     pub manifest_file: std::path::PathBuf,
+    pub resource_manifest_file: std::path::PathBuf,
+}
+
+// Keystore and credentials to sign an APK with.
+struct Signing {
+    keystore: std::path::PathBuf,
+    key_alias: Option<String>,
+    phrase: std::ffi::OsString,
+    key_phrase: Option<std::ffi::OsString>,
+}
+
+// Entry of the resource manifest, recording the content hash of a resource
+// file and the name of its compiled output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ResourceEntry {
+    hash: String,
+    output: String,
+    stamp: Option<(u64, u64)>,
+}
+
+// Native libraries of a single ABI. Besides the artifacts of the Cargo
+// build, these include libraries bundled from outside of Cargo, like the
+// shared C++ runtime of the NDK.
+struct Libs {
+    build: cargo::Build,
+    extra: Vec<std::path::PathBuf>,
 }
 
 struct Direct<'ctx> {
@@ -123,7 +210,6 @@ impl<'ctx> Build<'ctx> {
         build_dir: &'ctx std::path::Path,
     ) -> Self {
         // Prepare build directory paths
-        let v_apk_dir = build_dir.join("apk");
         let v_artifact_dir = build_dir.join("artifacts");
         let v_class_dir = build_dir.join("classes");
         let v_dex_dir = build_dir.join("dex");
@@ -133,18 +219,17 @@ impl<'ctx> Build<'ctx> {
         // Prepare artifact file paths
         let v_apk_aligned_file = v_artifact_dir.join("package-aligned.apk");
         let v_apk_base_file = v_artifact_dir.join("package-base.apk");
-        let v_apk_linked_file = v_artifact_dir.join("package-linked.apk");
         let v_apk_signed_file = v_artifact_dir.join("package-signed.apk");
         let v_classes_dex_file = v_dex_dir.join("classes.dex");
         let v_debug_keystore_file = v_artifact_dir.join("debug.keystore");
         let v_manifest_file = v_artifact_dir.join("AndroidManifest.xml");
+        let v_resource_manifest_file = v_resource_dir.join("resources.json");
 
         Self {
             android: android,
             build_dir: build_dir,
             op: op,
 
-            apk_dir: v_apk_dir,
             artifact_dir: v_artifact_dir,
             class_dir: v_class_dir,
             dex_dir: v_dex_dir,
@@ -153,47 +238,252 @@ impl<'ctx> Build<'ctx> {
 
             apk_aligned_file: v_apk_aligned_file,
             apk_base_file: v_apk_base_file,
-            apk_linked_file: v_apk_linked_file,
             apk_signed_file: v_apk_signed_file,
             classes_dex_file: v_classes_dex_file,
             debug_keystore_file: v_debug_keystore_file,
             manifest_file: v_manifest_file,
+            resource_manifest_file: v_resource_manifest_file,
+        }
+    }
+
+    // Final artifacts of a successful build.
+    fn output(&self) -> op::Output {
+        op::Output {
+            artifacts: vec![
+                op::Artifact {
+                    kind: op::ArtifactKind::AndroidApk,
+                    path: self.apk_signed_file.clone(),
+                },
+            ],
         }
     }
 
+    // Launcher activity of generated manifests, indented for the
+    // application element.
+    fn manifest_activity(&self) -> String {
+        format!(
+            concat!(
+                r#"        <activity"#, "\n",
+                r#"            android:name="{}""#, "\n",
+                r#"            android:exported="true">"#, "\n",
+                r#"            <intent-filter>"#, "\n",
+                r#"                <action android:name="android.intent.action.MAIN" />"#, "\n",
+                r#"                <category android:name="android.intent.category.LAUNCHER" />"#, "\n",
+                r#"            </intent-filter>"#, "\n",
+                r#"        </activity>"#, "\n",
+            ),
+            op::xml_escape_attr(&self.android.main_activity_class),
+        )
+    }
+
     fn generate_manifest(&self) -> String {
+        let mut permissions = String::new();
+        for v in &self.android.permissions {
+            permissions.push_str(&format!(
+                "    <uses-permission android:name=\"{}\" />\n",
+                op::xml_escape_attr(v),
+            ));
+        }
+
         format!(
             concat!(
                 r#"<?xml version="1.0" encoding="utf-8"?>"#, "\n",
                 r#"<manifest"#, "\n",
                 r#"    xmlns:android="http://schemas.android.com/apk/res/android""#, "\n",
                 r#"    xmlns:tools="http://schemas.android.com/tools""#, "\n",
-                r#"    package="com.example""#, "\n",
+                r#"    package="{}""#, "\n",
                 r#">"#, "\n",
+                r#"    <uses-sdk"#, "\n",
+                r#"        android:minSdkVersion="{}""#, "\n",
+                r#"        android:targetSdkVersion="{}" />"#, "\n",
+                "{}",
                 r#"    <application"#, "\n",
                 r#"        android:allowBackup="true""#, "\n",
                 r#"        android:supportsRtl="true""#, "\n",
-                r#"        tools:targetApi="31">"#, "\n",
-                r#"        <activity"#, "\n",
-                r#"            android:name=".MainActivity""#, "\n",
-                r#"            android:exported="true">"#, "\n",
-                r#"            <intent-filter>"#, "\n",
-                r#"                <action android:name="android.intent.action.MAIN" />"#, "\n",
-                r#"                <category android:name="android.intent.category.LAUNCHER" />"#, "\n",
-                r#"            </intent-filter>"#, "\n",
-                r#"        </activity>"#, "\n",
+                r#"        tools:targetApi="{}">"#, "\n",
+                "{}",
                 r#"    </application>"#, "\n",
                 r#"</manifest>"#, "\n",
             ),
+            op::xml_escape_attr(&self.android.namespace),
+            self.android.min_sdk,
+            self.android.target_sdk,
+            permissions,
+            self.android.target_sdk,
+            self.manifest_activity(),
         )
     }
 
+    // Select the keystore to sign the APK with. A configured keystore is
+    // always used, with its pass phrases looked up via `lookup` right before
+    // signing. Without keystore, debug builds fall back to the debug
+    // keystore, while release builds are rejected.
+    fn signing<Lookup>(&self, lookup: Lookup) -> Result<Signing, BuildError>
+    where
+        Lookup: Fn(&str) -> Option<std::ffi::OsString>,
+    {
+        let Some(ref keystore) = self.android.keystore else {
+            return match self.op.cargo_arguments.profile.as_deref() {
+                None | Some("dev") => Ok(Signing {
+                    keystore: self.debug_keystore_file.clone(),
+                    key_alias: Some(keystore::DEBUG_KEY_ALIAS.into()),
+                    phrase: keystore::DEBUG_PHRASE.into(),
+                    key_phrase: Some(keystore::DEBUG_KEY_PHRASE.into()),
+                }),
+                Some(v) => Err(BuildError::NoKeystore(v.into())),
+            };
+        };
+
+        let phrase = lookup(&keystore.phrase_env).ok_or_else(
+            || BuildError::NoKeystorePhrase(keystore.phrase_env.clone()),
+        )?;
+        let key_phrase = match keystore.key_phrase_env {
+            None => None,
+            Some(ref v) => Some(lookup(v).ok_or_else(
+                || BuildError::NoKeystorePhrase(v.clone()),
+            )?),
+        };
+
+        Ok(Signing {
+            keystore: keystore.path.clone(),
+            key_alias: keystore.key_alias.clone(),
+            phrase: phrase,
+            key_phrase: key_phrase,
+        })
+    }
+
+    // Produce the Android manifest for the build. If a package provides its
+    // own manifest, it is used verbatim (or merged, if configured), and the
+    // generated one otherwise. User manifests are only checked for their
+    // element structure (see `manifest` for the supported XML subset), since
+    // the APK linker fully validates them.
+    fn manifest(&self) -> Result<String, op::BuildError> {
+        let mut user = None;
+        for path in self.op.cargo_metadata.android_sets.iter()
+            .filter_map(|v| v.manifest_file.as_ref())
+        {
+            if let Some(first) = user.replace(path) {
+                return Err(BuildError::ManifestAmbiguous(first.clone(), path.clone()).into());
+            }
+        }
+
+        let Some(path) = user else {
+            return Ok(self.generate_manifest());
+        };
+
+        let content = std::fs::read_to_string(path).map_err(
+            |v| BuildError::ManifestRead(path.clone(), v),
+        )?;
+        let v = match self.android.manifest_merge {
+            true => manifest::merge(&content, &self.manifest_activity()),
+            false => manifest::check(&content).map(|_| content.clone()),
+        };
+
+        v.map_err(|v| match v {
+            manifest::Error::NoManifest => BuildError::ManifestInvalid(path.clone()).into(),
+            manifest::Error::Malformed(idx) => BuildError::ManifestMalformed(
+                path.clone(),
+                content[..idx].matches('\n').count() + 1,
+            ).into(),
+        })
+    }
+
+    // Path of the generated main activity, following the reverse-domain
+    // source layout of Java.
+    fn main_activity_file(&self) -> std::path::PathBuf {
+        self.java_dir
+            .join(self.android.namespace.replace('.', "/"))
+            .join(format!("{}.java", MAIN_CLASS))
+    }
+
+    // JNI symbol of the entry-point of a generated main activity, see
+    // `ENV_MAIN_SYMBOL`.
+    fn main_symbol(&self) -> String {
+        jni_symbol(&format!("{}.{}", self.android.namespace, MAIN_CLASS), MAIN_METHOD)
+    }
+
+    // Produce the Java source of the main activity. It loads the native
+    // library of the application and calls into its JNI entry-point, which
+    // must be exported under `Self::main_symbol()`.
+    fn generate_main_activity(&self, library: &str) -> String {
+        format!(
+            concat!(
+                "package {};\n",
+                "\n",
+                "public class {} extends android.app.Activity {{\n",
+                "    static {{\n",
+                "        System.loadLibrary(\"{}\");\n",
+                "    }}\n",
+                "\n",
+                "    // Exported by the native library as `{}`.\n",
+                "    private native void {}();\n",
+                "\n",
+                "    @Override\n",
+                "    protected void onCreate(android.os.Bundle savedInstanceState) {{\n",
+                "        super.onCreate(savedInstanceState);\n",
+                "        {}();\n",
+                "    }}\n",
+                "}}\n",
+            ),
+            self.android.namespace,
+            MAIN_CLASS,
+            library,
+            self.main_symbol(),
+            MAIN_METHOD,
+            MAIN_METHOD,
+        )
+    }
+
+    // Check whether a package provides its own main activity as Java or
+    // Kotlin source.
+    fn user_main_activity(&self) -> Result<bool, op::BuildError> {
+        for set in &self.op.cargo_metadata.android_sets {
+            for dir in set.java_dirs.iter().chain(set.kotlin_dirs.iter()) {
+                let found = op::lsrdir(dir.as_path())?.iter().any(|v| {
+                    v.file_name() == Some(std::ffi::OsStr::new("MainActivity.java"))
+                        || v.file_name() == Some(std::ffi::OsStr::new("MainActivity.kt"))
+                });
+                if found {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    // Emerge the generated main activity, if configured. If a package
+    // provides its own, a previously generated one is removed instead, so
+    // it does not clash with the user-provided class.
+    fn prepare_main_activity(&self) -> Result<(), op::BuildError> {
+        let path = self.main_activity_file();
+
+        match self.android.main_activity.as_deref() {
+            Some(library) if !self.user_main_activity()? => {
+                op::mkdir(path.parent().unwrap())?;
+                op::update_file(
+                    path.as_path(),
+                    self.generate_main_activity(library).as_bytes(),
+                )?;
+            },
+            _ => {
+                match std::fs::remove_file(&path) {
+                    Err(v) if v.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(op::ErrorFileSystem::FileUpdate { path: path, io: v }.into());
+                    },
+                    _ => {},
+                }
+            },
+        }
+
+        Ok(())
+    }
+
     fn prepare(&self) -> Result<(), op::BuildError> {
         // Create build root
         op::mkdir(self.build_dir)?;
 
         // Create build directories
-        op::mkdir(self.apk_dir.as_path())?;
         op::mkdir(self.artifact_dir.as_path())?;
         op::mkdir(self.class_dir.as_path())?;
         op::mkdir(self.dex_dir.as_path())?;
@@ -207,18 +497,29 @@ impl<'ctx> Build<'ctx> {
         )?;
         op::update_file(
             self.manifest_file.as_path(),
-            self.generate_manifest().as_bytes(),
+            self.manifest()?.as_bytes(),
         )?;
+        self.prepare_main_activity()?;
 
         Ok(())
     }
 
     fn direct(&self) -> Result<Direct, op::BuildError> {
         let android_home = match std::env::var_os("ANDROID_HOME") {
-            None => Err(BuildError::NoAndroidHome),
+            None => Err(BuildError::NoAndroidHome(self.android.min_sdk)),
             Some(v) => Ok(v),
         }?;
-        let v_sdk = match sdk::Sdk::new(std::path::Path::new(&android_home)) {
+
+        self.direct_at(std::path::Path::new(&android_home))
+    }
+
+    // Locate all tools and resources of a direct build in the Android SDK
+    // at the given path.
+    fn direct_at(
+        &self,
+        android_home: &std::path::Path,
+    ) -> Result<Direct<'_>, op::BuildError> {
+        let v_sdk = match sdk::Sdk::new(android_home) {
             Ok(v) => Ok::<_, op::BuildError>(v),
             Err(sdk::SdkError::NoSdk(v)) => Err(BuildError::NoSdk(v).into()),
             Err(sdk::SdkError::InvalidSdk(v)) => Err(BuildError::InvalidSdk(v).into()),
@@ -226,13 +527,13 @@ impl<'ctx> Build<'ctx> {
         }?;
         let v_ndk = match v_sdk.ndk(None) {
             Ok(v) => Ok::<_, op::BuildError>(v),
-            Err(sdk::SdkError::NoNdk) => Err(BuildError::NoNdk.into()),
+            Err(sdk::SdkError::NoNdk(v)) => Err(BuildError::NoNdk(v).into()),
             Err(sdk::SdkError::InvalidNdk(v)) => Err(BuildError::InvalidNdk(v).into()),
             Err(v) => Err(lib::error::Uncaught::box_debug(v).into()),
         }?;
         let v_build_tools = match v_sdk.build_tools(None) {
             Ok(v) => Ok::<_, op::BuildError>(v),
-            Err(sdk::SdkError::NoBuildTools) => Err(BuildError::NoBuildTools.into()),
+            Err(sdk::SdkError::NoBuildTools(v)) => Err(BuildError::NoBuildTools(v).into()),
             Err(sdk::SdkError::InvalidBuildTools(v)) => Err(BuildError::InvalidBuildTools(v).into()),
             Err(v) => Err(lib::error::Uncaught::box_debug(v).into()),
         }?;
@@ -272,91 +573,33 @@ impl<'ctx> Direct<'ctx> {
     fn build_resources(
         &self,
     ) -> Result<(bool, Vec<std::path::PathBuf>), op::BuildError> {
-        let mut res_files: BTreeMap::<std::path::PathBuf, std::path::PathBuf>;
-        let mut new = false;
-
-        // Collect all resource files to be compiled. We get a list of
-        // resource directories. Each of these contains a list of resource
-        // type directories, which then each contains resource files. Any
-        // stray entries are silently ignored.
-        res_files = BTreeMap::new();
-        for rdir in self.build.op.cargo_metadata.android_sets.iter()
-            .flat_map(|v| v.resource_dirs.iter())
-        {
-            let sdirs = std::fs::read_dir(rdir).map_err(
-                |io| op::ErrorFileSystem::DirectoryTraversal { path: rdir.into(), io },
-            )?;
-            for sdir_iter in sdirs {
-                let sdir_entry = sdir_iter.map_err(
-                    |io| op::ErrorFileSystem::DirectoryTraversal { path: rdir.into(), io },
-                )?;
-                let sdir = &sdir_entry.path();
-
-                if !sdir.is_dir() {
-                    continue;
-                }
+        let res_files = collect_resources(
+            self.build.op.cargo_metadata.android_sets.iter()
+                .flat_map(|v| v.resource_dirs.iter()),
+            &self.build.resource_dir,
+        )?;
 
-                let tdirs = std::fs::read_dir(sdir).map_err(
-                    |io| op::ErrorFileSystem::DirectoryTraversal { path: sdir.into(), io },
-                )?;
-                for tdir_iter in tdirs {
-                    let tdir_entry = tdir_iter.map_err(
-                        |io| op::ErrorFileSystem::DirectoryTraversal { path: sdir.into(), io },
-                    )?;
-                    let tdir = &tdir_entry.path();
-
-                    if !tdir.is_dir() {
-                        // Compute the output file name. Note that this cannot
-                        // fail here, since its only failure condition is when
-                        // an invalid path, or a path without directory is given.
-                        // We just iterated a directory, so both must be set.
-                        let out = flatres::Query::output_file_name(
-                            tdir,
-                        ).ok_or_else(
-                            || -> op::BuildError {
-                                lib::error::Uncaught::box_any(()).into()
-                            },
-                        )?;
-
-                        res_files.insert(
-                            self.build.resource_dir.join(out),
-                            tdir.into(),
-                        );
+        let new = update_resources(
+            &res_files,
+            &self.build.resource_manifest_file,
+            &mut |from| {
+                let query = flatres::Query {
+                    build_tools: self.build_tools.clone(),
+                    output_dir: self.build.resource_dir.clone(),
+                    resource_file: from.into(),
+                };
+
+                query.run(self.build.op).map_err(|v| -> op::BuildError {
+                    match v {
+                        flatres::Error::Exec(v) => BuildError::FlatresExec(v).into(),
+                        flatres::Error::Exit(v) => BuildError::FlatresExit(v).into(),
+                        v => lib::error::Uncaught::box_debug(v).into(),
                     }
-                }
-            }
-        }
-
-        // For each resource file, check whether the target file exists and is
-        // newer than the source. In this case, skip compilation. Otherwise,
-        // invoke the Android flat-resource compiler.
-        for (to, from) in &res_files {
-            let to_mod = to.metadata().ok()
-                .and_then(|v| v.modified().ok());
-            let from_mod = from.metadata().ok()
-                .and_then(|v| v.modified().ok());
-            if let (Some(dst), Some(src)) = (to_mod, from_mod) {
-                if src < dst {
-                    continue;
-                }
-            }
-
-            let query = flatres::Query {
-                build_tools: self.build_tools.clone(),
-                output_dir: self.build.resource_dir.clone(),
-                resource_file: from.clone(),
-            };
-
-            query.run().map_err(|v| -> op::BuildError {
-                match v {
-                    flatres::Error::Exec(v) => BuildError::FlatresExec(v).into(),
-                    flatres::Error::Exit(v) => BuildError::FlatresExit(v).into(),
-                    v => lib::error::Uncaught::box_debug(v).into(),
-                }
-            })?;
+                })?;
 
-            new = true;
-        }
+                Ok(())
+            },
+        )?;
 
         Ok((new, res_files.into_keys().collect()))
     }
@@ -368,6 +611,17 @@ impl<'ctx> Direct<'ctx> {
         let mut link_files = Vec::new();
         link_files.push(self.platform_jar.clone());
 
+        // Generated manifests use the namespace as package, so relative
+        // class names and resource classes resolve in the namespace. The
+        // application ID is applied when linking. User manifests are used
+        // as provided.
+        let generated = self.build.op.cargo_metadata.android_sets.iter()
+            .all(|v| v.manifest_file.is_none());
+        let rename_package = match generated && self.build.android.application_id != self.build.android.namespace {
+            true => Some(self.build.android.application_id.clone()),
+            false => None,
+        };
+
         let query = apk::LinkQuery {
             build_tools: self.build_tools.clone(),
             asset_dirs: Vec::new(),
@@ -375,10 +629,11 @@ impl<'ctx> Direct<'ctx> {
             manifest_file: self.build.manifest_file.clone(),
             output_file: self.build.apk_base_file.clone(),
             output_java_dir: Some(self.build.java_dir.clone()),
+            rename_package: rename_package,
             resource_files: resources.1.clone(),
         };
 
-        query.run().map_err(|v| -> op::BuildError {
+        query.run(self.build.op).map_err(|v| -> op::BuildError {
             match v {
                 apk::LinkError::Exec(v) => BuildError::FlatresExec(v).into(),
                 apk::LinkError::Exit(v) => BuildError::FlatresExit(v).into(),
@@ -425,7 +680,7 @@ impl<'ctx> Direct<'ctx> {
             source_files: &sources,
         };
 
-        query.run().map_err(|v| -> op::BuildError {
+        query.run(self.build.op).map_err(|v| -> op::BuildError {
             match v {
                 java::Error::UnsupportedPath(v) => BuildError::UnsupportedPath(v).into(),
                 java::Error::Exec(v) => BuildError::JavacExec(v).into(),
@@ -468,7 +723,7 @@ impl<'ctx> Direct<'ctx> {
             source_files: &sources,
         };
 
-        query.run().map_err(|v| -> op::BuildError {
+        query.run(self.build.op).map_err(|v| -> op::BuildError {
             match v {
                 kotlin::Error::UnsupportedPath(v) => BuildError::UnsupportedPath(v).into(),
                 kotlin::Error::Exec(v) => BuildError::KotlincExec(v).into(),
@@ -499,7 +754,7 @@ impl<'ctx> Direct<'ctx> {
             source_files: &sources,
         };
 
-        query.run().map_err(|v| -> op::BuildError {
+        query.run(self.build.op).map_err(|v| -> op::BuildError {
             match v {
                 dex::Error::Exec(v) => BuildError::DexExec(v).into(),
                 dex::Error::Exit(v) => BuildError::DexExit(v).into(),
@@ -511,7 +766,7 @@ impl<'ctx> Direct<'ctx> {
 
     fn build_cargo(
         &self,
-    ) -> Result<(bool, BTreeMap<String, cargo::Build>), op::BuildError> {
+    ) -> Result<(bool, BTreeMap<String, Libs>), op::BuildError> {
         let mut res = BTreeMap::new();
 
         // Android SDKs ship prebuilt toolchains for `x86_64` on linux, macos
@@ -558,157 +813,179 @@ impl<'ctx> Direct<'ctx> {
         };
 
         for abi in &self.build.android.abis {
-            let (target, linker_env, linker_prefix) = match abi.as_str() {
-                "armeabi-v7a" => Ok((
-                    "armv7-linux-androideabi",
-                    "CARGO_TARGET_ARMV7_LINUX_ANDROIDEABI_LINKER",
-                    "armv7a-linux-androideabi",
-                )),
-                "arm64-v8a" => Ok((
-                    "aarch64-linux-android",
-                    "CARGO_TARGET_AARCH64_LINUX_ANDROID_LINKER",
-                    "aarch64-linux-android",
-                )),
-                "x86" => Ok((
-                    "i686-linux-android",
-                    "CARGO_TARGET_I686_LINUX_ANDROID_LINKER",
-                    "i686-linux-android",
-                )),
-                "x86_64" => Ok((
-                    "x86_64-linux-android",
-                    "CARGO_TARGET_X86_64_LINUX_ANDROID_LINKER",
-                    "x86_64-linux-android",
-                )),
-                v => Err(BuildError::UnsupportedAbi(v.into())),
-            }?;
-            let linker_bin = format!(
-                "toolchains/llvm/prebuilt/{}/bin/{}{}-clang",
-                host,
-                linker_prefix,
-                self.build.android.min_sdk,
-            );
-            let linker_path = self.ndk.root().join(linker_bin);
-
-            let query = cargo::BuildQuery {
-                cargo_arguments: self.build.op.cargo_arguments,
-                cfgs: Vec::new(),
-                crate_type: Some("cdylib".into()),
-                envs: vec![(linker_env.into(), linker_path.into())],
-                target: Some(target.into()),
-            };
+            let (query, libcxx) = self.cargo_query(host, abi)?;
 
-            let build = query.run().map_err(
+            let build = query.run(self.build.op).map_err(
                 |v| -> op::BuildError { v.into() },
             )?;
 
-            res.insert(abi.into(), build);
+            // The shared C++ runtime is bundled next to the artifacts of
+            // Cargo, so collisions with them are detected.
+            let mut extra = Vec::new();
+            if let Some(path) = libcxx {
+                if !path.is_file() {
+                    return Err(BuildError::NoLibcxxShared(path).into());
+                }
+                extra.push(path);
+            }
+
+            res.insert(abi.into(), Libs { build: build, extra: extra });
         }
 
         Ok((true, res))
     }
 
-    fn link_apk(
+    // Assemble the Cargo query for the given ABI, including the linker of
+    // the NDK and the per-ABI configuration. The latter is applied last, so
+    // it can override the computed environment. If the shared C++ runtime
+    // is to be bundled, its path in the NDK is returned as well.
+    fn cargo_query(
         &self,
-        bins: &(bool, BTreeMap<String, cargo::Build>),
-    ) -> Result<bool, op::BuildError> {
-        let mut path: std::path::PathBuf = self.build.apk_dir.clone();
-        let mut add = Vec::new();
-
-        // Copy the intermediate APK to avoid operating on intermediates
-        // multiple times, and thus modifying timestamps needlessly.
-        op::copy_file(&self.build.apk_base_file, &self.build.apk_linked_file)?;
-
-        // Assemble the APK directory. Since `aapt` retains paths verbatim
-        // in the APK, we need to assemble a directory with the exact
-        // contents we want in the APK. Unfortunately, this means copying
-        // our artifacts to the desired sub-paths in the APK assembly
-        // directory.
-
-        path.push("classes.dex");
-        add.push("classes.dex".into());
-        op::copy_file(&self.build.classes_dex_file, path.as_path())?;
-        path.pop();
-
-        path.push("lib");
-        op::mkdir(path.as_path())?;
-        for (abi, set) in &bins.1 {
-            path.push(abi);
-            op::mkdir(path.as_path())?;
-            for v in &set.artifacts {
-                let file_name = std::path::Path::new(&v.path)
-                    .file_name()
-                    .expect("Cargo artifact has no file-name");
-
-                let mut lib_path = std::ffi::OsString::new();
-                lib_path.push(format!("lib/{}/", abi));
-                lib_path.push(file_name);
-
-                path.push(file_name);
-                add.push(lib_path.into());
-                op::copy_file(std::path::Path::new(&v.path), path.as_path())?;
-                path.pop();
-            }
-            path.pop();
+        host: &str,
+        abi: &str,
+    ) -> Result<(cargo::BuildQuery<'_>, Option<std::path::PathBuf>), op::BuildError> {
+        let (target, linker_env, linker_prefix, sysroot_triple) = match abi {
+            "armeabi-v7a" => Ok((
+                "armv7-linux-androideabi",
+                "CARGO_TARGET_ARMV7_LINUX_ANDROIDEABI_LINKER",
+                "armv7a-linux-androideabi",
+                "arm-linux-androideabi",
+            )),
+            "arm64-v8a" => Ok((
+                "aarch64-linux-android",
+                "CARGO_TARGET_AARCH64_LINUX_ANDROID_LINKER",
+                "aarch64-linux-android",
+                "aarch64-linux-android",
+            )),
+            "x86" => Ok((
+                "i686-linux-android",
+                "CARGO_TARGET_I686_LINUX_ANDROID_LINKER",
+                "i686-linux-android",
+                "i686-linux-android",
+            )),
+            "x86_64" => Ok((
+                "x86_64-linux-android",
+                "CARGO_TARGET_X86_64_LINUX_ANDROID_LINKER",
+                "x86_64-linux-android",
+                "x86_64-linux-android",
+            )),
+            v => Err(BuildError::UnsupportedAbi(v.into())),
+        }?;
+        let linker_bin = format!(
+            "toolchains/llvm/prebuilt/{}/bin/{}{}-clang",
+            host,
+            linker_prefix,
+            self.build.android.min_sdk,
+        );
+        let linker_path = self.ndk.root().join(linker_bin);
+
+        let mut query = cargo::BuildQuery::new(self.build.op.cargo_arguments)
+            .with_crate_type("cdylib")
+            .with_env(linker_env, linker_path)
+            .with_target(Some(target));
+        if self.build.android.main_activity.is_some() {
+            query = query.with_env(ENV_MAIN_SYMBOL, self.build.main_symbol());
         }
-        path.pop();
-
-        // Now invoke the `aapt` tools to alter and thus link the final
-        // APK. Preferably, we would just invoke a standard ZIP tool, but
-        // they are not packaged with the Android SDK, and thus would mean
-        // we have another build-time dependency.
-        //
-        // XXX: Ideally, we would ship, or depend, on a simple ZIP archive
-        //      builder in pure Rust, and thus avoid all this dance.
 
-        let query = apk::AlterQuery {
-            base_dir: Some(self.build.apk_dir.clone()),
-            build_tools: self.build_tools.clone(),
-            add_files: add,
-            apk_file: self.build.apk_linked_file.clone(),
+        let Some(config) = self.build.android.targets.get(abi) else {
+            return Ok((query, None));
         };
 
-        query.run().map_err(|v| -> op::BuildError {
+        for (key, value) in &config.envs {
+            query = query.with_env(key, value);
+        }
+        for (key, value) in &config.cfgs {
+            query = query.with_cfg(key, value.as_deref());
+        }
+        for arg in &config.rustc_args {
+            query = query.with_rustc_arg(arg);
+        }
+
+        let libcxx = config.libcxx_shared.then(|| {
+            self.ndk.root().join(format!(
+                "toolchains/llvm/prebuilt/{}/sysroot/usr/lib/{}/libc++_shared.so",
+                host,
+                sysroot_triple,
+            ))
+        });
+
+        Ok((query, libcxx))
+    }
+
+    fn link_apk(
+        &self,
+        bins: &(bool, BTreeMap<String, Libs>),
+    ) -> Result<bool, op::BuildError> {
+        let read = |path: &std::path::Path| -> Result<Vec<u8>, op::BuildError> {
+            std::fs::read(path).map_err(
+                |io| op::ErrorFileSystem::FileRead { path: path.into(), io }.into(),
+            )
+        };
+        let zip_error = |v: zip::Error| -> op::BuildError {
             match v {
-                apk::AlterError::Exec(v) => BuildError::AaptExec(v).into(),
-                apk::AlterError::Exit(v) => BuildError::AaptExit(v).into(),
+                zip::Error::Invalid => BuildError::ApkInvalid(self.build.apk_base_file.clone()).into(),
+                zip::Error::Overflow => BuildError::ApkOverflow.into(),
+                zip::Error::Duplicate(v) => BuildError::ApkDuplicate(v).into(),
             }
-        })?;
-
-        // Since APKs are normal zip-files, and those have no alignment
-        // restrictions, we have to align the file explicitly to ensure
-        // Android can run it directly.
+        };
 
-        let query = apk::AlignQuery {
-            build_tools: self.build_tools.clone(),
-            input_file: self.build.apk_linked_file.clone(),
-            output_file: self.build.apk_aligned_file.clone(),
+        // Assemble the final APK from the intermediate APK of `aapt2`, the
+        // DEX code, and all native libraries. Entries with one of the
+        // configured extensions are stored without compression, everything
+        // else is deflated. The ZIP writer aligns all stored entries, so
+        // Android can map them directly from the APK. This replaces the
+        // alignment otherwise done by `zipalign`, including the alignment
+        // of native libraries to the page size.
+
+        let mut apk = zip::Writer::with_archive(&read(&self.build.apk_base_file)?)
+            .map_err(zip_error)?;
+        let mut add = |name: &str, path: &std::path::Path, alignment: u16| -> Result<(), op::BuildError> {
+            let content = read(path)?;
+            match apk::stored(std::path::Path::new(name), &self.build.android.uncompressed_extensions) {
+                true => apk.add_stored(name, &content, alignment),
+                false => apk.add_deflated(name, &content),
+            }.map_err(zip_error)
         };
 
-        query.run().map_err(|v| -> op::BuildError {
-            match v {
-                apk::AlignError::Exec(v) => op::BuildError::Exec("zipalign".into(), v),
-                apk::AlignError::Exit(v) => op::BuildError::Exit("zipalign".into(), v),
+        add("classes.dex", &self.build.classes_dex_file, zip::ALIGNMENT)?;
+
+        for (abi, set) in &bins.1 {
+            for (file_name, (_, path)) in collect_libs(abi, set)? {
+                let name = file_name.to_str().ok_or_else(
+                    || BuildError::ApkEntryName(file_name.into()),
+                )?;
+                add(
+                    &format!("lib/{}/{}", abi, name),
+                    path,
+                    zip::ALIGNMENT_PAGE,
+                )?;
             }
-        })?;
+        }
+
+        op::update_file(
+            self.build.apk_aligned_file.as_path(),
+            &apk.finish().map_err(zip_error)?,
+        )?;
 
-        // As last step sign the APK using the debug-keystore. Android requires
-        // APKs to be signed (and uses key-information for optional process
-        // sharing). Hence, we must sign APKs even during development. For
-        // final production, online signing on the Android Store servers is
-        // mandatory, so no such support is currently provided here. This can
-        // be extended in the future, if offline signing becomes a thing again.
+        // As last step sign the APK. Android requires APKs to be signed
+        // (and uses key-information for optional process sharing). Hence,
+        // we must sign APKs even during development, using the debug
+        // keystore unless a release keystore is configured. Missing pass
+        // phrases are reported before invoking `apksigner`, which would
+        // otherwise prompt for them.
 
+        let signing = self.build.signing(|v| std::env::var_os(v))?;
         let query = apk::SignQuery {
             build_tools: self.build_tools.clone(),
             input_file: self.build.apk_aligned_file.clone(),
-            keystore: self.build.debug_keystore_file.clone(),
-            keystore_key_alias: Some(keystore::DEBUG_KEY_ALIAS.into()),
-            keystore_phrase: Some(keystore::DEBUG_PHRASE.into()),
-            key_phrase: Some(keystore::DEBUG_KEY_PHRASE.into()),
+            keystore: signing.keystore,
+            keystore_key_alias: signing.key_alias,
+            keystore_phrase: Some(signing.phrase),
+            key_phrase: signing.key_phrase,
             output_file: self.build.apk_signed_file.clone(),
         };
 
-        query.run().map_err(|v| -> op::BuildError {
+        query.run(self.build.op).map_err(|v| -> op::BuildError {
             match v {
                 apk::SignError::Exec(v) => op::BuildError::Exec("apksigner".into(), v),
                 apk::SignError::Exit(v) => op::BuildError::Exit("apksigner".into(), v),
@@ -719,29 +996,304 @@ impl<'ctx> Direct<'ctx> {
     }
 }
 
+// Collect all native libraries of a single ABI keyed by the file name they
+// will have in the APK, together with their origin and path. All libraries
+// of an ABI end up in the same directory, so libraries with equal file names
+// would silently overwrite each other. Instead, such collisions are reported
+// with the origin of both libraries. The origin of Cargo artifacts is their
+// package, while extra libraries are identified by their path.
+fn collect_libs<'build>(
+    abi: &str,
+    set: &'build Libs,
+) -> Result<BTreeMap<&'build std::ffi::OsStr, (String, &'build std::path::Path)>, BuildError> {
+    let mut libs = BTreeMap::<&std::ffi::OsStr, (String, &std::path::Path)>::new();
+
+    let artifacts = set.build.artifacts.iter().map(
+        |v| (v.package_id.clone(), std::path::Path::new(&v.path)),
+    );
+    let extra = set.extra.iter().map(
+        |v| (v.to_string_lossy().into_owned(), v.as_path()),
+    );
+
+    for (origin, path) in artifacts.chain(extra) {
+        let file_name = path.file_name().expect("Native library has no file-name");
+
+        if let Some((prev, _)) = libs.insert(file_name, (origin.clone(), path)) {
+            return Err(BuildError::ArtifactCollision {
+                abi: abi.into(),
+                file_name: file_name.into(),
+                first: prev,
+                second: origin,
+            });
+        }
+    }
+
+    Ok(libs)
+}
+
+// Collect all resource files to be compiled, keyed by the path of their
+// compiled output. We get a list of resource directories. Each of these
+// contains a list of resource type directories, which then each contains
+// resource files. Any stray entries are silently ignored.
+//
+// Resource files of different resource directories can map to the same
+// output file name, in which case one of them would silently be dropped.
+// Instead, such collisions are reported with the paths of both files.
+fn collect_resources<'dirs>(
+    resource_dirs: impl Iterator<Item = &'dirs std::path::PathBuf>,
+    output_dir: &std::path::Path,
+) -> Result<BTreeMap<std::path::PathBuf, std::path::PathBuf>, op::BuildError> {
+    let mut res_files = BTreeMap::<std::path::PathBuf, std::path::PathBuf>::new();
+
+    for rdir in resource_dirs {
+        let sdirs = std::fs::read_dir(rdir).map_err(
+            |io| op::ErrorFileSystem::DirectoryTraversal { path: rdir.into(), io },
+        )?;
+        for sdir_iter in sdirs {
+            let sdir_entry = sdir_iter.map_err(
+                |io| op::ErrorFileSystem::DirectoryTraversal { path: rdir.into(), io },
+            )?;
+            let sdir = &sdir_entry.path();
+
+            if !sdir.is_dir() {
+                continue;
+            }
+
+            let tdirs = std::fs::read_dir(sdir).map_err(
+                |io| op::ErrorFileSystem::DirectoryTraversal { path: sdir.into(), io },
+            )?;
+            for tdir_iter in tdirs {
+                let tdir_entry = tdir_iter.map_err(
+                    |io| op::ErrorFileSystem::DirectoryTraversal { path: sdir.into(), io },
+                )?;
+                let tdir = &tdir_entry.path();
+
+                if !tdir.is_dir() {
+                    // Compute the output file name. Note that this cannot
+                    // fail here, since its only failure condition is when
+                    // an invalid path, or a path without directory is given.
+                    // We just iterated a directory, so both must be set.
+                    let out = flatres::Query::output_file_name(
+                        tdir,
+                    ).ok_or_else(
+                        || -> op::BuildError {
+                            lib::error::Uncaught::box_any(()).into()
+                        },
+                    )?;
+
+                    if let Some(prev) = res_files.insert(output_dir.join(&out), tdir.into()) {
+                        return Err(BuildError::ResourceCollision {
+                            file_name: out,
+                            first: prev,
+                            second: tdir.into(),
+                        }.into());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(res_files)
+}
+
+// Read the resource manifest at the given path. The manifest is a JSON
+// object keyed by the path of the resource file. A missing or malformed
+// manifest is treated as empty, which just causes recompilation.
+fn load_resource_manifest(
+    path: &std::path::Path,
+) -> BTreeMap<String, ResourceEntry> {
+    let json: Option<serde_json::Value> = std::fs::read(path).ok()
+        .and_then(|v| serde_json::from_slice(&v).ok());
+
+    json.as_ref()
+        .and_then(|v| v.as_object())
+        .into_iter()
+        .flatten()
+        .filter_map(|(k, v)| {
+            Some((
+                k.clone(),
+                ResourceEntry {
+                    hash: v.get("hash")?.as_str()?.into(),
+                    output: v.get("output")?.as_str()?.into(),
+                    stamp: v.get("size").and_then(|v| v.as_u64())
+                        .zip(v.get("mtime").and_then(|v| v.as_u64())),
+                },
+            ))
+        })
+        .collect()
+}
+
+// Write the resource manifest to the given path. The file is only modified
+// if its content changed.
+fn store_resource_manifest(
+    path: &std::path::Path,
+    manifest: &BTreeMap<String, ResourceEntry>,
+) -> Result<(), op::BuildError> {
+    let json: serde_json::Map<String, serde_json::Value> = manifest.iter()
+        .map(|(k, v)| {
+            let mut entry = serde_json::json!({ "hash": v.hash, "output": v.output });
+            if let Some((size, mtime)) = v.stamp {
+                entry["size"] = size.into();
+                entry["mtime"] = mtime.into();
+            }
+            (k.clone(), entry)
+        })
+        .collect();
+    let mut data = serde_json::to_vec_pretty(&json)
+        .map_err(|v| -> op::BuildError { lib::error::Uncaught::box_debug(v).into() })?;
+    data.push(b'\n');
+
+    op::update_file(path, &data)?;
+    Ok(())
+}
+
+// Mangle the name of a native method as required by JNI to resolve it in a
+// native library. Package separators become underscores, which hence are
+// escaped as `_1`, while all other characters beyond ASCII alphanumerics are
+// escaped as their UTF-16 code units in hexadecimal.
+fn jni_symbol(class: &str, method: &str) -> String {
+    let mangle = |acc: &mut String, v: &str| {
+        for c in v.chars() {
+            match c {
+                '.' | '/' => acc.push('_'),
+                '_' => acc.push_str("_1"),
+                ';' => acc.push_str("_2"),
+                '[' => acc.push_str("_3"),
+                c if c.is_ascii_alphanumeric() => acc.push(c),
+                c => {
+                    for unit in c.encode_utf16(&mut [0; 2]) {
+                        acc.push_str(&format!("_0{:04x}", unit));
+                    }
+                },
+            }
+        }
+    };
+
+    let mut v = "Java_".to_string();
+    mangle(&mut v, class);
+    v.push('_');
+    mangle(&mut v, method);
+    v
+}
+
+// Yield the size and modification time (in nanoseconds since the UNIX
+// epoch) of a file, if the platform reports the latter.
+fn resource_stamp(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    let mtime = metadata.modified().ok()?
+        .duration_since(std::time::UNIX_EPOCH).ok()?
+        .as_nanos();
+    Some((metadata.len(), u64::try_from(mtime).ok()?))
+}
+
+// Compile all resource files whose content changed since they were last
+// compiled, using `compile` on the path of the resource file. The content
+// hash of every compiled file is recorded in the manifest at the given path.
+// Files are skipped if their hash and output match the manifest and the
+// output exists. Touched files are thus not recompiled, while modified files
+// are, regardless of their timestamp.
+//
+// Size and modification time of every file are recorded as well. If both
+// are unchanged, the file is not read at all and its recorded hash is used,
+// like build systems usually do. Only modifications that retain both the
+// size and the modification time are missed.
+//
+// Entries of resource files that no longer exist are dropped from the
+// manifest, and their outputs are removed. Returns whether any file was
+// compiled.
+fn update_resources(
+    res_files: &BTreeMap<std::path::PathBuf, std::path::PathBuf>,
+    manifest_path: &std::path::Path,
+    compile: &mut dyn FnMut(&std::path::Path) -> Result<(), op::BuildError>,
+) -> Result<bool, op::BuildError> {
+    let mut previous = load_resource_manifest(manifest_path);
+    let mut manifest = BTreeMap::new();
+    let mut new = false;
+    let mut r = Ok(());
+
+    for (to, from) in res_files {
+        let read_error = |io| op::ErrorFileSystem::FileRead { path: from.into(), io };
+        let stamp = resource_stamp(&std::fs::metadata(from).map_err(read_error)?);
+        let key = from.to_string_lossy().into_owned();
+        let prev = previous.remove(&key);
+
+        let hash = match prev {
+            Some(ref v) if stamp.is_some() && v.stamp == stamp => v.hash.clone(),
+            _ => {
+                let content = std::fs::read(from).map_err(read_error)?;
+                lib::hash::hash::<lib::hash::sha256::Engine>(&content).to_hex()
+            },
+        };
+        let entry = ResourceEntry {
+            hash: hash,
+            output: to.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            stamp: stamp,
+        };
+
+        let unchanged = prev.is_some_and(|v| v.hash == entry.hash && v.output == entry.output);
+        if !unchanged || !to.is_file() {
+            // Stop at the first failure, but still record the files that
+            // were compiled so far.
+            if let Err(e) = compile(from) {
+                r = Err(e);
+                break;
+            }
+            new = true;
+        }
+
+        manifest.insert(key, entry);
+    }
+
+    if r.is_ok() {
+        // Remove outputs of resource files that disappeared. Outputs that
+        // are still produced by another resource file are retained.
+        if let Some(dir) = manifest_path.parent() {
+            for entry in previous.values() {
+                if !manifest.values().any(|v| v.output == entry.output) {
+                    let _ = std::fs::remove_file(dir.join(&entry.output));
+                }
+            }
+        }
+    } else {
+        // Retain entries that were not visited due to the failure.
+        for (k, v) in previous {
+            manifest.entry(k).or_insert(v);
+        }
+    }
+
+    store_resource_manifest(manifest_path, &manifest)?;
+    r.map(|()| new)
+}
+
 fn build_direct(
-    build: &Build,
+    direct: &Direct,
 ) -> Result<(), op::BuildError> {
-    let direct = build.direct()?;
+    let build = direct.build;
 
+    build.op.check_cancel()?;
     eprintln!("Compile Android resources..");
     let res = direct.build_resources()?;
 
+    build.op.check_cancel()?;
     eprintln!("Build Android APK..");
     direct.build_apk(&res)?;
 
+    build.op.check_cancel()?;
     eprintln!("Compile Android Java sources..");
     direct.build_java()?;
 
+    build.op.check_cancel()?;
     eprintln!("Compile Android Kotlin sources..");
     direct.build_kotlin()?;
 
+    build.op.check_cancel()?;
     eprintln!("Build DEX files..");
     direct.build_dex()?;
 
+    build.op.check_cancel()?;
     eprintln!("Build Cargo package..");
     let bins = direct.build_cargo()?;
 
+    build.op.check_cancel()?;
     eprintln!("Link APK..");
     direct.link_apk(&bins)?;
 
@@ -752,7 +1304,7 @@ pub fn build(
     op: &op::Build,
     android: &config::ConfigPlatformAndroid,
     build_dir: &std::path::Path,
-) -> Result<(), op::BuildError> {
+) -> Result<op::Output, op::BuildError> {
     let build = Build::new(
         op,
         android,
@@ -760,9 +1312,176 @@ pub fn build(
     );
 
     build.prepare()?;
-    build_direct(&build)?;
+    build.op.check_cancel()?;
+    let direct = build.direct()?;
+    build_direct(&direct)?;
 
-    Ok(())
+    Ok(build.output())
+}
+
+pub fn emit_manifest(
+    op: &op::Build,
+    android: &config::ConfigPlatformAndroid,
+    build_dir: &std::path::Path,
+) -> Result<std::path::PathBuf, op::BuildError> {
+    let build = Build::new(
+        op,
+        android,
+        build_dir,
+    );
+
+    op::mkdir(build.artifact_dir.as_path())?;
+    op::update_file(
+        build.manifest_file.as_path(),
+        build.manifest()?.as_bytes(),
+    )?;
+
+    Ok(build.manifest_file)
+}
+
+// Convert SDK errors into the platform errors carrying remediation hints.
+fn sdk_hint(v: sdk::SdkError) -> String {
+    match v {
+        sdk::SdkError::NoSdk(v) => BuildError::NoSdk(v).to_string(),
+        sdk::SdkError::InvalidSdk(v) => BuildError::InvalidSdk(v).to_string(),
+        sdk::SdkError::NoNdk(v) => BuildError::NoNdk(v).to_string(),
+        sdk::SdkError::InvalidNdk(v) => BuildError::InvalidNdk(v).to_string(),
+        sdk::SdkError::NoBuildTools(v) => BuildError::NoBuildTools(v).to_string(),
+        sdk::SdkError::InvalidBuildTools(v) => BuildError::InvalidBuildTools(v).to_string(),
+        sdk::SdkError::NoPlatform(v) => BuildError::NoPlatform(v).to_string(),
+        sdk::SdkError::InvalidPlatform(v) => BuildError::InvalidPlatform(v).to_string(),
+        sdk::SdkError::Uncaught(v) => v.to_string(),
+    }
+}
+
+/// Probe for the Android SDK and its components required by the given
+/// platform configurations, as well as the JDK and KDK. The SDK components
+/// are only probed if the SDK itself is available.
+pub fn doctor(
+    probe: &doctor::Probe,
+    android: &[&config::ConfigPlatformAndroid],
+    acc: &mut Vec<doctor::Check>,
+) {
+    let mut min_sdks = android.iter().map(|v| v.min_sdk).collect::<Vec<_>>();
+    min_sdks.sort_unstable();
+    min_sdks.dedup();
+
+    let v_sdk = match probe.var("ANDROID_HOME") {
+        None => Err(BuildError::NoAndroidHome(min_sdks.last().copied().unwrap_or(0)).to_string()),
+        Some(v) => sdk::Sdk::new(std::path::Path::new(&v)).map_err(sdk_hint),
+    };
+
+    match v_sdk {
+        Err(e) => acc.push(doctor::Check::fail("android-sdk", &e)),
+        Ok(v_sdk) => {
+            acc.push(doctor::Check::pass("android-sdk", &v_sdk.android_home().display()));
+
+            acc.push(match v_sdk.ndk(None) {
+                Ok(v) => doctor::Check::pass("android-ndk", &v.root().display()),
+                Err(e) => doctor::Check::fail("android-ndk", &sdk_hint(e)),
+            });
+            acc.push(match v_sdk.build_tools(None) {
+                Ok(v) => doctor::Check::pass("android-build-tools", &v.root().display()),
+                Err(e) => doctor::Check::fail("android-build-tools", &sdk_hint(e)),
+            });
+            for min_sdk in min_sdks {
+                acc.push(match v_sdk.platform(min_sdk) {
+                    Ok(v) => doctor::Check::pass("android-platform", &v.display()),
+                    Err(e) => doctor::Check::fail("android-platform", &sdk_hint(e)),
+                });
+            }
+        },
+    }
+
+    acc.push(match probe.var("JAVA_HOME") {
+        Some(v) => match sdk::Jdk::new(Some(std::path::Path::new(&v))).map_err(|v| *v) {
+            Ok(_) => doctor::Check::pass("jdk", &std::path::Path::new(&v).display()),
+            Err(sdk::JdkError::NoJdk(v)) => doctor::Check::fail("jdk", &BuildError::NoJdk(v)),
+            Err(sdk::JdkError::InvalidJdk(v)) => doctor::Check::fail("jdk", &BuildError::InvalidJdk(v)),
+        },
+        None => match probe.find_tool("javac") {
+            Some(v) => doctor::Check::pass("jdk", &v.display()),
+            None => doctor::Check::fail("jdk", &"No Java SDK available, set `JAVA_HOME` or install a JDK providing `javac`"),
+        },
+    });
+
+    acc.push(match probe.var("KOTLIN_HOME") {
+        Some(v) => match sdk::Kdk::new(Some(std::path::Path::new(&v))).map_err(|v| *v) {
+            Ok(_) => doctor::Check::pass("kdk", &std::path::Path::new(&v).display()),
+            Err(sdk::KdkError::NoKdk(v)) => doctor::Check::fail("kdk", &BuildError::NoKdk(v)),
+            Err(sdk::KdkError::InvalidKdk(v)) => doctor::Check::fail("kdk", &BuildError::InvalidKdk(v)),
+        },
+        None => match probe.find_tool("kotlinc") {
+            Some(v) => doctor::Check::pass("kdk", &v.display()),
+            None => doctor::Check::fail("kdk", &"No Kotlin SDK available, set `KOTLIN_HOME` or install a KDK providing `kotlinc`"),
+        },
+    });
+}
+
+/// Install all Android SDK components that are required by the given
+/// platform configurations, but missing from the SDK. Components are
+/// installed via `sdkmanager`, which requires the user to accept their
+/// licenses interactively. Yields the installed packages.
+///
+/// Nothing is installed if no valid SDK is available, since `sdkmanager`
+/// is part of the SDK. This is reported by `doctor()` instead.
+pub fn install_missing(
+    probe: &doctor::Probe,
+    android: &[&config::ConfigPlatformAndroid],
+    runner: &dyn op::ToolRunner,
+) -> Result<Vec<String>, op::BuildError> {
+    let mut min_sdks = android.iter().map(|v| v.min_sdk).collect::<Vec<_>>();
+    min_sdks.sort_unstable();
+    min_sdks.dedup();
+
+    let Some(v_sdk) = probe.var("ANDROID_HOME")
+        .and_then(|v| sdk::Sdk::new(std::path::Path::new(&v)).ok())
+    else {
+        return Ok(Vec::new());
+    };
+
+    let mut missing = Vec::new();
+    if let Err(sdk::SdkError::NoNdk(v)) = v_sdk.ndk(None) {
+        missing.push(sdkmanager::Component::Ndk(v));
+    }
+    if let Err(sdk::SdkError::NoBuildTools(v)) = v_sdk.build_tools(None) {
+        missing.push(sdkmanager::Component::BuildTools(v));
+    }
+    for min_sdk in min_sdks {
+        if let Err(sdk::SdkError::NoPlatform(v)) = v_sdk.platform(min_sdk) {
+            missing.push(sdkmanager::Component::Platform(v));
+        }
+    }
+
+    let mut acc = Vec::new();
+    for component in missing.iter() {
+        let query = sdkmanager::InstallQuery {
+            android_home: v_sdk.android_home(),
+            component: component,
+        };
+
+        acc.push(query.run(runner).map_err(|v| -> op::BuildError {
+            match v {
+                sdkmanager::Error::NoSdkmanager(v) => BuildError::NoSdkmanager(v).into(),
+                sdkmanager::Error::NoPackage(v) => BuildError::NoSdkPackage(v).into(),
+                sdkmanager::Error::Exec(v) => BuildError::SdkmanagerExec(v).into(),
+                sdkmanager::Error::Exit(v) => BuildError::SdkmanagerExit(v).into(),
+            }
+        })?);
+    }
+
+    Ok(acc)
+}
+
+// Yield the version of an SDK component as suitable for `sdkmanager`. If no
+// version was requested, a placeholder is yielded, since `sdkmanager`
+// requires an explicit version for versioned components. Available versions
+// are listed via `sdkmanager --list`.
+fn sdkmanager_version(v: Option<&std::ffi::OsStr>) -> std::borrow::Cow<'_, str> {
+    match v {
+        Some(v) => v.to_string_lossy(),
+        None => "<version>".into(),
+    }
 }
 
 impl core::fmt::Display for BuildError {
@@ -771,18 +1490,19 @@ impl core::fmt::Display for BuildError {
             BuildError::UnsupportedPath(v) => fmt.write_fmt(core::format_args!("Path contains characters not supported by the Android SDK: {}", v.to_string_lossy())),
             BuildError::UnsupportedHost => fmt.write_fmt(core::format_args!("Host platform not supported by the Android SDK")),
             BuildError::UnsupportedAbi(v) => fmt.write_fmt(core::format_args!("ABI not supported by the Android SDK: {}", v)),
-            BuildError::NoAndroidHome => fmt.write_fmt(core::format_args!("No Android SDK available (`ANDROID_HOME` is not set)")),
+            BuildError::NoAndroidHome(v) => fmt.write_fmt(core::format_args!("No Android SDK available (`ANDROID_HOME` is not set), set it to an SDK root or create one via: sdkmanager --sdk_root=<path> \"platforms;android-{}\"", v)),
             BuildError::NoSdk(v) => fmt.write_fmt(core::format_args!("No Android SDK at: {}", v.to_string_lossy())),
             BuildError::InvalidSdk(v) => fmt.write_fmt(core::format_args!("Invalid Android SDK at: {}", v.to_string_lossy())),
             BuildError::NoJdk(v) => fmt.write_fmt(core::format_args!("No Java SDK at: {}", v.to_string_lossy())),
             BuildError::InvalidJdk(v) => fmt.write_fmt(core::format_args!("Invalid Java SDK at: {}", v.to_string_lossy())),
             BuildError::NoKdk(v) => fmt.write_fmt(core::format_args!("No Kotlin SDK at: {}", v.to_string_lossy())),
             BuildError::InvalidKdk(v) => fmt.write_fmt(core::format_args!("Invalid Kotlin SDK at: {}", v.to_string_lossy())),
-            BuildError::NoNdk => fmt.write_fmt(core::format_args!("No NDK in the Android SDK")),
+            BuildError::NoNdk(v) => fmt.write_fmt(core::format_args!("No NDK in the Android SDK, install it via: sdkmanager \"ndk;{}\"", sdkmanager_version(v.as_deref()))),
             BuildError::InvalidNdk(v) => fmt.write_fmt(core::format_args!("Invalid Android NDK at: {}", v.to_string_lossy())),
-            BuildError::NoBuildTools => fmt.write_fmt(core::format_args!("No build-tools in the Android SDK")),
+            BuildError::NoLibcxxShared(v) => fmt.write_fmt(core::format_args!("No shared C++ runtime in the Android NDK at: {}", v.to_string_lossy())),
+            BuildError::NoBuildTools(v) => fmt.write_fmt(core::format_args!("No build-tools in the Android SDK, install them via: sdkmanager \"build-tools;{}\"", sdkmanager_version(v.as_deref()))),
             BuildError::InvalidBuildTools(v) => fmt.write_fmt(core::format_args!("Invalid Android build-tools at: {}", v.to_string_lossy())),
-            BuildError::NoPlatform(v) => fmt.write_fmt(core::format_args!("No platform in the Android SDK for API-level: {}", v)),
+            BuildError::NoPlatform(v) => fmt.write_fmt(core::format_args!("No platform in the Android SDK for API-level {}, install it via: sdkmanager \"platforms;android-{}\"", v, v)),
             BuildError::InvalidPlatform(v) => fmt.write_fmt(core::format_args!("Invalid Android platform for API-level: {}", v)),
             BuildError::FlatresExec(e) => fmt.write_fmt(core::format_args!("Flatres compiler could not commence: {}", e)),
             BuildError::FlatresExit(e) => fmt.write_fmt(core::format_args!("Flatres compiler failed: {}", e)),
@@ -792,8 +1512,661 @@ impl core::fmt::Display for BuildError {
             BuildError::KotlincExit(e) => fmt.write_fmt(core::format_args!("Kotlin compiler failed: {}", e)),
             BuildError::DexExec(e) => fmt.write_fmt(core::format_args!("DEX compiler could not commence: {}", e)),
             BuildError::DexExit(e) => fmt.write_fmt(core::format_args!("DEX compiler failed: {}", e)),
-            BuildError::AaptExec(e) => fmt.write_fmt(core::format_args!("APT linker could not commence: {}", e)),
-            BuildError::AaptExit(e) => fmt.write_fmt(core::format_args!("APT linker failed: {}", e)),
+            BuildError::NoSdkmanager(v) => fmt.write_fmt(core::format_args!("No `sdkmanager` in the Android SDK, install the Android command-line tools to provide: {}", v.display())),
+            BuildError::NoSdkPackage(v) => fmt.write_fmt(core::format_args!("No installable version of the Android SDK package available: {}", v)),
+            BuildError::SdkmanagerExec(e) => fmt.write_fmt(core::format_args!("Android SDK manager could not commence: {}", e)),
+            BuildError::SdkmanagerExit(e) => fmt.write_fmt(core::format_args!("Android SDK manager failed: {}", e)),
+            BuildError::ManifestRead(path, e) => fmt.write_fmt(core::format_args!("Cannot read Android manifest ({}): {}", path.to_string_lossy(), e)),
+            BuildError::ManifestInvalid(v) => fmt.write_fmt(core::format_args!("Android manifest lacks a <manifest> root element: {}", v.to_string_lossy())),
+            BuildError::ManifestMalformed(v, line) => fmt.write_fmt(core::format_args!("Android manifest is malformed or uses unsupported XML features at line {}: {}", line, v.to_string_lossy())),
+            BuildError::ManifestAmbiguous(a, b) => fmt.write_fmt(core::format_args!("Multiple Android manifests provided: {} and {}", a.to_string_lossy(), b.to_string_lossy())),
+            BuildError::ArtifactCollision { abi, file_name, first, second } => fmt.write_fmt(core::format_args!("Artifacts of `{}` and `{}` collide on file name for ABI {}: {}", first, second, abi, file_name.to_string_lossy())),
+            BuildError::ResourceCollision { file_name, first, second } => fmt.write_fmt(core::format_args!("Resources {} and {} collide on compiled file name: {}", first.display(), second.display(), file_name.to_string_lossy())),
+            BuildError::ApkInvalid(v) => fmt.write_fmt(core::format_args!("Intermediate APK is not a supported ZIP archive: {}", v.display())),
+            BuildError::ApkOverflow => fmt.write_fmt(core::format_args!("APK exceeds the size limits of ZIP archives")),
+            BuildError::ApkDuplicate(v) => fmt.write_fmt(core::format_args!("Duplicate APK entry: {}", v)),
+            BuildError::ApkEntryName(v) => fmt.write_fmt(core::format_args!("Artifact file name is not valid UTF-8: {}", v.to_string_lossy())),
+            BuildError::NoKeystore(v) => fmt.write_fmt(core::format_args!("No keystore configured to sign Android builds of profile `{}`, set `keystore` of the Android platform", v)),
+            BuildError::NoKeystorePhrase(v) => fmt.write_fmt(core::format_args!("No pass phrase for the Android keystore in environment variable: {}", v)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Create metadata of the package `foobar` with an Android platform of
+    // the given configuration, building into `target_directory`. Null
+    // values are dropped, so optional settings can be passed as `Option`.
+    fn android_metadata(
+        mut android: serde_json::Value,
+        target_directory: &str,
+    ) -> cargo::Metadata {
+        if let serde_json::Value::Object(ref mut map) = android {
+            map.retain(|_, v| !v.is_null());
+        }
+
+        cargo::Metadata {
+            target_directory: target_directory.into(),
+            ..cargo::Metadata::from_osiris_json(&serde_json::json!({
+                "platforms": [
+                    {
+                        "id": "android",
+                        "android": android,
+                    },
+                ],
+            })).unwrap()
+        }
+    }
+
+    // Run `f` with the Android build of the given metadata and arguments,
+    // building into `build_dir` and invoking tools via `runner`.
+    fn with_build<R>(
+        arguments: &cargo::Arguments,
+        metadata: &cargo::Metadata,
+        runner: Option<&dyn op::ToolRunner>,
+        build_dir: &std::path::Path,
+        f: impl FnOnce(&Build<'_>) -> R,
+    ) -> Result<R, config::Error> {
+        op::with_build(arguments, metadata, "android", runner, |op| {
+            let config::ConfigPlatformConfiguration::Android(ref android) = op.platform.configuration else {
+                panic!("Platform must be an Android platform");
+            };
+            f(&Build::new(&op, android, build_dir))
+        })
+    }
+
+    // Verify that native libraries of a single ABI are keyed by file name,
+    // and colliding file names are reported with both origins, including
+    // extra libraries from outside of Cargo.
+    #[test]
+    fn collect_libs_collision() {
+        let artifact = |package_id: &str, path: &str| cargo::BuildArtifact {
+            is_executable: false,
+            package_id: package_id.into(),
+            path: path.into(),
+        };
+        let libs = |artifacts, extra| Libs {
+            build: cargo::Build { artifacts: artifacts, messages: Vec::new() },
+            extra: extra,
+        };
+
+        let set = libs(
+            vec![
+                artifact("foo (...)", "/target/foo/libfoo.so"),
+                artifact("bar (...)", "/target/bar/libbar.so"),
+            ],
+            vec!["/ndk/libc++_shared.so".into()],
+        );
+        let map = collect_libs("x86_64", &set).unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map[std::ffi::OsStr::new("libfoo.so")].0, "foo (...)");
+        assert_eq!(map[std::ffi::OsStr::new("libbar.so")].0, "bar (...)");
+        assert_eq!(
+            map[std::ffi::OsStr::new("libc++_shared.so")].1,
+            std::path::Path::new("/ndk/libc++_shared.so"),
+        );
+
+        let set = libs(
+            vec![
+                artifact("foo (...)", "/target/foo/libfoo.so"),
+                artifact("bar (...)", "/target/bar/libfoo.so"),
+            ],
+            Vec::new(),
+        );
+        match collect_libs("x86_64", &set) {
+            Err(BuildError::ArtifactCollision { abi, file_name, first, second }) => {
+                assert_eq!(abi, "x86_64");
+                assert_eq!(file_name, "libfoo.so");
+                assert_eq!(first, "foo (...)");
+                assert_eq!(second, "bar (...)");
+            },
+            _ => panic!("Colliding artifacts must be rejected"),
+        }
+
+        let set = libs(
+            vec![artifact("cxx (...)", "/target/cxx/libc++_shared.so")],
+            vec!["/ndk/libc++_shared.so".into()],
+        );
+        match collect_libs("x86_64", &set) {
+            Err(BuildError::ArtifactCollision { first, second, .. }) => {
+                assert_eq!(first, "cxx (...)");
+                assert_eq!(second, "/ndk/libc++_shared.so");
+            },
+            _ => panic!("Extra libraries colliding with artifacts must be rejected"),
+        }
+    }
+
+    // Verify that resource files are keyed by their compiled output, and
+    // resources of different directories colliding on it are rejected.
+    #[test]
+    fn collect_resources_collision() {
+        let dir = op::TestDir::new("resources");
+        let out = dir.join("out");
+        for v in ["res0/values", "res0/drawable", "res1/values", "res1/values-de"] {
+            op::mkdir(&dir.join(v)).unwrap();
+        }
+        std::fs::write(dir.join("res0/values/strings.xml"), "").unwrap();
+        std::fs::write(dir.join("res0/drawable/icon.png"), "").unwrap();
+        std::fs::write(dir.join("res1/values-de/strings.xml"), "").unwrap();
+
+        let dirs = [dir.join("res0"), dir.join("res1")];
+        let res = collect_resources(dirs.iter(), &out).unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(
+            res.into_iter().collect::<Vec<_>>(),
+            [
+                (out.join("drawable_icon.png.flat"), dir.join("res0/drawable/icon.png")),
+                (out.join("values-de_strings.arsc.flat"), dir.join("res1/values-de/strings.xml")),
+                (out.join("values_strings.arsc.flat"), dir.join("res0/values/strings.xml")),
+            ],
+        );
+
+        std::fs::write(dir.join("res1/values/strings.xml"), "").unwrap();
+        match collect_resources(dirs.iter(), &out) {
+            Err(op::BuildError::AndroidPlatform(BuildError::ResourceCollision { file_name, first, second })) => {
+                assert_eq!(file_name, "values_strings.arsc.flat");
+                assert_eq!(first, dir.join("res0/values/strings.xml"));
+                assert_eq!(second, dir.join("res1/values/strings.xml"));
+            },
+            _ => panic!("Colliding resources must be rejected"),
+        }
+    }
+
+    // Verify that resources are recompiled based on their content rather
+    // than their timestamps, and that disappeared resources are dropped.
+    #[test]
+    fn update_resources_hashed() {
+        let dir = op::TestDir::new("resources-hashed");
+        let out = dir.join("out");
+        let manifest = out.join("resources.json");
+        op::mkdir(&dir.join("res/values")).unwrap();
+        op::mkdir(&out).unwrap();
+        let strings = dir.join("res/values/strings.xml");
+        let colors = dir.join("res/values/colors.xml");
+        std::fs::write(&strings, "a").unwrap();
+        std::fs::write(&colors, "a").unwrap();
+
+        // Compile by writing the output next to the manifest.
+        let update = |compiled: &mut Vec<std::path::PathBuf>| {
+            let res = collect_resources([dir.join("res")].iter(), &out).unwrap_or_else(|e| panic!("{}", e));
+            update_resources(&res, &manifest, &mut |from| {
+                compiled.push(from.into());
+                let file = flatres::Query::output_file_name(from).unwrap();
+                std::fs::write(out.join(file), "").unwrap();
+                Ok(())
+            }).unwrap_or_else(|e| panic!("{}", e))
+        };
+        let mut compiled = Vec::new();
+
+        assert!(update(&mut compiled));
+        assert_eq!(compiled, [colors.clone(), strings.clone()]);
+
+        // Rewriting the same content does not trigger recompilation.
+        compiled.clear();
+        std::fs::write(&strings, "a").unwrap();
+        assert!(!update(&mut compiled));
+        assert!(compiled.is_empty());
+
+        // Modified content is recompiled, even with an older timestamp.
+        let mtime = std::fs::metadata(&strings).unwrap().modified().unwrap();
+        std::fs::write(&strings, "b").unwrap();
+        std::fs::File::options().write(true).open(&strings).unwrap()
+            .set_modified(mtime - std::time::Duration::from_secs(3600)).unwrap();
+        assert!(update(&mut compiled));
+        assert_eq!(compiled, [strings.as_path()]);
+
+        // Unchanged size and timestamp skip hashing, so such modifications
+        // are not detected.
+        compiled.clear();
+        let mtime = std::fs::metadata(&strings).unwrap().modified().unwrap();
+        std::fs::write(&strings, "c").unwrap();
+        std::fs::File::options().write(true).open(&strings).unwrap()
+            .set_modified(mtime).unwrap();
+        assert!(!update(&mut compiled));
+        assert!(compiled.is_empty());
+
+        // Missing outputs are recompiled.
+        compiled.clear();
+        std::fs::remove_file(out.join("values_colors.arsc.flat")).unwrap();
+        assert!(update(&mut compiled));
+        assert_eq!(compiled, [colors.as_path()]);
+
+        // Disappeared resources are dropped with their outputs.
+        compiled.clear();
+        std::fs::remove_file(&colors).unwrap();
+        assert!(!update(&mut compiled));
+        assert!(!out.join("values_colors.arsc.flat").exists());
+        let entries = load_resource_manifest(&manifest);
+        assert_eq!(entries.keys().collect::<Vec<_>>(), [&strings.to_string_lossy().into_owned()]);
+        assert_eq!(entries.values().next().unwrap().output, "values_strings.arsc.flat");
+    }
+
+    // Verify native method names are mangled as specified by JNI.
+    #[test]
+    fn jni_symbols() {
+        assert_eq!(jni_symbol("com.example.MainActivity", "osirisMain"), "Java_com_example_MainActivity_osirisMain");
+        assert_eq!(jni_symbol("com/my_app/Main$1", "run_it"), "Java_com_my_1app_Main_000241_run_1it");
+        assert_eq!(jni_symbol("a.ä", "😀"), "Java_a__000e4__0d83d_0de00");
+    }
+
+    // Verify that the main activity is generated with the configured
+    // package and library, and skipped if a package provides its own.
+    #[test]
+    fn main_activity() {
+        let dir = op::TestDir::new("main-activity");
+        let user_dir = dir.join("kotlin");
+        op::mkdir(&user_dir).unwrap();
+
+        let metadata = |library: Option<&str>, sets: Vec<cargo::MetadataAndroid>| cargo::Metadata {
+            android_sets: sets,
+            package_id: "foo-bar (...)".into(),
+            package_name: "foo-bar".into(),
+            ..android_metadata(
+                serde_json::json!({
+                    "namespace": "com.example.app",
+                    "min-sdk": 31,
+                    "main-activity": true,
+                    "main-activity-library": library,
+                }),
+                dir.to_str().unwrap(),
+            )
+        };
+        let prepare = |metadata: &cargo::Metadata| -> Option<String> {
+            with_build(&Default::default(), metadata, None, &dir.join("build"), |build| {
+                build.prepare().unwrap_or_else(|e| panic!("{}", e));
+
+                let path = build.java_dir.join("com/example/app/MainActivity.java");
+                std::fs::read_to_string(path).ok()
+            }).unwrap()
+        };
+
+        let java = prepare(&metadata(Some("foo_native"), Vec::new())).unwrap();
+        assert!(java.starts_with("package com.example.app;\n"));
+        assert!(java.contains("        System.loadLibrary(\"foo_native\");\n"));
+        assert!(java.contains("    private native void osirisMain();\n"));
+        assert!(java.contains("`Java_com_example_app_MainActivity_osirisMain`"));
+
+        // The library defaults to the symbolized application ID.
+        let java = prepare(&metadata(None, Vec::new())).unwrap();
+        assert!(java.contains("        System.loadLibrary(\"foo_bar\");\n"));
+
+        // A user-provided main activity replaces the generated one.
+        std::fs::write(user_dir.join("MainActivity.kt"), "").unwrap();
+        let set = cargo::MetadataAndroid {
+            java_dirs: Vec::new(),
+            kotlin_dirs: vec![user_dir.clone()],
+            manifest_file: None,
+            resource_dirs: Vec::new(),
+        };
+        assert_eq!(prepare(&metadata(None, vec![set])), None);
+
+        // Library names are embedded in Java and must be identifiers.
+        let arguments = Default::default();
+        assert!(matches!(
+            config::Config::from_cargo(&arguments, &metadata(Some("foo\"bar"), Vec::new())),
+            Err(config::Error::InvalidLibrary(ref v)) if v == "foo\"bar",
+        ));
+    }
+
+    // Verify that the build output reports the signed APK as final artifact.
+    #[test]
+    fn build_output() {
+        let metadata = android_metadata(
+            serde_json::json!({
+                "namespace": "com.example",
+                "min-sdk": 31,
+            }),
+            "/target",
+        );
+        let build_dir = std::path::Path::new("/target/osiris/build/android");
+        let output = with_build(&Default::default(), &metadata, None, build_dir, |build| {
+            build.output()
+        }).unwrap();
+
+        assert_eq!(output.artifacts.len(), 1);
+        assert_eq!(
+            output.path(op::ArtifactKind::AndroidApk).unwrap(),
+            build_dir.join("artifacts/package-signed.apk"),
+        );
+        assert!(output.path(op::ArtifactKind::MacosBundle).is_none());
+    }
+
+    // Verify that a manifest provided by a package is used verbatim, or
+    // merged if configured, and the generated manifest otherwise.
+    #[test]
+    fn manifest_user() {
+        let manifest = |sets: Vec<cargo::MetadataAndroid>, merge: bool| -> Result<String, String> {
+            let metadata = cargo::Metadata {
+                android_sets: sets,
+                ..android_metadata(
+                    serde_json::json!({
+                        "namespace": "com.example",
+                        "min-sdk": 31,
+                        "manifest-merge": merge,
+                    }),
+                    "/target",
+                )
+            };
+            let build_dir = std::path::Path::new("/target/osiris/build/android");
+            with_build(&Default::default(), &metadata, None, build_dir, |build| {
+                build.manifest().map_err(|e| e.to_string())
+            }).unwrap()
+        };
+        let set = |path: &std::path::Path| cargo::MetadataAndroid {
+            java_dirs: Vec::new(),
+            kotlin_dirs: Vec::new(),
+            manifest_file: Some(path.into()),
+            resource_dirs: Vec::new(),
+        };
+
+        let dir = op::TestDir::new("manifest-user");
+        let path = dir.join("AndroidManifest.xml");
+        let user = "<manifest package=\"com.example.user\">\n    <uses-permission android:name=\"android.permission.INTERNET\" />\n</manifest>\n";
+        std::fs::write(&path, user).unwrap();
+
+        // Without user manifest, the generated one is used.
+        let generated = manifest(Vec::new(), false).unwrap();
+        assert!(generated.contains("android.intent.category.LAUNCHER"));
+
+        // User manifests are used verbatim, or get the launcher merged.
+        assert_eq!(manifest(vec![set(&path)], false).unwrap(), user);
+        let merged = manifest(vec![set(&path)], true).unwrap();
+        assert!(merged.starts_with("<manifest package=\"com.example.user\">\n    <uses-permission"));
+        assert!(merged.contains("    <application>\n        <activity\n            android:name=\".MainActivity\"\n"));
+        assert!(merged.ends_with("        </activity>\n    </application>\n</manifest>\n"));
+        std::fs::write(&path, &merged).unwrap();
+        assert_eq!(manifest(vec![set(&path)], true).unwrap(), merged);
+
+        // Invalid, malformed, missing, and ambiguous user manifests are
+        // rejected.
+        std::fs::write(&path, "<application />").unwrap();
+        assert!(manifest(vec![set(&path)], false).unwrap_err().contains("lacks a <manifest> root element"));
+        std::fs::write(&path, "<manifest>\n    <application>\n</manifest>\n").unwrap();
+        assert!(manifest(vec![set(&path)], true).unwrap_err().contains("unsupported XML features at line 3"));
+        assert!(manifest(vec![set(&dir.join("missing.xml"))], false).unwrap_err().contains("Cannot read Android manifest"));
+        assert!(manifest(vec![set(&path), set(&path)], false).unwrap_err().contains("Multiple Android manifests"));
+    }
+
+    // Verify that generated manifests use the configured package, SDK
+    // versions, permissions, and launcher activity.
+    #[test]
+    fn manifest_generated() {
+        let manifest = |class: Option<&str>, generate: bool| -> Result<String, config::Error> {
+            let metadata = android_metadata(
+                serde_json::json!({
+                    "application-id": "com.example.app",
+                    "namespace": "com.example",
+                    "min-sdk": 26,
+                    "target-sdk": 34,
+                    "main-activity": generate,
+                    "main-activity-class": class,
+                    "permissions": [
+                        "android.permission.INTERNET",
+                        "android.permission.CAMERA",
+                    ],
+                }),
+                "/target",
+            );
+            let build_dir = std::path::Path::new("/target/osiris/build/android");
+            with_build(&Default::default(), &metadata, None, build_dir, |build| {
+                build.manifest().unwrap_or_else(|e| panic!("{}", e))
+            })
+        };
+
+        let generated = manifest(None, true).unwrap();
+        assert!(generated.contains("    package=\"com.example\"\n"));
+        assert!(generated.contains("        android:minSdkVersion=\"26\"\n        android:targetSdkVersion=\"34\" />\n"));
+        assert!(generated.contains(concat!(
+            "    <uses-permission android:name=\"android.permission.INTERNET\" />\n",
+            "    <uses-permission android:name=\"android.permission.CAMERA\" />\n",
+            "    <application\n",
+        )));
+        assert!(generated.contains("        tools:targetApi=\"34\">\n"));
+        assert!(generated.contains("            android:name=\".MainActivity\"\n"));
+        assert!(!generated.contains("com.example.app"));
+
+        // Custom launcher classes can be used without generated activity.
+        let generated = manifest(Some("org.example.Launcher"), false).unwrap();
+        assert!(generated.contains("            android:name=\"org.example.Launcher\"\n"));
+        assert!(manifest(Some("com.example.MainActivity"), true).is_ok());
+
+        // Class names must be valid, and match a generated activity.
+        assert!(matches!(
+            manifest(Some("org.example.Launcher"), true),
+            Err(config::Error::InvalidActivity(ref v)) if v == "org.example.Launcher",
+        ));
+        assert!(matches!(manifest(Some(".Main\"Activity"), false), Err(config::Error::InvalidActivity(_))));
+        assert!(matches!(manifest(Some("com..Main"), false), Err(config::Error::InvalidActivity(_))));
+        assert!(matches!(manifest(Some(".1Main"), false), Err(config::Error::InvalidActivity(_))));
+    }
+
+    // Verify that release keystores are used with pass phrases from the
+    // environment, and the debug keystore only serves debug builds.
+    #[test]
+    fn signing_keystore() {
+        let signing = |keystore: Option<&str>, profile: Option<&str>, env: &[(&str, &str)]| {
+            let metadata = android_metadata(
+                serde_json::json!({
+                    "namespace": "com.example",
+                    "min-sdk": 31,
+                    "keystore": keystore,
+                    "keystore-key-alias": "upload",
+                    "keystore-phrase-env": "TEST_KS_PASS",
+                    "key-phrase-env": "TEST_KEY_PASS",
+                }),
+                "/target",
+            );
+            let arguments = cargo::Arguments {
+                profile: profile.map(|v| v.into()),
+                ..Default::default()
+            };
+            let build_dir = std::path::Path::new("/target/osiris/build/android");
+            with_build(&arguments, &metadata, None, build_dir, |build| {
+                build.signing(|v| {
+                    env.iter().find(|e| e.0 == v).map(|e| e.1.into())
+                }).map(|v| (v.keystore, v.key_alias, v.phrase, v.key_phrase))
+            }).unwrap()
+        };
+
+        // Debug builds fall back to the debug keystore.
+        for profile in [None, Some("dev")] {
+            let (path, alias, phrase, _) = signing(None, profile, &[]).unwrap();
+            assert_eq!(path, std::path::Path::new("/target/osiris/build/android/artifacts/debug.keystore"));
+            assert_eq!(alias.as_deref(), Some(keystore::DEBUG_KEY_ALIAS));
+            assert_eq!(phrase, keystore::DEBUG_PHRASE);
+        }
+        assert!(matches!(
+            signing(None, Some("release"), &[]),
+            Err(BuildError::NoKeystore(ref v)) if v == "release",
+        ));
+
+        // Configured keystores are used for all builds, but require their
+        // pass phrases to be set.
+        let env = [("TEST_KS_PASS", "foo"), ("TEST_KEY_PASS", "bar")];
+        for profile in [None, Some("release")] {
+            let (path, alias, phrase, key_phrase) = signing(Some("/keys/release.jks"), profile, &env).unwrap();
+            assert_eq!(path, std::path::Path::new("/keys/release.jks"));
+            assert_eq!(alias.as_deref(), Some("upload"));
+            assert_eq!(phrase, "foo");
+            assert_eq!(key_phrase.unwrap(), "bar");
+        }
+        assert!(matches!(
+            signing(Some("/keys/release.jks"), Some("release"), &env[..1]),
+            Err(BuildError::NoKeystorePhrase(ref v)) if v == "TEST_KEY_PASS",
+        ));
+        assert!(matches!(
+            signing(Some("/keys/release.jks"), Some("release"), &env[1..]),
+            Err(BuildError::NoKeystorePhrase(ref v)) if v == "TEST_KS_PASS",
+        ));
+    }
+
+    // Drive the direct build pipeline with a fake tool runner and a fake
+    // SDK, and verify the sequence of tool invocations as well as the
+    // propagation of tool failures. The NDK only ships prebuilt toolchains
+    // for some hosts, so this is limited to one of them.
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn build_direct_tools() {
+        let dir = op::TestDir::new("direct");
+        let sdk_dir = dir.join("sdk");
+        for v in ["licenses", "ndk/26.1.10909125", "build-tools/34.0.0", "platforms/android-31"] {
+            op::mkdir(&sdk_dir.join(v)).unwrap();
+        }
+        std::fs::write(sdk_dir.join("licenses/android-sdk-license"), "").unwrap();
+        std::fs::write(sdk_dir.join("platforms/android-31/android.jar"), "").unwrap();
+        std::fs::write(dir.join("libfoobar.so"), "").unwrap();
+
+        let metadata = android_metadata(
+            serde_json::json!({
+                "namespace": "com.example",
+                "min-sdk": 31,
+                "abis": ["x86_64"],
+            }),
+            dir.to_str().unwrap(),
+        );
+
+        // Cargo invocations report a single library artifact.
+        let lib = dir.join("libfoobar.so");
+        let stdout = move |call: &[String]| match call[0].as_str() {
+            "cargo" => format!(
+                concat!(
+                    r#"{{"reason":"compiler-artifact","package_id":"foobar (...)","#,
+                    r#""target":{{"kind":["cdylib"]}},"filenames":["{}"]}}"#, "\n",
+                    r#"{{"reason":"build-finished","success":true}}"#, "\n",
+                ),
+                lib.display(),
+            ),
+            _ => String::new(),
+        };
+
+        for fail in [None, Some("apksigner")] {
+            let mut runner = op::FakeRunner::new(stdout.clone());
+            runner.fail = fail;
+            with_build(&Default::default(), &metadata, Some(&runner), &dir.join("build"), |build| {
+                build.prepare().unwrap_or_else(|e| panic!("{}", e));
+
+                // The fake tools produce no files, so provide the outputs of
+                // the tools that are consumed by later steps.
+                std::fs::write(&build.apk_base_file, zip::Writer::new().finish().unwrap()).unwrap();
+                std::fs::write(&build.classes_dex_file, "").unwrap();
+
+                let direct = build.direct_at(&sdk_dir).unwrap_or_else(|e| panic!("{}", e));
+                let r = build_direct(&direct);
+
+                match fail {
+                    None => {
+                        r.unwrap_or_else(|e| panic!("{}", e));
+                        assert_eq!(runner.tools(), ["aapt2", "cargo", "apksigner"]);
+                        let apk = std::fs::read(&build.apk_aligned_file).unwrap();
+                        assert!(apk.windows(20).any(|v| v == b"lib/x86_64/libfoobar"));
+                        assert!(apk.windows(11).any(|v| v == b"classes.dex"));
+                    },
+                    Some(_) => {
+                        assert!(matches!(r, Err(op::BuildError::Exit(ref v, _)) if v == "apksigner"));
+                        assert_eq!(runner.tools(), ["aapt2", "cargo", "apksigner"]);
+                    },
+                }
+            }).unwrap();
+        }
+    }
+
+    // Verify that the per-ABI configuration reaches the Cargo query of the
+    // matching ABI only, and overrides the computed linker environment.
+    #[test]
+    fn cargo_query_targets() {
+        let dir = op::TestDir::new("targets");
+        let sdk_dir = dir.join("sdk");
+        for v in ["licenses", "ndk/26.1.10909125", "build-tools/34.0.0", "platforms/android-31"] {
+            op::mkdir(&sdk_dir.join(v)).unwrap();
         }
+        std::fs::write(sdk_dir.join("licenses/android-sdk-license"), "").unwrap();
+        std::fs::write(sdk_dir.join("platforms/android-31/android.jar"), "").unwrap();
+
+        let metadata = |abis: serde_json::Value| android_metadata(
+            serde_json::json!({
+                "namespace": "com.example",
+                "min-sdk": 31,
+                "abis": abis,
+                "targets": {
+                    "arm64-v8a": {
+                        "rustc-args": ["-Clink-arg=-lc++_shared"],
+                        "envs": {
+                            "CARGO_TARGET_AARCH64_LINUX_ANDROID_LINKER": "/custom/clang",
+                            "CC": "clang",
+                        },
+                        "cfgs": ["foo", "bar=\"v\""],
+                        "libcxx-shared": true,
+                    },
+                },
+            }),
+            dir.to_str().unwrap(),
+        );
+        let metadata_ok = metadata(serde_json::json!(["arm64-v8a", "x86_64"]));
+        let arguments = Default::default();
+        with_build(&arguments, &metadata_ok, None, &dir.join("build"), |build| {
+            let direct = build.direct_at(&sdk_dir).unwrap_or_else(|e| panic!("{}", e));
+            let ndk = direct.ndk.root().to_path_buf();
+
+            let (query, libcxx) = direct.cargo_query("linux-x86_64", "arm64-v8a")
+                .unwrap_or_else(|e| panic!("{}", e));
+            assert_eq!(query.target.as_deref(), Some("aarch64-linux-android"));
+            assert_eq!(
+                query.envs,
+                vec![
+                    (
+                        "CARGO_TARGET_AARCH64_LINUX_ANDROID_LINKER".into(),
+                        ndk.join("toolchains/llvm/prebuilt/linux-x86_64/bin/aarch64-linux-android31-clang").into(),
+                    ),
+                    ("CARGO_TARGET_AARCH64_LINUX_ANDROID_LINKER".into(), "/custom/clang".into()),
+                    ("CC".into(), "clang".into()),
+                ],
+            );
+            assert_eq!(query.cfgs, vec![("foo".into(), None), ("bar".into(), Some("v".into()))]);
+            assert_eq!(query.rustc_args, vec!["-Clink-arg=-lc++_shared".to_string()]);
+            assert_eq!(
+                libcxx,
+                Some(ndk.join("toolchains/llvm/prebuilt/linux-x86_64/sysroot/usr/lib/aarch64-linux-android/libc++_shared.so")),
+            );
+
+            // ABIs without configuration only get the computed linker.
+            let (query, libcxx) = direct.cargo_query("linux-x86_64", "x86_64")
+                .unwrap_or_else(|e| panic!("{}", e));
+            assert_eq!(query.target.as_deref(), Some("x86_64-linux-android"));
+            assert_eq!(query.envs.len(), 1);
+            assert!(query.cfgs.is_empty() && query.rustc_args.is_empty() && libcxx.is_none());
+        }).unwrap();
+
+        // Configuration of ABIs that are not built is rejected.
+        assert!(matches!(
+            config::Config::from_cargo(&arguments, &metadata(serde_json::json!(["x86_64"]))),
+            Err(config::Error::InvalidTarget(ref v)) if v == "arm64-v8a",
+        ));
+    }
+
+    // Verify that missing SDK components suggest the matching `sdkmanager`
+    // invocation, using the requested version if available.
+    #[test]
+    fn sdkmanager_hints() {
+        assert_eq!(
+            BuildError::NoNdk(Some("26.1.10909125".into())).to_string(),
+            "No NDK in the Android SDK, install it via: sdkmanager \"ndk;26.1.10909125\"",
+        );
+        assert_eq!(
+            BuildError::NoNdk(None).to_string(),
+            "No NDK in the Android SDK, install it via: sdkmanager \"ndk;<version>\"",
+        );
+        assert_eq!(
+            BuildError::NoBuildTools(Some("34.0.0".into())).to_string(),
+            "No build-tools in the Android SDK, install them via: sdkmanager \"build-tools;34.0.0\"",
+        );
+        assert_eq!(
+            BuildError::NoBuildTools(None).to_string(),
+            "No build-tools in the Android SDK, install them via: sdkmanager \"build-tools;<version>\"",
+        );
+        assert!(
+            BuildError::NoAndroidHome(31).to_string()
+                .ends_with("sdkmanager --sdk_root=<path> \"platforms;android-31\""),
+        );
     }
 }
@@ -5,7 +5,7 @@
 //!
 //! This module allows creation, modification and inspection of APKs.
 
-use crate::platform::android;
+use crate::{op, platform::android};
 
 /// ## Link Error
 ///
@@ -16,7 +16,7 @@ pub enum LinkError {
     /// Program execution failed with the given error.
     Exec(std::io::Error),
     /// Program exited with a failure condition.
-    Exit(std::process::ExitStatus),
+    Exit(op::ToolExit),
 }
 
 /// ## APK Link Query
@@ -36,64 +36,13 @@ pub struct LinkQuery {
     pub output_file: std::path::PathBuf,
     /// Output path for the generated java resource classes.
     pub output_java_dir: Option<std::path::PathBuf>,
+    /// Package name to rename the manifest package to, retaining the
+    /// original package for resource classes.
+    pub rename_package: Option<String>,
     /// Resource files to link into the APK.
     pub resource_files: Vec<std::path::PathBuf>,
 }
 
-/// ## Alter Error
-///
-/// This is the error-enum of all possible errors raised by this
-/// alteration abstraction.
-#[derive(Debug)]
-pub enum AlterError {
-    /// Program execution failed with the given error.
-    Exec(std::io::Error),
-    /// Program exited with a failure condition.
-    Exit(std::process::ExitStatus),
-}
-
-/// ## APK Alteration Query
-///
-/// This represents the parameters to an APK alteration operation. It is to
-/// be filled in by the caller.
-pub struct AlterQuery {
-    /// Base directory to run the command in (to ensure relative paths are
-    /// anchored correctly). Note that paths are retained in the APK, so
-    /// very likely relative paths are desired.
-    pub base_dir: Option<std::path::PathBuf>,
-    /// Android SDK build tools to use for the link.
-    pub build_tools: android::sdk::BuildTools,
-    /// Files to add to the APK.
-    pub add_files: Vec<std::path::PathBuf>,
-    /// APK file to modify.
-    pub apk_file: std::path::PathBuf,
-}
-
-/// ## Align Error
-///
-/// This is the error-enum of all possible errors raised by this
-/// align abstraction.
-#[derive(Debug)]
-pub enum AlignError {
-    /// Program execution failed with the given error.
-    Exec(std::io::Error),
-    /// Program exited with a failure condition.
-    Exit(std::process::ExitStatus),
-}
-
-/// ## APK Align Query
-///
-/// This represents the parameters to an APK align operation. It is to
-/// be filled in by the caller.
-pub struct AlignQuery {
-    /// Android SDK build tools to use for the link.
-    pub build_tools: android::sdk::BuildTools,
-    /// Input path for the unaligned APK.
-    pub input_file: std::path::PathBuf,
-    /// Output path for the aligned APK.
-    pub output_file: std::path::PathBuf,
-}
-
 /// ## Sign Error
 ///
 /// This is the error-enum of all possible errors raised by this
@@ -103,7 +52,7 @@ pub enum SignError {
     /// Program execution failed with the given error.
     Exec(std::io::Error),
     /// Program exited with a failure condition.
-    Exit(std::process::ExitStatus),
+    Exit(op::ToolExit),
 }
 
 /// ## APK Sign Query
@@ -120,19 +69,35 @@ pub struct SignQuery {
     /// Key alias of the key in the keystore.
     pub keystore_key_alias: Option<String>,
     /// Pass phrase for the keystore.
-    pub keystore_phrase: Option<String>,
+    pub keystore_phrase: Option<std::ffi::OsString>,
     /// Pass phrase for the key.
-    pub key_phrase: Option<String>,
+    pub key_phrase: Option<std::ffi::OsString>,
     /// Output path for the signed APK.
     pub output_file: std::path::PathBuf,
 }
 
+/// ## Check for Uncompressed Entries
+///
+/// Return whether an APK entry at the given path should be stored
+/// uncompressed, given the list of file extensions to store uncompressed.
+/// Extensions are compared ASCII case-insensitively.
+///
+/// Uncompressed entries must be aligned in the final APK, so Android can
+/// map them directly. The APK writer aligns all uncompressed entries to 4
+/// bytes, and uncompressed native libraries to the page size.
+pub fn stored(path: &std::path::Path, extensions: &[String]) -> bool {
+    match path.extension() {
+        None => false,
+        Some(ext) => extensions.iter().any(|v| ext.eq_ignore_ascii_case(v)),
+    }
+}
+
 impl LinkQuery {
     /// ## Run `aapt2` linker
     ///
     /// Run the `aapt2` APK linker, producing an APK for the given input
     /// resources.
-    pub fn run(&self) -> Result<(), LinkError> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), LinkError> {
         // Set up basic `aapt2 link` command.
         let mut cmd = std::process::Command::new(
             self.build_tools.aapt2()
@@ -167,6 +132,12 @@ impl LinkQuery {
             cmd.arg(v);
         }
 
+        // Specify the final package name.
+        if let Some(ref v) = self.rename_package {
+            cmd.arg("--rename-manifest-package");
+            cmd.arg(v);
+        }
+
         // Append all input resource files. Ensure that they start with a
         // proper path prefix, since `aapt2` does not support `--` separators.
         for v in &self.resource_files {
@@ -178,93 +149,9 @@ impl LinkQuery {
         cmd.stderr(std::process::Stdio::inherit());
 
         // Run and verify it exited successfully.
-        let output = cmd.output().map_err(|v| LinkError::Exec(v))?;
-        if !output.status.success() {
-            return Err(LinkError::Exit(output.status));
-        }
-
-        // Not interested in the output of the tool.
-        drop(output);
-
-        Ok(())
-    }
-}
-
-impl AlterQuery {
-    /// ## Run `aapt` alteration
-    ///
-    /// Run the `aapt` APK tool to alter an existing APK.
-    pub fn run(&self) -> Result<(), AlterError> {
-        // Set up basic `aapt add` command.
-        let mut cmd = std::process::Command::new(
-            self.build_tools.aapt()
-        );
-        cmd.args([
-            "add",
-        ]);
-
-        // Append path to the APK, but ensure proper path prefixes.
-        cmd.arg(std::path::Path::new(".").join(&self.apk_file));
-
-        // Append all input resource files. Pass them verbatim. While `--` is
-        // not supported as separator, the caller should ensure the paths are
-        // provided suitably.
-        for v in &self.add_files {
-            cmd.arg(v);
-        }
-
-        // Set the working directory for `aapt` to allow paths to be specified
-        // as relative paths, given that they are retained verbatim in the
-        // target archive.
-        if let Some(ref v) = self.base_dir {
-            cmd.current_dir(v);
-        }
-
-        // Always forward diagnostics to the parent error stream, so
-        // the user can inspect them.
-        cmd.stderr(std::process::Stdio::inherit());
-
-        // Run and verify it exited successfully.
-        let output = cmd.output().map_err(|v| AlterError::Exec(v))?;
-        if !output.status.success() {
-            return Err(AlterError::Exit(output.status));
-        }
-
-        // Not interested in the output of the tool.
-        drop(output);
-
-        Ok(())
-    }
-}
-
-impl AlignQuery {
-    /// ## Run `zipalign`
-    ///
-    /// Run the `zipalign` APK tool to align an existing APK.
-    pub fn run(&self) -> Result<(), AlignError> {
-        // Set up basic `zipalign` command.
-        let mut cmd = std::process::Command::new(
-            self.build_tools.zipalign()
-        );
-        cmd.args([
-            "-f",
-            "-p",
-            "-v",
-            "4",
-        ]);
-
-        // Append paths to the APK, but ensure proper path prefixes.
-        cmd.arg(std::path::Path::new(".").join(&self.input_file));
-        cmd.arg(std::path::Path::new(".").join(&self.output_file));
-
-        // Always forward diagnostics to the parent error stream, so
-        // the user can inspect them.
-        cmd.stderr(std::process::Stdio::inherit());
-
-        // Run and verify it exited successfully.
-        let output = cmd.output().map_err(|v| AlignError::Exec(v))?;
+        let output = runner.run(&mut cmd).map_err(|v| LinkError::Exec(v))?;
         if !output.status.success() {
-            return Err(AlignError::Exit(output.status));
+            return Err(LinkError::Exit((&output).into()));
         }
 
         // Not interested in the output of the tool.
@@ -278,7 +165,7 @@ impl SignQuery {
     /// ## Run `apksigner`
     ///
     /// Run the `apksigner` APK tool to sign an existing APK.
-    pub fn run(&self) -> Result<(), SignError> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), SignError> {
         // Set up basic `apksigner` command.
         let mut cmd = std::process::Command::new(
             self.build_tools.apksigner()
@@ -324,9 +211,9 @@ impl SignQuery {
         cmd.stderr(std::process::Stdio::inherit());
 
         // Run and verify it exited successfully.
-        let output = cmd.output().map_err(|v| SignError::Exec(v))?;
+        let output = runner.run(&mut cmd).map_err(|v| SignError::Exec(v))?;
         if !output.status.success() {
-            return Err(SignError::Exit(output.status));
+            return Err(SignError::Exit((&output).into()));
         }
 
         // Not interested in the output of the tool.
@@ -335,3 +222,22 @@ impl SignQuery {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify the selection of uncompressed entries by file extension.
+    #[test]
+    fn stored_extensions() {
+        let extensions = vec!["so".to_string(), "ARSC".to_string()];
+
+        assert!(stored(std::path::Path::new("lib/x86_64/libfoo.so"), &extensions));
+        assert!(stored(std::path::Path::new("lib/x86_64/libfoo.SO"), &extensions));
+        assert!(stored(std::path::Path::new("resources.arsc"), &extensions));
+        assert!(!stored(std::path::Path::new("classes.dex"), &extensions));
+        assert!(!stored(std::path::Path::new("lib/x86_64/so"), &extensions));
+        assert!(!stored(std::path::Path::new("lib/x86_64/libfoo.so.1"), &extensions));
+        assert!(!stored(std::path::Path::new("lib/x86_64/libfoo.so"), &[]));
+    }
+}
@@ -3,7 +3,7 @@
 //! This module provides helpers to compile java byte-code into the DEX format
 //! using the Android D8 compiler.
 
-use crate::platform::android;
+use crate::{op, platform::android};
 
 /// ## Compilation Error
 ///
@@ -14,7 +14,7 @@ pub enum Error {
     /// Program execution failed with the given error.
     Exec(std::io::Error),
     /// Program exited with a failure condition.
-    Exit(std::process::ExitStatus),
+    Exit(op::ToolExit),
 }
 
 /// ## D8 Compiler Query
@@ -51,7 +51,7 @@ where
     ///
     /// Run the `d8` compiler to compile the specified source files for
     /// the configured Android Platform.
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), Error> {
         // Set up basic `d8` command.
         let mut cmd = std::process::Command::new(
             self.build_tools.d8(),
@@ -105,9 +105,9 @@ where
         cmd.stderr(std::process::Stdio::inherit());
 
         // Run and verify it exited successfully.
-        let output = cmd.output().map_err(|v| Error::Exec(v))?;
+        let output = runner.run(&mut cmd).map_err(|v| Error::Exec(v))?;
         if !output.status.success() {
-            return Err(Error::Exit(output.status));
+            return Err(Error::Exit((&output).into()));
         }
 
         // Not interested in the output of the tool.
@@ -5,7 +5,7 @@
 //! fast lookups before assembling an APK. This module provides helpers to
 //! deal with `aapt2`, the compiler for flat resource files.
 
-use crate::platform::android;
+use crate::{op, platform::android};
 
 /// ## Compilation Error
 ///
@@ -19,7 +19,7 @@ pub enum Error {
     /// Program execution failed with the given error.
     Exec(std::io::Error),
     /// Program exited with a failure condition.
-    Exit(std::process::ExitStatus),
+    Exit(op::ToolExit),
 }
 
 /// ## Flat Resource Compiler Query
@@ -155,7 +155,7 @@ impl Query {
     ///
     /// Run the `aapt2` flat resource compiler, producing a flat resource for
     /// the given resource input.
-    pub fn run(&self) -> Result<std::path::PathBuf, Error> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<std::path::PathBuf, Error> {
         let output_file = self.output_dir.join(
             Self::output_file_name(&self.resource_file).ok_or_else(
                 || Error::InvalidPath(self.resource_file.clone()),
@@ -187,9 +187,9 @@ impl Query {
         cmd.stderr(std::process::Stdio::inherit());
 
         // Run and verify it exited successfully.
-        let output = cmd.output().map_err(|v| Error::Exec(v))?;
+        let output = runner.run(&mut cmd).map_err(|v| Error::Exec(v))?;
         if !output.status.success() {
-            return Err(Error::Exit(output.status));
+            return Err(Error::Exit((&output).into()));
         }
 
         // Not interested in the output of the tool.
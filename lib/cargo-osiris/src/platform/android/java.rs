@@ -3,7 +3,7 @@
 //! This module provides helpers to compile java code for the Android
 //! Platform.
 
-use crate::platform::android;
+use crate::{op, platform::android};
 
 /// ## Compilation Error
 ///
@@ -16,7 +16,7 @@ pub enum Error {
     /// Program execution failed with the given error.
     Exec(std::io::Error),
     /// Program exited with a failure condition.
-    Exit(std::process::ExitStatus),
+    Exit(op::ToolExit),
 }
 
 /// ## Java Compiler Query
@@ -45,7 +45,7 @@ where
     ///
     /// Run the `javac` compiler to compile the specified source files for
     /// the configured Android Platform.
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), Error> {
         // Set up basic `javac` command.
         let mut cmd = self.jdk.javac();
 
@@ -76,9 +76,9 @@ where
         cmd.stderr(std::process::Stdio::inherit());
 
         // Run and verify it exited successfully.
-        let output = cmd.output().map_err(|v| Error::Exec(v))?;
+        let output = runner.run(&mut cmd).map_err(|v| Error::Exec(v))?;
         if !output.status.success() {
-            return Err(Error::Exit(output.status));
+            return Err(Error::Exit((&output).into()));
         }
 
         // Not interested in the output of the tool.
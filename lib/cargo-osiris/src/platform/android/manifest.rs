@@ -0,0 +1,357 @@
+//! # Android Manifest Inspection
+//!
+//! Packages can provide their own Android manifest, which optionally gets
+//! the generated launcher activity merged in. This module provides a minimal
+//! scanner for the element structure of manifests, which is sufficient to
+//! validate and merge them without a full XML parser.
+//!
+//! The scanner recognizes start, end, and empty-element tags with their
+//! attributes, verifies their nesting, and skips comments, CDATA sections,
+//! processing instructions, and document type declarations. It does not
+//! support internal subsets of document type declarations, does not expand
+//! entity references, and matches namespace prefixes literally. Hence, the
+//! Android namespace must be bound to the `android` prefix, as is done by
+//! all Android tooling.
+
+// Category of intent filters that mark the launcher activity.
+const CATEGORY_LAUNCHER: &str = "android.intent.category.LAUNCHER";
+
+/// ## Manifest Error
+///
+/// This is the error-enum of all possible errors raised when inspecting a
+/// manifest.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error {
+    /// Manifest is not well-formed, or uses unsupported XML features. The
+    /// byte offset of the offending markup is provided.
+    Malformed(usize),
+    /// Manifest lacks a `<manifest>` root element.
+    NoManifest,
+}
+
+// Kind of a tag in a manifest.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TagKind {
+    Start,
+    End,
+    Empty,
+}
+
+// Tag in a manifest, with its byte range in the document. Attribute values
+// are provided verbatim, without expanding entity references.
+#[derive(Debug)]
+struct Tag<'doc> {
+    kind: TagKind,
+    name: &'doc str,
+    attributes: Vec<(&'doc str, &'doc str)>,
+    range: core::ops::Range<usize>,
+}
+
+impl<'doc> Tag<'doc> {
+    fn attribute(&self, name: &str) -> Option<&'doc str> {
+        self.attributes.iter().find(|v| v.0 == name).map(|v| v.1)
+    }
+}
+
+// Scan all tags of a document and verify their nesting and the presence of
+// a single root element.
+fn scan(doc: &str) -> Result<Vec<Tag<'_>>, Error> {
+    let mut tags = Vec::new();
+    let mut stack = Vec::new();
+    let mut roots = 0;
+    let mut pos = 0;
+
+    while let Some(idx) = doc[pos..].find('<') {
+        let start = pos + idx;
+        let rest = &doc[start..];
+
+        let skip = [("<!--", "-->"), ("<![CDATA[", "]]>"), ("<?", "?>"), ("<!", ">")]
+            .into_iter()
+            .find(|v| rest.starts_with(v.0));
+        if let Some((open, close)) = skip {
+            let len = rest.find(close).ok_or(Error::Malformed(start))?;
+            if open == "<!" && rest[..len].contains('[') {
+                return Err(Error::Malformed(start));
+            }
+            pos = start + len + close.len();
+            continue;
+        }
+
+        let tag = scan_tag(doc, start)?;
+        match tag.kind {
+            TagKind::Start | TagKind::Empty => {
+                if stack.is_empty() {
+                    roots += 1;
+                }
+                if tag.kind == TagKind::Start {
+                    stack.push(tag.name);
+                }
+            },
+            TagKind::End => {
+                if stack.pop() != Some(tag.name) {
+                    return Err(Error::Malformed(start));
+                }
+            },
+        }
+        pos = tag.range.end;
+        tags.push(tag);
+    }
+
+    if !stack.is_empty() || roots > 1 {
+        return Err(Error::Malformed(doc.len()));
+    }
+
+    Ok(tags)
+}
+
+// Scan the tag starting at `start`, which must point to its opening angle
+// bracket.
+fn scan_tag(doc: &str, start: usize) -> Result<Tag<'_>, Error> {
+    let err = || Error::Malformed(start);
+    let is_delimiter = |c: char| c.is_ascii_whitespace() || c == '/' || c == '>' || c == '=';
+
+    let (kind, mut pos) = match doc[start..].starts_with("</") {
+        true => (TagKind::End, start + 2),
+        false => (TagKind::Start, start + 1),
+    };
+
+    let len = doc[pos..].find(is_delimiter).ok_or_else(err)?;
+    let name = &doc[pos..pos + len];
+    if name.is_empty() {
+        return Err(err());
+    }
+    pos += len;
+
+    let mut attributes = Vec::new();
+    loop {
+        pos += doc[pos..].len() - doc[pos..].trim_start().len();
+        let rest = &doc[pos..];
+
+        if rest.starts_with('>') {
+            return Ok(Tag { kind, name, attributes, range: start..pos + 1 });
+        } else if rest.starts_with("/>") && kind == TagKind::Start {
+            return Ok(Tag { kind: TagKind::Empty, name, attributes, range: start..pos + 2 });
+        } else if kind == TagKind::End {
+            return Err(err());
+        }
+
+        let len = rest.find(is_delimiter).ok_or_else(err)?;
+        let key = &rest[..len];
+        let rest = rest[len..].trim_start().strip_prefix('=').ok_or_else(err)?.trim_start();
+        let quote = rest.chars().next().filter(|v| *v == '"' || *v == '\'').ok_or_else(err)?;
+        let len = rest[1..].find(quote).ok_or_else(err)?;
+        if key.is_empty() {
+            return Err(err());
+        }
+
+        attributes.push((key, &rest[1..1 + len]));
+        pos = doc.len() - rest.len() + len + 2;
+    }
+}
+
+// Offset of the start of the line containing `idx`, if `idx` is only
+// preceded by indentation on its line.
+fn line_start(doc: &str, idx: usize) -> Option<usize> {
+    let head = doc[..idx].trim_end_matches([' ', '\t']).len();
+    (head == 0 || doc[..head].ends_with('\n')).then_some(head)
+}
+
+/// Verify that a manifest is well-formed and has a `<manifest>` root
+/// element.
+pub fn check(doc: &str) -> Result<(), Error> {
+    match scan(doc)?.first() {
+        Some(v) if v.kind == TagKind::Start && v.name == "manifest" => Ok(()),
+        _ => Err(Error::NoManifest),
+    }
+}
+
+/// Merge a launcher activity into a manifest, unless the application of the
+/// manifest declares a launcher itself. The activity must be indented for
+/// the `<application>` element, and is inserted at its end. An application
+/// element is created if missing, and empty-element tags are expanded.
+pub fn merge(doc: &str, activity: &str) -> Result<String, Error> {
+    check(doc)?;
+
+    let tags = scan(doc)?;
+    let mut stack: Vec<&str> = Vec::new();
+    let mut application = None;
+    let mut manifest_end = doc.len();
+
+    for tag in &tags {
+        if tag.kind == TagKind::End {
+            stack.pop();
+            if stack.is_empty() {
+                manifest_end = tag.range.start;
+            } else if stack == ["manifest"] && tag.name == "application" {
+                application.get_or_insert(tag);
+            }
+            continue;
+        }
+
+        // An application can only have a single launcher, so any category
+        // of an intent filter of an activity (or its alias) suffices.
+        if tag.name == "category"
+            && tag.attribute("android:name") == Some(CATEGORY_LAUNCHER)
+            && stack.len() == 4
+            && stack[..2] == ["manifest", "application"]
+            && (stack[2] == "activity" || stack[2] == "activity-alias")
+            && stack[3] == "intent-filter"
+        {
+            return Ok(doc.into());
+        }
+
+        if stack == ["manifest"] && tag.name == "application" && tag.kind == TagKind::Empty {
+            application.get_or_insert(tag);
+        }
+        if tag.kind == TagKind::Start {
+            stack.push(tag.name);
+        }
+    }
+
+    let mut v = String::with_capacity(doc.len() + activity.len() + 64);
+    match application {
+        Some(tag) if tag.kind == TagKind::Empty => {
+            let indent = match line_start(doc, tag.range.start) {
+                Some(head) => &doc[head..tag.range.start],
+                None => "    ",
+            };
+            v.push_str(doc[..tag.range.end - 2].trim_end());
+            v.push_str(">\n");
+            v.push_str(activity);
+            v.push_str(indent);
+            v.push_str("</application>");
+            v.push_str(&doc[tag.range.end..]);
+        },
+        Some(tag) => {
+            insert_lines(&mut v, doc, tag.range.start, &[activity]);
+        },
+        None => {
+            insert_lines(
+                &mut v,
+                doc,
+                manifest_end,
+                &["    <application>\n", activity, "    </application>\n"],
+            );
+        },
+    }
+
+    Ok(v)
+}
+
+// Append `doc` to `v` with `lines` inserted at the start of the line of
+// `idx`, or on a new line right before `idx` if other markup precedes it on
+// its line.
+fn insert_lines(v: &mut String, doc: &str, idx: usize, lines: &[&str]) {
+    let (idx, separator) = match line_start(doc, idx) {
+        Some(head) => (head, ""),
+        None => (idx, "\n"),
+    };
+    v.push_str(&doc[..idx]);
+    v.push_str(separator);
+    for line in lines {
+        v.push_str(line);
+    }
+    v.push_str(&doc[idx..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTIVITY: &str = "        <activity android:name=\".MainActivity\" />\n";
+
+    // Verify well-formed manifests are accepted and malformed ones are
+    // rejected with the offset of the offending markup.
+    #[test]
+    fn manifest_check() {
+        assert_eq!(check("<manifest></manifest>"), Ok(()));
+        assert_eq!(
+            check(concat!(
+                "<?xml version=\"1.0\"?>\n",
+                "<!DOCTYPE manifest>\n",
+                "<!-- <application> -->\n",
+                "<manifest a='>' b = \"/>\"><![CDATA[</manifest>]]></manifest>\n",
+            )),
+            Ok(()),
+        );
+
+        assert_eq!(check(""), Err(Error::NoManifest));
+        assert_eq!(check("<manifest />"), Err(Error::NoManifest));
+        assert_eq!(check("<application></application>"), Err(Error::NoManifest));
+        assert_eq!(check("<manifest>"), Err(Error::Malformed(10)));
+        assert_eq!(check("<manifest></manifest><manifest />"), Err(Error::Malformed(33)));
+        assert_eq!(check("<manifest><a></b></manifest>"), Err(Error::Malformed(13)));
+        assert_eq!(check("<manifest a></manifest>"), Err(Error::Malformed(0)));
+        assert_eq!(check("<manifest a=b></manifest>"), Err(Error::Malformed(0)));
+        assert_eq!(check("<manifest><!-- </manifest>"), Err(Error::Malformed(10)));
+        assert_eq!(check("<!DOCTYPE m [<!ENTITY a 'b'>]><manifest />"), Err(Error::Malformed(0)));
+    }
+
+    // Verify the launcher activity is inserted at the end of the
+    // application element, which is created or expanded as needed.
+    #[test]
+    fn manifest_merge() {
+        assert_eq!(
+            merge("<manifest>\n    <application>\n    </application>\n</manifest>\n", ACTIVITY).unwrap(),
+            concat!(
+                "<manifest>\n    <application>\n",
+                "        <activity android:name=\".MainActivity\" />\n",
+                "    </application>\n</manifest>\n",
+            ),
+        );
+        assert_eq!(
+            merge("<manifest>\n  <application android:label=\"A\" />\n</manifest>\n", ACTIVITY).unwrap(),
+            concat!(
+                "<manifest>\n  <application android:label=\"A\">\n",
+                "        <activity android:name=\".MainActivity\" />\n",
+                "  </application>\n</manifest>\n",
+            ),
+        );
+        assert_eq!(
+            merge("<manifest><uses-sdk /></manifest>", ACTIVITY).unwrap(),
+            concat!(
+                "<manifest><uses-sdk />\n    <application>\n",
+                "        <activity android:name=\".MainActivity\" />\n",
+                "    </application>\n</manifest>",
+            ),
+        );
+
+        // Nested application elements of other tags are not considered.
+        let v = merge("<manifest><queries><application /></queries></manifest>", ACTIVITY).unwrap();
+        assert!(v.ends_with("</queries>\n    <application>\n        <activity android:name=\".MainActivity\" />\n    </application>\n</manifest>"));
+
+        assert_eq!(merge("<application />", ACTIVITY), Err(Error::NoManifest));
+    }
+
+    // Verify manifests with a launcher are left untouched, while mentions
+    // of the launcher category elsewhere are ignored.
+    #[test]
+    fn manifest_merge_launcher() {
+        let launcher = concat!(
+            "<manifest>\n",
+            "    <application>\n",
+            "        <activity-alias android:name=\".Alias\">\n",
+            "            <intent-filter>\n",
+            "                <category android:name='android.intent.category.LAUNCHER' />\n",
+            "            </intent-filter>\n",
+            "        </activity-alias>\n",
+            "    </application>\n",
+            "</manifest>\n",
+        );
+        assert_eq!(merge(launcher, ACTIVITY).unwrap(), launcher);
+
+        let mention = concat!(
+            "<manifest>\n",
+            "    <!-- android.intent.category.LAUNCHER -->\n",
+            "    <queries>\n",
+            "        <intent>\n",
+            "            <category android:name=\"android.intent.category.LAUNCHER\" />\n",
+            "        </intent>\n",
+            "    </queries>\n",
+            "    <application>\n",
+            "    </application>\n",
+            "</manifest>\n",
+        );
+        assert!(merge(mention, ACTIVITY).unwrap().contains(ACTIVITY));
+    }
+}
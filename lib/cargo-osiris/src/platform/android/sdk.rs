@@ -3,7 +3,7 @@
 //! This module provides access to the Android SDK on a target machine. It
 //! allows invoking a wide range of functionality of the SDK.
 
-use crate::lib;
+use crate::{lib, misc};
 
 /// ## JDK Error
 ///
@@ -61,12 +61,14 @@ pub enum SdkError {
     NoSdk(std::path::PathBuf),
     /// Specified path is not a valid Android SDK.
     InvalidSdk(std::path::PathBuf),
-    /// No NDK component is available in the SDK.
-    NoNdk,
+    /// No NDK component is available in the SDK. Carries the requested
+    /// version, if any.
+    NoNdk(Option<std::ffi::OsString>),
     /// Specified NDK component is not available or invalid.
     InvalidNdk(std::ffi::OsString),
-    /// No Build Tools component is available in the SDK.
-    NoBuildTools,
+    /// No Build Tools component is available in the SDK. Carries the
+    /// requested version, if any.
+    NoBuildTools(Option<std::ffi::OsString>),
     /// Specified Build Tools component is not available or invalid.
     InvalidBuildTools(std::ffi::OsString),
     /// No platform component available for the given API version.
@@ -104,52 +106,45 @@ pub struct BuildTools {
     path: std::path::PathBuf,
 }
 
-// ## Return Directory Entry with Highest Natural Order
-//
-// Iterate the given directory and return the entry that orders highest of
-// all entries. If the directory is empty, then `Ok(None)` is returned.
-// If directory enumeration fails with an error, the error is propagated.
-//
-// Any non-unicode directory entries are ignored, since they cannot be
-// compared reasonably.
-fn dir_latest_entry(
+/// ## Resolve Newest Versioned Sub-directory
+///
+/// Iterate the given directory and return the sub-directory with the
+/// highest version, considering only versions accepted by the predicate.
+/// The names of all sub-directories are parsed as versions, and any entry
+/// that is not a directory or does not parse as version is ignored.
+///
+/// Returns `Ok(None)` if no suitable sub-directory exists. If directory
+/// enumeration fails, the error is propagated.
+pub fn newest_versioned<Predicate>(
     dir: &std::path::Path,
-) -> Result<Option<String>, Box<dyn std::error::Error>> {
-    std::fs::read_dir(dir)?
-        // Convert each entry into its Unicode file name, and drop any non
-        // Unicode file names.
-        .filter_map(|v| {
-            v.map(|v| {
-                v.file_name()
-                    .into_string()
-                    .ok()
-            }).transpose()
-        })
-        .reduce(move |acc, v| {
-            match (acc, v) {
-                // Preserve earliest error, if any.
-                (Err(acc), _) => Err(acc),
-                // Capture errors as soon as they happen.
-                (_, Err(v)) => Err(v),
-                // Compare new entry against accumulator.
-                (Ok(acc), Ok(v)) => {
-                    Ok(
-                        core::cmp::max_by(
-                            acc,
-                            v,
-                            |lhs, rhs| {
-                                lib::str::cmp_natural(
-                                    lhs.as_str(),
-                                    rhs.as_str(),
-                                )
-                            },
-                        ),
-                    )
-                },
-            }
-        })
-        .transpose()
-        .map_err(|v|v.into())
+    predicate: Predicate,
+) -> Result<Option<(misc::Version, std::path::PathBuf)>, std::io::Error>
+where
+    Predicate: Fn(&misc::Version) -> bool,
+{
+    let mut acc: Option<(misc::Version, std::path::PathBuf)> = None;
+
+    for iter in std::fs::read_dir(dir)? {
+        let entry = iter?;
+
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let Some(version) = entry.file_name().to_str().and_then(|v| v.parse().ok()) else {
+            continue;
+        };
+
+        if !predicate(&version) {
+            continue;
+        }
+
+        if acc.as_ref().map_or(true, |v| version > v.0) {
+            acc = Some((version, entry.path()));
+        }
+    }
+
+    Ok(acc)
 }
 
 impl From<lib::error::Uncaught> for SdkError {
@@ -460,20 +455,19 @@ impl Sdk {
         let mut path = self.android_home().join("ndk");
 
         if !path.is_dir() {
-            return Err(SdkError::NoNdk);
+            return Err(SdkError::NoNdk(version.map(|v| v.into())));
         }
 
         match version {
             // If no version is specified, we iterate all possible
-            // NDKs and pick the latest one.
+            // NDKs and pick the newest one.
             None => {
-                path.push(
-                    dir_latest_entry(path.as_path())
-                        .map_err(|v| -> SdkError {
-                            lib::error::Uncaught::fold_error(v).into()
-                        })?
-                        .ok_or_else(|| SdkError::NoNdk)?,
-                );
+                path = newest_versioned(path.as_path(), |_| true)
+                    .map_err(|v| -> SdkError {
+                        lib::error::Uncaught::box_error(v).into()
+                    })?
+                    .ok_or_else(|| SdkError::NoNdk(None))?
+                    .1;
             }
 
             // If a version is provided, it must be a single non-absolute
@@ -516,20 +510,19 @@ impl Sdk {
         let mut path = self.android_home().join("build-tools");
 
         if !path.is_dir() {
-            return Err(SdkError::NoBuildTools);
+            return Err(SdkError::NoBuildTools(version.map(|v| v.into())));
         }
 
         match version {
             // If no version is specified, we iterate all possible
-            // build-tools and pick the latest one.
+            // build-tools and pick the newest one.
             None => {
-                path.push(
-                    dir_latest_entry(path.as_path())
-                        .map_err(|v| -> SdkError {
-                            lib::error::Uncaught::fold_error(v).into()
-                        })?
-                        .ok_or_else(|| SdkError::NoBuildTools)?,
-                );
+                path = newest_versioned(path.as_path(), |_| true)
+                    .map_err(|v| -> SdkError {
+                        lib::error::Uncaught::box_error(v).into()
+                    })?
+                    .ok_or_else(|| SdkError::NoBuildTools(None))?
+                    .1;
             }
 
             // If a version is provided, it must be a single non-absolute
@@ -593,11 +586,11 @@ impl Ndk {
 }
 
 impl BuildTools {
-    /// ## Yield Path to `aapt` Binary
+    /// ## Yield Path to Build-Tools Root Directory
     ///
-    /// Yield the path to the `aapt` binary of this build-tools component.
-    pub fn aapt(&self) -> std::path::PathBuf {
-        self.path.join("aapt")
+    /// Yield the path to the root directory of the build-tools component.
+    pub fn root(&self) -> &std::path::Path {
+        self.path.as_path()
     }
 
     /// ## Yield Path to `aapt2` Binary
@@ -620,13 +613,6 @@ impl BuildTools {
     pub fn d8(&self) -> std::path::PathBuf {
         self.path.join("d8")
     }
-
-    /// ## Yield Path to `zipalign` Binary
-    ///
-    /// Yield the path to the `zipalign` binary of this build-tools component.
-    pub fn zipalign(&self) -> std::path::PathBuf {
-        self.path.join("zipalign")
-    }
 }
 
 #[cfg(test)]
@@ -674,4 +660,31 @@ mod tests {
             SdkError::InvalidSdk(_),
         ));
     }
+
+    // Verify that versioned sub-directories are ordered numerically, and
+    // that non-version entries and rejected versions are ignored.
+    #[test]
+    fn newest_versioned_order() {
+        let dir = crate::op::TestDir::new("sdk");
+        for v in ["9", "10", "10.0.1", "latest", "11-rc1"] {
+            std::fs::create_dir_all(dir.join(v)).unwrap();
+        }
+        std::fs::write(dir.join("12"), b"").unwrap();
+
+        let version = |v: &str| v.parse::<misc::Version>().unwrap();
+
+        assert_eq!(
+            newest_versioned(&dir, |_| true).unwrap(),
+            Some((version("10.0.1"), dir.join("10.0.1"))),
+        );
+        assert_eq!(
+            newest_versioned(&dir, |v| *v < version("10.0")).unwrap(),
+            Some((version("10"), dir.join("10"))),
+        );
+        assert_eq!(
+            newest_versioned(&dir, |v| *v < version("10")).unwrap(),
+            Some((version("9"), dir.join("9"))),
+        );
+        assert_eq!(newest_versioned(&dir, |v| *v < version("9")).unwrap(), None);
+    }
 }
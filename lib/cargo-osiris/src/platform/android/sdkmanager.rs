@@ -0,0 +1,225 @@
+//! # Android SDK Manager
+//!
+//! This module provides structured access to the `sdkmanager` utility of the
+//! Android command-line tools, which installs components into an Android
+//! SDK. Components are never installed implicitly. Callers must only use
+//! this on explicit request of the user, since installation requires the
+//! user to accept the license of the respective component.
+
+use crate::{misc, op};
+
+/// ## SDK Manager Error
+///
+/// This is the error-enum of all possible errors raised by the `sdkmanager`
+/// abstraction.
+#[derive(Debug)]
+pub enum Error {
+    /// There is no `sdkmanager` at the given path.
+    NoSdkmanager(std::path::PathBuf),
+    /// No installable version of the given package is available.
+    NoPackage(String),
+    /// Program execution failed with the given error.
+    Exec(std::io::Error),
+    /// Program exited with a failure condition.
+    Exit(op::ToolExit),
+}
+
+/// ## SDK Component
+///
+/// A versioned component of the Android SDK. If no version is given, the
+/// newest version available via `sdkmanager --list` is selected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Component {
+    /// Native Development Kit
+    Ndk(Option<std::ffi::OsString>),
+    /// Build-tools
+    BuildTools(Option<std::ffi::OsString>),
+    /// Platform of the given API-level
+    Platform(u32),
+}
+
+/// Combined arguments to an installation via `sdkmanager`.
+pub struct InstallQuery<'ctx> {
+    /// Root directory of the Android SDK to install into
+    pub android_home: &'ctx std::path::Path,
+    /// Component to install
+    pub component: &'ctx Component,
+}
+
+impl Component {
+    /// Yield the package prefix of the component as used by `sdkmanager`.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::Ndk(_) => "ndk",
+            Self::BuildTools(_) => "build-tools",
+            Self::Platform(_) => "platforms",
+        }
+    }
+
+    /// Yield the full package path of the component, or `None` if no
+    /// version was selected.
+    pub fn package(&self) -> Option<String> {
+        match self {
+            Self::Ndk(Some(v)) | Self::BuildTools(Some(v)) => {
+                Some(format!("{};{}", self.prefix(), v.to_string_lossy()))
+            },
+            Self::Ndk(None) | Self::BuildTools(None) => None,
+            Self::Platform(v) => Some(format!("{};android-{}", self.prefix(), v)),
+        }
+    }
+}
+
+// Select the newest stable version of all packages with the given prefix
+// listed by `sdkmanager --list`. Each package is listed on its own line as
+// `<path> | <version> | <description>`. Preview releases do not parse as
+// version, and are thus never selected.
+fn newest_package(list: &str, prefix: &str) -> Option<String> {
+    list.lines()
+        .filter_map(|v| v.split('|').next())
+        .map(|v| v.trim())
+        .filter_map(|v| {
+            let version = v.strip_prefix(prefix)?.strip_prefix(';')?;
+            Some((version.parse::<misc::Version>().ok()?, v))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|v| v.1.to_string())
+}
+
+impl<'ctx> InstallQuery<'ctx> {
+    /// Yield the path to `sdkmanager` of the latest command-line tools in
+    /// the Android SDK.
+    pub fn sdkmanager(&self) -> std::path::PathBuf {
+        let mut path = self.android_home.join("cmdline-tools/latest/bin/sdkmanager");
+        if cfg!(windows) {
+            path.set_extension("bat");
+        }
+        path
+    }
+
+    /// Assemble the `sdkmanager` command with the given arguments, operating
+    /// on the selected Android SDK.
+    pub fn command<Args>(&self, args: Args) -> std::process::Command
+    where
+        Args: IntoIterator,
+        <Args as IntoIterator>::Item: AsRef<std::ffi::OsStr>,
+    {
+        let mut sdk_root = std::ffi::OsString::from("--sdk_root=");
+        sdk_root.push(self.android_home);
+
+        let mut cmd = std::process::Command::new(self.sdkmanager());
+        cmd.arg(sdk_root);
+        cmd.args(args);
+        cmd
+    }
+
+    /// ## Resolve Package
+    ///
+    /// Yield the package path to install. If the component has no version
+    /// selected, the list of available packages is queried and the newest
+    /// version is used.
+    pub fn resolve(&self, runner: &dyn op::ToolRunner) -> Result<String, Error> {
+        if let Some(v) = self.component.package() {
+            return Ok(v);
+        }
+
+        let mut cmd = self.command(["--list"]);
+        cmd.stderr(std::process::Stdio::inherit());
+
+        let output = runner.run(&mut cmd).map_err(|v| Error::Exec(v))?;
+        if !output.status.success() {
+            return Err(Error::Exit((&output).into()));
+        }
+
+        newest_package(&String::from_utf8_lossy(&output.stdout), self.component.prefix())
+            .ok_or_else(|| Error::NoPackage(self.component.prefix().into()))
+    }
+
+    /// ## Run `sdkmanager`
+    ///
+    /// Install the selected component and yield the installed package path.
+    /// All standard streams are inherited, so progress is shown to the user
+    /// and licenses must be accepted interactively.
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<String, Error> {
+        if !self.sdkmanager().is_file() {
+            return Err(Error::NoSdkmanager(self.sdkmanager()));
+        }
+
+        let package = self.resolve(runner)?;
+
+        let mut cmd = self.command(["--install", package.as_str()]);
+        cmd.stdin(std::process::Stdio::inherit());
+        cmd.stdout(std::process::Stdio::inherit());
+        cmd.stderr(std::process::Stdio::inherit());
+
+        let output = runner.run(&mut cmd).map_err(|v| Error::Exec(v))?;
+        if !output.status.success() {
+            return Err(Error::Exit((&output).into()));
+        }
+
+        Ok(package)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify the `sdkmanager` arguments assembled to install missing
+    // components, with and without explicit versions.
+    #[test]
+    fn sdkmanager_arguments() {
+        let dir = op::TestDir::new("sdkmanager");
+        let home = dir.join("sdk");
+        let root = format!("--sdk_root={}", home.display());
+        // Listings report a fixed set of packages.
+        let runner = op::FakeRunner::new(|call| match call.iter().any(|v| v == "--list") {
+            true => String::from(concat!(
+                "Available Packages:\n",
+                "  Path                 | Version       | Description\n",
+                "  -------              | -------       | -------\n",
+                "  build-tools;34.0.0   | 34.0.0        | Android SDK Build-Tools 34\n",
+                "  ndk;25.2.9519653     | 25.2.9519653  | NDK (Side by side) 25.2.9519653\n",
+                "  ndk;26.1.10909125    | 26.1.10909125 | NDK (Side by side) 26.1.10909125\n",
+                "  ndk;27.0.11718014-rc1 | 27.0.11718014 rc1 | NDK (Side by side) 27.0.11718014\n",
+            )),
+            false => String::new(),
+        });
+
+        // Without `sdkmanager`, nothing is run.
+        let ndk = Component::Ndk(None);
+        let query = InstallQuery { android_home: &home, component: &ndk };
+        assert!(matches!(query.run(&runner), Err(Error::NoSdkmanager(_))));
+        assert!(runner.calls.borrow().is_empty());
+
+        op::mkdir(&home.join("cmdline-tools/latest/bin")).unwrap();
+        std::fs::write(query.sdkmanager(), "").unwrap();
+
+        // Unversioned components select the newest stable version.
+        assert_eq!(query.run(&runner).unwrap(), "ndk;26.1.10909125");
+        assert_eq!(
+            runner.calls.take(),
+            vec![
+                vec!["sdkmanager".into(), root.clone(), "--list".into()],
+                vec!["sdkmanager".into(), root.clone(), "--install".into(), "ndk;26.1.10909125".into()],
+            ],
+        );
+
+        // Versioned components are installed directly.
+        let build_tools = Component::BuildTools(Some("33.0.2".into()));
+        let query = InstallQuery { android_home: &home, component: &build_tools };
+        assert_eq!(query.run(&runner).unwrap(), "build-tools;33.0.2");
+        assert_eq!(
+            runner.calls.take(),
+            vec![vec!["sdkmanager".into(), root.clone(), "--install".into(), "build-tools;33.0.2".into()]],
+        );
+
+        let platform = Component::Platform(31);
+        assert_eq!(platform.package().unwrap(), "platforms;android-31");
+
+        // Only packages with the exact prefix are considered.
+        let build_tools = Component::BuildTools(None);
+        let query = InstallQuery { android_home: &home, component: &build_tools };
+        assert_eq!(query.resolve(&runner).unwrap(), "build-tools;34.0.0");
+        assert_eq!(newest_package("  ndk-bundle | 22.1.7171670 | NDK\n", "ndk"), None);
+    }
+}
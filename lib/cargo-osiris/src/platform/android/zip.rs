@@ -0,0 +1,753 @@
+//! # ZIP Archive Writer
+//!
+//! APKs are ZIP archives with the additional requirement that entries
+//! stored without compression are aligned, so Android can map them directly
+//! from the APK. This module provides a minimal writer that copies the
+//! entries of an existing archive and appends new entries. New entries are
+//! either stored without compression, or compressed with a simple deflate
+//! encoder that only uses the fixed Huffman codes. All stored entries are
+//! aligned via the alignment extra field also used by `zipalign` and
+//! `apksigner`.
+//!
+//! ZIP64 and multi-disk archives are not supported. Archive comments are
+//! dropped when copying an archive.
+
+// Signatures of the ZIP records.
+const SIGNATURE_LOCAL: u32 = 0x04034b50;
+const SIGNATURE_CENTRAL: u32 = 0x02014b50;
+const SIGNATURE_END: u32 = 0x06054b50;
+
+// Fixed lengths of the ZIP records, excluding variable-length fields.
+const LEN_LOCAL: usize = 30;
+const LEN_CENTRAL: usize = 46;
+const LEN_END: usize = 22;
+
+// ID of the extra field that pads an entry to the alignment it specifies.
+const EXTRA_ALIGNMENT: u16 = 0xd935;
+
+// General purpose flag signaling a data descriptor after the entry data.
+const FLAG_DESCRIPTOR: u16 = 1 << 3;
+
+// Compression methods of entries stored without compression, and of
+// deflated entries.
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+// Size of the deflate window, and the number of candidates of the hash
+// chains that are tried for each match.
+const DEFLATE_WINDOW: usize = 1 << 15;
+const DEFLATE_HASH: usize = 1 << 15;
+const DEFLATE_CHAIN: usize = 64;
+
+// Base values and number of extra bits of the deflate length codes 257 to
+// 285, and of the distance codes 0 to 29.
+const DEFLATE_LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const DEFLATE_LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DEFLATE_DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DEFLATE_DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+// Version 2.0 of the specification, created on MS-DOS. This is the version
+// needed to extract, as well as the version-made-by of new entries.
+const VERSION: u16 = 20;
+
+// Modification date of new entries, which is fixed to 1980-01-01 00:00 (the
+// earliest representable date) to keep archives reproducible.
+const DOS_DATE: u16 = 0x0021;
+const DOS_TIME: u16 = 0x0000;
+
+/// Alignment of stored entries, as required by Android.
+pub const ALIGNMENT: u16 = 4;
+
+/// Alignment of stored native libraries, so Android can map them with page
+/// granularity.
+pub const ALIGNMENT_PAGE: u16 = 4096;
+
+/// ## ZIP Error
+///
+/// This is the error-enum of all possible errors raised by the ZIP writer.
+#[derive(Debug)]
+pub enum Error {
+    /// Existing archive is malformed or uses unsupported features.
+    Invalid,
+    /// Archive exceeds the size limits of ZIP archives without ZIP64.
+    Overflow,
+    /// Entry with the given name is present multiple times.
+    Duplicate(String),
+}
+
+// Entry of an archive with all fields retained when copying it.
+struct Entry<'data> {
+    made_by: u16,
+    version: u16,
+    flags: u16,
+    method: u16,
+    time: u16,
+    date: u16,
+    crc: u32,
+    size: u32,
+    internal: u16,
+    external: u32,
+    name: &'data [u8],
+    extra: &'data [u8],
+    comment: &'data [u8],
+    content: &'data [u8],
+}
+
+/// ## ZIP Writer
+///
+/// Assemble a ZIP archive in memory. Entries are written in the order they
+/// are added, and the central directory is written on completion.
+#[derive(Default)]
+pub struct Writer {
+    data: Vec<u8>,
+    central: Vec<u8>,
+    names: std::collections::BTreeSet<Vec<u8>>,
+}
+
+// Bit stream of deflate data. Bits are packed starting with the least
+// significant bit of each byte.
+#[derive(Default)]
+struct Bits {
+    data: Vec<u8>,
+    acc: u32,
+    n: u32,
+}
+
+impl Bits {
+    fn put(&mut self, v: u32, n: u32) {
+        self.acc |= v << self.n;
+        self.n += n;
+        while self.n >= 8 {
+            self.data.push(self.acc as u8);
+            self.acc >>= 8;
+            self.n -= 8;
+        }
+    }
+
+    // Huffman codes are packed starting with their most significant bit.
+    fn put_code(&mut self, code: u32, n: u32) {
+        self.put(code.reverse_bits() >> (32 - n), n);
+    }
+
+    // Write a literal/length symbol with its fixed Huffman code.
+    fn put_symbol(&mut self, v: u32) {
+        match v {
+            0..=143 => self.put_code(0x30 + v, 8),
+            144..=255 => self.put_code(0x190 + v - 144, 9),
+            256..=279 => self.put_code(v - 256, 7),
+            _ => self.put_code(0xc0 + v - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.n > 0 {
+            self.data.push(self.acc as u8);
+        }
+        self.data
+    }
+}
+
+// Compress data as a single deflate block with fixed Huffman codes. Matches
+// are found greedily via hash chains over the last 3 bytes. This does not
+// reach the ratio of common deflate encoders, but avoids dynamic Huffman
+// trees entirely.
+fn deflate(src: &[u8]) -> Vec<u8> {
+    let mut bits = Bits::default();
+    let mut head = vec![usize::MAX; DEFLATE_HASH];
+    let mut prev = vec![usize::MAX; DEFLATE_WINDOW];
+    let hash = |p: usize| {
+        ((src[p] as usize) << 10 ^ (src[p + 1] as usize) << 5 ^ src[p + 2] as usize)
+            & (DEFLATE_HASH - 1)
+    };
+
+    // Final block with fixed Huffman codes.
+    bits.put(1, 1);
+    bits.put(1, 2);
+
+    let mut pos = 0;
+    while pos < src.len() {
+        let max = core::cmp::min(258, src.len() - pos);
+        let mut best = (0, 0);
+
+        if max >= 3 {
+            let mut candidate = head[hash(pos)];
+            let mut chain = DEFLATE_CHAIN;
+            while candidate != usize::MAX && pos - candidate <= DEFLATE_WINDOW && chain > 0 {
+                let len = src[candidate..].iter()
+                    .zip(&src[pos..pos + max])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best.0 {
+                    best = (len, pos - candidate);
+                    if len == max {
+                        break;
+                    }
+                }
+                candidate = prev[candidate % DEFLATE_WINDOW];
+                chain -= 1;
+            }
+        }
+
+        let n = if best.0 >= 3 {
+            let (len, distance) = best;
+            let l = DEFLATE_LENGTH_BASE.iter().rposition(|v| *v as usize <= len).unwrap();
+            let d = DEFLATE_DISTANCE_BASE.iter().rposition(|v| *v as usize <= distance).unwrap();
+            bits.put_symbol(257 + l as u32);
+            bits.put((len - DEFLATE_LENGTH_BASE[l] as usize) as u32, DEFLATE_LENGTH_EXTRA[l] as u32);
+            bits.put_code(d as u32, 5);
+            bits.put((distance - DEFLATE_DISTANCE_BASE[d] as usize) as u32, DEFLATE_DISTANCE_EXTRA[d] as u32);
+            len
+        } else {
+            bits.put_symbol(src[pos] as u32);
+            1
+        };
+
+        for p in pos..pos + n {
+            if p + 3 <= src.len() {
+                let h = hash(p);
+                prev[p % DEFLATE_WINDOW] = head[h];
+                head[h] = p;
+            }
+        }
+        pos += n;
+    }
+
+    bits.put_symbol(256);
+    bits.finish()
+}
+
+// Read a little-endian integer at the given offset, failing if out of
+// bounds.
+fn u16_at(src: &[u8], offset: usize) -> Result<u16, Error> {
+    let v = src.get(offset..).and_then(|v| v.get(..2)).ok_or(Error::Invalid)?;
+    Ok(u16::from_le_bytes([v[0], v[1]]))
+}
+
+fn u32_at(src: &[u8], offset: usize) -> Result<u32, Error> {
+    let v = src.get(offset..).and_then(|v| v.get(..4)).ok_or(Error::Invalid)?;
+    Ok(u32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+}
+
+// Yield the slice of the given length at the given offset, failing if out
+// of bounds.
+fn slice_at(src: &[u8], offset: usize, len: usize) -> Result<&[u8], Error> {
+    src.get(offset..).and_then(|v| v.get(..len)).ok_or(Error::Invalid)
+}
+
+// Parse all entries of an archive via its central directory. The end of
+// central directory record is followed by a comment of variable length, so
+// it is searched backwards from the end of the archive. Values that signal
+// ZIP64 extensions are rejected.
+fn entries(src: &[u8]) -> Result<Vec<Entry<'_>>, Error> {
+    let last = src.len().checked_sub(LEN_END).ok_or(Error::Invalid)?;
+    let end = (last.saturating_sub(0xffff)..=last).rev()
+        .find(|&v| u32_at(src, v).ok() == Some(SIGNATURE_END))
+        .ok_or(Error::Invalid)?;
+
+    let disk = u16_at(src, end + 4)?;
+    let disk_central = u16_at(src, end + 6)?;
+    let n_disk = u16_at(src, end + 8)?;
+    let n = u16_at(src, end + 10)?;
+    let offset = u32_at(src, end + 16)?;
+    if disk != 0 || disk_central != 0 || n_disk != n || n == 0xffff || offset == 0xffffffff {
+        return Err(Error::Invalid);
+    }
+
+    let mut pos = offset as usize;
+    let mut v = Vec::with_capacity(n as usize);
+    for _ in 0..n {
+        if u32_at(src, pos)? != SIGNATURE_CENTRAL {
+            return Err(Error::Invalid);
+        }
+
+        let compressed = u32_at(src, pos + 20)?;
+        let size = u32_at(src, pos + 24)?;
+        let n_name = u16_at(src, pos + 28)? as usize;
+        let n_extra = u16_at(src, pos + 30)? as usize;
+        let n_comment = u16_at(src, pos + 32)? as usize;
+        let local = u32_at(src, pos + 42)?;
+        if compressed == 0xffffffff || size == 0xffffffff || local == 0xffffffff {
+            return Err(Error::Invalid);
+        }
+
+        // The data follows the local header, whose variable-length fields
+        // can differ from the central directory.
+        let local = local as usize;
+        if u32_at(src, local)? != SIGNATURE_LOCAL {
+            return Err(Error::Invalid);
+        }
+        let start = local
+            + LEN_LOCAL
+            + u16_at(src, local + 26)? as usize
+            + u16_at(src, local + 28)? as usize;
+
+        v.push(Entry {
+            made_by: u16_at(src, pos + 4)?,
+            version: u16_at(src, pos + 6)?,
+            flags: u16_at(src, pos + 8)?,
+            method: u16_at(src, pos + 10)?,
+            time: u16_at(src, pos + 12)?,
+            date: u16_at(src, pos + 14)?,
+            crc: u32_at(src, pos + 16)?,
+            size: size,
+            internal: u16_at(src, pos + 36)?,
+            external: u32_at(src, pos + 38)?,
+            name: slice_at(src, pos + LEN_CENTRAL, n_name)?,
+            extra: slice_at(src, pos + LEN_CENTRAL + n_name, n_extra)?,
+            comment: slice_at(src, pos + LEN_CENTRAL + n_name + n_extra, n_comment)?,
+            content: slice_at(src, start, compressed as usize)?,
+        });
+
+        pos += LEN_CENTRAL + n_name + n_extra + n_comment;
+    }
+
+    Ok(v)
+}
+
+impl Writer {
+    /// Create a new writer for an empty archive.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// ## Copy Archive
+    ///
+    /// Create a new writer with all entries of an existing archive. The
+    /// entry data is copied verbatim, but local headers are rewritten, so
+    /// stored entries get aligned and data descriptors are dropped. Native
+    /// libraries are aligned to the page size, all other entries to the
+    /// default alignment.
+    pub fn with_archive(src: &[u8]) -> Result<Self, Error> {
+        let mut v = Self::new();
+        v.data.reserve(src.len());
+
+        for entry in entries(src)? {
+            let alignment = match entry.name.ends_with(b".so") {
+                true => ALIGNMENT_PAGE,
+                false => ALIGNMENT,
+            };
+            v.push(&entry, alignment)?;
+        }
+
+        Ok(v)
+    }
+
+    /// ## Add Stored Entry
+    ///
+    /// Append an entry with the given name and content to the archive,
+    /// stored without compression. The content is aligned to the given
+    /// alignment in the archive.
+    pub fn add_stored(
+        &mut self,
+        name: &str,
+        content: &[u8],
+        alignment: u16,
+    ) -> Result<(), Error> {
+        let size = u32::try_from(content.len())
+            .ok()
+            .filter(|v| *v != 0xffffffff)
+            .ok_or(Error::Overflow)?;
+
+        self.push(
+            &Entry {
+                made_by: VERSION,
+                version: VERSION,
+                flags: 0,
+                method: METHOD_STORED,
+                time: DOS_TIME,
+                date: DOS_DATE,
+                crc: crate::misc::crc32(content),
+                size: size,
+                internal: 0,
+                external: 0,
+                name: name.as_bytes(),
+                extra: &[],
+                comment: &[],
+                content: content,
+            },
+            alignment,
+        )
+    }
+
+    /// ## Add Deflated Entry
+    ///
+    /// Append an entry with the given name and content to the archive,
+    /// compressed with deflate. Compressed entries are not aligned, since
+    /// they cannot be mapped directly, anyway.
+    pub fn add_deflated(
+        &mut self,
+        name: &str,
+        content: &[u8],
+    ) -> Result<(), Error> {
+        let size = u32::try_from(content.len())
+            .ok()
+            .filter(|v| *v != 0xffffffff)
+            .ok_or(Error::Overflow)?;
+        let compressed = deflate(content);
+        if u32::try_from(compressed.len()).ok().filter(|v| *v != 0xffffffff).is_none() {
+            return Err(Error::Overflow);
+        }
+
+        self.push(
+            &Entry {
+                made_by: VERSION,
+                version: VERSION,
+                flags: 0,
+                method: METHOD_DEFLATED,
+                time: DOS_TIME,
+                date: DOS_DATE,
+                crc: crate::misc::crc32(content),
+                size: size,
+                internal: 0,
+                external: 0,
+                name: name.as_bytes(),
+                extra: &[],
+                comment: &[],
+                content: &compressed,
+            },
+            1,
+        )
+    }
+
+    /// ## Finish Archive
+    ///
+    /// Write the central directory and yield the content of the archive.
+    pub fn finish(mut self) -> Result<Vec<u8>, Error> {
+        let n = u16::try_from(self.names.len())
+            .ok()
+            .filter(|v| *v != 0xffff)
+            .ok_or(Error::Overflow)?;
+        let size = u32::try_from(self.central.len()).map_err(|_| Error::Overflow)?;
+        let offset = u32::try_from(self.data.len())
+            .ok()
+            .filter(|v| *v != 0xffffffff)
+            .ok_or(Error::Overflow)?;
+
+        self.data.append(&mut self.central);
+        self.data.extend_from_slice(&SIGNATURE_END.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(&n.to_le_bytes());
+        self.data.extend_from_slice(&n.to_le_bytes());
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+
+        Ok(self.data)
+    }
+
+    // Write the local header and data of an entry, and record its central
+    // directory header. Stored entries get an alignment extra field in
+    // their local header, padded so their data starts at a multiple of the
+    // alignment. Sizes and checksum are always written into the local
+    // header, so data descriptors are never needed.
+    fn push(&mut self, entry: &Entry, alignment: u16) -> Result<(), Error> {
+        if !self.names.insert(entry.name.to_vec()) {
+            return Err(Error::Duplicate(String::from_utf8_lossy(entry.name).into_owned()));
+        }
+
+        let offset = u32::try_from(self.data.len())
+            .ok()
+            .filter(|v| *v != 0xffffffff)
+            .ok_or(Error::Overflow)?;
+        let n_name = u16::try_from(entry.name.len()).map_err(|_| Error::Overflow)?;
+        let compressed = entry.content.len() as u32;
+        let flags = entry.flags & !FLAG_DESCRIPTOR;
+
+        let mut extra = Vec::new();
+        if entry.method == METHOD_STORED && alignment > 1 {
+            let start = self.data.len() + LEN_LOCAL + entry.name.len() + 6;
+            let pad = (alignment as usize - start % alignment as usize) % alignment as usize;
+            extra.extend_from_slice(&EXTRA_ALIGNMENT.to_le_bytes());
+            extra.extend_from_slice(&(2 + pad as u16).to_le_bytes());
+            extra.extend_from_slice(&alignment.to_le_bytes());
+            extra.resize(6 + pad, 0);
+        }
+
+        self.data.extend_from_slice(&SIGNATURE_LOCAL.to_le_bytes());
+        self.data.extend_from_slice(&entry.version.to_le_bytes());
+        self.data.extend_from_slice(&flags.to_le_bytes());
+        self.data.extend_from_slice(&entry.method.to_le_bytes());
+        self.data.extend_from_slice(&entry.time.to_le_bytes());
+        self.data.extend_from_slice(&entry.date.to_le_bytes());
+        self.data.extend_from_slice(&entry.crc.to_le_bytes());
+        self.data.extend_from_slice(&compressed.to_le_bytes());
+        self.data.extend_from_slice(&entry.size.to_le_bytes());
+        self.data.extend_from_slice(&n_name.to_le_bytes());
+        self.data.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        self.data.extend_from_slice(entry.name);
+        self.data.extend_from_slice(&extra);
+        self.data.extend_from_slice(entry.content);
+
+        self.central.extend_from_slice(&SIGNATURE_CENTRAL.to_le_bytes());
+        self.central.extend_from_slice(&entry.made_by.to_le_bytes());
+        self.central.extend_from_slice(&entry.version.to_le_bytes());
+        self.central.extend_from_slice(&flags.to_le_bytes());
+        self.central.extend_from_slice(&entry.method.to_le_bytes());
+        self.central.extend_from_slice(&entry.time.to_le_bytes());
+        self.central.extend_from_slice(&entry.date.to_le_bytes());
+        self.central.extend_from_slice(&entry.crc.to_le_bytes());
+        self.central.extend_from_slice(&compressed.to_le_bytes());
+        self.central.extend_from_slice(&entry.size.to_le_bytes());
+        self.central.extend_from_slice(&n_name.to_le_bytes());
+        self.central.extend_from_slice(&(entry.extra.len() as u16).to_le_bytes());
+        self.central.extend_from_slice(&(entry.comment.len() as u16).to_le_bytes());
+        self.central.extend_from_slice(&0u16.to_le_bytes());
+        self.central.extend_from_slice(&entry.internal.to_le_bytes());
+        self.central.extend_from_slice(&entry.external.to_le_bytes());
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(entry.name);
+        self.central.extend_from_slice(entry.extra);
+        self.central.extend_from_slice(entry.comment);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Yield the name, method, data offset, and content of all entries.
+    fn list(data: &[u8]) -> Vec<(String, u16, usize, Vec<u8>)> {
+        entries(data).unwrap().iter().map(|v| (
+            String::from_utf8(v.name.to_vec()).unwrap(),
+            v.method,
+            v.content.as_ptr() as usize - data.as_ptr() as usize,
+            v.content.to_vec(),
+        )).collect()
+    }
+
+    // Verify that stored entries are written aligned, and can be read
+    // back including their checksums.
+    #[test]
+    fn zip_stored() {
+        let mut w = Writer::new();
+        w.add_stored("classes.dex", b"dex\n035\0", ALIGNMENT).unwrap();
+        w.add_stored("lib/x86_64/libfoo.so", b"\x7fELF", ALIGNMENT_PAGE).unwrap();
+        w.add_stored("empty", b"", ALIGNMENT).unwrap();
+        assert!(matches!(
+            w.add_stored("empty", b"", ALIGNMENT),
+            Err(Error::Duplicate(ref v)) if v == "empty",
+        ));
+        let data = w.finish().unwrap();
+
+        let entries = list(&data);
+        assert_eq!(
+            entries.iter().map(|v| (v.0.as_str(), v.1, v.3.as_slice())).collect::<Vec<_>>(),
+            [
+                ("classes.dex", METHOD_STORED, b"dex\n035\0".as_slice()),
+                ("lib/x86_64/libfoo.so", METHOD_STORED, b"\x7fELF".as_slice()),
+                ("empty", METHOD_STORED, b"".as_slice()),
+            ],
+        );
+        assert_eq!(entries[0].2 % 4, 0);
+        assert_eq!(entries[1].2 % 4096, 0);
+        assert_eq!(entries[2].2 % 4, 0);
+        for v in super::entries(&data).unwrap() {
+            assert_eq!(v.crc, crate::misc::crc32(v.content));
+        }
+
+        // Copies retain the alignment of native libraries.
+        let copy = Writer::with_archive(&data).unwrap().finish().unwrap();
+        assert_eq!(copy, data);
+
+        // Empty archives consist of the end record only.
+        assert_eq!(Writer::new().finish().unwrap().len(), LEN_END);
+        assert!(list(&Writer::new().finish().unwrap()).is_empty());
+    }
+
+    // Decode a deflate stream that consists of blocks with fixed Huffman
+    // codes, as produced by the encoder.
+    fn inflate(src: &[u8]) -> Vec<u8> {
+        let mut pos = 0;
+        let mut bit = || {
+            let v = (src[pos / 8] >> (pos % 8)) & 1;
+            pos += 1;
+            v as u32
+        };
+        let mut bits = |n: u32, msb: bool| {
+            (0..n).fold(0, |acc, i| match msb {
+                true => (acc << 1) | bit(),
+                false => acc | (bit() << i),
+            })
+        };
+
+        let mut dst: Vec<u8> = Vec::new();
+        loop {
+            let last = bits(1, false);
+            assert_eq!(bits(2, false), 1);
+
+            loop {
+                let mut code = bits(7, true);
+                let symbol = if code < 0x18 {
+                    256 + code
+                } else {
+                    code = (code << 1) | bits(1, true);
+                    match code {
+                        0x30..=0xbf => code - 0x30,
+                        0xc0..=0xc7 => 280 + code - 0xc0,
+                        _ => 144 + ((code << 1) | bits(1, true)) - 0x190,
+                    }
+                };
+
+                match symbol {
+                    0..=255 => dst.push(symbol as u8),
+                    256 => break,
+                    _ => {
+                        let l = symbol as usize - 257;
+                        let len = DEFLATE_LENGTH_BASE[l] as usize
+                            + bits(DEFLATE_LENGTH_EXTRA[l] as u32, false) as usize;
+                        let d = bits(5, true) as usize;
+                        let distance = DEFLATE_DISTANCE_BASE[d] as usize
+                            + bits(DEFLATE_DISTANCE_EXTRA[d] as u32, false) as usize;
+                        for _ in 0..len {
+                            dst.push(dst[dst.len() - distance]);
+                        }
+                    },
+                }
+            }
+
+            if last == 1 {
+                return dst;
+            }
+        }
+    }
+
+    // Verify that deflated entries are compressed, not aligned, and decode
+    // to their content.
+    #[test]
+    fn zip_deflated() {
+        let mut text = Vec::new();
+        for i in 0..4096u32 {
+            text.extend_from_slice(format!("<entry id=\"{}\"/>\n", i % 37).as_bytes());
+        }
+        let binary: Vec<u8> = (0..70000u32).map(|v| (v.wrapping_mul(2654435761) >> 24) as u8).collect();
+
+        for content in [b"".as_slice(), b"a", b"aaaaaaaaaa", &text, &binary] {
+            assert_eq!(inflate(&deflate(content)), content);
+        }
+        assert!(deflate(&text).len() < text.len() / 10);
+
+        let mut w = Writer::new();
+        w.add_deflated("AndroidManifest.xml", &text).unwrap();
+        w.add_stored("lib/x86_64/libfoo.so", b"\x7fELF", ALIGNMENT_PAGE).unwrap();
+        let data = w.finish().unwrap();
+
+        let entries = super::entries(&data).unwrap();
+        assert_eq!(entries[0].method, METHOD_DEFLATED);
+        assert_eq!(entries[0].size as usize, text.len());
+        assert_eq!(entries[0].crc, crate::misc::crc32(&text));
+        assert_eq!(inflate(entries[0].content), text);
+        assert!(entries[0].extra.is_empty());
+        assert_eq!(list(&data)[1].2 % 4096, 0);
+    }
+
+    // Verify that copied archives retain their entries, get stored
+    // entries aligned, and drop data descriptors.
+    #[test]
+    fn zip_copy() {
+        // Archive with a deflated entry followed by a data descriptor, and
+        // an unaligned stored entry, followed by a comment.
+        let mut src = Vec::new();
+        let mut central = Vec::new();
+        for (name, flags, method, content) in [
+            ("AndroidManifest.xml", FLAG_DESCRIPTOR, 8u16, b"\x03\0".as_slice()),
+            ("resources.arsc", 0, METHOD_STORED, b"\x02\0\x0c\0".as_slice()),
+        ] {
+            let crc = crate::misc::crc32(content);
+            let (crc_local, size_local) = match flags {
+                FLAG_DESCRIPTOR => (0, 0),
+                _ => (crc, content.len() as u32),
+            };
+            let offset = src.len() as u32;
+
+            src.extend_from_slice(&SIGNATURE_LOCAL.to_le_bytes());
+            src.extend_from_slice(&[20, 0]);
+            src.extend_from_slice(&flags.to_le_bytes());
+            src.extend_from_slice(&method.to_le_bytes());
+            src.extend_from_slice(&[0; 4]);
+            src.extend_from_slice(&crc_local.to_le_bytes());
+            src.extend_from_slice(&size_local.to_le_bytes());
+            src.extend_from_slice(&size_local.to_le_bytes());
+            src.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            src.extend_from_slice(&1u16.to_le_bytes());
+            src.extend_from_slice(name.as_bytes());
+            src.push(0);
+            src.extend_from_slice(content);
+            if flags == FLAG_DESCRIPTOR {
+                src.extend_from_slice(&0x08074b50u32.to_le_bytes());
+                src.extend_from_slice(&crc.to_le_bytes());
+                src.extend_from_slice(&(content.len() as u32).to_le_bytes());
+                src.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            }
+
+            central.extend_from_slice(&SIGNATURE_CENTRAL.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0]);
+            central.extend_from_slice(&flags.to_le_bytes());
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 4]);
+            central.extend_from_slice(&crc.to_le_bytes());
+            central.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let offset = src.len() as u32;
+        src.extend_from_slice(&central);
+        src.extend_from_slice(&SIGNATURE_END.to_le_bytes());
+        src.extend_from_slice(&[0, 0, 0, 0, 2, 0, 2, 0]);
+        src.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        src.extend_from_slice(&offset.to_le_bytes());
+        src.extend_from_slice(&3u16.to_le_bytes());
+        src.extend_from_slice(b"foo");
+
+        let entries = list(&src);
+        assert_eq!(entries.len(), 2);
+        assert_ne!(entries[1].2 % 4, 0);
+
+        let mut w = Writer::with_archive(&src).unwrap();
+        w.add_stored("classes.dex", b"dex", ALIGNMENT).unwrap();
+        assert!(matches!(
+            w.add_stored("resources.arsc", b"", ALIGNMENT),
+            Err(Error::Duplicate(_)),
+        ));
+        let data = w.finish().unwrap();
+
+        let copied = list(&data);
+        assert_eq!(
+            copied.iter().map(|v| (v.0.as_str(), v.1, v.3.as_slice())).collect::<Vec<_>>(),
+            [
+                ("AndroidManifest.xml", 8, b"\x03\0".as_slice()),
+                ("resources.arsc", METHOD_STORED, b"\x02\0\x0c\0".as_slice()),
+                ("classes.dex", METHOD_STORED, b"dex".as_slice()),
+            ],
+        );
+        assert_eq!(copied[1].2 % 4, 0);
+        for v in super::entries(&data).unwrap() {
+            assert_eq!(v.flags & FLAG_DESCRIPTOR, 0);
+        }
+        assert_eq!(u16_at(&data, 6).unwrap(), 0);
+        assert_eq!(u32_at(&data, 14).unwrap(), crate::misc::crc32(b"\x03\0"));
+        assert_eq!(u32_at(&data, 18).unwrap(), 2);
+
+        // Malformed archives are rejected.
+        assert!(matches!(Writer::with_archive(b""), Err(Error::Invalid)));
+        assert!(matches!(Writer::with_archive(&src[..src.len() - 30]), Err(Error::Invalid)));
+        assert!(matches!(Writer::with_archive(&src[1..]), Err(Error::Invalid)));
+    }
+}
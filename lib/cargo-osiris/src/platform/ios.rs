@@ -0,0 +1,327 @@
+//! # iOS Platform Support
+//!
+//! This module implements application bundles for the iOS platform. It
+//! supports direct builds via the XCode tools, and shares the tool
+//! abstractions with the macOS platform. Only the iOS simulator is
+//! supported as target, so bundles are signed ad-hoc and no provisioning
+//! is required.
+
+use crate::{cargo, config, md, op};
+use std::collections::BTreeMap;
+
+use super::macos::{self, codesign, lipo};
+
+pub enum ErrorBuild {
+    /// Unsupported target ABI for the iOS platform.
+    UnsupportedAbi { abi: String },
+}
+
+struct Build<'ctx> {
+    // Configuration
+    pub build_dir: &'ctx std::path::Path,
+    pub ios: &'ctx config::ConfigPlatformIos,
+    pub op: &'ctx op::Build<'ctx>,
+}
+
+struct Direct<'ctx> {
+    // Build context
+    pub build: &'ctx Build<'ctx>,
+
+    // Build directories
+    pub artifact_dir: std::path::PathBuf,
+    pub bundle_dir: std::path::PathBuf,
+
+    // Artifact files
+    pub bundle_plist_file: std::path::PathBuf,
+}
+
+impl<'ctx> Build<'ctx> {
+    pub fn new(
+        op: &'ctx op::Build<'ctx>,
+        ios: &'ctx config::ConfigPlatformIos,
+        build_dir: &'ctx std::path::Path,
+    ) -> Self {
+        Self {
+            build_dir: build_dir,
+            ios: ios,
+            op: op,
+        }
+    }
+
+    pub fn direct(
+        &self,
+    ) -> Direct<'_> {
+        let v_artifact_dir = self.build_dir.join("artifacts");
+        let v_bundle_dir = self.build_dir.join(format!("{}.app", self.op.config.id_symbol));
+
+        let v_bundle_plist_file = v_artifact_dir.join("package.plist");
+
+        Direct {
+            build: self,
+
+            artifact_dir: v_artifact_dir,
+            bundle_dir: v_bundle_dir,
+
+            bundle_plist_file: v_bundle_plist_file,
+        }
+    }
+}
+
+impl<'ctx> Direct<'ctx> {
+    fn prepare_bundle_plist(&self) -> String {
+        let string = |v: &str| md::OsirisPlistValue::String(v.into());
+
+        macos::plist_document(&md::OsirisPlistValue::Dict(BTreeMap::from([
+            ("CFBundleDisplayName".into(), string(&self.build.op.config.name)),
+            ("CFBundleIdentifier".into(), string(&self.build.ios.bundle_id)),
+            ("CFBundleName".into(), string(&self.build.op.config.id_symbol)),
+            ("CFBundleShortVersionString".into(), string(&self.build.ios.version_name)),
+            ("CFBundleVersion".into(), string(&self.build.ios.version_code.to_string())),
+            ("CFBundleExecutable".into(), string(&self.build.op.config.id_symbol)),
+            ("CFBundleSupportedPlatforms".into(), md::OsirisPlistValue::Array(vec![string("iPhoneSimulator")])),
+            ("CFBundlePackageType".into(), string("APPL")),
+            ("LSRequiresIPhoneOS".into(), md::OsirisPlistValue::Bool(true)),
+            ("MinimumOSVersion".into(), string(&self.build.ios.min_os)),
+            (
+                "UIDeviceFamily".into(),
+                md::OsirisPlistValue::Array(vec![
+                    md::OsirisPlistValue::Integer(1),
+                    md::OsirisPlistValue::Integer(2),
+                ]),
+            ),
+            ("UILaunchScreen".into(), md::OsirisPlistValue::Dict(BTreeMap::new())),
+        ])))
+    }
+
+    fn prepare(&self) -> Result<(), op::BuildError> {
+        // Delete previous artifacts if re-use is not possible.
+        op::rmdir(&self.bundle_dir)?;
+
+        // Create build directories
+        op::mkdir(&self.artifact_dir)?;
+        op::mkdir(&self.bundle_dir)?;
+
+        // Emerge configuration files
+        op::update_file(
+            self.bundle_plist_file.as_path(),
+            self.prepare_bundle_plist().as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    fn build_cargo(&self) -> Result<BTreeMap<std::path::PathBuf, Vec<std::path::PathBuf>>, op::BuildError> {
+        let mut res: BTreeMap<std::path::PathBuf, Vec<std::path::PathBuf>> = BTreeMap::new();
+
+        // Supported ABI keys match the macOS platform. Both are mapped to
+        // their simulator targets. Note that `x86_64` is only supported on
+        // the simulator, and thus lacks the `-sim` suffix.
+        for abi in &self.build.ios.abis {
+            let target = match abi.as_str() {
+                "arm64" => Ok("aarch64-apple-ios-sim"),
+                "x86_64" => Ok("x86_64-apple-ios"),
+                v => Err(ErrorBuild::UnsupportedAbi { abi: v.into() }),
+            }?;
+
+            // The deployment target is picked up by `rustc` when linking,
+            // and must match `MinimumOSVersion` of the bundle.
+            let query = cargo::BuildQuery::new(self.build.op.cargo_arguments)
+                .with_crate_type("bin")
+                .with_target(Some(target))
+                .with_env("IPHONEOS_DEPLOYMENT_TARGET", &self.build.ios.min_os);
+
+            let build = query.run(self.build.op)?;
+
+            for artifact in build.artifacts {
+                let path = std::path::Path::new(&artifact.path);
+                let file_name = path.file_name().expect("Cargo artifacts must have file-names");
+
+                // iOS bundles are flat. The main executable is placed in the
+                // bundle root with the same name as the bundle, and dynamic
+                // libraries go into `Frameworks`. iOS does not allow
+                // spawning helper executables, and we have no reliable way
+                // to place resources, so everything else is skipped.
+                //
+                // XXX: As on macOS, we build with `--crate-type bin`, thus
+                //      the only artifact of the main package is the main
+                //      executable, even if Cargo does not report it as such.
+                let dst = if artifact.package_id == self.build.op.cargo_metadata.package_id {
+                    std::path::PathBuf::from(&self.build.op.config.id_symbol)
+                } else if path.extension().is_some_and(|v| v == "dylib") {
+                    std::path::Path::new("Frameworks").join(file_name)
+                } else {
+                    continue;
+                };
+
+                res.entry(dst).or_default().push(path.to_path_buf());
+            }
+        }
+
+        Ok(res)
+    }
+
+    fn build_bundle(
+        &self,
+        cargo_builds: &BTreeMap<std::path::PathBuf, Vec<std::path::PathBuf>>,
+    ) -> Result<(), op::BuildError> {
+        op::copy_file(&self.bundle_plist_file, &self.bundle_dir.join("Info.plist"))?;
+
+        for (dst, artifacts) in cargo_builds {
+            let to = self.bundle_dir.join(dst);
+            if let Some(dir) = to.parent() {
+                op::mkdir(dir)?;
+            }
+
+            match artifacts.len() {
+                0 => {},
+                1 => op::copy_file(&artifacts[0], &to)?,
+                _ => {
+                    lipo::CreateQuery {
+                        input_files: artifacts.iter(),
+                        output_file: &to,
+                    }.run(self.build.op)?;
+                },
+            }
+        }
+
+        // The simulator requires all code to be signed, but accepts ad-hoc
+        // signatures. This also seals the resources of the bundle.
+        codesign::SignQuery {
+            entitlements: None,
+            force: true,
+            identity: "-",
+            options: None::<core::iter::Empty<codesign::SignOption>>,
+            paths: [
+                &self.bundle_dir
+            ].iter(),
+            requirements: None,
+            timestamp: Some(false),
+        }.run(self.build.op)?;
+
+        Ok(())
+    }
+
+    pub fn build(&self) -> Result<op::Output, op::BuildError> {
+        self.prepare()?;
+        self.build.op.check_cancel()?;
+        let cargo_builds = self.build_cargo()?;
+        self.build.op.check_cancel()?;
+        self.build_bundle(&cargo_builds)?;
+
+        Ok(op::Output {
+            artifacts: vec![
+                op::Artifact {
+                    kind: op::ArtifactKind::IosBundle,
+                    path: self.bundle_dir.clone(),
+                },
+            ],
+        })
+    }
+}
+
+pub fn build(
+    op: &op::Build,
+    ios: &config::ConfigPlatformIos,
+    build_dir: &std::path::Path,
+) -> Result<op::Output, op::BuildError> {
+    let build = Build::new(
+        op,
+        ios,
+        build_dir,
+    );
+    let direct = build.direct();
+
+    direct.build()
+}
+
+pub fn emit_manifest(
+    op: &op::Build,
+    ios: &config::ConfigPlatformIos,
+    build_dir: &std::path::Path,
+) -> Result<std::path::PathBuf, op::BuildError> {
+    let build = Build::new(
+        op,
+        ios,
+        build_dir,
+    );
+    let direct = build.direct();
+
+    op::mkdir(&direct.artifact_dir)?;
+    op::update_file(
+        direct.bundle_plist_file.as_path(),
+        direct.prepare_bundle_plist().as_bytes(),
+    )?;
+
+    Ok(direct.bundle_plist_file)
+}
+
+impl core::fmt::Display for ErrorBuild {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            ErrorBuild::UnsupportedAbi { abi } => fmt.write_fmt(core::format_args!("Unsupported ABI: {}", abi)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify that the generated `Info.plist` targets the simulator with the
+    // configured bundle properties, and that defaults are applied.
+    #[test]
+    fn bundle_plist() {
+        let metadata = cargo::Metadata::from_osiris_json(&serde_json::json!({
+            "platforms": [
+                {
+                    "id": "ios",
+                    "ios": {
+                        "bundle-id": "com.example.foobar",
+                        "version-code": 7,
+                    },
+                },
+            ],
+        })).unwrap();
+        let plist = op::with_build(&Default::default(), &metadata, "ios", None, |op| {
+            let config::ConfigPlatformConfiguration::Ios(ref ios) = op.platform.configuration else {
+                panic!("Platform must be an iOS platform");
+            };
+            assert_eq!(ios.abis, ["arm64", "x86_64"]);
+
+            let build_dir = std::path::Path::new("/target/osiris/build/ios");
+            Build::new(&op, ios, build_dir).direct().prepare_bundle_plist()
+        }).unwrap();
+        assert!(plist.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(plist.ends_with("  </dict>\n</plist>\n"));
+        assert!(plist.contains(concat!(
+            "    <key>CFBundleIdentifier</key>\n",
+            "    <string>com.example.foobar</string>\n",
+        )));
+        assert!(plist.contains(concat!(
+            "    <key>CFBundleShortVersionString</key>\n",
+            "    <string>7.0.0</string>\n",
+        )));
+        assert!(plist.contains(concat!(
+            "    <key>CFBundleVersion</key>\n",
+            "    <string>7</string>\n",
+        )));
+        assert!(plist.contains(concat!(
+            "    <key>CFBundleSupportedPlatforms</key>\n",
+            "    <array>\n",
+            "      <string>iPhoneSimulator</string>\n",
+            "    </array>\n",
+        )));
+        assert!(plist.contains(concat!(
+            "    <key>MinimumOSVersion</key>\n",
+            "    <string>14.0</string>\n",
+            "    <key>UIDeviceFamily</key>\n",
+            "    <array>\n",
+            "      <integer>1</integer>\n",
+            "      <integer>2</integer>\n",
+            "    </array>\n",
+            "    <key>UILaunchScreen</key>\n",
+            "    <dict>\n",
+            "    </dict>\n",
+        )));
+    }
+}
@@ -3,15 +3,23 @@
 //! This module implements application bundles for the macOS platform. It
 //! supports direct builds via the XCode tools.
 
-use crate::{cargo, config, misc, op};
+use crate::{cargo, config, doctor, md, op, png};
 use std::collections::BTreeMap;
 
 mod actool;
-mod codesign;
-mod lipo;
+pub(crate) mod codesign;
+pub(crate) mod lipo;
+mod notarytool;
 mod plistbuddy;
 mod productbuild;
 
+// Icon sizes required by macOS asset catalogs. Each size is required with a
+// scale of `1x` and `2x`.
+const PLACEHOLDER_ICON_SIZES: [u32; 5] = [16, 32, 128, 256, 512];
+
+// Color of generated placeholder icons as 8-bit RGBA.
+const PLACEHOLDER_ICON_COLOR: [u8; 4] = [0x80, 0x80, 0x80, 0xff];
+
 pub enum ErrorBuild {
     /// Unsupported target ABI for the macOS platform.
     UnsupportedAbi { abi: String },
@@ -22,6 +30,18 @@ pub enum ErrorBuild {
     NoFile { path: std::path::PathBuf },
 }
 
+#[derive(Debug)]
+pub enum ErrorNotary {
+    /// Notarization was requested, but no credentials are available.
+    Credentials,
+    /// Notarization was requested, but the package is not signed.
+    Unsigned,
+    /// Output of the notary tool could not be parsed.
+    Output,
+    /// Notary service did not accept the submission.
+    Rejected { id: String, status: Option<String>, message: Option<String> },
+}
+
 struct ArchivePkg<'ctx> {
     // Configuration
     pub archive_dir: &'ctx std::path::Path,
@@ -69,6 +89,7 @@ struct Direct<'ctx> {
     pub accent_color: Option<&'ctx str>,
     pub app_icon: Option<&'ctx str>,
     pub icons: BTreeMap<(u32, u32), Vec<&'ctx str>>,
+    pub placeholder_icon: bool,
 }
 
 impl<'ctx> ArchivePkg<'ctx> {
@@ -114,14 +135,10 @@ impl<'ctx> ArchivePkg<'ctx> {
             acc,
         );
 
-        acc = format!(
-            concat!(
-                r#"{}"#,
-                r#"    <key>com.apple.security.app-sandbox</key>"#, "\n",
-                r#"    <true/>"#, "\n",
-            ),
-            acc,
-        );
+        for (k, v) in &self.macos_pkg.entitlements {
+            acc.push_str(&format!("    <key>{}</key>\n", op::xml_escape_text(k)));
+            plist_value(&mut acc, v, 4);
+        }
 
         if let Some(ref v) = self.macos_pkg.app_id {
             acc = format!(
@@ -131,7 +148,7 @@ impl<'ctx> ArchivePkg<'ctx> {
                     r#"    <string>{}</string>"#, "\n",
                 ),
                 acc,
-                misc::escape_xml_pcdata(v),
+                op::xml_escape_text(v),
             );
         }
 
@@ -143,7 +160,7 @@ impl<'ctx> ArchivePkg<'ctx> {
                     r#"    <string>{}</string>"#, "\n",
                 ),
                 acc,
-                misc::escape_xml_pcdata(v),
+                op::xml_escape_text(v),
             );
         }
 
@@ -199,10 +216,10 @@ impl<'ctx> ArchivePkg<'ctx> {
         cmd.stdin(std::process::Stdio::null());
         cmd.stdout(std::process::Stdio::inherit());
 
-        let status = cmd.status()
+        let output = op::ToolRunner::run(self.op, &mut cmd)
             .map_err(|io| op::ErrorProcess::Exec { name: "cp".into(), io })?;
-        if !status.success() {
-            return Err(op::ErrorProcess::Exit { name: "cp".into(), code: status }.into());
+        if !output.status.success() {
+            return Err(op::ErrorProcess::Exit { name: "cp".into(), code: output.status }.into());
         }
 
         if let Some(ref provfile) = self.macos_pkg.provision_file {
@@ -236,7 +253,7 @@ impl<'ctx> ArchivePkg<'ctx> {
             ].iter(),
             requirements: None,
             timestamp: Some(true),
-        }.run()?;
+        }.run(self.op)?;
 
         Ok(())
     }
@@ -248,18 +265,71 @@ impl<'ctx> ArchivePkg<'ctx> {
             ].iter(),
             identity: self.macos_pkg.pkgsign_identity.as_deref(),
             output_file: &self.pkg_file,
-        }.run()?;
+        }.run(self.op)?;
 
         Ok(())
     }
 
-    pub fn run(&self) -> Result<(), op::ArchiveError> {
+    fn notarize(&self) -> Result<(), op::ArchiveError> {
+        if !self.macos_pkg.notarize {
+            return Ok(());
+        }
+
+        // The notary service only accepts signed packages, so reject the
+        // request early rather than waiting for the service to do so.
+        if self.macos_pkg.pkgsign_identity.is_none() {
+            return Err(ErrorNotary::Unsigned.into());
+        }
+
+        let credentials = notarytool::Credentials::from_env()
+            .ok_or(ErrorNotary::Credentials)?;
+
+        let submission = notarytool::SubmitQuery {
+            credentials: &credentials,
+            file: &self.pkg_file,
+            wait: true,
+        }.run(self.op)?.ok_or(ErrorNotary::Output)?;
+
+        if !submission.accepted() {
+            return Err(ErrorNotary::Rejected {
+                id: submission.id,
+                status: submission.status,
+                message: submission.message,
+            }.into());
+        }
+
+        self.op.check_cancel()?;
+
+        notarytool::StapleQuery {
+            file: &self.pkg_file,
+        }.run(self.op)?;
+
+        Ok(())
+    }
+
+    pub fn run(&self) -> Result<op::Output, op::ArchiveError> {
         self.prepare()?;
+        self.op.check_cancel()?;
         self.import()?;
+        self.op.check_cancel()?;
         self.codesign()?;
+        self.op.check_cancel()?;
         self.productbuild()?;
-
-        Ok(())
+        self.op.check_cancel()?;
+        self.notarize()?;
+
+        Ok(op::Output {
+            artifacts: vec![
+                op::Artifact {
+                    kind: op::ArtifactKind::MacosBundle,
+                    path: self.bundle_dir.clone(),
+                },
+                op::Artifact {
+                    kind: op::ArtifactKind::MacosPkg,
+                    path: self.pkg_file.clone(),
+                },
+            ],
+        })
     }
 }
 
@@ -309,7 +379,8 @@ impl<'ctx> Build<'ctx> {
 
         let v_accent_color = Some("AccentColor");
         let v_icons = self.collect_icons();
-        let v_app_icon = (!v_icons.is_empty()).then(|| "AppIcon");
+        let v_placeholder_icon = v_icons.is_empty() && self.op.config.placeholder_icon;
+        let v_app_icon = (!v_icons.is_empty() || v_placeholder_icon).then(|| "AppIcon");
 
         Direct {
             build: self,
@@ -330,10 +401,59 @@ impl<'ctx> Build<'ctx> {
             accent_color: v_accent_color,
             app_icon: v_app_icon,
             icons: v_icons,
+            placeholder_icon: v_placeholder_icon,
         }
     }
 }
 
+// Write a plist document with the given value as root, usually a
+// dictionary. The iOS platform generates its `Info.plist` this way.
+pub(super) fn plist_document(root: &md::OsirisPlistValue) -> String {
+    let mut acc = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#, "\n",
+        r#"<!DOCTYPE plist PUBLIC "-//Apple Computer//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">"#, "\n",
+        r#"<plist version="1.0">"#, "\n",
+    ));
+    plist_value(&mut acc, root, 2);
+    acc.push_str("</plist>\n");
+    acc
+}
+
+// Write a value of an additional `Info.plist` entry as XML, indented by the
+// given number of spaces.
+fn plist_value(acc: &mut String, value: &md::OsirisPlistValue, indent: usize) {
+    let pad = " ".repeat(indent);
+
+    match value {
+        md::OsirisPlistValue::Bool(true) => acc.push_str(&format!("{}<true/>\n", pad)),
+        md::OsirisPlistValue::Bool(false) => acc.push_str(&format!("{}<false/>\n", pad)),
+        md::OsirisPlistValue::Integer(v) => acc.push_str(&format!("{}<integer>{}</integer>\n", pad, v)),
+        md::OsirisPlistValue::String(v) => {
+            acc.push_str(&format!("{}<string>{}</string>\n", pad, op::xml_escape_text(v)));
+        },
+        md::OsirisPlistValue::Array(list) => {
+            acc.push_str(&format!("{}<array>\n", pad));
+            for v in list {
+                plist_value(acc, v, indent + 2);
+            }
+            acc.push_str(&format!("{}</array>\n", pad));
+        },
+        md::OsirisPlistValue::Dict(map) => {
+            acc.push_str(&format!("{}<dict>\n", pad));
+            for (k, v) in map {
+                acc.push_str(&format!("{}  <key>{}</key>\n", pad, op::xml_escape_text(k)));
+                plist_value(acc, v, indent + 2);
+            }
+            acc.push_str(&format!("{}</dict>\n", pad));
+        },
+    }
+}
+
+// File name of a generated placeholder icon of the given size in pixels.
+fn placeholder_icon_name(size: u32) -> String {
+    format!("placeholder-{}.png", size)
+}
+
 impl<'ctx> Direct<'ctx> {
     fn prepare_bundle_plist(&self) -> String {
         format!(
@@ -368,21 +488,38 @@ impl<'ctx> Direct<'ctx> {
                 r#"    <string>{}</string>"#, "\n",
                 r#"    <key>LSMinimumSystemVersion</key>"#, "\n",
                 r#"    <string>{}</string>"#, "\n",
+                "{}",
                 r#"  </dict>"#, "\n",
                 r#"</plist>"#, "\n",
             ),
-            // XXX: Properly escape the values.
-            &self.build.op.config.name,
-            &self.build.macos.bundle_id,
-            &self.build.op.config.id_symbol,
-            &self.build.macos.version_name,
+            op::xml_escape_text(&self.build.op.config.name),
+            op::xml_escape_text(&self.build.macos.bundle_id),
+            op::xml_escape_text(&self.build.op.config.id_symbol),
+            op::xml_escape_text(&self.build.macos.version_name),
             &self.build.macos.version_code,
-            &self.build.op.config.id_symbol,
-            &self.build.macos.category,
-            &self.build.macos.min_os,
+            op::xml_escape_text(&self.build.op.config.id_symbol),
+            op::xml_escape_text(&self.build.macos.category),
+            op::xml_escape_text(&self.build.macos.min_os),
+            self.prepare_bundle_plist_extra(),
         )
     }
 
+    // Additional `Info.plist` entries of the configuration, separated from
+    // the generated entries by an empty line.
+    fn prepare_bundle_plist_extra(&self) -> String {
+        let mut acc = String::new();
+
+        if !self.build.macos.plist_extra.is_empty() {
+            acc.push('\n');
+        }
+        for (k, v) in &self.build.macos.plist_extra {
+            acc.push_str(&format!("    <key>{}</key>\n", op::xml_escape_text(k)));
+            plist_value(&mut acc, v, 4);
+        }
+
+        acc
+    }
+
     fn prepare_bundle_pkginfo(&self) -> String {
         "APPL????".into()
     }
@@ -475,6 +612,15 @@ impl<'ctx> Direct<'ctx> {
             keep(filename, scale, size);
         }
 
+        // Placeholders are generated for all required sizes and scales.
+        if self.placeholder_icon {
+            for size in PLACEHOLDER_ICON_SIZES {
+                for scale in [1, 2] {
+                    keep(&placeholder_icon_name(size * scale), scale, size);
+                }
+            }
+        }
+
         Ok(format!(
             concat!(
                 r#"{{"#, "\n",
@@ -550,15 +696,11 @@ impl<'ctx> Direct<'ctx> {
                 v => Err(ErrorBuild::UnsupportedAbi { abi: v.into() }),
             }?;
 
-            let query = cargo::BuildQuery {
-                cargo_arguments: self.build.op.cargo_arguments,
-                cfgs: Vec::new(),
-                crate_type: Some("bin".into()),
-                envs: Vec::new(),
-                target: o_target.map(|v| v.into()),
-            };
+            let query = cargo::BuildQuery::new(self.build.op.cargo_arguments)
+                .with_crate_type("bin")
+                .with_target(o_target);
 
-            let build = query.run()?;
+            let build = query.run(self.build.op)?;
 
             for artifact in build.artifacts {
                 let path = std::path::Path::new(&artifact.path);
@@ -704,6 +846,20 @@ impl<'ctx> Direct<'ctx> {
             op::copy_file(&from, &to)?;
         }
 
+        // Generate placeholder icons, if requested. Sizes are shared between
+        // scales, so only generate each size once.
+        if self.placeholder_icon {
+            let sizes: std::collections::BTreeSet<u32> = PLACEHOLDER_ICON_SIZES.iter()
+                .flat_map(|v| [*v, *v * 2])
+                .collect();
+            for size in sizes {
+                op::update_file(
+                    &self.xcassets_appicon_dir.join(placeholder_icon_name(size)),
+                    &png::solid(size, PLACEHOLDER_ICON_COLOR),
+                )?;
+            }
+        }
+
         actool::CompileQuery {
             accent_color: self.accent_color,
             app_icon: self.app_icon,
@@ -712,12 +868,12 @@ impl<'ctx> Direct<'ctx> {
             output_dir: res_dir,
             output_info_file: Some(&self.xcassets_plist_file),
             verbose: self.build.op.verbose,
-        }.run()?;
+        }.run(self.build.op)?;
 
         plistbuddy::MergeQuery {
             input_file: &self.xcassets_plist_file,
             plist_file: info_plist,
-        }.run()?;
+        }.run(self.build.op)?;
 
         Ok(())
     }
@@ -763,7 +919,7 @@ impl<'ctx> Direct<'ctx> {
                     lipo::CreateQuery {
                         input_files: artifacts.iter(),
                         output_file: &to,
-                    }.run()?;
+                    }.run(self.build.op)?;
                 },
             }
         }
@@ -771,12 +927,21 @@ impl<'ctx> Direct<'ctx> {
         Ok(())
     }
 
-    pub fn build(&self) -> Result<(), op::BuildError> {
+    pub fn build(&self) -> Result<op::Output, op::BuildError> {
         self.prepare()?;
+        self.build.op.check_cancel()?;
         let cargo_builds = self.build_cargo()?;
+        self.build.op.check_cancel()?;
         self.build_bundle(&cargo_builds)?;
 
-        Ok(())
+        Ok(op::Output {
+            artifacts: vec![
+                op::Artifact {
+                    kind: op::ArtifactKind::MacosBundle,
+                    path: self.bundle_dir.clone(),
+                },
+            ],
+        })
     }
 }
 
@@ -785,7 +950,7 @@ pub fn archive_pkg(
     macos_pkg: &config::ConfigArchiveMacosPkg,
     archive_dir: &std::path::Path,
     platform_dir: &std::path::Path,
-) -> Result<(), op::ArchiveError> {
+) -> Result<op::Output, op::ArchiveError> {
     let archive = ArchivePkg::new(
         op,
         macos_pkg,
@@ -800,7 +965,7 @@ pub fn build(
     op: &op::Build,
     macos: &config::ConfigPlatformMacos,
     build_dir: &std::path::Path,
-) -> Result<(), op::BuildError> {
+) -> Result<op::Output, op::BuildError> {
     let build = Build::new(
         op,
         macos,
@@ -811,6 +976,55 @@ pub fn build(
     direct.build()
 }
 
+pub fn emit_manifest(
+    op: &op::Build,
+    macos: &config::ConfigPlatformMacos,
+    build_dir: &std::path::Path,
+) -> Result<std::path::PathBuf, op::BuildError> {
+    let build = Build::new(
+        op,
+        macos,
+        build_dir,
+    );
+    let direct = build.direct();
+
+    op::mkdir(&direct.artifact_dir)?;
+    op::update_file(
+        direct.bundle_plist_file.as_path(),
+        direct.prepare_bundle_plist().as_bytes(),
+    )?;
+
+    Ok(direct.bundle_plist_file)
+}
+
+/// Probe for the Xcode tools required to build and archive macOS
+/// applications.
+pub fn doctor(
+    probe: &doctor::Probe,
+    acc: &mut Vec<doctor::Check>,
+) {
+    for tool in ["xcrun", "codesign"] {
+        acc.push(match probe.find_tool(tool) {
+            Some(v) => doctor::Check::pass(tool, &v.display()),
+            None => doctor::Check::fail(
+                tool,
+                &core::format_args!("`{}` is not available, install the Xcode command line tools via: xcode-select --install", tool),
+            ),
+        });
+    }
+}
+
+impl core::fmt::Display for ErrorNotary {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            ErrorNotary::Credentials => fmt.write_fmt(core::format_args!("No notarization credentials available, set `{}` (or `{}` and `{}`)", notarytool::ENV_KEYCHAIN_PROFILE, notarytool::ENV_KEY, notarytool::ENV_KEY_ID)),
+            ErrorNotary::Unsigned => fmt.write_fmt(core::format_args!("Notarization requires a signed package, but no `pkgsign-identity` is configured")),
+            ErrorNotary::Output => fmt.write_fmt(core::format_args!("Cannot parse the output of notarytool")),
+            ErrorNotary::Rejected { id, status, message } => fmt.write_fmt(core::format_args!("Notarization submission {} finished with status `{}`{}{}, inspect it via: xcrun notarytool log {}", id, status.as_deref().unwrap_or("unknown"), if message.is_some() { ": " } else { "" }, message.as_deref().unwrap_or(""), id)),
+        }
+    }
+}
+
 impl core::fmt::Display for ErrorBuild {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
         match self {
@@ -820,3 +1034,164 @@ impl core::fmt::Display for ErrorBuild {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify that additional `Info.plist` entries are parsed, escaped, and
+    // emitted into the generated plist, and that reserved keys and
+    // unsupported values are rejected.
+    #[test]
+    fn bundle_plist_extra() {
+        let metadata = |plist: serde_json::Value| -> Result<cargo::Metadata, md::OsirisError> {
+            cargo::Metadata::from_osiris_json(&serde_json::json!({
+                "platforms": [
+                    {
+                        "id": "macos",
+                        "macos": {
+                            "bundle-id": "com.example.foobar",
+                            "plist-extra": plist,
+                        },
+                    },
+                ],
+            }))
+        };
+
+        let metadata_ok = metadata(serde_json::json!({
+            "NSCameraUsageDescription": "Scan <codes> & more",
+            "LSUIElement": true,
+            "CFBundleURLTypes": [
+                {
+                    "CFBundleURLName": "com.example.foobar",
+                    "CFBundleURLSchemes": ["foobar", "foo&bar"],
+                },
+            ],
+        })).unwrap();
+        let arguments = Default::default();
+        let plist = op::with_build(&arguments, &metadata_ok, "macos", None, |op| {
+            let config::ConfigPlatformConfiguration::Macos(ref macos) = op.platform.configuration else {
+                panic!("Platform must be a macOS platform");
+            };
+            let build_dir = std::path::Path::new("/target/osiris/build/macos");
+            Build::new(&op, macos, build_dir).direct().prepare_bundle_plist()
+        }).unwrap();
+        assert!(plist.contains(concat!(
+            "    <string>10.13</string>\n",
+            "\n",
+            "    <key>CFBundleURLTypes</key>\n",
+            "    <array>\n",
+            "      <dict>\n",
+            "        <key>CFBundleURLName</key>\n",
+            "        <string>com.example.foobar</string>\n",
+            "        <key>CFBundleURLSchemes</key>\n",
+            "        <array>\n",
+            "          <string>foobar</string>\n",
+            "          <string>foo&amp;bar</string>\n",
+            "        </array>\n",
+            "      </dict>\n",
+            "    </array>\n",
+            "    <key>LSUIElement</key>\n",
+            "    <true/>\n",
+            "    <key>NSCameraUsageDescription</key>\n",
+            "    <string>Scan &lt;codes> &amp; more</string>\n",
+            "  </dict>\n",
+        )));
+
+        let metadata_reserved = metadata(serde_json::json!({ "CFBundleIdentifier": "foo" })).unwrap();
+        assert!(matches!(
+            config::Config::from_cargo(&arguments, &metadata_reserved),
+            Err(config::Error::InvalidPlistKey(v)) if v == "CFBundleIdentifier",
+        ));
+        let metadata_invalid = metadata(serde_json::json!({ "<key>": "foo" })).unwrap();
+        assert!(matches!(
+            config::Config::from_cargo(&arguments, &metadata_invalid),
+            Err(config::Error::InvalidPlistKey(v)) if v == "<key>",
+        ));
+        assert!(metadata(serde_json::json!({ "Foo": 1.5 })).is_err());
+    }
+
+    // Verify that configured entitlements are emitted next to the generated
+    // ones, that the sandbox is enabled unless overridden, and that invalid
+    // or reserved keys are rejected.
+    #[test]
+    fn bundle_entitlements() {
+        let metadata = |entitlements: serde_json::Value| -> Result<cargo::Metadata, md::OsirisError> {
+            cargo::Metadata::from_osiris_json(&serde_json::json!({
+                "archives": [
+                    {
+                        "id": "pkg",
+                        "macos-pkg": {
+                            "app-id": "TEAM.com.example.foobar",
+                            "entitlements": entitlements,
+                        },
+                    },
+                ],
+                "platforms": [
+                    {
+                        "id": "macos",
+                        "macos": {},
+                    },
+                ],
+            }))
+        };
+        let arguments = Default::default();
+        let entitlements = |metadata: &cargo::Metadata| -> String {
+            let config = config::Config::from_cargo(&arguments, metadata).unwrap();
+            let archive = &config.archives["pkg"];
+            let config::ConfigArchiveConfiguration::MacosPkg(ref macos_pkg) = archive.configuration;
+            let op = op::Archive {
+                archive: archive,
+                cargo_arguments: &arguments,
+                cargo_metadata: metadata,
+                config: &config,
+                platform: &config.platforms["macos"],
+                verbose: false,
+                cancel: None,
+            };
+            let dir = std::path::Path::new("/target/osiris");
+            ArchivePkg::new(&op, macos_pkg, dir, dir).prepare_bundle_entitlements()
+        };
+
+        let metadata_ok = metadata(serde_json::json!({
+            "com.apple.security.network.client": true,
+            "com.apple.security.files.user-selected.read-write": true,
+            "com.apple.security.application-groups": ["TEAM.group"],
+            "com.apple.developer.aps-environment": "development",
+        })).unwrap();
+        assert!(entitlements(&metadata_ok).contains(concat!(
+            "  <dict>\n",
+            "    <key>com.apple.developer.aps-environment</key>\n",
+            "    <string>development</string>\n",
+            "    <key>com.apple.security.app-sandbox</key>\n",
+            "    <true/>\n",
+            "    <key>com.apple.security.application-groups</key>\n",
+            "    <array>\n",
+            "      <string>TEAM.group</string>\n",
+            "    </array>\n",
+            "    <key>com.apple.security.files.user-selected.read-write</key>\n",
+            "    <true/>\n",
+            "    <key>com.apple.security.network.client</key>\n",
+            "    <true/>\n",
+            "    <key>com.apple.application-identifier</key>\n",
+            "    <string>TEAM.com.example.foobar</string>\n",
+            "  </dict>\n",
+        )));
+
+        let metadata_dev = metadata(serde_json::json!({
+            "com.apple.security.app-sandbox": false,
+        })).unwrap();
+        assert!(entitlements(&metadata_dev).contains(concat!(
+            "    <key>com.apple.security.app-sandbox</key>\n",
+            "    <false/>\n",
+        )));
+
+        for key in ["com.apple.application-identifier", "sandbox", "com..apple", "com.apple.<key>"] {
+            let metadata_invalid = metadata(serde_json::json!({ key: true })).unwrap();
+            assert!(matches!(
+                config::Config::from_cargo(&arguments, &metadata_invalid),
+                Err(config::Error::InvalidEntitlementKey(v)) if v == key,
+            ));
+        }
+    }
+}
@@ -30,7 +30,7 @@ where
     <InputList as Iterator>::Item: AsRef<std::path::Path>,
 {
     /// Execute an actool-compile query.
-    pub fn run(&self) -> Result<(), op::ErrorProcess> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), op::ErrorProcess> {
         let mut cmd = std::process::Command::new("xcrun");
 
         // Run an `actool --compile` query.
@@ -103,10 +103,10 @@ where
             cmd.stdout(std::process::Stdio::null());
         }
 
-        let status = cmd.status()
+        let output = runner.run(&mut cmd)
             .map_err(|io| op::ErrorProcess::Exec { name: "actool".into(), io })?;
-        if !status.success() {
-            return Err(op::ErrorProcess::Exit { name: "actool".into(), code: status });
+        if !output.status.success() {
+            return Err(op::ErrorProcess::Exit { name: "actool".into(), code: output.status });
         }
 
         Ok(())
@@ -101,7 +101,7 @@ where
     /// Execute a signing query via the `codesign` utility of the macOS SDK.
     /// This will run `codesign` with the `--sign` flag to perform a
     /// signing operation.
-    pub fn run(&self) -> Result<(), op::ErrorProcess> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), op::ErrorProcess> {
         let mut cmd = std::process::Command::new("codesign");
 
         // Select signing operation and specify the signing identity.
@@ -169,7 +169,7 @@ where
 
         cmd.stderr(std::process::Stdio::inherit());
 
-        let output = cmd.output()
+        let output = runner.run(&mut cmd)
             .map_err(|io| op::ErrorProcess::Exec { name: "codesign".into(), io })?;
         if !output.status.success() {
             return Err(op::ErrorProcess::Exit { name: "codesign".into(), code: output.status });
@@ -20,7 +20,7 @@ where
     <InputList as Iterator>::Item: AsRef<std::path::Path>,
 {
     /// Execute a lipo-create query via the `lipo` utility of the macOS SDK.
-    pub fn run(&self) -> Result<(), op::ErrorProcess> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), op::ErrorProcess> {
         let mut cmd = std::process::Command::new("xcrun");
 
         // Run a `lipo -create` query.
@@ -38,7 +38,7 @@ where
 
         cmd.stderr(std::process::Stdio::inherit());
 
-        let output = cmd.output()
+        let output = runner.run(&mut cmd)
             .map_err(|io| op::ErrorProcess::Exec { name: "lipo".into(), io })?;
         if !output.status.success() {
             return Err(op::ErrorProcess::Exit { name: "lipo".into(), code: output.status });
@@ -0,0 +1,311 @@
+//! # macOS Notarization
+//!
+//! Applications distributed outside of the App Store must be notarized by
+//! Apple. This module provides structured access to the `notarytool` utility,
+//! which submits signed archives to the notary service, as well as the
+//! `stapler` utility, which attaches the resulting ticket to the archive.
+
+use crate::op;
+
+/// Environment variable carrying the name of a keychain profile created via
+/// `xcrun notarytool store-credentials`.
+pub const ENV_KEYCHAIN_PROFILE: &str = "OSIRIS_NOTARY_KEYCHAIN_PROFILE";
+/// Environment variable carrying the path to the keychain holding the
+/// keychain profile, if not the default keychain.
+pub const ENV_KEYCHAIN: &str = "OSIRIS_NOTARY_KEYCHAIN";
+/// Environment variable carrying the path to an App Store Connect API key.
+pub const ENV_KEY: &str = "OSIRIS_NOTARY_KEY";
+/// Environment variable carrying the ID of the App Store Connect API key.
+pub const ENV_KEY_ID: &str = "OSIRIS_NOTARY_KEY_ID";
+/// Environment variable carrying the issuer of the App Store Connect API key.
+pub const ENV_ISSUER: &str = "OSIRIS_NOTARY_ISSUER";
+
+/// Credentials used to authenticate with the notary service.
+///
+/// Secrets are never passed on the command line, since arguments are visible
+/// to all users of the system. Apple ID passwords must thus be stored in a
+/// keychain profile beforehand. API keys are referenced by their path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Credentials {
+    /// Keychain profile created via `notarytool store-credentials`
+    KeychainProfile {
+        profile: std::ffi::OsString,
+        keychain: Option<std::path::PathBuf>,
+    },
+    /// App Store Connect API key
+    ApiKey {
+        key: std::path::PathBuf,
+        key_id: std::ffi::OsString,
+        issuer: Option<std::ffi::OsString>,
+    },
+}
+
+/// Result of a notarization submission as reported by `notarytool`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Submission {
+    /// Submission ID assigned by the notary service
+    pub id: String,
+    /// Status of the submission (e.g., `Accepted`, `Invalid`)
+    pub status: Option<String>,
+    /// Human-readable message of the notary service
+    pub message: Option<String>,
+}
+
+/// Combined arguments to a submission query via `notarytool`.
+pub struct SubmitQuery<'ctx> {
+    /// Credentials to authenticate with
+    pub credentials: &'ctx Credentials,
+    /// Path to the signed archive to submit
+    pub file: &'ctx std::path::Path,
+    /// Whether to wait for the notary service to finish processing
+    pub wait: bool,
+}
+
+/// Combined arguments to a stapling query via `stapler`.
+pub struct StapleQuery<'ctx> {
+    /// Path to the notarized archive
+    pub file: &'ctx std::path::Path,
+}
+
+impl Credentials {
+    /// Collect credentials via the specified variable lookup. An API key
+    /// takes precedence over a keychain profile.
+    pub fn from_lookup<Lookup>(lookup: Lookup) -> Option<Self>
+    where
+        Lookup: Fn(&str) -> Option<std::ffi::OsString>,
+    {
+        if let (Some(key), Some(key_id)) = (lookup(ENV_KEY), lookup(ENV_KEY_ID)) {
+            return Some(Credentials::ApiKey {
+                key: key.into(),
+                key_id: key_id,
+                issuer: lookup(ENV_ISSUER),
+            });
+        }
+
+        Some(Credentials::KeychainProfile {
+            profile: lookup(ENV_KEYCHAIN_PROFILE)?,
+            keychain: lookup(ENV_KEYCHAIN).map(|v| v.into()),
+        })
+    }
+
+    /// Collect credentials from the environment of this process. See
+    /// `Self::from_lookup()` for details.
+    pub fn from_env() -> Option<Self> {
+        Self::from_lookup(|v| std::env::var_os(v))
+    }
+
+    fn args(&self, cmd: &mut std::process::Command) {
+        match self {
+            Credentials::KeychainProfile { profile, keychain } => {
+                cmd.arg("--keychain-profile");
+                cmd.arg(profile);
+                if let Some(v) = keychain {
+                    cmd.arg("--keychain");
+                    cmd.arg(v);
+                }
+            },
+            Credentials::ApiKey { key, key_id, issuer } => {
+                cmd.arg("--key");
+                cmd.arg(key);
+                cmd.arg("--key-id");
+                cmd.arg(key_id);
+                if let Some(v) = issuer {
+                    cmd.arg("--issuer");
+                    cmd.arg(v);
+                }
+            },
+        }
+    }
+}
+
+impl Submission {
+    /// Parse the JSON output of `notarytool submit`. This returns `None` if
+    /// the output is not valid JSON or lacks a submission ID.
+    pub fn from_json(data: &[u8]) -> Option<Self> {
+        let json: serde_json::Value = serde_json::from_slice(data).ok()?;
+        let field = |v: &str| json.get(v).and_then(|v| v.as_str()).map(|v| v.to_string());
+
+        Some(Self {
+            id: field("id")?,
+            status: field("status"),
+            message: field("message"),
+        })
+    }
+
+    /// Check whether the notary service accepted the submission.
+    pub fn accepted(&self) -> bool {
+        self.status.as_deref() == Some("Accepted")
+    }
+}
+
+impl<'ctx> SubmitQuery<'ctx> {
+    fn command(&self) -> std::process::Command {
+        let mut cmd = std::process::Command::new("xcrun");
+
+        cmd.arg("notarytool");
+        cmd.arg("submit");
+
+        self.credentials.args(&mut cmd);
+
+        // Request machine-readable output, so the submission ID and status
+        // can be extracted.
+        cmd.arg("--output-format");
+        cmd.arg("json");
+
+        // Let `notarytool` poll the notary service until the submission was
+        // processed.
+        if self.wait {
+            cmd.arg("--wait");
+        }
+
+        cmd.arg(self.file);
+
+        cmd
+    }
+
+    /// Execute a submission query via the `notarytool` utility of macOS.
+    /// If the output cannot be parsed, this yields `None`, unless the tool
+    /// failed, in which case its exit condition is reported.
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<Option<Submission>, op::ErrorProcess> {
+        let mut cmd = self.command();
+
+        cmd.stderr(std::process::Stdio::inherit());
+        cmd.stdin(std::process::Stdio::null());
+
+        let output = runner.run(&mut cmd)
+            .map_err(|io| op::ErrorProcess::Exec { name: "notarytool".into(), io })?;
+
+        // `notarytool` reports rejected submissions with an error exit
+        // condition, so prefer the submission status if available.
+        match Submission::from_json(&output.stdout) {
+            Some(v) => Ok(Some(v)),
+            None if output.status.success() => Ok(None),
+            None => Err(op::ErrorProcess::Exit { name: "notarytool".into(), code: output.status }),
+        }
+    }
+}
+
+impl<'ctx> StapleQuery<'ctx> {
+    fn command(&self) -> std::process::Command {
+        let mut cmd = std::process::Command::new("xcrun");
+
+        cmd.arg("stapler");
+        cmd.arg("staple");
+        cmd.arg(self.file);
+
+        cmd
+    }
+
+    /// Execute a stapling query via the `stapler` utility of macOS.
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), op::ErrorProcess> {
+        let mut cmd = self.command();
+
+        cmd.stderr(std::process::Stdio::inherit());
+        cmd.stdin(std::process::Stdio::null());
+        cmd.stdout(std::process::Stdio::inherit());
+
+        let output = runner.run(&mut cmd)
+            .map_err(|io| op::ErrorProcess::Exec { name: "stapler".into(), io })?;
+        if !output.status.success() {
+            return Err(op::ErrorProcess::Exit { name: "stapler".into(), code: output.status });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cmd: &std::process::Command) -> Vec<&str> {
+        cmd.get_args().map(|v| v.to_str().unwrap()).collect()
+    }
+
+    // Verify credentials are collected with the correct precedence.
+    #[test]
+    fn notary_credentials() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |k: &str| vars.iter().find(|v| v.0 == k).map(|v| v.1.into())
+        };
+
+        assert_eq!(Credentials::from_lookup(env(&[])), None);
+        assert_eq!(Credentials::from_lookup(env(&[(ENV_KEYCHAIN, "ci.keychain")])), None);
+        assert_eq!(Credentials::from_lookup(env(&[(ENV_KEY, "key.p8")])), None);
+        assert_eq!(
+            Credentials::from_lookup(env(&[(ENV_KEYCHAIN_PROFILE, "osiris")])),
+            Some(Credentials::KeychainProfile {
+                profile: "osiris".into(),
+                keychain: None,
+            }),
+        );
+        assert_eq!(
+            Credentials::from_lookup(env(&[
+                (ENV_KEYCHAIN_PROFILE, "osiris"),
+                (ENV_KEY, "key.p8"),
+                (ENV_KEY_ID, "KEY"),
+            ])),
+            Some(Credentials::ApiKey {
+                key: "key.p8".into(),
+                key_id: "KEY".into(),
+                issuer: None,
+            }),
+        );
+    }
+
+    // Verify the argument assembly of submission and stapling queries.
+    #[test]
+    fn notary_arguments() {
+        let profile = Credentials::KeychainProfile {
+            profile: "osiris".into(),
+            keychain: Some("ci.keychain".into()),
+        };
+        let api_key = Credentials::ApiKey {
+            key: "key.p8".into(),
+            key_id: "KEY".into(),
+            issuer: Some("ISSUER".into()),
+        };
+        let file = std::path::Path::new("app.pkg");
+
+        let cmd = SubmitQuery { credentials: &profile, file: file, wait: true }.command();
+        assert_eq!(cmd.get_program(), "xcrun");
+        assert_eq!(
+            args(&cmd),
+            [
+                "notarytool", "submit",
+                "--keychain-profile", "osiris", "--keychain", "ci.keychain",
+                "--output-format", "json", "--wait", "app.pkg",
+            ],
+        );
+
+        let cmd = SubmitQuery { credentials: &api_key, file: file, wait: false }.command();
+        assert_eq!(
+            args(&cmd),
+            [
+                "notarytool", "submit",
+                "--key", "key.p8", "--key-id", "KEY", "--issuer", "ISSUER",
+                "--output-format", "json", "app.pkg",
+            ],
+        );
+
+        let cmd = StapleQuery { file: file }.command();
+        assert_eq!(args(&cmd), ["stapler", "staple", "app.pkg"]);
+    }
+
+    // Verify the submission output of `notarytool` is parsed.
+    #[test]
+    fn notary_submission() {
+        let v = Submission::from_json(
+            br#"{"id":"2efe2717","status":"Accepted","message":"Processing complete"}"#,
+        ).unwrap();
+        assert_eq!(v.id, "2efe2717");
+        assert!(v.accepted());
+
+        let v = Submission::from_json(br#"{"id":"2efe2717","status":"Invalid"}"#).unwrap();
+        assert!(!v.accepted());
+        assert_eq!(v.message, None);
+
+        assert_eq!(Submission::from_json(br#"{"status":"Accepted"}"#), None);
+        assert_eq!(Submission::from_json(b"Error: HTTP status code: 401"), None);
+    }
+}
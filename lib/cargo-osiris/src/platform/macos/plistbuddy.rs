@@ -17,7 +17,7 @@ pub struct MergeQuery<'ctx> {
 
 impl<'ctx> MergeQuery<'ctx> {
     /// Execute a merge query via the `PlistBuddy` utility of macOS.
-    pub fn run(&self) -> Result<(), op::ErrorProcess> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), op::ErrorProcess> {
         let mut cmd = std::process::Command::new("/usr/libexec/PlistBuddy");
 
         // Assemble the merge query.
@@ -36,7 +36,7 @@ impl<'ctx> MergeQuery<'ctx> {
 
         cmd.stderr(std::process::Stdio::inherit());
 
-        let output = cmd.output()
+        let output = runner.run(&mut cmd)
             .map_err(|io| op::ErrorProcess::Exec { name: "PlistBuddy".into(), io })?;
         if !output.status.success() {
             return Err(op::ErrorProcess::Exit { name: "PlistBuddy".into(), code: output.status });
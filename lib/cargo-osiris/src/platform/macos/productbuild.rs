@@ -20,7 +20,7 @@ where
     ComponentList: Clone + Iterator<Item = &'ctx (&'ctx std::path::Path, &'ctx std::path::Path)>,
 {
     /// Execute a build-query via the `productbuild` utility of macOS.
-    pub fn run(&self) -> Result<(), op::ErrorProcess> {
+    pub fn run(&self, runner: &dyn op::ToolRunner) -> Result<(), op::ErrorProcess> {
         let mut cmd = std::process::Command::new("xcrun");
 
         cmd.arg("productbuild");
@@ -43,10 +43,10 @@ where
         cmd.stdin(std::process::Stdio::null());
         cmd.stdout(std::process::Stdio::inherit());
 
-        let status = cmd.status()
+        let output = runner.run(&mut cmd)
             .map_err(|io| op::ErrorProcess::Exec { name: "productbuild".into(), io })?;
-        if !status.success() {
-            return Err(op::ErrorProcess::Exit { name: "productbuild".into(), code: status });
+        if !output.status.success() {
+            return Err(op::ErrorProcess::Exit { name: "productbuild".into(), code: output.status });
         }
 
         Ok(())
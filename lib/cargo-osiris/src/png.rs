@@ -0,0 +1,158 @@
+//! # PNG Encoder
+//!
+//! A minimal encoder for PNG images, sufficient to generate simple images
+//! (e.g., placeholder icons) without depending on an image library. Image
+//! data is written as 8-bit RGBA without any filtering, and embedded in
+//! uncompressed deflate blocks. This produces valid, but large, images.
+
+use crate::misc;
+
+// Maximum length of an uncompressed deflate block.
+const BLOCK_MAX: usize = 0xffff;
+
+// Calculate the Adler-32 checksum of the data, as used by zlib streams.
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &v in data {
+        a = (a + v as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    (b << 16) | a
+}
+
+// Append a PNG chunk with the given type and data to the image.
+fn chunk(acc: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let start = acc.len() + 4;
+
+    acc.extend_from_slice(&u32::try_from(data.len()).unwrap().to_be_bytes());
+    acc.extend_from_slice(kind);
+    acc.extend_from_slice(data);
+
+    let crc = misc::crc32(&acc[start..]);
+    acc.extend_from_slice(&crc.to_be_bytes());
+}
+
+// Wrap the data in a zlib stream with uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let n_blocks = core::cmp::max(data.len().div_ceil(BLOCK_MAX), 1);
+    let mut acc = Vec::with_capacity(2 + n_blocks * 5 + data.len() + 4);
+
+    // Deflate with a 32KiB window and no preset dictionary, which makes
+    // the header a multiple of 31 as required.
+    acc.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(BLOCK_MAX).peekable();
+    if blocks.peek().is_none() {
+        acc.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = u16::try_from(block.len()).unwrap();
+        acc.push(match blocks.peek() {
+            None => 0x01,
+            Some(_) => 0x00,
+        });
+        acc.extend_from_slice(&len.to_le_bytes());
+        acc.extend_from_slice(&(!len).to_le_bytes());
+        acc.extend_from_slice(block);
+    }
+
+    acc.extend_from_slice(&adler32(data).to_be_bytes());
+    acc
+}
+
+/// Encode an image of the given dimensions as PNG. The pixels must be
+/// given row by row as 8-bit RGBA values.
+pub fn encode_rgba(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let n_row = width as usize * 4;
+    assert_eq!(pixels.len(), n_row * height as usize);
+
+    // Prefix every row with filter-type 0 (i.e., no filtering).
+    let mut raw = Vec::with_capacity((n_row + 1) * height as usize);
+    for row in pixels.chunks(n_row.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8-bit depth, RGBA color, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut acc = Vec::new();
+    acc.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+    chunk(&mut acc, b"IHDR", &header);
+    chunk(&mut acc, b"IDAT", &zlib_stored(&raw));
+    chunk(&mut acc, b"IEND", &[]);
+    acc
+}
+
+/// Encode a square image of the given size filled with a single color as
+/// PNG.
+pub fn solid(size: u32, rgba: [u8; 4]) -> Vec<u8> {
+    let n = size as usize * size as usize;
+    let pixels: Vec<u8> = rgba.iter().copied().cycle().take(n * 4).collect();
+    encode_rgba(size, size, &pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify checksums against known values.
+    #[test]
+    fn png_checksums() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    // Verify the structure of generated images by decoding all chunks and
+    // the uncompressed deflate blocks again.
+    #[test]
+    fn png_solid() {
+        for size in [1, 16, 200] {
+            let data = solid(size, [0x80, 0x40, 0x20, 0xff]);
+            assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+
+            let mut chunks = Vec::new();
+            let mut rem = &data[8..];
+            while !rem.is_empty() {
+                let len = u32::from_be_bytes(rem[..4].try_into().unwrap()) as usize;
+                let crc = u32::from_be_bytes(rem[8 + len..12 + len].try_into().unwrap());
+                assert_eq!(misc::crc32(&rem[4..8 + len]), crc);
+                chunks.push((&rem[4..8], &rem[8..8 + len]));
+                rem = &rem[12 + len..];
+            }
+
+            assert_eq!(
+                chunks.iter().map(|v| v.0).collect::<Vec<_>>(),
+                [b"IHDR", b"IDAT", b"IEND"],
+            );
+            assert_eq!(&chunks[0].1[..4], &size.to_be_bytes());
+            assert_eq!(&chunks[0].1[4..8], &size.to_be_bytes());
+
+            let mut raw = Vec::new();
+            let mut rem = &chunks[1].1[2..];
+            loop {
+                let len = u16::from_le_bytes(rem[1..3].try_into().unwrap());
+                assert_eq!(!len, u16::from_le_bytes(rem[3..5].try_into().unwrap()));
+                raw.extend_from_slice(&rem[5..5 + len as usize]);
+                let last = rem[0] == 0x01;
+                rem = &rem[5 + len as usize..];
+                if last {
+                    break;
+                }
+            }
+            assert_eq!(rem, adler32(&raw).to_be_bytes());
+
+            let n_row = 1 + size as usize * 4;
+            assert_eq!(raw.len(), n_row * size as usize);
+            for row in raw.chunks(n_row) {
+                assert_eq!(row[0], 0);
+                assert!(row[1..].chunks(4).all(|v| v == [0x80, 0x40, 0x20, 0xff]));
+            }
+        }
+    }
+}
@@ -24,6 +24,125 @@ pub struct Setup<'ctx> {
     pub package: Option<&'ctx str>,
 }
 
+/// ## URL Errors
+///
+/// This is the error-enum of all possible errors raised when opening URLs.
+#[derive(Debug)]
+pub enum OpenUrlError {
+    /// URL is not a valid absolute URL.
+    Invalid,
+    /// URL scheme is not allowed.
+    SchemeUnsupported(String),
+    /// Platform failed to launch a handler for the URL.
+    Launch(Box<dyn std::error::Error>),
+}
+
+/// ## File Filter
+///
+/// A named set of file extensions offered by file dialogs. Extensions are
+/// given without leading dot (a leading `.` or `*.` is ignored). Platforms
+/// that do not support named filters allow the extensions of all filters
+/// together.
+pub struct FileFilter<'ctx> {
+    /// Name of the filter shown to the user.
+    pub name: &'ctx str,
+    /// File extensions allowed by the filter.
+    pub extensions: &'ctx [&'ctx str],
+}
+
+/// ## File Dialog
+///
+/// The parameters of a file dialog shown via `Context::open_file()` or
+/// `Context::save_file()`.
+#[derive(Default)]
+pub struct FileDialog<'ctx> {
+    /// Title of the dialog, or `None` to use a platform default.
+    pub title: Option<&'ctx str>,
+    /// File filters to offer to the user. If empty, all files are allowed.
+    pub filters: &'ctx [FileFilter<'ctx>],
+    /// Directory to start in, or `None` to use a platform default. Some
+    /// platforms only approximate this with a well-known location.
+    pub directory: Option<&'ctx std::path::Path>,
+    /// Suggested file name when saving.
+    pub name: Option<&'ctx str>,
+}
+
+/// ## File Dialog Errors
+///
+/// This is the error-enum of all possible errors raised by file dialogs.
+#[derive(Debug)]
+pub enum FileDialogError {
+    /// No display or window server is available to show dialogs on.
+    Headless,
+    /// Platform failed to show the dialog.
+    Native(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// ## Menu Accelerator
+///
+/// A keyboard shortcut that activates a menu action. `primary` selects the
+/// primary modifier of the platform, which is `Cmd` on macOS and `Ctrl`
+/// everywhere else. Keys are given as lower-case characters, and platforms
+/// might only support a limited set of keys (e.g., `a-z` and `0-9`).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Accelerator {
+    /// Key to press.
+    pub key: char,
+    /// Whether the primary modifier must be held.
+    pub primary: bool,
+    /// Whether `Shift` must be held.
+    pub shift: bool,
+    /// Whether `Alt` (`Option` on macOS) must be held.
+    pub alt: bool,
+}
+
+/// ## Menu Item
+///
+/// A single item of a menu. Actions carry a label, an optional accelerator,
+/// and the callback invoked on the main-loop when the action is activated.
+pub enum MenuItem {
+    /// Action with a label, an optional accelerator, and a callback.
+    Action(String, Option<Accelerator>, std::rc::Rc<dyn Fn()>),
+    /// Separator between groups of items.
+    Separator,
+    /// Nested menu.
+    Submenu(Menu),
+}
+
+/// ## Menu
+///
+/// A labeled list of menu items. Menus are described declaratively and
+/// installed via `Context::set_menu_bar()`.
+#[derive(Default)]
+pub struct Menu {
+    /// Label of the menu.
+    pub label: String,
+    /// Items of the menu, in display order.
+    pub items: Vec<MenuItem>,
+}
+
+// Shared state of a file dialog and the future waiting for it. The result
+// is stored by the platform completion, which can run on any thread.
+#[derive(Default)]
+struct FileDialogState {
+    result: Option<Result<Option<std::path::PathBuf>, FileDialogError>>,
+    waker: Option<core::task::Waker>,
+}
+
+// Future resolving to the result of a file dialog.
+struct FileDialogFuture {
+    state: std::sync::Arc<std::sync::Mutex<FileDialogState>>,
+}
+
+// Quit state of the application main-loop. It tracks the exit code
+// requested by the application, if any. Whether the main-loop runs is
+// tracked by the native backends, which also record requests made before
+// the main-loop is entered.
+#[derive(Default)]
+struct QuitState {
+    state: std::sync::Mutex<Option<u8>>,
+}
+
 /// ## Application Context
 ///
 /// The context of the local application, providing access to system APIs
@@ -32,6 +151,7 @@ pub struct Context {
     pub(crate) native: crate::native::application::Context,
     pub(crate) out_dir: Option<std::path::PathBuf>,
     pub(crate) package: Option<String>,
+    quit: QuitState,
 }
 
 impl<'ctx> From<crate::native::application::Setup<'ctx>> for Setup<'ctx> {
@@ -64,6 +184,7 @@ impl<'ctx> Setup<'ctx> {
             native: v_native,
             out_dir: self.out_dir.map(|v| v.into()),
             package: self.package.map(|v| v.into()),
+            quit: Default::default(),
         })
     }
 }
@@ -174,12 +295,559 @@ impl Context {
                 .join(subdir_install)
         )
     }
+
+    // Show a file dialog via the given platform function. Failures to show
+    // the dialog are reported through the returned future as well.
+    fn file_dialog(
+        &self,
+        dialog: &FileDialog,
+        show: fn(
+            &crate::native::application::Context,
+            &crate::native::dialog::Options,
+            crate::native::dialog::Done,
+        ) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> FileDialogFuture {
+        let future = FileDialogFuture::new();
+
+        if crate::native::dialog::headless() {
+            future.fail(FileDialogError::Headless);
+            return future;
+        }
+
+        let extensions: Vec<Vec<&str>> = dialog.filters.iter()
+            .map(|v| v.extensions.iter().map(|v| file_extension(v)).collect())
+            .collect();
+        let filters: Vec<(&str, &[&str])> = dialog.filters.iter()
+            .zip(extensions.iter())
+            .map(|(v, e)| (v.name, e.as_slice()))
+            .collect();
+        let options = crate::native::dialog::Options {
+            title: dialog.title,
+            filters: &filters,
+            directory: dialog.directory,
+            name: dialog.name,
+        };
+
+        if let Err(v) = show(&self.native, &options, future.completion()) {
+            future.fail(FileDialogError::Native(v.to_string().into()));
+        }
+
+        future
+    }
+
+    /// ## Show Open File Dialog
+    ///
+    /// Show a dialog to let the user select an existing file. The dialog is
+    /// shown immediately, and the returned future resolves to the selected
+    /// path, or `None` if the dialog was cancelled. If no display is
+    /// available, `FileDialogError::Headless` is returned.
+    ///
+    /// This must be called on the thread of the main-loop. On most
+    /// platforms, the dialog is driven by the main-loop, which must keep
+    /// running for the future to resolve. On Windows, the dialog is modal
+    /// and runs a nested message loop instead, so this returns only once
+    /// the dialog was closed, with the future already resolved. In either
+    /// case, the future must not be blocked on from the main thread.
+    pub fn open_file(
+        &self,
+        dialog: &FileDialog,
+    ) -> impl core::future::Future<Output = Result<Option<std::path::PathBuf>, FileDialogError>> {
+        self.file_dialog(dialog, crate::native::dialog::open_file)
+    }
+
+    /// ## Show Save File Dialog
+    ///
+    /// Show a dialog to let the user select a file path to save to. See
+    /// `Self::open_file()` for details.
+    pub fn save_file(
+        &self,
+        dialog: &FileDialog,
+    ) -> impl core::future::Future<Output = Result<Option<std::path::PathBuf>, FileDialogError>> {
+        self.file_dialog(dialog, crate::native::dialog::save_file)
+    }
+
+    /// ## Run Application Main-Loop
+    ///
+    /// Enter the main-loop of the platform and dispatch events until
+    /// `Self::request_quit()` is called. The exit code requested by the
+    /// application is returned, or `0` if the main-loop stopped for other
+    /// reasons.
+    ///
+    /// If a quit was requested before the main-loop was entered, the
+    /// main-loop returns without dispatching any events and the requested
+    /// exit code is returned.
+    pub fn run(&self) -> std::process::ExitCode {
+        self.quit.run(|| self.native.run()).into()
+    }
+
+    /// ## Request Application Quit
+    ///
+    /// Stop the main-loop of the platform and make `Self::run()` return
+    /// the given exit code. If the main-loop is not running, the request is
+    /// recorded and the next call to `Self::run()` returns immediately. If
+    /// multiple requests are made, the last one wins.
+    pub fn request_quit(&self, code: u8) {
+        self.quit.request(code, || self.native.quit());
+    }
+
+    /// ## Set Menu Bar
+    ///
+    /// Set the menu bar of the application to the given menus, replacing
+    /// any previous menu bar. Action callbacks are invoked on the main-loop.
+    ///
+    /// The menu bar is placed according to the conventions of the platform:
+    ///
+    /// - On macOS, the menus form the global main menu. The first menu is
+    ///   used as application menu and shown with the application name,
+    ///   regardless of its label.
+    /// - On Linux, there is no global menu bar. The menus are exported as
+    ///   menu model for windows to place in their header bar. Accelerators
+    ///   require a display, and are registered with GTK.
+    /// - On Windows, the menus form a menu bar that windows attach via
+    ///   `SetMenu()`. They must forward `WM_COMMAND` to the native menu
+    ///   module, while accelerators are translated by `Self::run()`.
+    ///   Windows showing a previous menu bar are switched to the new one.
+    pub fn set_menu_bar(
+        &self,
+        menus: &[Menu],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut actions = Vec::new();
+        let entries: Vec<_> = menus.iter()
+            .map(|v| crate::native::menu::Entry::Submenu {
+                label: &v.label,
+                entries: menu_entries(&v.items, &mut actions),
+            })
+            .collect();
+
+        crate::native::menu::set_menu_bar(&self.native, &entries, menu_dispatch(actions))
+    }
+}
+
+impl QuitState {
+    // Run the main-loop via `main` and yield the requested exit code. The
+    // lock is not held while the main-loop runs, so it can be requested to
+    // quit from within.
+    fn run(&self, main: impl FnOnce()) -> u8 {
+        main();
+        self.state.lock().unwrap().take().unwrap_or(0)
+    }
+
+    // Record the requested exit code and stop the main-loop via `quit`. The
+    // native backends keep the request pending if the main-loop is not
+    // running, so it is never lost.
+    fn request(&self, code: u8, quit: impl FnOnce()) {
+        *self.state.lock().unwrap() = Some(code);
+        quit();
+    }
+}
+
+impl core::fmt::Display for FileDialogError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::Headless => fmt.write_str("No display available to show file dialogs on"),
+            Self::Native(v) => fmt.write_fmt(core::format_args!("Cannot show file dialog: {}", v)),
+        }
+    }
+}
+
+impl std::error::Error for FileDialogError {
+}
+
+impl FileDialogFuture {
+    fn new() -> Self {
+        Self {
+            state: Default::default(),
+        }
+    }
+
+    // Create the completion callback passed to the platform. It stores the
+    // result and wakes the task awaiting the dialog.
+    fn completion(
+        &self,
+    ) -> Box<
+        dyn FnOnce(
+            Result<Option<std::path::PathBuf>, Box<dyn std::error::Error + Send + Sync>>,
+        ) + Send,
+    > {
+        let state = self.state.clone();
+
+        Box::new(move |v| {
+            let mut state = state.lock().unwrap();
+            state.result = Some(v.map_err(FileDialogError::Native));
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        })
+    }
+
+    fn fail(&self, error: FileDialogError) {
+        self.state.lock().unwrap().result = Some(Err(error));
+    }
+}
+
+impl core::future::Future for FileDialogFuture {
+    type Output = Result<Option<std::path::PathBuf>, FileDialogError>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        ctx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(v) => core::task::Poll::Ready(v),
+            None => {
+                state.waker = Some(ctx.waker().clone());
+                core::task::Poll::Pending
+            },
+        }
+    }
+}
+
+impl Accelerator {
+    /// ## Create Primary Accelerator
+    ///
+    /// Create an accelerator for the given key with the primary modifier of
+    /// the platform (e.g., `Ctrl+O` or `Cmd+O`).
+    pub fn primary(key: char) -> Self {
+        Self {
+            key: key,
+            primary: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl Menu {
+    /// ## Create New Menu
+    ///
+    /// Create a new empty menu with the given label.
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.into(),
+            items: Vec::new(),
+        }
+    }
+
+    /// ## Append Action
+    ///
+    /// Append an action with the given label and accelerator, invoking
+    /// `callback` when activated.
+    pub fn action(
+        mut self,
+        label: &str,
+        accelerator: Option<Accelerator>,
+        callback: impl Fn() + 'static,
+    ) -> Self {
+        self.items.push(MenuItem::Action(label.into(), accelerator, std::rc::Rc::new(callback)));
+        self
+    }
+
+    /// ## Append Separator
+    ///
+    /// Append a separator after the current items.
+    pub fn separator(mut self) -> Self {
+        self.items.push(MenuItem::Separator);
+        self
+    }
+
+    /// ## Append Submenu
+    ///
+    /// Append a nested menu.
+    pub fn submenu(mut self, menu: Menu) -> Self {
+        self.items.push(MenuItem::Submenu(menu));
+        self
+    }
+}
+
+// Convert menu items into native menu entries. Actions are numbered in
+// document order, and their callbacks are collected in `actions` at the
+// index of their ID.
+fn menu_entries<'menu>(
+    items: &'menu [MenuItem],
+    actions: &mut Vec<std::rc::Rc<dyn Fn()>>,
+) -> Vec<crate::native::menu::Entry<'menu>> {
+    items.iter()
+        .map(|v| match v {
+            MenuItem::Action(label, accelerator, callback) => {
+                actions.push(callback.clone());
+                crate::native::menu::Entry::Action {
+                    id: actions.len() - 1,
+                    label: label,
+                    accelerator: accelerator.map(|v| crate::native::menu::Accelerator {
+                        key: v.key.to_ascii_lowercase(),
+                        primary: v.primary,
+                        shift: v.shift,
+                        alt: v.alt,
+                    }),
+                }
+            },
+            MenuItem::Separator => crate::native::menu::Entry::Separator,
+            MenuItem::Submenu(v) => crate::native::menu::Entry::Submenu {
+                label: &v.label,
+                entries: menu_entries(&v.items, actions),
+            },
+        })
+        .collect()
+}
+
+// Create the dispatch callback of the native menu bar, which invokes the
+// callback of the action with the given ID. Unknown IDs are ignored.
+fn menu_dispatch(actions: Vec<std::rc::Rc<dyn Fn()>>) -> crate::native::menu::Dispatch {
+    Box::new(move |id| {
+        if let Some(v) = actions.get(id) {
+            v();
+        }
+    })
+}
+
+// Strip the optional leading `*.` or `.` of a file extension.
+fn file_extension(v: &str) -> &str {
+    let v = v.strip_prefix('*').unwrap_or(v);
+    v.strip_prefix('.').unwrap_or(v)
+}
+
+impl core::fmt::Display for OpenUrlError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::Invalid => fmt.write_str("Invalid URL"),
+            Self::SchemeUnsupported(v) => fmt.write_fmt(core::format_args!("Unsupported URL scheme: {}", v)),
+            Self::Launch(v) => fmt.write_fmt(core::format_args!("Cannot launch URL handler: {}", v)),
+        }
+    }
+}
+
+impl std::error::Error for OpenUrlError {
+}
+
+/// ## Validate URL
+///
+/// Verify that the given string is an absolute URL with one of the given
+/// schemes (compared ASCII case-insensitively), and return its scheme. URLs
+/// must not contain whitespace or control characters. For `http` and
+/// `https`, a non-empty authority is required.
+///
+/// This does not perform a full validation of the URL, but ensures no
+/// unexpected handler is launched for it.
+pub fn validate_url<'url>(
+    url: &'url str,
+    schemes: &[&str],
+) -> Result<&'url str, OpenUrlError> {
+    if url.chars().any(|v| v.is_whitespace() || v.is_control()) {
+        return Err(OpenUrlError::Invalid);
+    }
+
+    // Schemes must start with a letter, followed by any combination of
+    // letters, digits, `+`, `-`, and `.` (see RFC-3986).
+    let (scheme, rem) = url.split_once(':').ok_or(OpenUrlError::Invalid)?;
+    if !scheme.starts_with(|v: char| v.is_ascii_alphabetic())
+        || !scheme.chars().all(|v| v.is_ascii_alphanumeric() || v == '+' || v == '-' || v == '.')
+    {
+        return Err(OpenUrlError::Invalid);
+    }
+
+    if !schemes.iter().any(|v| v.eq_ignore_ascii_case(scheme)) {
+        return Err(OpenUrlError::SchemeUnsupported(scheme.into()));
+    }
+
+    if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") {
+        let authority = rem.strip_prefix("//").ok_or(OpenUrlError::Invalid)?;
+        let host = authority.split(['/', '?', '#']).next().unwrap_or("");
+        if host.is_empty() {
+            return Err(OpenUrlError::Invalid);
+        }
+    }
+
+    Ok(scheme)
+}
+
+/// ## Open URL with Selected Schemes
+///
+/// Launch the default handler of the platform for the given URL, allowing
+/// any of the given schemes. See `validate_url()` for the validation that
+/// is performed before launching a handler.
+pub fn open_url_with(url: &str, schemes: &[&str]) -> Result<(), OpenUrlError> {
+    validate_url(url, schemes)?;
+    crate::native::application::open_url(url).map_err(OpenUrlError::Launch)
+}
+
+/// ## Open URL
+///
+/// Launch the default handler of the platform for the given URL (usually
+/// the default web browser). Only `http` and `https` URLs are allowed, to
+/// avoid launching arbitrary handlers. Use `open_url_with()` to allow other
+/// schemes.
+pub fn open_url(url: &str) -> Result<(), OpenUrlError> {
+    open_url_with(url, &["http", "https"])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // Verify URL validation and scheme restrictions.
+    #[test]
+    fn url_validation() {
+        let web = ["http", "https"];
+
+        assert_eq!(validate_url("https://www.osiris.foo", &web).unwrap(), "https");
+        assert_eq!(validate_url("HTTP://osiris.foo/a?b#c", &web).unwrap(), "HTTP");
+        assert_eq!(validate_url("http://[::1]:80/", &web).unwrap(), "http");
+
+        for v in [
+            "",
+            "osiris.foo",
+            "://osiris.foo",
+            "1http://osiris.foo",
+            "ht tp://osiris.foo",
+            "https://osiris.foo/a b",
+            "https://osiris.foo/\n",
+            "https:osiris.foo",
+            "https://",
+            "https:///path",
+        ] {
+            assert!(matches!(validate_url(v, &web), Err(OpenUrlError::Invalid)), "{}", v);
+        }
+
+        for (v, scheme) in [
+            ("file:///etc/passwd", "file"),
+            ("javascript:alert(1)", "javascript"),
+            ("mailto:dev@osiris.foo", "mailto"),
+            ("ms-settings:display", "ms-settings"),
+        ] {
+            match validate_url(v, &web) {
+                Err(OpenUrlError::SchemeUnsupported(v)) => assert_eq!(v, scheme),
+                _ => panic!("Scheme must be rejected: {}", v),
+            }
+        }
+
+        assert_eq!(validate_url("mailto:dev@osiris.foo", &["mailto"]).unwrap(), "mailto");
+    }
+
+    // Verify file dialog futures resolve once the completion is invoked,
+    // and that extensions are normalized.
+    #[test]
+    fn file_dialog_future() {
+        struct Wake(std::sync::atomic::AtomicBool);
+
+        impl std::task::Wake for Wake {
+            fn wake(self: std::sync::Arc<Self>) {
+                self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+
+        let wake = std::sync::Arc::new(Wake(Default::default()));
+        let waker = core::task::Waker::from(wake.clone());
+        let mut ctx = core::task::Context::from_waker(&waker);
+
+        let mut future = FileDialogFuture::new();
+        let done = future.completion();
+        assert!(core::future::Future::poll(core::pin::Pin::new(&mut future), &mut ctx).is_pending());
+        assert!(!wake.0.load(std::sync::atomic::Ordering::SeqCst));
+
+        std::thread::spawn(move || done(Ok(Some("foo.json".into())))).join().unwrap();
+        assert!(wake.0.load(std::sync::atomic::Ordering::SeqCst));
+        match core::future::Future::poll(core::pin::Pin::new(&mut future), &mut ctx) {
+            core::task::Poll::Ready(Ok(Some(v))) => assert_eq!(v, std::path::Path::new("foo.json")),
+            _ => panic!("File dialog must resolve to the selected path"),
+        }
+
+        let mut future = FileDialogFuture::new();
+        future.fail(FileDialogError::Headless);
+        assert!(matches!(
+            core::future::Future::poll(core::pin::Pin::new(&mut future), &mut ctx),
+            core::task::Poll::Ready(Err(FileDialogError::Headless)),
+        ));
+
+        assert_eq!(file_extension("json"), "json");
+        assert_eq!(file_extension(".json"), "json");
+        assert_eq!(file_extension("*.json"), "json");
+    }
+
+    // Verify that requested exit codes are returned by the main-loop, both
+    // if requested from within the loop and before it is entered.
+    #[test]
+    fn quit_request() {
+        // Stubbed main-loop that keeps requests pending like the native
+        // backends, and counts the events it dispatched.
+        let quit = QuitState::default();
+        let pending = std::cell::Cell::new(false);
+        let events = std::cell::Cell::new(0);
+        let main = |event: &dyn Fn()| {
+            while !pending.replace(false) {
+                events.set(events.get() + 1);
+                event();
+            }
+        };
+
+        let code = quit.run(|| main(&|| quit.request(2, || pending.set(true))));
+        assert_eq!(code, 2);
+        assert_eq!(events.replace(0), 1);
+
+        quit.request(3, || pending.set(true));
+        assert_eq!(quit.run(|| main(&|| panic!("Main-loop must not dispatch"))), 3);
+        assert_eq!(events.get(), 0);
+
+        assert_eq!(quit.run(|| {}), 0);
+    }
+
+    // Verify that menus are converted to native entries with actions
+    // numbered in document order, and that dispatch invokes the callback of
+    // the respective action.
+    #[test]
+    fn menu_bar() {
+        let fired = std::rc::Rc::new(std::cell::Cell::new(None));
+        let fire = |v: usize| {
+            let fired = fired.clone();
+            move || fired.set(Some(v))
+        };
+
+        let menus = [
+            Menu::new("File")
+                .action("Open", Some(Accelerator::primary('O')), fire(0))
+                .separator()
+                .action("Quit", None, fire(1)),
+            Menu::new("Help")
+                .submenu(Menu::new("More").action("About", None, fire(2))),
+        ];
+
+        let mut actions = Vec::new();
+        let file = menu_entries(&menus[0].items, &mut actions);
+        let help = menu_entries(&menus[1].items, &mut actions);
+        assert_eq!(actions.len(), 3);
+
+        assert_eq!(file.len(), 3);
+        match &file[0] {
+            crate::native::menu::Entry::Action { id, label, accelerator: Some(v) } => {
+                assert_eq!((*id, *label), (0, "Open"));
+                assert_eq!((v.key, v.primary, v.shift, v.alt), ('o', true, false, false));
+            },
+            _ => panic!("Expected action with accelerator"),
+        }
+        assert!(matches!(file[1], crate::native::menu::Entry::Separator));
+        assert!(matches!(
+            file[2],
+            crate::native::menu::Entry::Action { id: 1, label: "Quit", accelerator: None },
+        ));
+
+        match &help[..] {
+            [crate::native::menu::Entry::Submenu { label: "More", entries }] => {
+                assert!(matches!(
+                    entries[..],
+                    [crate::native::menu::Entry::Action { id: 2, label: "About", .. }],
+                ));
+            },
+            _ => panic!("Expected submenu"),
+        }
+
+        let dispatch = menu_dispatch(actions);
+        dispatch(2);
+        assert_eq!(fired.get(), Some(2));
+        dispatch(1);
+        assert_eq!(fired.get(), Some(1));
+        dispatch(3);
+        assert_eq!(fired.get(), Some(1));
+    }
+
     #[test]
     fn data_location() {
         let app = Setup {
@@ -7,7 +7,7 @@
 //! See [`derive`] for the entry-point of the attribute handler.
 
 // XXX:
-// - Add support for `union` and `enum`.
+// - Add support for `union`.
 // - Implement infrastructure for proper diagnostics.
 // - Look into alternatives for `#[proc_macro_derive]` to replace
 //   `DeriveKind::Macro` with proper proc-macros.
@@ -59,9 +59,14 @@ pub struct DeriveList {
 
 /// ## Item to Derive for
 ///
-/// This is the type of the item to derive traits for. It currently aliases
-/// [`::syn::ItemStruct`], but may support more in the future.
-pub type DeriveItem = syn::ItemStruct;
+/// This is the type of the item to derive traits for. Both `struct`-types
+/// and `enum`-types are supported.
+pub enum DeriveItem {
+    /// Target is a `struct`-type.
+    Struct(syn::ItemStruct),
+    /// Target is an `enum`-type.
+    Enum(syn::ItemEnum),
+}
 
 impl quote::ToTokens for IdentOrLitInt {
     fn to_tokens(&self, code: &mut proc_macro2::TokenStream) {
@@ -72,6 +77,85 @@ impl quote::ToTokens for IdentOrLitInt {
     }
 }
 
+impl DeriveItem {
+    fn ident(&self) -> &syn::Ident {
+        match self {
+            DeriveItem::Struct(v) => &v.ident,
+            DeriveItem::Enum(v) => &v.ident,
+        }
+    }
+
+    fn generics(&self) -> &syn::Generics {
+        match self {
+            DeriveItem::Struct(v) => &v.generics,
+            DeriveItem::Enum(v) => &v.generics,
+        }
+    }
+
+    fn fields_mut(&mut self) -> Vec<&mut syn::Field> {
+        match self {
+            DeriveItem::Struct(v) => v.fields.iter_mut().collect(),
+            DeriveItem::Enum(v) => {
+                v.variants
+                    .iter_mut()
+                    .flat_map(|v| v.fields.iter_mut())
+                    .collect()
+            },
+        }
+    }
+
+    // Check whether further attribute macros will be applied to the item,
+    // which might be direct derives that need the field attributes as well.
+    // Paths cannot be resolved here, so any attribute but the inert builtin
+    // attributes is considered pending. If it turns out not to be a direct
+    // derive, the compiler rejects the left-over field attributes, rather
+    // than a later direct derive silently ignoring them.
+    fn macro_pending(&self) -> bool {
+        const INERT: &[&str] = &[
+            "allow", "cfg", "cfg_attr", "deny", "deprecated", "derive", "doc",
+            "expect", "forbid", "must_use", "non_exhaustive", "repr", "warn",
+        ];
+        const TOOLS: &[&str] = &["clippy", "diagnostic", "rustdoc", "rustfmt"];
+
+        let attrs = match self {
+            DeriveItem::Struct(v) => &v.attrs,
+            DeriveItem::Enum(v) => &v.attrs,
+        };
+
+        attrs.iter().any(|v| {
+            let path = v.path();
+            let first = &path.segments[0].ident;
+            if path.segments.len() > 1 {
+                !TOOLS.iter().any(|v| first == v)
+            } else {
+                !INERT.iter().any(|v| first == v)
+            }
+        })
+    }
+}
+
+impl syn::parse::Parse for DeriveItem {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        match input.parse::<syn::Item>()? {
+            syn::Item::Struct(v) => Ok(DeriveItem::Struct(v)),
+            syn::Item::Enum(v) => Ok(DeriveItem::Enum(v)),
+            v => Err(syn::Error::new_spanned(
+                v,
+                "Only `struct`-types and `enum`-types are supported",
+            )),
+        }
+    }
+}
+
+impl quote::ToTokens for DeriveItem {
+    fn to_tokens(&self, code: &mut proc_macro2::TokenStream) {
+        match self {
+            DeriveItem::Struct(v) => v.to_tokens(code),
+            DeriveItem::Enum(v) => v.to_tokens(code),
+        }
+    }
+}
+
 impl DeriveArg {
     fn new(kind: DeriveKind) -> Self {
         Self {
@@ -217,90 +301,206 @@ fn generics_wheres(
     }
 }
 
+/// ## Parse Field Attributes
+///
+/// Parse the helper attributes of a field and return whether the field is
+/// skipped via `#[dd(skip)]`. Any other argument to the `dd` helper
+/// attribute is rejected.
+fn field_skip(
+    field: &syn::Field,
+) -> syn::Result<bool> {
+    let mut skip = false;
+
+    for attr in field.attrs.iter().filter(|v| v.path().is_ident("dd")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported direct-derive field attribute"))
+            }
+        })?;
+    }
+
+    Ok(skip)
+}
+
 /// ## Extract Field Identifiers
 ///
 /// Extract the field identifiers of a struct-type and return them as a list
 /// to the caller. For named fields the identifier of each field is returned.
 /// For tuples, the integer index of each field is returned. For unit types,
-/// an empty list is returned.
+/// an empty list is returned. Only fields that are skipped (if `skip` is
+/// set) or not skipped (otherwise) are returned.
 fn fields_idents(
     fields: &syn::Fields,
+    skip: bool,
 ) -> Vec<IdentOrLitInt> {
-    match fields {
-        syn::Fields::Named(v) => {
-            v.named
-                .iter()
-                .map(|v| IdentOrLitInt::Ident(v.ident.clone().unwrap()))
-                .collect::<Vec<IdentOrLitInt>>()
-        },
-        syn::Fields::Unnamed(v) => {
-            v.unnamed
-                .iter()
-                .enumerate()
-                .map(
-                    |(i, v)| IdentOrLitInt::LitInt(syn::LitInt::new(
-                        &i.to_string(),
-                        <syn::Type as syn::spanned::Spanned>::span(&v.ty),
-                    ))
-                )
-                .collect::<Vec<IdentOrLitInt>>()
-        },
-        syn::Fields::Unit => {
-            Vec::new()
-        }
-    }
+    fields
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| field_skip(v).unwrap_or(false) == skip)
+        .map(|(i, v)| match v.ident {
+            Some(ref ident) => IdentOrLitInt::Ident(ident.clone()),
+            None => IdentOrLitInt::LitInt(syn::LitInt::new(
+                &i.to_string(),
+                <syn::Type as syn::spanned::Spanned>::span(&v.ty),
+            )),
+        })
+        .collect::<Vec<IdentOrLitInt>>()
 }
 
 /// ## Extract Field Types
 ///
 /// Extract the types of all fields of a struct-type or tuple-type and return
 /// them to the caller. Note that this is a shallow-copy which references the
-/// types in the original.
+/// types in the original. Fields are selected like in [`fields_idents`].
 fn fields_types(
     fields: &syn::Fields,
+    skip: bool,
 ) -> Vec<&syn::Type> {
-    match fields {
-        syn::Fields::Named(v) => {
-            v.named
-                .iter()
-                .map(|v| &v.ty)
-                .collect::<Vec<&syn::Type>>()
-        },
-        syn::Fields::Unnamed(v) => {
-            v.unnamed
-                .iter()
-                .map(|v| &v.ty)
-                .collect::<Vec<&syn::Type>>()
-        },
-        syn::Fields::Unit => {
-            Vec::new()
+    fields
+        .iter()
+        .filter(|v| field_skip(v).unwrap_or(false) == skip)
+        .map(|v| &v.ty)
+        .collect::<Vec<&syn::Type>>()
+}
+
+/// ## Extract Fields Kind
+///
+/// Yield the name of the derive-kind matching the given fields. Unit types
+/// are treated as structs without fields.
+fn fields_kind(
+    fields: &syn::Fields,
+) -> syn::Ident {
+    let s_kind = match fields {
+        syn::Fields::Named(_) => "derive_struct",
+        syn::Fields::Unnamed(_) => "derive_tuple",
+        syn::Fields::Unit => "derive_struct",
+    };
+    syn::Ident::new(s_kind, proc_macro2::Span::call_site())
+}
+
+/// ## Tokenize Skipped Fields
+///
+/// Tokenize the skipped fields of a struct-type or tuple-type as trailing
+/// `skip` argument. Nothing is produced if no field is skipped, so
+/// derive-operations without support for skipped fields remain usable on
+/// other types. See [`crate::derive`] for the format.
+fn fields_skip_quote(
+    fields: &syn::Fields,
+) -> proc_macro2::TokenStream {
+    let q_fields = fields_idents(fields, true);
+    let q_types = fields_types(fields, true);
+
+    if q_fields.is_empty() {
+        proc_macro2::TokenStream::new()
+    } else {
+        quote::quote! {
+            , skip = ((#(#q_fields),*), (#(#q_types),*))
         }
     }
 }
 
+/// ## Create Field Bindings
+///
+/// Create a list of identifiers to bind each field that is not skipped to
+/// in patterns, using the given prefix and the index of the binding. The
+/// identifiers are hygienic, so they cannot collide with items of the
+/// caller.
+fn fields_bindings(
+    fields: &syn::Fields,
+    prefix: &str,
+) -> Vec<syn::Ident> {
+    (0..fields_types(fields, false).len())
+        .map(|i| syn::Ident::new(
+            &format!("{}_{}", prefix, i),
+            proc_macro2::Span::mixed_site(),
+        ))
+        .collect::<Vec<syn::Ident>>()
+}
+
+/// ## Tokenize Enum Variants
+///
+/// Tokenize the variants of an enum-type as comma-separated list of
+/// variant descriptions. See [`crate::derive`] for the format of each
+/// description.
+fn variants_quote(
+    item: &syn::ItemEnum,
+    code: &mut proc_macro2::TokenStream,
+) {
+    for (i, v) in item.variants.iter().enumerate() {
+        let q_variant = &v.ident;
+        let q_index = syn::LitInt::new(&i.to_string(), proc_macro2::Span::call_site());
+        let q_kind = fields_kind(&v.fields);
+        let q_fields = fields_idents(&v.fields, false);
+        let q_lhs = fields_bindings(&v.fields, "lhs");
+        let q_rhs = fields_bindings(&v.fields, "rhs");
+        let q_types = fields_types(&v.fields, false);
+        let q_skip = fields_skip_quote(&v.fields);
+
+        *code = quote::quote! {
+            #code
+            (
+                #q_variant,
+                #q_index,
+                #q_kind,
+                (#(#q_fields),*),
+                (#(#q_lhs),*),
+                (#(#q_rhs),*),
+                (#(#q_types),*)
+                #q_skip
+            ),
+        };
+    }
+}
+
 // Derive backend for `DeriveKind::Macro`.
 fn derive_macro(
     kind_macro: &syn::Path,
     item: &DeriveItem,
     code: &mut proc_macro2::TokenStream,
 ) {
-    let q_item_fields = fields_idents(&item.fields);
-    let q_item_types = fields_types(&item.fields);
-    let q_item_ident = &item.ident;
-    let q_generics_where = generics_wheres(&item.generics);
+    let q_item_ident = item.ident();
+    let q_generics_where = generics_wheres(item.generics());
 
     let mut q_generics_decl = proc_macro2::TokenStream::new();
-    generics_quote_decls(&item.generics, &mut q_generics_decl);
+    generics_quote_decls(item.generics(), &mut q_generics_decl);
 
     let mut q_generics_ident = proc_macro2::TokenStream::new();
-    generics_quote_idents(&item.generics, &mut q_generics_ident);
-
-    let s_derive = match &item.fields {
-        syn::Fields::Named(_) => "derive_struct",
-        syn::Fields::Unnamed(_) => "derive_tuple",
-        syn::Fields::Unit => "derive_struct",
+    generics_quote_idents(item.generics(), &mut q_generics_ident);
+
+    let item = match item {
+        DeriveItem::Struct(v) => v,
+        DeriveItem::Enum(v) => {
+            let mut q_variants = proc_macro2::TokenStream::new();
+            variants_quote(v, &mut q_variants);
+
+            *code = quote::quote! {
+                #code
+                #kind_macro !(
+                    derive_enum,
+                    #q_item_ident,
+                    #q_item_ident #q_generics_ident,
+                    (#q_generics_decl),
+                    (
+                        #(
+                            (#q_generics_where)
+                        ),*
+                    ),
+                    (
+                        #q_variants
+                    )
+                );
+            };
+            return;
+        },
     };
-    let q_derive = syn::Ident::new(s_derive, proc_macro2::Span::call_site());
+
+    let q_item_fields = fields_idents(&item.fields, false);
+    let q_item_types = fields_types(&item.fields, false);
+    let q_skip = fields_skip_quote(&item.fields);
+    let q_derive = fields_kind(&item.fields);
 
     // Rather than generating code to implement the requested trait, we instead
     // evaluate to a macro invocation and pass all information required to
@@ -330,6 +530,7 @@ fn derive_macro(
                     #q_item_types
                 ),*
             )
+            #q_skip
         );
     };
 }
@@ -346,13 +547,33 @@ pub fn derive(
     list: DeriveList,
     item: DeriveItem,
 ) -> proc_macro2::TokenStream {
+    let mut item = item;
     let mut code = proc_macro2::TokenStream::new();
 
+    // Verify all field attributes before generating any code, so invalid
+    // attributes are reported rather than ignored.
+    for field in item.fields_mut() {
+        if let Err(e) = field_skip(field) {
+            code.extend(e.to_compile_error());
+        }
+    }
+
     // Generate code for every derive-argument.
-    for arg in &list.list {
-        match arg.kind {
-            // Only external macros are currently supported as target.
-            DeriveKind::Macro(ref v) => derive_macro(v, &item, &mut code),
+    if code.is_empty() {
+        for arg in &list.list {
+            match arg.kind {
+                // Only external macros are currently supported as target.
+                DeriveKind::Macro(ref v) => derive_macro(v, &item, &mut code),
+            }
+        }
+    }
+
+    // The field attributes are not known to the compiler, so they must be
+    // stripped. If further direct derives follow, they need them as well,
+    // and the last one strips them.
+    if !item.macro_pending() {
+        for field in item.fields_mut() {
+            field.attrs.retain(|v| !v.path().is_ident("dd"));
         }
     }
 
@@ -373,7 +594,7 @@ mod tests {
     #[test]
     fn kind_macro_basic() {
         let arg = syn::parse_quote! { ::foo::bar };
-        let item: syn::ItemStruct = syn::parse_quote! { struct Foobar; };
+        let item: DeriveItem = syn::parse_quote! { struct Foobar; };
 
         let list = DeriveList::from_iter(
             [DeriveArg::new(DeriveKind::Macro(arg))],
@@ -403,7 +624,7 @@ mod tests {
     #[test]
     fn kind_macro_generics() {
         let arg = syn::parse_quote! { ::foo::bar };
-        let item: syn::ItemStruct = syn::parse_quote! {
+        let item: DeriveItem = syn::parse_quote! {
             struct Foobar<'a, 'b, A: Copy = u8, B: Clone = u8>
             where
                 A: Clone + Debug,
@@ -441,4 +662,128 @@ mod tests {
             }.to_string(),
         );
     }
+
+    // Verify macro expansion with enums
+    //
+    // Use the `DeriveKind::Macro` expansion and verify the generated code for
+    // an enum with unit, tuple, and struct variants.
+    #[test]
+    fn kind_macro_enum() {
+        let arg = syn::parse_quote! { ::foo::bar };
+        let item: DeriveItem = syn::parse_quote! {
+            enum Foobar<A> {
+                A,
+                B(A, u8),
+                C { a: A },
+            }
+        };
+
+        let list = DeriveList::from_iter(
+            [DeriveArg::new(DeriveKind::Macro(arg))],
+        );
+
+        assert_eq!(
+            derive(list, item).to_string(),
+            quote::quote! {
+                enum Foobar<A> {
+                    A,
+                    B(A, u8),
+                    C { a: A },
+                }
+                ::foo::bar!(
+                    derive_enum,
+                    Foobar,
+                    Foobar<A>,
+                    (<A>),
+                    (),
+                    (
+                        (A, 0, derive_struct, (), (), (), ()),
+                        (B, 1, derive_tuple, (0, 1), (lhs_0, lhs_1), (rhs_0, rhs_1), (A, u8)),
+                        (C, 2, derive_struct, (a), (lhs_0), (rhs_0), (A)),
+                    )
+                );
+            }.to_string(),
+        );
+    }
+
+    // Verify skipped fields
+    //
+    // Use the `DeriveKind::Macro` expansion and verify that skipped fields
+    // are passed via the trailing `skip` argument, and their helper
+    // attributes are only stripped if no further attribute macro follows.
+    #[test]
+    fn kind_macro_skip() {
+        let arg: syn::Path = syn::parse_quote! { ::foo::bar };
+        let item: DeriveItem = syn::parse_quote! {
+            #[dd::derive(::foo::baz)]
+            struct Foobar(u8, #[dd(skip)] u16, u32);
+        };
+
+        let list = DeriveList::from_iter(
+            [DeriveArg::new(DeriveKind::Macro(arg.clone()))],
+        );
+
+        assert_eq!(
+            derive(list, item).to_string(),
+            quote::quote! {
+                #[dd::derive(::foo::baz)]
+                struct Foobar(u8, #[dd(skip)] u16, u32);
+                ::foo::bar!(
+                    derive_tuple,
+                    Foobar,
+                    Foobar,
+                    (),
+                    (),
+                    (0, 2),
+                    (u8, u32),
+                    skip = ((1), (u16))
+                );
+            }.to_string(),
+        );
+
+        let item: DeriveItem = syn::parse_quote! {
+            #[repr(u8)]
+            enum Foobar {
+                A { #[dd(skip)] a: u8, b: u16 },
+                B(u8),
+            }
+        };
+
+        let list = DeriveList::from_iter(
+            [DeriveArg::new(DeriveKind::Macro(arg.clone()))],
+        );
+
+        assert_eq!(
+            derive(list, item).to_string(),
+            quote::quote! {
+                #[repr(u8)]
+                enum Foobar {
+                    A { a: u8, b: u16 },
+                    B(u8),
+                }
+                ::foo::bar!(
+                    derive_enum,
+                    Foobar,
+                    Foobar,
+                    (),
+                    (),
+                    (
+                        (A, 0, derive_struct, (b), (lhs_0), (rhs_0), (u16), skip = ((a), (u8))),
+                        (B, 1, derive_tuple, (0), (lhs_0), (rhs_0), (u8)),
+                    )
+                );
+            }.to_string(),
+        );
+
+        // Unknown arguments to the helper attribute are rejected.
+        let item: DeriveItem = syn::parse_quote! {
+            struct Foobar { #[dd(foo)] a: u8 }
+        };
+
+        let list = DeriveList::from_iter(
+            [DeriveArg::new(DeriveKind::Macro(arg))],
+        );
+
+        assert!(derive(list, item).to_string().contains("compile_error"));
+    }
 }
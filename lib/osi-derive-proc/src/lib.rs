@@ -31,8 +31,9 @@ mod derive;
 ///
 /// ### Limitations
 ///
-/// - Only `struct`-types are supported as targets. Support for `enum`-types
-///   and more might be added later.
+/// - Only `struct`-types and `enum`-types are supported as targets.
+/// - Derive-operations might not support all kinds. In particular,
+///   `Default` cannot be derived for `enum`-types.
 ///
 /// ### Custom Trait Support
 ///
@@ -50,18 +51,67 @@ mod derive;
 /// - **kind**: The kind of macro invocation. This can be one of:
 ///     - **derive_struct**: The target type is a struct with named fields.
 ///     - **derive_tuple**: The target type is a tuple with unnamed fields.
+///     - **derive_enum**: The target type is an enum. Instead of the field
+///       arguments, a list of variants is passed (see below).
 /// - **ident**: The identifier of the target type this is derived on.
 /// - **type**: The fully-qualified type of the target type.
 /// - **generics**: The generics required for the target type, enclosed in
-///     angle brackets including their trait bounds. The entire block is
-///     surrounded by parentheses, and may be empty if no generics are used.
+///   angle brackets including their trait bounds. The entire block is
+///   surrounded by parentheses, and may be empty if no generics are used.
 /// - **where**: A comma-separated list of all where-clauses required for the
-///     target type. Each where-clause is enclosed in parentheses, and the
-///     entire argument is itself enclosed in parentheses.
+///   target type. Each where-clause is enclosed in parentheses, and the
+///   entire argument is itself enclosed in parentheses.
 /// - **field-idents**: A comma-separated list of all field identifiers of the
-///     target type. The entire argument is itself enclosed in parentheses.
+///   target type. The entire argument is itself enclosed in parentheses.
 /// - **field-types**: A comma-separated list of all field types of the target
-///     type. The entire argument is itself enclosed in parentheses.
+///   type. The entire argument is itself enclosed in parentheses.
+/// - **skip**: Only passed if any field is skipped (see below), as
+///   `skip = ((skip-idents), (skip-types))`. The identifiers and types of
+///   all skipped fields are listed in the same format as **field-idents**
+///   and **field-types**. Skipped fields are not part of the other field
+///   arguments.
+///
+/// For `derive_enum`, the field arguments are replaced by a single argument
+/// listing all variants, each enclosed in parentheses and followed by a
+/// comma. The entire argument is itself enclosed in parentheses. Each
+/// variant is described by a comma-separated list of:
+///
+/// - **ident**: The identifier of the variant.
+/// - **index**: The index of the variant in declaration order, as integer
+///   literal.
+/// - **kind**: `derive_struct` for variants with named fields and unit
+///   variants, or `derive_tuple` for variants with unnamed fields.
+/// - **field-idents**: The field identifiers of the variant, as for structs.
+///   Fields of all kinds of variants can be matched and constructed via
+///   `Variant { field: value }`.
+/// - **lhs-bindings**: A unique identifier for each field, to bind the
+///   fields to in patterns. The entire argument is enclosed in parentheses.
+/// - **rhs-bindings**: A second set of identifiers like **lhs-bindings**,
+///   used to bind the fields of a second value in the same pattern.
+/// - **field-types**: The field types of the variant, as for structs.
+///   Trait bounds should be placed on the field types of all variants.
+/// - **skip**: The skipped fields of the variant, as for structs. Only
+///   passed if the variant has skipped fields. Patterns must use `..` to
+///   ignore them.
+///
+/// ### Skipping Fields
+///
+/// Fields can be annotated with `#[dd(skip)]` to exclude them from the
+/// derived traits. Derive-operations ignore skipped fields and place no
+/// bounds on their types, unless the trait needs values for them. `Clone`
+/// and `Default` produce skipped fields via `Default::default()`. `Copy`
+/// rejects skipped fields, since a copy would not match a clone.
+///
+/// Since the **skip** argument is only passed for types with skipped
+/// fields, derive-operations without support for it keep working on all
+/// other types, and fail to match otherwise.
+///
+/// The helper attribute is stripped from the target type by the direct
+/// derive attribute, unless further attribute macros follow on the target
+/// type. Paths cannot be resolved by the attribute, so any attribute but
+/// the inert builtin attributes is considered a further direct derive.
+/// Other attribute macros must thus precede the direct derive attributes,
+/// or the compiler rejects the left-over helper attributes.
 #[proc_macro_attribute]
 pub fn derive(
     attributes: proc_macro::TokenStream,
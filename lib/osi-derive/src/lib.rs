@@ -43,18 +43,70 @@ macro_rules! derive_clone_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = (($($skip_ident: tt),* $(,)?), ($($skip_type: ty),* $(,)?)))?
         $(,)?
     ) => {
         impl $($generics)* ::core::clone::Clone for $type
         where
             $($($where)*,)*
             $($field_type: ::core::clone::Clone,)*
+            $($($skip_type: ::core::default::Default,)*)?
         {
             fn clone(&self) -> Self {
                 Self {
                     $(
                         $field_ident: self.$field_ident.clone(),
                     )*
+                    $($(
+                        $skip_ident: <_ as ::core::default::Default>::default(),
+                    )*)?
+                }
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_clone_enum {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($(
+            (
+                $variant: ident,
+                $index: literal,
+                $kind: ident,
+                ($($field_ident: tt),* $(,)?),
+                ($($lhs: ident),* $(,)?),
+                ($($rhs: ident),* $(,)?),
+                ($($field_type: ty),* $(,)?)
+                $(, skip = (($($skip_ident: tt),* $(,)?), ($($skip_type: ty),* $(,)?)))?
+                $(,)?
+            )
+        ),* $(,)?)
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::clone::Clone for $type
+        where
+            $($($where)*,)*
+            $($($field_type: ::core::clone::Clone,)*)*
+            $($($($skip_type: ::core::default::Default,)*)?)*
+        {
+            fn clone(&self) -> Self {
+                match *self {
+                    $(
+                        Self::$variant { $($field_ident: ref $lhs,)* .. } => Self::$variant {
+                            $(
+                                $field_ident: ::core::clone::Clone::clone($lhs),
+                            )*
+                            $($(
+                                $skip_ident: <_ as ::core::default::Default>::default(),
+                            )*)?
+                        },
+                    )*
                 }
             }
         }
@@ -70,6 +122,9 @@ macro_rules! derive_clone {
     (derive_tuple, $($tt: tt)*) => {
         $crate::derive_clone_inner!{$($tt)*}
     };
+    (derive_enum, $($tt: tt)*) => {
+        $crate::derive_clone_enum!{$($tt)*}
+    };
 }
 
 #[doc(hidden)]
@@ -82,6 +137,7 @@ macro_rules! derive_copy_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = $skip: tt)?
         $(,)?
     ) => {
         impl $($generics)* ::core::marker::Copy for $type
@@ -90,6 +146,60 @@ macro_rules! derive_copy_inner {
             $($field_type: ::core::marker::Copy,)*
         {
         }
+
+        $(
+            $crate::derive_copy_skip!{$ident, $skip}
+        )?
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_copy_enum {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($(
+            (
+                $variant: ident,
+                $index: literal,
+                $kind: ident,
+                ($($field_ident: tt),* $(,)?),
+                ($($lhs: ident),* $(,)?),
+                ($($rhs: ident),* $(,)?),
+                ($($field_type: ty),* $(,)?)
+                $(, skip = $skip: tt)?
+                $(,)?
+            )
+        ),* $(,)?)
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::marker::Copy for $type
+        where
+            $($($where)*,)*
+            $($($field_type: ::core::marker::Copy,)*)*
+        {
+        }
+
+        $($(
+            $crate::derive_copy_skip!{$ident, $skip}
+        )?)*
+    };
+}
+
+// `Clone` produces skipped fields via `Default`, so a bitwise copy would
+// differ from a clone. Hence, skipped fields are rejected for `Copy`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_copy_skip {
+    ($ident: ident, $skip: tt) => {
+        ::core::compile_error!(::core::concat!(
+            "`Copy` cannot be derived for types with skipped fields, like `",
+            ::core::stringify!($ident),
+            "`",
+        ));
     };
 }
 
@@ -102,6 +212,9 @@ macro_rules! derive_copy {
     (derive_tuple, $($tt: tt)*) => {
         $crate::derive_copy_inner!{$($tt)*}
     };
+    (derive_enum, $($tt: tt)*) => {
+        $crate::derive_copy_enum!{$($tt)*}
+    };
 }
 
 #[doc(hidden)]
@@ -115,6 +228,7 @@ macro_rules! derive_debug_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = $skip: tt)?
         $(,)?
     ) => {
         impl $($generics)* ::core::fmt::Debug for $type
@@ -146,6 +260,7 @@ macro_rules! derive_debug_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = $skip: tt)?
         $(,)?
     ) => {
         impl $($generics)* ::core::fmt::Debug for $type
@@ -166,6 +281,88 @@ macro_rules! derive_debug_inner {
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_debug_variant {
+    (
+        derive_struct,
+        $fmt: ident,
+        $variant: ident,
+        ($($field_ident: tt),* $(,)?),
+        ($($lhs: ident),* $(,)?)
+        $(,)?
+    ) => {
+        $fmt
+            .debug_struct(::core::stringify!($variant))
+            $(.field(::core::stringify!($field_ident), $lhs))*
+            .finish()
+    };
+    (
+        derive_tuple,
+        $fmt: ident,
+        $variant: ident,
+        ($($field_ident: tt),* $(,)?),
+        ($($lhs: ident),* $(,)?)
+        $(,)?
+    ) => {
+        $fmt
+            .debug_tuple(::core::stringify!($variant))
+            $(.field($lhs))*
+            .finish()
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_debug_enum {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($(
+            (
+                $variant: ident,
+                $index: literal,
+                $kind: ident,
+                ($($field_ident: tt),* $(,)?),
+                ($($lhs: ident),* $(,)?),
+                ($($rhs: ident),* $(,)?),
+                ($($field_type: ty),* $(,)?)
+                $(, skip = $skip: tt)?
+                $(,)?
+            )
+        ),* $(,)?)
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::fmt::Debug for $type
+        where
+            $($($where)*,)*
+            $($($field_type: ::core::fmt::Debug,)*)*
+        {
+            #[allow(unused_variables)]
+            fn fmt(
+                &self,
+                fmt: &mut ::core::fmt::Formatter<'_>,
+            ) -> ::core::fmt::Result {
+                match *self {
+                    $(
+                        Self::$variant { $($field_ident: ref $lhs,)* .. } => {
+                            $crate::derive_debug_variant!{
+                                $kind,
+                                fmt,
+                                $variant,
+                                ($($field_ident),*),
+                                ($($lhs),*),
+                            }
+                        },
+                    )*
+                }
+            }
+        }
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! derive_debug {
@@ -175,6 +372,9 @@ macro_rules! derive_debug {
     (derive_tuple, $($tt: tt)*) => {
         $crate::derive_debug_inner!{derive_tuple, $($tt)*}
     };
+    (derive_enum, $($tt: tt)*) => {
+        $crate::derive_debug_enum!{$($tt)*}
+    };
 }
 
 #[doc(hidden)]
@@ -187,18 +387,23 @@ macro_rules! derive_default_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = (($($skip_ident: tt),* $(,)?), ($($skip_type: ty),* $(,)?)))?
         $(,)?
     ) => {
         impl $($generics)* ::core::default::Default for $type
         where
             $($($where)*,)*
             $($field_type: ::core::default::Default,)*
+            $($($skip_type: ::core::default::Default,)*)?
         {
             fn default() -> Self {
                 Self {
                     $(
                         $field_ident: <_ as ::core::default::Default>::default(),
                     )*
+                    $($(
+                        $skip_ident: <_ as ::core::default::Default>::default(),
+                    )*)?
                 }
             }
         }
@@ -226,6 +431,7 @@ macro_rules! derive_eq_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = $skip: tt)?
         $(,)?
     ) => {
         impl $($generics)* ::core::cmp::Eq for $type
@@ -237,6 +443,38 @@ macro_rules! derive_eq_inner {
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_eq_enum {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($(
+            (
+                $variant: ident,
+                $index: literal,
+                $kind: ident,
+                ($($field_ident: tt),* $(,)?),
+                ($($lhs: ident),* $(,)?),
+                ($($rhs: ident),* $(,)?),
+                ($($field_type: ty),* $(,)?)
+                $(, skip = $skip: tt)?
+                $(,)?
+            )
+        ),* $(,)?)
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::cmp::Eq for $type
+        where
+            $($($where)*,)*
+            $($($field_type: ::core::cmp::Eq,)*)*
+        {
+        }
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! derive_eq {
@@ -246,6 +484,76 @@ macro_rules! derive_eq {
     (derive_tuple, $($tt: tt)*) => {
         $crate::derive_eq_inner!{$($tt)*}
     };
+    (derive_enum, $($tt: tt)*) => {
+        $crate::derive_eq_enum!{$($tt)*}
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_from_inner {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($field_ident: tt $(,)?),
+        ($field_type: ty $(,)?)
+        $(, skip = (($($skip_ident: tt),* $(,)?), ($($skip_type: ty),* $(,)?)))?
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::convert::From<$field_type> for $type
+        where
+            $($($where)*,)*
+            $($($skip_type: ::core::default::Default,)*)?
+        {
+            fn from(v: $field_type) -> Self {
+                Self {
+                    $field_ident: v,
+                    $($(
+                        $skip_ident: <_ as ::core::default::Default>::default(),
+                    )*)?
+                }
+            }
+        }
+
+        impl $($generics)* ::core::convert::From<$type> for $field_type
+        where
+            $($($where)*,)*
+        {
+            fn from(v: $type) -> Self {
+                v.$field_ident
+            }
+        }
+    };
+    (
+        $ident: ident,
+        $($tt: tt)*
+    ) => {
+        ::core::compile_error!(::core::concat!(
+            "`From` can only be derived for types with exactly one field, but `",
+            ::core::stringify!($ident),
+            "` has none or several",
+        ));
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_from {
+    (derive_struct, $($tt: tt)*) => {
+        $crate::derive_from_inner!{$($tt)*}
+    };
+    (derive_tuple, $($tt: tt)*) => {
+        $crate::derive_from_inner!{$($tt)*}
+    };
+    (derive_enum, $ident: ident, $($tt: tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "`From` cannot be derived for enums, like `",
+            ::core::stringify!($ident),
+            "`",
+        ));
+    };
 }
 
 #[doc(hidden)]
@@ -258,6 +566,7 @@ macro_rules! derive_hash_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = $skip: tt)?
         $(,)?
     ) => {
         impl $($generics)* ::core::hash::Hash for $type
@@ -278,6 +587,53 @@ macro_rules! derive_hash_inner {
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_hash_enum {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($(
+            (
+                $variant: ident,
+                $index: literal,
+                $kind: ident,
+                ($($field_ident: tt),* $(,)?),
+                ($($lhs: ident),* $(,)?),
+                ($($rhs: ident),* $(,)?),
+                ($($field_type: ty),* $(,)?)
+                $(, skip = $skip: tt)?
+                $(,)?
+            )
+        ),* $(,)?)
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::hash::Hash for $type
+        where
+            $($($where)*,)*
+            $($($field_type: ::core::hash::Hash,)*)*
+        {
+            fn hash<Op>(&self, state: &mut Op)
+            where
+                Op: ::core::hash::Hasher,
+            {
+                ::core::hash::Hash::hash(&::core::mem::discriminant(self), state);
+                match *self {
+                    $(
+                        Self::$variant { $($field_ident: ref $lhs,)* .. } => {
+                            $(
+                                ::core::hash::Hash::hash($lhs, state);
+                            )*
+                        },
+                    )*
+                }
+            }
+        }
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! derive_hash {
@@ -287,6 +643,9 @@ macro_rules! derive_hash {
     (derive_tuple, $($tt: tt)*) => {
         $crate::derive_hash_inner!{$($tt)*}
     };
+    (derive_enum, $($tt: tt)*) => {
+        $crate::derive_hash_enum!{$($tt)*}
+    };
 }
 
 #[doc(hidden)]
@@ -299,6 +658,7 @@ macro_rules! derive_ord_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = $skip: tt)?
         $(,)?
     ) => {
         impl $($generics)* ::core::cmp::Ord for $type
@@ -325,6 +685,66 @@ macro_rules! derive_ord_inner {
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_ord_enum {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($(
+            (
+                $variant: ident,
+                $index: literal,
+                $kind: ident,
+                ($($field_ident: tt),* $(,)?),
+                ($($lhs: ident),* $(,)?),
+                ($($rhs: ident),* $(,)?),
+                ($($field_type: ty),* $(,)?)
+                $(, skip = $skip: tt)?
+                $(,)?
+            )
+        ),* $(,)?)
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::cmp::Ord for $type
+        where
+            $($($where)*,)*
+            $($($field_type: ::core::cmp::Ord,)*)*
+        {
+            #[allow(unreachable_patterns)]
+            fn cmp(
+                &self,
+                other: &Self,
+            ) -> ::core::cmp::Ordering {
+                let index = |v: &Self| -> usize {
+                    match *v {
+                        $(Self::$variant { .. } => $index,)*
+                    }
+                };
+
+                match (self, other) {
+                    $(
+                        (
+                            &Self::$variant { $($field_ident: ref $lhs,)* .. },
+                            &Self::$variant { $($field_ident: ref $rhs,)* .. },
+                        ) => {
+                            ::core::cmp::Ordering::Equal
+                                $(
+                                    .then(
+                                        <_ as ::core::cmp::Ord>::cmp($lhs, $rhs)
+                                    )
+                                )*
+                        },
+                    )*
+                    _ => <_ as ::core::cmp::Ord>::cmp(&index(self), &index(other)),
+                }
+            }
+        }
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! derive_ord {
@@ -334,6 +754,9 @@ macro_rules! derive_ord {
     (derive_tuple, $($tt: tt)*) => {
         $crate::derive_ord_inner!{$($tt)*}
     };
+    (derive_enum, $($tt: tt)*) => {
+        $crate::derive_ord_enum!{$($tt)*}
+    };
 }
 
 #[doc(hidden)]
@@ -346,6 +769,7 @@ macro_rules! derive_partialeq_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = $skip: tt)?
         $(,)?
     ) => {
         impl $($generics)* ::core::cmp::PartialEq for $type
@@ -367,6 +791,55 @@ macro_rules! derive_partialeq_inner {
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_partialeq_enum {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($(
+            (
+                $variant: ident,
+                $index: literal,
+                $kind: ident,
+                ($($field_ident: tt),* $(,)?),
+                ($($lhs: ident),* $(,)?),
+                ($($rhs: ident),* $(,)?),
+                ($($field_type: ty),* $(,)?)
+                $(, skip = $skip: tt)?
+                $(,)?
+            )
+        ),* $(,)?)
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::cmp::PartialEq for $type
+        where
+            $($($where)*,)*
+            $($($field_type: ::core::cmp::PartialEq,)*)*
+        {
+            #[allow(unreachable_patterns)]
+            fn eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    $(
+                        (
+                            &Self::$variant { $($field_ident: ref $lhs,)* .. },
+                            &Self::$variant { $($field_ident: ref $rhs,)* .. },
+                        ) => {
+                            true
+                            $(
+                                && <_ as ::core::cmp::PartialEq>::eq($lhs, $rhs)
+                            )*
+                        },
+                    )*
+                    _ => false,
+                }
+            }
+        }
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! derive_partialeq {
@@ -376,6 +849,9 @@ macro_rules! derive_partialeq {
     (derive_tuple, $($tt: tt)*) => {
         $crate::derive_partialeq_inner!{$($tt)*}
     };
+    (derive_enum, $($tt: tt)*) => {
+        $crate::derive_partialeq_enum!{$($tt)*}
+    };
 }
 
 #[doc(hidden)]
@@ -388,6 +864,7 @@ macro_rules! derive_partialord_inner {
         ($( ($($where: tt)*) ),* $(,)?),
         ($($field_ident: tt),* $(,)?),
         ($($field_type: ty),* $(,)?)
+        $(, skip = $skip: tt)?
         $(,)?
     ) => {
         impl $($generics)* ::core::cmp::PartialOrd for $type
@@ -419,6 +896,74 @@ macro_rules! derive_partialord_inner {
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! derive_partialord_enum {
+    (
+        $ident: ident,
+        $type: ty,
+        ($($generics: tt)*),
+        ($( ($($where: tt)*) ),* $(,)?),
+        ($(
+            (
+                $variant: ident,
+                $index: literal,
+                $kind: ident,
+                ($($field_ident: tt),* $(,)?),
+                ($($lhs: ident),* $(,)?),
+                ($($rhs: ident),* $(,)?),
+                ($($field_type: ty),* $(,)?)
+                $(, skip = $skip: tt)?
+                $(,)?
+            )
+        ),* $(,)?)
+        $(,)?
+    ) => {
+        impl $($generics)* ::core::cmp::PartialOrd for $type
+        where
+            $($($where)*,)*
+            $($($field_type: ::core::cmp::PartialOrd,)*)*
+        {
+            #[allow(unreachable_patterns)]
+            fn partial_cmp(
+                &self,
+                other: &Self,
+            ) -> Option<::core::cmp::Ordering> {
+                let index = |v: &Self| -> usize {
+                    match *v {
+                        $(Self::$variant { .. } => $index,)*
+                    }
+                };
+
+                match (self, other) {
+                    $(
+                        (
+                            &Self::$variant { $($field_ident: ref $lhs,)* .. },
+                            &Self::$variant { $($field_ident: ref $rhs,)* .. },
+                        ) => {
+                            Some(::core::cmp::Ordering::Equal)
+                                $(
+                                    .and_then(
+                                        |v| match v {
+                                            ::core::cmp::Ordering::Equal => {
+                                                <_ as ::core::cmp::PartialOrd>::partial_cmp(
+                                                    $lhs,
+                                                    $rhs,
+                                                )
+                                            },
+                                            _ => Some(v),
+                                        },
+                                    )
+                                )*
+                        },
+                    )*
+                    _ => <_ as ::core::cmp::PartialOrd>::partial_cmp(&index(self), &index(other)),
+                }
+            }
+        }
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! derive_partialord {
@@ -428,6 +973,9 @@ macro_rules! derive_partialord {
     (derive_tuple, $($tt: tt)*) => {
         $crate::derive_partialord_inner!{$($tt)*}
     };
+    (derive_enum, $($tt: tt)*) => {
+        $crate::derive_partialord_enum!{$($tt)*}
+    };
 }
 
 // Expose the attribute from the underlying proc-crate.
@@ -444,6 +992,16 @@ pub use derive_clone as Clone;
 ///
 /// This derives [`core::marker::Copy`] for the target type,
 /// if used via [`derive`].
+///
+/// Types with skipped fields are rejected, since [`Clone`] produces them
+/// via [`core::default::Default`] rather than copying them:
+///
+/// ```rust,compile_fail
+/// use osi_derive as dd;
+///
+/// #[dd::derive(dd::Clone, dd::Copy)]
+/// struct Cached(u8, #[dd(skip)] u8);
+/// ```
 pub use derive_copy as Copy;
 
 /// ## Direct Derive of [`core::default::Default`]
@@ -458,6 +1016,25 @@ pub use derive_default as Default;
 /// if used via [`derive`].
 pub use derive_eq as Eq;
 
+/// ## Direct Derive of [`core::convert::From`]
+///
+/// This derives [`core::convert::From`] for newtypes with exactly one field,
+/// if used via [`derive`]. Both conversions from the field type into the
+/// target type and back are derived. Skipped fields are produced via
+/// [`core::default::Default`].
+///
+/// Due to the orphan rules, the conversion back into the field type cannot
+/// be derived if the field type is a bare generic parameter. Types with no
+/// or several fields are rejected:
+///
+/// ```rust,compile_fail
+/// use osi_derive as dd;
+///
+/// #[dd::derive(dd::From)]
+/// struct Pair(u8, u8);
+/// ```
+pub use derive_from as From;
+
 /// ## Direct Derive of [`core::hash::Hash`]
 ///
 /// This derives [`core::hash::Hash`] for the target type,
@@ -494,7 +1071,12 @@ pub use derive_partialeq as PartialEq;
 #[doc(inline)]
 pub use derive_partialord as PartialOrd;
 
+// Test types derive all traits via the direct derives, including `Copy`
+// alongside `Clone` and `Ord` alongside `PartialOrd`. The derives cannot
+// know about each other, so they cannot produce the canonical
+// implementations clippy asks for in this case.
 #[cfg(test)]
+#[allow(clippy::non_canonical_clone_impl, clippy::non_canonical_partial_ord_impl)]
 mod tests {
     use crate as dd;
 
@@ -534,6 +1116,58 @@ mod tests {
     #[dd::derive(dd::PartialEq, dd::PartialOrd)]
     struct TestTuple2(u8, u16);
 
+    #[dd::derive(dd::Clone, dd::Copy, dd::Debug)]
+    #[dd::derive(dd::Eq, dd::Hash, dd::Ord)]
+    #[dd::derive(dd::PartialEq, dd::PartialOrd)]
+    #[allow(dead_code)]
+    enum TestEnum0 {
+    }
+
+    #[dd::derive(dd::Clone, dd::Copy, dd::Debug)]
+    #[dd::derive(dd::Eq, dd::Hash, dd::Ord)]
+    #[dd::derive(dd::PartialEq, dd::PartialOrd)]
+    enum TestEnum3 {
+        A,
+        B(u8, u16),
+        C { a: u8, b: u16 },
+    }
+
+    // Iterator without any trait implementations, to verify that no bounds
+    // are placed on generic parameters.
+    struct TestIter;
+
+    impl Iterator for TestIter {
+        type Item = u8;
+
+        fn next(&mut self) -> Option<u8> {
+            None
+        }
+    }
+
+    #[dd::derive(dd::Clone, dd::Debug, dd::PartialEq)]
+    enum TestAssoc<I: Iterator> {
+        Item(I::Item),
+        Empty,
+    }
+
+    // Field type without any trait implementations but `Default`, to verify
+    // that skipped fields are not bound by the derived traits.
+    #[derive(Default)]
+    struct TestCache(u8);
+
+    #[dd::derive(dd::Clone, dd::Debug, dd::Default)]
+    #[dd::derive(dd::Eq, dd::Hash, dd::PartialEq)]
+    struct TestSkip {
+        a: u8,
+        #[dd(skip)]
+        cache: TestCache,
+    }
+
+    #[dd::derive(dd::Clone, dd::Debug, dd::PartialEq)]
+    enum TestSkipEnum {
+        A(#[dd(skip)] TestCache, u8),
+    }
+
     #[test]
     fn instantiation() {
         let _: TestStruct0 = Default::default();
@@ -543,4 +1177,76 @@ mod tests {
         let _: TestTuple1 = Default::default();
         let _: TestTuple2 = Default::default();
     }
+
+    // Verify the behavior of derives on enums, which operate on the fields
+    // of each variant, and order variants by their declaration.
+    #[test]
+    fn enums() {
+        let a = TestEnum3::A;
+        let b = TestEnum3::B(1, 2);
+        let c = TestEnum3::C { a: 1, b: 2 };
+
+        assert_eq!(format!("{:?}", a), "A");
+        assert_eq!(format!("{:?}", b), "B(1, 2)");
+        assert_eq!(format!("{:?}", c), "C { a: 1, b: 2 }");
+
+        assert_eq!(b.clone(), b);
+        assert_ne!(b, TestEnum3::B(1, 3));
+        assert_ne!(a, c);
+        assert!(a < b && b < c);
+        assert!(TestEnum3::B(1, 2) < TestEnum3::B(2, 0));
+        assert_eq!(c.partial_cmp(&TestEnum3::C { a: 1, b: 1 }), Some(core::cmp::Ordering::Greater));
+
+        let hash = |v: &TestEnum3| {
+            use std::hash::{Hash, Hasher};
+            let mut state = std::collections::hash_map::DefaultHasher::new();
+            v.hash(&mut state);
+            state.finish()
+        };
+        assert_eq!(hash(&b), hash(&TestEnum3::B(1, 2)));
+        assert_ne!(hash(&b), hash(&c));
+
+        // Only field types are bound, so `TestIter` needs no traits.
+        let v: TestAssoc<TestIter> = TestAssoc::Item(7);
+        assert_eq!(format!("{:?}", v.clone()), "Item(7)");
+        assert_ne!(v, TestAssoc::Empty);
+    }
+
+    #[dd::derive(dd::Debug, dd::From, dd::PartialEq)]
+    struct TestFromTuple(u32);
+
+    #[dd::derive(dd::From)]
+    struct TestFromStruct<T> {
+        inner: Vec<T>,
+        #[dd(skip)]
+        cache: Option<usize>,
+    }
+
+    // Verify conversions of newtypes in both directions.
+    #[test]
+    fn from() {
+        let v: TestFromTuple = 7.into();
+        assert_eq!(v, TestFromTuple(7));
+        assert_eq!(u32::from(v), 7);
+
+        let v = TestFromStruct::from(vec!["a"]);
+        assert_eq!(v.cache, None);
+        assert_eq!(Vec::from(v), ["a"]);
+    }
+
+    // Verify skipped fields are ignored by all derives, and produced via
+    // `Default` by `Clone`.
+    #[test]
+    fn skip() {
+        let v = TestSkip { a: 1, cache: TestCache(7) };
+        assert_eq!(format!("{:?}", v), "TestSkip { a: 1 }");
+        assert_eq!(v.clone().cache.0, 0);
+        assert!(v == TestSkip { a: 1, cache: TestCache(3) });
+        assert_eq!(TestSkip::default().a, 0);
+
+        let v = TestSkipEnum::A(TestCache(7), 2);
+        assert_eq!(format!("{:?}", v), "A(2)");
+        assert!(matches!(v.clone(), TestSkipEnum::A(TestCache(0), 2)));
+        assert!(v == TestSkipEnum::A(TestCache(3), 2));
+    }
 }
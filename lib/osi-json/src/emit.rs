@@ -0,0 +1,386 @@
+//! # JSON Serializer
+//!
+//! This module provides a streaming JSON serializer, mirroring the streaming
+//! tokenizer. Instead of building a value tree, callers report a sequence of
+//! events (e.g., opening an object, writing a key, writing a value), which
+//! are written as compact JSON into a `core::fmt::Write` destination.
+//!
+//! The serializer checks the order of events against the JSON grammar, and
+//! rejects any misuse without writing anything. Strings are escaped such that
+//! the tokenizer reads them back unchanged.
+
+/// ## Serializer Errors
+///
+/// This is the error-enum of all possible errors raised by the serializer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Error {
+    /// Writing to the destination failed.
+    Write(core::fmt::Error),
+    /// Key was given outside of a JSON Object, or twice in a row.
+    KeyUnexpected,
+    /// Value was given where a key is required, or after the top-level
+    /// value was completed.
+    ValueUnexpected,
+    /// Closing event does not match the innermost open container, or a
+    /// key is still pending.
+    CloseUnexpected,
+    /// Given lexical form is not a valid JSON Number.
+    NumberInvalid,
+    /// Serialization was finished before the top-level value was complete.
+    Incomplete,
+}
+
+/// ## Streaming Serializer
+///
+/// The serializer writes JSON events into a destination as compact JSON
+/// without any insignificant whitespace. A single serializer writes a
+/// single top-level value. Once it is complete, `Self::finish()` verifies
+/// that no container was left open.
+///
+/// If writing to the destination fails, the serializer state is undefined
+/// and it must not be used any further.
+pub struct Serializer<'dst> {
+    dst: &'dst mut dyn core::fmt::Write,
+    nesting: alloc::vec::Vec<bool>,
+    first: bool,
+    key: bool,
+    done: bool,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::Write(v) => fmt.write_fmt(core::format_args!("Cannot write JSON: {}", v)),
+            Self::KeyUnexpected => fmt.write_str("JSON key is only valid in place of a JSON Object key"),
+            Self::ValueUnexpected => fmt.write_str("JSON value is not valid at this position"),
+            Self::CloseUnexpected => fmt.write_str("Closing bracket does not match the open JSON container"),
+            Self::NumberInvalid => fmt.write_str("Invalid lexical form of a JSON Number"),
+            Self::Incomplete => fmt.write_str("JSON value is incomplete"),
+        }
+    }
+}
+
+// Write a JSON String with all quotes, backslashes and control characters
+// escaped. All other characters are written verbatim.
+pub(crate) fn write_string(
+    dst: &mut dyn core::fmt::Write,
+    v: &str,
+) -> Result<(), core::fmt::Error> {
+    dst.write_char('"')?;
+
+    for c in v.chars() {
+        match c {
+            '"' => dst.write_str("\\\"")?,
+            '\\' => dst.write_str("\\\\")?,
+            '\u{0008}' => dst.write_str("\\b")?,
+            '\u{000c}' => dst.write_str("\\f")?,
+            '\n' => dst.write_str("\\n")?,
+            '\r' => dst.write_str("\\r")?,
+            '\t' => dst.write_str("\\t")?,
+            '\u{0000}'..='\u{001f}' => {
+                dst.write_fmt(core::format_args!("\\u{:04x}", c as u32))?
+            },
+            _ => dst.write_char(c)?,
+        }
+    }
+
+    dst.write_char('"')
+}
+
+// Check whether the given string is exactly a single, valid JSON Number, as
+// read by the tokenizer.
+fn number_valid(v: &str) -> bool {
+    let mut n = 0;
+    let r = crate::token::Tokenizer::new().parse_str(
+        v,
+        &mut |token| {
+            n += 1;
+            match token {
+                crate::token::Token::Number(..) => core::ops::ControlFlow::Continue(()),
+                _ => core::ops::ControlFlow::Break(()),
+            }
+        },
+    );
+    r.is_continue() && n == 1
+}
+
+impl<'dst> Serializer<'dst> {
+    /// ## Create New Serializer
+    ///
+    /// Create a new serializer writing into the given destination.
+    pub fn new(dst: &'dst mut dyn core::fmt::Write) -> Self {
+        Self {
+            dst: dst,
+            nesting: alloc::vec::Vec::new(),
+            first: true,
+            key: false,
+            done: false,
+        }
+    }
+
+    // Verify a value can be written at the current position and write its
+    // leading separator, if any.
+    fn begin_value(&mut self) -> Result<(), Error> {
+        match self.nesting.last() {
+            None if self.done => Err(Error::ValueUnexpected),
+            None => Ok(()),
+            Some(true) if !self.key => Err(Error::ValueUnexpected),
+            Some(true) => {
+                self.key = false;
+                Ok(())
+            },
+            Some(false) => {
+                if !core::mem::replace(&mut self.first, false) {
+                    self.dst.write_char(',').map_err(|v| Error::Write(v))?;
+                }
+                Ok(())
+            },
+        }
+    }
+
+    // Record that a value was completed.
+    fn end_value(&mut self) {
+        if self.nesting.is_empty() {
+            self.done = true;
+        }
+    }
+
+    // Write a scalar value in its final JSON form.
+    fn scalar(&mut self, v: &str) -> Result<(), Error> {
+        self.begin_value()?;
+        self.dst.write_str(v).map_err(|v| Error::Write(v))?;
+        self.end_value();
+        Ok(())
+    }
+
+    // Open a new container.
+    fn begin(&mut self, object: bool) -> Result<(), Error> {
+        self.begin_value()?;
+        self.dst.write_char(if object { '{' } else { '[' }).map_err(|v| Error::Write(v))?;
+        self.nesting.push(object);
+        self.first = true;
+        Ok(())
+    }
+
+    // Close the innermost container, which must match `object`.
+    fn end(&mut self, object: bool) -> Result<(), Error> {
+        if self.nesting.last() != Some(&object) || self.key {
+            return Err(Error::CloseUnexpected);
+        }
+
+        self.dst.write_char(if object { '}' } else { ']' }).map_err(|v| Error::Write(v))?;
+        self.nesting.pop();
+        self.first = false;
+        self.end_value();
+        Ok(())
+    }
+
+    /// ## Open JSON Object
+    ///
+    /// Start a new JSON Object. Its members are written as alternating keys
+    /// and values, until it is closed via `Self::end_object()`.
+    pub fn begin_object(&mut self) -> Result<(), Error> {
+        self.begin(true)
+    }
+
+    /// ## Close JSON Object
+    ///
+    /// Close the innermost JSON Object. This fails if the innermost
+    /// container is not an object, or if a key is waiting for its value.
+    pub fn end_object(&mut self) -> Result<(), Error> {
+        self.end(true)
+    }
+
+    /// ## Open JSON Array
+    ///
+    /// Start a new JSON Array. Its elements are written as values, until
+    /// it is closed via `Self::end_array()`.
+    pub fn begin_array(&mut self) -> Result<(), Error> {
+        self.begin(false)
+    }
+
+    /// ## Close JSON Array
+    ///
+    /// Close the innermost JSON Array. This fails if the innermost
+    /// container is not an array.
+    pub fn end_array(&mut self) -> Result<(), Error> {
+        self.end(false)
+    }
+
+    /// ## Write Key
+    ///
+    /// Write the key of the next member of the innermost JSON Object. The
+    /// key must be followed by its value.
+    pub fn key(&mut self, v: &str) -> Result<(), Error> {
+        if self.nesting.last() != Some(&true) || self.key {
+            return Err(Error::KeyUnexpected);
+        }
+
+        if !core::mem::replace(&mut self.first, false) {
+            self.dst.write_char(',').map_err(|v| Error::Write(v))?;
+        }
+        write_string(self.dst, v).map_err(|v| Error::Write(v))?;
+        self.dst.write_char(':').map_err(|v| Error::Write(v))?;
+        self.key = true;
+        Ok(())
+    }
+
+    /// ## Write JSON Null
+    pub fn value_null(&mut self) -> Result<(), Error> {
+        self.scalar("null")
+    }
+
+    /// ## Write JSON Boolean
+    pub fn value_bool(&mut self, v: bool) -> Result<(), Error> {
+        self.scalar(if v { "true" } else { "false" })
+    }
+
+    /// ## Write JSON Number
+    ///
+    /// Write a JSON Number given in its lexical form. The lexical form is
+    /// written verbatim, but must be a valid JSON Number as accepted by the
+    /// tokenizer with default flags.
+    pub fn value_number(&mut self, v: &str) -> Result<(), Error> {
+        if !number_valid(v) {
+            return Err(Error::NumberInvalid);
+        }
+        self.scalar(v)
+    }
+
+    /// ## Write JSON String
+    ///
+    /// Write a JSON String with all quotes, backslashes, and control
+    /// characters escaped.
+    pub fn value_string(&mut self, v: &str) -> Result<(), Error> {
+        self.begin_value()?;
+        write_string(self.dst, v).map_err(|v| Error::Write(v))?;
+        self.end_value();
+        Ok(())
+    }
+
+    /// ## Finish Serialization
+    ///
+    /// Verify that the top-level value was completely written.
+    pub fn finish(self) -> Result<(), Error> {
+        match self.done {
+            true => Ok(()),
+            false => Err(Error::Incomplete),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify the serializer rejects events that violate the JSON grammar,
+    // without writing anything for them.
+    #[test]
+    fn serializer_misuse() {
+        let mut out = alloc::string::String::new();
+        let mut s = Serializer::new(&mut out);
+
+        assert_eq!(s.key("a"), Err(Error::KeyUnexpected));
+        assert_eq!(s.end_array(), Err(Error::CloseUnexpected));
+        s.begin_object().unwrap();
+        assert_eq!(s.value_null(), Err(Error::ValueUnexpected));
+        assert_eq!(s.end_array(), Err(Error::CloseUnexpected));
+        s.key("a").unwrap();
+        assert_eq!(s.key("b"), Err(Error::KeyUnexpected));
+        assert_eq!(s.end_object(), Err(Error::CloseUnexpected));
+        s.begin_array().unwrap();
+        assert_eq!(s.key("b"), Err(Error::KeyUnexpected));
+        assert_eq!(s.value_number("01"), Err(Error::NumberInvalid));
+        assert_eq!(s.value_number("1 2"), Err(Error::NumberInvalid));
+        s.value_number("-1.5e3").unwrap();
+        s.value_bool(false).unwrap();
+        s.end_array().unwrap();
+        s.key("b").unwrap();
+        s.value_string("\"\\\n\u{1}").unwrap();
+        s.end_object().unwrap();
+        assert_eq!(s.value_null(), Err(Error::ValueUnexpected));
+        s.finish().unwrap();
+
+        assert_eq!(out, r#"{"a":[-1.5e3,false],"b":"\"\\\n\u0001"}"#);
+
+        let mut out = alloc::string::String::new();
+        let mut s = Serializer::new(&mut out);
+        s.begin_array().unwrap();
+        assert_eq!(s.finish(), Err(Error::Incomplete));
+    }
+
+    // Verify that re-serializing a token stream yields the same tokens when
+    // tokenized again.
+    #[test]
+    fn serializer_roundtrip() {
+        use crate::token::{Token, Tokenizer};
+
+        // Collect all tokens but whitespace. Strings are compared by their
+        // decoded value, since escapes are normalized.
+        fn tokenize(data: &str) -> alloc::vec::Vec<alloc::string::String> {
+            let mut tokens = alloc::vec::Vec::new();
+            let _ = Tokenizer::new().parse_str(
+                data,
+                &mut |token| {
+                    match token {
+                        Token::Whitespace(_) => {},
+                        Token::String(_, v) => tokens.push(alloc::format!("String({:?})", v)),
+                        v => tokens.push(alloc::format!("{:?}", v)),
+                    }
+                    core::ops::ControlFlow::<()>::Continue(())
+                },
+            );
+            tokens
+        }
+
+        let input = r#" {"a": [1, -2.5E+3, true, null, {}], "bA\n": "\t\"\\\u001f😀",
+            "c": {"d": [[], false]}} "#;
+
+        let mut out = alloc::string::String::new();
+        let mut s = Serializer::new(&mut out);
+        let mut nesting = alloc::vec::Vec::new();
+        let mut key = false;
+        let _ = Tokenizer::new().parse_str(
+            input,
+            &mut |token| {
+                match token {
+                    Token::ObjectOpen => {
+                        nesting.push(true);
+                        key = true;
+                        s.begin_object().unwrap();
+                    },
+                    Token::ObjectClose => {
+                        nesting.pop();
+                        s.end_object().unwrap();
+                    },
+                    Token::ArrayOpen => {
+                        nesting.push(false);
+                        key = false;
+                        s.begin_array().unwrap();
+                    },
+                    Token::ArrayClose => {
+                        nesting.pop();
+                        s.end_array().unwrap();
+                    },
+                    Token::Comma => key = nesting.last() == Some(&true),
+                    Token::String(_, v) if key => {
+                        key = false;
+                        s.key(v).unwrap();
+                    },
+                    Token::String(_, v) => s.value_string(v).unwrap(),
+                    Token::Number(v, ..) => s.value_number(v).unwrap(),
+                    Token::Null => s.value_null().unwrap(),
+                    Token::True => s.value_bool(true).unwrap(),
+                    Token::False => s.value_bool(false).unwrap(),
+                    Token::Colon | Token::Whitespace(_) => {},
+                    Token::Error(v) => panic!("unexpected error: {:?}", v),
+                }
+                core::ops::ControlFlow::<()>::Continue(())
+            },
+        );
+        s.finish().unwrap();
+
+        assert_eq!(tokenize(&out), tokenize(input));
+        assert_eq!(crate::value::parse(&out).unwrap(), crate::value::parse(input).unwrap());
+    }
+}
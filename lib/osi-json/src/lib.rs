@@ -1,7 +1,8 @@
 //! # Standalone JSON Streaming Implementation
 //!
 //! This crate implements a standalone JSON streaming engine as well as a
-//! parser into an in-memory representation.
+//! parser into an in-memory representation. A streaming serializer allows
+//! producing JSON without building an in-memory representation first.
 //!
 //! ## Compatibility
 //!
@@ -24,4 +25,6 @@ extern crate std;
 extern crate alloc;
 extern crate core;
 
+pub mod emit;
 pub mod token;
+pub mod value;
@@ -21,6 +21,38 @@ pub const FLAG_ALLOW_LEADING_ZERO: Flag =       0x00000001;
 /// Values. These have no effect and are ignored.
 pub const FLAG_ALLOW_PLUS_SIGN: Flag =          0x00000002;
 
+/// ## Allow Unquoted Keys
+///
+/// When set, the JSON tokenizer accepts identifiers as keys of JSON Object
+/// members (e.g., `{foo: 1}`) and reports them as JSON String tokens. The
+/// tokenizer tracks the nesting of JSON Arrays and Objects to detect key
+/// positions. Identifiers anywhere else are still rejected.
+pub const FLAG_ALLOW_UNQUOTED_KEYS: Flag =      0x00000004;
+
+/// ## Allow Single Quotes
+///
+/// When set, the JSON tokenizer accepts JSON Strings enclosed in single
+/// quotes (e.g., `'foo'`) and reports them as normal JSON String tokens.
+/// Double quotes need no escaping in such strings, but single quotes must
+/// be escaped as `\'`. Double-quoted strings remain available.
+pub const FLAG_ALLOW_SINGLE_QUOTES: Flag =      0x00000008;
+
+/// ## Allow Trailing Commas
+///
+/// When set, structural validation accepts a comma right before the
+/// closing bracket of a JSON Array or Object (e.g., `[1, 2,]`). The
+/// tokenizer itself reports commas regardless of their position, so this
+/// only affects grammar-checking layers like `value::parse_with()`.
+pub const FLAG_ALLOW_TRAILING_COMMA: Flag =     0x00000010;
+
+/// ## Reject Duplicate Keys
+///
+/// When set, structural validation rejects JSON Objects that contain the
+/// same key more than once. Keys are compared by their decoded value, so
+/// differently escaped forms of the same key collide. Like
+/// `FLAG_ALLOW_TRAILING_COMMA`, this only affects grammar-checking layers.
+pub const FLAG_REJECT_DUPLICATE_KEYS: Flag =    0x00000020;
+
 /// ## Tokenizer Status
 ///
 /// After every operation that advances the tokenizer, the latter will report
@@ -49,6 +81,51 @@ pub enum Status {
 /// is otherwise propagated verbatim to the caller.
 pub type Report<T> = core::ops::ControlFlow<T, Status>;
 
+/// ## Progress Reporting
+///
+/// This trait allows observing the progress of long-running tokenizer
+/// operations, like `Tokenizer::feed()` with `Feed::progress`. It is
+/// implemented for all closures taking the byte offset reached.
+pub trait Progress {
+    /// Notify the observer that all input up to the given byte offset was
+    /// processed.
+    fn progress(&mut self, offset: usize);
+}
+
+/// ## Feed Input
+///
+/// Input for `Tokenizer::feed()`. Strings are pushed character by
+/// character. Bytes are decoded as UTF-8 and can be split at arbitrary
+/// offsets. Bytes of a character that is split across chunks are retained
+/// until the character is complete. Bytes that do not form valid UTF-8 are
+/// reported as `Error::Utf8Invalid`, and otherwise skipped. Retained bytes
+/// that are not completed before the input is finished are reported the
+/// same way.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Input<'data> {
+    /// String input, pushed character by character.
+    Str(&'data str),
+    /// UTF-8 encoded byte input.
+    Bytes(&'data [u8]),
+}
+
+/// ## Feed Options
+///
+/// Options for `Tokenizer::feed()`. By default, input is pushed into the
+/// tokenizer without finishing it, like `Tokenizer::push_str()` does.
+#[derive(Default)]
+pub struct Feed<'ctx> {
+    /// Push the End-Of-Input marker after the input and reset the engine,
+    /// like `Tokenizer::parse_str()` does.
+    pub finish: bool,
+    /// Notify the observer after every given number of input elements
+    /// (characters for strings, bytes otherwise) with the byte offset
+    /// reached so far. Offsets are relative to the start of the input. No
+    /// final notification is sent for input that does not fill an entire
+    /// interval.
+    pub progress: Option<(&'ctx mut dyn Progress, core::num::NonZeroUsize)>,
+}
+
 /// ## Number Signs
 ///
 /// This enum is used to represent the sign a JSON Number Value carries.
@@ -76,7 +153,8 @@ pub enum Error<'ctx> {
     WhitespaceInvalid(char),
     /// Given keyword is not valid in JSON.
     KeywordUnknown(&'ctx str),
-    /// Data ended with an unfinished number.
+    /// Number ended before all its required digits were given (e.g., after
+    /// a sign, a decimal point, or an exponent marker).
     NumberIncomplete,
     /// Data ended with an unclosed string.
     StringIncomplete,
@@ -92,6 +170,16 @@ pub enum Error<'ctx> {
     StringEscapeUnicode,
     /// Comments are not supported by JSON.
     Comment(&'ctx str),
+    /// Closing bracket does not match the innermost open bracket.
+    BracketMismatch(char),
+    /// Closing bracket without any open bracket.
+    BracketStray(char),
+    /// Data ended with the given bracket still open.
+    BracketUnclosed(char),
+    /// Brackets are nested deeper than supported.
+    BracketDepth,
+    /// Input bytes are not valid UTF-8.
+    Utf8Invalid,
 }
 
 /// ## JSON Token
@@ -124,11 +212,289 @@ pub enum Token<'ctx> {
     /// Block of continuous whitespace
     Whitespace(&'ctx str),
     /// JSON number value
+    ///
+    /// Carries the number in its lexical form exactly as in the input,
+    /// followed by the decimal values (0-9) of all its digits, the sign of
+    /// the integer part, the digit counts of the integer and fraction
+    /// parts, and the sign and digit count of the exponent. The digit
+    /// counts always sum up to the number of digit values, which hold the
+    /// integer, fraction, and exponent digits in this order. The digit
+    /// values are the source of truth for numeric conversions, and no
+    /// leading zeros are skipped.
     Number(&'ctx str, &'ctx [u8], Sign, usize, usize, Sign, usize),
     /// JSON string value
+    ///
+    /// Carries the raw data between the quotes exactly as in the input,
+    /// including all escape sequences, followed by the decoded string.
     String(&'ctx str, &'ctx str),
 }
 
+/// ## Number Conversion Errors
+///
+/// This is the error-enum of all possible errors when converting a JSON
+/// Number Value into a native Rust type.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum NumberError {
+    /// Value is out of range of the target type.
+    Overflow,
+    /// Value has a fractional part, which the target type cannot represent.
+    Inexact,
+}
+
+/// ## Error Context
+///
+/// A tokenizer error together with the position where its input range
+/// starts. Lines and columns are counted from 0, and columns count
+/// characters rather than bytes. See `Tokenizer::position()` for details.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ErrorContext<'ctx> {
+    /// Error that was raised.
+    pub error: Error<'ctx>,
+    /// Line of the input character.
+    pub line: usize,
+    /// Column of the input character.
+    pub column: usize,
+}
+
+/// ## Number Values
+///
+/// This represents the payload of `Token::Number` (in the same order) and
+/// provides checked conversions into native Rust types. Conversions operate
+/// on the digit values, not the lexical form.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Number<'ctx> {
+    /// Lexical form of the number exactly as in the input.
+    pub raw: &'ctx str,
+    /// Decimal values (0-9) of all digits.
+    pub digits: &'ctx [u8],
+    /// Sign of the integer part.
+    pub sign: Sign,
+    /// Number of integer digits.
+    pub n_integer: usize,
+    /// Number of fraction digits.
+    pub n_fraction: usize,
+    /// Sign of the exponent.
+    pub sign_exponent: Sign,
+    /// Number of exponent digits.
+    pub n_exponent: usize,
+}
+
+impl<F: FnMut(usize)> Progress for F {
+    fn progress(&mut self, offset: usize) {
+        self(offset)
+    }
+}
+
+impl<'data> From<&'data str> for Input<'data> {
+    fn from(v: &'data str) -> Self {
+        Self::Str(v)
+    }
+}
+
+impl<'data> From<&'data [u8]> for Input<'data> {
+    fn from(v: &'data [u8]) -> Self {
+        Self::Bytes(v)
+    }
+}
+
+impl<'ctx> core::fmt::Debug for Feed<'ctx> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        fmt.debug_struct("Feed")
+            .field("finish", &self.finish)
+            .field("interval", &self.progress.as_ref().map(|v| v.1))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'ctx> core::fmt::Display for Error<'ctx> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::CharacterInvalid(v) => fmt.write_fmt(core::format_args!("Invalid character {:?}", v)),
+            Self::CharacterStray(v) => fmt.write_fmt(core::format_args!("Unexpected character {:?}", v)),
+            Self::WhitespaceInvalid(v) => fmt.write_fmt(core::format_args!("Invalid whitespace character {:?}", v)),
+            Self::KeywordUnknown(v) => fmt.write_fmt(core::format_args!("Unknown keyword `{}`", v)),
+            Self::NumberIncomplete => fmt.write_str("Incomplete number"),
+            Self::StringIncomplete => fmt.write_str("Unterminated string"),
+            Self::StringCharacterInvalid(v) => fmt.write_fmt(core::format_args!("Invalid character {:?} in string", v)),
+            Self::StringEscapeInvalid(v) => fmt.write_fmt(core::format_args!("Invalid escape sequence `\\{}` in string", v)),
+            Self::StringEscapeIncomplete => fmt.write_str("Incomplete escape sequence in string"),
+            Self::StringSurrogateUnpaired => fmt.write_str("Unpaired surrogate in string"),
+            Self::StringEscapeUnicode => fmt.write_str("Escape sequence in string is not a valid Unicode Scalar Value"),
+            Self::Comment(_) => fmt.write_str("Comments are not allowed"),
+            Self::BracketMismatch(v) => fmt.write_fmt(core::format_args!("Closing bracket `{}` does not match the open bracket", v)),
+            Self::BracketStray(v) => fmt.write_fmt(core::format_args!("Closing bracket `{}` without open bracket", v)),
+            Self::BracketUnclosed(v) => fmt.write_fmt(core::format_args!("Unclosed bracket `{}`", v)),
+            Self::BracketDepth => fmt.write_str("Brackets are nested too deeply"),
+            Self::Utf8Invalid => fmt.write_str("Invalid UTF-8"),
+        }
+    }
+}
+
+impl core::fmt::Display for NumberError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::Overflow => fmt.write_str("Number is out of range of the target type"),
+            Self::Inexact => fmt.write_str("Number cannot be represented exactly by the target type"),
+        }
+    }
+}
+
+impl<'ctx> core::fmt::Display for ErrorContext<'ctx> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        fmt.write_fmt(core::format_args!("Invalid JSON at line {}, column {}: {}", self.line + 1, self.column + 1, self.error))
+    }
+}
+
+impl<'ctx> Number<'ctx> {
+    /// ## Create Number
+    ///
+    /// Create a number from the payload of `Token::Number`. The digit
+    /// counts must sum up to the number of digit values.
+    pub fn new(
+        raw: &'ctx str,
+        digits: &'ctx [u8],
+        sign: Sign,
+        n_integer: usize,
+        n_fraction: usize,
+        sign_exponent: Sign,
+        n_exponent: usize,
+    ) -> Self {
+        Self {
+            raw: raw,
+            digits: digits,
+            sign: sign,
+            n_integer: n_integer,
+            n_fraction: n_fraction,
+            sign_exponent: sign_exponent,
+            n_exponent: n_exponent,
+        }
+    }
+
+    /// ## Create Number from Token
+    ///
+    /// Create a number from a `Token::Number`, or return `None` for any
+    /// other token.
+    pub fn from_token(token: &Token<'ctx>) -> Option<Self> {
+        match *token {
+            Token::Number(raw, digits, sign, n_int, n_frac, sign_exp, n_exp) => {
+                Some(Self::new(raw, digits, sign, n_int, n_frac, sign_exp, n_exp))
+            },
+            _ => None,
+        }
+    }
+
+    // Yield the magnitude of the number as integer. Zero is always exact,
+    // regardless of its exponent. Otherwise, the exponent is applied to the
+    // digits of the integer and fraction parts, which must not leave any
+    // non-zero fraction digits.
+    fn magnitude(&self) -> Result<u64, NumberError> {
+        let (significand, exponent) = self.digits.split_at(self.n_integer + self.n_fraction);
+
+        if significand.iter().all(|v| *v == 0) {
+            return Ok(0);
+        }
+
+        // Exponents beyond `u64` are out of range for non-zero values of any
+        // native integer, so saturating is fine.
+        let exponent = exponent.iter()
+            .fold(0u64, |acc, v| acc.saturating_mul(10).saturating_add(*v as u64));
+        let shift = match self.sign_exponent {
+            Sign::Plus => exponent as i128,
+            Sign::Minus => -(exponent as i128),
+        } - self.n_fraction as i128;
+
+        let (digits, scale) = if shift < 0 {
+            let cut = usize::try_from(-shift).unwrap_or(usize::MAX);
+            let (digits, rest) = significand.split_at(significand.len().saturating_sub(cut));
+            if rest.iter().any(|v| *v != 0) {
+                return Err(NumberError::Inexact);
+            }
+            (digits, 0)
+        } else {
+            (significand, shift)
+        };
+
+        let mut acc: u64 = 0;
+        for &v in digits {
+            acc = acc.checked_mul(10)
+                .and_then(|acc| acc.checked_add(v as u64))
+                .ok_or(NumberError::Overflow)?;
+        }
+
+        let scale = u32::try_from(scale).map_err(|_| NumberError::Overflow)?;
+        10u64.checked_pow(scale)
+            .and_then(|v| acc.checked_mul(v))
+            .ok_or(NumberError::Overflow)
+    }
+
+    /// ## Convert to `u64`
+    ///
+    /// Convert the number to an unsigned integer. Fractions and exponents
+    /// are allowed, as long as the value is integral (e.g., `1.5e1`).
+    /// Negative zero yields `0`.
+    pub fn to_u64(&self) -> Result<u64, NumberError> {
+        match (self.sign, self.magnitude()?) {
+            (Sign::Minus, v) if v > 0 => Err(NumberError::Overflow),
+            (_, v) => Ok(v),
+        }
+    }
+
+    /// ## Convert to `i64`
+    ///
+    /// Convert the number to a signed integer. See `Self::to_u64()` for
+    /// details.
+    pub fn to_i64(&self) -> Result<i64, NumberError> {
+        let v = self.magnitude()?;
+        match self.sign {
+            Sign::Plus => i64::try_from(v).map_err(|_| NumberError::Overflow),
+            Sign::Minus => 0i64.checked_sub_unsigned(v).ok_or(NumberError::Overflow),
+        }
+    }
+
+    /// ## Convert to `f64`
+    ///
+    /// Convert the number to a floating point number, rounding to the
+    /// nearest representable value. Values too small to be represented
+    /// yield zero, retaining the sign. Values too large to be represented
+    /// yield `NumberError::Overflow`.
+    pub fn to_f64(&self) -> Result<f64, NumberError> {
+        let (integer, rest) = self.digits.split_at(self.n_integer);
+        let (fraction, exponent) = rest.split_at(self.n_fraction);
+        let digit = |v: &u8| char::from(b'0' + *v);
+
+        // Re-assemble the number in canonical form, rather than relying on
+        // the lexical form, which might use extensions.
+        let mut s = alloc::string::String::with_capacity(self.digits.len() + 4);
+        if self.sign == Sign::Minus {
+            s.push('-');
+        }
+        s.extend(integer.iter().map(digit));
+        if !fraction.is_empty() {
+            s.push('.');
+            s.extend(fraction.iter().map(digit));
+        }
+        if !exponent.is_empty() {
+            s.push('e');
+            if self.sign_exponent == Sign::Minus {
+                s.push('-');
+            }
+            s.extend(exponent.iter().map(digit));
+        }
+
+        match s.parse::<f64>() {
+            Ok(v) if v.is_finite() => Ok(v),
+            _ => Err(NumberError::Overflow),
+        }
+    }
+}
+
+// Check whether a character continues a word, rather than starting a new
+// token. This covers all characters that would otherwise be treated as
+// part of a keyword.
+fn continues_word(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '.' || ch == '_'
+}
+
 // ## Tokenizer State
 //
 // The internal state of the tokenizer. `State::None` is used when the
@@ -140,7 +506,7 @@ enum State {
     None,
     Slash,
     Keyword,
-    Whitespace,
+    Whitespace(bool),
     NumberIntegerNone(Sign),
     NumberIntegerSome(Sign, usize),
     NumberIntegerZero(Sign),
@@ -149,6 +515,7 @@ enum State {
     NumberExponentNone(Sign, usize, usize),
     NumberExponentSign(Sign, usize, usize, Sign),
     NumberExponentSome(Sign, usize, usize, Sign, usize),
+    NumberIncomplete,
     String,
     StringEscape,
     StringUnicode(u8, u32),
@@ -158,6 +525,15 @@ enum State {
     CommentLine,
 }
 
+// Position in the input, tracked as byte offset for input ranges, as well
+// as line and column for diagnostics.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+struct Mark {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
 /// ## Tokenizer Engine
 ///
 /// The tokenizer engine takes an input stream of Unicode Scalar Values
@@ -173,6 +549,16 @@ pub struct Tokenizer {
     acc_str: alloc::string::String,
     acc_num: alloc::vec::Vec<u8>,
     state: State,
+    finished: bool,
+    nesting: Brackets,
+    key: bool,
+    quote: char,
+    start: Mark,
+    at: Mark,
+    next: Mark,
+    reported: Mark,
+    utf8: [u8; 4],
+    n_utf8: usize,
 }
 
 impl Tokenizer {
@@ -198,15 +584,20 @@ impl Tokenizer {
     }
 
     // Clear current buffers and prepare for the next token. This should be
-    // called after a token was finished.
+    // called after a token was finished. If the engine was finished via
+    // `Self::finish()`, the buffers are retained for inspection.
     fn prepare(&mut self) {
+        self.state = State::None;
+        if self.finished {
+            return;
+        }
+
         self.acc.clear();
         self.acc.shrink_to(4096);
         self.acc_str.clear();
         self.acc_str.shrink_to(4096);
         self.acc_num.clear();
         self.acc_num.shrink_to(4096);
-        self.state = State::None;
     }
 
     /// ## Reset Tokenizer
@@ -215,9 +606,60 @@ impl Tokenizer {
     /// buffers might remain allocated for performance reasons. However, any
     /// data is cleared.
     pub fn reset(&mut self) {
+        self.finished = false;
+        self.nesting = Brackets::new();
+        self.key = false;
+        self.quote = Default::default();
+        self.start = Default::default();
+        self.at = Default::default();
+        self.next = Default::default();
+        self.reported = Default::default();
+        self.n_utf8 = 0;
         self.prepare();
     }
 
+    /// ## Yield Token Buffer
+    ///
+    /// Return the raw data accumulated for the token that is currently
+    /// parsed. If the engine was finished via `Self::finish()`, this yields
+    /// the data of the final token until the engine is reset. This is
+    /// meant for diagnostics and does not include the opening quote of
+    /// JSON Strings.
+    pub fn buffer(&self) -> &str {
+        &self.acc
+    }
+
+    /// ## Yield Input Position
+    ///
+    /// Return the line and column where the token that was reported last
+    /// starts, counted from 0 since the engine was last reset. This is the
+    /// start of the input range of the token (see `Self::feed()`), so an
+    /// error that replaces an entire token (e.g., an unknown keyword) is
+    /// located at the start of the token rather than at the character that
+    /// finalized it. Every character advances the column by one, except for
+    /// newlines, which start a new line. If a push reported multiple
+    /// tokens, only the last one can be located via `Self::context()` once
+    /// the push returned.
+    ///
+    /// Note that the engine is reset when a handler breaks, so errors must
+    /// be collected by the handler and located once the push returned,
+    /// rather than breaking on them.
+    pub fn position(&self) -> (usize, usize) {
+        (self.reported.line, self.reported.column)
+    }
+
+    /// ## Attach Position to Error
+    ///
+    /// Attach the current input position to the given error. See
+    /// `Self::position()` for details.
+    pub fn context<'ctx>(&self, error: Error<'ctx>) -> ErrorContext<'ctx> {
+        ErrorContext {
+            error: error,
+            line: self.reported.line,
+            column: self.reported.column,
+        }
+    }
+
     /// ## Report Status
     ///
     /// Report the status of the tokenizer engine. If a token is currently
@@ -230,9 +672,58 @@ impl Tokenizer {
         }
     }
 
+    // Input range of the current token up to, but excluding, the current
+    // character. Used for tokens that are finalized by a character that is
+    // not part of them.
+    fn span_token(&self) -> core::ops::Range<usize> {
+        self.start.offset..self.at.offset
+    }
+
+    // Input range of the current token including the current character.
+    fn span_through(&self) -> core::ops::Range<usize> {
+        self.start.offset..self.next.offset
+    }
+
+    // Input range of the current character.
+    fn span_char(&self) -> core::ops::Range<usize> {
+        self.at.offset..self.next.offset
+    }
+
+    // Track the nesting of JSON Arrays and Objects, so keys of JSON Object
+    // members can be detected. This is only needed with
+    // `FLAG_ALLOW_UNQUOTED_KEYS`. Bracket errors are left to the caller, and
+    // key detection is limited to `BRACKET_DEPTH` levels of nesting.
+    fn track(&mut self, ch: char) {
+        if (self.flags & FLAG_ALLOW_UNQUOTED_KEYS) == 0 {
+            return;
+        }
+
+        match ch {
+            '{' => {
+                let _ = self.nesting.open(true);
+                self.key = true;
+            },
+            '[' => {
+                let _ = self.nesting.open(false);
+                self.key = false;
+            },
+            '}' | ']' => {
+                let _ = self.nesting.close(ch == '}');
+                self.key = false;
+            },
+            ',' => {
+                self.key = self.nesting.innermost() == Some('{');
+            },
+            ':' | '=' => {
+                self.key = false;
+            },
+            _ => {},
+        }
+    }
+
     fn advance_misc<
         HandlerValue,
-        HandlerFn: FnMut(Token) -> core::ops::ControlFlow<HandlerValue>,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
     >(
         &mut self,
         ch: Option<char>,
@@ -254,7 +745,7 @@ impl Tokenizer {
                     core::ops::ControlFlow::Continue(None)
                 },
                 None => {
-                    handler(Token::Error(Error::CharacterStray('/')))?;
+                    handler(Token::Error(Error::CharacterStray('/')), self.span_token())?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -276,13 +767,23 @@ impl Tokenizer {
                     core::ops::ControlFlow::Continue(None)
                 },
                 v => {
+                    // In key position, identifiers are treated as keys if
+                    // allowed. Keyword-tokens starting with anything but a
+                    // letter carry invalid characters and are not
+                    // identifiers.
+                    let key = self.key
+                        && self.acc.starts_with(|v: char| v.is_ascii_alphabetic());
+                    self.key = false;
+
                     handler(
                         match self.acc.as_str() {
+                            v if key => Token::String(v, v),
                             "null" => Token::Null,
                             "true" => Token::True,
                             "false" => Token::False,
                             _ => Token::Error(Error::KeywordUnknown(&self.acc)),
-                        }
+                        },
+                        self.span_token(),
                     )?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(v)
@@ -292,21 +793,30 @@ impl Tokenizer {
             // Merge as much whitespace into a single whitespace token as
             // possible. Once the first non-whitespace token is found, signal
             // the whitespace token and return the next character as unhandled.
-            State::Whitespace => match ch {
+            // Invalid whitespace is not accumulated, and only the first
+            // character of a consecutive run is reported, so long runs of
+            // invalid whitespace need neither memory nor produce floods of
+            // errors. The state tracks whether such a run is ongoing.
+            State::Whitespace(_) => match ch {
                 Some(v @ ' ')
                 | Some(v @ '\n')
                 | Some(v @ '\r')
                 | Some(v @ '\t') => {
                     self.acc.push(v);
+                    self.state = State::Whitespace(false);
                     core::ops::ControlFlow::Continue(None)
                 },
                 Some(v) if v.is_whitespace() => {
-                    handler(Token::Error(Error::WhitespaceInvalid(v)))?;
-                    self.acc.push(v);
+                    if self.state == State::Whitespace(false) {
+                        handler(Token::Error(Error::WhitespaceInvalid(v)), self.span_char())?;
+                        self.state = State::Whitespace(true);
+                    }
                     core::ops::ControlFlow::Continue(None)
                 },
                 v => {
-                    handler(Token::Whitespace(&self.acc))?;
+                    if !self.acc.is_empty() {
+                        handler(Token::Whitespace(&self.acc), self.span_token())?;
+                    }
                     self.prepare();
                     core::ops::ControlFlow::Continue(v)
                 },
@@ -317,7 +827,7 @@ impl Tokenizer {
             // comments, but we parse them for better diagnostics.
             State::CommentLine => match ch {
                 Some('\n') => {
-                    handler(Token::Error(Error::Comment(&self.acc)))?;
+                    handler(Token::Error(Error::Comment(&self.acc)), self.span_token())?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(ch)
                 },
@@ -326,7 +836,7 @@ impl Tokenizer {
                     core::ops::ControlFlow::Continue(None)
                 },
                 None => {
-                    handler(Token::Error(Error::Comment(&self.acc)))?;
+                    handler(Token::Error(Error::Comment(&self.acc)), self.span_token())?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -338,7 +848,7 @@ impl Tokenizer {
 
     fn advance_number<
         HandlerValue,
-        HandlerFn: FnMut(Token) -> core::ops::ControlFlow<HandlerValue>,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
     >(
         &mut self,
         ch: Option<char>,
@@ -350,6 +860,23 @@ impl Tokenizer {
         // push all digits into a separate accumulator and remember how
         // many digits each component occupies. This allows much simpler
         // number conversions later on.
+        //
+        // Every digit is pushed to `acc_num` exactly once, and counted in
+        // the component it belongs to. Hence, the counts of a number token
+        // always sum up to the length of `acc_num`. The counts cannot
+        // overflow, as they are bounded by the length of `acc_num`.
+        //
+        // A number ends with the first character that cannot continue it,
+        // which is then returned to start a new token. Hence, `1 2` and
+        // `1-2` both yield two numbers, leaving it to the parser to reject
+        // them. If a required component is missing (e.g., in `1e` or `-`),
+        // the number is reported as incomplete and the character is still
+        // returned, since it is likely a valid token on its own.
+        //
+        // However, if the character would continue a word (e.g., in `-a`,
+        // `-.5`, or `1.e5`), it is unlikely to be a valid token. Instead of
+        // cascading into further errors, the rest of the word is consumed
+        // and reported as part of the incomplete number.
         match self.state {
             State::NumberIntegerNone(sign_int) => match ch {
                 Some(v @ '0'..='9') => {
@@ -364,15 +891,15 @@ impl Tokenizer {
                     }
                     core::ops::ControlFlow::Continue(None)
                 },
-                Some(v) => {
-                    handler(Token::Error(Error::CharacterStray(v)))?;
-                    self.prepare();
-                    core::ops::ControlFlow::Continue(Some(v))
+                Some(v) if continues_word(v) => {
+                    self.acc.push(v);
+                    self.state = State::NumberIncomplete;
+                    core::ops::ControlFlow::Continue(None)
                 },
-                None => {
-                    handler(Token::Error(Error::NumberIncomplete))?;
+                v => {
+                    handler(Token::Error(Error::NumberIncomplete), self.span_token())?;
                     self.prepare();
-                    core::ops::ControlFlow::Continue(None)
+                    core::ops::ControlFlow::Continue(v)
                 },
             },
 
@@ -385,7 +912,8 @@ impl Tokenizer {
                     self.state = State::NumberIntegerSome(sign_int, n_int + 1);
                     core::ops::ControlFlow::Continue(None)
                 },
-                Some('.') => {
+                Some(v @ '.') => {
+                    self.acc.push(v);
                     self.state = State::NumberFractionNone(sign_int, n_int);
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -399,13 +927,14 @@ impl Tokenizer {
                 v => {
                     handler(Token::Number(
                         &self.acc, self.acc_num.as_slice(), sign_int, n_int, 0, Sign::Plus, 0,
-                    ))?;
+                    ), self.span_token())?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(v)
                 },
             },
             State::NumberIntegerZero(sign_int) => match ch {
-                Some('.') => {
+                Some(v @ '.') => {
+                    self.acc.push(v);
                     self.state = State::NumberFractionNone(sign_int, 1);
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -419,7 +948,7 @@ impl Tokenizer {
                 v => {
                     handler(Token::Number(
                         &self.acc, self.acc_num.as_slice(), sign_int, 1, 0, Sign::Plus, 0,
-                    ))?;
+                    ), self.span_token())?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(v)
                 },
@@ -434,15 +963,15 @@ impl Tokenizer {
                     self.state = State::NumberFractionSome(sign_int, n_int, 1);
                     core::ops::ControlFlow::Continue(None)
                 },
-                Some(v) => {
-                    handler(Token::Error(Error::CharacterStray(v)))?;
-                    self.prepare();
-                    core::ops::ControlFlow::Continue(Some(v))
+                Some(v) if continues_word(v) => {
+                    self.acc.push(v);
+                    self.state = State::NumberIncomplete;
+                    core::ops::ControlFlow::Continue(None)
                 },
-                None => {
-                    handler(Token::Error(Error::NumberIncomplete))?;
+                v => {
+                    handler(Token::Error(Error::NumberIncomplete), self.span_token())?;
                     self.prepare();
-                    core::ops::ControlFlow::Continue(None)
+                    core::ops::ControlFlow::Continue(v)
                 },
             },
 
@@ -465,7 +994,7 @@ impl Tokenizer {
                 v => {
                     handler(Token::Number(
                         &self.acc, self.acc_num.as_slice(), sign_int, n_int, n_frac, Sign::Plus, 0,
-                    ))?;
+                    ), self.span_token())?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(v)
                 },
@@ -496,15 +1025,15 @@ impl Tokenizer {
                     );
                     core::ops::ControlFlow::Continue(None)
                 },
-                Some(v) => {
-                    handler(Token::Error(Error::CharacterStray(v)))?;
-                    self.prepare();
-                    core::ops::ControlFlow::Continue(Some(v))
+                Some(v) if continues_word(v) => {
+                    self.acc.push(v);
+                    self.state = State::NumberIncomplete;
+                    core::ops::ControlFlow::Continue(None)
                 },
-                None => {
-                    handler(Token::Error(Error::NumberIncomplete))?;
+                v => {
+                    handler(Token::Error(Error::NumberIncomplete), self.span_token())?;
                     self.prepare();
-                    core::ops::ControlFlow::Continue(None)
+                    core::ops::ControlFlow::Continue(v)
                 },
             },
 
@@ -519,15 +1048,15 @@ impl Tokenizer {
                     );
                     core::ops::ControlFlow::Continue(None)
                 },
-                Some(v) => {
-                    handler(Token::Error(Error::CharacterStray(v)))?;
-                    self.prepare();
-                    core::ops::ControlFlow::Continue(Some(v))
+                Some(v) if continues_word(v) => {
+                    self.acc.push(v);
+                    self.state = State::NumberIncomplete;
+                    core::ops::ControlFlow::Continue(None)
                 },
-                None => {
-                    handler(Token::Error(Error::NumberIncomplete))?;
+                v => {
+                    handler(Token::Error(Error::NumberIncomplete), self.span_token())?;
                     self.prepare();
-                    core::ops::ControlFlow::Continue(None)
+                    core::ops::ControlFlow::Continue(v)
                 },
             },
 
@@ -545,7 +1074,19 @@ impl Tokenizer {
                 v => {
                     handler(Token::Number(
                         &self.acc, self.acc_num.as_slice(), sign_int, n_int, n_frac, sign_exp, n_exp,
-                    ))?;
+                    ), self.span_token())?;
+                    self.prepare();
+                    core::ops::ControlFlow::Continue(v)
+                },
+            },
+
+            State::NumberIncomplete => match ch {
+                Some(v) if continues_word(v) => {
+                    self.acc.push(v);
+                    core::ops::ControlFlow::Continue(None)
+                },
+                v => {
+                    handler(Token::Error(Error::NumberIncomplete), self.span_token())?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(v)
                 },
@@ -557,17 +1098,24 @@ impl Tokenizer {
 
     fn advance_string<
         HandlerValue,
-        HandlerFn: FnMut(Token) -> core::ops::ControlFlow<HandlerValue>,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
     >(
         &mut self,
         ch: Option<char>,
         handler: &mut HandlerFn,
     ) -> core::ops::ControlFlow<HandlerValue, Option<char>> {
         // Strings must be terminated with a quote. Therefore, we can handle
-        // `None` early for all string states.
+        // `None` early for all string states. If a lead-surrogate is still
+        // pending, it can never be paired, so report this before the
+        // incomplete string.
         let ch_value = match ch {
             None => {
-                handler(Token::Error(Error::StringIncomplete))?;
+                if let State::StringSurrogate(_)
+                | State::StringSurrogateEscape(_)
+                | State::StringSurrogateUnicode(_, _, _) = self.state {
+                    handler(Token::Error(Error::StringSurrogateUnpaired), self.span_token())?;
+                }
+                handler(Token::Error(Error::StringIncomplete), self.span_token())?;
                 self.prepare();
                 return core::ops::ControlFlow::Continue(None);
             },
@@ -578,10 +1126,14 @@ impl Tokenizer {
         // accumulator and tracking escape-sequences. Unicode-escapes make up
         // most of the complexity, since we must track surrogate pairs to avoid
         // strings with non-paired surrogate escapes.
+        //
+        // Strings are terminated by the quote that opened them, which is
+        // remembered in `self.quote`.
         match self.state {
             State::String => match ch_value {
-                '"' => {
-                    handler(Token::String(&self.acc, &self.acc_str))?;
+                v if v == self.quote => {
+                    self.key = false;
+                    handler(Token::String(&self.acc, &self.acc_str), self.span_through())?;
                     self.prepare();
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -590,11 +1142,8 @@ impl Tokenizer {
                     self.state = State::StringEscape;
                     core::ops::ControlFlow::Continue(None)
                 },
-                v @ '\x20'..='\x21'
-                // '\x22' is '"'
-                | v @ '\x23'..='\x5b'
-                // '\x5c' is '\\'
-                | v @ '\x5d'..='\u{d7ff}'
+                // '\x5c' is '\\' and handled above, as is the quote
+                v @ '\x20'..='\u{d7ff}'
                 // '\u{d800}'..='\u{dfff}' are surrogates
                 | v @ '\u{e000}'..='\u{10ffff}' => {
                     self.acc.push(v);
@@ -602,7 +1151,7 @@ impl Tokenizer {
                     core::ops::ControlFlow::Continue(None)
                 },
                 v @ '\x00'..='\x1f' => {
-                    handler(Token::Error(Error::StringCharacterInvalid(v)))?;
+                    handler(Token::Error(Error::StringCharacterInvalid(v)), self.span_char())?;
                     self.acc.push(v);
                     self.acc_str.push(v);
                     core::ops::ControlFlow::Continue(None)
@@ -626,15 +1175,24 @@ impl Tokenizer {
                     self.state = State::String;
                     core::ops::ControlFlow::Continue(None)
                 },
+                // Single quotes can only be escaped in single-quoted
+                // strings, to retain strict escapes for JSON Strings.
+                v @ '\'' if self.quote == '\'' => {
+                    self.acc.push(v);
+                    self.acc_str.push(v);
+                    self.state = State::String;
+                    core::ops::ControlFlow::Continue(None)
+                },
                 v @ 'u' => {
                     self.acc.push(v);
                     self.state = State::StringUnicode(0, 0);
                     core::ops::ControlFlow::Continue(None)
                 },
                 v => {
-                    handler(Token::Error(Error::StringEscapeInvalid(v)))?;
+                    handler(Token::Error(Error::StringEscapeInvalid(v)), self.span_char())?;
                     self.acc.push(v);
                     self.acc_str.push(v);
+                    self.state = State::String;
                     core::ops::ControlFlow::Continue(None)
                 },
             },
@@ -665,19 +1223,19 @@ impl Tokenizer {
                     } else if value >= 0xdc00 && value <= 0xdfff {
                         // Got an unpaired trail-surrogate. This is not
                         // allowed, so reject it straight away.
-                        handler(Token::Error(Error::StringSurrogateUnpaired))?;
+                        handler(Token::Error(Error::StringSurrogateUnpaired), self.span_char())?;
                     } else if let Some(v) = char::from_u32(value) {
                         // Got a valid Unicode Scalar Value.
                         self.acc_str.push(v);
                         self.state = State::String;
                     } else {
                         // Code-point is not a Unicode Scalar Value.
-                        handler(Token::Error(Error::StringEscapeUnicode))?;
+                        handler(Token::Error(Error::StringEscapeUnicode), self.span_char())?;
                     }
                     core::ops::ControlFlow::Continue(None)
                 },
                 v => {
-                    handler(Token::Error(Error::StringEscapeIncomplete))?;
+                    handler(Token::Error(Error::StringEscapeIncomplete), self.span_char())?;
                     self.acc.push(v);
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -690,7 +1248,7 @@ impl Tokenizer {
                     core::ops::ControlFlow::Continue(None)
                 },
                 v => {
-                    handler(Token::Error(Error::StringSurrogateUnpaired))?;
+                    handler(Token::Error(Error::StringSurrogateUnpaired), self.span_char())?;
                     self.acc.push(v);
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -703,7 +1261,7 @@ impl Tokenizer {
                     core::ops::ControlFlow::Continue(None)
                 },
                 v => {
-                    handler(Token::Error(Error::StringSurrogateUnpaired))?;
+                    handler(Token::Error(Error::StringSurrogateUnpaired), self.span_char())?;
                     self.acc.push(v);
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -720,7 +1278,7 @@ impl Tokenizer {
                     } else if trail >= 0xd800 && trail <= 0xdbff {
                         // This is another lead-surrogate, but we expected
                         // a trail-surrogate. Reject it.
-                        handler(Token::Error(Error::StringSurrogateUnpaired))?;
+                        handler(Token::Error(Error::StringSurrogateUnpaired), self.span_char())?;
                     } else if trail >= 0xdc00 && trail <= 0xdfff {
                         // This is a trail-surrogate following a
                         // lead-surrogate. This finalizes the surrogate
@@ -736,15 +1294,15 @@ impl Tokenizer {
                     } else if let Some(_) = char::from_u32(trail) {
                         // We expected a trail-surrogate, but got a
                         // Unicode Scalar Value. Reject this.
-                        handler(Token::Error(Error::StringSurrogateUnpaired))?;
+                        handler(Token::Error(Error::StringSurrogateUnpaired), self.span_char())?;
                     } else {
                         // Code-point is not a Unicode Scalar Value.
-                        handler(Token::Error(Error::StringEscapeUnicode))?;
+                        handler(Token::Error(Error::StringEscapeUnicode), self.span_char())?;
                     }
                     core::ops::ControlFlow::Continue(None)
                 },
                 v => {
-                    handler(Token::Error(Error::StringEscapeIncomplete))?;
+                    handler(Token::Error(Error::StringEscapeIncomplete), self.span_char())?;
                     self.acc.push(v);
                     core::ops::ControlFlow::Continue(None)
                 },
@@ -785,6 +1343,68 @@ impl Tokenizer {
         &mut self,
         ch: Option<char>,
         handler: &mut HandlerFn,
+    ) -> Report<HandlerValue> {
+        debug_assert!(!self.finished, "Tokenizer must be reset after finish");
+        self.advance(ch, &mut |v, _| handler(v))
+    }
+
+    // Advance the engine by a single character. This implements
+    // `Self::push()` but skips any state verification, so it can be used
+    // to push the End-Of-Input marker in `Self::finish()`.
+    fn advance<
+        HandlerValue,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
+    >(
+        &mut self,
+        ch: Option<char>,
+        handler: &mut HandlerFn,
+    ) -> Report<HandlerValue> {
+        // Remember the position of this character and advance the position
+        // for the next one. The End-Of-Input marker does not occupy any
+        // input.
+        self.at = self.next;
+        self.next = match ch {
+            Some('\n') => Mark {
+                offset: self.at.offset + 1,
+                line: self.at.line + 1,
+                column: 0,
+            },
+            Some(v) => Mark {
+                offset: self.at.offset + v.len_utf8(),
+                line: self.at.line,
+                column: self.at.column + 1,
+            },
+            None => self.at,
+        };
+
+        // Remember where the token reported last starts. Input ranges of
+        // tokens either start at the current token, or at the current
+        // character, so their start can be mapped back to its position.
+        let (start, at) = (self.start, self.at);
+        let mut reported = None;
+        let r = self.advance_char(
+            ch,
+            &mut |token, range: core::ops::Range<usize>| {
+                reported = Some(if range.start == start.offset { start } else { at });
+                handler(token, range)
+            },
+        );
+        if let (Report::Continue(_), Some(v)) = (&r, reported) {
+            self.reported = v;
+        }
+        r
+    }
+
+    // Process a single character with the current token or start a new
+    // token with it. This implements `Self::advance()` apart from tracking
+    // the position.
+    fn advance_char<
+        HandlerValue,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
+    >(
+        &mut self,
+        ch: Option<char>,
+        handler: &mut HandlerFn,
     ) -> Report<HandlerValue> {
         // First try to push the next character into the current token
         // handler. If either no token is currently parsed, or if the
@@ -795,7 +1415,7 @@ impl Tokenizer {
 
             State::Slash
             | State::Keyword
-            | State::Whitespace
+            | State::Whitespace(_)
             | State::CommentLine => {
                 self.advance_misc(ch, handler)
             },
@@ -807,7 +1427,8 @@ impl Tokenizer {
             | State::NumberFractionSome(_, _, _)
             | State::NumberExponentNone(_, _, _)
             | State::NumberExponentSign(_, _, _, _)
-            | State::NumberExponentSome(_, _, _, _, _) => {
+            | State::NumberExponentSome(_, _, _, _, _)
+            | State::NumberIncomplete => {
                 self.advance_number(ch, handler)
             },
 
@@ -821,6 +1442,11 @@ impl Tokenizer {
             },
         };
 
+        if let core::ops::ControlFlow::Continue(Some(v)) = rem {
+            self.start = self.at;
+            self.track(v);
+        }
+
         match rem {
             // A handler yielded a break value. Reset the engine and propagate
             // the break to the caller. A break cannot be recovered from, so
@@ -839,22 +1465,22 @@ impl Tokenizer {
             // finalized it. Either way, the character starts a new token.
             core::ops::ControlFlow::Continue(Some(v)) => match v {
                 ':' => {
-                    handler(Token::Colon)?;
+                    handler(Token::Colon, self.span_char())?;
                 },
                 ',' => {
-                    handler(Token::Comma)?;
+                    handler(Token::Comma, self.span_char())?;
                 },
                 '[' => {
-                    handler(Token::ArrayOpen)?;
+                    handler(Token::ArrayOpen, self.span_char())?;
                 },
                 ']' => {
-                    handler(Token::ArrayClose)?;
+                    handler(Token::ArrayClose, self.span_char())?;
                 },
                 '{' => {
-                    handler(Token::ObjectOpen)?;
+                    handler(Token::ObjectOpen, self.span_char())?;
                 },
                 '}' => {
-                    handler(Token::ObjectClose)?;
+                    handler(Token::ObjectClose, self.span_char())?;
                 },
                 'a'..='z' | 'A'..='Z' => {
                     self.acc.push(v);
@@ -862,7 +1488,7 @@ impl Tokenizer {
                 },
                 ' ' | '\n' | '\r' | '\t' => {
                     self.acc.push(v);
-                    self.state = State::Whitespace;
+                    self.state = State::Whitespace(false);
                 },
                 '-' => {
                     self.acc.push(v);
@@ -880,6 +1506,11 @@ impl Tokenizer {
                     }
                 },
                 '"' => {
+                    self.quote = v;
+                    self.state = State::String;
+                },
+                '\'' if (self.flags & FLAG_ALLOW_SINGLE_QUOTES) != 0 => {
+                    self.quote = v;
                     self.state = State::String;
                 },
                 '#' => {
@@ -890,7 +1521,7 @@ impl Tokenizer {
                     // then treat it as part of a keyword. We could try to
                     // parse it as single-quote string, but it is unclear
                     // whether it would yield better diagnostics.
-                    handler(Token::Error(Error::CharacterInvalid(v)))?;
+                    handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
                     self.acc.push(v);
                     self.state = State::Keyword;
                 },
@@ -900,7 +1531,7 @@ impl Tokenizer {
                     // match them and ignore anything in between to get
                     // better diagnostics. But for now we just do the
                     // simple thing and treat it as keyword.
-                    handler(Token::Error(Error::CharacterInvalid(v)))?;
+                    handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
                     self.acc.push(v);
                     self.state = State::Keyword;
                 },
@@ -910,7 +1541,7 @@ impl Tokenizer {
                     // an error, unless explicitly allowed, and then continue
                     // parsing the number.
                     if (self.flags & FLAG_ALLOW_PLUS_SIGN) == 0 {
-                        handler(Token::Error(Error::CharacterInvalid(v)))?;
+                        handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
                     }
                     self.acc.push(v);
                     self.state = State::NumberIntegerNone(Sign::Plus);
@@ -926,43 +1557,46 @@ impl Tokenizer {
                 '=' => {
                     // Raise errors about equal signs, but then treat them as
                     // colons, as they usually serve similar purposes.
-                    handler(Token::Error(Error::CharacterInvalid(v)))?;
-                    handler(Token::Colon)?;
+                    handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
+                    handler(Token::Colon, self.span_char())?;
                 },
                 '`' => {
                     // Backticks are not allowed, but treat them as part of a
                     // keyword for diagnotics. We could try to match them and
                     // ignore anything in between, but it is unclear whether it
                     // would benefit diagnostics.
-                    handler(Token::Error(Error::CharacterInvalid(v)))?;
+                    handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
                     self.acc.push(v);
                     self.state = State::Keyword;
                 },
-                '!' | '$' | '%' | '&' | '*' | '.' | '<' | '>'
+                '!' | '$' | '%' | '&' | '*' | '<' | '>'
                 | '?' | '@' | '\\' | '^' | '_' | '|' | '~' => {
                     // Raise errors about these punctuation characters, but
                     // continue as if they are part of keywords, given that
                     // they are often used in special keywords elsewhere.
-                    handler(Token::Error(Error::CharacterInvalid(v)))?;
+                    handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
                     self.acc.push(v);
                     self.state = State::Keyword;
                 },
                 v if v.is_ascii_punctuation() => {
                     // Raise errors about stray unsupported punctuation
                     // characters, but otherwise ignore them and continue.
-                    handler(Token::Error(Error::CharacterInvalid(v)))?;
+                    // This includes stray dots, so the digits following a
+                    // second dot in a number are parsed as a new number
+                    // rather than reporting the dot again in a keyword.
+                    handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
                 },
                 v if v.is_control() => {
                     // Raise errors about stray control characters, but
                     // otherwise ignore them and continue.
-                    handler(Token::Error(Error::CharacterInvalid(v)))?;
+                    handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
                 },
                 v if v.is_whitespace() => {
                     // Raise errors about unsupported whitespace characters,
-                    // but then include them in the whitespace token verbatim.
-                    handler(Token::Error(Error::WhitespaceInvalid(v)))?;
-                    self.acc.push(v);
-                    self.state = State::Whitespace;
+                    // but then treat them as part of a whitespace token. They
+                    // are not included in the token, though.
+                    handler(Token::Error(Error::WhitespaceInvalid(v)), self.span_char())?;
+                    self.state = State::Whitespace(true);
                 },
                 v if v.is_alphanumeric() => {
                     // Unsupported alphanumeric characters are simply treated
@@ -975,7 +1609,7 @@ impl Tokenizer {
                     // Any other character we simply treat as invalid and
                     // ignore it. There is nothing reasonable to do about it,
                     // as all other things have been handled before.
-                    handler(Token::Error(Error::CharacterInvalid(v)))?;
+                    handler(Token::Error(Error::CharacterInvalid(v)), self.span_char())?;
                 },
             },
         }
@@ -1002,45 +1636,394 @@ impl Tokenizer {
         Report::Continue(self.status())
     }
 
-    /// ## Parse a String with the Tokenizer
+    /// ## Feed Input into the Tokenizer
     ///
-    /// Push the entire string into the tokenizer engine, followed by an
-    /// End-Of-Input marker. See `Self::push()` for details.
+    /// Push an entire string or chunk of bytes into the tokenizer like
+    /// `Self::push_str()`, or parse it like `Self::parse_str()` if
+    /// `Feed::finish` is set. See `Input` and `Feed` for details.
     ///
-    /// Note that this does not clear the engine before pushing the string
-    /// into it. Hence, make sure to call this on a clean engine, unless
-    /// it is meant to be pushed on top of the previous input.
+    /// Unlike those, the handler is passed the input range of each token.
+    /// Ranges are byte offsets into the input fed since the engine was last
+    /// reset, so they can be used to map tokens back to the original input.
+    /// Note that the raw token buffer can differ from the input (e.g., the
+    /// quotes of JSON Strings are not part of it). Tokens cover the input
+    /// they were parsed from. Errors cover the character that triggered
+    /// them, unless they replace a token (e.g., an unknown keyword or an
+    /// incomplete number), in which case they cover the entire token.
+    /// Invalid bytes occupy input as well.
     ///
-    /// This will finalize the input and thus always reset the tokenizer
-    /// before returning. Moreover, the tokenizer will always report a
-    /// status of `Status::Done` when finished.
-    pub fn parse_str<
+    /// If the handler breaks, the break value is returned together with the
+    /// byte offset of the character that was processed when the handler
+    /// broke. All input before this offset was fully consumed. Since the
+    /// tokenizer is reset on break, input can be resumed at this offset.
+    /// Note that a single character can produce multiple tokens (e.g., a
+    /// terminating comma produces the token it terminates as well as a comma
+    /// token), so resuming might report tokens a second time. Retained bytes
+    /// of earlier chunks are discarded with the reset. If the handler breaks
+    /// on the End-Of-Input marker, the length of the input is reported as
+    /// offset.
+    pub fn feed<
+        'data,
         HandlerValue,
-        HandlerFn: FnMut(Token) -> core::ops::ControlFlow<HandlerValue>,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
     >(
         &mut self,
-        data: &str,
+        input: impl Into<Input<'data>>,
+        options: Feed,
         handler: &mut HandlerFn,
-    ) -> Report<HandlerValue> {
-        for ch in data.chars() {
-            self.push(Some(ch), handler)?;
+    ) -> Report<(HandlerValue, usize)> {
+        let Feed { finish, mut progress } = options;
+        let mut n = 0;
+        let mut step = |offset: usize| {
+            if let Some((observer, interval)) = progress.as_mut() {
+                n += 1;
+                if n == interval.get() {
+                    n = 0;
+                    observer.progress(offset);
+                }
+            }
+        };
+
+        debug_assert!(!self.finished, "Tokenizer must be reset after finish");
+
+        let len = match input.into() {
+            Input::Str(data) => {
+                for (idx, ch) in data.char_indices() {
+                    if let Report::Break(v) = self.advance(Some(ch), handler) {
+                        return Report::Break((v, idx));
+                    }
+                    step(idx + ch.len_utf8());
+                }
+                data.len()
+            },
+            Input::Bytes(data) => {
+                for (idx, &v) in data.iter().enumerate() {
+                    if let Report::Break(v) = self.push_byte(v, handler) {
+                        return Report::Break((v, idx));
+                    }
+                    step(idx + 1);
+                }
+                data.len()
+            },
+        };
+
+        if !finish {
+            return Report::Continue(self.status());
         }
-        self.push(None, handler)?;
+        if let Report::Break(v) = self.conclude(handler) {
+            return Report::Break((v, len));
+        }
+        self.reset();
         Report::Continue(Status::Done)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // Push a single byte of UTF-8 input. Bytes are collected until they
+    // form a complete character, which is then pushed into the engine.
+    fn push_byte<
+        HandlerValue,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
+    >(
+        &mut self,
+        byte: u8,
+        handler: &mut HandlerFn,
+    ) -> Report<HandlerValue> {
+        debug_assert!(!self.finished, "Tokenizer must be reset after finish");
 
-    // Assert that the specified input tokenizes to the specified stream of
-    // tokens. This also verifies that the tokenizer finishes successfully.
-    fn assert_tokenize(
-        from: &str,
-        to: &alloc::vec::Vec<Token>,
-    ) {
-        let mut iter = to.iter();
+        // A pending sequence is cut short by anything but a continuation
+        // byte. The byte is then processed as start of a new sequence.
+        if self.n_utf8 > 0 && (byte & 0xc0) != 0x80 {
+            let n = core::mem::take(&mut self.n_utf8);
+            self.invalid(n, handler)?;
+        }
+
+        if self.n_utf8 == 0 && utf8_len(byte) == 0 {
+            return self.invalid(1, handler);
+        }
+
+        self.utf8[self.n_utf8] = byte;
+        self.n_utf8 += 1;
+        if self.n_utf8 < utf8_len(self.utf8[0]) {
+            return Report::Continue(self.status());
+        }
+
+        // The sequence is complete, but might still encode an overlong
+        // form, a surrogate, or a value beyond the Unicode range.
+        let n = core::mem::take(&mut self.n_utf8);
+        match core::str::from_utf8(&self.utf8[..n]).ok().and_then(|v| v.chars().next()) {
+            Some(ch) => self.advance(Some(ch), handler),
+            None => self.invalid(n, handler),
+        }
+    }
+
+    // Report `n` bytes of input as invalid UTF-8. The bytes occupy input
+    // and a single column, but are otherwise skipped.
+    fn invalid<
+        HandlerValue,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
+    >(
+        &mut self,
+        n: usize,
+        handler: &mut HandlerFn,
+    ) -> Report<HandlerValue> {
+        self.at = self.next;
+        self.next = Mark {
+            offset: self.at.offset + n,
+            column: self.at.column + 1,
+            ..self.at
+        };
+
+        if let core::ops::ControlFlow::Break(v) = handler(Token::Error(Error::Utf8Invalid), self.span_char()) {
+            self.reset();
+            return Report::Break(v);
+        }
+        self.reported = self.at;
+        Report::Continue(self.status())
+    }
+
+    /// ## Finish Input
+    ///
+    /// Push the End-Of-Input marker into the tokenizer. This finalizes the
+    /// current token, or reports it as incomplete. See `Self::push()` for
+    /// details.
+    ///
+    /// Unlike `Self::parse_str()`, this does not clear the internal buffers.
+    /// Instead, the data of the final token remains available via
+    /// `Self::buffer()`. The engine must be reset via `Self::reset()`
+    /// before it can be used again. If the handler breaks, the engine is
+    /// reset as usual.
+    pub fn finish<
+        HandlerValue,
+        HandlerFn: FnMut(Token) -> core::ops::ControlFlow<HandlerValue>,
+    >(
+        &mut self,
+        handler: &mut HandlerFn,
+    ) -> Report<HandlerValue> {
+        self.conclude(&mut |v, _| handler(v))
+    }
+
+    // Push the End-Of-Input marker, reporting the input range of the final
+    // token. This implements `Self::finish()`.
+    fn conclude<
+        HandlerValue,
+        HandlerFn: FnMut(Token, core::ops::Range<usize>) -> core::ops::ControlFlow<HandlerValue>,
+    >(
+        &mut self,
+        handler: &mut HandlerFn,
+    ) -> Report<HandlerValue> {
+        debug_assert!(!self.finished, "Tokenizer must be reset after finish");
+
+        // Bytes of an incomplete UTF-8 sequence can no longer be completed.
+        if self.n_utf8 > 0 {
+            let n = core::mem::take(&mut self.n_utf8);
+            self.invalid(n, handler)?;
+        }
+
+        self.finished = true;
+        self.advance(None, handler)
+    }
+
+    /// ## Parse a String with the Tokenizer
+    ///
+    /// Push the entire string into the tokenizer engine, followed by an
+    /// End-Of-Input marker. See `Self::push()` for details.
+    ///
+    /// Note that this does not clear the engine before pushing the string
+    /// into it. Hence, make sure to call this on a clean engine, unless
+    /// it is meant to be pushed on top of the previous input.
+    ///
+    /// This will finalize the input and thus always reset the tokenizer
+    /// before returning. Moreover, the tokenizer will always report a
+    /// status of `Status::Done` when finished.
+    pub fn parse_str<
+        HandlerValue,
+        HandlerFn: FnMut(Token) -> core::ops::ControlFlow<HandlerValue>,
+    >(
+        &mut self,
+        data: &str,
+        handler: &mut HandlerFn,
+    ) -> Report<HandlerValue> {
+        self.push_str(data, handler)?;
+        self.finish(handler)?;
+        self.reset();
+        Report::Continue(Status::Done)
+    }
+
+}
+
+// Length of the UTF-8 sequence started by the given byte, or 0 if the byte
+// cannot start a sequence.
+fn utf8_len(byte: u8) -> usize {
+    match byte {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 0,
+    }
+}
+
+/// ## Maximum Bracket Depth
+///
+/// The maximum nesting depth of brackets supported by `validate_brackets()`.
+pub const BRACKET_DEPTH: usize = 1024;
+
+// Move a tokenizer error into the lifetime of the input. Errors borrow from
+// the internal buffer of the tokenizer, but borrowed data is always a
+// suffix of the input range covered by the error.
+pub(crate) fn rebase<'ctx>(
+    error: Error,
+    data: &'ctx str,
+    range: core::ops::Range<usize>,
+) -> Error<'ctx> {
+    match error {
+        Error::CharacterInvalid(v) => Error::CharacterInvalid(v),
+        Error::CharacterStray(v) => Error::CharacterStray(v),
+        Error::WhitespaceInvalid(v) => Error::WhitespaceInvalid(v),
+        Error::KeywordUnknown(v) => Error::KeywordUnknown(&data[range.end - v.len()..range.end]),
+        Error::NumberIncomplete => Error::NumberIncomplete,
+        Error::StringIncomplete => Error::StringIncomplete,
+        Error::StringCharacterInvalid(v) => Error::StringCharacterInvalid(v),
+        Error::StringEscapeInvalid(v) => Error::StringEscapeInvalid(v),
+        Error::StringEscapeIncomplete => Error::StringEscapeIncomplete,
+        Error::StringSurrogateUnpaired => Error::StringSurrogateUnpaired,
+        Error::StringEscapeUnicode => Error::StringEscapeUnicode,
+        Error::Comment(v) => Error::Comment(&data[range.end - v.len()..range.end]),
+        Error::BracketMismatch(v) => Error::BracketMismatch(v),
+        Error::BracketStray(v) => Error::BracketStray(v),
+        Error::BracketUnclosed(v) => Error::BracketUnclosed(v),
+        Error::BracketDepth => Error::BracketDepth,
+        Error::Utf8Invalid => Error::Utf8Invalid,
+    }
+}
+
+// Bit-stack of open brackets, shared by the tokenizer and all structural
+// checks on top of it. Each bit records whether the bracket at that depth
+// opens a JSON Array (0) or a JSON Object (1). The stack has a fixed size,
+// which limits the nesting depth to `BRACKET_DEPTH`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct Brackets {
+    stack: [u8; BRACKET_DEPTH / 8],
+    depth: usize,
+}
+
+impl Brackets {
+    pub(crate) fn new() -> Self {
+        Self {
+            stack: [0; BRACKET_DEPTH / 8],
+            depth: 0,
+        }
+    }
+
+    // Check whether the bracket at the given depth opens a JSON Object.
+    fn object_at(&self, depth: usize) -> bool {
+        (self.stack[depth / 8] & (1u8 << (depth % 8))) != 0
+    }
+
+    // Record an opening bracket, unless the maximum depth is reached.
+    pub(crate) fn open(&mut self, object: bool) -> Result<(), Error<'static>> {
+        if self.depth >= BRACKET_DEPTH {
+            return Err(Error::BracketDepth);
+        }
+
+        let bit = 1u8 << (self.depth % 8);
+        if object {
+            self.stack[self.depth / 8] |= bit;
+        } else {
+            self.stack[self.depth / 8] &= !bit;
+        }
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    // Record a closing bracket, which must match the innermost open bracket.
+    pub(crate) fn close(&mut self, object: bool) -> Result<(), Error<'static>> {
+        let close = if object { '}' } else { ']' };
+
+        if self.depth == 0 {
+            return Err(Error::BracketStray(close));
+        }
+        if self.object_at(self.depth - 1) != object {
+            return Err(Error::BracketMismatch(close));
+        }
+        self.depth -= 1;
+
+        Ok(())
+    }
+
+    // Yield the innermost open bracket, if any.
+    pub(crate) fn innermost(&self) -> Option<char> {
+        match self.depth {
+            0 => None,
+            v if self.object_at(v - 1) => Some('{'),
+            _ => Some('['),
+        }
+    }
+}
+
+impl Default for Brackets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ## Validate Bracket Nesting
+///
+/// Tokenize the given string and verify that all JSON Arrays and Objects are
+/// correctly closed and nested, without building any value tree. Open
+/// brackets are tracked on a fixed-size bit-stack, which limits the nesting
+/// depth to `BRACKET_DEPTH`. Apart from the buffers of the tokenizer for
+/// strings and numbers, no memory is allocated.
+///
+/// The first error is returned together with its byte offset into the
+/// input. This includes errors reported by the tokenizer. Unclosed brackets
+/// are reported with the innermost open bracket at the end of the input.
+pub fn validate_brackets(data: &str) -> Result<(), (Error<'_>, usize)> {
+    let mut brackets = Brackets::new();
+
+    let r = Tokenizer::new().feed(
+        data,
+        Feed { finish: true, ..Default::default() },
+        &mut |token, range| {
+            let r = match token {
+                Token::Error(v) => {
+                    let offset = range.start;
+                    return core::ops::ControlFlow::Break((rebase(v, data, range), offset));
+                },
+                Token::ArrayOpen => brackets.open(false),
+                Token::ObjectOpen => brackets.open(true),
+                Token::ArrayClose => brackets.close(false),
+                Token::ObjectClose => brackets.close(true),
+                _ => Ok(()),
+            };
+
+            match r {
+                Ok(()) => core::ops::ControlFlow::Continue(()),
+                Err(v) => core::ops::ControlFlow::Break((v, range.start)),
+            }
+        },
+    );
+
+    match r {
+        Report::Break(((v, offset), _)) => Err((v, offset)),
+        Report::Continue(_) => match brackets.innermost() {
+            Some(v) => Err((Error::BracketUnclosed(v), data.len())),
+            None => Ok(()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Assert that the specified input tokenizes to the specified stream of
+    // tokens. This also verifies that the tokenizer finishes successfully.
+    fn assert_tokenize(
+        from: &str,
+        to: &alloc::vec::Vec<Token>,
+    ) {
+        let mut iter = to.iter();
 
         Tokenizer::new().parse_str(
             from,
@@ -1103,4 +2086,739 @@ mod tests {
             ],
         );
     }
+
+    // Verify that single-quoted strings are accepted with
+    // `FLAG_ALLOW_SINGLE_QUOTES`, including escapes, and that they are
+    // rejected by default.
+    #[test]
+    fn token_single_quotes() {
+        let tokenize = |flags: Flag, data: &str| {
+            let mut tokens = alloc::vec::Vec::new();
+            assert!(Tokenizer::with(flags).parse_str(
+                data,
+                &mut |v| -> core::ops::ControlFlow<()> {
+                    tokens.push(alloc::format!("{:?}", v));
+                    core::ops::ControlFlow::Continue(())
+                },
+            ).is_continue());
+            tokens
+        };
+
+        assert_eq!(
+            tokenize(FLAG_ALLOW_SINGLE_QUOTES, r#"'foo'"#),
+            [r#"String("foo", "foo")"#],
+        );
+        assert_eq!(
+            tokenize(FLAG_ALLOW_SINGLE_QUOTES, r#"'it\'s "a"\n\u0041'"#),
+            [r#"String("it\\'s \"a\"\\n\\u0041", "it's \"a\"\nA")"#],
+        );
+        assert_eq!(
+            tokenize(FLAG_ALLOW_SINGLE_QUOTES, r#"["it's",'b']"#),
+            [
+                "ArrayOpen", r#"String("it's", "it's")"#, "Comma",
+                r#"String("b", "b")"#, "ArrayClose",
+            ],
+        );
+
+        // Strings must be closed by the quote that opened them.
+        assert_eq!(
+            tokenize(FLAG_ALLOW_SINGLE_QUOTES, r#"'foo""#),
+            ["Error(StringIncomplete)"],
+        );
+        assert_eq!(
+            tokenize(FLAG_ALLOW_SINGLE_QUOTES, r#""foo'"#),
+            ["Error(StringIncomplete)"],
+        );
+
+        // Single quotes cannot be escaped in double-quoted strings.
+        assert_eq!(
+            tokenize(FLAG_ALLOW_SINGLE_QUOTES, r#""\'""#),
+            ["Error(StringEscapeInvalid('\\''))", r#"String("\\'", "'")"#],
+        );
+
+        // Single quotes are rejected by default.
+        assert_eq!(
+            tokenize(0, r#"'foo'"#)[0],
+            "Error(CharacterInvalid('\\''))",
+        );
+    }
+
+    // Verify that identifiers are accepted as keys of JSON Object members
+    // with `FLAG_ALLOW_UNQUOTED_KEYS`, but nowhere else, and that they are
+    // rejected by default.
+    #[test]
+    fn token_unquoted_keys() {
+        let tokenize = |flags: Flag, data: &str| {
+            let mut tokens = alloc::vec::Vec::new();
+            assert!(Tokenizer::with(flags).parse_str(
+                data,
+                &mut |v| -> core::ops::ControlFlow<()> {
+                    match v {
+                        Token::Whitespace(_) => {},
+                        Token::Number(v, ..) => tokens.push(alloc::format!("Number({})", v)),
+                        v => tokens.push(alloc::format!("{:?}", v)),
+                    }
+                    core::ops::ControlFlow::Continue(())
+                },
+            ).is_continue());
+            tokens
+        };
+
+        assert_eq!(
+            tokenize(FLAG_ALLOW_UNQUOTED_KEYS, "{foo: 1}"),
+            ["ObjectOpen", "String(\"foo\", \"foo\")", "Colon", "Number(1)", "ObjectClose"],
+        );
+        assert_eq!(
+            tokenize(0, "{foo: 1}"),
+            ["ObjectOpen", "Error(KeywordUnknown(\"foo\"))", "Colon", "Number(1)", "ObjectClose"],
+        );
+
+        // Only key positions are affected, including those of nested
+        // objects and those following array values.
+        assert_eq!(
+            tokenize(FLAG_ALLOW_UNQUOTED_KEYS, "{a: [b, {c: true}], \"d\": x, null: e}"),
+            [
+                "ObjectOpen", "String(\"a\", \"a\")", "Colon",
+                "ArrayOpen", "Error(KeywordUnknown(\"b\"))", "Comma",
+                "ObjectOpen", "String(\"c\", \"c\")", "Colon", "True", "ObjectClose",
+                "ArrayClose", "Comma",
+                "String(\"d\", \"d\")", "Colon", "Error(KeywordUnknown(\"x\"))", "Comma",
+                "String(\"null\", \"null\")", "Colon", "Error(KeywordUnknown(\"e\"))",
+                "ObjectClose",
+            ],
+        );
+        assert_eq!(
+            tokenize(FLAG_ALLOW_UNQUOTED_KEYS, "[foo]"),
+            ["ArrayOpen", "Error(KeywordUnknown(\"foo\"))", "ArrayClose"],
+        );
+    }
+
+    // Verify the boundaries of number tokens. Numbers end at the first
+    // character that cannot continue them, and numbers with missing
+    // components are reported as incomplete before the next token.
+    #[test]
+    fn token_number_boundaries() {
+        let tokenize = |data: &str| {
+            let mut tokens = alloc::vec::Vec::new();
+            assert!(Tokenizer::new().parse_str(
+                data,
+                &mut |v| -> core::ops::ControlFlow<()> {
+                    match v {
+                        Token::Whitespace(_) => tokens.push("Whitespace".into()),
+                        Token::Number(v, ..) => tokens.push(alloc::format!("Number({})", v)),
+                        v => tokens.push(alloc::format!("{:?}", v)),
+                    }
+                    core::ops::ControlFlow::Continue(())
+                },
+            ).is_continue());
+            tokens
+        };
+
+        assert_eq!(tokenize("12"), ["Number(12)"]);
+        assert_eq!(tokenize("1 2"), ["Number(1)", "Whitespace", "Number(2)"]);
+        assert_eq!(tokenize("1-2"), ["Number(1)", "Number(-2)"]);
+        assert_eq!(tokenize("01"), ["Number(0)", "Number(1)"]);
+        assert_eq!(tokenize("1.5e-3"), ["Number(1.5e-3)"]);
+        assert_eq!(
+            tokenize("1.2.3"),
+            ["Number(1.2)", "Error(CharacterInvalid('.'))", "Number(3)"],
+        );
+
+        // Missing components at the end of the input.
+        assert_eq!(tokenize("-"), ["Error(NumberIncomplete)"]);
+        assert_eq!(tokenize("1."), ["Error(NumberIncomplete)"]);
+        assert_eq!(tokenize("1e"), ["Error(NumberIncomplete)"]);
+        assert_eq!(tokenize("1e+"), ["Error(NumberIncomplete)"]);
+
+        // Missing components before other tokens, which are retained.
+        assert_eq!(tokenize("[1e]"), ["ArrayOpen", "Error(NumberIncomplete)", "ArrayClose"]);
+        assert_eq!(tokenize("1.,2"), ["Error(NumberIncomplete)", "Comma", "Number(2)"]);
+        assert_eq!(tokenize("- 1"), ["Error(NumberIncomplete)", "Whitespace", "Number(1)"]);
+        assert_eq!(tokenize("--1"), ["Error(NumberIncomplete)", "Number(-1)"]);
+
+        // Words continuing incomplete numbers are part of the error.
+        assert_eq!(tokenize("1e+x"), ["Error(NumberIncomplete)"]);
+        assert_eq!(tokenize("[1.e5]"), ["ArrayOpen", "Error(NumberIncomplete)", "ArrayClose"]);
+    }
+
+    // Verify truncated literals yield a single error covering the entire
+    // literal, both standalone and followed by other tokens.
+    #[test]
+    fn token_truncated_literals() {
+        let tokenize = |data: &str| {
+            let mut tokens = alloc::vec::Vec::new();
+            assert!(Tokenizer::new().feed(
+                data,
+                Feed { finish: true, ..Default::default() },
+                &mut |v, range| -> core::ops::ControlFlow<()> {
+                    tokens.push((alloc::format!("{:?}", v), range));
+                    core::ops::ControlFlow::Continue(())
+                },
+            ).is_continue());
+            tokens
+        };
+        let keyword = |v: &str| alloc::format!("{:?}", Token::Error(Error::KeywordUnknown(v)));
+        let number = alloc::format!("{:?}", Token::Error(Error::NumberIncomplete));
+        let string = alloc::format!("{:?}", Token::Error(Error::StringIncomplete));
+
+        assert_eq!(tokenize(""), []);
+
+        for v in ["n", "nu", "nul", "t", "tr", "tru", "f", "fa", "fal", "fals", "nulll", "truex"] {
+            assert_eq!(tokenize(v), [(keyword(v), 0..v.len())], "{}", v);
+        }
+
+        for v in ["-", "1.", "1e", "1E", "1e+", "1e-", "-.", "-.5", "-a", "-x1", "1.e5", "1ex", "-Infinity"] {
+            assert_eq!(tokenize(v), [(number.clone(), 0..v.len())], "{}", v);
+        }
+
+        assert_eq!(tokenize("\""), [(string.clone(), 0..1)]);
+        assert_eq!(tokenize("\"a"), [(string.clone(), 0..2)]);
+        assert_eq!(tokenize("\"\\"), [(string.clone(), 0..2)]);
+
+        // Truncated literals followed by delimiters keep the delimiters.
+        assert_eq!(
+            tokenize("-.5,1"),
+            [
+                (number.clone(), 0..3),
+                (alloc::format!("{:?}", Token::Comma), 3..4),
+                (alloc::format!("{:?}", Token::Number("1", &[1], Sign::Plus, 1, 0, Sign::Plus, 0)), 4..5),
+            ],
+        );
+        assert_eq!(
+            tokenize("[tru]"),
+            [
+                (alloc::format!("{:?}", Token::ArrayOpen), 0..1),
+                (keyword("tru"), 1..4),
+                (alloc::format!("{:?}", Token::ArrayClose), 4..5),
+            ],
+        );
+
+        // Structural validation reports the truncated literal only.
+        assert_eq!(validate_brackets("nul"), Err((Error::KeywordUnknown("nul"), 0)));
+        assert_eq!(validate_brackets("[-x]"), Err((Error::NumberIncomplete, 1)));
+        assert_eq!(validate_brackets("{\"a\": fals}"), Err((Error::KeywordUnknown("fals"), 6)));
+    }
+
+    // Verify that the digit counts of number tokens sum up to the number of
+    // digit values, and that the digit values match the lexical form.
+    #[test]
+    fn token_number_counts() {
+        let inputs = [
+            "0", "-0", "7", "123", "-123", "0.5", "0.000", "10.01",
+            "1e5", "1E+05", "1e-0", "0e0", "-0.0e-00", "12.345e678",
+            "1-2", "01", "[1,20,300]", "{\"a\":9.99E+999}",
+        ];
+
+        for input in inputs {
+            let mut n = 0;
+            assert!(Tokenizer::new().parse_str(input, &mut |token| {
+                if let Token::Number(lex, digits, _, n_int, n_frac, _, n_exp) = token {
+                    assert_eq!(n_int + n_frac + n_exp, digits.len(), "{}", lex);
+                    assert!(n_int > 0);
+                    let lex_digits: alloc::vec::Vec<u8> = lex.bytes()
+                        .filter(|v| v.is_ascii_digit())
+                        .map(|v| v - b'0')
+                        .collect();
+                    assert_eq!(digits, lex_digits.as_slice());
+                    n += 1;
+                }
+                core::ops::ControlFlow::<()>::Continue(())
+            }).is_continue());
+            assert!(n > 0);
+        }
+    }
+
+    // Verify numeric conversions of number tokens, including negative zero,
+    // exponents, and the boundaries of the target types.
+    #[test]
+    fn token_number_conversions() {
+        fn number(input: &str, f: impl FnOnce(Number)) {
+            let mut f = Some(f);
+            assert!(Tokenizer::new().parse_str(input, &mut |token| {
+                if let Some(v) = Number::from_token(&token) {
+                    (f.take().unwrap())(v);
+                }
+                core::ops::ControlFlow::<()>::Continue(())
+            }).is_continue());
+            assert!(f.is_none(), "{}", input);
+        }
+
+        let int = |input: &str, i: Result<i64, NumberError>, u: Result<u64, NumberError>| {
+            number(input, |v| {
+                assert_eq!(v.to_i64(), i, "{}", input);
+                assert_eq!(v.to_u64(), u, "{}", input);
+            });
+        };
+
+        int("0", Ok(0), Ok(0));
+        int("-0", Ok(0), Ok(0));
+        int("-0.0e-5", Ok(0), Ok(0));
+        int("0e99999999999999999999", Ok(0), Ok(0));
+        int("42", Ok(42), Ok(42));
+        int("-42", Ok(-42), Err(NumberError::Overflow));
+        int("1e3", Ok(1000), Ok(1000));
+        int("1E+3", Ok(1000), Ok(1000));
+        int("1.50e1", Ok(15), Ok(15));
+        int("1500e-2", Ok(15), Ok(15));
+        int("1.5", Err(NumberError::Inexact), Err(NumberError::Inexact));
+        int("15e-1", Err(NumberError::Inexact), Err(NumberError::Inexact));
+        int("1e-99999999999999999999", Err(NumberError::Inexact), Err(NumberError::Inexact));
+        int("1e99999999999999999999", Err(NumberError::Overflow), Err(NumberError::Overflow));
+        int("9223372036854775807", Ok(i64::MAX), Ok(i64::MAX as u64));
+        int("9223372036854775808", Err(NumberError::Overflow), Ok(1 << 63));
+        int("-9223372036854775808", Ok(i64::MIN), Err(NumberError::Overflow));
+        int("-9223372036854775809", Err(NumberError::Overflow), Err(NumberError::Overflow));
+        int("18446744073709551615", Err(NumberError::Overflow), Ok(u64::MAX));
+        int("18446744073709551616", Err(NumberError::Overflow), Err(NumberError::Overflow));
+        int("1.8446744073709551615e19", Err(NumberError::Overflow), Ok(u64::MAX));
+
+        let float = |input: &str, f: Result<f64, NumberError>| {
+            number(input, |v| assert_eq!(v.to_f64(), f, "{}", input));
+        };
+
+        float("0", Ok(0.0));
+        float("1.5", Ok(1.5));
+        float("-2.5e-3", Ok(-0.0025));
+        float("1e3", Ok(1000.0));
+        float("1e-400", Ok(0.0));
+        float("1e400", Err(NumberError::Overflow));
+        number("-0", |v| assert!(v.to_f64().unwrap().is_sign_negative()));
+
+        assert!(Number::from_token(&Token::Null).is_none());
+    }
+
+    // Verify that runs of invalid whitespace are reported once per run, are
+    // not accumulated, and do not affect subsequent tokens.
+    #[test]
+    fn token_whitespace_invalid() {
+        let mut tokens = alloc::vec::Vec::new();
+        let mut tokenizer = Tokenizer::new();
+        let mut handler = |v: Token| -> core::ops::ControlFlow<()> {
+            tokens.push(alloc::format!("{:?}", v));
+            core::ops::ControlFlow::Continue(())
+        };
+
+        let run = "\u{2003}".repeat(100_000);
+        assert_eq!(tokenizer.push_str(&run, &mut handler), Report::Continue(Status::Busy));
+        assert_eq!(tokenizer.buffer(), "");
+        assert_eq!(tokenizer.push_str("null", &mut handler), Report::Continue(Status::Busy));
+        assert_eq!(tokenizer.finish(&mut handler), Report::Continue(Status::Done));
+        assert_eq!(tokens, ["Error(WhitespaceInvalid('\\u{2003}'))", "Null"]);
+
+        tokens.clear();
+        let r = Tokenizer::new().parse_str(
+            " \u{2003}\u{a0}\t\u{2003}[]",
+            &mut |v| -> core::ops::ControlFlow<()> {
+                tokens.push(alloc::format!("{:?}", v));
+                core::ops::ControlFlow::Continue(())
+            },
+        );
+        assert_eq!(r, Report::Continue(Status::Done));
+        assert_eq!(
+            tokens,
+            [
+                "Error(WhitespaceInvalid('\\u{2003}'))",
+                "Error(WhitespaceInvalid('\\u{2003}'))",
+                "Whitespace(\" \\t\")",
+                "ArrayOpen",
+                "ArrayClose",
+            ],
+        );
+    }
+
+    // Verify that data ending in any of the surrogate states reports the
+    // pending lead-surrogate as unpaired, followed by the incomplete string.
+    #[test]
+    fn token_string_surrogate_eof() {
+        for v in ["\"\\uD834", "\"\\uD834\\", "\"\\uD834\\u", "\"\\uD834\\uDD"] {
+            assert_tokenize(
+                v,
+                &alloc::vec![
+                    Token::Error(Error::StringSurrogateUnpaired),
+                    Token::Error(Error::StringIncomplete),
+                ],
+            );
+        }
+
+        // Without a pending lead-surrogate, only the string is incomplete.
+        for v in ["\"\\uD8", "\"\\u", "\"\\"] {
+            assert_tokenize(
+                v,
+                &alloc::vec![Token::Error(Error::StringIncomplete)],
+            );
+        }
+
+        // A complete pair without the closing quote is just incomplete.
+        assert_tokenize(
+            "\"\\uD834\\uDD1E",
+            &alloc::vec![Token::Error(Error::StringIncomplete)],
+        );
+    }
+
+    // Verify that breaking handlers report the offset that was reached.
+    #[test]
+    fn token_break_offset() {
+        let parse = || Feed { finish: true, ..Default::default() };
+
+        let mut tokens = alloc::vec::Vec::new();
+        let r = Tokenizer::new().feed(
+            "[1, 2]",
+            parse(),
+            &mut |v, _| -> core::ops::ControlFlow<()> {
+                tokens.push(alloc::format!("{:?}", v));
+                core::ops::ControlFlow::Break(())
+            },
+        );
+        assert_eq!(r, Report::Break(((), 0)));
+        assert_eq!(tokens.len(), 1);
+
+        // The number token is only produced when the comma terminates it,
+        // so the comma is the character reported as offset.
+        let mut n = 0;
+        let r = Tokenizer::new().feed(
+            "[1, 2]",
+            parse(),
+            &mut |v, _| -> core::ops::ControlFlow<usize> {
+                n += 1;
+                match v {
+                    Token::Number(..) => core::ops::ControlFlow::Break(n),
+                    _ => core::ops::ControlFlow::Continue(()),
+                }
+            },
+        );
+        assert_eq!(r, Report::Break((2, 2)));
+
+        // Breaking on the End-Of-Input marker reports the input length.
+        let r = Tokenizer::new().feed(
+            "null",
+            parse(),
+            &mut |_, _| -> core::ops::ControlFlow<()> {
+                core::ops::ControlFlow::Break(())
+            },
+        );
+        assert_eq!(r, Report::Break(((), 4)));
+
+        // Without any break, the input is fully consumed, and only finished
+        // if requested.
+        let r = Tokenizer::new().feed(
+            "null",
+            Feed::default(),
+            &mut |_, _| -> core::ops::ControlFlow<()> {
+                core::ops::ControlFlow::Continue(())
+            },
+        );
+        assert_eq!(r, Report::Continue(Status::Busy));
+
+        // The plain entry points report the break value only.
+        let r = Tokenizer::new().parse_str(
+            "null",
+            &mut |_| -> core::ops::ControlFlow<()> {
+                core::ops::ControlFlow::Break(())
+            },
+        );
+        assert_eq!(r, Report::Break(()));
+    }
+
+    // Verify that finishing the engine reports the incomplete token, but
+    // retains its data until the engine is reset.
+    #[test]
+    fn token_finish() {
+        let mut tokens = alloc::vec::Vec::new();
+        let mut tokenizer = Tokenizer::new();
+        let mut handler = |v: Token| -> core::ops::ControlFlow<()> {
+            tokens.push(alloc::format!("{:?}", v));
+            core::ops::ControlFlow::Continue(())
+        };
+
+        assert_eq!(
+            tokenizer.push_str("[\"foo\\n", &mut handler),
+            Report::Continue(Status::Busy),
+        );
+        assert_eq!(tokenizer.buffer(), "foo\\n");
+        assert_eq!(tokenizer.finish(&mut handler), Report::Continue(Status::Done));
+        assert_eq!(tokenizer.buffer(), "foo\\n");
+
+        tokenizer.reset();
+        assert_eq!(tokenizer.buffer(), "");
+        assert_eq!(tokenizer.status(), Status::Done);
+        assert_eq!(tokenizer, Tokenizer::new());
+
+        assert_eq!(
+            tokens,
+            alloc::vec!["ArrayOpen", "Error(StringIncomplete)"],
+        );
+    }
+
+    // Verify progress is reported once per full interval of characters,
+    // with byte offsets at character boundaries.
+    #[test]
+    fn token_progress() {
+        let mut offsets = alloc::vec::Vec::new();
+        let mut tokens = 0;
+
+        let r = Tokenizer::new().feed(
+            "[\"ää\", 10, 20]",
+            Feed {
+                finish: true,
+                progress: Some((&mut |v| offsets.push(v), core::num::NonZeroUsize::new(4).unwrap())),
+            },
+            &mut |_, _| -> core::ops::ControlFlow<()> {
+                tokens += 1;
+                core::ops::ControlFlow::Continue(())
+            },
+        );
+        assert_eq!(r, Report::Continue(Status::Done));
+        assert_eq!(tokens, 9);
+        assert_eq!(offsets, [6, 10, 14]);
+
+        // Byte input counts bytes rather than characters.
+        offsets.clear();
+        let r = Tokenizer::new().feed(
+            "[\"ää\"]".as_bytes(),
+            Feed {
+                finish: false,
+                progress: Some((&mut |v| offsets.push(v), core::num::NonZeroUsize::new(3).unwrap())),
+            },
+            &mut |_, _| -> core::ops::ControlFlow<()> {
+                core::ops::ControlFlow::Continue(())
+            },
+        );
+        assert_eq!(r, Report::Continue(Status::Done));
+        assert_eq!(offsets, [3, 6]);
+    }
+    // Verify tokens are reported with the input ranges they were parsed
+    // from, including quotes, multi-byte characters, and tokens that are
+    // finalized by the following character or the end of input.
+    #[test]
+    fn token_located() {
+        let input = "{\"ä\": [-1.5e2, tru],\n\"b\" :null} #x";
+        let mut tokens = alloc::vec::Vec::new();
+
+        let r = Tokenizer::new().feed(
+            input,
+            Feed { finish: true, ..Default::default() },
+            &mut |v, range| -> core::ops::ControlFlow<()> {
+                tokens.push((alloc::format!("{:?}", v), range));
+                core::ops::ControlFlow::Continue(())
+            },
+        );
+        assert_eq!(r, Report::Continue(Status::Done));
+
+        let spans: alloc::vec::Vec<&str> = tokens.iter()
+            .map(|(_, v)| &input[v.clone()])
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                "{", "\"ä\"", ":", " ", "[", "-1.5e2", ",", " ", "tru", "]",
+                ",", "\n", "\"b\"", " ", ":", "null", "}", " ", "#x",
+            ],
+        );
+        assert_eq!(tokens[1], (alloc::format!("{:?}", Token::String("ä", "ä")), 1..5));
+        assert_eq!(tokens[8].0, "Error(KeywordUnknown(\"tru\"))");
+        assert_eq!(tokens[18], (alloc::format!("{:?}", Token::Error(Error::Comment("x"))), 33..35));
+
+        // Errors raised within a token cover the offending character only.
+        let mut tokens = alloc::vec::Vec::new();
+        assert!(Tokenizer::new().feed(
+            "\"a\\qb\"",
+            Feed { finish: true, ..Default::default() },
+            &mut |v, range| -> core::ops::ControlFlow<()> {
+                tokens.push((alloc::format!("{:?}", v), range));
+                core::ops::ControlFlow::Continue(())
+            },
+        ).is_continue());
+        assert_eq!(
+            tokens,
+            [
+                (alloc::format!("{:?}", Token::Error(Error::StringEscapeInvalid('q'))), 3..4),
+                (alloc::format!("{:?}", Token::String("a\\qb", "aqb")), 0..6),
+            ],
+        );
+    }
+
+    // Verify that the raw data of numbers and strings is a byte-for-byte
+    // copy of the input without any normalization, so formatters can
+    // reproduce the input faithfully. The raw data of strings excludes the
+    // quotes, and everything else of the reported range is raw data.
+    #[test]
+    fn token_raw_fidelity() {
+        let input = concat!(
+            r#"["\uD834\uDD1E", "\ud834\udd1e", "\u00E9\u00e9\/\"", "a\u0000b", "ä\\"],"#,
+            "\n",
+            r#"[1E+05, 1e5, 1.00, -0.0e-0, 0, 10E-010]"#,
+        );
+        let mut raw = alloc::vec::Vec::new();
+
+        let r = Tokenizer::new().feed(
+            input,
+            Feed { finish: true, ..Default::default() },
+            &mut |v, range| -> core::ops::ControlFlow<()> {
+                match v {
+                    Token::Number(v, ..) => raw.push((alloc::string::String::from(v), &input[range])),
+                    Token::String(v, _) => {
+                        raw.push((alloc::string::String::from(v), &input[(range.start + 1)..(range.end - 1)]));
+                    },
+                    Token::Error(e) => panic!("Unexpected error: {:?}", e),
+                    _ => {},
+                }
+                core::ops::ControlFlow::Continue(())
+            },
+        );
+        assert_eq!(r, Report::Continue(Status::Done));
+
+        for (v, span) in raw.iter() {
+            assert_eq!(v, span);
+        }
+        assert_eq!(
+            raw.iter().map(|v| v.0.as_str()).collect::<alloc::vec::Vec<_>>(),
+            [
+                r#"\uD834\uDD1E"#, r#"\ud834\udd1e"#, r#"\u00E9\u00e9\/\""#, r#"a\u0000b"#, r#"ä\\"#,
+                "1E+05", "1e5", "1.00", "-0.0e-0", "0", "10E-010",
+            ],
+        );
+    }
+
+    // Verify bracket validation for balanced, mismatched, and unclosed
+    // input, as well as stray closing brackets and tokenizer errors.
+    #[test]
+    fn bracket_validation() {
+        assert_eq!(validate_brackets(""), Ok(()));
+        assert_eq!(validate_brackets("[]"), Ok(()));
+        assert_eq!(validate_brackets("{\"a\": [1, {\"b\": []}], \"c\": \"]}\"}"), Ok(()));
+
+        assert_eq!(validate_brackets("[}"), Err((Error::BracketMismatch('}'), 1)));
+        assert_eq!(validate_brackets("{\"a\": [1}"), Err((Error::BracketMismatch('}'), 8)));
+        assert_eq!(validate_brackets("[]]"), Err((Error::BracketStray(']'), 2)));
+
+        assert_eq!(validate_brackets("[{}"), Err((Error::BracketUnclosed('['), 3)));
+        assert_eq!(validate_brackets("[{"), Err((Error::BracketUnclosed('{'), 2)));
+
+        assert_eq!(validate_brackets("[@]"), Err((Error::CharacterInvalid('@'), 1)));
+        assert_eq!(validate_brackets("[nul]"), Err((Error::KeywordUnknown("nul"), 1)));
+        assert_eq!(validate_brackets("[] // x"), Err((Error::Comment("// x"), 3)));
+
+        let deep = "[".repeat(BRACKET_DEPTH) + &"]".repeat(BRACKET_DEPTH);
+        assert_eq!(validate_brackets(&deep), Ok(()));
+        let deep = "[".repeat(BRACKET_DEPTH + 1);
+        assert_eq!(validate_brackets(&deep), Err((Error::BracketDepth, BRACKET_DEPTH)));
+    }
+
+    // Verify line and column tracking of errors across multiple pushes, that
+    // errors replacing a token are located at its start, and that resetting
+    // the engine clears the position.
+    #[test]
+    fn token_position() {
+        fn push(
+            tokenizer: &mut Tokenizer,
+            data: &str,
+            errors: &mut alloc::vec::Vec<ErrorContext<'static>>,
+        ) {
+            for ch in data.chars() {
+                let mut error = None;
+                assert!(tokenizer.push(Some(ch), &mut |v| {
+                    if let Token::Error(Error::CharacterInvalid(v)) = v {
+                        error = Some(Error::CharacterInvalid(v));
+                    }
+                    core::ops::ControlFlow::<()>::Continue(())
+                }).is_continue());
+                if let Some(v) = error {
+                    errors.push(tokenizer.context(v));
+                }
+            }
+        }
+
+        let mut tokenizer = Tokenizer::new();
+        let mut errors = alloc::vec::Vec::new();
+        assert_eq!(tokenizer.position(), (0, 0));
+
+        push(&mut tokenizer, "{\"a\"=1,\n", &mut errors);
+        assert_eq!(tokenizer.position(), (0, 6));
+        push(&mut tokenizer, "\"b\" = 2}\n=", &mut errors);
+        assert_eq!(tokenizer.position(), (2, 0));
+        assert_eq!(
+            errors,
+            [
+                ErrorContext { error: Error::CharacterInvalid('='), line: 0, column: 4 },
+                ErrorContext { error: Error::CharacterInvalid('='), line: 1, column: 4 },
+                ErrorContext { error: Error::CharacterInvalid('='), line: 2, column: 0 },
+            ],
+        );
+        assert_eq!(
+            alloc::format!("{}", errors[1]),
+            "Invalid JSON at line 2, column 5: Invalid character '='",
+        );
+
+        // Columns count characters, not bytes.
+        tokenizer.reset();
+        errors.clear();
+        assert_eq!(tokenizer.position(), (0, 0));
+        push(&mut tokenizer, "\"\u{e9}\"=", &mut errors);
+        assert_eq!(
+            errors,
+            [ErrorContext { error: Error::CharacterInvalid('='), line: 0, column: 3 }],
+        );
+
+        // Errors replacing a token are located at the start of the token,
+        // rather than at the character that finalized it.
+        tokenizer.reset();
+        let mut unknown = false;
+        assert!(tokenizer.push_str("[\n  nul ", &mut |v| {
+            unknown |= matches!(v, Token::Error(Error::KeywordUnknown("nul")));
+            core::ops::ControlFlow::<()>::Continue(())
+        }).is_continue());
+        assert!(unknown);
+        assert_eq!(tokenizer.position(), (1, 2));
+        assert_eq!(
+            tokenizer.context(Error::KeywordUnknown("nul")),
+            ErrorContext { error: Error::KeywordUnknown("nul"), line: 1, column: 2 },
+        );
+    }
+
+    // Verify that byte input decodes characters split across chunks, and
+    // reports invalid and truncated UTF-8 sequences.
+    #[test]
+    fn token_push_bytes() {
+        let mut tokenizer = Tokenizer::new();
+        let mut tokens = alloc::vec::Vec::new();
+        let mut handler = |token: Token, range: core::ops::Range<usize>| {
+            tokens.push((alloc::format!("{:?}", token), range));
+            core::ops::ControlFlow::<()>::Continue(())
+        };
+
+        for chunk in [&b"[\"\xc3"[..], b"\xa9\"", b",\xff,", b"\xe2\x82", b"1]", b"\xf0\x9f"] {
+            assert!(tokenizer.feed(chunk, Feed::default(), &mut handler).is_continue());
+        }
+        assert_eq!(
+            tokenizer.feed(&b""[..], Feed { finish: true, ..Default::default() }, &mut handler),
+            Report::Continue(Status::Done),
+        );
+
+        let invalid = alloc::format!("{:?}", Token::Error(Error::Utf8Invalid));
+        assert_eq!(
+            tokens,
+            [
+                (alloc::format!("{:?}", Token::ArrayOpen), 0..1),
+                (alloc::format!("{:?}", Token::String("\u{e9}", "\u{e9}")), 1..5),
+                (alloc::format!("{:?}", Token::Comma), 5..6),
+                (invalid.clone(), 6..7),
+                (alloc::format!("{:?}", Token::Comma), 7..8),
+                (invalid.clone(), 8..10),
+                (alloc::format!("{:?}", Token::Number("1", &[1], Sign::Plus, 1, 0, Sign::Plus, 0)), 10..11),
+                (alloc::format!("{:?}", Token::ArrayClose), 11..12),
+                (invalid.clone(), 12..14),
+            ],
+        );
+
+        // Overlong forms and surrogates are rejected as a whole.
+        tokenizer.reset();
+        let mut n = 0;
+        let r = tokenizer.feed(&b"\xc0\xaf\xed\xa0\x80"[..], Feed::default(), &mut |v, _| {
+            assert_eq!(v, Token::Error(Error::Utf8Invalid));
+            n += 1;
+            core::ops::ControlFlow::<()>::Continue(())
+        });
+        assert_eq!(r, Report::Continue(Status::Done));
+        assert_eq!(n, 3);
+    }
 }
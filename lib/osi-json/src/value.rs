@@ -0,0 +1,1519 @@
+//! # JSON Values
+//!
+//! This module provides an in-memory representation of JSON Values. Unlike
+//! the streaming tokenizer, this keeps an entire JSON document in memory and
+//! allows inspecting and modifying it. Documents are parsed via `parse()`,
+//! which builds the value tree from the tokens of the streaming tokenizer.
+//!
+//! JSON Numbers are stored in their lexical form, to retain full precision
+//! and allow faithful reproduction of the input. JSON Objects are stored as
+//! lists of key-value pairs in document order.
+
+/// ## JSON Values
+///
+/// This enum represents a single JSON Value with all its child values.
+///
+/// Values are dropped without recursion, so arbitrarily deep values can be
+/// released safely. Comparing, hashing, cloning, and serializing values
+/// recurses, though, and is thus only safe for values nested no deeper than
+/// `token::BRACKET_DEPTH`. `parse()` never produces deeper values.
+///
+/// The non-recursive drop requires `Value` to implement `Drop`, which in turn
+/// prevents moving contents out of a value via destructuring. Match on a
+/// mutable reference instead, and take the contents via `core::mem::take()`
+/// (all variant payloads implement `Default`). This was deemed a reasonable
+/// price for values that are safe to drop regardless of their depth.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Value {
+    /// JSON Null value.
+    Null,
+    /// JSON Boolean value.
+    Bool(bool),
+    /// JSON Number value in its lexical form.
+    Number(alloc::string::String),
+    /// JSON String value.
+    String(alloc::string::String),
+    /// JSON Array value with all its elements.
+    Array(alloc::vec::Vec<Value>),
+    /// JSON Object value with all its members in document order.
+    Object(alloc::vec::Vec<(alloc::string::String, Value)>),
+}
+
+/// ## Serde Conversion Errors
+///
+/// This enum represents the errors raised when converting a JSON Value into
+/// its `serde_json` representation. Each error carries the offending JSON
+/// Number in its lexical form.
+#[cfg(feature = "serde_json")]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SerdeError {
+    /// JSON Number is not in valid lexical form.
+    NumberInvalid(alloc::string::String),
+    /// JSON Number exceeds the finite range of double-precision floats.
+    NumberNonFinite(alloc::string::String),
+}
+
+/// ## Form Encoding Errors
+///
+/// This enum represents the errors raised when encoding a JSON Value as
+/// `application/x-www-form-urlencoded` via `to_form_urlencoded()`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum FormError {
+    /// JSON Value is not a JSON Object.
+    NotObject,
+    /// Member with the given key is a JSON Array or JSON Object, which
+    /// cannot be represented in a flat form.
+    Nested(alloc::string::String),
+}
+
+/// ## Parse Error Kinds
+///
+/// This enum represents the kinds of errors raised by `parse()`. Apart from
+/// errors of the tokenizer, these are violations of the JSON grammar, named
+/// after what was expected at the position of the offending token.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ParseErrorKind<'ctx> {
+    /// Tokenizer reported the given error. This includes all bracket
+    /// errors.
+    Token(crate::token::Error<'ctx>),
+    /// Expected a JSON Value (e.g., after a comma or colon, or as input).
+    ValueExpected,
+    /// Expected a JSON String as key of an object member.
+    KeyExpected,
+    /// Expected a colon after the key of an object member.
+    ColonExpected,
+    /// Expected a comma or closing bracket after an element or member.
+    CommaExpected,
+    /// Input continues after the top-level JSON Value.
+    TrailingData,
+    /// Key of an object member was already used in the same object. This
+    /// carries the key as given in the input, without quotes.
+    KeyDuplicate(&'ctx str),
+}
+
+/// ## Parse Errors
+///
+/// This represents the first error encountered by `parse()`, together with
+/// the byte offset into the input where it was encountered.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ParseError<'ctx> {
+    pub kind: ParseErrorKind<'ctx>,
+    pub offset: usize,
+}
+
+/// ## Object Entries
+///
+/// This represents a single member of a JSON Object, which might either be
+/// present or absent. It is acquired via `Value::entry()` and mirrors the
+/// entry API of `BTreeMap`.
+#[derive(Debug)]
+pub enum Entry<'value, 'key> {
+    /// Member is present with the given value.
+    Occupied(&'value mut Value),
+    /// Member is absent from the given member list.
+    Vacant(&'value mut alloc::vec::Vec<(alloc::string::String, Value)>, &'key str),
+}
+
+/// ## Value Changes
+///
+/// This enum represents a single change between two JSON Values, as produced
+/// by `Value::diff()`. Each change carries the location it applies to as
+/// JSON Pointer (RFC 6901), relative to the root of the compared values.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Change {
+    /// Value was added at the given location.
+    Added(alloc::string::String, Value),
+    /// Value was removed from the given location.
+    Removed(alloc::string::String),
+    /// Value at the given location was replaced, carrying the old and new
+    /// value.
+    Replaced(alloc::string::String, Value, Value),
+}
+
+/// ## Number Formats
+///
+/// This enum selects how JSON Numbers are written by `Writer`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum NumberFormat {
+    /// Write numbers in their lexical form, unchanged.
+    #[default]
+    Verbatim,
+    /// Write numbers in the canonical form of their double-precision
+    /// value (e.g., `1.0` as `1` and `1e2` as `100`). Note that this rounds
+    /// numbers that cannot be represented exactly. Numbers that are not
+    /// valid or exceed the finite range are written verbatim.
+    Normalized,
+}
+
+/// ## JSON Writer
+///
+/// The writer serializes JSON Values with configurable layout and number
+/// formatting. The default configuration produces compact JSON, equivalent
+/// to the `Display` implementation of `Value`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Writer {
+    /// Number of spaces to indent nested values with, or `None` to write
+    /// compact JSON on a single line.
+    pub indent: Option<usize>,
+    /// Format to write JSON Numbers in.
+    pub numbers: NumberFormat,
+}
+
+// Append a reference token to a JSON Pointer, escaping `~` and `/` as
+// required by RFC 6901.
+fn pointer_push(path: &str, token: &str) -> alloc::string::String {
+    let mut v = alloc::string::String::with_capacity(path.len() + token.len() + 1);
+    v.push_str(path);
+    v.push('/');
+    for c in token.chars() {
+        match c {
+            '~' => v.push_str("~0"),
+            '/' => v.push_str("~1"),
+            _ => v.push(c),
+        }
+    }
+    v
+}
+
+// Decompose a JSON Number in its lexical form into its sign, significant
+// digits, and exponent, such that its value is `digits * 10^exponent`. Leading
+// and trailing zeros are stripped, so equal values yield equal parts. Zero is
+// always positive with no digits. Yields `None` if the number is not valid or
+// its exponent exceeds the supported range.
+fn number_parts(v: &str) -> Option<(bool, alloc::string::String, i128)> {
+    let (negative, v) = match v.strip_prefix('-') {
+        Some(v) => (true, v),
+        None => (false, v),
+    };
+    let (mantissa, exponent) = match v.find(['e', 'E']) {
+        Some(idx) => (&v[..idx], Some(&v[idx + 1..])),
+        None => (v, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (mantissa, None),
+    };
+
+    let is_digits = |v: &str| !v.is_empty() && v.bytes().all(|v| v.is_ascii_digit());
+    if !is_digits(integer) || !fraction.map_or(true, is_digits) {
+        return None;
+    }
+
+    let mut exp = match exponent {
+        None => 0,
+        Some(v) => {
+            let digits = v.strip_prefix(['+', '-']).unwrap_or(v);
+            if !is_digits(digits) {
+                return None;
+            }
+            v.strip_prefix('+').unwrap_or(v).parse::<i128>().ok()?
+        },
+    };
+
+    let fraction = fraction.unwrap_or("");
+    exp = exp.checked_sub(fraction.len() as i128)?;
+
+    let mut digits = alloc::string::String::with_capacity(integer.len() + fraction.len());
+    digits.push_str(integer);
+    digits.push_str(fraction);
+
+    let trimmed = digits.trim_end_matches('0');
+    exp = exp.checked_add((digits.len() - trimmed.len()) as i128)?;
+    let trimmed = trimmed.trim_start_matches('0');
+
+    if trimmed.is_empty() {
+        Some((false, alloc::string::String::new(), 0))
+    } else {
+        Some((negative, trimmed.into(), exp))
+    }
+}
+
+impl<'value, 'key> Entry<'value, 'key> {
+    /// ## Insert Default Value
+    ///
+    /// Insert the given value if the member is absent. Return a reference to
+    /// the value of the member.
+    pub fn or_insert(self, v: Value) -> &'value mut Value {
+        self.or_insert_with(|| v)
+    }
+
+    /// ## Insert Computed Default Value
+    ///
+    /// Insert the value produced by the given closure if the member is
+    /// absent. Return a reference to the value of the member.
+    pub fn or_insert_with<F: FnOnce() -> Value>(self, f: F) -> &'value mut Value {
+        match self {
+            Entry::Occupied(v) => v,
+            Entry::Vacant(list, key) => {
+                list.push((key.into(), f()));
+                &mut list.last_mut().unwrap().1
+            },
+        }
+    }
+
+    /// ## Modify Present Value
+    ///
+    /// Invoke the given closure on the value of the member, if present.
+    pub fn and_modify<F: FnOnce(&mut Value)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(v) => {
+                f(v);
+                Entry::Occupied(v)
+            },
+            v => v,
+        }
+    }
+}
+
+impl Value {
+    /// ## Get Object Member
+    ///
+    /// Return a reference to the value of the member with the given key, or
+    /// `None` if no such member exists or this is not a JSON Object. If
+    /// multiple members share the key, the first in document order is
+    /// returned.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(list) => list.iter().find(|v| v.0 == key).map(|v| &v.1),
+            _ => None,
+        }
+    }
+
+    /// ## Get Object Member Case-Insensitively
+    ///
+    /// Return a reference to the value of the member whose key matches the
+    /// given key, ignoring ASCII case, or `None` if no such member exists or
+    /// this is not a JSON Object. Non-ASCII characters must match exactly.
+    /// If multiple members match (e.g., `key` and `KEY`), the first in
+    /// document order is returned.
+    pub fn get_ci(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(list) => list.iter().find(|v| v.0.eq_ignore_ascii_case(key)).map(|v| &v.1),
+            _ => None,
+        }
+    }
+
+    /// ## Get Mutable Object Member
+    ///
+    /// Return a mutable reference to the value of the member with the given
+    /// key. See `Self::get()` for details.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        match self {
+            Value::Object(list) => list.iter_mut().find(|v| v.0 == key).map(|v| &mut v.1),
+            _ => None,
+        }
+    }
+
+    /// ## Get Object Entry
+    ///
+    /// Return the entry of the member with the given key, or `None` if this
+    /// is not a JSON Object. If multiple members share the key, the first in
+    /// document order is used. New members are appended to the object.
+    ///
+    /// Since JSON Objects are stored as lists in document order, this
+    /// performs a linear search and thus takes `O(n)` time.
+    pub fn entry<'value, 'key>(
+        &'value mut self,
+        key: &'key str,
+    ) -> Option<Entry<'value, 'key>> {
+        let Value::Object(list) = self else {
+            return None;
+        };
+
+        match list.iter().position(|v| v.0 == key) {
+            Some(idx) => Some(Entry::Occupied(&mut list[idx].1)),
+            None => Some(Entry::Vacant(list, key)),
+        }
+    }
+
+    /// ## Canonicalize Value
+    ///
+    /// Recursively sort the members of all JSON Objects by their keys. Keys
+    /// are compared lexically by their UTF-16 code units, which matches the
+    /// ordering rule of common canonical JSON schemes (e.g., RFC 8785). Note
+    /// that this differs from ordering by Unicode Scalar Values for
+    /// characters outside the Basic Multilingual Plane. Members with equal
+    /// keys retain their relative order.
+    ///
+    /// The order of JSON Array elements is preserved, and no other
+    /// normalization is applied (in particular, JSON Numbers retain their
+    /// lexical form). Combined with the compact serialization of `Display`,
+    /// this yields a deterministic representation of a JSON Value.
+    pub fn canonicalize(&mut self) {
+        let mut todo = alloc::vec![self];
+
+        while let Some(v) = todo.pop() {
+            match v {
+                Value::Array(list) => {
+                    todo.extend(list.iter_mut());
+                },
+                Value::Object(list) => {
+                    list.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+                    todo.extend(list.iter_mut().map(|v| &mut v.1));
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// ## Compare Numbers by Value
+    ///
+    /// Return whether both values are JSON Numbers with the same mathematical
+    /// value, regardless of their lexical form. That is, `1`, `1.0`, `10e-1`,
+    /// and `1E0` all compare equal, and so do `0` and `-0`. The comparison is
+    /// exact and never rounds. Numbers that are not valid JSON Numbers only
+    /// compare equal if their lexical forms are identical.
+    ///
+    /// Returns `false` if either value is not a JSON Number.
+    pub fn numeric_eq(&self, other: &Value) -> bool {
+        let (Value::Number(a), Value::Number(b)) = (self, other) else {
+            return false;
+        };
+
+        match (number_parts(a), number_parts(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => a == b,
+        }
+    }
+
+    /// ## Compare Values Deeply
+    ///
+    /// Return whether both values are structurally equal. If `numeric` is
+    /// `false`, this is equivalent to `PartialEq`, which compares JSON
+    /// Numbers by their lexical form. Otherwise, JSON Numbers are compared by
+    /// their value via `Self::numeric_eq()`. Object members are compared in
+    /// document order.
+    pub fn deep_eq(&self, other: &Value, numeric: bool) -> bool {
+        let mut todo = alloc::vec![(self, other)];
+
+        while let Some(v) = todo.pop() {
+            match v {
+                (Value::Number(_), Value::Number(_)) if numeric => {
+                    if !v.0.numeric_eq(v.1) {
+                        return false;
+                    }
+                },
+                (Value::Array(a), Value::Array(b)) => {
+                    if a.len() != b.len() {
+                        return false;
+                    }
+                    todo.extend(a.iter().zip(b.iter()));
+                },
+                (Value::Object(a), Value::Object(b)) => {
+                    if a.len() != b.len() || a.iter().zip(b.iter()).any(|(a, b)| a.0 != b.0) {
+                        return false;
+                    }
+                    todo.extend(a.iter().map(|v| &v.1).zip(b.iter().map(|v| &v.1)));
+                },
+                (Value::Array(_), _) | (Value::Object(_), _) => return false,
+                (a, b) => {
+                    if a != b {
+                        return false;
+                    }
+                },
+            }
+        }
+
+        true
+    }
+
+    /// ## Compute Differences
+    ///
+    /// Return the list of changes needed to turn this value into `other`.
+    /// JSON Objects are compared member-wise by key, considering only the
+    /// first member of duplicate keys. JSON Arrays are compared element-wise
+    /// by index, without trying to detect moved or inserted elements. All
+    /// other values are compared via `PartialEq` and replaced as a whole if
+    /// they differ, as are values of different types.
+    ///
+    /// Changes are reported in document order. Array elements beyond the
+    /// common length are removed starting at the last index, so the changes
+    /// can be applied in order.
+    pub fn diff(&self, other: &Value) -> alloc::vec::Vec<Change> {
+        enum Work<'a> {
+            Compare(alloc::string::String, &'a Value, &'a Value),
+            Emit(Change),
+        }
+
+        let mut changes = alloc::vec::Vec::new();
+        let mut todo = alloc::vec![Work::Compare(alloc::string::String::new(), self, other)];
+
+        while let Some(work) = todo.pop() {
+            let (path, a, b) = match work {
+                Work::Compare(path, a, b) => (path, a, b),
+                Work::Emit(v) => {
+                    changes.push(v);
+                    continue;
+                },
+            };
+
+            // Collect the work of this level in document order, and then
+            // schedule it in reverse on the stack.
+            let mut level = alloc::vec::Vec::new();
+
+            match (a, b) {
+                (Value::Array(la), Value::Array(lb)) => {
+                    for (i, (a, b)) in la.iter().zip(lb.iter()).enumerate() {
+                        level.push(Work::Compare(pointer_push(&path, &alloc::string::ToString::to_string(&i)), a, b));
+                    }
+                    for i in (lb.len()..la.len()).rev() {
+                        level.push(Work::Emit(Change::Removed(pointer_push(&path, &alloc::string::ToString::to_string(&i)))));
+                    }
+                    for (i, b) in lb.iter().enumerate().skip(la.len()) {
+                        level.push(Work::Emit(Change::Added(pointer_push(&path, &alloc::string::ToString::to_string(&i)), b.clone())));
+                    }
+                },
+                (Value::Object(la), Value::Object(lb)) => {
+                    let first = |list: &[(alloc::string::String, Value)], i: usize| {
+                        !list[..i].iter().any(|v| v.0 == list[i].0)
+                    };
+
+                    for (i, (k, a)) in la.iter().enumerate() {
+                        if !first(la, i) {
+                            continue;
+                        }
+                        match b.get(k) {
+                            Some(b) => level.push(Work::Compare(pointer_push(&path, k), a, b)),
+                            None => level.push(Work::Emit(Change::Removed(pointer_push(&path, k)))),
+                        }
+                    }
+                    for (i, (k, b)) in lb.iter().enumerate() {
+                        if first(lb, i) && a.get(k).is_none() {
+                            level.push(Work::Emit(Change::Added(pointer_push(&path, k), b.clone())));
+                        }
+                    }
+                },
+                (a, b) => {
+                    if a != b {
+                        changes.push(Change::Replaced(path, a.clone(), b.clone()));
+                    }
+                },
+            }
+
+            todo.extend(level.into_iter().rev());
+        }
+
+        changes
+    }
+
+    /// ## Visit All Values
+    ///
+    /// Invoke the given closure on this value and all its child values,
+    /// depth-first and in document order. Each value is visited before its
+    /// children, so any children introduced by the closure are visited as
+    /// well. The traversal uses a worklist rather than recursion, so deeply
+    /// nested values cannot overflow the stack.
+    pub fn walk_mut<F: FnMut(&mut Value)>(&mut self, mut f: F) {
+        let mut todo = alloc::vec![self];
+
+        while let Some(v) = todo.pop() {
+            f(v);
+
+            match v {
+                Value::Array(list) => {
+                    todo.extend(list.iter_mut().rev());
+                },
+                Value::Object(list) => {
+                    todo.extend(list.iter_mut().rev().map(|v| &mut v.1));
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// ## Transform All Strings
+    ///
+    /// Invoke the given closure on all JSON String values contained in this
+    /// value, in document order. Keys of object members are not considered
+    /// values and thus left untouched.
+    pub fn map_strings<F: FnMut(&mut alloc::string::String)>(&mut self, mut f: F) {
+        self.walk_mut(|v| {
+            if let Value::String(v) = v {
+                f(v);
+            }
+        });
+    }
+}
+
+// Release child values via a worklist rather than the recursive drop glue,
+// so deeply nested values cannot overflow the stack. Child values are moved
+// out of their parents before being dropped, so each drop only ever sees
+// empty lists.
+impl Drop for Value {
+    fn drop(&mut self) {
+        let mut todo = alloc::vec::Vec::new();
+
+        match self {
+            Value::Array(list) => todo.append(list),
+            Value::Object(list) => todo.extend(list.drain(..).map(|v| v.1)),
+            _ => return,
+        }
+
+        while let Some(mut v) = todo.pop() {
+            match &mut v {
+                Value::Array(list) => todo.append(list),
+                Value::Object(list) => todo.extend(list.drain(..).map(|v| v.1)),
+                _ => {},
+            }
+        }
+    }
+}
+
+// Convert from `serde_json` recursively. Its numbers are always finite and
+// are retained in their serialized form.
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Value> for Value {
+    fn from(v: serde_json::Value) -> Self {
+        match v {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(v) => Value::Bool(v),
+            serde_json::Value::Number(v) => Value::Number(alloc::string::ToString::to_string(&v)),
+            serde_json::Value::String(v) => Value::String(v),
+            serde_json::Value::Array(list) => Value::Array(
+                list.into_iter().map(Value::from).collect(),
+            ),
+            serde_json::Value::Object(list) => Value::Object(
+                list.into_iter().map(|(k, v)| (k, Value::from(v))).collect(),
+            ),
+        }
+    }
+}
+
+// Convert to `serde_json` recursively. Numbers are rejected if they are not
+// valid or exceed the finite range of `f64`, even if `serde_json` could
+// represent them with arbitrary precision. Object members with duplicate
+// keys are collapsed, with the last member taking precedence.
+#[cfg(feature = "serde_json")]
+impl TryFrom<Value> for serde_json::Value {
+    type Error = SerdeError;
+
+    fn try_from(mut v: Value) -> Result<Self, Self::Error> {
+        // `Value` implements `Drop`, so its contents must be taken rather
+        // than moved out.
+        Ok(match &mut v {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(v) => serde_json::Value::Bool(*v),
+            Value::Number(v) => {
+                let v = core::mem::take(v);
+                // Check the range first, since `serde_json` rejects numbers
+                // out of range as invalid, unless it uses arbitrary precision.
+                if number_parts(&v).is_some() && !v.parse::<f64>().is_ok_and(|v| v.is_finite()) {
+                    return Err(SerdeError::NumberNonFinite(v));
+                }
+                match v.parse::<serde_json::Number>() {
+                    Ok(v) => serde_json::Value::Number(v),
+                    Err(_) => return Err(SerdeError::NumberInvalid(v)),
+                }
+            },
+            Value::String(v) => serde_json::Value::String(core::mem::take(v)),
+            Value::Array(list) => serde_json::Value::Array(
+                core::mem::take(list).into_iter().map(serde_json::Value::try_from).collect::<Result<_, _>>()?,
+            ),
+            Value::Object(list) => serde_json::Value::Object(
+                core::mem::take(list).into_iter()
+                    .map(|(k, v)| Ok((k, serde_json::Value::try_from(v)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
+impl Writer {
+    /// ## Create New Writer
+    ///
+    /// Create a new writer with the default configuration, producing
+    /// compact JSON with numbers written verbatim.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // Write a JSON Number in the configured format.
+    fn write_number(
+        &self,
+        dst: &mut dyn core::fmt::Write,
+        v: &str,
+    ) -> Result<(), core::fmt::Error> {
+        // `number_parts()` tolerates leading zeros, which JSON does not.
+        let unsigned = v.strip_prefix('-').unwrap_or(v);
+        let valid = number_parts(v).is_some()
+            && !(unsigned.starts_with('0') && unsigned[1..].starts_with(|v: char| v.is_ascii_digit()));
+
+        if self.numbers == NumberFormat::Normalized && valid {
+            if let Ok(f) = v.parse::<f64>() {
+                if f.is_finite() {
+                    return dst.write_fmt(core::format_args!("{}", f));
+                }
+            }
+        }
+        dst.write_str(v)
+    }
+
+    // Start a new line at the given nesting depth, if indentation is
+    // enabled.
+    fn write_newline(
+        &self,
+        dst: &mut dyn core::fmt::Write,
+        depth: usize,
+    ) -> Result<(), core::fmt::Error> {
+        if let Some(indent) = self.indent {
+            dst.write_char('\n')?;
+            for _ in 0..(indent * depth) {
+                dst.write_char(' ')?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_at(
+        &self,
+        dst: &mut dyn core::fmt::Write,
+        value: &Value,
+        depth: usize,
+    ) -> Result<(), core::fmt::Error> {
+        match value {
+            Value::Null => dst.write_str("null"),
+            Value::Bool(true) => dst.write_str("true"),
+            Value::Bool(false) => dst.write_str("false"),
+            Value::Number(v) => self.write_number(dst, v),
+            Value::String(v) => crate::emit::write_string(dst, v),
+            Value::Array(list) => {
+                dst.write_str("[")?;
+                for (i, v) in list.iter().enumerate() {
+                    if i > 0 {
+                        dst.write_str(",")?;
+                    }
+                    self.write_newline(dst, depth + 1)?;
+                    self.write_at(dst, v, depth + 1)?;
+                }
+                if !list.is_empty() {
+                    self.write_newline(dst, depth)?;
+                }
+                dst.write_str("]")
+            },
+            Value::Object(list) => {
+                dst.write_str("{")?;
+                for (i, (k, v)) in list.iter().enumerate() {
+                    if i > 0 {
+                        dst.write_str(",")?;
+                    }
+                    self.write_newline(dst, depth + 1)?;
+                    crate::emit::write_string(dst, k)?;
+                    dst.write_str(match self.indent {
+                        Some(_) => ": ",
+                        None => ":",
+                    })?;
+                    self.write_at(dst, v, depth + 1)?;
+                }
+                if !list.is_empty() {
+                    self.write_newline(dst, depth)?;
+                }
+                dst.write_str("}")
+            },
+        }
+    }
+
+    /// ## Write Value
+    ///
+    /// Serialize the value into the given destination. With indentation,
+    /// each array element and object member is put on its own line, while
+    /// empty arrays and objects are written as `[]` and `{}`. No trailing
+    /// newline is written.
+    pub fn write(
+        &self,
+        dst: &mut dyn core::fmt::Write,
+        value: &Value,
+    ) -> Result<(), core::fmt::Error> {
+        self.write_at(dst, value, 0)
+    }
+
+    /// ## Write Value to String
+    ///
+    /// Serialize the value into a new string. See `Self::write()` for
+    /// details.
+    pub fn to_string(&self, value: &Value) -> alloc::string::String {
+        let mut v = alloc::string::String::new();
+        // Writing into a string cannot fail.
+        self.write(&mut v, value).unwrap();
+        v
+    }
+}
+
+// Serialize the value as compact JSON without any insignificant whitespace.
+impl core::fmt::Display for Value {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        Writer::new().write(fmt, self)
+    }
+}
+
+impl core::fmt::Display for FormError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::NotObject => fmt.write_str("Only JSON Objects can be form-encoded"),
+            Self::Nested(v) => fmt.write_fmt(core::format_args!("Nested value cannot be form-encoded: {}", v)),
+        }
+    }
+}
+
+// Append the percent-encoded form of `v` to `dst`, following the
+// `application/x-www-form-urlencoded` serializer of the WHATWG URL
+// standard. Spaces are encoded as `+`.
+fn form_escape(dst: &mut alloc::string::String, v: &str) {
+    for b in v.bytes() {
+        match b {
+            b'*' | b'-' | b'.' | b'_' | b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' => dst.push(b as char),
+            b' ' => dst.push('+'),
+            _ => {
+                // Writing into a string cannot fail.
+                core::fmt::Write::write_fmt(dst, core::format_args!("%{:02X}", b)).unwrap();
+            },
+        }
+    }
+}
+
+/// ## Encode as Form Data
+///
+/// Encode a flat JSON Object as `application/x-www-form-urlencoded` string,
+/// as used for URL query strings and HTML form submissions. Members are
+/// encoded in document order as `key=value` pairs joined by `&`, with keys
+/// and values percent-encoded.
+///
+/// JSON Strings are encoded verbatim, JSON Numbers in their lexical form,
+/// and JSON Booleans as `true` or `false`. Members with JSON Null values
+/// are omitted. Nested JSON Arrays or JSON Objects are rejected, as is any
+/// value other than a JSON Object.
+pub fn to_form_urlencoded(value: &Value) -> Result<alloc::string::String, FormError> {
+    let Value::Object(list) = value else {
+        return Err(FormError::NotObject);
+    };
+
+    let mut acc = alloc::string::String::new();
+
+    for (k, v) in list.iter() {
+        let v = match v {
+            Value::Null => continue,
+            Value::Bool(true) => "true",
+            Value::Bool(false) => "false",
+            Value::Number(v) | Value::String(v) => v.as_str(),
+            Value::Array(_) | Value::Object(_) => return Err(FormError::Nested(k.clone())),
+        };
+
+        if !acc.is_empty() {
+            acc.push('&');
+        }
+        form_escape(&mut acc, k);
+        acc.push('=');
+        form_escape(&mut acc, v);
+    }
+
+    Ok(acc)
+}
+
+impl<'ctx> core::fmt::Display for ParseError<'ctx> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self.kind {
+            ParseErrorKind::Token(v) => fmt.write_fmt(core::format_args!("Invalid JSON at byte offset {}: {}", self.offset, v)),
+            ParseErrorKind::ValueExpected => fmt.write_fmt(core::format_args!("Expected JSON Value at byte offset {}", self.offset)),
+            ParseErrorKind::KeyExpected => fmt.write_fmt(core::format_args!("Expected JSON String as key at byte offset {}", self.offset)),
+            ParseErrorKind::ColonExpected => fmt.write_fmt(core::format_args!("Expected colon at byte offset {}", self.offset)),
+            ParseErrorKind::CommaExpected => fmt.write_fmt(core::format_args!("Expected comma or closing bracket at byte offset {}", self.offset)),
+            ParseErrorKind::TrailingData => fmt.write_fmt(core::format_args!("Unexpected data after JSON Value at byte offset {}", self.offset)),
+            ParseErrorKind::KeyDuplicate(v) => fmt.write_fmt(core::format_args!("Duplicate key `{}` at byte offset {}", v, self.offset)),
+        }
+    }
+}
+
+// Grammar position of the DOM parser, describing what is expected next.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Expect {
+    Value,
+    ValueOrClose,
+    Key,
+    KeyOrClose,
+    Colon,
+    CommaOrClose,
+    End,
+}
+
+// Container under construction by the DOM parser. Objects carry the key
+// of the member whose value is pending, as well as the set of all keys
+// seen so far if duplicate keys are rejected.
+enum Frame {
+    Array(alloc::vec::Vec<Value>),
+    Object(
+        alloc::vec::Vec<(alloc::string::String, Value)>,
+        alloc::string::String,
+        alloc::collections::BTreeSet<alloc::string::String>,
+    ),
+}
+
+impl Expect {
+    // Yield the error kind for any unexpected token at this position.
+    fn error(self) -> ParseErrorKind<'static> {
+        match self {
+            Expect::Value | Expect::ValueOrClose => ParseErrorKind::ValueExpected,
+            Expect::Key | Expect::KeyOrClose => ParseErrorKind::KeyExpected,
+            Expect::Colon => ParseErrorKind::ColonExpected,
+            Expect::CommaOrClose => ParseErrorKind::CommaExpected,
+            Expect::End => ParseErrorKind::TrailingData,
+        }
+    }
+}
+
+/// ## Parse JSON
+///
+/// Parse the given string as a single JSON Value, driving the JSON
+/// tokenizer and building the value tree from its tokens. Unlike the
+/// tokenizer, this enforces the JSON grammar, including separators and
+/// balanced brackets. Whitespace is allowed around all tokens.
+///
+/// The first error is returned with its byte offset into the input.
+/// Nesting is limited to `token::BRACKET_DEPTH`, and deeper input is
+/// rejected with `token::Error::BracketDepth`.
+pub fn parse(data: &str) -> Result<Value, ParseError<'_>> {
+    parse_with(data, 0)
+}
+
+/// ## Parse JSON with Flags
+///
+/// Parse the given string like `parse()`, but with the given tokenizer
+/// flags. The flags are passed to the tokenizer, and both
+/// `token::FLAG_ALLOW_TRAILING_COMMA` and
+/// `token::FLAG_REJECT_DUPLICATE_KEYS` are honored by the grammar checks.
+pub fn parse_with(data: &str, flags: crate::token::Flag) -> Result<Value, ParseError<'_>> {
+    let trailing = (flags & crate::token::FLAG_ALLOW_TRAILING_COMMA) != 0;
+    let unique = (flags & crate::token::FLAG_REJECT_DUPLICATE_KEYS) != 0;
+    let mut brackets = crate::token::Brackets::new();
+    let mut stack: alloc::vec::Vec<Frame> = alloc::vec::Vec::new();
+    let mut root = None;
+    let mut expect = Expect::Value;
+
+    let r = crate::token::Tokenizer::with(flags).feed(
+        data,
+        crate::token::Feed { finish: true, ..Default::default() },
+        &mut |token, range| {
+            use crate::token::Token;
+
+            let fail = |kind| core::ops::ControlFlow::Break(ParseError { kind: kind, offset: range.start });
+            let value = match token {
+                Token::Whitespace(_) => return core::ops::ControlFlow::Continue(()),
+                Token::Error(v) => {
+                    let v = crate::token::rebase(v, data, range.clone());
+                    return fail(ParseErrorKind::Token(v));
+                },
+                Token::String(raw, v) if matches!(expect, Expect::Key | Expect::KeyOrClose) => {
+                    if let Some(Frame::Object(_, key, keys)) = stack.last_mut() {
+                        if unique && !keys.insert(v.into()) {
+                            // Strip the quotes, if any, from the input range
+                            // of the key.
+                            let start = range.start + (range.len() - raw.len()) / 2;
+                            return fail(ParseErrorKind::KeyDuplicate(&data[start..start + raw.len()]));
+                        }
+                        *key = v.into();
+                    }
+                    expect = Expect::Colon;
+                    return core::ops::ControlFlow::Continue(());
+                },
+                Token::Colon if expect == Expect::Colon => {
+                    expect = Expect::Value;
+                    return core::ops::ControlFlow::Continue(());
+                },
+                Token::Comma if expect == Expect::CommaOrClose => {
+                    expect = match (stack.last(), trailing) {
+                        (Some(Frame::Object(..)), false) => Expect::Key,
+                        (Some(Frame::Object(..)), true) => Expect::KeyOrClose,
+                        (_, false) => Expect::Value,
+                        (_, true) => Expect::ValueOrClose,
+                    };
+                    return core::ops::ControlFlow::Continue(());
+                },
+                Token::ArrayClose | Token::ObjectClose => {
+                    if let Err(v) = brackets.close(token == Token::ObjectClose) {
+                        return fail(ParseErrorKind::Token(v));
+                    }
+                    if !matches!(expect, Expect::ValueOrClose | Expect::KeyOrClose | Expect::CommaOrClose) {
+                        return fail(expect.error());
+                    }
+                    match stack.pop() {
+                        Some(Frame::Array(v)) => Value::Array(v),
+                        Some(Frame::Object(v, ..)) => Value::Object(v),
+                        None => unreachable!(),
+                    }
+                },
+                _ if !matches!(expect, Expect::Value | Expect::ValueOrClose) => {
+                    return fail(expect.error());
+                },
+                Token::ArrayOpen | Token::ObjectOpen => {
+                    if let Err(v) = brackets.open(token == Token::ObjectOpen) {
+                        return fail(ParseErrorKind::Token(v));
+                    }
+                    if token == Token::ObjectOpen {
+                        stack.push(Frame::Object(
+                            alloc::vec::Vec::new(),
+                            alloc::string::String::new(),
+                            alloc::collections::BTreeSet::new(),
+                        ));
+                        expect = Expect::KeyOrClose;
+                    } else {
+                        stack.push(Frame::Array(alloc::vec::Vec::new()));
+                        expect = Expect::ValueOrClose;
+                    }
+                    return core::ops::ControlFlow::Continue(());
+                },
+                Token::Null => Value::Null,
+                Token::True => Value::Bool(true),
+                Token::False => Value::Bool(false),
+                Token::Number(v, ..) => Value::Number(v.into()),
+                Token::String(_, v) => Value::String(v.into()),
+                Token::Colon | Token::Comma => return fail(expect.error()),
+            };
+
+            // Attach the completed value to its parent, or make it the
+            // top-level value.
+            expect = Expect::CommaOrClose;
+            match stack.last_mut() {
+                None => {
+                    root = Some(value);
+                    expect = Expect::End;
+                },
+                Some(Frame::Array(list)) => list.push(value),
+                Some(Frame::Object(list, key, _)) => list.push((core::mem::take(key), value)),
+            }
+
+            core::ops::ControlFlow::Continue(())
+        },
+    );
+
+    if let crate::token::Report::Break((v, _)) = r {
+        return Err(v);
+    }
+
+    match (brackets.innermost(), root) {
+        (Some(v), _) => Err(ParseError {
+            kind: ParseErrorKind::Token(crate::token::Error::BracketUnclosed(v)),
+            offset: data.len(),
+        }),
+        (None, None) => Err(ParseError { kind: ParseErrorKind::ValueExpected, offset: data.len() }),
+        (None, Some(v)) => Ok(v),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    // Verify the compact serialization including string escapes.
+    #[test]
+    fn value_display() {
+        let v = Value::Array(alloc::vec![
+            Value::Null,
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Number("-1.5e3".into()),
+            Value::String("a\"b\\c\n\u{0001}ä".into()),
+            Value::Object(alloc::vec![
+                ("key".into(), Value::Array(alloc::vec![])),
+            ]),
+        ]);
+
+        assert_eq!(
+            v.to_string(),
+            "[null,true,false,-1.5e3,\"a\\\"b\\\\c\\n\\u0001ä\",{\"key\":[]}]",
+        );
+    }
+
+    // Verify indented output and that numbers are written verbatim unless
+    // normalization is requested.
+    #[test]
+    fn value_writer() {
+        let num = |v: &str| Value::Number(v.into());
+        let v = Value::Object(alloc::vec![
+            ("a".into(), Value::Array(alloc::vec![num("1.0"), num("1e10"), num("-0.50")])),
+            ("b".into(), Value::Object(alloc::vec![])),
+            ("c".into(), Value::Array(alloc::vec![num("1e400"), num("01")])),
+        ]);
+
+        let mut writer = Writer::new();
+        assert_eq!(writer.to_string(&v), v.to_string());
+        assert_eq!(
+            writer.to_string(&v),
+            "{\"a\":[1.0,1e10,-0.50],\"b\":{},\"c\":[1e400,01]}",
+        );
+
+        writer.numbers = NumberFormat::Normalized;
+        assert_eq!(
+            writer.to_string(&v),
+            "{\"a\":[1,10000000000,-0.5],\"b\":{},\"c\":[1e400,01]}",
+        );
+
+        writer.indent = Some(2);
+        writer.numbers = NumberFormat::Verbatim;
+        assert_eq!(
+            writer.to_string(&v),
+            "{\n  \"a\": [\n    1.0,\n    1e10,\n    -0.50\n  ],\n  \"b\": {},\n  \"c\": [\n    1e400,\n    01\n  ]\n}",
+        );
+    }
+
+    // Verify that canonicalization sorts object keys recursively, but
+    // retains the order of array elements.
+    #[test]
+    fn value_canonicalize() {
+        let mut a = Value::Object(alloc::vec![
+            ("b".into(), Value::Number("1".into())),
+            ("a".into(), Value::Object(alloc::vec![
+                ("y".into(), Value::Null),
+                ("x".into(), Value::Array(alloc::vec![
+                    Value::Number("2".into()),
+                    Value::Number("1".into()),
+                ])),
+            ])),
+        ]);
+        let mut b = Value::Object(alloc::vec![
+            ("a".into(), Value::Object(alloc::vec![
+                ("x".into(), Value::Array(alloc::vec![
+                    Value::Number("2".into()),
+                    Value::Number("1".into()),
+                ])),
+                ("y".into(), Value::Null),
+            ])),
+            ("b".into(), Value::Number("1".into())),
+        ]);
+
+        assert_ne!(a.to_string(), b.to_string());
+        a.canonicalize();
+        b.canonicalize();
+        assert_eq!(a.to_string(), b.to_string());
+        assert_eq!(a.to_string(), "{\"a\":{\"x\":[2,1],\"y\":null},\"b\":1}");
+    }
+
+    // Verify that diffs report added and removed members, replaced scalars,
+    // and nested changes in document order.
+    #[test]
+    fn value_diff() {
+        let num = |v: &str| Value::Number(v.into());
+        let a = Value::Object(alloc::vec![
+            ("keep".into(), num("1")),
+            ("drop".into(), Value::Null),
+            ("swap".into(), Value::Bool(true)),
+            ("nest".into(), Value::Object(alloc::vec![
+                ("a/b~c".into(), num("1")),
+                ("list".into(), Value::Array(alloc::vec![num("1"), num("2"), num("3")])),
+            ])),
+        ]);
+        let b = Value::Object(alloc::vec![
+            ("keep".into(), num("1")),
+            ("swap".into(), Value::String("true".into())),
+            ("nest".into(), Value::Object(alloc::vec![
+                ("a/b~c".into(), num("1.0")),
+                ("list".into(), Value::Array(alloc::vec![num("1"), num("4")])),
+            ])),
+            ("new".into(), Value::Array(alloc::vec![])),
+        ]);
+
+        assert_eq!(a.diff(&a), alloc::vec![]);
+        assert_eq!(
+            a.diff(&b),
+            alloc::vec![
+                Change::Removed("/drop".into()),
+                Change::Replaced("/swap".into(), Value::Bool(true), Value::String("true".into())),
+                Change::Replaced("/nest/a~1b~0c".into(), num("1"), num("1.0")),
+                Change::Replaced("/nest/list/1".into(), num("2"), num("4")),
+                Change::Removed("/nest/list/2".into()),
+                Change::Added("/new".into(), Value::Array(alloc::vec![])),
+            ],
+        );
+        assert_eq!(
+            b.diff(&a)[3..],
+            [
+                Change::Added("/nest/list/2".into(), num("3")),
+                Change::Removed("/new".into()),
+                Change::Added("/drop".into(), Value::Null),
+            ],
+        );
+
+        // Type changes and root scalars replace the value as a whole.
+        assert_eq!(
+            Value::Null.diff(&a),
+            alloc::vec![Change::Replaced("".into(), Value::Null, a.clone())],
+        );
+
+        // Trailing array elements are removed from the end.
+        assert_eq!(
+            Value::Array(alloc::vec![Value::Null; 3]).diff(&Value::Array(alloc::vec![Value::Null])),
+            alloc::vec![Change::Removed("/2".into()), Change::Removed("/1".into())],
+        );
+    }
+
+    // Verify that keys are ordered by UTF-16 code units rather than by
+    // Unicode Scalar Values.
+    #[test]
+    fn value_canonicalize_utf16() {
+        // U+FF61 sorts after U+1F600 in UTF-16, since the latter is encoded
+        // as surrogate pair starting with 0xD83D.
+        let mut v = Value::Object(alloc::vec![
+            ("\u{ff61}".into(), Value::Null),
+            ("\u{1f600}".into(), Value::Null),
+            ("a".into(), Value::Null),
+        ]);
+        v.canonicalize();
+
+        assert_eq!(
+            v,
+            Value::Object(alloc::vec![
+                ("a".into(), Value::Null),
+                ("\u{1f600}".into(), Value::Null),
+                ("\u{ff61}".into(), Value::Null),
+            ]),
+        );
+    }
+
+    // Verify the entry API on present and absent members, as well as on
+    // values other than objects.
+    #[test]
+    fn value_entry() {
+        let mut v = Value::Object(alloc::vec![
+            ("a".into(), Value::Number("1".into())),
+        ]);
+
+        // `or_insert()` retains present members and appends absent ones.
+        *v.entry("a").unwrap().or_insert(Value::Null) = Value::Bool(true);
+        v.entry("b").unwrap().or_insert(Value::Null);
+        assert_eq!(v.get("a"), Some(&Value::Bool(true)));
+        assert_eq!(v.get("b"), Some(&Value::Null));
+        assert_eq!(v.get("c"), None);
+
+        // `and_modify()` only runs on present members.
+        v.entry("a").unwrap()
+            .and_modify(|v| *v = Value::Bool(false))
+            .or_insert(Value::Null);
+        v.entry("c").unwrap()
+            .and_modify(|v| *v = Value::Bool(false))
+            .or_insert(Value::Number("3".into()));
+        assert_eq!(
+            v,
+            Value::Object(alloc::vec![
+                ("a".into(), Value::Bool(false)),
+                ("b".into(), Value::Null),
+                ("c".into(), Value::Number("3".into())),
+            ]),
+        );
+
+        // Values other than objects have no entries.
+        assert!(Value::Null.entry("a").is_none());
+        assert!(Value::Array(alloc::vec![]).get_mut("a").is_none());
+    }
+
+    // Verify case-insensitive lookup matches across ASCII case differences
+    // only, and prefers the first match in document order.
+    #[test]
+    fn value_get_ci() {
+        let v = Value::Object(alloc::vec![
+            ("Path".into(), Value::Number("1".into())),
+            ("PATH".into(), Value::Number("2".into())),
+            ("Straße".into(), Value::Number("3".into())),
+        ]);
+
+        assert_eq!(v.get_ci("path"), Some(&Value::Number("1".into())));
+        assert_eq!(v.get_ci("PATH"), Some(&Value::Number("1".into())));
+        assert_eq!(v.get("PATH"), Some(&Value::Number("2".into())));
+        assert_eq!(v.get_ci("STRAßE"), Some(&Value::Number("3".into())));
+
+        // Only ASCII case is ignored, and keys must match in full.
+        assert_eq!(v.get_ci("STRASSE"), None);
+        assert_eq!(v.get_ci("pat"), None);
+        assert_eq!(Value::Null.get_ci("path"), None);
+    }
+
+    // Verify that parsing builds the value tree in document order, and
+    // reports grammar violations with their kind and byte offset.
+    #[test]
+    fn value_parse() {
+        use crate::token::Error;
+
+        assert_eq!(
+            parse(" {\"a\": [1, true, null, \"x\\n\"], \"b\": {}, \"a\": -2.5e1} ").unwrap(),
+            Value::Object(alloc::vec![
+                ("a".into(), Value::Array(alloc::vec![
+                    Value::Number("1".into()),
+                    Value::Bool(true),
+                    Value::Null,
+                    Value::String("x\n".into()),
+                ])),
+                ("b".into(), Value::Object(alloc::vec![])),
+                ("a".into(), Value::Number("-2.5e1".into())),
+            ]),
+        );
+        assert_eq!(parse("42").unwrap(), Value::Number("42".into()));
+        assert_eq!(parse("[[], [{}]]").unwrap().to_string(), "[[],[{}]]");
+
+        fn err(data: &str) -> Result<(), (ParseErrorKind<'_>, usize)> {
+            parse(data).map(|_| ()).map_err(|v| (v.kind, v.offset))
+        }
+
+        assert_eq!(err(""), Err((ParseErrorKind::ValueExpected, 0)));
+        assert_eq!(err(" , "), Err((ParseErrorKind::ValueExpected, 1)));
+        assert_eq!(err("[1 2]"), Err((ParseErrorKind::CommaExpected, 3)));
+        assert_eq!(err("[1,]"), Err((ParseErrorKind::ValueExpected, 3)));
+        assert_eq!(err("[,1]"), Err((ParseErrorKind::ValueExpected, 1)));
+        assert_eq!(err("{\"a\" 1}"), Err((ParseErrorKind::ColonExpected, 5)));
+        assert_eq!(err("{\"a\"}"), Err((ParseErrorKind::ColonExpected, 4)));
+        assert_eq!(err("{1: 2}"), Err((ParseErrorKind::KeyExpected, 1)));
+        assert_eq!(err("{\"a\":1,}"), Err((ParseErrorKind::KeyExpected, 7)));
+        assert_eq!(err("{\"a\":}"), Err((ParseErrorKind::ValueExpected, 5)));
+        assert_eq!(err("1 2"), Err((ParseErrorKind::TrailingData, 2)));
+        assert_eq!(err("[1}"), Err((ParseErrorKind::Token(Error::BracketMismatch('}')), 2)));
+        assert_eq!(err("{]"), Err((ParseErrorKind::Token(Error::BracketMismatch(']')), 1)));
+        assert_eq!(err("1]"), Err((ParseErrorKind::Token(Error::BracketStray(']')), 1)));
+        assert_eq!(err("{\"a\": ["), Err((ParseErrorKind::Token(Error::BracketUnclosed('[')), 7)));
+        assert_eq!(err("[tru]"), Err((ParseErrorKind::Token(Error::KeywordUnknown("tru")), 1)));
+
+        let deep = "[".repeat(crate::token::BRACKET_DEPTH + 1);
+        assert_eq!(
+            err(&deep),
+            Err((ParseErrorKind::Token(Error::BracketDepth), crate::token::BRACKET_DEPTH)),
+        );
+
+        assert_eq!(
+            parse("[1 2]").unwrap_err().to_string(),
+            "Expected comma or closing bracket at byte offset 3",
+        );
+        assert_eq!(
+            parse("[1}").unwrap_err().to_string(),
+            "Invalid JSON at byte offset 2: Closing bracket `}` does not match the open bracket",
+        );
+    }
+
+    // Verify trailing commas are only accepted with the respective flag,
+    // and only right before a closing bracket.
+    #[test]
+    fn value_parse_trailing_comma() {
+        use crate::token::FLAG_ALLOW_TRAILING_COMMA as FLAG;
+
+        assert_eq!(
+            parse_with("{\"a\": [1, 2,], \"b\": {},}", FLAG).unwrap().to_string(),
+            "{\"a\":[1,2],\"b\":{}}",
+        );
+        assert_eq!(parse_with("[1,]", 0).unwrap_err().kind, ParseErrorKind::ValueExpected);
+        assert_eq!(parse_with("{\"a\":1,}", 0).unwrap_err().kind, ParseErrorKind::KeyExpected);
+
+        // Commas must still separate values.
+        assert_eq!(parse_with("[,]", FLAG).unwrap_err().offset, 1);
+        assert_eq!(parse_with("[1,,]", FLAG).unwrap_err().offset, 3);
+        assert_eq!(parse_with("1,", FLAG).unwrap_err().kind, ParseErrorKind::TrailingData);
+    }
+
+    // Verify duplicate keys are only rejected with the respective flag,
+    // comparing decoded keys within each object separately.
+    #[test]
+    fn value_parse_duplicate_keys() {
+        use crate::token::FLAG_REJECT_DUPLICATE_KEYS as FLAG;
+
+        let data = r#"{"a": 1, "b": {"a": 2}, "\u0061": 3}"#;
+        assert!(parse(data).is_ok());
+        assert_eq!(
+            parse_with(data, FLAG).unwrap_err(),
+            ParseError { kind: ParseErrorKind::KeyDuplicate("\\u0061"), offset: 24 },
+        );
+        assert_eq!(
+            parse_with(data, FLAG).unwrap_err().to_string(),
+            "Duplicate key `\\u0061` at byte offset 24",
+        );
+        assert!(parse_with(r#"[{"a": 1}, {"a": 2}]"#, FLAG).is_ok());
+
+        // Unquoted keys are reported as given.
+        let flags = FLAG | crate::token::FLAG_ALLOW_UNQUOTED_KEYS;
+        assert_eq!(
+            parse_with(r#"{a: 1, "a": 2}"#, flags).unwrap_err().kind,
+            ParseErrorKind::KeyDuplicate("a"),
+        );
+        assert_eq!(
+            parse_with(r#"{"a": 1, a: 2}"#, flags).unwrap_err(),
+            ParseError { kind: ParseErrorKind::KeyDuplicate("a"), offset: 9 },
+        );
+    }
+
+    // Verify the visitor reaches all values in document order, and string
+    // transformations leave other values untouched.
+    #[test]
+    fn value_walk() {
+        let mut v = Value::Object(alloc::vec![
+            ("${A}".into(), Value::String("${A}/x".into())),
+            ("b".into(), Value::Array(alloc::vec![
+                Value::Number("1".into()),
+                Value::String("y-${A}".into()),
+                Value::Object(alloc::vec![
+                    ("c".into(), Value::String("${A}".into())),
+                ]),
+            ])),
+            ("d".into(), Value::Null),
+        ]);
+
+        let mut order = alloc::vec::Vec::new();
+        v.walk_mut(|v| order.push(match v {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }));
+        assert_eq!(
+            order,
+            ["object", "string", "array", "number", "string", "object", "string", "null"],
+        );
+
+        v.map_strings(|v| *v = v.replace("${A}", "a"));
+        assert_eq!(
+            v,
+            Value::Object(alloc::vec![
+                ("${A}".into(), Value::String("a/x".into())),
+                ("b".into(), Value::Array(alloc::vec![
+                    Value::Number("1".into()),
+                    Value::String("y-a".into()),
+                    Value::Object(alloc::vec![
+                        ("c".into(), Value::String("a".into())),
+                    ]),
+                ])),
+                ("d".into(), Value::Null),
+            ]),
+        );
+
+        // Deep nesting does not exhaust the stack.
+        let mut v = Value::Null;
+        for _ in 0..100_000 {
+            v = Value::Array(alloc::vec![v]);
+        }
+        let mut n = 0;
+        v.walk_mut(|_| n += 1);
+        assert_eq!(n, 100_001);
+
+        // Dropping deeply nested values does not exhaust the stack either.
+        drop(v);
+        let mut v = Value::Null;
+        for _ in 0..100_000 {
+            v = Value::Object(alloc::vec![("a".into(), v), ("b".into(), Value::Null)]);
+        }
+        drop(v);
+    }
+
+    // Verify form encoding of flat objects including special characters,
+    // and the rejection of nested values.
+    #[test]
+    fn value_form_urlencoded() {
+        let v = Value::Object(alloc::vec![
+            ("q".into(), Value::String("a b&c=d".into())),
+            ("n".into(), Value::Number("-1.5e3".into())),
+            ("flag".into(), Value::Bool(true)),
+            ("none".into(), Value::Null),
+            ("k\u{e4}y/?".into(), Value::String("100%*-._~+".into())),
+        ]);
+        assert_eq!(
+            to_form_urlencoded(&v).unwrap(),
+            "q=a+b%26c%3Dd&n=-1.5e3&flag=true&k%C3%A4y%2F%3F=100%25*-._%7E%2B",
+        );
+
+        assert_eq!(to_form_urlencoded(&Value::Object(alloc::vec![])).unwrap(), "");
+        assert_eq!(to_form_urlencoded(&Value::Array(alloc::vec![])), Err(FormError::NotObject));
+        assert_eq!(
+            to_form_urlencoded(&Value::Object(alloc::vec![
+                ("a".into(), Value::Number("1".into())),
+                ("b".into(), Value::Array(alloc::vec![])),
+            ])),
+            Err(FormError::Nested("b".into())),
+        );
+        assert_eq!(
+            to_form_urlencoded(&Value::Object(alloc::vec![
+                ("c".into(), Value::Object(alloc::vec![])),
+            ])).unwrap_err().to_string(),
+            "Nested value cannot be form-encoded: c",
+        );
+    }
+
+    // Verify numbers compare by value with `numeric_eq()`, but by their
+    // lexical form with `PartialEq`.
+    #[test]
+    fn value_numeric_eq() {
+        let n = |v: &str| Value::Number(v.into());
+
+        assert!(n("1").numeric_eq(&n("1.0")));
+        assert_ne!(n("1"), n("1.0"));
+        assert!(n("1").numeric_eq(&n("1E0")));
+        assert!(n("1").numeric_eq(&n("10e-1")));
+        assert!(n("100").numeric_eq(&n("1e+2")));
+        assert!(n("-0.0").numeric_eq(&n("0")));
+        assert!(n("0.00120").numeric_eq(&n("12e-4")));
+        assert!(n("123456789012345678901234567890").numeric_eq(&n("1.2345678901234567890123456789e29")));
+        assert!(!n("1").numeric_eq(&n("-1")));
+        assert!(!n("1").numeric_eq(&n("1.0000000000000000000001")));
+        assert!(!n("1").numeric_eq(&Value::String("1".into())));
+        assert!(!n("1").numeric_eq(&n("1e99999999999999999999999999999999999999999")));
+
+        // Invalid lexical forms only compare equal to themselves.
+        assert!(n("01x").numeric_eq(&n("01x")));
+        assert!(!n("1.").numeric_eq(&n("1")));
+
+        let a = Value::Array(alloc::vec![
+            n("1"),
+            Value::Object(alloc::vec![("a".into(), n("2.50"))]),
+        ]);
+        let b = Value::Array(alloc::vec![
+            n("1.0"),
+            Value::Object(alloc::vec![("a".into(), n("25e-1"))]),
+        ]);
+        assert!(a.deep_eq(&b, true));
+        assert!(!a.deep_eq(&b, false));
+        assert!(a.deep_eq(&a.clone(), false));
+        assert!(!a.deep_eq(&Value::Array(alloc::vec![n("1")]), true));
+        assert!(!a.deep_eq(&Value::Null, true));
+    }
+
+    // Verify the conversion from and to `serde_json` for all kinds of
+    // values, and the rejection of numbers `serde_json` cannot represent.
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn value_serde() {
+        let v = Value::Object(alloc::vec![
+            ("array".into(), Value::Array(alloc::vec![
+                Value::Null,
+                Value::Bool(true),
+                Value::Bool(false),
+            ])),
+            ("number".into(), Value::Array(alloc::vec![
+                Value::Number("0".into()),
+                Value::Number("-12".into()),
+                Value::Number("1.5".into()),
+            ])),
+            ("object".into(), Value::Object(alloc::vec![])),
+            ("string".into(), Value::String("foo\"\u{e4}".into())),
+        ]);
+
+        let serde: serde_json::Value = v.clone().try_into().unwrap();
+        assert_eq!(
+            serde,
+            serde_json::json!({
+                "array": [null, true, false],
+                "number": [0, -12, 1.5],
+                "object": {},
+                "string": "foo\"\u{e4}",
+            }),
+        );
+        assert_eq!(Value::from(serde), v);
+
+        assert_eq!(
+            serde_json::Value::try_from(Value::Array(alloc::vec![Value::Number("1e400".into())])),
+            Err(SerdeError::NumberNonFinite("1e400".into())),
+        );
+        assert_eq!(
+            serde_json::Value::try_from(Value::Number("-1e400".into())),
+            Err(SerdeError::NumberNonFinite("-1e400".into())),
+        );
+        assert_eq!(
+            serde_json::Value::try_from(Value::Number("01".into())),
+            Err(SerdeError::NumberInvalid("01".into())),
+        );
+        assert_eq!(
+            serde_json::Value::try_from(Value::Number("NaN".into())),
+            Err(SerdeError::NumberInvalid("NaN".into())),
+        );
+    }
+}
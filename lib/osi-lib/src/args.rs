@@ -8,7 +8,7 @@ use crate::compat;
 /// Error definitions for all possible errors of the argument parser.
 #[derive(Debug)]
 #[non_exhaustive]
-pub enum Error<'args> {
+pub enum Error<'args, 'ctx> {
     /// Specified flag contains invalid Unicode.
     FlagInvalidUnicode(&'args compat::OsStr),
     /// Specified flag is not known.
@@ -29,12 +29,41 @@ pub enum Error<'args> {
     FlagToggleValue(&'args str, bool, sink::Error),
     /// Value parser for parse-flag failed.
     FlagParseValue(&'args str, &'args compat::OsStr, sink::Error),
-    /// Short flags are unknown.
-    ShortsUnknown(&'args compat::OsStr),
+    /// Value parser for the default value of a flag failed.
+    FlagDefaultValue(&'ctx str, &'args compat::OsStr, sink::Error),
+    /// Specified flag is required but was not given.
+    FlagMissing(&'ctx str),
+    /// More flags with defaults or requirements were given than supported
+    /// by the parser.
+    FlagCountExceeded(&'ctx str),
+    /// Specified short flag is not known.
+    ShortsUnknown(char),
+    /// Specified short flag needs a value.
+    ShortNeedsValue(char),
+    /// Value parser for short flag failed.
+    ShortValue(char, sink::Error),
     /// Parameter parser for command failed.
-    CommandParameter(alloc::string::String, &'args compat::OsStr, sink::Error),
+    CommandParameter(&'ctx str, &'args compat::OsStr, sink::Error),
     /// Specified command takes no parameters.
-    CommandTakesNoParameters(alloc::string::String, &'args compat::OsStr),
+    CommandTakesNoParameters(&'ctx str, &'args compat::OsStr),
+    /// Specified sub-command is unknown, but close to the suggested one.
+    CommandUnknownDidYouMean(&'args str, &'ctx str),
+    /// Sub-commands are nested deeper than supported by the parser. Carries
+    /// the argument naming the sub-command, or `None` if even the root
+    /// command does not fit.
+    CommandDepthExceeded(Option<&'args compat::OsStr>),
+}
+
+/// Parser error annotated with the zero-based index of the argument it was
+/// raised for. Flag values that are passed as separate argument are
+/// attributed to their flag. Errors that are not caused by any argument carry
+/// the index of the argument that would have been parsed next.
+#[derive(Debug)]
+pub struct SpannedError<'args, 'ctx> {
+    /// Index of the argument in the input iterator.
+    pub index: usize,
+    /// Underlying parser error.
+    pub error: Error<'args, 'ctx>,
 }
 
 // Type alias for value parsers.
@@ -52,6 +81,9 @@ pub enum Value<'args, Id> {
     Set(Sink<'args, Id, ()>),
     Toggle(Sink<'args, Id, bool>),
     Parse(Sink<'args, Id, &'args compat::OsStr>),
+    /// Nullary flag that can be repeated. The sink is invoked once for
+    /// every occurrence, so integer sinks count the occurrences.
+    Count(Sink<'args, Id, ()>),
 }
 
 /// An audited list of command-line configuration.
@@ -70,7 +102,10 @@ pub struct AuditedList<T: ?Sized> {
 #[derive(Debug)]
 pub struct Flag<'args, 'ctx, Id> {
     name: &'ctx str,
+    short: Option<char>,
     value: Value<'args, Id>,
+    default: Option<&'args str>,
+    required: bool,
 
     help_short: Option<&'ctx str>,
 }
@@ -78,6 +113,9 @@ pub struct Flag<'args, 'ctx, Id> {
 /// An audited list of command-line flags.
 pub type FlagList<'args, 'ctx, const N: usize, Id> = AuditedList<[Flag<'args, 'ctx, Id>; N]>;
 
+/// A boxed audited list of command-line flags with dynamic length.
+pub type FlagListBox<'args, 'ctx, Id> = alloc::boxed::Box<AuditedList<[Flag<'args, 'ctx, Id>]>>;
+
 /// A reference to an audited list of command-line flags.
 pub type FlagListRef<'args, 'ctx, Id> = &'ctx AuditedList<[Flag<'args, 'ctx, Id>]>;
 
@@ -98,6 +136,9 @@ pub struct Command<'args, 'ctx, Id> {
 /// An audited list of command-line sub-commands.
 pub type CommandList<'args, 'ctx, const N: usize, Id> = AuditedList<[Command<'args, 'ctx, Id>; N]>;
 
+/// A boxed audited list of command-line sub-commands with dynamic length.
+pub type CommandListBox<'args, 'ctx, Id> = alloc::boxed::Box<AuditedList<[Command<'args, 'ctx, Id>]>>;
+
 /// A reference to an audited list of command-line sub-commands.
 pub type CommandListRef<'args, 'ctx, Id> = &'ctx AuditedList<[Command<'args, 'ctx, Id>]>;
 
@@ -105,6 +146,38 @@ pub type CommandListRef<'args, 'ctx, Id> = &'ctx AuditedList<[Command<'args, 'ct
 /// possible caches for repeated parser operations.
 #[derive(Debug)]
 pub struct Parser {
+    fail_fast: bool,
+}
+
+/// Fixed-capacity list of parser state.
+///
+/// This is used by `Parser::parse_fixed()` to collect parser state without
+/// requiring any allocations. Once the capacity is exhausted, further entries
+/// are dropped and only their number is retained.
+#[derive(Debug)]
+pub struct FixedList<T, const N: usize> {
+    list: [Option<T>; N],
+    len: usize,
+    dropped: usize,
+}
+
+// Iterator adapter that counts the number of arguments consumed so far. This
+// allows attributing errors to their argument, even if further arguments are
+// consumed while handling it (e.g., flag values).
+struct Counted<Source> {
+    source: Source,
+    next: usize,
+}
+
+// Backing storage of the parser state. This allows running the parser on top
+// of allocated vectors as well as fixed-capacity lists. `push()` returns
+// `false` if the entry could not be stored. `pushed()` counts all pushed
+// entries, including the ones that could not be stored.
+trait Stack<T> {
+    fn len(&self) -> usize;
+    fn pushed(&self) -> usize;
+    fn get(&self, idx: usize) -> Option<&T>;
+    fn push(&mut self, v: T) -> bool;
 }
 
 /// Flag collector for standard `--help` flags. It implements `sink::Sink` and
@@ -114,7 +187,15 @@ pub struct Help<Id> {
     cell: core::cell::RefCell<Option<Id>>,
 }
 
-impl<'args> core::fmt::Display for Error<'args> {
+/// Shells supported by `Command::write_completion()`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Shell {
+    Bash,
+    Zsh,
+}
+
+impl<'args, 'ctx> core::fmt::Display for Error<'args, 'ctx> {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
         match self {
             Self::FlagInvalidUnicode(flag) => fmt.write_fmt(core::format_args!("Flag name contains invalid Unicode: {}", flag.to_string_lossy())),
@@ -127,13 +208,27 @@ impl<'args> core::fmt::Display for Error<'args> {
             Self::FlagSetValue(flag, e) => fmt.write_fmt(core::format_args!("Cannot parse value for flag `--{}`: {}", flag, e)),
             Self::FlagToggleValue(flag, v, e) => fmt.write_fmt(core::format_args!("Cannot parse value for toggle-flag `--[no-]{}={}`: {}", flag, v, e)),
             Self::FlagParseValue(flag, v, e) => fmt.write_fmt(core::format_args!("Cannot parse value for flag `--[no-]{}={}`: {}", flag, v.to_string_lossy(), e)),
-            Self::ShortsUnknown(flags) => fmt.write_fmt(core::format_args!("Invalid short flags: {}", flags.to_string_lossy())),
+            Self::FlagDefaultValue(flag, v, e) => fmt.write_fmt(core::format_args!("Cannot parse default value for flag `--{}={}`: {}", flag, v.to_string_lossy(), e)),
+            Self::FlagMissing(flag) => fmt.write_fmt(core::format_args!("Missing required flag: --{}", flag)),
+            Self::FlagCountExceeded(flag) => fmt.write_fmt(core::format_args!("Too many flags given at: --{}", flag)),
+            Self::ShortsUnknown(flag) => fmt.write_fmt(core::format_args!("Invalid short flag: -{}", flag)),
+            Self::ShortNeedsValue(flag) => fmt.write_fmt(core::format_args!("Short flag requires a value: -{}", flag)),
+            Self::ShortValue(flag, e) => fmt.write_fmt(core::format_args!("Cannot parse value for short flag `-{}`: {}", flag, e)),
             Self::CommandParameter(cmd, v, e) => fmt.write_fmt(core::format_args!("Cannot parse parameter for command `{} {}`: {}", cmd, v.to_string_lossy(), e)),
             Self::CommandTakesNoParameters(cmd, v) => fmt.write_fmt(core::format_args!("Invalid parameters for command: {} {}", cmd, v.to_string_lossy())),
+            Self::CommandUnknownDidYouMean(cmd, suggestion) => fmt.write_fmt(core::format_args!("Unknown sub-command: {} (did you mean `{}`?)", cmd, suggestion)),
+            Self::CommandDepthExceeded(Some(cmd)) => fmt.write_fmt(core::format_args!("Sub-commands nested too deeply at: {}", cmd.to_string_lossy())),
+            Self::CommandDepthExceeded(None) => fmt.write_fmt(core::format_args!("Sub-commands nested too deeply at the root command")),
         }
     }
 }
 
+impl<'args, 'ctx> core::fmt::Display for SpannedError<'args, 'ctx> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        fmt.write_fmt(core::format_args!("arg[{}]: {}", self.index, self.error))
+    }
+}
+
 // Allow creation of empty lists for all audited lists. This requires all
 // implementors to allow empty lists without auditing.
 impl<'a, T> Default for &'a AuditedList<[T]> {
@@ -144,6 +239,31 @@ impl<'a, T> Default for &'a AuditedList<[T]> {
     }
 }
 
+impl<T> AuditedList<[T]> {
+    // Wrap a sorted slice into an audited list. The caller must ensure the
+    // slice satisfies the invariants of the list type.
+    fn from_boxed(list: alloc::boxed::Box<[T]>) -> alloc::boxed::Box<Self> {
+        unsafe {
+            // SAFETY: `AuditedList` is `repr(transparent)`, so it has the
+            //         same layout as the wrapped slice.
+            alloc::boxed::Box::from_raw(
+                alloc::boxed::Box::into_raw(list) as *mut Self,
+            )
+        }
+    }
+
+    // Unwrap an audited list into its underlying slice.
+    fn into_boxed(self: alloc::boxed::Box<Self>) -> alloc::boxed::Box<[T]> {
+        unsafe {
+            // SAFETY: `AuditedList` is `repr(transparent)`, so it has the
+            //         same layout as the wrapped slice.
+            alloc::boxed::Box::from_raw(
+                alloc::boxed::Box::into_raw(self) as *mut [T],
+            )
+        }
+    }
+}
+
 impl<'args, 'ctx, Id> Flag<'args, 'ctx, Id> {
     fn with(
         name: &'ctx str,
@@ -152,7 +272,10 @@ impl<'args, 'ctx, Id> Flag<'args, 'ctx, Id> {
     ) -> Self {
         Self {
             name: name,
+            short: None,
             value: value,
+            default: None,
+            required: false,
 
             help_short: help_short,
         }
@@ -167,6 +290,34 @@ impl<'args, 'ctx, Id> Flag<'args, 'ctx, Id> {
     ) -> Self {
         Self::with(name, value, help_short)
     }
+
+    /// Assign a short name to the flag, which allows specifying it as `-c`
+    /// on the command-line. Short flags can be bundled into a single
+    /// argument (e.g., `-abc`).
+    pub fn with_short(mut self, short: char) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    /// Assign a default value to the flag. If the flag is not given on the
+    /// command-line, the default is pushed into its value parser once all
+    /// arguments were parsed. Defaults only apply to flags that take values,
+    /// and only if their command was selected on the command-line.
+    pub fn with_default(mut self, default: &'args str) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Mark the flag as required. If the flag is not given on the
+    /// command-line and has no default value, parsing fails with
+    /// `Error::FlagMissing`. A flag counts as given even if its value fails
+    /// to parse, in which case only the parsing error is reported. Like
+    /// defaults, this is only enforced if the command of the flag was
+    /// selected on the command-line.
+    pub fn with_required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
 }
 
 impl<'args, 'ctx, const N: usize, Id> FlagList<'args, 'ctx, N, Id> {
@@ -181,6 +332,32 @@ impl<'args, 'ctx, const N: usize, Id> FlagList<'args, 'ctx, N, Id> {
     }
 }
 
+impl<'args, 'ctx, Id> AuditedList<[Flag<'args, 'ctx, Id>]> {
+    /// Create an audited list of command-line flags with dynamic length from
+    /// user configuration. Like `FlagList::with()`, this sorts the flags by
+    /// their names.
+    pub fn with_vec(mut list: alloc::vec::Vec<Flag<'args, 'ctx, Id>>) -> FlagListBox<'args, 'ctx, Id> {
+        list.sort_unstable_by_key(|v| v.name);
+        Self::from_boxed(list.into_boxed_slice())
+    }
+}
+
+impl<'args, 'ctx, Id> core::iter::FromIterator<Flag<'args, 'ctx, Id>> for FlagListBox<'args, 'ctx, Id> {
+    fn from_iter<I: IntoIterator<Item = Flag<'args, 'ctx, Id>>>(iter: I) -> Self {
+        AuditedList::<[Flag<'args, 'ctx, Id>]>::with_vec(iter.into_iter().collect())
+    }
+}
+
+// Extending the list re-sorts it to retain the audit guarantees.
+impl<'args, 'ctx, Id> core::iter::Extend<Flag<'args, 'ctx, Id>> for FlagListBox<'args, 'ctx, Id> {
+    fn extend<I: IntoIterator<Item = Flag<'args, 'ctx, Id>>>(&mut self, iter: I) {
+        let this = core::mem::replace(self, AuditedList::<[Flag<'args, 'ctx, Id>]>::with_vec(alloc::vec::Vec::new()));
+        let mut list = this.into_boxed().into_vec();
+        list.extend(iter);
+        *self = AuditedList::<[Flag<'args, 'ctx, Id>]>::with_vec(list);
+    }
+}
+
 impl<'args, 'ctx, Id> Command<'args, 'ctx, Id> {
     fn with(
         id: Id,
@@ -227,6 +404,22 @@ impl<'args, 'ctx, Id> Command<'args, 'ctx, Id> {
         }
     }
 
+    // Find the sub-command closest to the given name, if it is within an
+    // edit distance that likely indicates a typo. Ties are resolved in favor
+    // of the first sub-command in sort order.
+    fn suggest_command(
+        &self,
+        name: &str,
+    ) -> Option<&'ctx str> {
+        let bound = core::cmp::max(1, name.chars().count() / 3);
+
+        self.commands.list.iter()
+            .map(|v| (crate::str::edit_distance(name, v.name), v.name))
+            .filter(|(distance, _)| *distance <= bound)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, v)| v)
+    }
+
     fn find_flag(
         &self,
         name: &str,
@@ -240,6 +433,16 @@ impl<'args, 'ctx, Id> Command<'args, 'ctx, Id> {
         }
     }
 
+    // Find the flag with the given short name. Flag lists are sorted by
+    // their long names, so this scans the list linearly. Short names are
+    // limited to single characters, so lists are expected to be small.
+    fn find_short(
+        &self,
+        short: char,
+    ) -> Option<&'ctx Flag<'args, 'ctx, Id>> {
+        self.flags.list.iter().find(|v| v.short == Some(short))
+    }
+
     /// Write usage information to the specified format stream. This will
     /// include short explanations for the individual items.
     ///
@@ -281,14 +484,26 @@ impl<'args, 'ctx, Id> Command<'args, 'ctx, Id> {
                 .map(|v| v.name.len())
                 .max()
                 .unwrap();
+            let shorts = flags.clone().any(|v| v.short.is_some());
 
             for flag in flags {
+                match (shorts, flag.short) {
+                    (false, _) => dst.write_str("    ")?,
+                    (true, None) => dst.write_str("        ")?,
+                    (true, Some(v)) => dst.write_fmt(core::format_args!("    -{}, ", v))?,
+                }
                 dst.write_fmt(core::format_args!(
-                    "    --{0:1$}  {2}\n",
+                    "--{0:1$}  {2}",
                     flag.name,
                     maxlen,
                     flag.help_short.unwrap(),
                 ))?;
+                // Defaults only apply to flags that take values, so do not
+                // advertise them for anything else.
+                match (&flag.value, flag.default) {
+                    (Value::Parse(_), Some(v)) => dst.write_fmt(core::format_args!(" (default: {})\n", v))?,
+                    _ => dst.write_str("\n")?,
+                }
             }
         }
 
@@ -316,6 +531,243 @@ impl<'args, 'ctx, Id> Command<'args, 'ctx, Id> {
 
         Ok(())
     }
+
+    // Invoke `f` for this command and all its sub-commands, in sort order.
+    // The trace passed to `f` ends with the visited command.
+    fn walk<'a>(
+        &'a self,
+        trace: &mut alloc::vec::Vec<&'a Self>,
+        f: &mut dyn FnMut(&[&'a Self]) -> Result<(), core::fmt::Error>,
+    ) -> Result<(), core::fmt::Error> {
+        trace.push(self);
+        f(trace)?;
+        for cmd in self.commands.list.iter() {
+            cmd.walk(trace, f)?;
+        }
+        trace.pop();
+        Ok(())
+    }
+
+    // Yield the completion candidates for the last command of the trace,
+    // together with their help text. Flags of parent commands are valid as
+    // well, unless shadowed by a flag of the same name.
+    fn completion_words(
+        trace: &[&Self],
+    ) -> alloc::vec::Vec<(alloc::string::String, Option<&'ctx str>)> {
+        let mut words: alloc::vec::Vec<(alloc::string::String, _)> = alloc::vec::Vec::new();
+        let mut push = |word: alloc::string::String, help| {
+            if !words.iter().any(|(v, _)| *v == word) {
+                words.push((word, help));
+            }
+        };
+
+        for cmd in trace.iter().rev() {
+            for flag in cmd.flags.list.iter() {
+                push(alloc::format!("--{}", flag.name), flag.help_short);
+                if let Value::Toggle(_) = flag.value {
+                    push(alloc::format!("--no-{}", flag.name), flag.help_short);
+                }
+                if let Some(v) = flag.short {
+                    push(alloc::format!("-{}", v), flag.help_short);
+                }
+            }
+        }
+
+        if let Some(cmd) = trace.last() {
+            for sub in cmd.commands.list.iter() {
+                push(sub.name.into(), sub.help_short);
+            }
+        }
+
+        words
+    }
+
+    // Yield all forms of the flags that take their value from the next
+    // argument, valid for the last command of the trace.
+    fn completion_values(
+        trace: &[&Self],
+    ) -> alloc::vec::Vec<alloc::string::String> {
+        let mut values = alloc::vec::Vec::new();
+
+        for cmd in trace.iter().rev() {
+            for flag in cmd.flags.list.iter() {
+                if let Value::Parse(_) = flag.value {
+                    values.push(alloc::format!("--{}", flag.name));
+                    if let Some(v) = flag.short {
+                        values.push(alloc::format!("-{}", v));
+                    }
+                }
+            }
+        }
+
+        values
+    }
+
+    // Yield the names of all commands of the trace, separated by spaces.
+    fn completion_path(trace: &[&Self]) -> alloc::string::String {
+        trace.iter().map(|v| v.name).collect::<alloc::vec::Vec<_>>().join(" ")
+    }
+
+    // Write the given string with a backslash in front of every character
+    // contained in `special`.
+    fn completion_escape(
+        dst: &mut dyn core::fmt::Write,
+        v: &str,
+        special: &str,
+    ) -> Result<(), core::fmt::Error> {
+        for c in v.chars() {
+            if special.contains(c) {
+                dst.write_char('\\')?;
+            }
+            dst.write_char(c)?;
+        }
+        Ok(())
+    }
+
+    // Write the arms of a shell `case` statement that follow sub-commands
+    // and skip flag values when walking the words of a command-line.
+    fn completion_arms(
+        &self,
+        dst: &mut dyn core::fmt::Write,
+        open: &str,
+    ) -> Result<(), core::fmt::Error> {
+        self.walk(&mut alloc::vec::Vec::new(), &mut |trace| {
+            let path = Self::completion_path(trace);
+
+            for sub in trace.last().unwrap().commands.list.iter() {
+                dst.write_fmt(core::format_args!("            {}\"", open))?;
+                Self::completion_escape(dst, &path, "\"$`\\")?;
+                dst.write_char(' ')?;
+                Self::completion_escape(dst, sub.name, "\"$`\\")?;
+                dst.write_str("\") cmdpath=\"")?;
+                Self::completion_escape(dst, &path, "\"$`\\")?;
+                dst.write_char(' ')?;
+                Self::completion_escape(dst, sub.name, "\"$`\\")?;
+                dst.write_str("\" ;;\n")?;
+            }
+
+            for value in Self::completion_values(trace) {
+                dst.write_fmt(core::format_args!("            {}\"", open))?;
+                Self::completion_escape(dst, &path, "\"$`\\")?;
+                dst.write_char(' ')?;
+                Self::completion_escape(dst, &value, "\"$`\\")?;
+                dst.write_str("\") ((i++)) ;;\n")?;
+            }
+
+            Ok(())
+        })
+    }
+
+    // Yield the name of the shell function implementing completions.
+    fn completion_function(&self) -> alloc::string::String {
+        let mut r = alloc::string::String::from("_");
+        r.extend(self.name.chars().map(|v| if v.is_ascii_alphanumeric() { v } else { '_' }));
+        r
+    }
+
+    fn completion_bash(
+        &self,
+        dst: &mut dyn core::fmt::Write,
+    ) -> Result<(), core::fmt::Error> {
+        let function = self.completion_function();
+
+        dst.write_fmt(core::format_args!("{}() {{\n", function))?;
+        dst.write_str("    local cur cmdpath i\n")?;
+        dst.write_str("    cur=\"${COMP_WORDS[COMP_CWORD]}\"\n")?;
+        dst.write_str("    cmdpath=\"")?;
+        Self::completion_escape(dst, self.name, "\"$`\\")?;
+        dst.write_str("\"\n")?;
+        dst.write_str("    for ((i = 1; i < COMP_CWORD; i++)); do\n")?;
+        dst.write_str("        case \"${cmdpath} ${COMP_WORDS[i]}\" in\n")?;
+        self.completion_arms(dst, "")?;
+        dst.write_str("        esac\n")?;
+        dst.write_str("    done\n")?;
+        dst.write_str("    case \"${cmdpath}\" in\n")?;
+
+        self.walk(&mut alloc::vec::Vec::new(), &mut |trace| {
+            dst.write_str("        \"")?;
+            Self::completion_escape(dst, &Self::completion_path(trace), "\"$`\\")?;
+            dst.write_str("\") COMPREPLY=($(compgen -W \"")?;
+            for (idx, (word, _)) in Self::completion_words(trace).iter().enumerate() {
+                if idx > 0 {
+                    dst.write_char(' ')?;
+                }
+                Self::completion_escape(dst, word, "\"$`\\")?;
+            }
+            dst.write_str("\" -- \"${cur}\")) ;;\n")
+        })?;
+
+        dst.write_str("    esac\n")?;
+        dst.write_str("}\n")?;
+        dst.write_fmt(core::format_args!("complete -F {} {}\n", function, self.name))?;
+
+        Ok(())
+    }
+
+    fn completion_zsh(
+        &self,
+        dst: &mut dyn core::fmt::Write,
+    ) -> Result<(), core::fmt::Error> {
+        let function = self.completion_function();
+
+        dst.write_fmt(core::format_args!("#compdef {}\n\n", self.name))?;
+        dst.write_fmt(core::format_args!("{}() {{\n", function))?;
+        dst.write_str("    local cmdpath i\n")?;
+        dst.write_str("    local -a candidates\n")?;
+        dst.write_str("    cmdpath=\"")?;
+        Self::completion_escape(dst, self.name, "\"$`\\")?;
+        dst.write_str("\"\n")?;
+        dst.write_str("    for ((i = 2; i < CURRENT; i++)); do\n")?;
+        dst.write_str("        case \"${cmdpath} ${words[i]}\" in\n")?;
+        self.completion_arms(dst, "(")?;
+        dst.write_str("        esac\n")?;
+        dst.write_str("    done\n")?;
+        dst.write_str("    case \"${cmdpath}\" in\n")?;
+
+        self.walk(&mut alloc::vec::Vec::new(), &mut |trace| {
+            dst.write_str("        (\"")?;
+            Self::completion_escape(dst, &Self::completion_path(trace), "\"$`\\")?;
+            dst.write_str("\")\n")?;
+            dst.write_str("            candidates=(\n")?;
+            for (word, help) in Self::completion_words(trace) {
+                // Candidates are single-quoted, so quotes must be closed
+                // and re-opened. Colons in the word separate the help.
+                dst.write_str("                '")?;
+                Self::completion_escape(dst, &word.replace('\'', "'\\''"), ":")?;
+                if let Some(v) = help {
+                    dst.write_fmt(core::format_args!(":{}", v.replace('\'', "'\\''")))?;
+                }
+                dst.write_str("'\n")?;
+            }
+            dst.write_str("            )\n")?;
+            dst.write_str("            _describe 'command' candidates\n")?;
+            dst.write_str("            ;;\n")
+        })?;
+
+        dst.write_str("    esac\n")?;
+        dst.write_str("}\n\n")?;
+        dst.write_fmt(core::format_args!("{} \"$@\"\n", function))?;
+
+        Ok(())
+    }
+
+    /// Write a shell completion script for this command to the specified
+    /// format stream. The script completes all flags and sub-commands of
+    /// the command tree. Flags that can be toggled are offered with and
+    /// without `no-` prefix.
+    ///
+    /// The script is static and derived only from the command definition.
+    /// Since all lists are sorted when audited, the output is deterministic.
+    pub fn write_completion(
+        &self,
+        shell: Shell,
+        dst: &mut dyn core::fmt::Write,
+    ) -> Result<(), core::fmt::Error> {
+        match shell {
+            Shell::Bash => self.completion_bash(dst),
+            Shell::Zsh => self.completion_zsh(dst),
+        }
+    }
 }
 
 impl<'args, 'ctx, const N: usize, Id> CommandList<'args, 'ctx, N, Id> {
@@ -330,20 +782,152 @@ impl<'args, 'ctx, const N: usize, Id> CommandList<'args, 'ctx, N, Id> {
     }
 }
 
+impl<'args, 'ctx, Id> AuditedList<[Command<'args, 'ctx, Id>]> {
+    /// Create an audited list of command-line sub-commands with dynamic
+    /// length from user configuration. Like `CommandList::with()`, this sorts
+    /// the sub-commands by their names.
+    pub fn with_vec(mut list: alloc::vec::Vec<Command<'args, 'ctx, Id>>) -> CommandListBox<'args, 'ctx, Id> {
+        list.sort_unstable_by_key(|v| v.name);
+        Self::from_boxed(list.into_boxed_slice())
+    }
+}
+
+impl<'args, 'ctx, Id> core::iter::FromIterator<Command<'args, 'ctx, Id>> for CommandListBox<'args, 'ctx, Id> {
+    fn from_iter<I: IntoIterator<Item = Command<'args, 'ctx, Id>>>(iter: I) -> Self {
+        AuditedList::<[Command<'args, 'ctx, Id>]>::with_vec(iter.into_iter().collect())
+    }
+}
+
+// Extending the list re-sorts it to retain the audit guarantees.
+impl<'args, 'ctx, Id> core::iter::Extend<Command<'args, 'ctx, Id>> for CommandListBox<'args, 'ctx, Id> {
+    fn extend<I: IntoIterator<Item = Command<'args, 'ctx, Id>>>(&mut self, iter: I) {
+        let this = core::mem::replace(self, AuditedList::<[Command<'args, 'ctx, Id>]>::with_vec(alloc::vec::Vec::new()));
+        let mut list = this.into_boxed().into_vec();
+        list.extend(iter);
+        *self = AuditedList::<[Command<'args, 'ctx, Id>]>::with_vec(list);
+    }
+}
+
+impl<T, const N: usize> FixedList<T, N> {
+    fn new() -> Self {
+        Self {
+            list: core::array::from_fn(|_| None),
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Return the number of entries stored in the list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return whether no entries are stored in the list.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the number of entries that were dropped because the capacity
+    /// of the list was exhausted.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Return a reference to the entry at the specified index, or `None` if
+    /// out of bounds.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.list[..self.len].get(idx).and_then(|v| v.as_ref())
+    }
+
+    /// Iterate all entries stored in the list.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.list[..self.len].iter().filter_map(|v| v.as_ref())
+    }
+}
+
+impl<T, const N: usize> Stack<T> for FixedList<T, N> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn pushed(&self) -> usize {
+        self.len + self.dropped
+    }
+
+    fn get(&self, idx: usize) -> Option<&T> {
+        FixedList::get(self, idx)
+    }
+
+    fn push(&mut self, v: T) -> bool {
+        if self.len < N {
+            self.list[self.len] = Some(v);
+            self.len += 1;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+}
+
+impl<T> Stack<T> for alloc::vec::Vec<T> {
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn pushed(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn get(&self, idx: usize) -> Option<&T> {
+        <[T]>::get(self, idx)
+    }
+
+    fn push(&mut self, v: T) -> bool {
+        alloc::vec::Vec::push(self, v);
+        true
+    }
+}
+
+impl<'args, Source> Iterator for Counted<Source>
+where
+    Source: Iterator<Item = &'args compat::OsStr>,
+{
+    type Item = &'args compat::OsStr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let v = self.source.next()?;
+        self.next += 1;
+        Some(v)
+    }
+}
+
 impl Parser {
     /// Create a new command-line parser with the default settings. This parser
     /// can be used to parse multiple command-lines, if desired.
     pub fn new() -> Self {
         Self {
+            fail_fast: false,
         }
     }
 
-    fn lookup_flag<'args, 'ctx, Id>(
-        history: &alloc::vec::Vec<&'ctx Command<'args, 'ctx, Id>>,
+    /// Select whether the parser stops at the first error. By default, the
+    /// parser continues after errors and collects all of them. With
+    /// fail-fast operation, the remaining arguments are not looked at once
+    /// an error occurred, and thus only a single error is reported.
+    pub fn set_fail_fast(&mut self, fail_fast: bool) {
+        self.fail_fast = fail_fast;
+    }
+
+    fn lookup_flag<'args, 'ctx, Id, History>(
+        history: &History,
         flag: &str,
-    ) -> Option<&'ctx Flag<'args, 'ctx, Id>> {
-        for cmd in history.iter().rev() {
-            if let Some(v) = cmd.find_flag(flag) {
+    ) -> Option<&'ctx Flag<'args, 'ctx, Id>>
+    where
+        History: Stack<&'ctx Command<'args, 'ctx, Id>>,
+    {
+        for idx in (0..history.len()).rev() {
+            if let Some(v) = history.get(idx).and_then(|v| v.find_flag(flag)) {
                 return Some(v);
             }
         }
@@ -351,17 +935,90 @@ impl Parser {
         None
     }
 
-    fn parse_flag<'args, 'ctx, Id, Source>(
+    fn lookup_short<'args, 'ctx, Id, History>(
+        history: &History,
+        short: char,
+    ) -> Option<&'ctx Flag<'args, 'ctx, Id>>
+    where
+        History: Stack<&'ctx Command<'args, 'ctx, Id>>,
+    {
+        for idx in (0..history.len()).rev() {
+            if let Some(v) = history.get(idx).and_then(|v| v.find_short(short)) {
+                return Some(v);
+            }
+        }
+
+        None
+    }
+
+    // Check whether a flag was encountered on the command-line.
+    fn seen<'args, 'ctx, Id, Seen>(
+        seen: &Seen,
+        flag: &'ctx Flag<'args, 'ctx, Id>,
+    ) -> bool
+    where
+        Seen: Stack<&'ctx Flag<'args, 'ctx, Id>>,
+    {
+        (0..seen.len())
+            .filter_map(|v| seen.get(v))
+            .any(|v| core::ptr::eq(*v, flag))
+    }
+
+    // Record that a flag was encountered on the command-line, regardless of
+    // whether its value can be parsed. Only flags with defaults or
+    // requirements are tracked, since nothing else depends on it.
+    fn see<'args, 'ctx, Id, Seen>(
+        seen: &mut Seen,
+        flag: &'ctx Flag<'args, 'ctx, Id>,
+    ) -> Result<(), Error<'args, 'ctx>>
+    where
+        Seen: Stack<&'ctx Flag<'args, 'ctx, Id>>,
+    {
+        if (flag.default.is_none() && !flag.required)
+            || Self::seen(seen, flag)
+            || seen.push(flag)
+        {
+            Ok(())
+        } else {
+            Err(Error::FlagCountExceeded(flag.name))
+        }
+    }
+
+    // Get the UTF-8 prefix of the argument. Anything we can parse must be
+    // valid UTF-8, but some of it might be trailed by arbitrary OS data (e.g.,
+    // `--path=./some/path` can contain trailing non-UTF-8 data). This performs
+    // a UTF-8 check on all arguments, but avoids any allocation. Hence, you
+    // can parse large data chunks as arguments without incurring anything
+    // more expensive than a UTF-8 check. For anything bigger than this, you
+    // likely want side channels, anyway.
+    fn split_utf8(arg_os: &compat::OsStr) -> (&str, bool) {
+        let arg_bytes = arg_os.as_encoded_bytes();
+        match core::str::from_utf8(arg_bytes) {
+            Ok(v) => (v, false),
+            Err(e) => unsafe {
+                // SAFETY: `Utf8Error::valid_up_to()` points exactly at the
+                //         first byte past a valid UTF-8 section, so we can
+                //         safely cast it to a `str` unchecked.
+                let v = &arg_bytes[..e.valid_up_to()];
+                (core::str::from_utf8_unchecked(v), true)
+            },
+        }
+    }
+
+    fn parse_flag<'args, 'ctx, Id, Source, History, Seen>(
         &mut self,
         arguments: &mut Source,
         current: &'ctx Command<'args, 'ctx, Id>,
-        history: &alloc::vec::Vec<&'ctx Command<'args, 'ctx, Id>>,
+        history: &History,
+        seen: &mut Seen,
         flag_str: &'args str,
         value_opt: Option<&'args compat::OsStr>,
-    ) -> Result<(), Error<'args>>
+    ) -> Result<&'ctx str, Error<'args, 'ctx>>
     where
         Id: Clone,
         Source: Iterator<Item = &'args compat::OsStr>,
+        History: Stack<&'ctx Command<'args, 'ctx, Id>>,
+        Seen: Stack<&'ctx Flag<'args, 'ctx, Id>>,
     {
         let (flag, flag_toggled) = match Self::lookup_flag(history, flag_str) {
             Some(v) => (v, None),
@@ -373,15 +1030,18 @@ impl Parser {
                 }
             },
         };
+        let r_seen = Self::see(seen, flag);
 
-        match (&flag.value, flag_toggled, value_opt) {
+        let r = match (&flag.value, flag_toggled, value_opt) {
             (Value::Set(_), Some(v), _)
+            | (Value::Count(_), Some(v), _)
             | (Value::Parse(_), Some(v), _) => {
                 // Flag only exists without `no-*` prefix, but this flag cannot
                 // be toggled. Hence, signal an error and ignore the argument.
                 Err(Error::FlagCannotBeToggled(v))
             },
-            (Value::Set(_), None, Some(v)) => {
+            (Value::Set(_), None, Some(v))
+            | (Value::Count(_), None, Some(v)) => {
                 // Flag is nullary but a value was assigned inline. Signal an
                 // error and ignore the argument.
                 Err(Error::FlagTakesNoValue(flag_str, v))
@@ -391,8 +1051,9 @@ impl Parser {
                 // error and ignore the argument.
                 Err(Error::FlagToggleTakesNoValue(t.unwrap_or(flag_str), v))
             },
-            (Value::Set(s), None, None) => {
-                // Correct use of settable-flag.
+            (Value::Set(s), None, None)
+            | (Value::Count(s), None, None) => {
+                // Correct use of settable-flag or counting-flag.
                 s.push(current.id.clone(), ()).map_err(
                     |e| Error::FlagSetValue(flag_str, e),
                 )
@@ -418,18 +1079,124 @@ impl Parser {
                     |e| Error::FlagParseValue(flag_str, v, e),
                 )
             },
+        };
+
+        r.and(r_seen).map(|()| flag.name)
+    }
+
+    fn parse_short<'args, 'ctx, Id, Source, History, Seen>(
+        &mut self,
+        arguments: &mut Source,
+        current: &'ctx Command<'args, 'ctx, Id>,
+        history: &History,
+        seen: &mut Seen,
+        arg_os: &'args compat::OsStr,
+        mut applied: Option<&mut alloc::collections::BTreeSet<&'ctx str>>,
+    ) -> Result<(), Error<'args, 'ctx>>
+    where
+        Id: Clone,
+        Source: Iterator<Item = &'args compat::OsStr>,
+        History: Stack<&'ctx Command<'args, 'ctx, Id>>,
+        Seen: Stack<&'ctx Flag<'args, 'ctx, Id>>,
+    {
+        // Handle each short flag in order. Nullary flags can be bundled, but
+        // a flag that takes a value consumes the remainder of the argument,
+        // or the next argument if nothing remains. Any unknown flag stops
+        // parsing of this argument, but previous flags stay applied.
+        let arg_bytes = arg_os.as_encoded_bytes();
+        let (arg_front, arg_tail) = Self::split_utf8(arg_os);
+
+        for (idx, short) in arg_front[1..].char_indices() {
+            let flag = Self::lookup_short(history, short)
+                .ok_or(Error::ShortsUnknown(short))?;
+            let r_seen = Self::see(seen, flag);
+
+            let consumed = match flag.value {
+                Value::Set(s) | Value::Count(s) => {
+                    s.push(current.id.clone(), ()).map_err(
+                        |e| Error::ShortValue(short, e),
+                    )?;
+                    false
+                },
+                Value::Toggle(s) => {
+                    s.push(current.id.clone(), true).map_err(
+                        |e| Error::ShortValue(short, e),
+                    )?;
+                    false
+                },
+                Value::Parse(s) => {
+                    let offset = 1 + idx + short.len_utf8();
+                    let v = if offset < arg_bytes.len() {
+                        unsafe {
+                            // SAFETY: We split off a well-defined UTF-8
+                            //         sequence, which is allowed for
+                            //         `std::ffi::OsStr`.
+                            compat::OsStr::from_encoded_bytes_unchecked(
+                                &arg_bytes[offset..],
+                            )
+                        }
+                    } else {
+                        arguments.next().ok_or(Error::ShortNeedsValue(short))?
+                    };
+                    s.push(current.id.clone(), v).map_err(
+                        |e| Error::ShortValue(short, e),
+                    )?;
+                    true
+                },
+            };
+
+            r_seen?;
+            if let Some(ref mut v) = applied {
+                v.insert(flag.name);
+            }
+            if consumed {
+                return Ok(());
+            }
+        }
+
+        // Trailing data that is not valid UTF-8 cannot name any short flag.
+        if arg_tail {
+            Err(Error::ShortsUnknown(char::REPLACEMENT_CHARACTER))
+        } else {
+            Ok(())
         }
     }
 
-    fn parse_short<'args, 'ctx, Id>(
+    fn parse_unseen<'args, 'ctx, Id, History, Seen, Errors>(
         &mut self,
-        _history: &alloc::vec::Vec<&'ctx Command<'args, 'ctx, Id>>,
-        short_str: &'args compat::OsStr,
-    ) -> Result<(), Error<'args>> {
-        // Our configuration does not allow specifying short options, so none
-        // of these can ever match. Hence, treat them all as invalid for now
-        // and signal an error. Then ignore the argument and continue.
-        Err(Error::ShortsUnknown(short_str))
+        current: &'ctx Command<'args, 'ctx, Id>,
+        history: &History,
+        seen: &Seen,
+        errors: &mut Errors,
+        index: usize,
+    )
+    where
+        Id: Clone,
+        History: Stack<&'ctx Command<'args, 'ctx, Id>>,
+        Seen: Stack<&'ctx Flag<'args, 'ctx, Id>>,
+        Errors: Stack<SpannedError<'args, 'ctx>>,
+    {
+        // Push the defaults of all flags that were not seen, running them
+        // through the same value parsers as values from the command-line.
+        // Required flags without default are reported as missing.
+        for cmd in (0..history.len()).filter_map(|v| history.get(v)) {
+            for flag in cmd.flags.list.iter().filter(|v| !Self::seen(seen, v)) {
+                let r = match (&flag.value, flag.default) {
+                    (Value::Parse(s), Some(v)) => s.push(current.id.clone(), v.into()).map_err(
+                        |e| Error::FlagDefaultValue(flag.name, v.into(), e),
+                    ),
+                    _ if flag.required => Err(Error::FlagMissing(flag.name)),
+                    _ => Ok(()),
+                };
+
+                if let Err(e) = r {
+                    errors.push(SpannedError { index: index, error: e });
+                    if self.fail_fast {
+                        return;
+                    }
+                }
+            }
+        }
     }
 
     fn parse_command<'args, 'ctx, Id>(
@@ -437,7 +1204,7 @@ impl Parser {
         command: &'ctx Command<'args, 'ctx, Id>,
         arg_os: &'args compat::OsStr,
         arg_str_opt: Option<&'args str>,
-    ) -> Result<Option<&'ctx Command<'args, 'ctx, Id>>, Error<'args>>
+    ) -> Result<Option<&'ctx Command<'args, 'ctx, Id>>, Error<'args, 'ctx>>
     where
         Id: Clone,
     {
@@ -450,52 +1217,61 @@ impl Parser {
             Ok(Some(sub))
         } else if let Some(ref v) = command.parameters {
             v.push(command.id.clone(), arg_os).map_err(
-                |e| Error::CommandParameter(command.name.into(), arg_os, e),
+                |e| Error::CommandParameter(command.name, arg_os, e),
             )?;
             Ok(None)
+        } else if let Some((arg_str, suggestion)) = arg_str_opt.and_then(
+            |v| command.suggest_command(v).map(|s| (v, s)),
+        ) {
+            // The command takes no parameters, so this was likely meant as
+            // sub-command. Suggest the closest one, if any.
+            Err(Error::CommandUnknownDidYouMean(arg_str, suggestion))
         } else {
-            Err(Error::CommandTakesNoParameters(command.name.into(), arg_os))
+            Err(Error::CommandTakesNoParameters(command.name, arg_os))
         }
     }
 
-    fn parse_root<'args, 'ctx, Id, Source>(
+    fn parse_root<'args, 'ctx, Id, Source, History, Seen, Errors>(
         &mut self,
-        mut arguments: Source,
+        arguments: Source,
         command: &'ctx Command<'args, 'ctx, Id>,
-    ) -> Result<Id, alloc::boxed::Box<[Error<'args>]>>
+        history: &mut History,
+        seen: &mut Seen,
+        errors: &mut Errors,
+        mut applied: Option<&mut alloc::collections::BTreeSet<&'ctx str>>,
+    ) -> Id
     where
         Id: Clone,
         Source: Iterator<Item = &'args compat::OsStr>,
+        History: Stack<&'ctx Command<'args, 'ctx, Id>>,
+        Seen: Stack<&'ctx Flag<'args, 'ctx, Id>>,
+        Errors: Stack<SpannedError<'args, 'ctx>>,
     {
-        let mut errors = alloc::vec::Vec::new();
-        let mut history = alloc::vec![command];
+        let mut arguments = Counted { source: arguments, next: 0 };
         let mut current = command;
 
+        // The root command is always part of the history, unless the
+        // history has no capacity at all.
+        if !history.push(command) {
+            errors.push(SpannedError {
+                index: 0,
+                error: Error::CommandDepthExceeded(None),
+            });
+        }
+
         loop {
+            if self.fail_fast && errors.pushed() > 0 {
+                break;
+            }
+
             let arg_os = match arguments.next() {
                 None => break,
                 Some(v) => v,
             };
+            let index = arguments.next - 1;
 
-            // Get the UTF-8 prefix of the argument. Anything we can parse must
-            // be valid UTF-8, but some of it might be trailed by arbitrary OS
-            // data (e.g., `--path=./some/path` can contain trailing non-UTF-8
-            // data). This performs a UTF-8 check on all arguments, but avoids
-            // any allocation. Hence, you can parse large data chunks as
-            // arguments without incurring anything more expensive than a UTF-8
-            // check. For anything bigger than this, you likely want side
-            // channels, anyway.
             let arg_bytes = arg_os.as_encoded_bytes();
-            let (arg_front, arg_tail) = match core::str::from_utf8(arg_bytes) {
-                Ok(v) => (v, false),
-                Err(e) => unsafe {
-                    // SAFETY: `Utf8Error::valid_up_to()` points exactly at the
-                    //         first byte past a valid UTF-8 section, so we can
-                    //         safely cast it to a `str` unchecked.
-                    let v = &arg_bytes[..e.valid_up_to()];
-                    (core::str::from_utf8_unchecked(v), true)
-                },
-            };
+            let (arg_front, arg_tail) = Self::split_utf8(arg_os);
 
             if let Some(arg_front_dd) = arg_front.strip_prefix("--") {
                 // This argument starts with `--` and thus specifies a flag.
@@ -522,7 +1298,7 @@ impl Parser {
                         // We have invalid UTF-8 as part of the flag name
                         // (i.e., before any possible `=`). This cannot match
                         // any flag we know, so signal an error and ignore it.
-                        errors.push(Error::FlagInvalidUnicode(arg_os));
+                        errors.push(SpannedError { index: index, error: Error::FlagInvalidUnicode(arg_os) });
                     },
 
                     ("", false, None) => {
@@ -531,21 +1307,37 @@ impl Parser {
                         if let Some(ref p) = current.parameters {
                             while let Some(v) = arguments.next() {
                                 if let Err(e) = p.push(current.id.clone(), v) {
-                                    errors.push(Error::CommandParameter(
-                                        current.name.into(), v, e,
-                                    ));
+                                    errors.push(SpannedError {
+                                        index: arguments.next - 1,
+                                        error: Error::CommandParameter(
+                                            current.name, v, e,
+                                        ),
+                                    });
+                                    if self.fail_fast {
+                                        break;
+                                    }
                                 }
                             }
                         } else if let Some(v) = arguments.next() {
-                            errors.push(Error::CommandTakesNoParameters(current.name.into(), v));
+                            errors.push(SpannedError {
+                                index: arguments.next - 1,
+                                error: Error::CommandTakesNoParameters(current.name, v),
+                            });
                         }
                     },
 
                     (_, false, _) => {
                         // We got a complete flag with or without value. Look
                         // up the flag and pass the value along, if required.
-                        if let Err(e) = self.parse_flag(&mut arguments, &current, &history, flag, value) {
-                            errors.push(e);
+                        match self.parse_flag(&mut arguments, &current, history, seen, flag, value) {
+                            Ok(name) => {
+                                if let Some(ref mut v) = applied {
+                                    v.insert(name);
+                                }
+                            },
+                            Err(e) => {
+                                errors.push(SpannedError { index: index, error: e });
+                            },
                         }
                     },
                 }
@@ -554,8 +1346,15 @@ impl Parser {
                 // combined into a single argument. Note that a single dash
                 // without following flags has no special meaning and we avoid
                 // handling it here.
-                if let Err(e) = self.parse_short(&history, arg_os) {
-                    errors.push(e);
+                if let Err(e) = self.parse_short(
+                    &mut arguments,
+                    &current,
+                    history,
+                    seen,
+                    arg_os,
+                    applied.as_deref_mut(),
+                ) {
+                    errors.push(SpannedError { index: index, error: e });
                 }
             } else {
                 // This argument is either a sub-command or a parameter of the
@@ -568,19 +1367,30 @@ impl Parser {
                 ) {
                     Ok(None) => {},
                     Ok(Some(next)) => {
-                        current = next;
-                        history.push(current);
+                        // If the history cannot hold the sub-command, its
+                        // flags could not be resolved. Hence, signal an error
+                        // and stay with the current command.
+                        if history.push(next) {
+                            current = next;
+                        } else {
+                            errors.push(SpannedError {
+                                index: index,
+                                error: Error::CommandDepthExceeded(Some(arg_os)),
+                            });
+                        }
+                    },
+                    Err(e) => {
+                        errors.push(SpannedError { index: index, error: e });
                     },
-                    Err(e) => errors.push(e),
                 }
             }
         }
 
-        if errors.is_empty() {
-            Ok(current.id.clone())
-        } else {
-            Err(errors.into_boxed_slice())
+        if !self.fail_fast || errors.pushed() == 0 {
+            self.parse_unseen(current, history, seen, errors, arguments.next);
         }
+
+        current.id.clone()
     }
 
     /// Parse all arguments as command-line arguments for the specified command
@@ -593,17 +1403,97 @@ impl Parser {
     ///
     /// The parser continues operation when encountering a parsing error. All
     /// errors will be collected and then returned to the caller. This allows
-    /// producing combined diagnostics for multiple errors, if desired.
+    /// producing combined diagnostics for multiple errors, if desired. Each
+    /// error carries the index of the argument it was raised for. See
+    /// `Self::set_fail_fast()` to stop at the first error instead.
     pub fn parse<'args, 'ctx, Id, Source>(
         &mut self,
         arguments: Source,
         command: &'ctx Command<'args, 'ctx, Id>,
-    ) -> Result<Id, alloc::boxed::Box<[Error<'args>]>>
+    ) -> Result<Id, alloc::boxed::Box<[SpannedError<'args, 'ctx>]>>
+    where
+        Id: Clone,
+        Source: Iterator<Item = &'args compat::OsStr>,
+    {
+        let mut history = alloc::vec::Vec::new();
+        let mut seen = alloc::vec::Vec::new();
+        let mut errors = alloc::vec::Vec::new();
+
+        let id = self.parse_root(arguments, command, &mut history, &mut seen, &mut errors, None);
+
+        if errors.is_empty() {
+            Ok(id)
+        } else {
+            Err(errors.into_boxed_slice())
+        }
+    }
+
+    /// Parse all arguments like `Self::parse()`, but also return the names
+    /// of all flags that were applied successfully. Toggled flags are
+    /// reported by their name without `no-` prefix. This allows callers to
+    /// distinguish explicitly provided flags from defaults (e.g., to let
+    /// explicit flags override configuration files).
+    pub fn parse_applied<'args, 'ctx, Id, Source>(
+        &mut self,
+        arguments: Source,
+        command: &'ctx Command<'args, 'ctx, Id>,
+    ) -> Result<(Id, alloc::collections::BTreeSet<&'ctx str>), alloc::boxed::Box<[SpannedError<'args, 'ctx>]>>
+    where
+        Id: Clone,
+        Source: Iterator<Item = &'args compat::OsStr>,
+    {
+        let mut history = alloc::vec::Vec::new();
+        let mut seen = alloc::vec::Vec::new();
+        let mut errors = alloc::vec::Vec::new();
+        let mut applied = alloc::collections::BTreeSet::new();
+
+        let id = self.parse_root(arguments, command, &mut history, &mut seen, &mut errors, Some(&mut applied));
+
+        if errors.is_empty() {
+            Ok((id, applied))
+        } else {
+            Err(errors.into_boxed_slice())
+        }
+    }
+
+    /// Parse all arguments as command-line arguments without allocating
+    /// parser state.
+    ///
+    /// This behaves like `Self::parse()`, but uses fixed-capacity lists for
+    /// the parser state. `DEPTH` limits the nesting of sub-commands
+    /// (including the root command), `FLAGS` limits the number of distinct
+    /// flags with defaults or requirements that can be given, and `ERRORS`
+    /// limits the number of errors that are retained.
+    ///
+    /// ## Errors
+    ///
+    /// If sub-commands are nested deeper than `DEPTH`, the sub-command is
+    /// rejected with `Error::CommandDepthExceeded`. If more than `FLAGS`
+    /// flags with defaults or requirements are given, the excess flags are
+    /// rejected with `Error::FlagCountExceeded`. If more than `ERRORS`
+    /// errors occur, the excess errors are dropped, but still counted via
+    /// `FixedList::dropped()`. Parsing fails if any error occurred, even if
+    /// it was dropped.
+    pub fn parse_fixed<'args, 'ctx, const DEPTH: usize, const FLAGS: usize, const ERRORS: usize, Id, Source>(
+        &mut self,
+        arguments: Source,
+        command: &'ctx Command<'args, 'ctx, Id>,
+    ) -> Result<Id, FixedList<SpannedError<'args, 'ctx>, ERRORS>>
     where
         Id: Clone,
         Source: Iterator<Item = &'args compat::OsStr>,
     {
-        self.parse_root(arguments, command)
+        let mut history = FixedList::<_, DEPTH>::new();
+        let mut seen = FixedList::<_, FLAGS>::new();
+        let mut errors = FixedList::<_, ERRORS>::new();
+
+        let id = self.parse_root(arguments, command, &mut history, &mut seen, &mut errors, None);
+
+        if errors.is_empty() && errors.dropped() == 0 {
+            Ok(id)
+        } else {
+            Err(errors)
+        }
     }
 
     /// Parse all arguments as command-line arguments.
@@ -614,7 +1504,7 @@ impl Parser {
         &mut self,
         arguments: Source,
         command: &'ctx Command<'args, 'ctx, Id>,
-    ) -> Result<Id, alloc::boxed::Box<[Error<'args>]>>
+    ) -> Result<Id, alloc::boxed::Box<[SpannedError<'args, 'ctx>]>>
     where
         Id: Clone,
         Source: IntoIterator<Item = &'args SourceItem>,
@@ -635,7 +1525,7 @@ impl Parser {
         &mut self,
         arguments: Source,
         command: &'ctx Command<'args, 'ctx, Id>,
-    ) -> Result<Id, alloc::boxed::Box<[Error<'args>]>>
+    ) -> Result<Id, alloc::boxed::Box<[SpannedError<'args, 'ctx>]>>
     where
         Id: Clone,
         Source: IntoIterator<Item = &'args SourceItem>,
@@ -800,6 +1690,29 @@ pub mod sink {
         }
     }
 
+    // Integers count the number of pushes, failing if the count overflows.
+    impl<Context> SinkMut<Context, ()> for u32 {
+        fn push(
+            &mut self,
+            _ctx: Context,
+            _data: (),
+        ) -> Result<(), Error> {
+            *self = self.checked_add(1).ok_or(Error::ValueInvalid)?;
+            Ok(())
+        }
+    }
+
+    impl<Context> SinkMut<Context, ()> for usize {
+        fn push(
+            &mut self,
+            _ctx: Context,
+            _data: (),
+        ) -> Result<(), Error> {
+            *self = self.checked_add(1).ok_or(Error::ValueInvalid)?;
+            Ok(())
+        }
+    }
+
     impl<'args, Context> SinkMut<Context, bool> for bool {
         fn push(
             &mut self,
@@ -972,10 +1885,13 @@ mod tests {
         barbar: core::cell::RefCell<Option<String>>,
     }
 
-    fn parse<'args>(
+    // Parse the arguments with nested test commands. Errors borrow from the
+    // command definitions, so the result is passed to `check`.
+    fn parse<'args, R>(
         arguments: &'args [&'args str],
         values: &'args mut Values,
-    ) -> Result<Id, alloc::boxed::Box<[Error<'args>]>> {
+        check: impl FnOnce(Result<Id, alloc::boxed::Box<[SpannedError<'_, '_>]>>) -> R,
+    ) -> R {
         let flags_foo = FlagList::with([
             Flag::with_name("foofoo", Value::Parse(&mut values.foofoo), None),
             Flag::with_name("foobar", Value::Parse(&mut values.foobar), None),
@@ -993,7 +1909,7 @@ mod tests {
             Flag::with_name("bar", Value::Parse(&mut values.bar), None),
         ]);
         let cmd = Command::with_name(Id::Root, "cmd", &cmds, &flags, None, None);
-        Parser::new().parse_str(arguments, &cmd)
+        check(Parser::new().parse_str(arguments, &cmd))
     }
 
     #[test]
@@ -1003,7 +1919,8 @@ mod tests {
         let r = parse(
             &["foo", "--foo", "value-foo"],
             &mut values,
-        ).unwrap();
+            |r| r.unwrap(),
+        );
         assert_eq!(r, Id::Foo);
         assert_eq!(
             values,
@@ -1016,7 +1933,8 @@ mod tests {
         let r = parse(
             &["--foo", "value-foo", "bar", "--bar", "value-bar", "--barbar", "value-barbar"],
             &mut values,
-        ).unwrap();
+            |r| r.unwrap(),
+        );
         assert_eq!(r, Id::Bar);
         assert_eq!(
             values,
@@ -1029,28 +1947,501 @@ mod tests {
         );
     }
 
+    // Parse the arguments like `parse()`, but with the fixed-capacity parser.
+    fn parse_fixed<'args, const DEPTH: usize, const FLAGS: usize, const ERRORS: usize, R>(
+        arguments: &'args [&'args str],
+        values: &'args mut Values,
+        check: impl FnOnce(Result<Id, FixedList<SpannedError<'_, '_>, ERRORS>>) -> R,
+    ) -> R {
+        let flags_foo = FlagList::with([
+            Flag::with_name("foofoo", Value::Parse(&mut values.foofoo), None),
+        ]);
+        let cmds_foo = CommandList::with([
+            Command::with_name(Id::Bar, "bar", Default::default(), Default::default(), None, None),
+        ]);
+        let cmds = CommandList::with([
+            Command::with_name(Id::Foo, "foo", &cmds_foo, &flags_foo, None, None),
+        ]);
+        let flags = FlagList::with([
+            Flag::with_name("foo", Value::Parse(&mut values.foo), None).with_default("default-foo"),
+        ]);
+        let cmd = Command::with_name(Id::Root, "cmd", &cmds, &flags, None, None);
+        check(Parser::new().parse_fixed::<DEPTH, FLAGS, ERRORS, _, _>(
+            arguments.iter().map(|v| (*v).into()),
+            &cmd,
+        ))
+    }
+
     #[test]
     fn test_errors() {
         let mut values: Values = Default::default();
 
-        let r = parse(
+        parse(
             &["invalid"],
             &mut values,
-        ).unwrap_err();
-        assert_eq!(r.len(), 1);
-        assert!(core::matches!(
-            r[0],
-            Error::CommandTakesNoParameters(ref v, _) if v == "cmd",
-        ));
+            |r| {
+                let r = r.unwrap_err();
+                assert_eq!(r.len(), 1);
+                assert!(core::matches!(
+                    r[0].error,
+                    Error::CommandTakesNoParameters("cmd", _),
+                ));
+            },
+        );
 
-        let r = parse(
+        parse(
             &["foo", "invalid"],
             &mut values,
-        ).unwrap_err();
-        assert_eq!(r.len(), 1);
-        assert!(core::matches!(
-            r[0],
-            Error::CommandTakesNoParameters(ref v, _) if v == "foo",
+            |r| {
+                let r = r.unwrap_err();
+                assert_eq!(r.len(), 1);
+                assert!(core::matches!(
+                    r[0].error,
+                    Error::CommandTakesNoParameters("foo", _),
+                ));
+                assert_eq!(r[0].index, 1);
+            },
+        );
+    }
+
+    // Verify that near-misses of sub-commands are reported with a
+    // suggestion, unless the command takes parameters.
+    #[test]
+    fn test_did_you_mean() {
+        let mut values: Values = Default::default();
+
+        parse(
+            &["fo"],
+            &mut values,
+            |r| {
+                let r = r.unwrap_err();
+                assert_eq!(r.len(), 1);
+                assert!(core::matches!(
+                    r[0].error,
+                    Error::CommandUnknownDidYouMean("fo", "foo"),
+                ));
+                assert_eq!(
+                    alloc::format!("{}", r[0].error),
+                    "Unknown sub-command: fo (did you mean `foo`?)",
+                );
+            },
+        );
+
+        parse(
+            &["bra"],
+            &mut values,
+            |r| {
+                let r = r.unwrap_err();
+                assert!(core::matches!(
+                    r[0].error,
+                    Error::CommandUnknownDidYouMean("bra", "bar"),
+                ));
+            },
+        );
+
+        let params: core::cell::RefCell<Option<alloc::string::String>> = Default::default();
+        let cmds = CommandList::with([
+            Command::with_name(Id::Foo, "foo", Default::default(), Default::default(), None, None),
+        ]);
+        let cmd = Command::with_name(Id::Root, "cmd", &cmds, Default::default(), Some(&params), None);
+        let r = Parser::new().parse_str(["fo"].iter(), &cmd).unwrap();
+        assert_eq!(r, Id::Root);
+        assert_eq!(*params.borrow(), Some("fo".into()));
+    }
+
+    // Verify the fixed-capacity parser behaves like the allocating parser
+    // and reports overflows of its parser state.
+    #[test]
+    fn test_fixed() {
+        let mut values: Values = Default::default();
+
+        let r = parse_fixed::<3, 1, 1, _>(
+            &["--foo", "value-foo", "foo", "bar", "--foofoo", "value-foofoo"],
+            &mut values,
+            |r| r.unwrap(),
+        );
+        assert_eq!(r, Id::Bar);
+        assert_eq!(
+            values,
+            Values {
+                foo: core::cell::RefCell::new(Some("value-foo".into())),
+                foofoo: core::cell::RefCell::new(Some("value-foofoo".into())),
+                ..Default::default()
+            },
+        );
+
+        // Nesting deeper than the history allows is rejected.
+        parse_fixed::<2, 1, 1, _>(
+            &["foo", "bar"],
+            &mut values,
+            |r| {
+                let r = r.unwrap_err();
+                assert_eq!(r.len(), 1);
+                assert_eq!(r.dropped(), 0);
+                assert!(core::matches!(
+                    r.get(0),
+                    Some(SpannedError { index: 1, error: Error::CommandDepthExceeded(Some(v)) }) if v.to_str() == Ok("bar"),
+                ));
+            },
+        );
+
+        // Excess errors are dropped, but still counted.
+        parse_fixed::<3, 1, 2, _>(
+            &["--invalid", "--invalid", "--invalid", "--invalid"],
+            &mut values,
+            |r| {
+                let r = r.unwrap_err();
+                assert_eq!(r.len(), 2);
+                assert_eq!(r.dropped(), 2);
+                assert!(r.iter().all(|v| core::matches!(v.error, Error::FlagUnknown("invalid"))));
+            },
+        );
+
+        // Without any error capacity, errors are still reported.
+        parse_fixed::<3, 1, 0, _>(
+            &["invalid"],
+            &mut values,
+            |r| {
+                let r = r.unwrap_err();
+                assert!(r.is_empty());
+                assert_eq!(r.dropped(), 1);
+            },
+        );
+
+        // Flags with defaults beyond the capacity are rejected.
+        parse_fixed::<3, 0, 1, _>(
+            &["--foo", "value-foo"],
+            &mut values,
+            |r| {
+                let r = r.unwrap_err();
+                assert_eq!(r.len(), 1);
+                assert!(core::matches!(
+                    r.get(0),
+                    Some(SpannedError { index: 0, error: Error::FlagCountExceeded("foo") }),
+                ));
+            },
+        );
+    }
+
+    // Verify audited lists can be assembled from runtime-sized collections
+    // and retain their sorting when extended.
+    #[test]
+    fn test_dynamic() {
+        let mut values: Values = Default::default();
+        let names = ["foofoo", "foo", "barbar"];
+
+        let mut flags: FlagListBox<Id> = names.iter().zip([
+            &mut values.foofoo,
+            &mut values.foo,
+            &mut values.barbar,
+        ]).map(|(n, v)| Flag::with_name(n, Value::Parse(v), None)).collect();
+        flags.extend([Flag::with_name("bar", Value::Parse(&mut values.bar), None)]);
+
+        let cmds: CommandListBox<Id> = [
+            Command::with_name(Id::Foo, "foo", Default::default(), Default::default(), None, None),
+        ].into_iter().collect();
+        let cmd = Command::with_name(Id::Root, "cmd", &cmds, &flags, None, None);
+
+        let r = Parser::new().parse_str(
+            &["--foofoo", "a", "--bar", "b", "--foo", "c", "foo", "--barbar", "d"],
+            &cmd,
+        ).unwrap();
+        assert_eq!(r, Id::Foo);
+        drop(flags);
+        assert_eq!(
+            values,
+            Values {
+                foo: core::cell::RefCell::new(Some("c".into())),
+                bar: core::cell::RefCell::new(Some("b".into())),
+                foofoo: core::cell::RefCell::new(Some("a".into())),
+                barbar: core::cell::RefCell::new(Some("d".into())),
+                ..Default::default()
+            },
+        );
+    }
+
+    // Verify errors carry the index of the argument they were raised for,
+    // including arguments consumed as flag values.
+    #[test]
+    fn test_error_index() {
+        let mut values: Values = Default::default();
+
+        parse(
+            &["--foo", "value-foo", "foo", "--frozn", "--foofoo", "v", "-x"],
+            &mut values,
+            |r| {
+                let r = r.unwrap_err();
+                assert_eq!(r.len(), 2);
+                assert!(core::matches!(r[0].error, Error::FlagUnknown("frozn")));
+                assert_eq!(r[0].index, 3);
+                assert_eq!(r[0].to_string(), "arg[3]: Invalid flag name: --frozn");
+                assert!(core::matches!(r[1].error, Error::ShortsUnknown(_)));
+                assert_eq!(r[1].index, 6);
+            },
+        );
+    }
+
+    // Verify fail-fast operation stops at the first bad argument and
+    // reports exactly one error, while the default collects all errors.
+    #[test]
+    fn test_fail_fast() {
+        let parse = |fail_fast: bool, bar: &mut core::cell::RefCell<Option<String>>| {
+            let mut foo = core::cell::RefCell::new(None::<String>);
+            let flags = FlagList::with([
+                Flag::with_name("foo", Value::Parse(&mut foo), None),
+                Flag::with_name("bar", Value::Parse(bar), None),
+            ]);
+            let cmd = Command::with_name(Id::Root, "cmd", Default::default(), &flags, None, None);
+            let mut parser = Parser::new();
+            parser.set_fail_fast(fail_fast);
+            parser.parse_str(&["--foo", "a", "--unknown", "-x", "--bar", "b"], &cmd)
+                .unwrap_err()
+                .iter()
+                .map(|v| (v.index, v.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut bar = Default::default();
+        assert_eq!(parse(false, &mut bar).len(), 2);
+        assert_eq!(bar.get_mut().as_deref(), Some("b"));
+
+        let mut bar = Default::default();
+        assert_eq!(parse(true, &mut bar), [(2, "arg[2]: Invalid flag name: --unknown".into())]);
+        assert_eq!(bar.get_mut().as_deref(), None);
+    }
+
+    // Verify that exactly the flags given on the command-line are reported
+    // as applied, including toggled flags and flags of parent commands.
+    #[test]
+    fn test_applied() {
+        let mut foo = core::cell::RefCell::new(None::<String>);
+        let mut bar = core::cell::RefCell::new(None::<bool>);
+        let mut baz = core::cell::RefCell::new(None::<bool>);
+        let mut sub = core::cell::RefCell::new(None::<String>);
+        let flags_sub = FlagList::with([
+            Flag::with_name("sub", Value::Parse(&mut sub), None),
+        ]);
+        let cmds = CommandList::with([
+            Command::with_name(Id::Foo, "sub", Default::default(), &flags_sub, None, None),
+        ]);
+        let flags = FlagList::with([
+            Flag::with_name("foo", Value::Parse(&mut foo), None),
+            Flag::with_name("bar", Value::Toggle(&mut bar), None),
+            Flag::with_name("baz", Value::Toggle(&mut baz), None),
+        ]);
+        let cmd = Command::with_name(Id::Root, "cmd", &cmds, &flags, None, None);
+
+        let args = ["--foo=a", "sub", "--no-bar", "--sub", "b"];
+        let (id, applied) = Parser::new().parse_applied(
+            args.iter().map(|v| (*v).into()),
+            &cmd,
+        ).unwrap();
+
+        assert_eq!(id, Id::Foo);
+        assert_eq!(applied.into_iter().collect::<Vec<_>>(), ["bar", "foo", "sub"]);
+    }
+
+    // Verify defaults are applied to flags of selected commands unless the
+    // flag was given, and that defaults are validated by the value parser.
+    #[test]
+    fn test_default() {
+        let foo = core::cell::RefCell::new(None::<String>);
+        let bar = core::cell::RefCell::new(None::<bool>);
+        let sub = core::cell::RefCell::new(None::<String>);
+        let parse = |args: &[&'static str], bar_default: &'static str| {
+            let flags_sub = FlagList::with([
+                Flag::with_name("sub", Value::Parse(&sub), None).with_default("sub-default"),
+            ]);
+            let cmds = CommandList::with([
+                Command::with_name(Id::Foo, "sub", Default::default(), &flags_sub, None, None),
+            ]);
+            let flags = FlagList::with([
+                Flag::with_name("foo", Value::Parse(&foo), None).with_default("foo-default"),
+                Flag::with_name("bar", Value::Parse(&bar), None).with_default(bar_default),
+            ]);
+            let cmd = Command::with_name(Id::Root, "cmd", &cmds, &flags, None, None);
+            Parser::new().parse_str(args, &cmd)
+                .map_err(|v| v.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(parse(&["--foo", "a"], "yes").unwrap(), Id::Root);
+        assert_eq!(foo.take().as_deref(), Some("a"));
+        assert_eq!(bar.take(), Some(true));
+        assert_eq!(sub.take(), None);
+
+        assert_eq!(parse(&["sub", "--bar", "no"], "yes").unwrap(), Id::Foo);
+        assert_eq!(foo.take().as_deref(), Some("foo-default"));
+        assert_eq!(bar.take(), Some(false));
+        assert_eq!(sub.take().as_deref(), Some("sub-default"));
+
+        let r = parse(&["--foo", "a"], "maybe").unwrap_err();
+        assert_eq!(r, ["arg[2]: Cannot parse default value for flag `--bar=maybe`: Value is not valid"]);
+        foo.take();
+
+        // A flag with an invalid value counts as given, so its default is
+        // not applied on top.
+        let r = parse(&["--bar", "maybe"], "yes").unwrap_err();
+        assert_eq!(r, ["arg[0]: Cannot parse value for flag `--[no-]bar=maybe`: Value is not valid"]);
+        assert_ne!(bar.take(), Some(true));
+    }
+
+    // Verify required flags are reported only if their command was selected
+    // and neither the flag nor a default was given.
+    #[test]
+    fn test_required() {
+        let foo = core::cell::RefCell::new(None::<String>);
+        let bar = core::cell::RefCell::new(None::<String>);
+        let sub = core::cell::RefCell::new(None::<String>);
+        let parse = |args: &[&'static str]| {
+            let flags_sub = FlagList::with([
+                Flag::with_name("sub", Value::Parse(&sub), None).with_required(true),
+            ]);
+            let cmds = CommandList::with([
+                Command::with_name(Id::Foo, "sub", Default::default(), &flags_sub, None, None),
+            ]);
+            let flags = FlagList::with([
+                Flag::with_name("foo", Value::Parse(&foo), None).with_required(true),
+                Flag::with_name("bar", Value::Parse(&bar), None).with_required(true).with_default("b"),
+            ]);
+            let cmd = Command::with_name(Id::Root, "cmd", &cmds, &flags, None, None);
+            Parser::new().parse_str(args, &cmd)
+                .map_err(|v| v.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(parse(&["--foo", "a"]).unwrap(), Id::Root);
+        assert_eq!(bar.take().as_deref(), Some("b"));
+
+        let r = parse(&["sub"]).unwrap_err();
+        assert_eq!(r, [
+            "arg[1]: Missing required flag: --foo",
+            "arg[1]: Missing required flag: --sub",
+        ]);
+
+        // A required flag that was given but failed to parse is reported
+        // only for its own error, not as missing.
+        let r = parse(&["--foo"]).unwrap_err();
+        assert_eq!(r, ["arg[0]: Flag requires a value: --foo"]);
+        let r = parse(&["--no-foo"]).unwrap_err();
+        assert_eq!(r, ["arg[0]: Flag cannot be toggled: --no-foo"]);
+    }
+
+    // Verify completion scripts follow sub-commands, skip flag values, and
+    // offer both forms of toggle-flags.
+    #[test]
+    fn test_completion() {
+        let foo = core::cell::RefCell::new(None::<String>);
+        let bar = core::cell::RefCell::new(None::<bool>);
+        let flags_sub = FlagList::with([
+            Flag::with_name("bar", Value::Toggle(&bar), Some("Don't bar")),
+        ]);
+        let cmds = CommandList::with([
+            Command::with_name(Id::Foo, "sub", Default::default(), &flags_sub, None, Some("Sub: command")),
+        ]);
+        let flags = FlagList::with([
+            Flag::with_name("foo", Value::Parse(&foo), None).with_short('f'),
+        ]);
+        let cmd = Command::with_name(Id::Root, "my-cmd", &cmds, &flags, None, None);
+
+        let mut r = String::new();
+        cmd.write_completion(Shell::Bash, &mut r).unwrap();
+        assert_eq!(r, concat!(
+            "_my_cmd() {\n",
+            "    local cur cmdpath i\n",
+            "    cur=\"${COMP_WORDS[COMP_CWORD]}\"\n",
+            "    cmdpath=\"my-cmd\"\n",
+            "    for ((i = 1; i < COMP_CWORD; i++)); do\n",
+            "        case \"${cmdpath} ${COMP_WORDS[i]}\" in\n",
+            "            \"my-cmd sub\") cmdpath=\"my-cmd sub\" ;;\n",
+            "            \"my-cmd --foo\") ((i++)) ;;\n",
+            "            \"my-cmd -f\") ((i++)) ;;\n",
+            "            \"my-cmd sub --foo\") ((i++)) ;;\n",
+            "            \"my-cmd sub -f\") ((i++)) ;;\n",
+            "        esac\n",
+            "    done\n",
+            "    case \"${cmdpath}\" in\n",
+            "        \"my-cmd\") COMPREPLY=($(compgen -W \"--foo -f sub\" -- \"${cur}\")) ;;\n",
+            "        \"my-cmd sub\") COMPREPLY=($(compgen -W \"--bar --no-bar --foo -f\" -- \"${cur}\")) ;;\n",
+            "    esac\n",
+            "}\n",
+            "complete -F _my_cmd my-cmd\n",
         ));
+
+        // Help texts are quoted for Zsh, and colons in them are retained.
+        let mut r = String::new();
+        cmd.write_completion(Shell::Zsh, &mut r).unwrap();
+        assert!(r.starts_with("#compdef my-cmd\n\n_my_cmd() {\n"));
+        assert!(r.ends_with("}\n\n_my_cmd \"$@\"\n"));
+        assert!(r.contains("                'sub:Sub: command'\n"));
+        assert!(r.contains("                '--no-bar:Don'\\''t bar'\n"));
+        assert!(r.contains("            (\"my-cmd sub\") cmdpath=\"my-cmd sub\" ;;\n"));
+    }
+
+    // Verify counting-flags count every occurrence, including bundled short
+    // flags, and take no values.
+    #[test]
+    fn test_count() {
+        let verbose = core::cell::RefCell::new(0u32);
+        let quiet = core::cell::RefCell::new(None::<usize>);
+        let parse = |args: &[&'static str]| {
+            let flags = FlagList::with([
+                Flag::with_name("verbose", Value::Count(&verbose), None).with_short('v'),
+                Flag::with_name("quiet", Value::Count(&quiet), None),
+            ]);
+            let cmd = Command::with_name(Id::Root, "cmd", Default::default(), &flags, None, None);
+            Parser::new().parse_str(args, &cmd)
+                .map_err(|v| v.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        };
+
+        parse(&["-vv", "--verbose", "-v"]).unwrap();
+        assert_eq!(verbose.take(), 4);
+        assert_eq!(quiet.take(), None);
+
+        parse(&["--quiet", "--quiet"]).unwrap();
+        assert_eq!(verbose.take(), 0);
+        assert_eq!(quiet.take(), Some(2));
+
+        let r = parse(&["--verbose=2", "--no-verbose"]).unwrap_err();
+        assert_eq!(r, [
+            "arg[0]: Flag takes no value: --verbose=2",
+            "arg[1]: Flag cannot be toggled: --no-verbose",
+        ]);
+    }
+
+    // Verify short flags can be bundled, take their value from the rest of
+    // the argument or the next argument, and report unknown characters.
+    #[test]
+    fn test_short() {
+        let foo = core::cell::RefCell::new(None::<String>);
+        let bar = core::cell::RefCell::new(None::<bool>);
+        let baz = core::cell::RefCell::new(None::<bool>);
+        let parse = |args: &[&'static str]| {
+            let flags = FlagList::with([
+                Flag::with_name("foo", Value::Parse(&foo), None).with_short('f'),
+                Flag::with_name("bar", Value::Toggle(&bar), None).with_short('b'),
+                Flag::with_name("baz", Value::Toggle(&baz), None).with_short('z'),
+            ]);
+            let cmd = Command::with_name(Id::Root, "cmd", Default::default(), &flags, None, None);
+            Parser::new().parse_applied(args.iter().map(|v| (*v).into()), &cmd)
+                .map(|(_, v)| v.into_iter().map(String::from).collect::<Vec<_>>())
+                .map_err(|v| v.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        };
+
+        let r = parse(&["-bz"]).unwrap();
+        assert_eq!(r, ["bar", "baz"]);
+        assert_eq!((*bar.borrow(), *baz.borrow()), (Some(true), Some(true)));
+
+        let r = parse(&["-bfvalue"]).unwrap();
+        assert_eq!(r, ["bar", "foo"]);
+        assert_eq!(foo.borrow().as_deref(), Some("value"));
+
+        let r = parse(&["-f", "-z"]).unwrap();
+        assert_eq!(r, ["foo"]);
+        assert_eq!(foo.borrow().as_deref(), Some("-z"));
+
+        let r = parse(&["-bxz", "-f"]).unwrap_err();
+        assert_eq!(r, [
+            "arg[0]: Invalid short flag: -x",
+            "arg[1]: Short flag requires a value: -f",
+        ]);
     }
 }
@@ -0,0 +1,223 @@
+//! # Base64 Encoding
+//!
+//! This module implements the `base64` and `base64url` encodings of
+//! [RFC 4648]. Both alphabets are supported, and padding can be enabled or
+//! disabled when encoding. When decoding, padding is optional, but if present
+//! it must be correct.
+//!
+//! [RFC 4648]: https://www.rfc-editor.org/rfc/rfc4648
+
+/// Error definitions for all possible errors of the decoder.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Input contains a character outside of the alphabet at the given
+    /// byte offset.
+    CharacterInvalid(usize),
+    /// Input length cannot be produced by the encoder.
+    LengthInvalid,
+    /// Padding is present, but does not match the input length.
+    PaddingInvalid,
+    /// Final character carries set bits that are not part of the data.
+    TrailingBits,
+}
+
+/// Alphabets supported by the encoder and decoder.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Alphabet {
+    /// Standard alphabet using `+` and `/` (RFC 4648, section 4).
+    Standard,
+    /// URL and filename safe alphabet using `-` and `_` (RFC 4648,
+    /// section 5).
+    UrlSafe,
+}
+
+const PAD: u8 = b'=';
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URLSAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::CharacterInvalid(offset) => fmt.write_fmt(core::format_args!("Invalid base64 character at offset {}", offset)),
+            Self::LengthInvalid => fmt.write_str("Invalid base64 input length"),
+            Self::PaddingInvalid => fmt.write_str("Invalid base64 padding"),
+            Self::TrailingBits => fmt.write_str("Non-zero trailing bits in base64 input"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+}
+
+impl Alphabet {
+    fn table(&self) -> &'static [u8; 64] {
+        match self {
+            Self::Standard => STANDARD,
+            Self::UrlSafe => URLSAFE,
+        }
+    }
+
+    fn value(&self, v: u8) -> Option<u8> {
+        match v {
+            b'A'..=b'Z' => Some(v - b'A'),
+            b'a'..=b'z' => Some(v - b'a' + 26),
+            b'0'..=b'9' => Some(v - b'0' + 52),
+            b'+' if *self == Self::Standard => Some(62),
+            b'/' if *self == Self::Standard => Some(63),
+            b'-' if *self == Self::UrlSafe => Some(62),
+            b'_' if *self == Self::UrlSafe => Some(63),
+            _ => None,
+        }
+    }
+}
+
+/// ## Encode with Alphabet
+///
+/// Encode the data with the given alphabet. If `pad` is set, the output is
+/// padded with `=` to a multiple of 4 characters.
+pub fn encode_with(data: &[u8], alphabet: Alphabet, pad: bool) -> alloc::string::String {
+    let table = alphabet.table();
+    let mut acc = alloc::vec::Vec::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let v = [
+            b[0] >> 2,
+            ((b[0] & 0x03) << 4) | (b[1] >> 4),
+            ((b[1] & 0x0f) << 2) | (b[2] >> 6),
+            b[2] & 0x3f,
+        ];
+
+        // Every input byte yields at least one character, plus one
+        // to carry the remaining bits.
+        let n = chunk.len() + 1;
+        acc.extend(v[..n].iter().map(|v| table[*v as usize]));
+        if pad {
+            acc.resize(acc.len() + 4 - n, PAD);
+        }
+    }
+
+    // The output only contains characters of the alphabet and padding.
+    alloc::string::String::from_utf8(acc).unwrap()
+}
+
+/// ## Encode
+///
+/// Encode the data with the standard alphabet and padding.
+pub fn encode(data: &[u8]) -> alloc::string::String {
+    encode_with(data, Alphabet::Standard, true)
+}
+
+/// ## Decode with Alphabet
+///
+/// Decode the input with the given alphabet. Padding is optional, but if
+/// present, it must complete the input to a multiple of 4 characters.
+/// Non-canonical encodings (i.e., with set trailing bits) are rejected.
+pub fn decode_with(input: &str, alphabet: Alphabet) -> Result<alloc::vec::Vec<u8>, Error> {
+    let input = input.as_bytes();
+    let body = input.strip_suffix(b"==")
+        .or_else(|| input.strip_suffix(b"="))
+        .unwrap_or(input);
+
+    if body.len() != input.len() && input.len() % 4 != 0 {
+        return Err(Error::PaddingInvalid);
+    }
+    if body.len() % 4 == 1 {
+        return Err(Error::LengthInvalid);
+    }
+
+    let mut acc = alloc::vec::Vec::with_capacity(body.len() / 4 * 3 + 2);
+
+    for (i_chunk, chunk) in body.chunks(4).enumerate() {
+        let mut v = [0u8; 4];
+        for (i, c) in chunk.iter().enumerate() {
+            v[i] = match (*c, alphabet.value(*c)) {
+                (_, Some(v)) => v,
+                (PAD, None) => return Err(Error::PaddingInvalid),
+                (_, None) => return Err(Error::CharacterInvalid(i_chunk * 4 + i)),
+            };
+        }
+
+        let b = [
+            (v[0] << 2) | (v[1] >> 4),
+            (v[1] << 4) | (v[2] >> 2),
+            (v[2] << 6) | v[3],
+        ];
+
+        // A short chunk must not carry bits beyond its last full byte.
+        let n = chunk.len() - 1;
+        if b[n..].iter().any(|v| *v != 0) {
+            return Err(Error::TrailingBits);
+        }
+        acc.extend_from_slice(&b[..n]);
+    }
+
+    Ok(acc)
+}
+
+/// ## Decode
+///
+/// Decode the input with the standard alphabet. See `decode_with()` for
+/// details.
+pub fn decode(input: &str) -> Result<alloc::vec::Vec<u8>, Error> {
+    decode_with(input, Alphabet::Standard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify the test vectors of RFC 4648 with both alphabets, with and
+    // without padding.
+    #[test]
+    fn rfc4648_vectors() {
+        let vectors: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+        ];
+
+        for (data, enc) in vectors {
+            assert_eq!(encode(data), *enc);
+            assert_eq!(decode(enc).unwrap(), *data);
+
+            let unpadded = enc.trim_end_matches('=');
+            assert_eq!(encode_with(data, Alphabet::UrlSafe, false), unpadded);
+            assert_eq!(decode_with(unpadded, Alphabet::UrlSafe).unwrap(), *data);
+            assert_eq!(decode_with(enc, Alphabet::UrlSafe).unwrap(), *data);
+        }
+
+        // Only the last two characters differ between the alphabets.
+        let data = [0xfb, 0xff, 0xbf];
+        assert_eq!(encode_with(&data, Alphabet::Standard, true), "+/+/");
+        assert_eq!(encode_with(&data, Alphabet::UrlSafe, true), "-_-_");
+        assert_eq!(decode_with("+/+/", Alphabet::Standard).unwrap(), data);
+        assert_eq!(decode_with("-_-_", Alphabet::UrlSafe).unwrap(), data);
+    }
+
+    // Verify that invalid input is rejected with the correct error.
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(decode("Zm9v!A"), Err(Error::CharacterInvalid(4)));
+        assert_eq!(decode("-_-_"), Err(Error::CharacterInvalid(0)));
+        assert_eq!(decode_with("+/+/", Alphabet::UrlSafe), Err(Error::CharacterInvalid(0)));
+        assert_eq!(decode("Z"), Err(Error::LengthInvalid));
+        assert_eq!(decode("Zm9vY"), Err(Error::LengthInvalid));
+        assert_eq!(decode("Zg="), Err(Error::PaddingInvalid));
+        assert_eq!(decode("Zm8=="), Err(Error::PaddingInvalid));
+        assert_eq!(decode("Zg==Zg=="), Err(Error::PaddingInvalid));
+        assert_eq!(decode("Zm9v===="), Err(Error::PaddingInvalid));
+        assert_eq!(decode("Zh=="), Err(Error::TrailingBits));
+        assert_eq!(decode("Zm9="), Err(Error::TrailingBits));
+    }
+}
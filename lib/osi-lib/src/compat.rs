@@ -62,6 +62,13 @@ impl OsStr {
         }
     }
 
+    /// Create an `OsStr` compatibility type from a `std` path. Paths are
+    /// represented as `std::ffi::OsStr`, so the conversion is free.
+    #[cfg(feature = "std")]
+    pub fn from_path(v: &std::path::Path) -> &Self {
+        Self::from_osstr(v.as_os_str())
+    }
+
     /// Create an `OsStr` compatibility type from a valid UTF-8 string
     /// given as Rust `str`.
     pub fn from_str(v: &str) -> &Self {
@@ -95,6 +102,13 @@ impl OsStr {
         }
     }
 
+    /// Return a `std` path for the value of this compatibility type. Paths
+    /// are represented as `std::ffi::OsStr`, so the conversion is free.
+    #[cfg(feature = "std")]
+    pub fn as_path(&self) -> &std::path::Path {
+        std::path::Path::new(self.as_osstr())
+    }
+
     /// Return a Rust `str` for the value of this compatibility type. This is
     /// only possible of the data is valid UTF-8. Hence, this requires a UTF-8
     /// check and returns its errors, if any.
@@ -123,9 +137,67 @@ impl<'a> From<&'a std::ffi::OsStr> for &'a OsStr {
     }
 }
 
+#[cfg(feature = "std")]
+impl<'a> From<&'a std::path::Path> for &'a OsStr {
+    fn from(v: &'a std::path::Path) -> &'a OsStr {
+        OsStr::from_path(v)
+    }
+}
+
 #[cfg(feature = "std")]
 impl AsRef<std::ffi::OsStr> for OsStr {
     fn as_ref(&self) -> &std::ffi::OsStr {
         self.as_osstr()
     }
 }
+
+#[cfg(feature = "std")]
+impl AsRef<std::path::Path> for OsStr {
+    fn as_ref(&self) -> &std::path::Path {
+        self.as_path()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify that strings and UTF-8 buffers convert without the standard
+    // library, and that invalid UTF-8 is rejected.
+    #[test]
+    fn osstr_str() {
+        let v = OsStr::from_str("foo/bar");
+        assert_eq!(v.as_encoded_bytes(), b"foo/bar");
+        assert_eq!(v.to_str().unwrap(), "foo/bar");
+
+        let v: &OsStr = "foo".into();
+        assert_eq!(v, OsStr::from_utf8(b"foo").unwrap());
+        assert!(OsStr::from_utf8(b"foo\xff").is_err());
+    }
+
+    // Verify paths round-trip through the compatibility type, including
+    // components that are not valid UTF-8.
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn osstr_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let raw = b"/tmp/foo\xff/bar";
+        let path = std::path::Path::new(std::ffi::OsStr::from_bytes(raw));
+
+        let v = OsStr::from_path(path);
+        assert_eq!(v.as_encoded_bytes(), raw);
+        assert!(v.to_str().is_err());
+        assert_eq!(v.as_path(), path);
+        assert_eq!(v.as_path().file_name().unwrap(), "bar");
+        assert_eq!(
+            v.as_path().parent().unwrap().as_os_str().as_bytes(),
+            b"/tmp/foo\xff",
+        );
+
+        let v: &OsStr = path.into();
+        let r: &std::path::Path = v.as_ref();
+        assert_eq!(r, path);
+        assert_eq!(OsStr::from_str("a/b").as_path(), std::path::Path::new("a/b"));
+    }
+}
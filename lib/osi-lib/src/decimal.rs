@@ -0,0 +1,198 @@
+//! # Fixed-Point Decimals
+//!
+//! This module implements an exact fixed-point decimal type. Values are
+//! represented as an integer mantissa scaled by a power of ten, which allows
+//! exact handling of decimal input (e.g., configuration values or currency
+//! amounts) without the rounding errors of binary floating point.
+//!
+//! Decimals are constructed from the digits of a number in scientific
+//! notation, matching the number representation of the JSON tokenizer.
+
+/// Error definitions for all possible errors of decimal construction.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Given digit value is not in the range 0-9.
+    DigitInvalid(u8),
+    /// Value cannot be represented exactly by a decimal.
+    Overflow,
+}
+
+/// ## Fixed-Point Decimal
+///
+/// A decimal number with the value `mantissa * 10^(-scale)`. Decimals are
+/// not normalized, so the same value can be represented with different
+/// scales (e.g., `1.5` and `1.50`).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Decimal {
+    /// Integer value of all digits.
+    pub mantissa: i128,
+    /// Number of fractional digits of the mantissa.
+    pub scale: u32,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::DigitInvalid(v) => fmt.write_fmt(core::format_args!("Invalid decimal digit value: {}", v)),
+            Self::Overflow => fmt.write_str("Decimal value out of range"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+}
+
+// Accumulate decimal digits onto `acc`, moving away from zero in the
+// direction given by `negative`. This allows representing `i128::MIN`.
+fn accumulate(mut acc: i128, negative: bool, digits: &[u8]) -> Result<i128, Error> {
+    for &v in digits {
+        if v > 9 {
+            return Err(Error::DigitInvalid(v));
+        }
+
+        acc = acc.checked_mul(10)
+            .and_then(|acc| {
+                if negative {
+                    acc.checked_sub(v as i128)
+                } else {
+                    acc.checked_add(v as i128)
+                }
+            })
+            .ok_or(Error::Overflow)?;
+    }
+
+    Ok(acc)
+}
+
+impl Decimal {
+    /// ## Create New Decimal
+    ///
+    /// Create a new decimal with the value `mantissa * 10^(-scale)`.
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        Self {
+            mantissa: mantissa,
+            scale: scale,
+        }
+    }
+
+    /// ## Create Decimal from Number Parts
+    ///
+    /// Create a decimal from the parts of a number in scientific notation.
+    /// `integer`, `fraction`, and `exponent` are the decimal values (0-9) of
+    /// the respective digits, with the most significant digit first. This
+    /// matches the digit values reported by the JSON tokenizer.
+    ///
+    /// The scale of the input is preserved, so trailing zeros of the
+    /// fraction are kept as part of the mantissa. A positive effective
+    /// exponent is applied to the mantissa, yielding a scale of 0.
+    ///
+    /// If the value cannot be represented exactly, `Error::Overflow` is
+    /// returned.
+    pub fn from_parts(
+        negative: bool,
+        integer: &[u8],
+        fraction: &[u8],
+        exponent_negative: bool,
+        exponent: &[u8],
+    ) -> Result<Self, Error> {
+        let mut mantissa = accumulate(0, negative, integer)?;
+        mantissa = accumulate(mantissa, negative, fraction)?;
+
+        let exponent = accumulate(0, exponent_negative, exponent)?;
+        let scale = (fraction.len() as i128)
+            .checked_sub(exponent)
+            .ok_or(Error::Overflow)?;
+
+        if scale >= 0 {
+            let scale = u32::try_from(scale).map_err(|_| Error::Overflow)?;
+            Ok(Self::new(mantissa, scale))
+        } else {
+            let shift = u32::try_from(-scale).map_err(|_| Error::Overflow)?;
+            let mantissa = 10i128.checked_pow(shift)
+                .and_then(|v| mantissa.checked_mul(v))
+                .ok_or(Error::Overflow)?;
+            Ok(Self::new(mantissa, 0))
+        }
+    }
+}
+
+impl core::fmt::Display for Decimal {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        let digits = alloc::format!("{}", self.mantissa.unsigned_abs());
+        let scale = self.scale as usize;
+
+        if self.mantissa < 0 {
+            fmt.write_str("-")?;
+        }
+
+        if scale == 0 {
+            fmt.write_str(&digits)
+        } else if digits.len() > scale {
+            let (int, frac) = digits.split_at(digits.len() - scale);
+            fmt.write_fmt(core::format_args!("{}.{}", int, frac))
+        } else {
+            fmt.write_fmt(core::format_args!("0.{:0>width$}", digits, width = scale))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verify construction from number parts, including exponents and the
+    // boundaries of the mantissa.
+    #[test]
+    fn from_parts() {
+        // 12.34
+        let v = Decimal::from_parts(false, &[1, 2], &[3, 4], false, &[]).unwrap();
+        assert_eq!(v, Decimal::new(1234, 2));
+        assert_eq!(alloc::format!("{}", v), "12.34");
+
+        // 1e-3
+        let v = Decimal::from_parts(false, &[1], &[], true, &[3]).unwrap();
+        assert_eq!(v, Decimal::new(1, 3));
+        assert_eq!(alloc::format!("{}", v), "0.001");
+
+        // -1.50e2
+        let v = Decimal::from_parts(true, &[1], &[5, 0], false, &[2]).unwrap();
+        assert_eq!(v, Decimal::new(-150, 0));
+        assert_eq!(alloc::format!("{}", v), "-150");
+
+        // -0.0
+        let v = Decimal::from_parts(true, &[0], &[0], false, &[]).unwrap();
+        assert_eq!(v, Decimal::new(0, 1));
+        assert_eq!(alloc::format!("{}", v), "0.0");
+
+        // i128::MIN is representable, its negation is not.
+        let min: alloc::vec::Vec<u8> = "170141183460469231731687303715884105728"
+            .bytes()
+            .map(|v| v - b'0')
+            .collect();
+        assert_eq!(
+            Decimal::from_parts(true, &min, &[], false, &[]),
+            Ok(Decimal::new(i128::MIN, 0)),
+        );
+        assert_eq!(
+            Decimal::from_parts(false, &min, &[], false, &[]),
+            Err(Error::Overflow),
+        );
+
+        // 1e39 and 1e-4294967296
+        assert_eq!(
+            Decimal::from_parts(false, &[1], &[], false, &[3, 9]),
+            Err(Error::Overflow),
+        );
+        assert_eq!(
+            Decimal::from_parts(false, &[1], &[], true, &[4, 2, 9, 4, 9, 6, 7, 2, 9, 6]),
+            Err(Error::Overflow),
+        );
+
+        assert_eq!(
+            Decimal::from_parts(false, &[1, 10], &[], false, &[]),
+            Err(Error::DigitInvalid(10)),
+        );
+    }
+}
@@ -13,7 +13,9 @@ extern crate std;
 
 pub mod align;
 pub mod args;
+pub mod base64;
 pub mod compat;
+pub mod decimal;
 pub mod error;
 pub mod hash;
 pub mod hmac;
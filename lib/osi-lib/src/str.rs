@@ -85,6 +85,40 @@ pub fn cmp_natural(
     core::cmp::Ordering::Equal
 }
 
+/// ## Compute Edit Distance
+///
+/// Compute the edit distance between two strings as the minimal number of
+/// character insertions, deletions, substitutions, and transpositions of
+/// adjacent characters to turn one into the other (known as _optimal string
+/// alignment distance_). This is suitable to detect typos.
+pub fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let lhs: alloc::vec::Vec<char> = lhs.chars().collect();
+    let rhs: alloc::vec::Vec<char> = rhs.chars().collect();
+
+    // Only the last two rows of the distance matrix are needed, since
+    // transpositions look back by two characters at most.
+    let mut prev2: alloc::vec::Vec<usize> = alloc::vec![0; rhs.len() + 1];
+    let mut prev: alloc::vec::Vec<usize> = (0..=rhs.len()).collect();
+    let mut cur: alloc::vec::Vec<usize> = alloc::vec![0; rhs.len() + 1];
+
+    for i in 1..=lhs.len() {
+        cur[0] = i;
+        for j in 1..=rhs.len() {
+            let cost = (lhs[i - 1] != rhs[j - 1]) as usize;
+            cur[j] = (prev[j] + 1)
+                .min(cur[j - 1] + 1)
+                .min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && lhs[i - 1] == rhs[j - 2] && lhs[i - 2] == rhs[j - 1] {
+                cur[j] = cur[j].min(prev2[j - 2] + 1);
+            }
+        }
+        core::mem::swap(&mut prev2, &mut prev);
+        core::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[rhs.len()]
+}
+
 /// ## Turn strings into valid symbol identifiers
 ///
 /// Create a new string that has the same content as the input but all
@@ -159,6 +193,22 @@ mod tests {
         );
     }
 
+    // Verify edit distances, including transpositions of adjacent
+    // characters and non-ASCII characters.
+    #[test]
+    fn edit_distance_basic() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("", "foo"), 3);
+        assert_eq!(edit_distance("foo", ""), 3);
+        assert_eq!(edit_distance("build", "build"), 0);
+        assert_eq!(edit_distance("biuld", "build"), 1);
+        assert_eq!(edit_distance("buld", "build"), 1);
+        assert_eq!(edit_distance("builds", "build"), 1);
+        assert_eq!(edit_distance("bxild", "build"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("äb", "bä"), 1);
+    }
+
     // Run some basic string conversion tests on the `symbolize()` helper. It
     // should properly preprend prefixes and replace unsupported characters.
     #[test]
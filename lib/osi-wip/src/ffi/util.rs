@@ -235,6 +235,35 @@ where
     fn to_native(self) -> Raw;
 }
 
+/// ## Address Arithmetic
+///
+/// This trait provides offset arithmetic on addresses in their native
+/// representation. Unlike `NativeAddress`, it is not limited to addresses
+/// of the size of a native pointer. Instead, all arithmetic is performed
+/// with the width of the implementing type. This allows computing 32-bit
+/// addresses on 64-bit machines, and vice-versa.
+///
+/// Since addresses of pointers are never 0, both operations fail if the
+/// resulting address would be 0.
+pub trait AddressOffset
+where
+    Self: Copy,
+{
+    /// ## Add offset with overflow check
+    ///
+    /// Add the offset in bytes to the address. If the result cannot be
+    /// represented by the type, or if it is 0, this yields `None`.
+    #[must_use]
+    fn checked_add_offset(self, offset: i128) -> Option<Self>;
+
+    /// ## Add offset with wrapping
+    ///
+    /// Add the offset in bytes to the address, wrapping around at the
+    /// boundaries of the type. If the result is 0, this yields `None`.
+    #[must_use]
+    fn wrapping_add_offset(self, offset: i128) -> Option<Self>;
+}
+
 /// ## Big-endian Encoded Values
 ///
 /// This type represents values encoded as big-endian. It is a simple
@@ -314,6 +343,23 @@ where
     target: core::marker::PhantomData<*const Target>,
 }
 
+/// ## Fixed-size Slice Pointers
+///
+/// This type is a companion to `Pointer` for slices. It combines the address
+/// of the first element with the number of elements, similar to how Rust
+/// represents references to slices. Unlike Rust slice references, the
+/// address uses a fixed-size address type.
+///
+/// The length is always given as number of elements, not bytes.
+#[repr(C)]
+pub struct SlicePointer<Address, Target>
+where
+    Address: Copy,
+{
+    pointer: Pointer<Address, Target>,
+    len: usize,
+}
+
 /// ## Value Selector based on Address Size
 ///
 /// Return either of the arguments, depending on the pointer-width of the
@@ -531,7 +577,8 @@ implement_address_nonzero!(core::num::NonZeroU32);
 #[cfg(target_pointer_width = "64")]
 implement_address_nonzero!(core::num::NonZeroU64);
 
-// Implement `NativeEndian` on all primitive integers via identity mappings.
+// Implement `NativeEndian` on all primitive integers, as well as `bool` and
+// `char`, via identity mappings.
 macro_rules! implement_endian_identity {
     ( $self:ty ) => {
         unsafe impl NativeEndian<$self> for $self {
@@ -662,6 +709,8 @@ macro_rules! implement_endian_le_nonzero {
     }
 }
 
+implement_endian_identity!(bool);
+implement_endian_identity!(char);
 implement_endian_identity!(i8);
 implement_endian_identity!(i16);
 implement_endian_identity!(i32);
@@ -737,6 +786,194 @@ implement_endian_le_nonzero!(LittleEndian<core::num::NonZeroU64>, core::num::Non
 implement_endian_le_nonzero!(LittleEndian<core::num::NonZeroU128>, core::num::NonZeroU128, u128);
 implement_endian_le_nonzero!(LittleEndian<core::num::NonZeroUsize>, core::num::NonZeroUsize, usize);
 
+// Implement `AddressOffset` on unsigned integers. All integers are
+// converted to `i128`, which can represent all their values plus any
+// offset without overflow. Truncating casts then wrap at the type width.
+macro_rules! implement_offset {
+    ( $self:ty ) => {
+        impl AddressOffset for $self {
+            #[inline]
+            #[must_use]
+            fn checked_add_offset(self, offset: i128) -> Option<Self> {
+                let v = (self as i128).checked_add(offset)?;
+                <$self>::try_from(v).ok().filter(|v| *v != 0)
+            }
+
+            #[inline]
+            #[must_use]
+            fn wrapping_add_offset(self, offset: i128) -> Option<Self> {
+                Some((self as i128).wrapping_add(offset) as $self).filter(|v| *v != 0)
+            }
+        }
+    }
+}
+
+// Implement `AddressOffset` on non-zero unsigned integers via their
+// underlying primitive integer.
+macro_rules! implement_offset_nonzero {
+    ( $self:ty ) => {
+        impl AddressOffset for $self {
+            #[inline]
+            #[must_use]
+            fn checked_add_offset(self, offset: i128) -> Option<Self> {
+                Self::new(self.get().checked_add_offset(offset)?)
+            }
+
+            #[inline]
+            #[must_use]
+            fn wrapping_add_offset(self, offset: i128) -> Option<Self> {
+                Self::new(self.get().wrapping_add_offset(offset)?)
+            }
+        }
+    }
+}
+
+implement_offset!(u16);
+implement_offset!(u32);
+implement_offset!(u64);
+implement_offset!(usize);
+implement_offset_nonzero!(core::num::NonZeroU16);
+implement_offset_nonzero!(core::num::NonZeroU32);
+implement_offset_nonzero!(core::num::NonZeroU64);
+implement_offset_nonzero!(core::num::NonZeroUsize);
+
+// Implement `FromStr` for all wrappers of a primitive integer. The string is
+// parsed as native value, and then converted to the target endianness. This
+// mirrors `Display`, which prints the native value. Decimal notation is
+// used, unless the string starts with `0x`, in which case hexadecimal
+// notation is used.
+macro_rules! implement_from_str {
+    ( $native:ty ) => {
+        impl core::str::FromStr for BigEndian<$native> {
+            type Err = core::num::ParseIntError;
+
+            fn from_str(v: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from_native(implement_from_str!(@parse $native, v)?))
+            }
+        }
+
+        impl core::str::FromStr for LittleEndian<$native> {
+            type Err = core::num::ParseIntError;
+
+            fn from_str(v: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from_native(implement_from_str!(@parse $native, v)?))
+            }
+        }
+
+        impl<Value, Alignment> core::str::FromStr for Integer<Value, Alignment, $native>
+        where
+            Value: Copy + NativeEndian<$native>,
+            Alignment: Copy,
+        {
+            type Err = core::num::ParseIntError;
+
+            fn from_str(v: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from_native(implement_from_str!(@parse $native, v)?))
+            }
+        }
+    };
+    ( @parse $native:ty, $v:expr ) => {
+        match $v.strip_prefix("0x") {
+            Some(hex) => <$native>::from_str_radix(hex, 16),
+            None => <$native as core::str::FromStr>::from_str($v),
+        }
+    };
+}
+
+// Implement `bool` conversions for all wrappers of an unsigned integer.
+// Foreign booleans are usually integers where any non-zero value is true,
+// which `bool` cannot represent. Hence, they are kept as integers and only
+// converted on access.
+macro_rules! implement_bool {
+    ( $native:ty ) => {
+        implement_bool!(@impl BigEndian<$native>, $native, );
+        implement_bool!(@impl LittleEndian<$native>, $native, );
+        implement_bool!(
+            @impl Integer<Value, Alignment, $native>,
+            $native,
+            Value: Copy + NativeEndian<$native>,
+            Alignment: Copy,
+        );
+    };
+    ( @impl $self:ty, $native:ty, $($bounds:tt)* ) => {
+        impl<$($bounds)*> $self {
+            /// ## Create from boolean
+            ///
+            /// Create a new value from a boolean, encoding `true` as 1.
+            #[inline]
+            #[must_use]
+            pub fn from_bool(v: bool) -> Self {
+                Self::from_native(v as $native)
+            }
+
+            /// ## Yield value as boolean
+            ///
+            /// Return whether the value is non-zero.
+            #[inline]
+            #[must_use]
+            pub fn to_bool(self) -> bool {
+                self.to_native() != 0
+            }
+        }
+    };
+}
+
+// Implement `char` conversions for all wrappers of `u32`. Foreign code
+// points can be any integer, which `char` cannot represent. Hence, they are
+// kept as integers and validated when read.
+macro_rules! implement_char {
+    ( $native:ty ) => {
+        implement_char!(@impl BigEndian<$native>, );
+        implement_char!(@impl LittleEndian<$native>, );
+        implement_char!(
+            @impl Integer<Value, Alignment, $native>,
+            Value: Copy + NativeEndian<$native>,
+            Alignment: Copy,
+        );
+    };
+    ( @impl $self:ty, $($bounds:tt)* ) => {
+        impl<$($bounds)*> $self {
+            /// ## Create from character
+            ///
+            /// Create a new value from the code point of a character.
+            #[inline]
+            #[must_use]
+            pub fn from_char(v: char) -> Self {
+                Self::from_native(v.into())
+            }
+
+            /// ## Yield value as character
+            ///
+            /// Return the character with the value as code point, or `None`
+            /// if the value is not a valid Unicode scalar value.
+            #[inline]
+            #[must_use]
+            pub fn to_char(self) -> Option<char> {
+                char::from_u32(self.to_native())
+            }
+        }
+    };
+}
+
+implement_bool!(u8);
+implement_bool!(u16);
+implement_bool!(u32);
+implement_bool!(u64);
+implement_char!(u32);
+
+implement_from_str!(i8);
+implement_from_str!(i16);
+implement_from_str!(i32);
+implement_from_str!(i64);
+implement_from_str!(i128);
+implement_from_str!(isize);
+implement_from_str!(u8);
+implement_from_str!(u16);
+implement_from_str!(u32);
+implement_from_str!(u64);
+implement_from_str!(u128);
+implement_from_str!(usize);
+
 impl<Value, Alignment, Native> Integer<Value, Alignment, Native>
 where
     Value: Copy,
@@ -956,7 +1193,10 @@ where
     }
 }
 
-// Compare based on native value.
+// Compare based on raw value. Both operands share the same encoding, and
+// conversion to native values is a bijection. Hence, comparing the raw
+// values is equivalent to comparing native values, but avoids the
+// conversion of foreign-ordered values.
 impl<Value, Alignment, Native> PartialEq for Integer<Value, Alignment, Native>
 where
     Value: Copy + NativeEndian<Native>,
@@ -965,7 +1205,7 @@ where
 {
     #[must_use]
     fn eq(&self, other: &Self) -> bool {
-        self.to_native().eq(&other.to_native())
+        self.to_raw().eq(&other.to_raw())
     }
 }
 
@@ -1020,6 +1260,115 @@ where
     }
 }
 
+impl<Address, Target> Pointer<Address, Target>
+where
+    Address: Copy,
+    Target: Sized,
+{
+    /// ## Offset pointer by bytes with overflow check
+    ///
+    /// Add the offset in bytes to the address of this pointer. The
+    /// arithmetic is performed on the native value of the address, and thus
+    /// with the width of the address type rather than `usize`. If the
+    /// result overflows the address type, or if it is 0, this yields
+    /// `None`.
+    ///
+    /// Combined with `cast()` and `core::mem::offset_of!()`, this can be
+    /// used to project pointers to structures onto their fields.
+    #[inline]
+    #[must_use]
+    pub fn checked_byte_offset<Native>(self, bytes: isize) -> Option<Self>
+    where
+        Self: NativeEndian<Native>,
+        Native: AddressOffset,
+    {
+        Some(Self::from_native(self.to_native().checked_add_offset(bytes as i128)?))
+    }
+
+    /// ## Offset pointer by elements with overflow check
+    ///
+    /// Add the offset in number of elements to the address of this pointer.
+    /// See `checked_byte_offset()` for details.
+    #[inline]
+    #[must_use]
+    pub fn checked_offset<Native>(self, count: isize) -> Option<Self>
+    where
+        Self: NativeEndian<Native>,
+        Native: AddressOffset,
+    {
+        let bytes = (count as i128).checked_mul(core::mem::size_of::<Target>() as i128)?;
+        Some(Self::from_native(self.to_native().checked_add_offset(bytes)?))
+    }
+
+    /// ## Offset pointer by bytes
+    ///
+    /// Add the offset in bytes to the address of this pointer. See
+    /// `checked_byte_offset()` for details.
+    ///
+    /// Panics
+    /// ------
+    ///
+    /// Panics if the result overflows the address type, or if it is 0.
+    #[inline]
+    #[must_use]
+    pub fn byte_offset<Native>(self, bytes: isize) -> Self
+    where
+        Self: NativeEndian<Native>,
+        Native: AddressOffset,
+    {
+        self.checked_byte_offset(bytes).expect("pointer offset must not overflow")
+    }
+
+    /// ## Offset pointer by elements
+    ///
+    /// Add the offset in number of elements to the address of this pointer.
+    /// See `checked_byte_offset()` for details.
+    ///
+    /// Panics
+    /// ------
+    ///
+    /// Panics if the result overflows the address type, or if it is 0.
+    #[inline]
+    #[must_use]
+    pub fn offset<Native>(self, count: isize) -> Self
+    where
+        Self: NativeEndian<Native>,
+        Native: AddressOffset,
+    {
+        self.checked_offset(count).expect("pointer offset must not overflow")
+    }
+
+    /// ## Offset pointer by bytes with wrapping
+    ///
+    /// Add the offset in bytes to the address of this pointer, wrapping
+    /// around at the boundaries of the address type. Since pointers cannot
+    /// be 0, this yields `None` if the result is 0.
+    #[inline]
+    #[must_use]
+    pub fn wrapping_byte_offset<Native>(self, bytes: isize) -> Option<Self>
+    where
+        Self: NativeEndian<Native>,
+        Native: AddressOffset,
+    {
+        Some(Self::from_native(self.to_native().wrapping_add_offset(bytes as i128)?))
+    }
+
+    /// ## Offset pointer by elements with wrapping
+    ///
+    /// Add the offset in number of elements to the address of this pointer.
+    /// See `wrapping_byte_offset()` for details.
+    #[inline]
+    #[must_use]
+    pub fn wrapping_offset<Native>(self, count: isize) -> Option<Self>
+    where
+        Self: NativeEndian<Native>,
+        Native: AddressOffset,
+    {
+        let bytes = (count as i128).wrapping_mul(core::mem::size_of::<Target>() as i128);
+        Some(Self::from_native(self.to_native().wrapping_add_offset(bytes)?))
+    }
+}
+
 // Implement clone via shallow-copy.
 impl<Address, Target> Clone for Pointer<Address, Target>
 where
@@ -1260,6 +1609,202 @@ where
     }
 }
 
+impl<Address, Target> SlicePointer<Address, Target>
+where
+    Address: Copy,
+{
+    /// ## Create new instance
+    ///
+    /// Create a new instance of this slice pointer type from the provided
+    /// pointer to the first element and the number of elements.
+    #[inline]
+    #[must_use]
+    pub const fn new(pointer: Pointer<Address, Target>, len: usize) -> Self {
+        Self {
+            pointer: pointer,
+            len: len,
+        }
+    }
+
+    /// ## Yield pointer to first element
+    ///
+    /// Return the pointer to the first element of the slice. For empty
+    /// slices, this might be dangling.
+    #[inline(always)]
+    #[must_use]
+    pub const fn pointer(&self) -> Pointer<Address, Target> {
+        self.pointer
+    }
+
+    /// ## Yield underlying address
+    ///
+    /// Return the address of the first element of the slice.
+    #[inline(always)]
+    #[must_use]
+    pub const fn address(&self) -> Address {
+        self.pointer.address()
+    }
+
+    /// ## Yield number of elements
+    ///
+    /// Return the number of elements of the slice.
+    #[inline(always)]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// ## Check for empty slice
+    ///
+    /// Return `true` if the slice has no elements.
+    #[inline(always)]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<Address, Target> SlicePointer<Address, Target>
+where
+    Address: Copy + NativeAddress<Target>,
+{
+    /// ## Yield as slice
+    ///
+    /// Return the slice described by this slice pointer.
+    ///
+    /// Safety
+    /// ------
+    ///
+    /// The caller must ensure that the address and length describe a valid
+    /// slice, following the requirements of `core::slice::from_raw_parts()`.
+    #[inline]
+    #[must_use]
+    pub unsafe fn as_slice<'a>(&self) -> &'a [Target] {
+        // SAFETY: Delegated to caller.
+        unsafe { core::slice::from_raw_parts(self.pointer.as_ptr(), self.len) }
+    }
+
+    /// ## Yield as mutable slice
+    ///
+    /// Return the mutable slice described by this slice pointer.
+    ///
+    /// Safety
+    /// ------
+    ///
+    /// The caller must ensure that the address and length describe a valid
+    /// slice, following the requirements of
+    /// `core::slice::from_raw_parts_mut()`.
+    #[inline]
+    #[must_use]
+    pub unsafe fn as_mut_slice<'a>(&self) -> &'a mut [Target] {
+        // SAFETY: Delegated to caller.
+        unsafe { core::slice::from_raw_parts_mut(self.pointer.as_mut_ptr(), self.len) }
+    }
+}
+
+// Implement clone via shallow-copy.
+impl<Address, Target> Clone for SlicePointer<Address, Target>
+where
+    Address: Copy,
+{
+    #[inline]
+    #[must_use]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+// Implement copy via shallow-copy.
+impl<Address, Target> Copy for SlicePointer<Address, Target>
+where
+    Address: Copy,
+{
+}
+
+// For debugging simply print the values.
+impl<Address, Target> core::fmt::Debug for SlicePointer<Address, Target>
+where
+    Address: Copy + core::fmt::Debug,
+{
+    fn fmt(
+        &self,
+        fmt: &mut core::fmt::Formatter<'_>,
+    ) -> Result<(), core::fmt::Error> {
+        fmt.debug_tuple("SlicePointer")
+           .field(&self.address())
+           .field(&self.len())
+           .finish()
+    }
+}
+
+// Compare address and length.
+impl<Address, Target> Eq for SlicePointer<Address, Target>
+where
+    Address: Copy + Eq,
+{
+}
+
+// Hash address and length.
+impl<Address, Target> core::hash::Hash for SlicePointer<Address, Target>
+where
+    Address: Copy + core::hash::Hash,
+{
+    fn hash<Op>(&self, state: &mut Op)
+    where
+        Op: core::hash::Hasher,
+    {
+        self.address().hash(state);
+        self.len().hash(state);
+    }
+}
+
+// Compare address and length.
+impl<Address, Target> PartialEq for SlicePointer<Address, Target>
+where
+    Address: Copy + PartialEq,
+{
+    #[must_use]
+    fn eq(&self, other: &Self) -> bool {
+        self.address().eq(&other.address()) && self.len() == other.len()
+    }
+}
+
+// Implement import from slice based on NativeAddress.
+impl<Address, Target> From<&[Target]> for SlicePointer<Address, Target>
+where
+    Address: Copy + NativeAddress<Target>,
+{
+    #[inline]
+    #[must_use]
+    fn from(v: &[Target]) -> Self {
+        // SAFETY: Slice addresses cannot be NULL, not even for empty slices.
+        unsafe {
+            Self::new(
+                Pointer::from_usize_unchecked(v.as_ptr() as usize),
+                v.len(),
+            )
+        }
+    }
+}
+
+// Implement import from mutable slice based on NativeAddress.
+impl<Address, Target> From<&mut [Target]> for SlicePointer<Address, Target>
+where
+    Address: Copy + NativeAddress<Target>,
+{
+    #[inline]
+    #[must_use]
+    fn from(v: &mut [Target]) -> Self {
+        // SAFETY: Slice addresses cannot be NULL, not even for empty slices.
+        unsafe {
+            Self::new(
+                Pointer::from_usize_unchecked(v.as_mut_ptr() as usize),
+                v.len(),
+            )
+        }
+    }
+}
+
 // Implement `constant()` for a type
 //
 // Unfortunately, Rust does not allow `const fn` in traits, thus making it
@@ -1352,6 +1897,67 @@ macro_rules! ffi_util_constant {
 #[doc(inline)]
 pub use ffi_util_constant as constant;
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ffi_util_bitfield {
+    (
+        impl $type:ty {
+            $field:ident: $native:ty;
+            $(
+                $(#[$meta:meta])*
+                $vis:vis $get:ident, $set:ident: $lo:literal..$hi:literal;
+            )*
+        }
+    ) => {
+        impl $type {
+            $(
+                $(#[$meta])*
+                #[inline]
+                #[must_use]
+                $vis fn $get(&self) -> $native {
+                    const MASK: $native = <$native>::MAX >> (<$native>::BITS - ($hi - $lo));
+                    let v: $native = $crate::ffi::util::NativeEndian::to_native(self.$field);
+                    (v >> $lo) & MASK
+                }
+
+                $(#[$meta])*
+                #[inline]
+                $vis fn $set(&mut self, v: $native) {
+                    const MASK: $native = <$native>::MAX >> (<$native>::BITS - ($hi - $lo));
+                    debug_assert!(v & !MASK == 0, "Value exceeds the bitfield range");
+                    let old: $native = $crate::ffi::util::NativeEndian::to_native(self.$field);
+                    let new = (old & !(MASK << $lo)) | ((v & MASK) << $lo);
+                    self.$field = $crate::ffi::util::NativeEndian::from_native(new);
+                }
+            )*
+        }
+    }
+}
+
+/// ## Bitfield Accessors
+///
+/// This macro generates getters and setters for bit-ranges of an integer
+/// field of a type. The field must implement `NativeEndian` for the given
+/// unsigned native integer type (e.g., `Integer<BigEndian<u32>, ..>` for
+/// `u32`). Each accessor converts the field to native endianness, extracts
+/// or inserts the bit-range, and converts it back.
+///
+/// Bit-ranges are given as exclusive ranges of bit indices, starting at the
+/// least significant bit. Setters ignore bits of the value that exceed the
+/// range, but assert on them in debug builds.
+///
+/// ```ignore
+/// bitfield!(
+///     impl Header {
+///         flags: u32;
+///         pub kind, set_kind: 0..4;
+///         pub size, set_size: 4..32;
+///     }
+/// );
+/// ```
+#[doc(inline)]
+pub use ffi_util_bitfield as bitfield;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1493,6 +2099,25 @@ mod tests {
         assert!(Test16::from_native(0x0010) < Test16::from_native(0x0100));
     }
 
+    // Verify that comparing the raw values of `Integer` yields the same
+    // results as comparing native values, for all encodings.
+    #[test]
+    fn integer_eq_raw() {
+        type Big32 = Integer<BigEndian<u32>, align::Align4, u32>;
+        type Little32 = Integer<LittleEndian<u32>, align::Align4, u32>;
+        type Native32 = Integer<u32, align::Align4, u32>;
+
+        let values = [0, 1, 0xff, 0x100, 0x0102_0304, 0x0403_0201, u32::MAX];
+
+        for a in values {
+            for b in values {
+                assert_eq!(Big32::from_native(a) == Big32::from_native(b), a == b);
+                assert_eq!(Little32::from_native(a) == Little32::from_native(b), a == b);
+                assert_eq!(Native32::from_native(a) == Native32::from_native(b), a == b);
+            }
+        }
+    }
+
     // Verify `Integer` advanced type layout
     //
     // Check some non-standard type-parameters for `Integer` and verify the
@@ -1539,4 +2164,160 @@ mod tests {
             8,
         );
     }
+
+    // Verify `Pointer` offsets
+    //
+    // Compute element and byte offsets on native and 32-bit foreign
+    // addresses, and verify overflows are detected or wrapped at the width
+    // of the address type rather than `usize`.
+    #[test]
+    fn pointer_offset() {
+        type P32 = Pointer<Integer<LittleEndian<u32>, align::Align4, u32>, u32>;
+        type PNz = Pointer<core::num::NonZeroUsize, u16>;
+
+        let data: [u16; 4] = [1, 2, 3, 4];
+        let p = PNz::from(&data[0]);
+        assert_eq!(unsafe { *p.offset(2).as_ref() }, 3);
+        assert_eq!(unsafe { *p.offset(3).offset(-1).as_ref() }, 3);
+        assert_eq!(unsafe { *p.byte_offset(2).as_ref() }, 2);
+
+        let p = P32::from_native(0xffff_fff0);
+        assert_eq!(p.offset(2).to_native(), 0xffff_fff8);
+        assert_eq!(p.byte_offset(-0x10).to_native(), 0xffff_ffe0);
+        assert_eq!(p.checked_offset(3).map(|v| v.to_native()), Some(0xffff_fffc));
+        assert_eq!(p.checked_offset(4), None);
+        assert_eq!(p.checked_byte_offset(0x11), None);
+        assert_eq!(p.checked_byte_offset(-0xffff_fff0), None);
+        assert_eq!(p.checked_offset(isize::MAX), None);
+
+        // Wrapping happens at `u32::MAX`, but never yields 0.
+        assert_eq!(p.wrapping_offset(5).map(|v| v.to_native()), Some(0x4));
+        assert_eq!(p.wrapping_byte_offset(0x11).map(|v| v.to_native()), Some(0x1));
+        assert_eq!(p.wrapping_offset(4), None);
+        assert_eq!(P32::from_native(0x4).wrapping_offset(-2).map(|v| v.to_native()), Some(0xffff_fffc));
+    }
+
+    // Verify `SlicePointer` round-trips
+    //
+    // Create slice pointers from Rust slices and verify that the elements
+    // can be read back, and that the layout matches an address plus length.
+    #[test]
+    fn slice_pointer() {
+        let data: [u16; 3] = [1, 2, 3];
+        let p = SlicePointer::<core::num::NonZeroUsize, u16>::from(&data[..]);
+
+        assert_eq!(p.len(), 3);
+        assert!(!p.is_empty());
+        assert_eq!(p.address().get(), data.as_ptr() as usize);
+        assert_eq!(unsafe { p.as_slice() }, &[1, 2, 3]);
+        assert_eq!(p, p.clone());
+
+        // Empty slices have a non-NULL address as well.
+        let p = SlicePointer::<core::num::NonZeroUsize, u16>::from(&data[..0]);
+        assert!(p.is_empty());
+        assert_eq!(unsafe { p.as_slice() }, &[] as &[u16]);
+
+        // Mutable slices can be written through.
+        let mut data: [u32; 2] = [1, 2];
+        let p = SlicePointer::<usize, u32>::from(&mut data[..]);
+        unsafe { p.as_mut_slice()[1] = 7 };
+        assert_eq!(data, [1, 7]);
+
+        assert_eq!(
+            size_of::<SlicePointer<usize, u8>>(),
+            2 * size_of::<usize>(),
+        );
+    }
+
+    // Verify `bitfield!()` accessors
+    //
+    // Define a bitfield with three fields over a big-endian integer, and
+    // verify that values round-trip and are encoded in the correct bits and
+    // byte order.
+    #[test]
+    fn bitfield_roundtrip() {
+        #[derive(Clone, Copy, Default)]
+        struct Test {
+            flags: Integer<BigEndian<u32>, align::Align4, u32>,
+        }
+
+        bitfield!(
+            impl Test {
+                flags: u32;
+                kind, set_kind: 0..4;
+                size, set_size: 4..28;
+                tag, set_tag: 28..32;
+            }
+        );
+
+        let mut v = Test::default();
+        assert_eq!((v.kind(), v.size(), v.tag()), (0, 0, 0));
+
+        v.set_kind(0x5);
+        v.set_size(0xabcdef);
+        v.set_tag(0xf);
+        assert_eq!((v.kind(), v.size(), v.tag()), (0x5, 0xabcdef, 0xf));
+        assert_eq!(v.flags.to_native(), 0xfabcdef5);
+        assert_eq!(v.flags.to_raw(), 0xfabcdef5u32.to_be());
+
+        // Setting a field must not affect neighboring fields.
+        v.set_size(0x1);
+        assert_eq!((v.kind(), v.size(), v.tag()), (0x5, 0x1, 0xf));
+        assert_eq!(v.flags.to_native(), 0xf0000015);
+    }
+
+    // Verify `FromStr` for endian wrappers
+    //
+    // Parse decimal and hexadecimal strings into big-endian and
+    // little-endian wrappers, and verify that the native value is stored in
+    // the correct byte order.
+    #[test]
+    fn endian_from_str() {
+        let be: BigEndian<u32> = "305419896".parse().unwrap();
+        let le: LittleEndian<u32> = "305419896".parse().unwrap();
+        assert_eq!(be.to_native(), 0x12345678);
+        assert_eq!(be.to_raw(), 0x12345678u32.to_be());
+        assert_eq!(le.to_native(), 0x12345678);
+        assert_eq!(le.to_raw(), 0x12345678u32.to_le());
+
+        let be: BigEndian<u32> = "0x12345678".parse().unwrap();
+        let le: LittleEndian<u32> = "0x12345678".parse().unwrap();
+        assert_eq!(be.to_native(), 0x12345678);
+        assert_eq!(le.to_native(), 0x12345678);
+
+        let v: Integer<BigEndian<i16>, align::Align2, i16> = "-2".parse().unwrap();
+        assert_eq!(v.to_native(), -2);
+        assert_eq!(std::format!("{}", v), "-2");
+
+        assert!("0x".parse::<BigEndian<u8>>().is_err());
+        assert!("256".parse::<LittleEndian<u8>>().is_err());
+        assert!("foo".parse::<Integer<u32, align::Align4, u32>>().is_err());
+    }
+
+    // Verify `bool` and `char` support
+    //
+    // Wrap booleans and characters directly, preserving their niches, and
+    // convert foreign-ordered integers to and from them.
+    #[test]
+    fn endian_bool_char() {
+        let v: Integer<bool, align::Align4, bool> = Integer::new(true);
+        assert!(v.to_native());
+        assert_eq!(size_of::<Integer<bool, align::Align4, bool>>(), 4);
+        assert_eq!(size_of::<Option<Integer<bool, align::Align1, bool>>>(), 1);
+        assert_eq!(size_of::<Option<Integer<char, align::Align4, char>>>(), 4);
+
+        // Any non-zero integer is true.
+        assert!(BigEndian::<u16>::from_native(0x100).to_bool());
+        assert!(LittleEndian::<u8>::from_raw(0xff).to_bool());
+        assert!(!Integer::<BigEndian<u32>, align::Align4, u32>::from_native(0).to_bool());
+        assert_eq!(BigEndian::<u32>::from_bool(true).to_raw(), 1u32.to_be());
+
+        // Code points are validated when read.
+        let v = BigEndian::<u32>::from_char('\u{1f600}');
+        assert_eq!(v.to_raw(), 0x1f600u32.to_be());
+        assert_eq!(v.to_char(), Some('\u{1f600}'));
+        assert_eq!(LittleEndian::<u32>::from_native(0xd800).to_char(), None);
+        assert_eq!(Integer::<LittleEndian<u32>, align::Align4, u32>::from_native(0x110000).to_char(), None);
+        assert_eq!(Integer::<u32, align::Align4, u32>::from_char('a').to_native(), 0x61);
+    }
 }
@@ -25,6 +25,8 @@ pub struct Setup<'ctx> {
 /// regarding the state and lifetime of the application.
 pub struct Context {
     pub(crate) gio: gio::Application,
+    quit_pending: std::rc::Rc<std::cell::Cell<bool>>,
+    pub(crate) menu_bar: std::cell::RefCell<Option<gio::MenuModel>>,
 }
 
 impl<'ctx> Setup<'ctx> {
@@ -35,25 +37,100 @@ impl<'ctx> Setup<'ctx> {
     pub fn initialize(
         &self,
     ) -> Result<Context, Box<dyn std::error::Error>> {
-        let v_gio = gio::Application::new(
-            match self.id {
-                // XXX: The application ID is not used for `NON_UNIQUE`
-                //      applications, yet gio has some weird behavior if
-                //      none is set. We use a dummy value for now, but
-                //      this should be solved properly.
-                None => Some("foo.osiris.unknown"),
-                Some(ref v) => Some(v),
-            },
-            gio::ApplicationFlags::NON_UNIQUE,
-        );
+        let v_id = match self.id {
+            // XXX: The application ID is not used for `NON_UNIQUE`
+            //      applications, yet gio has some weird behavior if
+            //      none is set. We use a dummy value for now, but
+            //      this should be solved properly.
+            None => Some("foo.osiris.unknown"),
+            Some(ref v) => Some(*v),
+        };
+
+        // GTK applications handle accelerators of menus and track their
+        // windows, but require a display. Without one, fall back to a plain
+        // gio application.
+        let v_gio: gio::Application = if gtk::init().is_ok() {
+            <_ as gio::prelude::Cast>::upcast(
+                gtk::Application::new(v_id, gio::ApplicationFlags::NON_UNIQUE),
+            )
+        } else {
+            gio::Application::new(v_id, gio::ApplicationFlags::NON_UNIQUE)
+        };
 
         <_ as gio::prelude::ApplicationExt>::register(
             &v_gio,
             None::<&gio::Cancellable>,
         ).map_err(|v| Box::new(v))?;
 
+        // The application is activated when its main-loop is entered. There
+        // is nothing to do, but gio warns about applications without any
+        // handler.
+        <_ as gio::prelude::ApplicationExt>::connect_activate(&v_gio, |_| {});
+
         Ok(Context {
             gio: v_gio,
+            quit_pending: Default::default(),
+            menu_bar: Default::default(),
         })
     }
 }
+
+impl Context {
+    /// ## Run Application Main-Loop
+    ///
+    /// Run the main-loop of the application and dispatch events until
+    /// `Self::quit()` is called. The application is held while the
+    /// main-loop runs, so it does not stop when idle.
+    pub fn run(
+        &self,
+    ) {
+        // Requests made before the main-loop was entered are picked up by
+        // the first iteration of the main-loop.
+        let v_gio = self.gio.clone();
+        let v_pending = self.quit_pending.clone();
+        gio::glib::idle_add_local_once(move || {
+            if v_pending.get() {
+                <_ as gio::prelude::ApplicationExt>::quit(&v_gio);
+            }
+        });
+
+        let _hold = <_ as gio::prelude::ApplicationExtManual>::hold(&self.gio);
+        <_ as gio::prelude::ApplicationExtManual>::run_with_args::<&str>(&self.gio, &[]);
+
+        self.quit_pending.set(false);
+    }
+
+    /// ## Quit Application Main-Loop
+    ///
+    /// Stop the main-loop entered via `Self::run()`. Requests made while no
+    /// main-loop runs make the next call to `Self::run()` return
+    /// immediately.
+    pub fn quit(
+        &self,
+    ) {
+        self.quit_pending.set(true);
+        <_ as gio::prelude::ApplicationExt>::quit(&self.gio);
+    }
+
+    /// ## Yield Menu Bar
+    ///
+    /// Yield the model of the menu bar set via `menu::set_menu_bar()`, if
+    /// any. Its actions are registered on the application with the `app.`
+    /// prefix, so windows can place the model in their header bar.
+    pub fn menu_bar(&self) -> Option<gio::MenuModel> {
+        self.menu_bar.borrow().clone()
+    }
+}
+
+/// ## Open URL
+///
+/// Launch the default handler of the desktop environment for the given URL.
+/// The URL is passed verbatim and must be validated by the caller.
+pub fn open_url(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    gio::AppInfo::launch_default_for_uri(
+        url,
+        None::<&gio::AppLaunchContext>,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! # File Dialog APIs
+//!
+//! This module provides file dialogs to let the user select files to open or
+//! save. The dialogs are based on `GtkFileChooserNative`, which uses the
+//! file-chooser portal of the XDG Desktop Portal Specification[^portal] if
+//! available, and falls back to a GTK dialog otherwise.
+//!
+//! [^portal]: <https://flatpak.github.io/xdg-desktop-portal/>
+
+use crate::application;
+use gio;
+use gtk;
+
+/// ## Dialog Options
+///
+/// The options used to set up a file dialog. `filters` lists a name for each
+/// filter, and the file extensions (without leading dot) it allows.
+#[derive(Default)]
+pub struct Options<'ctx> {
+    /// Title of the dialog, or `None` to use a default title.
+    pub title: Option<&'ctx str>,
+    /// File filters to offer to the user.
+    pub filters: &'ctx [(&'ctx str, &'ctx [&'ctx str])],
+    /// Directory to start in, or `None` to use a default.
+    pub directory: Option<&'ctx std::path::Path>,
+    /// Suggested file name when saving.
+    pub name: Option<&'ctx str>,
+}
+
+/// ## Completion Callback
+///
+/// The callback invoked once a dialog is closed. It carries the selected
+/// path, or `None` if the dialog was cancelled.
+pub type Done = Box<
+    dyn FnOnce(
+        Result<Option<std::path::PathBuf>, Box<dyn std::error::Error + Send + Sync>>,
+    ) + Send,
+>;
+
+/// ## Check for Headless Systems
+///
+/// Return whether no display server is available to show dialogs on.
+pub fn headless() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_none()
+        && std::env::var_os("DISPLAY").is_none()
+}
+
+fn show(
+    _app: &application::Context,
+    action: gtk::FileChooserAction,
+    options: &Options,
+    done: Done,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use gtk::prelude::*;
+
+    // GTK is initialized lazily, since the application does not use it
+    // otherwise. This fails if no display can be opened.
+    gtk::init().map_err(|v| Box::new(v))?;
+
+    let v_dialog = gtk::FileChooserNative::new(
+        options.title,
+        None::<&gtk::Window>,
+        action,
+        None,
+        None,
+    );
+    v_dialog.set_modal(true);
+
+    for (name, extensions) in options.filters {
+        let v_filter = gtk::FileFilter::new();
+        v_filter.set_name(Some(name));
+        for v in extensions.iter() {
+            v_filter.add_pattern(&format!("*.{}", v));
+        }
+        v_dialog.add_filter(&v_filter);
+    }
+
+    if let Some(v) = options.directory {
+        v_dialog.set_current_folder(Some(&gio::File::for_path(v)))
+            .map_err(|v| Box::new(v))?;
+    }
+    if let Some(v) = options.name {
+        v_dialog.set_current_name(v);
+    }
+
+    // Native dialogs are not owned by any window, so the response handler
+    // keeps the dialog alive until it is closed. The handler is dispatched
+    // by the default main-context, which must be run by the caller.
+    let holder = std::cell::RefCell::new(Some((v_dialog.clone(), done)));
+    v_dialog.connect_response(move |dialog, response| {
+        let Some((_, done)) = holder.borrow_mut().take() else {
+            return;
+        };
+
+        let r = match response {
+            gtk::ResponseType::Accept => dialog.file().and_then(|v| v.path()),
+            _ => None,
+        };
+        done(Ok(r));
+    });
+    v_dialog.show();
+
+    Ok(())
+}
+
+/// ## Show Open Dialog
+///
+/// Show a dialog to select an existing file and invoke `done` once the
+/// dialog is closed.
+pub fn open_file(
+    app: &application::Context,
+    options: &Options,
+    done: Done,
+) -> Result<(), Box<dyn std::error::Error>> {
+    show(app, gtk::FileChooserAction::Open, options, done)
+}
+
+/// ## Show Save Dialog
+///
+/// Show a dialog to select a file path to save to and invoke `done` once
+/// the dialog is closed.
+pub fn save_file(
+    app: &application::Context,
+    options: &Options,
+    done: Done,
+) -> Result<(), Box<dyn std::error::Error>> {
+    show(app, gtk::FileChooserAction::Save, options, done)
+}
@@ -13,4 +13,6 @@
 //! can switch to a custom implementation of the specifications in the future.
 
 pub mod application;
+pub mod dialog;
+pub mod menu;
 pub mod notification;
@@ -0,0 +1,170 @@
+//! # Menu APIs
+//!
+//! This module provides menu bars for applications. Menus are exported as
+//! `GMenuModel` with their actions registered on the application. Unlike
+//! other platforms, there is no global menu bar on Linux. Instead, windows
+//! are expected to place the model in their header bar (e.g., via a
+//! `GtkPopoverMenuBar` or a primary menu button). Accelerators are
+//! registered with GTK, and thus only available if the application could
+//! connect to a display.
+
+use crate::application;
+use gio;
+use gtk;
+
+/// ## Menu Accelerator
+///
+/// A keyboard shortcut that activates a menu action. `primary` selects the
+/// primary modifier of the platform, which is `Ctrl` on Linux.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Accelerator {
+    /// Key to press, as lower-case character.
+    pub key: char,
+    /// Whether the primary modifier must be held.
+    pub primary: bool,
+    /// Whether `Shift` must be held.
+    pub shift: bool,
+    /// Whether `Alt` must be held.
+    pub alt: bool,
+}
+
+/// ## Menu Entries
+///
+/// A single entry of a menu. Actions are identified by their `id`, which is
+/// passed to the dispatch callback when the action is activated.
+pub enum Entry<'ctx> {
+    /// Action with a label and an optional accelerator.
+    Action {
+        id: usize,
+        label: &'ctx str,
+        accelerator: Option<Accelerator>,
+    },
+    /// Separator between groups of entries.
+    Separator,
+    /// Nested menu with a label.
+    Submenu {
+        label: &'ctx str,
+        entries: Vec<Entry<'ctx>>,
+    },
+}
+
+/// ## Dispatch Callback
+///
+/// The callback invoked with the ID of an activated action. It is always
+/// invoked on the main-loop of the application.
+pub type Dispatch = Box<dyn Fn(usize)>;
+
+// Prefix of the names of all actions registered for menus.
+const ACTION_PREFIX: &str = "osiris-menu-";
+
+// Format an accelerator in the syntax of `gtk_accelerator_parse()`.
+fn accel(v: &Accelerator) -> String {
+    let mut r = String::new();
+
+    if v.primary {
+        r.push_str("<Primary>");
+    }
+    if v.shift {
+        r.push_str("<Shift>");
+    }
+    if v.alt {
+        r.push_str("<Alt>");
+    }
+    r.push(v.key);
+
+    r
+}
+
+// Build the menu model of the given entries and register their actions on
+// the application. Separators are represented by splitting the entries into
+// sections. A single section is returned directly.
+fn build(
+    app: &gio::Application,
+    entries: &[Entry],
+    dispatch: &std::rc::Rc<Dispatch>,
+) -> gio::Menu {
+    use gio::prelude::*;
+
+    let mut sections = vec![gio::Menu::new()];
+
+    for entry in entries {
+        let section = sections.last().unwrap();
+
+        match *entry {
+            Entry::Action { id, label, ref accelerator } => {
+                let name = format!("{}{}", ACTION_PREFIX, id);
+                let detailed = format!("app.{}", name);
+                let v_action = gio::SimpleAction::new(&name, None);
+                let dispatch = dispatch.clone();
+                v_action.connect_activate(move |_, _| dispatch(id));
+                app.add_action(&v_action);
+
+                let v_item = gio::MenuItem::new(Some(label), Some(&detailed));
+                if let Some(v) = accelerator {
+                    // The attribute is only shown in the menu, while GTK
+                    // needs the accelerator to activate the action.
+                    let v_accel = accel(v);
+                    if let Some(v_gtk) = app.downcast_ref::<gtk::Application>() {
+                        <_ as gtk::prelude::GtkApplicationExt>::set_accels_for_action(
+                            v_gtk,
+                            &detailed,
+                            &[&v_accel],
+                        );
+                    }
+                    v_item.set_attribute_value("accel", Some(&v_accel.to_variant()));
+                }
+                section.append_item(&v_item);
+            },
+            Entry::Separator => {
+                sections.push(gio::Menu::new());
+            },
+            Entry::Submenu { label, ref entries } => {
+                section.append_submenu(Some(label), &build(app, entries, dispatch));
+            },
+        }
+    }
+
+    if sections.len() == 1 {
+        return sections.pop().unwrap();
+    }
+
+    let v_menu = gio::Menu::new();
+    for v in sections.iter().filter(|v| v.n_items() > 0) {
+        v_menu.append_section(None, v);
+    }
+    v_menu
+}
+
+/// ## Set Menu Bar
+///
+/// Set the menu bar of the application to the given entries, replacing any
+/// previous menu bar. `dispatch` is invoked on the main-loop whenever an
+/// action is activated. The resulting model can be acquired via
+/// `application::Context::menu_bar()`.
+pub fn set_menu_bar(
+    app: &application::Context,
+    entries: &[Entry],
+    dispatch: Dispatch,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use gio::prelude::*;
+
+    // Drop all actions of the previous menu bar, including their
+    // accelerators.
+    for name in app.gio.list_actions() {
+        if name.starts_with(ACTION_PREFIX) {
+            if let Some(v_gtk) = app.gio.downcast_ref::<gtk::Application>() {
+                <_ as gtk::prelude::GtkApplicationExt>::set_accels_for_action(
+                    v_gtk,
+                    &format!("app.{}", name),
+                    &[],
+                );
+            }
+            app.gio.remove_action(&name);
+        }
+    }
+
+    let v_menu = build(&app.gio, entries, &std::rc::Rc::new(dispatch));
+    app.menu_bar.replace(Some(v_menu.upcast()));
+
+    Ok(())
+}
@@ -25,6 +25,7 @@ pub struct Setup<'ctx> {
 /// regarding the state and lifetime of the application.
 pub struct Context {
     pub(crate) app: objc2::rc::Id<icrate::AppKit::NSApplication>,
+    quit_pending: std::sync::atomic::AtomicBool,
 }
 
 impl<'ctx> Setup<'ctx> {
@@ -42,6 +43,7 @@ impl<'ctx> Setup<'ctx> {
 
         Ok(Context {
             app: icrate::AppKit::NSApplication::sharedApplication(mtm),
+            quit_pending: std::sync::atomic::AtomicBool::new(false),
         })
     }
 }
@@ -50,12 +52,73 @@ impl Context {
     /// ## Run Application Main-Loop
     ///
     /// Enter the main-loop of the application context and dispatch messages
-    /// until the application is terminated.
+    /// until the application is terminated. If a quit was requested before,
+    /// the main-loop is not entered at all.
     pub fn run(
         &self,
     ) {
+        if self.quit_pending.swap(false, std::sync::atomic::Ordering::SeqCst) {
+            return;
+        }
+
         unsafe {
             self.app.run();
         }
+
+        self.quit_pending.store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// ## Quit Application Main-Loop
+    ///
+    /// Stop the main-loop entered via `Self::run()`. The main-loop only
+    /// checks for stop requests after dispatching an event, so an empty
+    /// application-defined event is posted to wake it up. Requests made
+    /// while no main-loop runs make the next call to `Self::run()` return
+    /// immediately.
+    pub fn quit(
+        &self,
+    ) {
+        self.quit_pending.store(true, std::sync::atomic::Ordering::SeqCst);
+
+        unsafe {
+            self.app.stop(None);
+
+            let v_event = icrate::AppKit::NSEvent::otherEventWithType_location_modifierFlags_timestamp_windowNumber_context_subtype_data1_data2(
+                icrate::AppKit::NSEventTypeApplicationDefined,
+                icrate::Foundation::NSPoint::new(0.0, 0.0),
+                0,
+                0.0,
+                0,
+                None,
+                0,
+                0,
+                0,
+            );
+            if let Some(v) = v_event {
+                self.app.postEvent_atStart(&v, true);
+            }
+        }
     }
 }
+
+/// ## Open URL
+///
+/// Launch the default handler of the system for the given URL via the
+/// shared workspace. The URL is passed verbatim and must be validated by
+/// the caller.
+pub fn open_url(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let v_str = icrate::Foundation::NSString::from_str(url);
+    let v_url = unsafe { icrate::Foundation::NSURL::URLWithString(&v_str) }
+        .ok_or::<Box<dyn std::error::Error>>(
+            "error: cannot create URL object".into(),
+        )?;
+
+    let ok = unsafe {
+        icrate::AppKit::NSWorkspace::sharedWorkspace().openURL(&v_url)
+    };
+    if !ok {
+        return Err("error: cannot open URL".into());
+    }
+
+    Ok(())
+}
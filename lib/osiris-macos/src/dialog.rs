@@ -0,0 +1,156 @@
+//! # File Dialog APIs
+//!
+//! This module provides file dialogs to let the user select files to open or
+//! save. The dialogs are based on `NSOpenPanel` and `NSSavePanel` of AppKit.
+
+use crate::application;
+use block2;
+use icrate;
+use objc2;
+
+/// ## Dialog Options
+///
+/// The options used to set up a file dialog. `filters` lists a name for each
+/// filter, and the file extensions (without leading dot) it allows.
+#[derive(Default)]
+pub struct Options<'ctx> {
+    /// Title of the dialog, or `None` to use a default title.
+    pub title: Option<&'ctx str>,
+    /// File filters to offer to the user.
+    pub filters: &'ctx [(&'ctx str, &'ctx [&'ctx str])],
+    /// Directory to start in, or `None` to use a default.
+    pub directory: Option<&'ctx std::path::Path>,
+    /// Suggested file name when saving.
+    pub name: Option<&'ctx str>,
+}
+
+/// ## Completion Callback
+///
+/// The callback invoked once a dialog is closed. It carries the selected
+/// path, or `None` if the dialog was cancelled.
+pub type Done = Box<
+    dyn FnOnce(
+        Result<Option<std::path::PathBuf>, Box<dyn std::error::Error + Send + Sync>>,
+    ) + Send,
+>;
+
+/// ## Check for Headless Systems
+///
+/// Return whether no window server is available to show dialogs on. Panels
+/// can only be shown from the main thread, so this is also reported for
+/// any other thread.
+pub fn headless() -> bool {
+    icrate::Foundation::MainThreadMarker::new().is_none()
+}
+
+// Apply the options to a panel. Panels do not support named filters, so
+// the extensions of all filters are allowed together.
+fn setup(
+    panel: &icrate::AppKit::NSSavePanel,
+    options: &Options,
+) {
+    if let Some(v) = options.title {
+        let v_str = icrate::Foundation::NSString::from_str(v);
+        unsafe { panel.setTitle(&v_str) };
+    }
+
+    let extensions: Vec<objc2::rc::Id<icrate::Foundation::NSString>> = options.filters
+        .iter()
+        .flat_map(|(_, v)| v.iter())
+        .map(|v| icrate::Foundation::NSString::from_str(v))
+        .collect();
+    if !extensions.is_empty() {
+        let v_array = icrate::Foundation::NSArray::from_vec(extensions);
+        unsafe { panel.setAllowedFileTypes(Some(&v_array)) };
+    }
+
+    if let Some(v) = options.directory.and_then(|v| v.to_str()) {
+        let v_str = icrate::Foundation::NSString::from_str(v);
+        let v_url = unsafe { icrate::Foundation::NSURL::fileURLWithPath(&v_str) };
+        unsafe { panel.setDirectoryURL(Some(&v_url)) };
+    }
+
+    if let Some(v) = options.name {
+        let v_str = icrate::Foundation::NSString::from_str(v);
+        unsafe { panel.setNameFieldStringValue(&v_str) };
+    }
+}
+
+// Begin a panel and report the selected path once it is closed. Panels are
+// begun modeless, so the event loop of the application keeps running while
+// the panel is shown, and the completion is invoked on the main thread
+// after this returned.
+fn run(
+    panel: objc2::rc::Id<icrate::AppKit::NSSavePanel>,
+    done: Done,
+) {
+    // Blocks can be invoked multiple times, so the callback is taken on
+    // first invocation. The panel is retained by the block until the
+    // completion handler is released by AppKit.
+    let done = core::cell::Cell::new(Some(done));
+    let v_panel = panel.clone();
+    let v_block = block2::ConcreteBlock::new(
+        move |response: icrate::AppKit::NSModalResponse| {
+            let r = match response {
+                icrate::AppKit::NSModalResponseOK => unsafe { v_panel.URL() }
+                    .and_then(|v| unsafe { v.path() })
+                    .map(|v| v.to_string().into()),
+                _ => None,
+            };
+            if let Some(done) = done.take() {
+                done(Ok(r));
+            }
+        },
+    ).copy();
+
+    unsafe { panel.beginWithCompletionHandler(&v_block) };
+}
+
+/// ## Show Open Dialog
+///
+/// Show a dialog to select an existing file and invoke `done` once the
+/// dialog is closed. The completion is invoked on the main thread.
+pub fn open_file(
+    _app: &application::Context,
+    options: &Options,
+    done: Done,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mtm = icrate::Foundation::MainThreadMarker::new()
+        .ok_or::<Box<dyn std::error::Error>>(
+            "error: cannot show dialogs on non-main thread".into(),
+        )?;
+
+    let v_panel = unsafe { icrate::AppKit::NSOpenPanel::openPanel(mtm) };
+    unsafe {
+        v_panel.setCanChooseFiles(true);
+        v_panel.setCanChooseDirectories(false);
+        v_panel.setAllowsMultipleSelection(false);
+    }
+
+    setup(&v_panel, options);
+    run(objc2::rc::Id::into_super(v_panel), done);
+
+    Ok(())
+}
+
+/// ## Show Save Dialog
+///
+/// Show a dialog to select a file path to save to and invoke `done` once
+/// the dialog is closed. The completion is invoked on the main thread.
+pub fn save_file(
+    _app: &application::Context,
+    options: &Options,
+    done: Done,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mtm = icrate::Foundation::MainThreadMarker::new()
+        .ok_or::<Box<dyn std::error::Error>>(
+            "error: cannot show dialogs on non-main thread".into(),
+        )?;
+
+    let v_panel = unsafe { icrate::AppKit::NSSavePanel::savePanel(mtm) };
+
+    setup(&v_panel, options);
+    run(v_panel, done);
+
+    Ok(())
+}
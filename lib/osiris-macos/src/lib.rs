@@ -4,3 +4,5 @@
 //! Objective-C APIs of the macOS platform to communicate with the platform.
 
 pub mod application;
+pub mod dialog;
+pub mod menu;
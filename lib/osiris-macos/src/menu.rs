@@ -0,0 +1,197 @@
+//! # Menu APIs
+//!
+//! This module provides the menu bar of applications. Menus are built from
+//! `NSMenu` and `NSMenuItem` of AppKit and installed as main menu of the
+//! application. By convention, the first menu of the menu bar is the
+//! application menu, which is labeled with the application name by the
+//! system regardless of its title.
+
+use crate::application;
+use icrate;
+use objc2;
+
+/// ## Menu Accelerator
+///
+/// A keyboard shortcut that activates a menu action. `primary` selects the
+/// primary modifier of the platform, which is `Cmd` on macOS.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Accelerator {
+    /// Key to press, as lower-case character.
+    pub key: char,
+    /// Whether the primary modifier must be held.
+    pub primary: bool,
+    /// Whether `Shift` must be held.
+    pub shift: bool,
+    /// Whether `Option` must be held.
+    pub alt: bool,
+}
+
+/// ## Menu Entries
+///
+/// A single entry of a menu. Actions are identified by their `id`, which is
+/// passed to the dispatch callback when the action is activated.
+pub enum Entry<'ctx> {
+    /// Action with a label and an optional accelerator.
+    Action {
+        id: usize,
+        label: &'ctx str,
+        accelerator: Option<Accelerator>,
+    },
+    /// Separator between groups of entries.
+    Separator,
+    /// Nested menu with a label.
+    Submenu {
+        label: &'ctx str,
+        entries: Vec<Entry<'ctx>>,
+    },
+}
+
+/// ## Dispatch Callback
+///
+/// The callback invoked with the ID of an activated action. It is always
+/// invoked on the main thread.
+pub type Dispatch = Box<dyn Fn(usize)>;
+
+std::thread_local! {
+    // Dispatch callback and action target of the current menu bar. Menus can
+    // only be used on the main thread, so no synchronization is needed.
+    static DISPATCH: std::cell::RefCell<Option<std::rc::Rc<Dispatch>>> = Default::default();
+    static TARGET: std::cell::RefCell<Option<objc2::rc::Id<objc2::runtime::NSObject>>> = Default::default();
+}
+
+// Action method of the menu target. The ID of the action is stored as tag
+// of the sending menu item. The dispatch callback is cloned before it is
+// invoked, so the callback can replace the menu bar.
+extern "C" fn action(
+    _this: &objc2::runtime::NSObject,
+    _cmd: objc2::runtime::Sel,
+    sender: &icrate::AppKit::NSMenuItem,
+) {
+    let tag = unsafe { sender.tag() };
+    let dispatch = DISPATCH.with(|v| v.borrow().clone());
+
+    if let (Some(f), Ok(id)) = (dispatch, usize::try_from(tag)) {
+        f(id);
+    }
+}
+
+// Create the target object of all menu actions. Its class is registered
+// with the runtime on first use.
+fn target() -> objc2::rc::Id<objc2::runtime::NSObject> {
+    static CLASS: std::sync::OnceLock<&'static objc2::runtime::AnyClass> = std::sync::OnceLock::new();
+
+    let class = CLASS.get_or_init(|| {
+        let mut builder = objc2::declare::ClassBuilder::new(
+            "OsirisMenuTarget",
+            objc2::class!(NSObject),
+        ).expect("menu target class must not be registered elsewhere");
+
+        unsafe {
+            builder.add_method(
+                objc2::sel!(osirisMenuAction:),
+                action as extern "C" fn(_, _, _),
+            );
+        }
+
+        builder.register()
+    });
+
+    unsafe { objc2::msg_send_id![*class, new] }
+}
+
+// Build the menu with the given title and entries.
+fn build(
+    mtm: icrate::Foundation::MainThreadMarker,
+    target: &objc2::runtime::NSObject,
+    title: &str,
+    entries: &[Entry],
+) -> objc2::rc::Id<icrate::AppKit::NSMenu> {
+    let v_title = icrate::Foundation::NSString::from_str(title);
+    let v_menu = unsafe { icrate::AppKit::NSMenu::initWithTitle(mtm.alloc(), &v_title) };
+
+    for entry in entries {
+        let v_item = match *entry {
+            Entry::Action { id, label, ref accelerator } => {
+                let v_label = icrate::Foundation::NSString::from_str(label);
+                let v_key = icrate::Foundation::NSString::from_str(
+                    &accelerator.map(|v| v.key.to_string()).unwrap_or_default(),
+                );
+                let v_item = unsafe {
+                    icrate::AppKit::NSMenuItem::initWithTitle_action_keyEquivalent(
+                        mtm.alloc(),
+                        &v_label,
+                        Some(objc2::sel!(osirisMenuAction:)),
+                        &v_key,
+                    )
+                };
+
+                unsafe {
+                    v_item.setTag(id as isize);
+                    v_item.setTarget(Some(target));
+                }
+
+                if let Some(v) = accelerator {
+                    let mut mask = 0;
+                    if v.primary {
+                        mask |= icrate::AppKit::NSEventModifierFlagCommand;
+                    }
+                    if v.shift {
+                        mask |= icrate::AppKit::NSEventModifierFlagShift;
+                    }
+                    if v.alt {
+                        mask |= icrate::AppKit::NSEventModifierFlagOption;
+                    }
+                    unsafe { v_item.setKeyEquivalentModifierMask(mask) };
+                }
+
+                v_item
+            },
+            Entry::Separator => {
+                unsafe { icrate::AppKit::NSMenuItem::separatorItem(mtm) }
+            },
+            Entry::Submenu { label, ref entries } => {
+                let v_label = icrate::Foundation::NSString::from_str(label);
+                let v_empty = icrate::Foundation::NSString::from_str("");
+                let v_item = unsafe {
+                    icrate::AppKit::NSMenuItem::initWithTitle_action_keyEquivalent(
+                        mtm.alloc(),
+                        &v_label,
+                        None,
+                        &v_empty,
+                    )
+                };
+                let v_submenu = build(mtm, target, label, entries);
+                unsafe { v_item.setSubmenu(Some(&v_submenu)) };
+                v_item
+            },
+        };
+
+        unsafe { v_menu.addItem(&v_item) };
+    }
+
+    v_menu
+}
+
+/// ## Set Menu Bar
+///
+/// Set the main menu of the application to the given entries, replacing
+/// any previous menu bar. `dispatch` is invoked on the main thread whenever
+/// an action is activated.
+pub fn set_menu_bar(
+    app: &application::Context,
+    entries: &[Entry],
+    dispatch: Dispatch,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mtm = icrate::Foundation::MainThreadMarker::new()
+        .ok_or::<Box<dyn std::error::Error>>(
+            "error: cannot set menu bar on non-main thread".into(),
+        )?;
+
+    let v_target = TARGET.with(|v| v.borrow_mut().get_or_insert_with(target).clone());
+    let v_menu = build(mtm, &v_target, "", entries);
+
+    DISPATCH.with(|v| *v.borrow_mut() = Some(std::rc::Rc::new(dispatch)));
+    unsafe { app.app.setMainMenu(Some(&v_menu)) };
+
+    Ok(())
+}
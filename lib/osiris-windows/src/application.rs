@@ -23,6 +23,8 @@ pub struct Setup<'ctx> {
 /// regarding the state and lifetime of the application.
 pub struct Context {
     aumid: Option<windows::core::HSTRING>,
+    thread: std::sync::atomic::AtomicU32,
+    quit_pending: std::sync::atomic::AtomicBool,
 }
 
 impl<'ctx> Setup<'ctx> {
@@ -56,6 +58,8 @@ impl<'ctx> Setup<'ctx> {
 
         Ok(Context {
             aumid: v_aumid,
+            thread: std::sync::atomic::AtomicU32::new(0),
+            quit_pending: std::sync::atomic::AtomicBool::new(false),
         })
     }
 }
@@ -106,4 +110,111 @@ impl Context {
     pub fn claim(&self) {
         // no-op
     }
+
+    /// ## Run Application Main-Loop
+    ///
+    /// Run the message loop of the calling thread and dispatch messages
+    /// until `Self::quit()` is called, or `WM_QUIT` is received otherwise.
+    /// Accelerators of the menu bar are translated before dispatch. If a
+    /// quit was requested before, the loop returns without dispatching
+    /// anything.
+    pub fn run(
+        &self,
+    ) {
+        let mut message = windows::Win32::UI::WindowsAndMessaging::MSG::default();
+
+        // Make sure the thread has a message queue before publishing its
+        // ID, as messages cannot be posted to threads without one.
+        unsafe {
+            let _ = windows::Win32::UI::WindowsAndMessaging::PeekMessageW(
+                &mut message,
+                None,
+                0,
+                0,
+                windows::Win32::UI::WindowsAndMessaging::PM_NOREMOVE,
+            );
+        }
+
+        self.thread.store(
+            unsafe { windows::Win32::System::Threading::GetCurrentThreadId() },
+            std::sync::atomic::Ordering::SeqCst,
+        );
+
+        // The pending flag is checked after the thread ID was published,
+        // so any request is either seen here or wakes up the loop.
+        while
+            !self.quit_pending.swap(false, std::sync::atomic::Ordering::SeqCst)
+            && unsafe {
+                windows::Win32::UI::WindowsAndMessaging::GetMessageW(
+                    &mut message,
+                    None,
+                    0,
+                    0,
+                ).into()
+            }
+        {
+            if crate::menu::translate(&message) {
+                continue;
+            }
+
+            unsafe {
+                windows::Win32::UI::WindowsAndMessaging::TranslateMessage(&message);
+                windows::Win32::UI::WindowsAndMessaging::DispatchMessageW(&message);
+            }
+        }
+
+        self.thread.store(0, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// ## Quit Application Main-Loop
+    ///
+    /// Stop the message loop entered via `Self::run()`. This can be called
+    /// from any thread. The request is recorded and the loop is woken up
+    /// via an empty message to its thread. Requests made while no message
+    /// loop runs make the next call to `Self::run()` return immediately.
+    pub fn quit(
+        &self,
+    ) {
+        self.quit_pending.store(true, std::sync::atomic::Ordering::SeqCst);
+
+        let thread = self.thread.load(std::sync::atomic::Ordering::SeqCst);
+        if thread != 0 {
+            // Posting only fails if the thread has no message queue, in
+            // which case the loop has already ended.
+            let _ = unsafe {
+                windows::Win32::UI::WindowsAndMessaging::PostThreadMessageW(
+                    thread,
+                    windows::Win32::UI::WindowsAndMessaging::WM_NULL,
+                    windows::Win32::Foundation::WPARAM(0),
+                    windows::Win32::Foundation::LPARAM(0),
+                )
+            };
+        }
+    }
+}
+
+/// ## Open URL
+///
+/// Launch the default handler of the shell for the given URL. The URL is
+/// passed verbatim and must be validated by the caller.
+pub fn open_url(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let v_url = windows::core::HSTRING::from(url);
+
+    // `ShellExecuteW()` returns a value greater than 32 on success, and an
+    // error code otherwise. The return type is only for compatibility.
+    let r = unsafe {
+        windows::Win32::UI::Shell::ShellExecuteW(
+            windows::Win32::Foundation::HWND(0),
+            windows::core::w!("open"),
+            &v_url,
+            windows::core::PCWSTR::null(),
+            windows::core::PCWSTR::null(),
+            windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL,
+        )
+    };
+    if r.0 <= 32 {
+        return Err(format!("error: cannot open URL (code: {})", r.0).into());
+    }
+
+    Ok(())
 }
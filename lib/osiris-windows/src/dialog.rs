@@ -0,0 +1,256 @@
+//! # File Dialog APIs
+//!
+//! This module provides file dialogs to let the user select files to open or
+//! save. The dialogs are based on the Common Item Dialog[^dialog] of the
+//! Windows Shell.
+//!
+//! [^dialog]: <https://learn.microsoft.com/en-us/windows/win32/shell/common-file-dialog>
+
+use crate::application;
+use windows;
+
+/// ## Dialog Options
+///
+/// The options used to set up a file dialog. `filters` lists a name for each
+/// filter, and the file extensions (without leading dot) it allows.
+#[derive(Default)]
+pub struct Options<'ctx> {
+    /// Title of the dialog, or `None` to use a default title.
+    pub title: Option<&'ctx str>,
+    /// File filters to offer to the user.
+    pub filters: &'ctx [(&'ctx str, &'ctx [&'ctx str])],
+    /// Directory to start in, or `None` to use a default.
+    pub directory: Option<&'ctx std::path::Path>,
+    /// Suggested file name when saving.
+    pub name: Option<&'ctx str>,
+}
+
+/// ## Completion Callback
+///
+/// The callback invoked once a dialog is closed. It carries the selected
+/// path, or `None` if the dialog was cancelled.
+pub type Done = Box<
+    dyn FnOnce(
+        Result<Option<std::path::PathBuf>, Box<dyn std::error::Error + Send + Sync>>,
+    ) + Send,
+>;
+
+/// ## Check for Headless Systems
+///
+/// Return whether the process runs on a non-interactive window station
+/// (e.g., as a service), where no dialogs can be shown.
+pub fn headless() -> bool {
+    let mut flags = windows::Win32::System::StationsAndDesktops::USEROBJECTFLAGS::default();
+
+    let r = unsafe {
+        windows::Win32::System::StationsAndDesktops::GetProcessWindowStation()
+            .and_then(|v| {
+                windows::Win32::System::StationsAndDesktops::GetUserObjectInformationW(
+                    windows::Win32::Foundation::HANDLE(v.0),
+                    windows::Win32::System::StationsAndDesktops::UOI_FLAGS,
+                    Some(&mut flags as *mut _ as *mut core::ffi::c_void),
+                    core::mem::size_of_val(&flags) as u32,
+                    None,
+                )
+            })
+    };
+
+    match r {
+        Ok(()) => (flags.dwFlags & windows::Win32::System::StationsAndDesktops::WSF_VISIBLE) == 0,
+        Err(_) => false,
+    }
+}
+
+// Dialog options converted to the string types of the Shell. Filters are
+// converted to the `*.ext;*.ext` specs expected by the dialog.
+struct Setup {
+    title: Option<windows::core::HSTRING>,
+    filters: Vec<(windows::core::HSTRING, windows::core::HSTRING)>,
+    extension: Option<windows::core::HSTRING>,
+    directory: Option<windows::core::HSTRING>,
+    name: Option<windows::core::HSTRING>,
+}
+
+impl Setup {
+    fn new(options: &Options) -> Self {
+        let filters = options.filters
+            .iter()
+            .map(|(name, extensions)| {
+                let spec = match extensions.is_empty() {
+                    true => "*.*".to_string(),
+                    false => extensions
+                        .iter()
+                        .map(|v| format!("*.{}", v))
+                        .collect::<Vec<_>>()
+                        .join(";"),
+                };
+                (windows::core::HSTRING::from(*name), windows::core::HSTRING::from(spec))
+            })
+            .collect();
+
+        Self {
+            title: options.title.map(windows::core::HSTRING::from),
+            filters: filters,
+            extension: options.filters
+                .first()
+                .and_then(|v| v.1.first())
+                .map(|v| windows::core::HSTRING::from(*v)),
+            directory: options.directory.map(windows::core::HSTRING::from),
+            name: options.name.map(windows::core::HSTRING::from),
+        }
+    }
+
+    // Apply the options to a dialog. The default extension is only used by
+    // save dialogs, and appended to names typed without extension. Start
+    // directories that cannot be resolved are ignored.
+    unsafe fn apply(
+        &self,
+        dialog: &windows::Win32::UI::Shell::IFileDialog,
+    ) -> windows::core::Result<()> {
+        if let Some(ref v) = self.title {
+            dialog.SetTitle(v)?;
+        }
+
+        if !self.filters.is_empty() {
+            let specs: Vec<windows::Win32::UI::Shell::Common::COMDLG_FILTERSPEC> = self.filters
+                .iter()
+                .map(|(name, spec)| windows::Win32::UI::Shell::Common::COMDLG_FILTERSPEC {
+                    pszName: windows::core::PCWSTR(name.as_ptr()),
+                    pszSpec: windows::core::PCWSTR(spec.as_ptr()),
+                })
+                .collect();
+            dialog.SetFileTypes(&specs)?;
+        }
+        if let Some(ref v) = self.extension {
+            dialog.SetDefaultExtension(v)?;
+        }
+
+        if let Some(ref v) = self.directory {
+            let r: windows::core::Result<windows::Win32::UI::Shell::IShellItem> =
+                windows::Win32::UI::Shell::SHCreateItemFromParsingName(v, None);
+            if let Ok(v_item) = r {
+                dialog.SetFolder(&v_item)?;
+            }
+        }
+
+        if let Some(ref v) = self.name {
+            dialog.SetFileName(v)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Create and show a dialog of the given class, owned by `owner`, and yield
+// the selected path. The calling thread must have COM initialized as
+// single-threaded apartment.
+unsafe fn pick(
+    class: &windows::core::GUID,
+    setup: &Setup,
+    flags: windows::Win32::UI::Shell::FILEOPENDIALOGOPTIONS,
+    owner: windows::Win32::Foundation::HWND,
+) -> Result<Option<std::path::PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let v_dialog: windows::Win32::UI::Shell::IFileDialog = windows::Win32::System::Com::CoCreateInstance(
+        class,
+        None,
+        windows::Win32::System::Com::CLSCTX_INPROC_SERVER,
+    )?;
+
+    v_dialog.SetOptions(
+        v_dialog.GetOptions()?
+            | windows::Win32::UI::Shell::FOS_FORCEFILESYSTEM
+            | flags,
+    )?;
+    setup.apply(&v_dialog)?;
+
+    // Cancelled dialogs are reported via `ERROR_CANCELLED`.
+    match v_dialog.Show(owner) {
+        Err(v) if v.code() == windows::Win32::Foundation::ERROR_CANCELLED.to_hresult() => {
+            return Ok(None);
+        },
+        v => v?,
+    }
+
+    let v_path = v_dialog.GetResult()?.GetDisplayName(
+        windows::Win32::UI::Shell::SIGDN_FILESYSPATH,
+    )?;
+    let path: std::ffi::OsString = std::os::windows::ffi::OsStringExt::from_wide(v_path.as_wide());
+    windows::Win32::System::Com::CoTaskMemFree(Some(v_path.0 as *const core::ffi::c_void));
+
+    Ok(Some(path.into()))
+}
+
+// Show a dialog of the given class and invoke `done` once it is closed.
+// The dialog is modal to the active window of the calling thread, and runs
+// a nested message loop on that thread until it is closed, which keeps the
+// windows of the application serviced. Hence, this must be called on the
+// thread of the main-loop. Owner windows of other threads would attach the
+// input queues of both threads, and could stall the main-loop.
+fn show(
+    class: windows::core::GUID,
+    options: &Options,
+    flags: windows::Win32::UI::Shell::FILEOPENDIALOGOPTIONS,
+    done: Done,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let owner = unsafe { windows::Win32::UI::Input::KeyboardAndMouse::GetActiveWindow() };
+    if owner.0 == 0 {
+        return Err("error: no active window to show the dialog for".into());
+    }
+
+    let setup = Setup::new(options);
+
+    // If COM is already initialized as single-threaded apartment on this
+    // thread, this merely increments its reference count.
+    unsafe {
+        windows::Win32::System::Com::CoInitializeEx(
+            None,
+            windows::Win32::System::Com::COINIT_APARTMENTTHREADED,
+        )?;
+    }
+
+    let r = unsafe { pick(&class, &setup, flags, owner) };
+    unsafe { windows::Win32::System::Com::CoUninitialize() };
+    done(r);
+
+    Ok(())
+}
+
+/// ## Show Open Dialog
+///
+/// Show a dialog to select an existing file and invoke `done` once the
+/// dialog is closed. This must be called on the thread of the main-loop.
+/// The dialog is modal, so this returns only after the dialog was closed
+/// and `done` was invoked on the calling thread.
+pub fn open_file(
+    app: &application::Context,
+    options: &Options,
+    done: Done,
+) -> Result<(), Box<dyn std::error::Error>> {
+    app.claim();
+
+    show(
+        windows::Win32::UI::Shell::FileOpenDialog,
+        options,
+        windows::Win32::UI::Shell::FOS_FILEMUSTEXIST,
+        done,
+    )
+}
+
+/// ## Show Save Dialog
+///
+/// Show a dialog to select a file path to save to and invoke `done` once
+/// the dialog is closed. See `open_file()` for details.
+pub fn save_file(
+    app: &application::Context,
+    options: &Options,
+    done: Done,
+) -> Result<(), Box<dyn std::error::Error>> {
+    app.claim();
+
+    show(
+        windows::Win32::UI::Shell::FileSaveDialog,
+        options,
+        windows::Win32::UI::Shell::FOS_OVERWRITEPROMPT,
+        done,
+    )
+}
@@ -5,4 +5,6 @@
 //! if available.
 
 pub mod application;
+pub mod dialog;
+pub mod menu;
 pub mod notification;
@@ -0,0 +1,290 @@
+//! # Menu APIs
+//!
+//! This module provides menu bars for applications. Menus are built as
+//! native `HMENU` together with an accelerator table for their keyboard
+//! shortcuts. Windows own their menu bar, so windows must attach the menu
+//! via `SetMenu()` and forward `WM_COMMAND` to `dispatch_command()`. When the
+//! menu bar is replaced, windows of the calling thread are switched to the
+//! new menu bar before the previous one is destroyed.
+
+use crate::application;
+use windows;
+
+/// ## Menu Accelerator
+///
+/// A keyboard shortcut that activates a menu action. `primary` selects the
+/// primary modifier of the platform, which is `Ctrl` on Windows. Only the
+/// keys `a-z` and `0-9` can be used as accelerators.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Accelerator {
+    /// Key to press, as lower-case character.
+    pub key: char,
+    /// Whether the primary modifier must be held.
+    pub primary: bool,
+    /// Whether `Shift` must be held.
+    pub shift: bool,
+    /// Whether `Alt` must be held.
+    pub alt: bool,
+}
+
+/// ## Menu Entries
+///
+/// A single entry of a menu. Actions are identified by their `id`, which is
+/// passed to the dispatch callback when the action is activated.
+pub enum Entry<'ctx> {
+    /// Action with a label and an optional accelerator.
+    Action {
+        id: usize,
+        label: &'ctx str,
+        accelerator: Option<Accelerator>,
+    },
+    /// Separator between groups of entries.
+    Separator,
+    /// Nested menu with a label.
+    Submenu {
+        label: &'ctx str,
+        entries: Vec<Entry<'ctx>>,
+    },
+}
+
+/// ## Dispatch Callback
+///
+/// The callback invoked with the ID of an activated action. It is always
+/// invoked on the thread running the message loop.
+pub type Dispatch = Box<dyn Fn(usize)>;
+
+// Native state of the current menu bar. The menu and accelerator table are
+// destroyed when the menu bar is replaced.
+struct State {
+    menu: windows::Win32::UI::WindowsAndMessaging::HMENU,
+    accel: Option<windows::Win32::UI::WindowsAndMessaging::HACCEL>,
+    actions: std::collections::BTreeSet<usize>,
+    dispatch: std::rc::Rc<Dispatch>,
+}
+
+std::thread_local! {
+    // Menu bar of the calling thread. Menus are bound to the thread of the
+    // windows they are attached to, so no synchronization is needed.
+    static STATE: std::cell::RefCell<Option<State>> = Default::default();
+}
+
+// Command IDs are offset by one, since `0` is used for controls without
+// identifier.
+const COMMAND_OFFSET: usize = 1;
+
+impl Drop for State {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(v) = self.accel {
+                let _ = windows::Win32::UI::WindowsAndMessaging::DestroyAcceleratorTable(v);
+            }
+            let _ = windows::Win32::UI::WindowsAndMessaging::DestroyMenu(self.menu);
+        }
+    }
+}
+
+// Map an accelerator to its virtual-key code and the suffix shown in the
+// menu label. Unsupported keys yield `None`.
+fn accel(v: &Accelerator) -> Option<(u16, String)> {
+    let key = v.key.to_ascii_uppercase();
+    if !key.is_ascii_uppercase() && !key.is_ascii_digit() {
+        return None;
+    }
+
+    let mut label = String::new();
+    if v.primary {
+        label.push_str("Ctrl+");
+    }
+    if v.shift {
+        label.push_str("Shift+");
+    }
+    if v.alt {
+        label.push_str("Alt+");
+    }
+    label.push(key);
+
+    Some((key as u16, label))
+}
+
+// Append the given entries to a menu and collect the IDs of all actions in
+// `state`, and their accelerators in `accels`.
+fn build(
+    menu: windows::Win32::UI::WindowsAndMessaging::HMENU,
+    entries: &[Entry],
+    state: &mut State,
+    accels: &mut Vec<windows::Win32::UI::WindowsAndMessaging::ACCEL>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use windows::Win32::UI::WindowsAndMessaging as wm;
+
+    for entry in entries {
+        match *entry {
+            Entry::Action { id, label, ref accelerator } => {
+                let cmd = u16::try_from(id + COMMAND_OFFSET)
+                    .map_err(|_| "error: too many menu actions")?;
+                let mut text = label.to_string();
+                state.actions.insert(id);
+
+                if let Some((key, suffix)) = accelerator.as_ref().and_then(accel) {
+                    let v = accelerator.as_ref().unwrap();
+                    let mut flags = wm::FVIRTKEY;
+                    if v.primary {
+                        flags |= wm::FCONTROL;
+                    }
+                    if v.shift {
+                        flags |= wm::FSHIFT;
+                    }
+                    if v.alt {
+                        flags |= wm::FALT;
+                    }
+                    accels.push(wm::ACCEL { fVirt: flags, key: key, cmd: cmd });
+
+                    text.push('\t');
+                    text.push_str(&suffix);
+                }
+
+                let v_text = windows::core::HSTRING::from(text);
+                unsafe { wm::AppendMenuW(menu, wm::MF_STRING, cmd as usize, &v_text)? };
+            },
+            Entry::Separator => {
+                unsafe {
+                    wm::AppendMenuW(menu, wm::MF_SEPARATOR, 0, windows::core::PCWSTR::null())?;
+                }
+            },
+            Entry::Submenu { label, ref entries } => {
+                let v_submenu = unsafe { wm::CreatePopupMenu()? };
+                if let Err(e) = build(v_submenu, entries, state, accels) {
+                    let _ = unsafe { wm::DestroyMenu(v_submenu) };
+                    return Err(e);
+                }
+
+                // Once appended, the submenu is owned by its parent.
+                let v_text = windows::core::HSTRING::from(label);
+                let r = unsafe { wm::AppendMenuW(menu, wm::MF_POPUP, v_submenu.0 as usize, &v_text) };
+                if let Err(e) = r {
+                    let _ = unsafe { wm::DestroyMenu(v_submenu) };
+                    return Err(e.into());
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+// Switch all windows of the calling thread showing the menu `from` to the
+// menu `to`, so `from` can be destroyed safely.
+fn reattach(
+    from: windows::Win32::UI::WindowsAndMessaging::HMENU,
+    to: windows::Win32::UI::WindowsAndMessaging::HMENU,
+) {
+    use windows::Win32::UI::WindowsAndMessaging as wm;
+
+    unsafe extern "system" fn visit(
+        hwnd: windows::Win32::Foundation::HWND,
+        lparam: windows::Win32::Foundation::LPARAM,
+    ) -> windows::Win32::Foundation::BOOL {
+        let (from, to) = *(lparam.0 as *const (wm::HMENU, wm::HMENU));
+        if wm::GetMenu(hwnd) == from {
+            let _ = wm::SetMenu(hwnd, to);
+        }
+        true.into()
+    }
+
+    let menus = (from, to);
+    unsafe {
+        let _ = wm::EnumThreadWindows(
+            windows::Win32::System::Threading::GetCurrentThreadId(),
+            Some(visit),
+            windows::Win32::Foundation::LPARAM(&menus as *const _ as isize),
+        );
+    }
+}
+
+/// ## Set Menu Bar
+///
+/// Set the menu bar of the application to the given entries, replacing and
+/// destroying any previous menu bar. Windows of the calling thread showing
+/// the previous menu bar are switched to the new one. Other windows must
+/// attach it via `menu_bar()`. `dispatch` is invoked on the message loop
+/// whenever an action is activated.
+pub fn set_menu_bar(
+    _app: &application::Context,
+    entries: &[Entry],
+    dispatch: Dispatch,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use windows::Win32::UI::WindowsAndMessaging as wm;
+
+    let mut accels = Vec::new();
+    let mut state = State {
+        menu: unsafe { wm::CreateMenu()? },
+        accel: None,
+        actions: Default::default(),
+        dispatch: std::rc::Rc::new(dispatch),
+    };
+
+    let v_menu = state.menu;
+    build(v_menu, entries, &mut state, &mut accels)?;
+    if !accels.is_empty() {
+        state.accel = Some(unsafe { wm::CreateAcceleratorTableW(&accels)? });
+    }
+
+    // The previous menu bar is destroyed when dropped, so no window must
+    // show it anymore.
+    let previous = STATE.with(|v| v.borrow_mut().replace(state));
+    if let Some(v) = previous {
+        reattach(v.menu, v_menu);
+    }
+
+    Ok(())
+}
+
+/// ## Yield Menu Bar
+///
+/// Yield the menu bar of the calling thread, if any. Pass it to `SetMenu()`
+/// to show it on a window.
+pub fn menu_bar() -> Option<windows::Win32::UI::WindowsAndMessaging::HMENU> {
+    STATE.with(|v| v.borrow().as_ref().map(|v| v.menu))
+}
+
+/// ## Dispatch Menu Command
+///
+/// Dispatch the command ID of a `WM_COMMAND` message (the low word of its
+/// `WPARAM`) to the menu bar. Returns whether the command belonged to a menu
+/// action. Commands of other menus or controls are not dispatched.
+pub fn dispatch_command(command: u16) -> bool {
+    // Clone the callback, so it can replace the menu bar.
+    let dispatch = STATE.with(|v| {
+        let state = v.borrow();
+        let state = state.as_ref()?;
+        let id = (command as usize).checked_sub(COMMAND_OFFSET)?;
+        state.actions.contains(&id).then(|| (state.dispatch.clone(), id))
+    });
+
+    match dispatch {
+        Some((f, id)) => {
+            f(id);
+            true
+        },
+        None => false,
+    }
+}
+
+/// ## Translate Accelerators
+///
+/// Translate keyboard messages matching an accelerator of the menu bar into
+/// `WM_COMMAND` messages of the target window. Returns whether the message
+/// was translated, in which case it must not be dispatched further.
+pub fn translate(message: &windows::Win32::UI::WindowsAndMessaging::MSG) -> bool {
+    let accel = STATE.with(|v| v.borrow().as_ref().and_then(|v| v.accel));
+
+    match accel {
+        Some(v) if message.hwnd.0 != 0 => unsafe {
+            windows::Win32::UI::WindowsAndMessaging::TranslateAcceleratorW(
+                message.hwnd,
+                v,
+                message,
+            ) != 0
+        },
+        _ => false,
+    }
+}